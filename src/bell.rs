@@ -0,0 +1,238 @@
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, SystemClock};
+use crate::tui::canvas::{Canvas, Modifier};
+use crate::tui::drawbuffer::{DrawBuffer, DrawBufferOwner, Owner};
+use crate::tui::error::Result;
+use crate::tui::geometry::{Idx, Rectangle};
+use crate::tui48::FLASH_LAYER_IDX;
+
+/// Number of rendered frames the flash stays on screen.
+const FLASH_FRAMES: usize = 2;
+
+/// Minimum time between two flashes so that rapid notifications don't strobe.
+const FLASH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Events the player is notified about.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Notification {
+    InvalidMove,
+    GameOver,
+    NewRecord,
+}
+
+/// Allows at most one event per interval.
+struct RateLimiter {
+    clock: Box<dyn Clock>,
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl RateLimiter {
+    fn new(clock: Box<dyn Clock>, interval: Duration) -> Self {
+        Self {
+            clock,
+            interval,
+            last: None,
+        }
+    }
+
+    /// Returns true and records the event if at least one interval has passed since the last
+    /// allowed event.
+    fn allow(&mut self) -> bool {
+        let now = self.clock.now();
+        match self.last {
+            Some(last) if now.duration_since(last) < self.interval => false,
+            _ => {
+                self.last = Some(now);
+                true
+            }
+        }
+    }
+}
+
+/// A visual alternative to the terminal bell: briefly lights up a one-cell-thick frame around the
+/// edge of the canvas when a notification fires.
+///
+/// The frame lives in its own DrawBuffer on the topmost canvas layer. It is created when the bell
+/// rings and dropped once the flash is over so it never outlives the canvas it was drawn on.
+pub(crate) struct VisualBell {
+    enabled: bool,
+    limiter: RateLimiter,
+    flash: Option<DrawBuffer>,
+    frames_remaining: usize,
+}
+
+impl VisualBell {
+    /// A bell that never flashes.
+    pub(crate) fn disabled() -> Self {
+        Self::new(false)
+    }
+
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            limiter: RateLimiter::new(Box::new(SystemClock), FLASH_INTERVAL),
+            flash: None,
+            frames_remaining: 0,
+        }
+    }
+
+    #[cfg(test)]
+    fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.limiter.clock = Box::new(clock);
+        self
+    }
+
+    /// Draws the flash on the given canvas if the bell is enabled and hasn't flashed recently.
+    /// Returns true if the flash was drawn.
+    pub(crate) fn ring(&mut self, canvas: &Canvas, notification: Notification) -> Result<bool> {
+        if !self.enabled || !self.limiter.allow() {
+            return Ok(false);
+        }
+        log::trace!("flashing visual bell for {:?}", notification);
+
+        if self.flash.is_none() {
+            let rectangle = Rectangle(Idx(0, 0, FLASH_LAYER_IDX), canvas.bounds());
+            let owner = Owner::Named("visual bell");
+            let mut flash = canvas.get_draw_buffer_unchecked(rectangle, owner)?;
+            flash.modify(Modifier::SetBackgroundColor(255, 255, 255));
+            flash.modify(Modifier::SetForegroundColor(0, 0, 0));
+            self.flash = Some(flash);
+        }
+        if let Some(flash) = &mut self.flash {
+            flash.fill_edge(' ')?;
+        }
+        self.frames_remaining = FLASH_FRAMES;
+        Ok(true)
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(crate) fn is_flashing(&self) -> bool {
+        self.frames_remaining > 0
+    }
+
+    /// Marks one frame of the flash as rendered, clearing the flash after its last frame.
+    pub(crate) fn advance(&mut self) {
+        self.frames_remaining = self.frames_remaining.saturating_sub(1);
+        if self.frames_remaining == 0 {
+            // dropping the buffer clears its tuxels and hands the layer back to the canvas
+            let _ = self.flash.take();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use rstest::*;
+
+    use super::*;
+    use crate::clock::FakeClock;
+
+    fn lit_cells(canvas: &Canvas) -> BTreeSet<(usize, usize)> {
+        canvas
+            .get_changed()
+            .into_iter()
+            .filter(|stack| stack.colors().1.is_some())
+            .map(|stack| stack.coordinates())
+            .collect()
+    }
+
+    fn edge_cells(width: usize, height: usize) -> BTreeSet<(usize, usize)> {
+        let mut cells = BTreeSet::new();
+        for y in 0..height {
+            for x in 0..width {
+                if x == 0 || y == 0 || x + 1 == width || y + 1 == height {
+                    cells.insert((x, y));
+                }
+            }
+        }
+        cells
+    }
+
+    #[rstest]
+    #[case::single_cell(1, 1)]
+    #[case::single_row(5, 1)]
+    #[case::single_column(1, 5)]
+    #[case::too_small(10, 10)]
+    #[case::realistic(100, 50)]
+    fn flash_only_lights_the_edge(#[case] width: usize, #[case] height: usize) -> Result<()> {
+        let canvas = Canvas::new(width, height);
+        let mut bell = VisualBell::new(true).with_clock(FakeClock::new());
+
+        assert!(bell.ring(&canvas, Notification::InvalidMove)?);
+        assert_eq!(lit_cells(&canvas), edge_cells(width, height));
+        Ok(())
+    }
+
+    #[test]
+    fn flash_clears_after_its_frames() -> Result<()> {
+        let canvas = Canvas::new(20, 10);
+        let mut bell = VisualBell::new(true).with_clock(FakeClock::new());
+
+        assert!(bell.ring(&canvas, Notification::InvalidMove)?);
+        let _ = canvas.get_changed();
+        for _ in 0..FLASH_FRAMES {
+            assert!(bell.is_flashing());
+            assert!(canvas.layer_occupied(FLASH_LAYER_IDX));
+            bell.advance();
+        }
+
+        assert!(!bell.is_flashing());
+        assert!(!canvas.layer_occupied(FLASH_LAYER_IDX));
+        let changed = canvas.get_changed();
+        assert!(!changed.is_empty());
+        for stack in changed {
+            assert_eq!(stack.content(), Some(' '));
+            assert!(stack.colors().1.is_none());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn flash_is_rate_limited() -> Result<()> {
+        let canvas = Canvas::new(20, 10);
+        let clock = FakeClock::new();
+        let mut bell = VisualBell::new(true).with_clock(clock.clone());
+
+        assert!(bell.ring(&canvas, Notification::InvalidMove)?);
+        while bell.is_flashing() {
+            bell.advance();
+        }
+
+        clock.advance(Duration::from_millis(100));
+        assert!(!bell.ring(&canvas, Notification::InvalidMove)?);
+        assert!(!bell.is_flashing());
+
+        clock.advance(Duration::from_millis(399));
+        assert!(!bell.ring(&canvas, Notification::InvalidMove)?);
+
+        clock.advance(Duration::from_millis(1));
+        assert!(bell.ring(&canvas, Notification::InvalidMove)?);
+        Ok(())
+    }
+
+    #[rstest]
+    fn rings_for_every_notification(
+        #[values(
+            Notification::InvalidMove,
+            Notification::GameOver,
+            Notification::NewRecord
+        )]
+        notification: Notification,
+        #[values(true, false)] enabled: bool,
+    ) -> Result<()> {
+        let canvas = Canvas::new(20, 10);
+        let mut bell = VisualBell::new(enabled).with_clock(FakeClock::new());
+
+        assert_eq!(bell.ring(&canvas, notification)?, enabled);
+        assert_eq!(bell.is_flashing(), enabled);
+        assert_eq!(canvas.layer_occupied(FLASH_LAYER_IDX), enabled);
+        Ok(())
+    }
+}
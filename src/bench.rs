@@ -0,0 +1,186 @@
+use std::cell::Cell;
+
+use crate::engine::board::{Board, BoardConfig};
+use crate::error::Result;
+use crate::frametimes::{FrameReport, FrameTimer};
+use crate::tui::canvas::Canvas;
+use crate::tui::events::{Event, EventSource, UserInput};
+use crate::tui::geometry::Direction;
+use crate::tui::renderer::Renderer;
+use crate::tui48::Tui48;
+
+/// The number of shifts the benchmark plays.
+const BENCH_SHIFTS: usize = 20;
+
+/// Seed for the benchmark's board, so that every run plays out the same moves.
+const BENCH_SEED: u64 = 2048;
+
+/// Shifts played in turn; keeping to the bottom left corner means few of them fail to move.
+const BENCH_DIRECTIONS: [Direction; 4] = [
+    Direction::Left,
+    Direction::Down,
+    Direction::Right,
+    Direction::Down,
+];
+
+/// An EventSource that shifts the board the given number of times and then asks to quit.
+struct ScriptedShifts {
+    remaining: Cell<usize>,
+}
+
+impl ScriptedShifts {
+    fn new(shifts: usize) -> Self {
+        Self {
+            remaining: Cell::new(shifts),
+        }
+    }
+}
+
+impl EventSource for ScriptedShifts {
+    fn next_event(&self) -> crate::tui::error::Result<Event> {
+        let remaining = self.remaining.get();
+        if remaining == 0 {
+            return Ok(Event::UserInput(UserInput::Quit));
+        }
+        self.remaining.set(remaining - 1);
+        let direction = BENCH_DIRECTIONS[remaining % BENCH_DIRECTIONS.len()].clone();
+        Ok(Event::UserInput(UserInput::Direction(direction)))
+    }
+}
+
+/// Plays a scripted set of shifts on the given canvas and renderer with full animation, timing
+/// every frame with the given timer. The renderer has been recovered by the time this returns,
+/// whether or not the run succeeded.
+pub(crate) fn run<R: Renderer>(
+    canvas: Canvas,
+    renderer: R,
+    timer: FrameTimer,
+) -> Result<FrameReport> {
+    let board = Board::new_seeded(BENCH_SEED, BoardConfig::default());
+    let tui48 = Tui48::new(board, renderer, ScriptedShifts::new(BENCH_SHIFTS))?
+        .with_canvas(canvas)
+        .with_frame_timer(timer.clone());
+    tui48.run()?;
+    Ok(timer.report())
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::clock::FakeClock;
+    use crate::frametimes::PhaseStats;
+    use crate::tui::error::{InnerError, Result as TuiResult};
+    use crate::tui::testing::TestRenderer;
+    use crate::tui48::init;
+
+    const RENDER_TIME: Duration = Duration::from_millis(20);
+
+    /// A TestRenderer on a fake clock that takes a fixed time to render and can be made to fail
+    /// after a number of renders.
+    struct SlowRenderer {
+        inner: TestRenderer,
+        clock: FakeClock,
+        renders_left: Option<usize>,
+    }
+
+    impl Renderer for SlowRenderer {
+        fn size_hint(&self) -> TuiResult<(u16, u16)> {
+            self.inner.size_hint()
+        }
+
+        fn render(&mut self, c: &Canvas) -> TuiResult<()> {
+            if let Some(left) = &mut self.renders_left {
+                if *left == 0 {
+                    return Err(InnerError::DrawBufferTranslationFailed("render".into()).into());
+                }
+                *left -= 1;
+            }
+            self.clock.advance(RENDER_TIME);
+            self.inner.render(c)
+        }
+
+        fn clear(&mut self, c: &Canvas) -> TuiResult<()> {
+            self.inner.clear(c)
+        }
+
+        fn recover(&mut self) {
+            self.inner.recover()
+        }
+    }
+
+    fn slow_renderer(renders_left: Option<usize>) -> (SlowRenderer, FrameTimer, Rc<Cell<bool>>) {
+        let clock = FakeClock::new();
+        let inner = TestRenderer::new(100, 50);
+        let recovered = inner.recovered();
+        let renderer = SlowRenderer {
+            inner,
+            clock: clock.clone(),
+            renders_left,
+        };
+        (renderer, FrameTimer::with_clock(clock), recovered)
+    }
+
+    #[test]
+    fn scripted_shifts_shift_then_quit() -> Result<()> {
+        let events = ScriptedShifts::new(BENCH_DIRECTIONS.len() + 1);
+        let mut directions = Vec::new();
+        loop {
+            match events.next_event()? {
+                Event::UserInput(UserInput::Direction(d)) => directions.push(d),
+                Event::UserInput(UserInput::Quit) => break,
+                _ => panic!("only shifts and quitting are scripted"),
+            }
+        }
+        assert_eq!(directions.len(), BENCH_DIRECTIONS.len() + 1);
+        for d in BENCH_DIRECTIONS {
+            assert!(directions.contains(&d), "{:?} is never played", d);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn run_reports_every_animated_frame() -> Result<()> {
+        init()?;
+        let (renderer, timer, recovered) = slow_renderer(None);
+        let report = run(Canvas::new(100, 50), renderer, timer)?;
+
+        assert!(recovered.get());
+        assert!(report.frames > BENCH_SHIFTS, "{}", report);
+        // the fake clock only moves while rendering
+        assert_eq!(report.over_budget, report.frames);
+        assert_eq!(
+            report.render,
+            Some(PhaseStats {
+                p50: RENDER_TIME,
+                p95: RENDER_TIME,
+                max: RENDER_TIME,
+            })
+        );
+        assert_eq!(report.animate.as_ref().map(|s| s.max), Some(Duration::ZERO));
+        assert!(
+            report.changed.as_ref().is_some_and(|s| s.p50 > 0),
+            "{}",
+            report
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn run_recovers_the_terminal_when_rendering_fails() -> Result<()> {
+        init()?;
+        for renders_left in [0, 1, 10] {
+            let (renderer, timer, recovered) = slow_renderer(Some(renders_left));
+            let result = run(Canvas::new(100, 50), renderer, timer);
+            assert!(
+                result.is_err(),
+                "render {} should have failed",
+                renders_left
+            );
+            assert!(recovered.get(), "failed on render {}", renders_left);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use std::time::Instant;
+
+/// A source of the current time.
+pub(crate) trait Clock {
+    fn now(&self) -> Instant;
+}
+
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time.
+#[cfg(test)]
+#[derive(Clone)]
+pub(crate) struct FakeClock {
+    now: std::rc::Rc<std::cell::Cell<Instant>>,
+}
+
+#[cfg(test)]
+impl FakeClock {
+    pub(crate) fn new() -> Self {
+        Self {
+            now: std::rc::Rc::new(std::cell::Cell::new(Instant::now())),
+        }
+    }
+
+    pub(crate) fn advance(&self, d: std::time::Duration) {
+        self.now.set(self.now.get() + d);
+    }
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
@@ -0,0 +1,191 @@
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::engine::board::BoardConfig;
+use crate::engine::practice::Profile;
+use crate::error::{Error, Result};
+use crate::packs::PackChoice;
+use crate::quality::{AnimationSpeed, EasingFn, QualityLevel};
+use crate::tui::watchdog::Deadlines;
+use crate::tui48::{Assist, Mode};
+
+/// Settings read from a TOML file, eg
+///
+/// ```toml
+/// seed = 42
+/// visual-bell = true
+/// assist = "merges"
+/// ```
+///
+/// Every setting is optional and keys are named like the command line flags they stand in for.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct GameConfig {
+    /// Seed for the random number generator, so that the same moves play out the same game.
+    pub(crate) seed: Option<u64>,
+    pub(crate) outlook: bool,
+    pub(crate) visual_bell: bool,
+    pub(crate) assist: Option<Assist>,
+    pub(crate) score_breakdown: bool,
+    /// Leaves the window title alone rather than showing the score in it.
+    pub(crate) no_title: bool,
+    pub(crate) practice: Option<Profile>,
+    /// How many slots across and down the board is, eg "5x5".
+    pub(crate) size: Option<BoardConfig>,
+    pub(crate) mode: Option<Mode>,
+    /// How long the terminal may take to accept a frame before animations are turned off; the
+    /// game gives up on the terminal altogether after a few times as long.
+    pub(crate) render_deadline_ms: Option<u64>,
+    /// The label pack to label tiles with: one of the built-in packs or the path to a pack file.
+    pub(crate) pack: Option<PackChoice>,
+    /// Pins how finely moves are animated rather than letting it adapt to the terminal's speed.
+    pub(crate) animation_quality: Option<QualityLevel>,
+    /// How fast moves are animated; off shows them in one go whatever the animation quality.
+    pub(crate) animation_speed: Option<AnimationSpeed>,
+    /// How tiles pick up and lose speed as they slide.
+    pub(crate) easing: Option<EasingFn>,
+    /// Lays tiles out wide enough to look square rather than tall.
+    pub(crate) square_tiles: bool,
+    /// How many times as tall as they are wide the terminal's cells are, which square tiles are
+    /// sized for.
+    pub(crate) cell_aspect: Option<f64>,
+    /// How long the computer waits between the moves it makes while it plays, in milliseconds.
+    pub(crate) ai_delay_ms: Option<u64>,
+}
+
+impl GameConfig {
+    pub(crate) fn render_deadlines(&self) -> Deadlines {
+        self.render_deadline_ms
+            .map_or_else(Deadlines::default, |ms| {
+                Deadlines::from_soft(Duration::from_millis(ms))
+            })
+    }
+
+    /// Reads the config from the given file, falling back to the defaults if there is no such
+    /// file.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        toml::from_str(&text).map_err(|source| Error::InvalidConfig {
+            path: path.to_path_buf(),
+            source: Box::new(source),
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::packs::BuiltinPack;
+
+    /// Writes the given config to a path unique to this call and returns the path.
+    pub(crate) fn config_file(config: &str) -> PathBuf {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let n = COUNT.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("tui48-{}-{}.toml", std::process::id(), n));
+        std::fs::write(&path, config).expect("config should be writable");
+        path
+    }
+
+    #[test]
+    fn load_reads_every_setting() -> Result<()> {
+        let path = config_file(
+            r#"
+            seed = 42
+            outlook = true
+            visual-bell = true
+            assist = "merges"
+            score-breakdown = true
+            no-title = true
+            practice = "late-game"
+            size = "5x6"
+            mode = "arcade"
+            render-deadline-ms = 500
+            pack = "elements"
+            animation-quality = "four-cell"
+            animation-speed = "slow"
+            easing = "ease-in-out"
+            square-tiles = true
+            cell-aspect = 2.5
+            ai-delay-ms = 50
+            "#,
+        );
+        let config = GameConfig::load(&path);
+        std::fs::remove_file(&path)?;
+        let config = config?;
+        assert_eq!(
+            config,
+            GameConfig {
+                seed: Some(42),
+                outlook: true,
+                visual_bell: true,
+                assist: Some(Assist::Merges),
+                score_breakdown: true,
+                no_title: true,
+                practice: Some(Profile::LateGame),
+                size: Some(BoardConfig {
+                    width: 5,
+                    height: 6,
+                }),
+                mode: Some(Mode::Arcade),
+                render_deadline_ms: Some(500),
+                pack: Some(PackChoice::Builtin(BuiltinPack::Elements)),
+                animation_quality: Some(QualityLevel::FourCell),
+                animation_speed: Some(AnimationSpeed::Slow),
+                easing: Some(EasingFn::EaseInOut),
+                square_tiles: true,
+                cell_aspect: Some(2.5),
+                ai_delay_ms: Some(50),
+            }
+        );
+        assert_eq!(
+            config.render_deadlines(),
+            Deadlines::from_soft(Duration::from_millis(500))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn load_defaults_missing_settings() -> Result<()> {
+        let path = config_file("visual-bell = true\n");
+        let config = GameConfig::load(&path);
+        std::fs::remove_file(&path)?;
+        assert_eq!(
+            config?,
+            GameConfig {
+                visual_bell: true,
+                ..GameConfig::default()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn load_defaults_missing_file() -> Result<()> {
+        let path = std::env::temp_dir().join("tui48-no-such-config.toml");
+        let config = GameConfig::load(&path)?;
+        assert_eq!(config, GameConfig::default());
+        assert_eq!(config.render_deadlines(), Deadlines::default());
+        Ok(())
+    }
+
+    #[test]
+    fn load_rejects_unknown_settings() -> Result<()> {
+        let path = config_file("visual_bell = true\n");
+        let config = GameConfig::load(&path);
+        std::fs::remove_file(&path)?;
+        match config {
+            Err(Error::InvalidConfig { path: p, .. }) => assert_eq!(p, path),
+            other => panic!("expected an invalid config, got {:?}", other),
+        }
+        Ok(())
+    }
+}
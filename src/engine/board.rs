@@ -1,67 +1,1938 @@
-use rand::RngCore;
+use std::fmt::Write;
+use std::path::Path;
+use std::str::FromStr;
 
-use super::round::{AnimationHint, Round, Score};
-use crate::tui::geometry::Direction;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::direction::Direction;
+use super::highscore::HighScore;
+use super::history::RoundStore;
+use super::powerup::{PowerUp, PowerUps};
+use super::practice::{self, Profile};
+use super::round::{
+    card_from_display, display_value, parse_start, AnimationHint, Idx, RewindPlan, Round, Score,
+};
+use super::spawns::Spawn;
+use crate::error::{Error, Result};
+use crate::migrate::{Format, JsonObject};
+
+/// The result of attempting to shift the board in a given direction.
+pub(crate) enum MoveOutcome {
+    /// The board changed; the hint describes how tiles got where they are.
+    Moved(AnimationHint),
+    /// Nothing on the board could move in the given direction.
+    Rejected,
+}
+
+impl MoveOutcome {
+    #[cfg(test)]
+    pub(crate) fn hint(self) -> Option<AnimationHint> {
+        match self {
+            Self::Moved(hint) => Some(hint),
+            Self::Rejected => None,
+        }
+    }
+}
+
+/// How many slots across and down a board is, written `NxM` on the command line and in the
+/// config file, eg `5x5`. The classic board is 4x4.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) struct BoardConfig {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+}
+
+impl BoardConfig {
+    /// The number of slots a board may have across or down.
+    const SIZES: std::ops::RangeInclusive<usize> = 3..=8;
+
+    /// Returns the size given, or why a board can't be that size.
+    pub(crate) fn new(width: usize, height: usize) -> std::result::Result<Self, String> {
+        for (side, slots) in [("wide", width), ("high", height)] {
+            if !Self::SIZES.contains(&slots) {
+                return Err(format!(
+                    "a board {} slots {} is out of range, it must be between {} and {}",
+                    slots,
+                    side,
+                    Self::SIZES.start(),
+                    Self::SIZES.end()
+                ));
+            }
+        }
+        Ok(Self { width, height })
+    }
+
+    /// The scores at which a board in the growth mode gains a row and a column, one growth each.
+    pub(crate) const GROWTH_MILESTONES: [Score; 2] = [500, 3000];
+
+    /// Returns true for the classic 4x4 board.
+    pub(crate) fn is_classic(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The size a board in the growth mode starts at unless another is asked for.
+    pub(crate) fn growing() -> Self {
+        Self {
+            width: 3,
+            height: 3,
+        }
+    }
+
+    /// Returns the size a board of this size grows to, a row and a column larger, or None if it
+    /// is as large as a board may be either way.
+    pub(crate) fn grown(&self) -> Option<Self> {
+        Self::new(self.width + 1, self.height + 1).ok()
+    }
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        Self {
+            width: 4,
+            height: 4,
+        }
+    }
+}
+
+impl FromStr for BoardConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parse = |slots: &str| slots.trim().parse::<usize>().ok();
+        match s.split_once(['x', 'X']) {
+            Some((width, height)) => match (parse(width), parse(height)) {
+                (Some(width), Some(height)) => Self::new(width, height),
+                _ => Err(format!("expected a size like 5x5, found {:?}", s)),
+            },
+            None => Err(format!("expected a size like 5x5, found {:?}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for BoardConfig {
+    type Error = String;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl std::fmt::Display for BoardConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// A move taken back off the board by `Board::take_back`, kept to be made again exactly as it was.
+pub(crate) struct TakenMove {
+    round: Round,
+    hint: AnimationHint,
+    powers: Option<PowerUps>,
+    draw: u128,
+}
 
 /// Board represents a 2048 board that keeps track of the history of its game states.
 pub(crate) struct Board {
-    rng: Box<dyn RngCore>,
+    // kept rather than any generator given, so that it can be saved along with the game
+    rng: ChaCha8Rng,
+    rounds: RoundStore,
+    // hints[i] describes the move from round i to round i + 1
+    hints: Vec<AnimationHint>,
+    // draws[i] is where the random number generator stood before the move from round i to
+    // round i + 1, so that taking the move back takes back the tiles it dealt too and the seed
+    // along with the moves left replays the game
+    draws: Vec<u128>,
+    // powers[i] is where the power-ups stood at round i; empty unless the board plays the
+    // arcade mode
+    powers: Vec<PowerUps>,
+    // the moves and power-ups taken back, most recently taken back last; forgotten as soon as
+    // the game moves on
+    undone: Vec<Undone>,
+    high_score: HighScore,
+    // the seed the random number generator was seeded with, if the game can be replayed from it
+    seed: Option<u64>,
+    // the scores at which the board grows by a row and a column, in order; empty unless the board
+    // plays the growth mode
+    milestones: Vec<Score>,
+}
+
+/// The format games are saved and left to be resumed in. Append a migration here whenever a field
+/// of `SavedGame` is renamed or reinterpreted, and capture a file of the new version under
+/// `tests/fixtures/save`.
+const SAVE_FORMAT: Format<JsonObject> = Format {
+    name: "save",
+    migrations: &[],
+};
+
+/// A game as `Board::to_json` writes it down.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct SavedGame {
+    // the version of `SAVE_FORMAT` the game was written with
+    version: u32,
+    score: Score,
     rounds: Vec<Round>,
+    // hints[i] describes the move from round i to round i + 1
+    hints: Vec<AnimationHint>,
+    // where the random number generator stood before every move
+    draws: Vec<u128>,
+    // where the power-ups stood at every round; empty unless the game plays the arcade mode
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    powers: Vec<PowerUps>,
+    // the scores the board grows at; empty unless the game plays the growth mode
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    milestones: Vec<Score>,
+    seed: Option<u64>,
+    rng: ChaCha8Rng,
+}
+
+/// A move or power-up taken back, kept so that it can be made again: the round it led to, its
+/// hint, where the power-ups stood after it and where the random number generator stood before
+/// and after it.
+struct Undone {
+    round: Round,
+    hint: AnimationHint,
+    powers: Option<PowerUps>,
+    draws: (u128, u128),
 }
 
 impl Board {
-    /// Initialize new board using the given random number generator.
-    pub(crate) fn new(mut rng: impl RngCore + 'static) -> Self {
-        let mut rounds = Vec::with_capacity(2000);
-        rounds.push(Round::random(&mut rng));
+    /// Initialize new board of the given size using the given random number generator, which
+    /// seeds the board's own for the rest of the game.
+    pub(crate) fn new(mut rng: impl RngCore, config: BoardConfig) -> Self {
+        let rounds = RoundStore::new(Round::random(&mut rng, config.width, config.height));
         Self {
-            rng: Box::new(rng),
+            rng: ChaCha8Rng::seed_from_u64(rng.next_u64()),
             rounds,
+            hints: Vec::with_capacity(2000),
+            draws: Vec::with_capacity(2000),
+            powers: Vec::new(),
+            undone: Vec::new(),
+            high_score: HighScore::default(),
+            seed: None,
+            milestones: Vec::new(),
+        }
+    }
+
+    /// Initialize new board of the given size with a random number generator seeded with the
+    /// given seed, so that the same moves always play out the same game.
+    pub(crate) fn new_seeded(seed: u64, config: BoardConfig) -> Self {
+        Self {
+            seed: Some(seed),
+            ..Self::new(ChaCha8Rng::seed_from_u64(seed), config)
+        }
+    }
+
+    /// Initialize a board starting from a practice position of the given profile, using the given
+    /// random number generator both to synthesize the position and for the rest of the game.
+    pub(crate) fn practice_position(mut rng: impl RngCore, profile: Profile) -> Result<Self> {
+        let round = practice::generate(&mut rng, profile)?;
+        Ok(Self::from_position(rng, round))
+    }
+
+    /// Like `practice_position`, with a random number generator seeded with the given seed.
+    pub(crate) fn practice_position_seeded(seed: u64, profile: Profile) -> Result<Self> {
+        Ok(Self {
+            seed: Some(seed),
+            ..Self::practice_position(ChaCha8Rng::seed_from_u64(seed), profile)?
+        })
+    }
+
+    /// Starts a game from the given round rather than an empty board, eg one built in the board
+    /// editor.
+    pub(crate) fn from_position(mut rng: impl RngCore, round: Round) -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(rng.next_u64()),
+            rounds: RoundStore::new(round),
+            hints: Vec::with_capacity(2000),
+            draws: Vec::with_capacity(2000),
+            powers: Vec::new(),
+            undone: Vec::new(),
+            high_score: HighScore::default(),
+            seed: None,
+            milestones: Vec::new(),
+        }
+    }
+
+    /// Plays the arcade mode, where merging tiles earns power-ups (see `PowerUp`).
+    pub(crate) fn with_power_ups(mut self) -> Self {
+        self.powers = vec![PowerUps::default(); self.rounds.len()];
+        self
+    }
+
+    /// Plays the growth mode, where the board grows by a row and a column each time the score
+    /// reaches the next of the given milestones; see `grow`.
+    pub(crate) fn with_growth(mut self, milestones: &[Score]) -> Self {
+        self.milestones = milestones.to_vec();
+        self
+    }
+
+    /// Returns true if the board plays the growth mode.
+    pub(crate) fn grows(&self) -> bool {
+        !self.milestones.is_empty()
+    }
+
+    /// Returns the size the board grows to next if its score has reached the milestone for it,
+    /// or None if it isn't due to grow.
+    fn growth_due(&self) -> Option<BoardConfig> {
+        let (first_width, _) = self.rounds.first().dimensions();
+        // every growth adds a column, so the board's width says how many milestones it has reached
+        let grown = self.dimensions().0 - first_width;
+        let milestone = self.milestones.get(grown)?;
+        match self.score() >= *milestone {
+            true => self.size().grown(),
+            false => None,
         }
     }
 
+    /// Grows the board by a row and a column if the score has reached the next milestone of the
+    /// growth mode, returning the hint describing the growth, or None if it isn't due. Meant to
+    /// be called between moves; each milestone grows the board once, and the growth is recorded
+    /// in the history like a move, so that taking it back shrinks the board again.
+    pub(crate) fn grow(&mut self) -> Option<AnimationHint> {
+        let size = self.growth_due()?;
+        let mut round = self.current();
+        let draw = self.rng.get_word_pos();
+        let hint = round.grow_to(size.width, size.height);
+        if let Some(powers) = self.powers.last() {
+            self.powers.push(powers.clone());
+        }
+        log::trace!("round {} grew to {}", self.rounds.len() - 1, size);
+        self.rounds.push(round, &hint);
+        self.hints.push(hint.clone());
+        self.draws.push(draw);
+        self.undone.clear();
+        Some(hint)
+    }
+
+    /// Measures the game against the given high score, which the board's score raises as soon as
+    /// it beats it.
+    pub(crate) fn set_high_score(&mut self, high_score: HighScore) {
+        self.high_score = high_score;
+        self.high_score.record(self.score());
+    }
+
+    /// Returns the best score reached so far, this game's included.
+    pub(crate) fn high_score(&self) -> &HighScore {
+        &self.high_score
+    }
+
+    /// Returns the seed the game was started from, or None unless it was started from one.
+    pub(crate) fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Returns where the power-ups stand, or None unless the board plays the arcade mode.
+    pub(crate) fn power_ups(&self) -> Option<&PowerUps> {
+        self.powers.last()
+    }
+
+    /// Returns the number of moves made since the game started. Using a power-up isn't a move.
+    pub(crate) fn move_count(&self) -> usize {
+        self.hints
+            .iter()
+            .filter(|hint| hint.direction().is_some())
+            .count()
+    }
+
     pub(crate) fn score(&self) -> Score {
-        self.rounds.last().map_or(0, |r| r.score())
+        self.rounds.current().score()
     }
 
-    /// try_shift attempts to shift the board in the given direction and returns an AnimationHint
-    /// if anything changes.
-    pub(crate) fn shift(&mut self, direction: Direction) -> Option<AnimationHint> {
-        let prev = self
-            .rounds
-            .last()
-            .expect("there should always be a previous round");
+    /// shift attempts to shift the board in the given direction. Moves that wouldn't change
+    /// anything are rejected up front without cloning the current round or consuming the RNG.
+    pub(crate) fn shift(&mut self, direction: Direction) -> MoveOutcome {
+        let prev = self.rounds.current();
+        if !prev.would_change(&direction) {
+            log::trace!(
+                "rejected shift {} of round {}",
+                direction,
+                self.rounds.len() - 1
+            );
+            return MoveOutcome::Rejected;
+        }
+
+        let draw = self.rng.get_word_pos();
         let mut round = prev.clone();
-        let hint = round.shift(&mut self.rng, &direction);
+        match round.shift(&mut self.rng, &direction) {
+            Some(hint) => self.push_shift(&direction, round, hint, draw),
+            None => unreachable!("would_change guarantees that the shift changes the round"),
+        }
+    }
+
+    /// Returns the hint `shift` would return for the given direction, new tile included, without
+    /// making the move, or None if the move would be rejected. The new tile is drawn from a clone
+    /// of the board's own random number generator rather than a fixed seed, so that it's the tile
+    /// the move would really deal.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn peek_shift(&self, direction: Direction) -> Option<AnimationHint> {
+        let prev = self.rounds.current();
+        if !prev.would_change(&direction) {
+            return None;
+        }
+        let mut round = prev.clone();
+        let mut hint = round.shift(&mut self.rng.clone(), &direction)?;
+        if self.powers.last().is_some_and(|powers| powers.doubling()) {
+            round.double_new_tile(&mut hint);
+        }
+        Some(hint)
+    }
+
+    /// Shifts the board like `shift`, but places the new tile the given spawn decided on rather
+    /// than a random one; see `Spawn::place`. The board's own random number generator is left
+    /// alone.
+    pub(crate) fn shift_spawning(&mut self, direction: Direction, spawn: &Spawn) -> MoveOutcome {
+        let prev = self.rounds.current();
+        if !prev.would_change(&direction) {
+            return MoveOutcome::Rejected;
+        }
+
+        let mut slid = prev.clone();
+        slid.slide(&direction);
+        let candidates = slid.spawn_candidates(&direction);
+        let idx = spawn
+            .place(&candidates)
+            .expect("a shift that changes the round leaves a slot for the new tile");
+        let mut round = prev.clone();
+        let draw = self.rng.get_word_pos();
+        match round.shift_placing(&direction, idx, spawn.value()) {
+            Some(hint) => self.push_shift(&direction, round, hint, draw),
+            None => unreachable!("the new tile is placed in a slot the shift leaves empty"),
+        }
+    }
+
+    /// Records the round a shift led to, counting its merges towards the next power-up, along
+    /// with where the random number generator stood before it.
+    fn push_shift(
+        &mut self,
+        direction: &Direction,
+        mut round: Round,
+        mut hint: AnimationHint,
+        draw: u128,
+    ) -> MoveOutcome {
+        if let Some(powers) = self.powers.last() {
+            let mut powers = powers.clone();
+            if powers.doubling() {
+                round.double_new_tile(&mut hint);
+                powers.set_doubling(false);
+            }
+            powers.record_merges(hint.merges());
+            self.powers.push(powers);
+        }
+        log::trace!(
+            "round {} shifted {}: {}",
+            self.rounds.len() - 1,
+            direction,
+            hint.to_debug_string()
+        );
+        self.rounds.push(round, &hint);
+        self.hints.push(hint.clone());
+        self.draws.push(draw);
+        self.undone.clear();
+        self.high_score.record(self.score());
+        MoveOutcome::Moved(hint)
+    }
 
-        if hint.is_some() {
-            self.rounds.push(round);
+    /// Uses the charged power-up, recording it in the history like a move. Power-ups that
+    /// wouldn't change the board, such as merging all pairs when no two tiles are equal, are
+    /// rejected and stay charged, as is everything when no power-up is charged.
+    pub(crate) fn activate_power_up(&mut self) -> MoveOutcome {
+        let mut powers = match self.powers.last() {
+            Some(powers) if powers.charged().is_some() => powers.clone(),
+            _ => return MoveOutcome::Rejected,
+        };
+        let power_up = powers.next();
+        let mut round = self.current();
+        let draw = self.rng.get_word_pos();
+        let hint = power_up.apply(&mut round, &mut self.rng);
+        match power_up {
+            PowerUp::DoubleNextSpawn => powers.set_doubling(true),
+            // nothing it drew is kept, so that replaying the moves made draws the same
+            _ if !hint.changed() => {
+                self.rng.set_word_pos(draw);
+                return MoveOutcome::Rejected;
+            }
+            _ => (),
         }
-        hint
+        powers.spend();
+        log::trace!(
+            "round {} used power-up {}: {}",
+            self.rounds.len() - 1,
+            power_up.name(),
+            hint.to_debug_string()
+        );
+        self.rounds.push(round, &hint);
+        self.hints.push(hint.clone());
+        self.draws.push(draw);
+        self.powers.push(powers);
+        self.undone.clear();
+        self.high_score.record(self.score());
+        MoveOutcome::Moved(hint)
+    }
+
+    /// Returns whether there is a move or power-up used to take back.
+    pub(crate) fn can_undo(&self) -> bool {
+        !self.hints.is_empty()
+    }
+
+    /// Takes back the latest move or power-up used, returning the plan for animating it backwards,
+    /// or None if there was none to take back; the first round is never taken back.
+    pub(crate) fn undo(&mut self) -> Option<RewindPlan> {
+        let plan = self.rewind_plan()?;
+        let round = self.rounds.pop().expect("the move led to a round");
+        let hint = self.hints.pop().expect("a move to take back has a hint");
+        let powers = match self.powers.len() > self.rounds.len() {
+            true => self.powers.pop(),
+            false => None,
+        };
+        let before = self.draws.pop().expect("a move to take back has a draw");
+        let after = self.rng.get_word_pos();
+        self.rng.set_word_pos(before);
+        log::trace!("took back round {}: {:?}", self.rounds.len(), plan);
+        self.undone.push(Undone {
+            round,
+            hint,
+            powers,
+            draws: (before, after),
+        });
+        Some(plan)
+    }
+
+    /// Makes the move or power-up most recently taken back again, exactly as it was made the first
+    /// time, new tile and all, returning its hint, or None if there is none to make again. What's
+    /// taken back can be made again until another move or power-up is made in its place.
+    pub(crate) fn redo(&mut self) -> Option<AnimationHint> {
+        let Undone {
+            round,
+            hint,
+            powers,
+            draws: (before, after),
+        } = self.undone.pop()?;
+        log::trace!(
+            "made round {} again: {}",
+            self.rounds.len(),
+            hint.to_debug_string()
+        );
+        self.rounds.push(round, &hint);
+        self.hints.push(hint.clone());
+        self.draws.push(before);
+        self.rng.set_word_pos(after);
+        self.powers.extend(powers);
+        self.high_score.record(self.score());
+        Some(hint)
     }
 
     pub(crate) fn current(&self) -> Round {
-        self.rounds
-            .last()
-            .expect("a board must always have at least one round")
-            .clone()
+        self.rounds.current().clone()
     }
 
+    /// Returns the round after the given number of moves and power-ups used, or None if fewer
+    /// have been made.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn round_at(&self, n: usize) -> Option<Round> {
+        self.rounds.round_at(n)
+    }
+
+    /// Returns the plan for animating the latest move backwards, or None if no move has been made.
+    pub(crate) fn rewind_plan(&self) -> Option<RewindPlan> {
+        let hint = self.hints.last()?;
+        Some(self.current().rewind_plan(hint))
+    }
+
+    /// Takes the latest move back off the board, leaving the random number generator alone so that
+    /// `put_back` can make it again exactly as it was; None if no move has been made.
+    pub(crate) fn take_back(&mut self) -> Option<TakenMove> {
+        let hint = self.hints.pop()?;
+        let round = self
+            .rounds
+            .pop()
+            .expect("every hint has the round it led to");
+        let powers = if self.powers.is_empty() {
+            None
+        } else {
+            self.powers.pop()
+        };
+        let draw = self
+            .draws
+            .pop()
+            .expect("every hint has the draw it was dealt from");
+        Some(TakenMove {
+            round,
+            hint,
+            powers,
+            draw,
+        })
+    }
+
+    /// Makes a move taken back by `take_back` again, returning its hint.
+    pub(crate) fn put_back(&mut self, taken: TakenMove) -> AnimationHint {
+        self.rounds.push(taken.round, &taken.hint);
+        self.hints.push(taken.hint.clone());
+        self.draws.push(taken.draw);
+        self.powers.extend(taken.powers);
+        taken.hint
+    }
+
+    /// Writes the game to the given path as a `[Start "..."]` line giving the starting tiles row by
+    /// row, followed by one line per move in the form
+    /// `move_number direction new_tile_idx new_tile_value score`, eg `1 Down (3,2) 2 0`. Tile
+    /// values are written as shown on the board and the score is the score after the move.
+    /// Games that used power-ups or grew can't be written this way.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn export_pgn_like(&self, path: &Path) -> Result<()> {
+        if self.hints.iter().any(|hint| hint.grew_from().is_some()) {
+            return Err(Error::GrowthNotRecordable);
+        }
+        if self.move_count() != self.hints.len() {
+            return Err(Error::PowerUpsNotRecordable);
+        }
+        std::fs::write(path, self.pgn_like())?;
+        Ok(())
+    }
+
+    /// Reads a game written by `export_pgn_like`, replaying every move to rebuild the history. The
+    /// given random number generator is used for moves made after the import.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn import_pgn_like(path: &Path, mut rng: impl RngCore) -> Result<Board> {
+        let notation = std::fs::read_to_string(path)?;
+        let mut lines = notation
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+
+        let invalid = |line: usize, reason: String| Error::InvalidMoveRecord { line, reason };
+        let (line, start) = lines
+            .next()
+            .ok_or_else(|| invalid(1, String::from("missing start position")))?;
+        let start = parse_start(start).map_err(|reason| invalid(line, reason))?;
+
+        let mut rounds = RoundStore::new(start);
+        let mut hints = Vec::new();
+        for (line, record) in lines {
+            let mut round = rounds.current().clone();
+            let hint = replay_move(&mut round, record, rounds.len())
+                .map_err(|reason| invalid(line, reason))?;
+            rounds.push(round, &hint);
+            hints.push(hint);
+        }
+        Ok(Board {
+            rng: ChaCha8Rng::seed_from_u64(rng.next_u64()),
+            rounds,
+            // the moves were made before the generator drew anything
+            draws: vec![0; hints.len()],
+            hints,
+            powers: Vec::new(),
+            undone: Vec::new(),
+            high_score: HighScore::default(),
+            seed: None,
+            milestones: Vec::new(),
+        })
+    }
+
+    fn pgn_like(&self) -> String {
+        let mut rounds = self.rounds.iter();
+        let start = rounds
+            .next()
+            .expect("a board must always have at least one round");
+        let mut out = format!("{}\n", start.start_tag());
+        let mut prev = start;
+        for (n, (next, hint)) in rounds.zip(self.hints.iter()).enumerate() {
+            let direction = hint.direction().expect("every move places a new tile");
+            let mut slid = prev.clone();
+            slid.slide(&direction);
+            let (idx, _, value) = Round::diff(&slid, &next)
+                .pop()
+                .expect("every move places a new tile");
+            let _ = writeln!(
+                out,
+                "{} {:?} ({},{}) {} {}",
+                n + 1,
+                direction,
+                idx.x(),
+                idx.y(),
+                display_value(value),
+                next.score()
+            );
+            prev = next;
+        }
+        out
+    }
+
+    /// Writes the game down as JSON: every round it went through, so that moves can still be
+    /// taken back once it's loaded, and the state of its random number generator, so that it goes
+    /// on to deal the tiles it would have. Moves taken back can't be made again once it's loaded.
+    fn to_json(&self) -> String {
+        let saved = SavedGame {
+            version: SAVE_FORMAT.current_version(),
+            score: self.score(),
+            rounds: self.rounds.iter().collect(),
+            hints: self.hints.clone(),
+            draws: self.draws.clone(),
+            powers: self.powers.clone(),
+            milestones: self.milestones.clone(),
+            seed: self.seed,
+            rng: self.rng.clone(),
+        };
+        serde_json::to_string(&saved).expect("a game can always be written as JSON")
+    }
+
+    /// Saves the game to the given file, see `to_json`.
+    pub(crate) fn save_to(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_json())?;
+        Ok(())
+    }
+
+    /// Loads the game saved to the given file, or None if there is no such file. A file that
+    /// isn't a game `save_to` could have written is refused, whatever is wrong with it.
+    pub(crate) fn load_from(path: &Path) -> Result<Option<Board>> {
+        let json = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Self::from_json(&json)
+            .map(Some)
+            .map_err(|reason| Error::CorruptSave {
+                path: path.to_path_buf(),
+                reason,
+            })
+    }
+
+    /// Reads a game written by `to_json`, by this release or an older one. The high score is left
+    /// for the caller to set.
+    fn from_json(s: &str) -> std::result::Result<Board, String> {
+        let mut saved: JsonObject = serde_json::from_str(s).map_err(|e| e.to_string())?;
+        SAVE_FORMAT.upgrade(&mut saved).map_err(|e| e.to_string())?;
+        let saved: SavedGame = serde_json::from_value(saved.into()).map_err(|e| e.to_string())?;
+        let mut rounds = saved.rounds.into_iter();
+        let first = rounds
+            .next()
+            .ok_or_else(|| String::from("there are no rounds"))?;
+        let (width, height) = first.dimensions();
+        BoardConfig::new(width, height)
+            .map_err(|reason| format!("unsupported board size: {}", reason))?;
+        first.validate().map_err(|e| e.to_string())?;
+        if saved.hints.len() != rounds.len() {
+            return Err(format!(
+                "expected a hint for each of the {} moves, found {}",
+                rounds.len(),
+                saved.hints.len()
+            ));
+        }
+        if saved.draws.len() != rounds.len() {
+            return Err(format!(
+                "expected where the random number generator stood before each of the {} moves, \
+                 found {}",
+                rounds.len(),
+                saved.draws.len()
+            ));
+        }
+        if !saved.powers.is_empty() && saved.powers.len() != rounds.len() + 1 {
+            return Err(format!(
+                "expected where the power-ups stood at each of the {} rounds, found {}",
+                rounds.len() + 1,
+                saved.powers.len()
+            ));
+        }
+
+        let mut store = RoundStore::new(first);
+        for (n, (round, hint)) in rounds.zip(saved.hints.iter()).enumerate() {
+            let prev = store.current();
+            if let Some((width, height)) = hint.grew_from() {
+                let (grown_width, grown_height) = round.dimensions();
+                BoardConfig::new(grown_width, grown_height)
+                    .map_err(|reason| format!("round {} grew too large: {}", n + 1, reason))?;
+                let mut shrunk = round.clone();
+                let grew = shrunk.shrink_to(width, height).is_ok() && shrunk == *prev;
+                if !grew {
+                    return Err(format!("round {} didn't grow from the round before", n + 1));
+                }
+            } else if round.dimensions() != prev.dimensions() {
+                return Err(format!("round {} is a different size", n + 1));
+            }
+            round.validate().map_err(|e| e.to_string())?;
+            if hint.grew_from().is_none() && prev.replay(hint).as_ref() != Some(&round) {
+                return Err(format!(
+                    "round {} doesn't follow from the round before by its hint",
+                    n + 1
+                ));
+            }
+            store.push(round, hint);
+        }
+        if store.current().score() != saved.score {
+            return Err(format!(
+                "the score is {} but the last round scores {}",
+                saved.score,
+                store.current().score()
+            ));
+        }
+        Ok(Board {
+            rng: saved.rng,
+            rounds: store,
+            hints: saved.hints,
+            draws: saved.draws,
+            powers: saved.powers,
+            undone: Vec::new(),
+            high_score: HighScore::default(),
+            seed: saved.seed,
+            milestones: saved.milestones,
+        })
+    }
+
+    /// Returns the number of slots across and down the board.
     pub(crate) fn dimensions(&self) -> (usize, usize) {
-        (4, 4)
+        self.rounds.current().dimensions()
+    }
+
+    /// Returns the size of the board.
+    pub(crate) fn size(&self) -> BoardConfig {
+        let (width, height) = self.dimensions();
+        BoardConfig { width, height }
+    }
+
+    /// Returns the size the board started the game at, which differs from its size once it has
+    /// grown.
+    pub(crate) fn first_size(&self) -> BoardConfig {
+        let (width, height) = self.rounds.first().dimensions();
+        BoardConfig { width, height }
+    }
+
+    /// Returns the number of empty slots in the current round.
+    pub(crate) fn empty_count(&self) -> usize {
+        self.rounds.current().empty_count()
     }
 
+    /// Returns true once a tile worth 2048 or more is on the board.
+    pub(crate) fn has_won(&self) -> bool {
+        self.rounds.current().has_won()
+    }
+
+    /// Returns true if no shift would change the board, unless the arcade mode has a power-up
+    /// charged that would take a tile off it or the growth mode is about to grow the board.
     pub(crate) fn is_game_over(&self) -> bool {
-        self.rounds
-            .last()
-            .expect("a board must always have at least one round")
-            .is_game_over(&Direction::Right)
+        let rescue = self.power_ups().and_then(|powers| powers.charged());
+        self.rounds.current().is_game_over()
+            && rescue != Some(PowerUp::RemoveSmallest)
+            && self.growth_due().is_none()
     }
 
     #[cfg(test)]
     pub(crate) fn set_initial_round(&mut self, round: Round) {
-        let mut v = Vec::with_capacity(1);
-        v.push(round);
-        self.rounds = v;
+        self.rounds = RoundStore::new(round);
+        self.hints.clear();
+        self.draws.clear();
+        self.powers.truncate(1);
+        self.undone.clear();
+    }
+
+    /// Charges the given power-up, as if enough merges had been made to earn it.
+    #[cfg(test)]
+    pub(crate) fn charge_power_up(&mut self, power_up: PowerUp) {
+        if let Some(powers) = self.powers.last_mut() {
+            *powers = PowerUps::charged_with(power_up);
+        }
+    }
+}
+
+/// Applies the move described by the given record to the round, checking that it is the expected
+/// move number and reaches the recorded score.
+fn replay_move(
+    round: &mut Round,
+    record: &str,
+    number: usize,
+) -> std::result::Result<AnimationHint, String> {
+    let fields: Vec<&str> = record.split_whitespace().collect();
+    let [n, direction, idx, value, score] = fields[..] else {
+        return Err(format!("expected 5 fields, found {}", fields.len()));
+    };
+    if n.parse::<usize>().ok() != Some(number) {
+        return Err(format!("expected move {}, found {:?}", number, n));
+    }
+    let direction: Direction = direction.parse()?;
+    let (width, height) = round.dimensions();
+    let idx = idx
+        .strip_prefix('(')
+        .and_then(|rest| rest.strip_suffix(')'))
+        .and_then(|coords| coords.split_once(','))
+        .and_then(|(x, y)| Some(Idx(x.parse().ok()?, y.parse().ok()?)))
+        .filter(|idx| idx.x() < width && idx.y() < height)
+        .ok_or_else(|| format!("invalid tile position {:?}", idx))?;
+    let card = value
+        .parse()
+        .ok()
+        .and_then(card_from_display)
+        .filter(|card| *card > 0)
+        .ok_or_else(|| format!("invalid tile {:?}", value))?;
+    let score: Score = score
+        .parse()
+        .map_err(|_| format!("invalid score {:?}", score))?;
+
+    let hint = round.shift_placing(&direction, &idx, card).ok_or_else(|| {
+        format!(
+            "{:?} can't place a tile at ({},{})",
+            direction,
+            idx.x(),
+            idx.y()
+        )
+    })?;
+    if round.score() != score {
+        return Err(format!("expected score {}, found {}", round.score(), score));
+    }
+    Ok(hint)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use rstest::*;
+
+    use super::*;
+    use crate::engine::fixtures::{one_merge_from_winning, round, Values};
+    use crate::engine::history::RECENT_ROUNDS;
+    use crate::engine::powerup::MERGES_PER_POWER_UP;
+    use crate::engine::round::{Card, Hint, DIRECTIONS};
+    use crate::engine::strategy::Strategy;
+    use crate::migrate::test::fixtures;
+
+    #[rstest]
+    #[case::classic("4x4", Ok((4, 4)))]
+    #[case::wide("6x3", Ok((6, 3)))]
+    #[case::capital_x("5X5", Ok((5, 5)))]
+    #[case::too_narrow("2x4", Err("a board 2 slots wide is out of range"))]
+    #[case::too_high("4x9", Err("a board 9 slots high is out of range"))]
+    #[case::no_height("5x", Err("expected a size like 5x5"))]
+    #[case::not_a_size("big", Err("expected a size like 5x5"))]
+    fn board_sizes_are_read_as_width_by_height(
+        #[case] text: &str,
+        #[case] expected: std::result::Result<(usize, usize), &str>,
+    ) {
+        match (text.parse::<BoardConfig>(), expected) {
+            (Ok(size), Ok((width, height))) => {
+                assert_eq!(size, BoardConfig { width, height });
+                assert_eq!(size.to_string().parse(), Ok(size));
+            }
+            (Err(e), Err(reason)) => assert!(e.contains(reason), "{}", e),
+            (outcome, expected) => panic!("{:?} read as {:?}", expected, outcome),
+        }
+    }
+
+    #[test]
+    fn boards_are_made_the_size_asked_for() {
+        let size = BoardConfig::new(5, 6).expect("5x6 is a board size");
+        let mut board = Board::new_seeded(3, size);
+        assert_eq!(board.dimensions(), (5, 6));
+        assert_eq!(board.size(), size);
+        assert!(!size.is_classic());
+        for direction in DIRECTIONS {
+            let _ = board.shift(direction);
+        }
+        assert!(board.move_count() > 0);
+        assert_eq!(board.current().dimensions(), (5, 6));
+    }
+
+    fn board(values: Values) -> Board {
+        let mut b = Board::new(SmallRng::seed_from_u64(42), BoardConfig::default());
+        b.set_initial_round(round!(values));
+        b
+    }
+
+    #[test]
+    fn empty_count_follows_scripted_moves() {
+        let mut b = board([[2, 2, 0, 0], [4, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0]]);
+        assert_eq!(b.empty_count(), 12);
+
+        // every move spawns one tile and frees one slot per merge
+        let script = [
+            (Direction::Left, 1),
+            (Direction::Up, 1),
+            (Direction::Right, 0),
+        ];
+        for (direction, merges) in script {
+            let before = b.empty_count();
+            let hint = b
+                .shift(direction.clone())
+                .hint()
+                .expect("every scripted move should change the board");
+            assert_eq!(hint.merges(), merges, "merges shifting {:?}", direction);
+            assert_eq!(b.empty_count(), before + merges - 1);
+            assert_eq!(b.empty_count(), b.current().empty_count());
+        }
+    }
+
+    #[test]
+    fn rejected_shift_leaves_history_untouched() {
+        let mut b = board([[2, 4, 0, 0], [8, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]);
+        let before = b.current();
+        assert!(matches!(b.shift(Direction::Left), MoveOutcome::Rejected));
+        assert_eq!(b.rounds.len(), 1);
+        assert_eq!(b.current(), before);
+        assert_eq!(b.move_count(), 0, "a shift changing nothing isn't a move");
+    }
+
+    // the counting allocator replaces the global allocator of the whole test binary, so it's
+    // only built in when asked for
+    #[cfg(feature = "bench-allocations")]
+    mod allocations {
+        use std::alloc::{GlobalAlloc, Layout, System};
+        use std::cell::Cell;
+
+        use super::*;
+
+        /// Counts the allocations made on a thread while it has counting switched on, leaving every
+        /// other thread's alone.
+        struct CountingAllocator;
+
+        thread_local! {
+            static COUNTING: Cell<bool> = const { Cell::new(false) };
+            static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        unsafe impl GlobalAlloc for CountingAllocator {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                if COUNTING.try_with(Cell::get).unwrap_or(false) {
+                    let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+                }
+                System.alloc(layout)
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                System.dealloc(ptr, layout)
+            }
+        }
+
+        #[global_allocator]
+        static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+        /// Runs the given function, returning what it returns along with how many allocations it
+        /// made on this thread.
+        fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+            ALLOCATIONS.with(|n| n.set(0));
+            COUNTING.with(|counting| counting.set(true));
+            let result = f();
+            COUNTING.with(|counting| counting.set(false));
+            (result, ALLOCATIONS.with(Cell::get))
+        }
+
+        /// Times shifts that are rejected for changing nothing, checking that they allocate nothing
+        /// along the way. Run with
+        /// `cargo test --release --features bench-allocations -- --ignored --nocapture bench_`.
+        #[test]
+        #[ignore]
+        fn bench_rejected_shift() {
+            use std::hint::black_box;
+            use std::time::Instant;
+
+            const ITERATIONS: usize = 1_000_000;
+            // nothing can move left or up
+            let mut b = board([[2, 4, 0, 0], [8, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]);
+
+            let (elapsed, allocations) = count_allocations(|| {
+                let start = Instant::now();
+                for _ in 0..ITERATIONS {
+                    for direction in [Direction::Left, Direction::Up] {
+                        let outcome = black_box(b.shift(black_box(direction)));
+                        assert!(matches!(outcome, MoveOutcome::Rejected));
+                    }
+                }
+                start.elapsed()
+            });
+
+            println!(
+                "{} rejected shifts: {:?} ({:?} each), {} allocations",
+                2 * ITERATIONS,
+                elapsed,
+                elapsed / (2 * ITERATIONS) as u32,
+                allocations
+            );
+            assert_eq!(allocations, 0, "rejected shifts shouldn't allocate");
+            assert_eq!(b.rounds.len(), 1);
+        }
+    }
+
+    #[rstest]
+    fn peeking_at_a_shift_changes_nothing(
+        #[values(Direction::Left, Direction::Right, Direction::Up, Direction::Down)]
+        direction: Direction,
+    ) {
+        let mut b = board([[2, 2, 0, 0], [4, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0]]);
+        b.shift(Direction::Right);
+        let rounds: Vec<Round> = b.rounds.iter().collect();
+        let draw = b.rng.get_word_pos();
+
+        let peeked = b.peek_shift(direction.clone());
+        assert!(b.rounds.iter().eq(rounds.iter().cloned()));
+        assert_eq!(b.hints.len(), 1);
+        assert_eq!(b.rng.get_word_pos(), draw);
+        // the move made is the one peeked at, new tile and all
+        match (peeked, b.shift(direction)) {
+            (Some(peeked), MoveOutcome::Moved(hint)) => assert!(peeked == hint),
+            (None, MoveOutcome::Rejected) => (),
+            (peeked, _) => panic!("peeked at {:?}", peeked.map(|hint| hint.to_string())),
+        }
+    }
+
+    #[rstest]
+    #[case::fresh(Board::new_seeded(7, BoardConfig::default()))]
+    #[case::wide(Board::new_seeded(7, BoardConfig::new(6, 3).unwrap()))]
+    #[case::doubling({
+        let mut b = Board::new_seeded(7, BoardConfig::default()).with_power_ups();
+        b.charge_power_up(PowerUp::DoubleNextSpawn);
+        b.activate_power_up();
+        b
+    })]
+    fn peeking_in_every_direction_leaves_the_board_as_it_was(#[case] mut b: Board) {
+        b.shift(Direction::Left);
+        let before = b.to_json();
+        for direction in DIRECTIONS {
+            b.peek_shift(direction.clone());
+            assert_eq!(
+                b.to_json(),
+                before,
+                "peeking {:?} changed the board",
+                direction
+            );
+        }
+    }
+
+    #[test]
+    fn rejected_shift_leaves_rng_untouched() {
+        let slots = [[2, 4, 0, 0], [8, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]];
+        let mut with_rejections = board(slots);
+        let mut without_rejections = board(slots);
+        let all = [
+            Direction::Left,
+            Direction::Right,
+            Direction::Up,
+            Direction::Down,
+        ];
+
+        let mut rejections = 0;
+        for direction in [
+            Direction::Down,
+            Direction::Right,
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+        ] {
+            // interleave every move that would be rejected at this point
+            for rejected in all.iter() {
+                if !with_rejections.current().would_change(rejected) {
+                    let outcome = with_rejections.shift(rejected.clone());
+                    assert!(matches!(outcome, MoveOutcome::Rejected));
+                    rejections += 1;
+                }
+            }
+
+            let a = with_rejections.shift(direction.clone()).hint();
+            let b = without_rejections.shift(direction).hint();
+            assert_eq!(a.is_some(), b.is_some());
+            assert_eq!(with_rejections.current(), without_rejections.current());
+        }
+        assert!(rejections > 0);
+    }
+
+    #[rstest]
+    #[case::empty([[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]])]
+    #[case::identity([[2, 0, 0, 0], [0, 2, 0, 0], [0, 0, 2, 0], [0, 0, 0, 2]])]
+    #[case::packed_left([[2, 4, 0, 0], [8, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]])]
+    #[case::packed_row([[2, 4, 8, 16], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]])]
+    #[case::mergeable_row([[2, 2, 8, 16], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]])]
+    #[case::mergeable_across_gap([[4, 0, 0, 4], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]])]
+    #[case::full_no_merges([[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]])]
+    #[case::full_one_merge([[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 4]])]
+    #[case::one_gap([[8, 16, 32, 64], [64, 0, 16, 8], [8, 16, 32, 64], [64, 32, 16, 8]])]
+    fn would_change_matches_shift(
+        #[case] slots: Values,
+        #[values(Direction::Left, Direction::Right, Direction::Up, Direction::Down)]
+        direction: Direction,
+    ) {
+        let r = round!(slots);
+        let mut shifted = r.clone();
+        let changed = shifted
+            .shift(SmallRng::seed_from_u64(42), &direction)
+            .is_some();
+        assert_eq!(
+            r.would_change(&direction),
+            changed,
+            "shifting {:?}",
+            direction
+        );
+
+        let mut b = board(slots);
+        let outcome = b.shift(direction.clone());
+        assert_eq!(
+            outcome.hint().is_some(),
+            changed,
+            "shifting {:?}",
+            direction
+        );
+    }
+
+    #[test]
+    fn practice_position_starts_the_history() {
+        let mut b = Board::practice_position(SmallRng::seed_from_u64(42), Profile::LateGame)
+            .expect("a late game position should be generated");
+        let start = b.current();
+        assert_eq!(b.rounds.len(), 1);
+        assert!(b.hints.is_empty());
+        assert_eq!(b.score(), start.min_score());
+        assert!(start.empty_count() <= 3);
+
+        let direction = start.legal_moves()[0].clone();
+        assert!(matches!(b.shift(direction), MoveOutcome::Moved(_)));
+        assert_eq!(b.round_at(0), Some(start));
+    }
+
+    #[test]
+    fn rewind_plan_takes_back_the_latest_move() {
+        let mut b = board([[2, 2, 0, 0], [4, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0]]);
+        assert!(b.rewind_plan().is_none(), "nothing to take back yet");
+
+        let mut history = vec![b.current()];
+        for direction in [Direction::Left, Direction::Up, Direction::Right] {
+            assert!(matches!(b.shift(direction), MoveOutcome::Moved(_)));
+            history.push(b.current());
+        }
+        let plan = b.rewind_plan().expect("moves have been made");
+        assert_eq!(b.current().rewind(&plan), history[history.len() - 2]);
+        assert_eq!(b.hints.len(), b.rounds.len() - 1);
+    }
+
+    #[test]
+    fn undo_takes_moves_back_down_to_the_first_round() {
+        let mut b = board([[2, 2, 0, 0], [4, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0]]);
+        assert!(!b.can_undo());
+        assert!(b.undo().is_none(), "there is nothing to take back yet");
+        let mut history = vec![b.current()];
+        for direction in [Direction::Left, Direction::Up, Direction::Right] {
+            assert!(matches!(b.shift(direction), MoveOutcome::Moved(_)));
+            history.push(b.current());
+        }
+        while history.len() > 1 {
+            let undone = history.pop().expect("there is a move to take back");
+            assert!(b.can_undo());
+            let plan = b.undo().expect("there is a move to take back");
+            assert_eq!(undone.rewind(&plan), history[history.len() - 1]);
+            assert_eq!(b.current(), history[history.len() - 1]);
+            assert_eq!(b.move_count(), history.len() - 1);
+            assert_eq!(b.score(), history[history.len() - 1].score());
+        }
+        assert!(!b.can_undo());
+        assert!(b.undo().is_none());
+        assert_eq!(b.current(), history[0]);
+        assert_eq!(b.hints.len(), 0);
+    }
+
+    #[test]
+    fn redo_makes_undone_moves_again_until_a_new_move_is_made() {
+        let mut b = board([[2, 2, 0, 0], [4, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0]]);
+        assert!(b.redo().is_none(), "nothing has been taken back");
+        let mut history = vec![b.current()];
+        let mut hints = Vec::new();
+        for direction in [Direction::Left, Direction::Up, Direction::Right] {
+            let hint = b.shift(direction).hint().expect("the shift moves tiles");
+            hints.push(hint);
+            history.push(b.current());
+        }
+        for _ in 0..3 {
+            assert!(b.undo().is_some());
+        }
+        for n in 1..history.len() {
+            assert!(b.redo().as_ref() == Some(&hints[n - 1]));
+            assert_eq!(b.current(), history[n]);
+            assert_eq!(b.score(), history[n].score());
+            assert_eq!(b.move_count(), n);
+        }
+        assert!(b.redo().is_none(), "everything has been made again");
+
+        assert!(b.undo().is_some());
+        assert!(matches!(b.shift(Direction::Down), MoveOutcome::Moved(_)));
+        assert!(b.redo().is_none(), "a new move forgets what was taken back");
+        assert_eq!(b.hints.len(), b.rounds.len() - 1);
+    }
+
+    #[test]
+    fn the_game_is_won_once_a_2048_is_made() {
+        let mut b = Board::new_seeded(3, BoardConfig::default());
+        b.set_initial_round(one_merge_from_winning());
+        assert!(!b.has_won());
+        assert!(matches!(b.shift(Direction::Left), MoveOutcome::Moved(_)));
+        assert!(b.has_won());
+        assert!(b.undo().is_some());
+        assert!(
+            !b.has_won(),
+            "taking the winning move back takes the win back"
+        );
+    }
+
+    #[test]
+    fn the_high_score_keeps_the_best_score_reached() {
+        let mut best = HighScore::default();
+        best.record(6);
+        let mut b = board([[2, 2, 0, 0], [4, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0]]);
+        b.set_high_score(best);
+        assert!(matches!(b.shift(Direction::Left), MoveOutcome::Moved(_)));
+        assert_eq!(b.score(), 4);
+        assert_eq!(
+            b.high_score().best(),
+            6,
+            "a lower score leaves the best alone"
+        );
+
+        assert!(matches!(b.shift(Direction::Up), MoveOutcome::Moved(_)));
+        assert_eq!(b.score(), 12);
+        assert_eq!(b.high_score().best(), 12);
+        assert!(b.undo().is_some());
+        assert_eq!(
+            b.high_score().best(),
+            12,
+            "taking a move back keeps the best reached"
+        );
+    }
+
+    fn arcade(values: Values) -> Board {
+        board(values).with_power_ups()
+    }
+
+    #[test]
+    fn merges_earn_power_ups() {
+        let mut b = arcade([[2, 2, 0, 0], [4, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0]]);
+        assert!(board([[2; 4]; 4]).power_ups().is_none());
+        let mut merges = 0;
+        for direction in [
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+        ]
+        .into_iter()
+        .cycle()
+        .take(400)
+        {
+            let powers = b.power_ups().expect("the board plays the arcade mode");
+            if powers.charged().is_some() {
+                break;
+            }
+            assert_eq!(powers.merges(), merges);
+            if let MoveOutcome::Moved(hint) = b.shift(direction) {
+                merges += hint.merges();
+            }
+        }
+        let powers = b.power_ups().expect("the board plays the arcade mode");
+        assert_eq!(powers.charged(), Some(PowerUp::DoubleNextSpawn));
+        assert!(merges >= MERGES_PER_POWER_UP);
+        assert_eq!(powers.merges(), merges - MERGES_PER_POWER_UP);
+        assert_eq!(b.powers.len(), b.rounds.len());
+    }
+
+    #[rstest]
+    #[case::remove_smallest(PowerUp::RemoveSmallest, 0)]
+    #[case::merge_all(PowerUp::MergeAll, 4 + 8)]
+    fn using_a_power_up_can_be_taken_back(#[case] power_up: PowerUp, #[case] points: Score) {
+        let mut b = arcade([[2, 2, 4, 0], [4, 0, 4, 0], [0, 0, 0, 0], [0, 0, 0, 8]]);
+        let prior = b.current();
+        b.charge_power_up(power_up);
+
+        let hint = b
+            .activate_power_up()
+            .hint()
+            .expect("the power-up should change the board");
+        assert_eq!(hint.direction(), None);
+        assert_eq!(b.score(), prior.score() + points);
+        assert_eq!(b.move_count(), 0, "using a power-up isn't a move");
+        let powers = b.power_ups().expect("the board plays the arcade mode");
+        assert_eq!(powers.charged(), None);
+        assert_eq!(powers.next(), power_up.following());
+
+        let plan = b.rewind_plan().expect("the power-up was used");
+        assert_eq!(b.current().rewind(&plan), prior);
+        assert_eq!(b.powers[b.powers.len() - 2].charged(), Some(power_up));
+
+        let used = b.current();
+        assert_eq!(b.undo(), Some(plan));
+        assert_eq!(b.current(), prior);
+        assert_eq!(
+            b.power_ups().and_then(|powers| powers.charged()),
+            Some(power_up)
+        );
+        assert_eq!(b.powers.len(), b.rounds.len());
+
+        assert!(b.redo().is_some());
+        assert_eq!(b.current(), used);
+        assert_eq!(b.power_ups().and_then(|powers| powers.charged()), None);
+        assert_eq!(b.powers.len(), b.rounds.len());
+    }
+
+    #[test]
+    fn double_next_spawn_doubles_the_next_new_tile() {
+        let values = [[2, 2, 0, 0], [4, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0]];
+        let mut b = arcade(values);
+        let start = b.current();
+        b.charge_power_up(PowerUp::DoubleNextSpawn);
+        let hint = b
+            .activate_power_up()
+            .hint()
+            .expect("doubling is always taken");
+        assert!(hint.hints().is_empty());
+        assert_eq!(b.current(), start);
+        assert!(b.power_ups().is_some_and(|powers| powers.doubling()));
+
+        // doubling doesn't draw from the RNG, so a board that didn't double places the same tile
+        let mut undoubled = arcade(values);
+        assert!(matches!(
+            undoubled.shift(Direction::Left),
+            MoveOutcome::Moved(_)
+        ));
+        let hint = b
+            .shift(Direction::Left)
+            .hint()
+            .expect("the shift moves tiles");
+        let (idx, value) = hint
+            .hints()
+            .into_iter()
+            .find_map(|(idx, h)| match h {
+                Hint::NewTile(value, _) => Some((idx, value)),
+                _ => None,
+            })
+            .expect("every shift places a new tile");
+        assert_eq!(value, undoubled.current().get(&idx) + 1);
+        assert_eq!(b.current().get(&idx), value);
+        assert!(b.power_ups().is_some_and(|powers| !powers.doubling()));
+
+        let path = notation_path();
+        assert!(matches!(
+            b.export_pgn_like(&path),
+            Err(Error::PowerUpsNotRecordable)
+        ));
+    }
+
+    #[test]
+    fn power_ups_are_rejected_unless_charged_and_useful() {
+        let values = [[2, 4, 0, 0], [8, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]];
+        let mut classic = board(values);
+        assert!(matches!(classic.activate_power_up(), MoveOutcome::Rejected));
+
+        let mut b = arcade(values);
+        assert!(matches!(b.activate_power_up(), MoveOutcome::Rejected));
+        // no two tiles are equal, so there is nothing to merge
+        b.charge_power_up(PowerUp::MergeAll);
+        assert!(matches!(b.activate_power_up(), MoveOutcome::Rejected));
+        assert_eq!(b.rounds.len(), 1);
+        assert_eq!(
+            b.power_ups().and_then(|powers| powers.charged()),
+            Some(PowerUp::MergeAll)
+        );
+    }
+
+    #[test]
+    fn a_charged_removal_keeps_the_game_going() {
+        let full = [[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]];
+        assert!(board(full).is_game_over());
+        let mut b = arcade(full);
+        assert!(b.is_game_over());
+        b.charge_power_up(PowerUp::MergeAll);
+        assert!(b.is_game_over(), "there is nothing to merge");
+        b.charge_power_up(PowerUp::RemoveSmallest);
+        assert!(!b.is_game_over());
+        assert!(matches!(b.activate_power_up(), MoveOutcome::Moved(_)));
+        assert!(!b.is_game_over());
+    }
+
+    /// Returns a path unique to this call for a notation file.
+    fn notation_path() -> std::path::PathBuf {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let n = COUNT.fetch_add(1, Ordering::Relaxed);
+        std::env::temp_dir().join(format!("tui48-{}-{}.txt", std::process::id(), n))
+    }
+
+    #[test]
+    fn pgn_like_roundtrip() {
+        let mut b = board([[2, 2, 0, 0], [4, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0]]);
+        for direction in [
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+        ] {
+            assert!(matches!(b.shift(direction), MoveOutcome::Moved(_)));
+        }
+
+        let path = notation_path();
+        b.export_pgn_like(&path).expect("export should succeed");
+        let notation = std::fs::read_to_string(&path).expect("export should be readable");
+        let imported = Board::import_pgn_like(&path, SmallRng::seed_from_u64(7));
+        std::fs::remove_file(&path).expect("export should be removable");
+        let imported = imported.expect("import should succeed");
+
+        let lines: Vec<&str> = notation.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "[Start \"2,2,0,0/4,0,0,0/4,0,0,0/0,0,0,0\"]");
+        assert!(lines[1].starts_with("1 Left "), "{}", lines[1]);
+        assert!(lines[1].ends_with(" 4"), "{}", lines[1]);
+        assert!(imported.rounds.iter().eq(b.rounds.iter()));
+        assert_eq!(imported.hints.len(), b.hints.len());
+        for (a, b) in imported.hints.iter().zip(b.hints.iter()) {
+            assert_eq!(a.to_string(), b.to_string());
+        }
+    }
+
+    #[rstest]
+    #[case::small(3, 3)]
+    #[case::large(5, 5)]
+    fn pgn_like_roundtrip_of_other_sizes(#[case] width: usize, #[case] height: usize) {
+        let size = BoardConfig::new(width, height).expect("the size is a board size");
+        let mut b = Board::new_seeded(9, size);
+        for _ in 0..40 {
+            let Some(direction) = Strategy::Greedy.choose(&b.current()) else {
+                break;
+            };
+            b.shift(direction);
+        }
+        assert!(b.move_count() > 5, "{} moves", b.move_count());
+
+        let path = notation_path();
+        b.export_pgn_like(&path).expect("export should succeed");
+        let imported = Board::import_pgn_like(&path, SmallRng::seed_from_u64(7));
+        std::fs::remove_file(&path).expect("export should be removable");
+        let imported = imported.expect("import should succeed");
+        assert!(imported.rounds.iter().eq(b.rounds.iter()));
+    }
+
+    #[rstest]
+    #[case::missing_start("1 Left (3,0) 2 4\n", 1)]
+    #[case::bad_direction(
+        "[Start \"2,2,0,0/0,0,0,0/0,0,0,0/0,0,0,0\"]\n1 Sideways (3,0) 2 4\n",
+        2
+    )]
+    #[case::occupied_tile("[Start \"2,2,0,0/0,0,0,0/0,0,0,0/0,0,0,0\"]\n1 Left (0,0) 2 4\n", 2)]
+    #[case::off_the_edge("[Start \"2,2,0,0/0,0,0,0/0,0,0,0/0,0,0,0\"]\n1 Left (1,1) 2 4\n", 2)]
+    #[case::wrong_score("[Start \"2,2,0,0/0,0,0,0/0,0,0,0/0,0,0,0\"]\n1 Left (3,0) 2 8\n", 2)]
+    #[case::out_of_order("[Start \"2,2,0,0/0,0,0,0/0,0,0,0/0,0,0,0\"]\n2 Left (3,0) 2 4\n", 2)]
+    #[case::not_a_tile("[Start \"2,2,0,0/0,0,0,0/0,0,0,0/0,0,0,0\"]\n1 Left (3,0) 3 4\n", 2)]
+    #[case::off_a_small_board("[Start \"2,2,0/0,0,0/0,0,0\"]\n1 Left (3,0) 2 4\n", 2)]
+    #[case::off_a_large_board(
+        "[Start \"2,2,0,0,0/0,0,0,0,0/0,0,0,0,0/0,0,0,0,0/0,0,0,0,0\"]\n1 Left (5,0) 2 4\n",
+        2
+    )]
+    fn import_pgn_like_rejects_invalid_records(#[case] notation: &str, #[case] expected: usize) {
+        let path = notation_path();
+        std::fs::write(&path, notation).expect("notation should be writable");
+        let result = Board::import_pgn_like(&path, SmallRng::seed_from_u64(7));
+        std::fs::remove_file(&path).expect("notation should be removable");
+        match result {
+            Err(Error::InvalidMoveRecord { line, .. }) => assert_eq!(line, expected),
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("import should fail"),
+        }
+    }
+
+    #[test]
+    fn moves_taken_back_are_put_back_as_they_were() {
+        let mut b = board([[2, 2, 0, 0], [4, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0]]);
+        let mut history = vec![b.current()];
+        for direction in [Direction::Left, Direction::Up, Direction::Right] {
+            assert!(matches!(b.shift(direction), MoveOutcome::Moved(_)));
+            history.push(b.current());
+        }
+
+        let mut taken = Vec::new();
+        while let Some(m) = b.take_back() {
+            taken.push(m);
+            assert_eq!(b.current(), history[b.move_count()]);
+        }
+        assert_eq!(taken.len(), 3);
+        while let Some(m) = taken.pop() {
+            b.put_back(m);
+            assert_eq!(b.current(), history[b.move_count()]);
+        }
+
+        // the moves made from here on get the same tiles as if nothing had been taken back
+        let mut untouched = board([[2, 2, 0, 0], [4, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0]]);
+        for direction in [
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+        ] {
+            let _ = untouched.shift(direction);
+        }
+        let _ = b.shift(Direction::Down);
+        assert_eq!(b.current(), untouched.current());
+    }
+
+    #[test]
+    fn saved_games_go_on_as_they_would_have() -> Result<()> {
+        let mut b = Board::new_seeded(11, BoardConfig::default()).with_power_ups();
+        for direction in DIRECTIONS.iter().cycle().take(12) {
+            b.shift(direction.clone());
+        }
+        let mut loaded = Board::from_json(&b.to_json()).expect("the game should load");
+        assert!(loaded.rounds.iter().eq(b.rounds.iter()));
+        assert_eq!(loaded.hints.len(), b.hints.len());
+        assert_eq!(loaded.power_ups(), b.power_ups());
+        assert_eq!(loaded.score(), b.score());
+        assert_eq!(loaded.seed(), Some(11));
+
+        // the same moves deal the same tiles
+        for direction in DIRECTIONS.iter().cycle().take(12) {
+            b.shift(direction.clone());
+            loaded.shift(direction.clone());
+            assert_eq!(loaded.current(), b.current());
+        }
+        // and moves made before saving can still be taken back
+        while b.undo().is_some() {
+            assert!(loaded.undo().is_some());
+            assert_eq!(loaded.current(), b.current());
+        }
+        assert!(loaded.undo().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn the_seed_and_the_moves_made_replay_the_game() {
+        let mut b = Board::new_seeded(5, BoardConfig::default());
+        for direction in DIRECTIONS.iter().cycle().take(10) {
+            b.shift(direction.clone());
+        }
+        // moves taken back deal their tiles again once made again, or other moves in their place
+        b.undo();
+        b.undo();
+        b.redo();
+        b.undo();
+        for direction in [Direction::Down, Direction::Left, Direction::Down] {
+            b.shift(direction);
+        }
+
+        let mut replay = Board::new_seeded(b.seed().expect("the game was seeded"), b.size());
+        for hint in b.hints.iter() {
+            let direction = hint.direction().expect("every move was a shift");
+            assert!(matches!(replay.shift(direction), MoveOutcome::Moved(_)));
+        }
+        assert!(replay.rounds.iter().eq(b.rounds.iter()));
+    }
+
+    #[rstest]
+    #[case::not_json("", serde_json::Value::Null, "invalid type")]
+    #[case::bad_version("/version", serde_json::json!(0), "save file has an invalid version")]
+    #[case::no_rounds("/rounds", serde_json::json!([]), "there are no rounds")]
+    #[case::missing_hints("/hints", serde_json::json!([]), "for each of the 2 moves, found 0")]
+    #[case::missing_draws("/draws", serde_json::json!([]), "before each of the 2 moves, found 0")]
+    #[case::wrong_score("/score", serde_json::json!(1), "the score is 1 but the last round")]
+    #[case::ragged_row(
+        "/rounds/1/slots/1",
+        serde_json::json!([1, 1, 0]),
+        "row 1 is 3 tiles long rather than 4"
+    )]
+    #[case::too_small(
+        "/rounds",
+        serde_json::json!([{"slots": [[1, 1], [0, 0]], "score": 0}]),
+        "unsupported board size"
+    )]
+    #[case::too_large_a_card("/rounds/2/slots/3/3", serde_json::json!(30), "larger than")]
+    #[case::wrong_hint("/hints/0/hint", serde_json::json!([]), "round 1 doesn't follow")]
+    fn damaged_saved_games_are_refused(
+        #[case] pointer: &str,
+        #[case] replacement: serde_json::Value,
+        #[case] expected: &str,
+    ) {
+        let mut b = board([[2, 2, 0, 0], [4, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0]]);
+        b.shift(Direction::Left);
+        b.shift(Direction::Up);
+        let mut saved: serde_json::Value =
+            serde_json::from_str(&b.to_json()).expect("saved games should be JSON");
+        *saved
+            .pointer_mut(pointer)
+            .expect("the damaged value should be saved") = replacement;
+        let damaged = saved.to_string();
+        match Board::from_json(&damaged) {
+            Err(reason) => assert!(reason.contains(expected), "{}", reason),
+            Ok(_) => panic!("{} should have been refused", damaged),
+        }
+    }
+
+    #[test]
+    fn hints_swapped_between_moves_are_refused() {
+        let mut b = board([[2, 2, 0, 0], [4, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0]]);
+        b.shift(Direction::Left);
+        b.shift(Direction::Up);
+        let mut saved: serde_json::Value =
+            serde_json::from_str(&b.to_json()).expect("saved games should be JSON");
+        saved["hints"]
+            .as_array_mut()
+            .expect("the hints are saved as a list")
+            .swap(0, 1);
+        match Board::from_json(&saved.to_string()) {
+            Err(reason) => assert!(reason.contains("round 1 doesn't follow"), "{}", reason),
+            Ok(_) => panic!("{} should have been refused", saved),
+        }
+    }
+
+    #[test]
+    fn games_using_power_ups_load_again() {
+        let mut b = arcade([[2, 2, 4, 0], [4, 0, 4, 0], [0, 0, 0, 0], [0, 0, 0, 2]]);
+        for power_up in [
+            PowerUp::MergeAll,
+            PowerUp::RemoveSmallest,
+            PowerUp::DoubleNextSpawn,
+        ] {
+            b.charge_power_up(power_up);
+            assert!(matches!(b.activate_power_up(), MoveOutcome::Moved(_)));
+        }
+        assert!(matches!(b.shift(Direction::Left), MoveOutcome::Moved(_)));
+
+        let loaded = Board::from_json(&b.to_json()).expect("the game should load");
+        assert!(loaded.rounds.iter().eq(b.rounds.iter()));
+    }
+
+    #[test]
+    fn every_save_fixture_loads_and_goes_on() -> Result<()> {
+        for path in fixtures(&SAVE_FORMAT) {
+            let mut b = Board::load_from(&path)?.expect("fixtures should hold a game");
+            assert_eq!(b.move_count(), 6, "{:?}", path);
+            assert_eq!(b.score(), 16, "{:?}", path);
+            assert_eq!(b.seed(), Some(2048), "{:?}", path);
+            // the game goes on, and what gets written back reads the same
+            let moved = DIRECTIONS
+                .iter()
+                .any(|direction| matches!(b.shift(direction.clone()), MoveOutcome::Moved(_)));
+            assert!(moved, "{:?}", path);
+            let json = b.to_json();
+            let rewritten: serde_json::Value =
+                serde_json::from_str(&json).expect("saved games should be JSON");
+            assert_eq!(
+                rewritten["version"],
+                SAVE_FORMAT.current_version(),
+                "{:?}",
+                path
+            );
+            let reloaded = Board::from_json(&json).expect("the game should load again");
+            assert!(reloaded.rounds.iter().eq(b.rounds.iter()), "{:?}", path);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn saves_are_loaded_from_their_files() -> Result<()> {
+        let path = notation_path();
+        assert!(Board::load_from(&path)?.is_none(), "nothing was saved yet");
+
+        let mut b = Board::new_seeded(3, BoardConfig::new(5, 5).expect("5x5 is a board size"));
+        b.shift(Direction::Left);
+        b.save_to(&path)?;
+        let loaded = Board::load_from(&path);
+        std::fs::write(&path, "{\"score\": ")?;
+        let damaged = Board::load_from(&path);
+        std::fs::remove_file(&path)?;
+
+        let loaded = loaded?.expect("the game was saved");
+        assert!(loaded.rounds.iter().eq(b.rounds.iter()));
+        match damaged {
+            Err(Error::CorruptSave { path: p, .. }) => assert_eq!(p, path),
+            other => panic!("expected a corrupt save, got {:?}", other.map(|_| ())),
+        }
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::five(5, 5)]
+    #[case::six(6, 6)]
+    #[case::tall(3, 5)]
+    fn older_rounds_of_other_sizes_are_rebuilt_in_place(
+        #[case] width: usize,
+        #[case] height: usize,
+    ) -> Result<()> {
+        let size = BoardConfig::new(width, height).expect("the size is a board size");
+        let mut b = Board::new_seeded(4, size);
+        let mut rounds = vec![b.current()];
+        // well past the rounds kept whole, so that the older ones are rebuilt
+        while rounds.len() <= 2 * RECENT_ROUNDS {
+            let Some(direction) = Strategy::Greedy.choose(&b.current()) else {
+                break;
+            };
+            b.shift(direction);
+            rounds.push(b.current());
+        }
+        assert!(rounds.len() > RECENT_ROUNDS + 1, "{} rounds", rounds.len());
+        for (n, round) in rounds.iter().enumerate() {
+            assert_eq!(b.round_at(n).as_ref(), Some(round), "round {}", n);
+        }
+
+        let path = notation_path();
+        b.save_to(&path)?;
+        let loaded = Board::load_from(&path);
+        std::fs::remove_file(&path)?;
+        let loaded = loaded?.expect("the game was saved");
+        assert!(loaded.rounds.iter().eq(rounds.iter().cloned()));
+
+        while rounds.len() > 1 {
+            rounds.pop();
+            assert!(b.undo().is_some());
+            assert_eq!(Some(&b.current()), rounds.last());
+        }
+        Ok(())
+    }
+
+    /// A board in the growth mode starting 3x3 from the given cards, growing at the given
+    /// milestones.
+    fn growing(cards: [[Card; 3]; 3], milestones: &[Score]) -> Board {
+        let mut b =
+            Board::new(SmallRng::seed_from_u64(42), BoardConfig::growing()).with_growth(milestones);
+        b.set_initial_round(Round::from_cards(cards));
+        b
+    }
+
+    #[test]
+    fn growing_keeps_the_tiles_and_the_score() {
+        let mut b = growing([[1, 1, 0], [0, 2, 0], [0, 0, 0]], &[4, 100]);
+        assert!(b.grow().is_none(), "no milestone has been reached yet");
+        assert!(b.shift(Direction::Left).hint().is_some());
+        let before = b.current();
+        assert_eq!(before.score(), 4);
+
+        let hint = b.grow().expect("the first milestone has been reached");
+        assert_eq!(hint.grew_from(), Some((3, 3)));
+        assert_eq!(b.dimensions(), (4, 4));
+        assert_eq!(b.score(), before.score());
+        for y in 0..3 {
+            for x in 0..3 {
+                assert_eq!(b.current().get(&Idx(x, y)), before.get(&Idx(x, y)));
+            }
+        }
+        assert_eq!(b.empty_count(), before.empty_count() + 7);
+        // growing isn't a move
+        assert_eq!(b.move_count(), 1);
+        assert!(b.grow().is_none(), "the milestone was reached once");
+        assert_eq!(b.first_size(), BoardConfig::growing());
+    }
+
+    #[test]
+    fn each_milestone_grows_the_board_once() {
+        let milestones = [16, 64, 128];
+        let mut b = Board::new_seeded(6, BoardConfig::growing()).with_growth(&milestones);
+        let mut growths = Vec::new();
+        while growths.len() < milestones.len() {
+            let Some(direction) = Strategy::Greedy.choose(&b.current()) else {
+                break;
+            };
+            let before = b.score();
+            b.shift(direction);
+            if let Some(hint) = b.grow() {
+                growths.push((before, b.score(), hint.grew_from()));
+            }
+            assert!(b.grow().is_none(), "the board grows once between moves");
+        }
+        assert_eq!(growths.len(), milestones.len(), "{:?}", growths);
+        for (n, (before, after, from)) in growths.into_iter().enumerate() {
+            // the board grew on the move that reached the milestone
+            assert!(before < milestones[n] && after >= milestones[n], "{}", n);
+            assert_eq!(from, Some((3 + n, 3 + n)));
+        }
+        assert_eq!(b.dimensions(), (6, 6));
+
+        // once every milestone has been reached, the board stays the size it is
+        for _ in 0..20 {
+            let Some(direction) = Strategy::Greedy.choose(&b.current()) else {
+                break;
+            };
+            b.shift(direction);
+            assert!(b.grow().is_none());
+        }
+        assert_eq!(b.dimensions(), (6, 6));
+    }
+
+    #[test]
+    fn undoing_a_growth_restores_the_smaller_board() {
+        let mut b = growing([[1, 1, 0], [0, 2, 0], [0, 0, 0]], &[4]);
+        b.shift(Direction::Left);
+        let small = b.current();
+        b.grow();
+        let grown = b.current();
+
+        let plan = b.undo().expect("the growth can be taken back");
+        assert!(
+            plan.hints().is_empty(),
+            "nothing moves as the board shrinks"
+        );
+        assert_eq!(b.current(), small);
+        assert_eq!(b.dimensions(), (3, 3));
+        assert_eq!(b.move_count(), 1);
+
+        assert!(b.undo().is_some());
+        assert_eq!(b.dimensions(), (3, 3));
+        assert!(b.redo().is_some_and(|hint| hint.grew_from().is_none()));
+        let hint = b.redo().expect("the growth can be made again");
+        assert_eq!(hint.grew_from(), Some((3, 3)));
+        assert_eq!(b.current(), grown);
+
+        // taken back for good, the growth is due again
+        b.undo();
+        assert!(b.grow().is_some());
+        assert_eq!(b.current(), grown);
+    }
+
+    #[test]
+    fn a_board_about_to_grow_isnt_over() {
+        let stuck = [[3, 4, 3], [4, 3, 4], [3, 4, 3]];
+        assert!(growing(stuck, &[200]).is_game_over());
+
+        let mut b = growing(stuck, &[100]);
+        assert!(b.score() >= 100);
+        assert!(!b.is_game_over());
+        b.grow();
+        assert!(!b.is_game_over());
+        assert!(b.shift(Direction::Right).hint().is_some());
+    }
+
+    #[test]
+    fn grown_games_are_saved_and_loaded() -> Result<()> {
+        let mut b = growing([[1, 1, 0], [0, 2, 0], [0, 0, 0]], &[4, 100]);
+        b.shift(Direction::Left);
+        b.grow();
+        b.shift(Direction::Right);
+
+        let path = notation_path();
+        b.save_to(&path)?;
+        let loaded = Board::load_from(&path);
+        let exported = b.export_pgn_like(&path);
+        std::fs::remove_file(&path)?;
+        assert!(matches!(exported, Err(Error::GrowthNotRecordable)));
+
+        let mut loaded = loaded?.expect("the game was saved");
+        assert!(loaded.rounds.iter().eq(b.rounds.iter()));
+        assert!(loaded.grows());
+        assert_eq!(loaded.first_size(), BoardConfig::growing());
+        loaded.undo();
+        loaded.undo();
+        assert_eq!(loaded.dimensions(), (3, 3));
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::tile_in_the_new_column("/rounds/2/slots/0/3", serde_json::json!(1))]
+    #[case::from_another_size("/hints/1/grew_from", serde_json::json!([4, 3]))]
+    fn damaged_growths_are_refused(#[case] pointer: &str, #[case] replacement: serde_json::Value) {
+        let mut b = growing([[1, 1, 0], [0, 2, 0], [0, 0, 0]], &[4]);
+        b.shift(Direction::Left);
+        b.grow();
+        let mut saved: serde_json::Value =
+            serde_json::from_str(&b.to_json()).expect("saved games should be JSON");
+        *saved
+            .pointer_mut(pointer)
+            .expect("the damaged value should be saved") = replacement;
+        match Board::from_json(&saved.to_string()) {
+            Err(reason) => assert!(
+                reason.contains("round 2 didn't grow from the round before"),
+                "{}",
+                reason
+            ),
+            Ok(_) => panic!("{} should have been refused", saved),
+        }
     }
 }
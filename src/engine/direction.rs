@@ -0,0 +1,39 @@
+/// Direction represents the direction tiles slide in, in board coordinates.
+///
+/// The TUI has its own screen-space `Direction`; conversions between the two live next to it in
+/// `tui::geometry`.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum Direction {
+    #[default]
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Left => "left",
+            Self::Right => "right",
+            Self::Up => "up",
+            Self::Down => "down",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::str::FromStr for Direction {
+    type Err = String;
+
+    /// Parses a direction name, ignoring case.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            "up" => Ok(Self::Up),
+            "down" => Ok(Self::Down),
+            _ => Err(format!("unknown direction {:?}", s)),
+        }
+    }
+}
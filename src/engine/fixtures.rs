@@ -0,0 +1,140 @@
+//! Rounds for tests to start from. Fixtures are written with the values shown on the board, eg
+//! `round!([[2, 0, 0, 4], ...])`, and converted to cards (see `Card`) as they're built, so only
+//! what the engine reports back, such as hints and merged pairs, is written as cards.
+
+use super::round::{card_from_display, Card, Idx, Round, Score, WINNING_CARD};
+
+/// The rows of a board as the values shown on it, 0 being an empty slot.
+pub(crate) type Values = [[u32; 4]; 4];
+
+/// Builds a round from the rows of values shown on the board, scored 0 unless a score is given.
+///
+/// ```ignore
+/// let round = round!([[2, 0, 0, 4], [0; 4], [0; 4], [0, 0, 0, 2048]]);
+/// ```
+macro_rules! round {
+    ($values:expr) => {
+        $crate::engine::fixtures::from_values($values, 0)
+    };
+    ($values:expr, $score:expr) => {
+        $crate::engine::fixtures::from_values($values, $score)
+    };
+}
+pub(crate) use round;
+
+/// Returns the card for the value shown on the board, panicking if no tile shows it.
+pub(crate) fn card(value: u32) -> Card {
+    card_from_display(value).unwrap_or_else(|| panic!("{} isn't a tile value", value))
+}
+
+/// Returns the cards for the rows of values shown on the board.
+pub(crate) fn cards(values: Values) -> [[Card; 4]; 4] {
+    values.map(|row| row.map(card))
+}
+
+pub(crate) fn from_values(values: Values, score: Score) -> Round {
+    Round::from_cards(cards(values)).with_score(score)
+}
+
+/// Returns an otherwise empty round with the given values at the given indices, scored 0.
+pub(crate) fn with_tiles(tiles: &[(Idx, u32)]) -> Round {
+    let mut round = Round::default();
+    for (idx, value) in tiles {
+        round.set_value(idx, card(*value));
+    }
+    round
+}
+
+/// Returns a full round of alternating 2s and 4s, where nothing can move.
+pub(crate) fn full_without_merges() -> Round {
+    let rows: [[Card; 4]; 4] =
+        std::array::from_fn(|y| std::array::from_fn(|x| 1 + ((x + y) % 2) as Card));
+    Round::from_cards(rows)
+}
+
+/// Returns a round with two 1024s next to each other in the top row and nothing else, so merging
+/// them either way along the row wins.
+pub(crate) fn one_merge_from_winning() -> Round {
+    let almost = WINNING_CARD - 1;
+    Round::from_cards([[almost, almost, 0, 0], [0; 4], [0; 4], [0; 4]])
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use rstest::*;
+
+    use super::*;
+    use crate::engine::direction::Direction;
+
+    #[rstest]
+    #[case::empty(0, 0)]
+    #[case::two(2, 1)]
+    #[case::four(4, 2)]
+    #[case::winning(2048, WINNING_CARD)]
+    #[case::largest(131072, 17)]
+    fn card_is_the_exponent_of_the_value(#[case] value: u32, #[case] expected: Card) {
+        assert_eq!(card(value), expected);
+    }
+
+    #[rstest]
+    #[case::one(1)]
+    #[case::odd(3)]
+    #[case::not_a_power_of_two(12)]
+    #[should_panic(expected = "isn't a tile value")]
+    fn card_rejects_values_no_tile_shows(#[case] value: u32) {
+        card(value);
+    }
+
+    #[test]
+    fn round_macro_places_rows_top_to_bottom() {
+        let round = round!([[2, 0, 0, 4], [0; 4], [0; 4], [8, 0, 0, 2048]]);
+        assert_eq!(round.get(&Idx(0, 0)), 1);
+        assert_eq!(round.get(&Idx(3, 0)), 2);
+        assert_eq!(round.get(&Idx(0, 3)), 3);
+        assert_eq!(round.get(&Idx(3, 3)), WINNING_CARD);
+        assert_eq!(round.empty_count(), 12);
+        assert_eq!(round.score(), 0);
+        assert_eq!(round!([[4, 0, 0, 0], [0; 4], [0; 4], [0; 4]], 4).score(), 4);
+    }
+
+    #[rstest]
+    #[case::top_left(Idx(0, 0))]
+    #[case::top_right(Idx(3, 0))]
+    #[case::bottom_left(Idx(0, 3))]
+    #[case::bottom_right(Idx(3, 3))]
+    #[case::last_row(Idx(1, 3))]
+    #[case::last_column(Idx(3, 2))]
+    fn with_tiles_reaches_every_edge(#[case] idx: Idx) {
+        let round = with_tiles(&[(idx.clone(), 8)]);
+        assert_eq!(round.get(&idx), 3);
+        assert_eq!(round.empty_count(), 15);
+        assert_eq!(round.score(), 0);
+    }
+
+    #[test]
+    fn full_without_merges_has_no_moves() {
+        let round = full_without_merges();
+        assert_eq!(round.empty_count(), 0);
+        assert!(!round.has_moves());
+        assert!(round.largest_mergeable_pair().is_none());
+        assert_eq!(round.get(&Idx(3, 3)), 1);
+        assert_eq!(round.get(&Idx(2, 3)), 2);
+        round.validate().expect("the round should be valid");
+    }
+
+    #[rstest]
+    #[case::left(Direction::Left)]
+    #[case::right(Direction::Right)]
+    fn one_merge_from_winning_wins_in_one_move(#[case] direction: Direction) {
+        let mut round = one_merge_from_winning();
+        round.validate().expect("the round should be valid");
+        assert!(!round.has_won());
+        round
+            .shift(SmallRng::seed_from_u64(42), &direction)
+            .expect("the pair should merge");
+        assert_eq!(round.max_card(), WINNING_CARD);
+        assert!(round.has_won());
+    }
+}
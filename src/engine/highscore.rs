@@ -0,0 +1,153 @@
+//! The best score ever reached, kept across sessions in a small JSON file, eg
+//!
+//! ```json
+//! {"version": 1, "best": 20480}
+//! ```
+use std::path::Path;
+
+use super::round::Score;
+use crate::error::{Error, Result};
+use crate::migrate::{Format, JsonObject, VERSION_KEY};
+
+/// The high score file's format. Append a migration here whenever its fields change, and capture
+/// a file of the new version under `tests/fixtures/highscore`.
+pub(crate) const HIGH_SCORE_FORMAT: Format<JsonObject> = Format {
+    name: "highscore",
+    migrations: &[],
+};
+
+/// The best score reached in any game so far, this session's included.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct HighScore {
+    best: Score,
+}
+
+impl HighScore {
+    /// Reads the high score from the given file, starting from nothing if there is no such file.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        Self::from_json(&text).map_err(|reason| Error::InvalidHighScore {
+            path: path.to_path_buf(),
+            reason,
+        })
+    }
+
+    pub(crate) fn best(&self) -> Score {
+        self.best
+    }
+
+    /// Takes the given score into account, returning whether it beat the best so far.
+    pub(crate) fn record(&mut self, score: Score) -> bool {
+        if score <= self.best {
+            return false;
+        }
+        self.best = score;
+        true
+    }
+
+    /// Writes the high score down as it is kept in its file.
+    pub(crate) fn to_json(&self) -> String {
+        format!(
+            "{{\"{}\": {}, \"best\": {}}}\n",
+            VERSION_KEY,
+            HIGH_SCORE_FORMAT.current_version(),
+            self.best
+        )
+    }
+
+    /// Reads a high score written by `to_json`, by this release or an older one.
+    fn from_json(text: &str) -> std::result::Result<Self, String> {
+        let mut fields: JsonObject = match serde_json::from_str(text) {
+            Ok(serde_json::Value::Object(fields)) => fields,
+            Ok(_) => return Err(String::from("expected a JSON object")),
+            Err(e) => return Err(e.to_string()),
+        };
+        HIGH_SCORE_FORMAT
+            .upgrade(&mut fields)
+            .map_err(|e| e.to_string())?;
+        if let Some(key) = fields
+            .keys()
+            .find(|key| !["best", VERSION_KEY].contains(&key.as_str()))
+        {
+            return Err(format!("unexpected field {:?}", key));
+        }
+        let value = fields
+            .get("best")
+            .ok_or_else(|| String::from("expected a \"best\" field"))?;
+        let best = value
+            .as_u64()
+            .and_then(|best| Score::try_from(best).ok())
+            .ok_or_else(|| format!("invalid score {:?}", value.to_string()))?;
+        Ok(Self { best })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+    use crate::migrate::test::fixtures;
+
+    #[test]
+    fn only_better_scores_are_recorded() {
+        let mut high_score = HighScore::default();
+        assert!(high_score.record(100));
+        assert!(!high_score.record(100));
+        assert!(!high_score.record(40));
+        assert!(high_score.record(104));
+        assert_eq!(high_score.best(), 104);
+    }
+
+    #[rstest]
+    #[case::written(HighScore { best: 20480 }.to_json(), Ok(20480))]
+    #[case::spaced(String::from(" { \"best\" : 8 }\n"), Ok(8))]
+    #[case::not_an_object(String::from("20480"), Err("expected a JSON object"))]
+    #[case::other_field(String::from("{\"worst\": 4}"), Err("unexpected field \"worst\""))]
+    #[case::bad_score(String::from("{\"best\": -4}"), Err("invalid score \"-4\""))]
+    #[case::no_score(String::from("{\"version\": 1}"), Err("expected a \"best\" field"))]
+    #[case::bad_version(
+        String::from("{\"version\": \"one\", \"best\": 8}"),
+        Err("highscore file has an invalid version: \"one\"")
+    )]
+    fn high_scores_are_read_back_from_json(
+        #[case] text: String,
+        #[case] expected: std::result::Result<Score, &str>,
+    ) {
+        let read = HighScore::from_json(&text).map(|high_score| high_score.best());
+        assert_eq!(read, expected.map_err(String::from));
+    }
+
+    #[test]
+    fn every_fixture_loads() -> Result<()> {
+        for path in fixtures(&HIGH_SCORE_FORMAT) {
+            assert_eq!(HighScore::load(&path)?.best(), 20480, "{:?}", path);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn a_missing_file_holds_no_high_score() -> Result<()> {
+        let path = std::env::temp_dir().join("tui48-no-such-highscore.json");
+        assert_eq!(HighScore::load(&path)?, HighScore::default());
+        Ok(())
+    }
+
+    #[test]
+    fn a_damaged_file_is_reported() -> Result<()> {
+        let path =
+            std::env::temp_dir().join(format!("tui48-highscore-{}.json", std::process::id()));
+        std::fs::write(&path, "{\"best\": ")?;
+        let loaded = HighScore::load(&path);
+        std::fs::remove_file(&path)?;
+        match loaded {
+            Err(Error::InvalidHighScore { path: p, .. }) => assert_eq!(p, path),
+            other => panic!("expected an invalid high score, got {:?}", other),
+        }
+        Ok(())
+    }
+}
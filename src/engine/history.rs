@@ -0,0 +1,373 @@
+//! The rounds of a game, kept compactly. Only the latest rounds are kept whole; older ones are
+//! rebuilt when asked for by replaying the moves that led to them from the nearest keyframe, a
+//! round kept whole at regular intervals.
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+
+use super::direction::Direction;
+use super::round::{AnimationHint, Card, Hint, Idx, Round};
+
+/// How many of the latest rounds are kept whole, so that taking back moves stays cheap.
+pub(crate) const RECENT_ROUNDS: usize = 50;
+
+/// Every how many rounds one is kept whole, bounding the moves replayed to rebuild a round.
+pub(crate) const KEYFRAME_INTERVAL: usize = 100;
+
+/// What turned one round into the next, in a few bytes: enough to replay it without the random
+/// number generator that decided on it.
+#[derive(Clone, Debug, PartialEq)]
+enum Delta {
+    /// A shift that placed a new tile of the given value at the given slot, counting row by row.
+    Shift {
+        direction: Direction,
+        at: u8,
+        value: Card,
+    },
+    /// The tile at the given slot was taken off the board.
+    Remove { at: u8 },
+    /// Every pair of equal neighbours merged where they are.
+    MergeAll,
+    /// Nothing on the board changed, eg a power-up that only takes effect later.
+    Unchanged,
+    /// The board grew to the given number of slots across and down.
+    Grow { width: u8, height: u8 },
+}
+
+impl Delta {
+    /// The delta the given hint describes, leading to a board of the given size.
+    fn from_hint(hint: &AnimationHint, (width, height): (usize, usize)) -> Self {
+        if hint.grew_from().is_some() {
+            return Delta::Grow {
+                width: width as u8,
+                height: height as u8,
+            };
+        }
+        let slot = |idx: &Idx| (idx.y() * width + idx.x()) as u8;
+        let hints = hint.hints();
+        let new_tile = hints.iter().find_map(|(idx, h)| match h {
+            Hint::NewTile(value, direction) => Some(Delta::Shift {
+                direction: direction.clone(),
+                at: slot(idx),
+                value: *value,
+            }),
+            _ => None,
+        });
+        let removed = || {
+            hints.iter().find_map(|(idx, h)| match h {
+                Hint::Remove(_) => Some(Delta::Remove { at: slot(idx) }),
+                _ => None,
+            })
+        };
+        new_tile.or_else(removed).unwrap_or(if hint.changed() {
+            Delta::MergeAll
+        } else {
+            Delta::Unchanged
+        })
+    }
+
+    fn replay(&self, round: &mut Round) {
+        let (width, _) = round.dimensions();
+        let idx = |at: &u8| Idx(*at as usize % width, *at as usize / width);
+        match self {
+            Delta::Shift {
+                direction,
+                at,
+                value,
+            } => {
+                round.slide(direction);
+                round.set_value(&idx(at), *value);
+            }
+            Delta::Remove { at } => round.set_value(&idx(at), 0),
+            Delta::MergeAll => {
+                round.merge_adjacent();
+            }
+            Delta::Unchanged => (),
+            Delta::Grow { width, height } => {
+                round.grow_to(*width as usize, *height as usize);
+            }
+        }
+    }
+}
+
+/// The rounds of a game from the first to the current one. There's always at least one.
+pub(crate) struct RoundStore {
+    // round n * KEYFRAME_INTERVAL for every n it has been reached
+    keyframes: Vec<Round>,
+    // deltas[i] turns round i into round i + 1
+    deltas: Vec<Delta>,
+    // the latest rounds, the last of them the current one
+    recent: VecDeque<Round>,
+    // the round rebuilt last, which rebuilding the rounds in order picks up from
+    memo: RefCell<Option<(usize, Round)>>,
+    memo_hits: Cell<usize>,
+}
+
+impl RoundStore {
+    pub(crate) fn new(first: Round) -> Self {
+        Self {
+            keyframes: vec![first.clone()],
+            deltas: Vec::with_capacity(2000),
+            recent: VecDeque::from([first]),
+            memo: RefCell::new(None),
+            memo_hits: Cell::new(0),
+        }
+    }
+
+    /// The number of rounds, one more than the moves made.
+    pub(crate) fn len(&self) -> usize {
+        self.deltas.len() + 1
+    }
+
+    /// The round the game started from.
+    pub(crate) fn first(&self) -> &Round {
+        &self.keyframes[0]
+    }
+
+    pub(crate) fn current(&self) -> &Round {
+        self.recent
+            .back()
+            .expect("a round store always has at least one round")
+    }
+
+    /// Adds the round the move the given hint describes led to.
+    pub(crate) fn push(&mut self, round: Round, hint: &AnimationHint) {
+        // slots are counted along the rows of the round the move led to, which only differ in
+        // width from the round before for a growth
+        self.deltas.push(Delta::from_hint(hint, round.dimensions()));
+        if (self.len() - 1).is_multiple_of(KEYFRAME_INTERVAL) {
+            self.keyframes.push(round.clone());
+        }
+        self.recent.push_back(round);
+        if self.recent.len() > RECENT_ROUNDS {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Takes back the current round, returning it, unless it's the first.
+    pub(crate) fn pop(&mut self) -> Option<Round> {
+        if self.len() == 1 {
+            return None;
+        }
+        let popped = self.recent.pop_back();
+        self.deltas.pop();
+        self.keyframes
+            .truncate((self.len() - 1) / KEYFRAME_INTERVAL + 1);
+        if matches!(&*self.memo.borrow(), Some((n, _)) if *n >= self.len()) {
+            self.memo.replace(None);
+        }
+        if self.recent.is_empty() {
+            let current = self.rebuild(self.len() - 1);
+            self.recent.push_back(current);
+        }
+        popped
+    }
+
+    /// Returns the round after the given number of moves, or None if fewer have been made.
+    pub(crate) fn round_at(&self, n: usize) -> Option<Round> {
+        let first_recent = self.len() - self.recent.len();
+        match n {
+            n if n >= self.len() => None,
+            n if n >= first_recent => self.recent.get(n - first_recent).cloned(),
+            n => Some(self.rebuild(n)),
+        }
+    }
+
+    /// Returns every round in order, rebuilding each from the one before.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = Round> + '_ {
+        (0..self.len()).filter_map(|n| self.round_at(n))
+    }
+
+    /// Rebuilds the given round from the round rebuilt last if it comes between it and the given
+    /// one, or else from the nearest keyframe before it.
+    fn rebuild(&self, n: usize) -> Round {
+        let keyframe = n / KEYFRAME_INTERVAL * KEYFRAME_INTERVAL;
+        let (from, mut round) = match self.memo.take() {
+            Some((m, round)) if (keyframe..=n).contains(&m) => {
+                self.memo_hits.set(self.memo_hits.get() + 1);
+                (m, round)
+            }
+            _ => (keyframe, self.keyframes[n / KEYFRAME_INTERVAL].clone()),
+        };
+        for delta in &self.deltas[from..n] {
+            delta.replay(&mut round);
+        }
+        self.memo.replace(Some((n, round.clone())));
+        round
+    }
+
+    /// Roughly how many bytes the rounds take up.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn approximate_size(&self) -> usize {
+        let rounds = self.keyframes.len() + self.recent.len() + 1;
+        std::mem::size_of::<Self>()
+            + rounds * std::mem::size_of::<Round>()
+            + self.deltas.len() * std::mem::size_of::<Delta>()
+    }
+
+    /// The number of rounds rebuilt from the round rebuilt before them.
+    #[cfg(test)]
+    pub(crate) fn memo_hits(&self) -> usize {
+        self.memo_hits.get()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::engine::board::{Board, BoardConfig};
+    use crate::engine::round::DIRECTIONS;
+    use crate::engine::strategy::Strategy;
+
+    /// Plays a game with power-ups by making the moves the greedy strategy picks and using every
+    /// power-up as soon as it's charged, keeping every round the plain way alongside.
+    fn scripted_game(seed: u64) -> (Board, Vec<Round>) {
+        let mut board = Board::new_seeded(seed, BoardConfig::default()).with_power_ups();
+        let mut rounds = vec![board.current()];
+        loop {
+            let charged = board.power_ups().and_then(|powers| powers.charged());
+            let moved = charged.is_some() && board.activate_power_up().hint().is_some();
+            let moved = moved
+                || Strategy::Greedy
+                    .choose(&board.current())
+                    .is_some_and(|direction| board.shift(direction).hint().is_some());
+            if !moved {
+                break;
+            }
+            rounds.push(board.current());
+        }
+        (board, rounds)
+    }
+
+    /// The moves of a game of the given number of moves, each with the round it led to: a shift
+    /// in the first direction that changes anything or, when none does, a tile taken off the
+    /// board.
+    fn synthetic_moves(moves: usize) -> (Round, Vec<(Round, AnimationHint)>) {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut round = Round::default();
+        round.set_value(&Idx(0, 0), 1);
+        let first = round.clone();
+        let moves = (0..moves)
+            .map(|_| {
+                let hint = DIRECTIONS
+                    .iter()
+                    .find_map(|direction| round.shift(&mut rng, direction))
+                    .unwrap_or_else(|| round.remove_smallest(&mut rng));
+                (round.clone(), hint)
+            })
+            .collect();
+        (first, moves)
+    }
+
+    /// A store of a synthetic game of the given number of moves, see `synthetic_moves`, along
+    /// with every round the plain way.
+    fn synthetic_game(moves: usize) -> (RoundStore, Vec<Round>) {
+        let (first, moves) = synthetic_moves(moves);
+        let mut store = RoundStore::new(first.clone());
+        let mut rounds = vec![first];
+        for (round, hint) in moves {
+            store.push(round.clone(), &hint);
+            rounds.push(round);
+        }
+        (store, rounds)
+    }
+
+    #[test]
+    fn every_round_of_a_long_game_is_rebuilt() {
+        for seed in 1..6 {
+            let (board, rounds) = scripted_game(seed);
+            assert!(rounds.len() > 2 * KEYFRAME_INTERVAL, "{}", rounds.len());
+            // power-ups were used too
+            assert!(board.move_count() < rounds.len() - 1);
+            for (n, round) in rounds.iter().enumerate() {
+                assert_eq!(board.round_at(n).as_ref(), Some(round), "round {}", n);
+            }
+            assert_eq!(board.round_at(rounds.len()), None);
+        }
+    }
+
+    #[test]
+    fn rounds_are_rebuilt_from_the_nearest_keyframe() {
+        let (store, rounds) = synthetic_game(600);
+        // rebuilt from the last in reverse, so that nothing is picked up from the round before
+        for n in (0..rounds.len()).rev() {
+            assert_eq!(store.round_at(n).as_ref(), Some(&rounds[n]), "round {}", n);
+        }
+        assert_eq!(store.memo_hits(), 0);
+    }
+
+    #[test]
+    fn taking_back_moves_across_a_keyframe() {
+        let (mut store, mut rounds) = synthetic_game(KEYFRAME_INTERVAL + RECENT_ROUNDS + 10);
+        while rounds.len() > KEYFRAME_INTERVAL - 10 {
+            let expected = rounds.pop();
+            assert_eq!(store.pop(), expected);
+            assert_eq!(store.len(), rounds.len());
+            assert_eq!(Some(store.current()), rounds.last());
+        }
+
+        // moves made after taking some back are kept like any other
+        let (_, moves) = synthetic_moves(KEYFRAME_INTERVAL + 20);
+        for (round, hint) in moves[store.len() - 1..].iter() {
+            store.push(round.clone(), hint);
+        }
+        let (_, rounds) = synthetic_game(KEYFRAME_INTERVAL + 20);
+        for (n, round) in rounds.iter().enumerate() {
+            assert_eq!(store.round_at(n).as_ref(), Some(round), "round {}", n);
+        }
+    }
+
+    #[test]
+    fn rounds_on_either_side_of_a_growth_are_rebuilt() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut round = Round::empty(3, 3);
+        round.set_value(&Idx(0, 0), 1);
+        let mut store = RoundStore::new(round.clone());
+        let mut rounds = vec![round.clone()];
+        for n in 0..KEYFRAME_INTERVAL + RECENT_ROUNDS {
+            let hint = match n {
+                20 => round.grow_to(4, 4),
+                _ => DIRECTIONS
+                    .iter()
+                    .find_map(|direction| round.shift(&mut rng, direction))
+                    .unwrap_or_else(|| round.remove_smallest(&mut rng)),
+            };
+            store.push(round.clone(), &hint);
+            rounds.push(round.clone());
+        }
+        for n in (0..rounds.len()).rev() {
+            assert_eq!(store.round_at(n).as_ref(), Some(&rounds[n]), "round {}", n);
+        }
+    }
+
+    #[test]
+    fn the_first_round_is_never_taken_back() {
+        let mut store = RoundStore::new(Round::default());
+        assert_eq!(store.pop(), None);
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn scrubbing_in_order_picks_up_from_the_round_before() {
+        let (store, rounds) = synthetic_game(1000);
+        let old = rounds.len() - RECENT_ROUNDS;
+        let rebuilt: Vec<Round> = store.iter().collect();
+        assert_eq!(rebuilt, rounds);
+        // every old round but the keyframes' is rebuilt from the one before it
+        assert_eq!(store.memo_hits(), old - old.div_ceil(KEYFRAME_INTERVAL));
+    }
+
+    #[test]
+    fn old_rounds_take_up_a_fraction_of_the_room() {
+        let (store, rounds) = synthetic_game(5000);
+        let plain = rounds.capacity() * std::mem::size_of::<Round>();
+        assert!(
+            store.approximate_size() * 4 < plain,
+            "{} bytes against {}",
+            store.approximate_size(),
+            plain
+        );
+    }
+}
@@ -1,2 +1,52 @@
+//! The game engine. It must not depend on the TUI, so nothing in here may import from
+//! `crate::tui`; `test::engine_does_not_depend_on_tui` enforces this.
+
 pub(crate) mod board;
+pub(crate) mod direction;
+#[cfg(test)]
+pub(crate) mod fixtures;
+pub(crate) mod highscore;
+pub(crate) mod history;
+pub(crate) mod playout;
+pub(crate) mod powerup;
+pub(crate) mod practice;
 pub(crate) mod round;
+pub(crate) mod spawns;
+pub(crate) mod strategy;
+
+#[cfg(test)]
+mod test {
+    use std::ffi::OsStr;
+    use std::path::Path;
+
+    #[test]
+    fn engine_does_not_depend_on_tui() {
+        // built at runtime so this file doesn't match itself
+        let forbidden = ["crate", "tui"].join("::");
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src")
+            .join("engine");
+        let mut checked = 0;
+        for entry in std::fs::read_dir(&dir).expect("engine sources should be readable") {
+            let path = entry.expect("engine sources should be readable").path();
+            if path.extension() != Some(OsStr::new("rs")) {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).expect("engine source should be utf-8");
+            for (i, line) in source.lines().enumerate() {
+                if line.trim_start().starts_with("//") {
+                    continue;
+                }
+                assert!(
+                    !line.contains(&forbidden),
+                    "{}:{} depends on the TUI: {}",
+                    path.display(),
+                    i + 1,
+                    line.trim()
+                );
+            }
+            checked += 1;
+        }
+        assert!(checked > 0, "no engine sources found in {}", dir.display());
+    }
+}
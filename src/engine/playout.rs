@@ -0,0 +1,89 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use super::round::{Round, DIRECTIONS, WINNING_CARD};
+
+/// Upper bound on the number of moves in a single playout; a random game from an empty board
+/// dies long before reaching this so it only exists to guarantee termination.
+const MAX_PLAYOUT_MOVES: usize = 10_000;
+
+/// Plays random moves from the given round until the game can no longer progress or the winning
+/// tile appears, returning the final round.
+pub(crate) fn simulate_playout<T: Rng>(round: &Round, rng: &mut T) -> Round {
+    let mut round = round.clone();
+    let mut directions = DIRECTIONS;
+    for _ in 0..MAX_PLAYOUT_MOVES {
+        if round.max_card() >= WINNING_CARD {
+            break;
+        }
+        directions.shuffle(rng);
+        // a shift that doesn't change anything leaves the round untouched, so we can simply try
+        // each direction in turn until one of them sticks
+        let moved = directions
+            .iter()
+            .any(|direction| round.shift(&mut *rng, direction).is_some());
+        if !moved {
+            break;
+        }
+    }
+    round
+}
+
+/// Estimates the probability of reaching the winning tile from the given round by running
+/// `rollouts` random playouts.
+#[cfg(test)]
+pub(crate) fn estimate_win_probability<T: Rng>(round: &Round, rollouts: usize, rng: &mut T) -> f32 {
+    if rollouts == 0 {
+        return 0.0;
+    }
+    let wins = count_wins(round, rollouts, rng);
+    wins as f32 / rollouts as f32
+}
+
+/// Runs `rollouts` random playouts from the given round and returns how many of them reached the
+/// winning tile.
+pub(crate) fn count_wins<T: Rng>(round: &Round, rollouts: usize, rng: &mut T) -> usize {
+    (0..rollouts)
+        .filter(|_| simulate_playout(round, rng).max_card() >= WINNING_CARD)
+        .count()
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::engine::fixtures::{one_merge_from_winning, round};
+
+    #[test]
+    fn playout_terminates_from_empty_board() {
+        let mut rng = SmallRng::seed_from_u64(42);
+        let start = round!([[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 2, 0]]);
+        let end = simulate_playout(&start, &mut rng);
+        assert_ne!(start, end);
+    }
+
+    #[test]
+    fn nearly_won_board_estimates_near_one() {
+        let mut rng = SmallRng::seed_from_u64(42);
+        let start = one_merge_from_winning();
+        let estimate = estimate_win_probability(&start, 200, &mut rng);
+        assert!(estimate > 0.9, "estimate was {}", estimate);
+    }
+
+    #[test]
+    fn nearly_dead_board_estimates_near_zero() {
+        let mut rng = SmallRng::seed_from_u64(42);
+        let start = round!([[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 0]]);
+        let estimate = estimate_win_probability(&start, 200, &mut rng);
+        assert!(estimate < 0.1, "estimate was {}", estimate);
+    }
+
+    #[test]
+    fn zero_rollouts_estimate_zero() {
+        let mut rng = SmallRng::seed_from_u64(42);
+        let start = one_merge_from_winning();
+        assert_eq!(estimate_win_probability(&start, 0, &mut rng), 0.0);
+    }
+}
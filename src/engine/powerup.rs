@@ -0,0 +1,270 @@
+use rand::Rng;
+
+use super::round::{AnimationHint, Round};
+
+/// The number of merges it takes to earn a power-up in the arcade mode.
+pub(crate) const MERGES_PER_POWER_UP: usize = 20;
+
+/// One-shot boosts the arcade mode hands out as the player merges tiles. They are earned in the
+/// order they are declared in, starting over after the last one.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum PowerUp {
+    /// The next new tile comes out at twice the value it would have.
+    DoubleNextSpawn,
+    /// The smallest tile is taken off the board.
+    RemoveSmallest,
+    /// Every pair of equal neighbouring tiles merges at once, without anything sliding.
+    MergeAll,
+}
+
+impl PowerUp {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::DoubleNextSpawn => "double next",
+            Self::RemoveSmallest => "remove smallest",
+            Self::MergeAll => "merge all",
+        }
+    }
+
+    /// The power-up earned after this one.
+    pub(crate) fn following(&self) -> PowerUp {
+        match self {
+            Self::DoubleNextSpawn => Self::RemoveSmallest,
+            Self::RemoveSmallest => Self::MergeAll,
+            Self::MergeAll => Self::DoubleNextSpawn,
+        }
+    }
+
+    /// Applies the power-up to the round, describing what it did to the tiles. Doubling the next
+    /// new tile leaves the round alone, since it only takes effect with the next shift; see
+    /// `Board::activate_power_up`.
+    pub(crate) fn apply<T: Rng>(&self, round: &mut Round, rng: T) -> AnimationHint {
+        match self {
+            Self::DoubleNextSpawn => AnimationHint::default(),
+            Self::RemoveSmallest => round.remove_smallest(rng),
+            Self::MergeAll => round.merge_adjacent(),
+        }
+    }
+}
+
+/// Where an arcade game stands with its power-ups: how close the next one is, whether one is
+/// charged and waiting to be used, and whether the next new tile is to be doubled.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct PowerUps {
+    // merges made towards the next power-up; merges don't count while one is charged
+    merges: usize,
+    charged: bool,
+    // the power-up that is charged, or else the one earned next
+    next: PowerUp,
+    doubling: bool,
+}
+
+impl Default for PowerUps {
+    fn default() -> Self {
+        Self {
+            merges: 0,
+            charged: false,
+            next: PowerUp::DoubleNextSpawn,
+            doubling: false,
+        }
+    }
+}
+
+impl PowerUps {
+    /// Returns the power-up ready to be used, if there is one.
+    pub(crate) fn charged(&self) -> Option<PowerUp> {
+        self.charged.then_some(self.next)
+    }
+
+    /// Returns the power-up that is charged, or else the one earned next.
+    pub(crate) fn next(&self) -> PowerUp {
+        self.next
+    }
+
+    /// Returns the number of merges made towards the next power-up.
+    pub(crate) fn merges(&self) -> usize {
+        self.merges
+    }
+
+    /// Returns true if the next new tile is to be doubled.
+    pub(crate) fn doubling(&self) -> bool {
+        self.doubling
+    }
+
+    /// Counts merges made by a shift towards the next power-up, charging it once there have been
+    /// enough. Merges beyond those it takes carry over to the one after.
+    pub(crate) fn record_merges(&mut self, merges: usize) {
+        if self.charged {
+            return;
+        }
+        self.merges += merges;
+        if self.merges >= MERGES_PER_POWER_UP {
+            self.merges -= MERGES_PER_POWER_UP;
+            self.charged = true;
+        }
+    }
+
+    /// Uses up the charged power-up, lining up the one after it.
+    pub(crate) fn spend(&mut self) {
+        self.charged = false;
+        self.next = self.next.following();
+    }
+
+    pub(crate) fn set_doubling(&mut self, doubling: bool) {
+        self.doubling = doubling;
+    }
+
+    /// Returns the state with the given power-up charged.
+    #[cfg(test)]
+    pub(crate) fn charged_with(power_up: PowerUp) -> Self {
+        Self {
+            charged: true,
+            next: power_up,
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use rstest::*;
+
+    use super::*;
+    use crate::engine::fixtures::{card, round};
+    use crate::engine::round::{Hint, Idx};
+
+    fn rng() -> SmallRng {
+        SmallRng::seed_from_u64(3)
+    }
+
+    #[test]
+    fn merge_all_merges_rows_then_columns_in_place() {
+        let mut r = round!([[2, 2, 4, 4], [0, 8, 0, 4], [2, 8, 2, 2], [0, 0, 0, 0],]);
+        let hint = PowerUp::MergeAll.apply(&mut r, rng());
+        assert_eq!(
+            r,
+            round!(
+                [[4, 0, 8, 0], [0, 16, 0, 4], [2, 0, 4, 0], [0, 0, 0, 0],],
+                4 + 8 + 4 + 16
+            )
+        );
+        assert_eq!(
+            hint.hints(),
+            vec![
+                (Idx(1, 0), Hint::NewValueToIdx(card(4), Idx(0, 0))),
+                (Idx(3, 0), Hint::NewValueToIdx(card(8), Idx(2, 0))),
+                (Idx(3, 2), Hint::NewValueToIdx(card(4), Idx(2, 2))),
+                (Idx(1, 2), Hint::NewValueToIdx(card(16), Idx(1, 1))),
+            ]
+        );
+        assert_eq!(hint.merges(), 4);
+        assert_eq!(hint.direction(), None);
+    }
+
+    #[test]
+    fn merge_all_leaves_a_board_without_pairs_alone() {
+        let before = round!([[2, 4, 2, 4], [4, 2, 4, 2], [0, 0, 0, 0], [2, 0, 0, 2],]);
+        let mut r = before.clone();
+        let hint = PowerUp::MergeAll.apply(&mut r, rng());
+        assert!(!hint.changed());
+        assert_eq!(r, before);
+    }
+
+    #[test]
+    fn remove_smallest_takes_the_smallest_tile_off() {
+        let mut r = round!(
+            [[8, 4, 0, 0], [0, 16, 0, 0], [0, 0, 0, 0], [0, 0, 0, 32],],
+            100
+        );
+        let hint = PowerUp::RemoveSmallest.apply(&mut r, rng());
+        assert_eq!(hint.hints(), vec![(Idx(1, 0), Hint::Remove(card(4)))]);
+        assert_eq!(r.get(&Idx(1, 0)), 0);
+        assert_eq!(r.score(), 100);
+        assert_eq!(r.empty_count(), 13);
+    }
+
+    #[test]
+    fn remove_smallest_picks_one_of_several_smallest_tiles() {
+        let smallest = [Idx(0, 0), Idx(2, 1), Idx(3, 3)];
+        let mut picked: Vec<Idx> = Vec::new();
+        for seed in 0..20 {
+            let mut r = round!([[2, 4, 0, 0], [0, 0, 2, 0], [0, 8, 0, 0], [0, 0, 0, 2],]);
+            let hint = PowerUp::RemoveSmallest.apply(&mut r, SmallRng::seed_from_u64(seed));
+            let hints = hint.hints();
+            let [(idx, Hint::Remove(removed))] = hints.as_slice() else {
+                panic!("expected a single tile to be removed, got {}", hint);
+            };
+            assert_eq!(*removed, card(2));
+            assert!(smallest.contains(idx));
+            picked.push(idx.clone());
+        }
+        picked.sort_by_key(|idx| (idx.y(), idx.x()));
+        picked.dedup();
+        assert!(picked.len() > 1, "always picked {:?}", picked);
+    }
+
+    #[test]
+    fn remove_smallest_keeps_the_last_tile() {
+        let before = round!([[0, 0, 0, 0], [0, 64, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]);
+        let mut r = before.clone();
+        assert!(!PowerUp::RemoveSmallest.apply(&mut r, rng()).changed());
+        assert_eq!(r, before);
+    }
+
+    #[test]
+    fn double_next_spawn_leaves_the_round_alone() {
+        let before = round!([[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 4, 0], [0, 0, 0, 0]]);
+        let mut r = before.clone();
+        assert!(!PowerUp::DoubleNextSpawn.apply(&mut r, rng()).changed());
+        assert_eq!(r, before);
+    }
+
+    #[test]
+    fn power_ups_are_earned_in_turn() {
+        let mut earned = Vec::new();
+        let mut power_ups = PowerUps::default();
+        for _ in 0..4 {
+            power_ups.record_merges(MERGES_PER_POWER_UP);
+            earned.extend(power_ups.charged());
+            power_ups.spend();
+        }
+        assert_eq!(
+            earned,
+            vec![
+                PowerUp::DoubleNextSpawn,
+                PowerUp::RemoveSmallest,
+                PowerUp::MergeAll,
+                PowerUp::DoubleNextSpawn,
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::short(&[5, 5, 5], None, 15)]
+    #[case::exact(&[10, 10], Some(PowerUp::DoubleNextSpawn), 0)]
+    #[case::carried_over(&[15, 8], Some(PowerUp::DoubleNextSpawn), 3)]
+    // merges made while a power-up is charged don't count towards the next one
+    #[case::while_charged(&[20, 7], Some(PowerUp::DoubleNextSpawn), 0)]
+    fn merges_charge_power_ups(
+        #[case] merges: &[usize],
+        #[case] charged: Option<PowerUp>,
+        #[case] towards_next: usize,
+    ) {
+        let mut power_ups = PowerUps::default();
+        for m in merges {
+            power_ups.record_merges(*m);
+        }
+        assert_eq!(power_ups.charged(), charged);
+        assert_eq!(power_ups.merges(), towards_next);
+    }
+
+    #[test]
+    fn spending_lines_up_the_next_power_up() {
+        let mut power_ups = PowerUps::charged_with(PowerUp::MergeAll);
+        power_ups.spend();
+        assert_eq!(power_ups.charged(), None);
+        assert_eq!(power_ups.next(), PowerUp::DoubleNextSpawn);
+    }
+}
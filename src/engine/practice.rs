@@ -0,0 +1,313 @@
+use std::ops::RangeInclusive;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::index::sample;
+use rand::Rng;
+
+use super::direction::Direction;
+use super::round::{Card, Round, DIRECTIONS};
+use crate::error::{Error, Result};
+
+type Cards = [[Card; 4]; 4];
+
+/// Upper bound on the number of boards synthesized for one position before giving up. Every
+/// profile builds boards that nearly always qualify, so this only exists to guarantee termination.
+const MAX_ATTEMPTS: usize = 100;
+
+/// Number of cards on a mid-game board, about 60% of the board.
+const MID_GAME_CARDS: RangeInclusive<usize> = 9..=11;
+/// Largest card on a mid-game board.
+const MID_GAME_MAX_CARD: RangeInclusive<Card> = 6..=8;
+/// Number of cards on a late-game board, about 85% of the board.
+const LATE_GAME_CARDS: RangeInclusive<usize> = 13..=14;
+/// The large card on a late-game board.
+const LATE_GAME_BIG_CARD: RangeInclusive<Card> = 9..=11;
+/// Largest of the other cards on a late-game board.
+const LATE_GAME_MAX_CARD: Card = 7;
+/// Largest card on a board one move from death.
+const ONE_MOVE_MAX_CARD: Card = 9;
+/// The corner card on a big-tile-corner board.
+const CORNER_CARD: RangeInclusive<Card> = 8..=11;
+
+/// The kinds of position the game can start from to practice.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Profile {
+    /// About 60% of the board filled, smaller tiles more common than larger ones.
+    MidGame,
+    /// About 85% of the board filled around one large tile.
+    LateGame,
+    /// A crowded board where only one direction is left to move in.
+    OneMoveFromDeath,
+    /// The largest tile in a corner with the others descending away from it.
+    BigTileCorner,
+}
+
+/// Synthesizes a position for the given profile. Positions are valid rounds that still have moves
+/// left, scored with the least points it takes to build them.
+pub(crate) fn generate<T: Rng>(rng: &mut T, profile: Profile) -> Result<Round> {
+    for _ in 0..MAX_ATTEMPTS {
+        let cards = match profile {
+            Profile::MidGame => mid_game(rng),
+            Profile::LateGame => late_game(rng),
+            Profile::OneMoveFromDeath => one_move_from_death(rng),
+            Profile::BigTileCorner => big_tile_corner(rng),
+        };
+        let round = Round::from_cards(cards);
+        if round.validate().is_err() || !round.has_moves() {
+            continue;
+        }
+        if profile == Profile::OneMoveFromDeath && round.legal_moves().len() != 1 {
+            continue;
+        }
+        return Ok(round);
+    }
+    Err(Error::PracticePositionUnavailable(profile))
+}
+
+fn mid_game<T: Rng>(rng: &mut T) -> Cards {
+    let max = rng.gen_range(MID_GAME_MAX_CARD);
+    let count = rng.gen_range(MID_GAME_CARDS);
+    let others: Vec<Card> = (1..count).map(|_| descending(rng, max)).collect();
+    scatter(rng, std::iter::once(max).chain(others))
+}
+
+fn late_game<T: Rng>(rng: &mut T) -> Cards {
+    let big = rng.gen_range(LATE_GAME_BIG_CARD);
+    let count = rng.gen_range(LATE_GAME_CARDS);
+    let others: Vec<Card> = (1..count)
+        .map(|_| descending(rng, LATE_GAME_MAX_CARD))
+        .collect();
+    scatter(rng, std::iter::once(big).chain(others))
+}
+
+/// Fills the board except for the line at one edge, with no two neighboring cards equal. Nothing
+/// can merge and every other line is packed against the far edge, so the only move left is
+/// towards the empty line.
+fn one_move_from_death<T: Rng>(rng: &mut T) -> Cards {
+    let mut cards = Cards::default();
+    for y in 0..4 {
+        for x in 0..4 {
+            let card = loop {
+                let card = rng.gen_range(1..=ONE_MOVE_MAX_CARD);
+                let left = x > 0 && cards[y][x - 1] == card;
+                let up = y > 0 && cards[y - 1][x] == card;
+                if !left && !up {
+                    break card;
+                }
+            };
+            cards[y][x] = card;
+        }
+    }
+    let direction = &DIRECTIONS[rng.gen_range(0..DIRECTIONS.len())];
+    match direction {
+        Direction::Left => cards.iter_mut().for_each(|row| row[0] = 0),
+        Direction::Right => cards.iter_mut().for_each(|row| row[3] = 0),
+        Direction::Up => cards[0] = [0; 4],
+        Direction::Down => cards[3] = [0; 4],
+    }
+    cards
+}
+
+/// Puts a large card in a random corner and lays out strictly smaller cards after it along a
+/// snake through the board, stopping when they run out.
+fn big_tile_corner<T: Rng>(rng: &mut T) -> Cards {
+    let mut cards = Cards::default();
+    let corner = (rng.gen_range(0..2) * 3, rng.gen_range(0..2) * 3);
+    let mut card = rng.gen_range(CORNER_CARD);
+    for (x, y) in snake(corner) {
+        cards[y][x] = card;
+        let step = rng.gen_range(1..=2);
+        if card <= step {
+            break;
+        }
+        card -= step;
+    }
+    cards
+}
+
+/// Returns every position of the board starting from the given corner, going along its row and
+/// then back and forth along the following rows.
+fn snake((x, y): (usize, usize)) -> Vec<(usize, usize)> {
+    let rows: Vec<usize> = if y == 0 {
+        (0..4).collect()
+    } else {
+        (0..4).rev().collect()
+    };
+    let mut columns: Vec<usize> = if x == 0 {
+        (0..4).collect()
+    } else {
+        (0..4).rev().collect()
+    };
+    let mut positions = Vec::with_capacity(16);
+    for row in rows {
+        positions.extend(columns.iter().map(|column| (*column, row)));
+        columns.reverse();
+    }
+    positions
+}
+
+/// Picks a card no larger than `max`, smaller cards being proportionally more likely.
+fn descending<T: Rng>(rng: &mut T, max: Card) -> Card {
+    let weights = (1..=max).map(|card| (max - card + 1) as usize);
+    let index = WeightedIndex::new(weights).expect("max should be at least 1");
+    index.sample(rng) as Card + 1
+}
+
+/// Places the given cards at random positions on an empty board.
+fn scatter<T: Rng>(rng: &mut T, cards: impl Iterator<Item = Card>) -> Cards {
+    let cards: Vec<Card> = cards.collect();
+    let mut slots = Cards::default();
+    for (position, card) in sample(rng, 16, cards.len()).into_iter().zip(cards) {
+        slots[position / 4][position % 4] = card;
+    }
+    slots
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use rstest::*;
+
+    use super::*;
+    use crate::engine::round::Idx;
+
+    const SEEDS: std::ops::Range<u64> = 0..50;
+
+    fn cards(round: &Round) -> Vec<Card> {
+        (0..4)
+            .flat_map(|y| (0..4).map(move |x| Idx(x, y)))
+            .map(|idx| round.get(&idx))
+            .filter(|card| *card > 0)
+            .collect()
+    }
+
+    #[rstest]
+    fn positions_are_playable(
+        #[values(
+            Profile::MidGame,
+            Profile::LateGame,
+            Profile::OneMoveFromDeath,
+            Profile::BigTileCorner
+        )]
+        profile: Profile,
+    ) -> Result<()> {
+        for seed in SEEDS {
+            let round = generate(&mut SmallRng::seed_from_u64(seed), profile)?;
+            round.validate()?;
+            assert!(round.has_moves(), "{:?} seed {}", profile, seed);
+            assert!(!round.is_game_over(), "{:?} seed {}", profile, seed);
+            assert_eq!(round.score(), round.min_score());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn generation_is_seeded() -> Result<()> {
+        let a = generate(&mut SmallRng::seed_from_u64(3), Profile::LateGame)?;
+        let b = generate(&mut SmallRng::seed_from_u64(3), Profile::LateGame)?;
+        assert_eq!(a, b);
+        Ok(())
+    }
+
+    #[test]
+    fn mid_game_fills_about_sixty_percent() -> Result<()> {
+        for seed in SEEDS {
+            let round = generate(&mut SmallRng::seed_from_u64(seed), Profile::MidGame)?;
+            let cards = cards(&round);
+            assert!(MID_GAME_CARDS.contains(&cards.len()), "seed {}", seed);
+            assert!(
+                MID_GAME_MAX_CARD.contains(&round.max_card()),
+                "seed {}",
+                seed
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn late_game_has_one_large_tile() -> Result<()> {
+        for seed in SEEDS {
+            let round = generate(&mut SmallRng::seed_from_u64(seed), Profile::LateGame)?;
+            let cards = cards(&round);
+            assert!(LATE_GAME_CARDS.contains(&cards.len()), "seed {}", seed);
+            let big: Vec<&Card> = cards.iter().filter(|c| **c > LATE_GAME_MAX_CARD).collect();
+            assert_eq!(big.len(), 1, "seed {}", seed);
+            assert!(LATE_GAME_BIG_CARD.contains(big[0]), "seed {}", seed);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn one_move_from_death_reports_its_only_move() -> Result<()> {
+        let mut seen = Vec::new();
+        for seed in SEEDS {
+            let round = generate(
+                &mut SmallRng::seed_from_u64(seed),
+                Profile::OneMoveFromDeath,
+            )?;
+            let legal = round.legal_moves();
+            assert_eq!(legal.len(), 1, "seed {}", seed);
+            assert_eq!(round.empty_count(), 4, "seed {}", seed);
+            for direction in DIRECTIONS.iter() {
+                assert_eq!(
+                    round.would_change(direction),
+                    legal[0] == *direction,
+                    "seed {} shifting {:?}",
+                    seed,
+                    direction
+                );
+            }
+            if !seen.contains(&legal[0]) {
+                seen.push(legal[0].clone());
+            }
+        }
+        assert_eq!(
+            seen.len(),
+            DIRECTIONS.len(),
+            "every direction should come up"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn big_tile_corner_descends_along_a_snake() -> Result<()> {
+        for seed in SEEDS {
+            let round = generate(&mut SmallRng::seed_from_u64(seed), Profile::BigTileCorner)?;
+            let corner = [(0, 0), (3, 0), (0, 3), (3, 3)]
+                .into_iter()
+                .find(|(x, y)| round.get(&Idx(*x, *y)) == round.max_card())
+                .expect("the largest card should be in a corner");
+            assert!(CORNER_CARD.contains(&round.max_card()), "seed {}", seed);
+
+            let along: Vec<Card> = snake(corner)
+                .into_iter()
+                .map(|(x, y)| round.get(&Idx(x, y)))
+                .collect();
+            let filled = along.iter().take_while(|card| **card > 0).count();
+            assert_eq!(filled, cards(&round).len(), "seed {}", seed);
+            assert!(
+                along[..filled].windows(2).all(|pair| pair[0] > pair[1]),
+                "seed {}: {:?}",
+                seed,
+                along
+            );
+        }
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::top_left((0, 0), [(0, 0), (1, 0), (2, 0), (3, 0), (3, 1)])]
+    #[case::top_right((3, 0), [(3, 0), (2, 0), (1, 0), (0, 0), (0, 1)])]
+    #[case::bottom_left((0, 3), [(0, 3), (1, 3), (2, 3), (3, 3), (3, 2)])]
+    #[case::bottom_right((3, 3), [(3, 3), (2, 3), (1, 3), (0, 3), (0, 2)])]
+    fn snake_starts_in_the_corner(
+        #[case] corner: (usize, usize),
+        #[case] start: [(usize, usize); 5],
+    ) {
+        let positions = snake(corner);
+        assert_eq!(positions.len(), 16);
+        assert_eq!(positions[..5], start);
+    }
+}
@@ -1,11 +1,15 @@
+use std::sync::OnceLock;
+
 use rand::distributions::Distribution;
 use rand::distributions::WeightedIndex;
 use rand::seq::IteratorRandom;
 use rand::Rng;
 
-use crate::tui::geometry::Direction;
+use super::board::BoardConfig;
+use super::direction::Direction;
+use crate::error::{Error, Result};
 
-#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, serde::Deserialize, serde::Serialize)]
 pub(crate) struct Idx(pub(crate) usize, pub(crate) usize);
 
 impl std::fmt::Display for Idx {
@@ -24,11 +28,13 @@ impl Idx {
     }
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub(crate) enum Hint {
     ToIdx(Idx),
     NewValueToIdx(u8, Idx),
     NewTile(u8, Direction),
+    /// The tile of the given value is taken off the board, eg by a power-up.
+    Remove(u8),
 }
 
 impl std::fmt::Display for Hint {
@@ -41,15 +47,19 @@ impl std::fmt::Display for Hint {
             Self::NewTile(value, direction) => {
                 write!(f, "Hint::NewTile({0}, {1})", value, direction)
             }
+            Self::Remove(value) => write!(f, "Hint::Remove({0})", value),
         }
     }
 }
 
-#[derive(Default, PartialEq)]
+#[derive(Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
 pub(crate) struct AnimationHint {
     hint: Vec<(Idx, Hint)>,
     changed: bool,
     game_over: bool,
+    // the size the board was before it grew, for a growth rather than a move
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    grew_from: Option<(usize, usize)>,
 }
 
 impl std::fmt::Display for AnimationHint {
@@ -70,6 +80,7 @@ impl AnimationHint {
             hint: Vec::new(),
             changed: false,
             game_over: false,
+            grew_from: None,
         }
     }
 
@@ -82,11 +93,198 @@ impl AnimationHint {
         self.hint.clone()
     }
 
+    #[cfg_attr(not(test), allow(dead_code))]
     pub(crate) fn game_over(&self) -> bool {
         self.game_over
     }
+
+    /// Returns true if the move described by the hint changed the board.
+    pub(crate) fn changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Returns the number of slots across and down the board before it grew, or None unless the
+    /// hint describes a growth.
+    pub(crate) fn grew_from(&self) -> Option<(usize, usize)> {
+        self.grew_from
+    }
+
+    /// Returns the direction of the shift, taken from the new tile it placed.
+    pub(crate) fn direction(&self) -> Option<Direction> {
+        self.hint.iter().find_map(|(_, hint)| match hint {
+            Hint::NewTile(_, direction) => Some(direction.clone()),
+            _ => None,
+        })
+    }
+
+    /// Returns the number of tile pairs merged by the shift.
+    pub(crate) fn merges(&self) -> usize {
+        self.hint
+            .iter()
+            .filter(|(_, hint)| matches!(hint, Hint::NewValueToIdx(..)))
+            .count()
+    }
+
+    /// Lists the hints on a single line for logging, eg `(1,0)→(0,0)=4 (2,0)→(1,0) (3,3)+2`: a
+    /// tile moving to another index, merging into a tile of the given value there, a new tile of
+    /// the given value placed at its index, or a tile of the given value taken off the board, eg
+    /// `(2,1)-4`.
+    pub(crate) fn to_debug_string(&self) -> String {
+        self.hint
+            .iter()
+            .map(|(Idx(x, y), hint)| match hint {
+                Hint::ToIdx(Idx(tx, ty)) => format!("({x},{y})\u{2192}({tx},{ty})"),
+                Hint::NewValueToIdx(card, Idx(tx, ty)) => {
+                    format!("({x},{y})\u{2192}({tx},{ty})={}", display_value(*card))
+                }
+                Hint::NewTile(card, _) => format!("({x},{y})+{}", display_value(*card)),
+                Hint::Remove(card) => format!("({x},{y})-{}", display_value(*card)),
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+/// One step of taking back a move, the reverse of one or more Hints. Each step applies to the
+/// tile at the index it is paired with in a RewindPlan.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum RewindHint {
+    /// The tile slides back to the given index, ending up with the given value.
+    ToIdx(Card, Idx),
+    /// A tile of the given value splits off the merged tile and slides back to the given index.
+    SplitToIdx(Card, Idx),
+    /// The tile stays where it is but goes back to the given value.
+    SetValue(Card),
+    /// The tile placed after the move goes away.
+    RemoveTile,
+    /// A tile of the given value comes back to the empty slot it was taken from.
+    PutBack(Card),
+}
+
+impl std::fmt::Display for RewindHint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ToIdx(value, idx) => write!(f, "RewindHint::ToIdx({0}, {1})", value, idx),
+            Self::SplitToIdx(value, idx) => {
+                write!(f, "RewindHint::SplitToIdx({0}, {1})", value, idx)
+            }
+            Self::SetValue(value) => write!(f, "RewindHint::SetValue({0})", value),
+            Self::RemoveTile => write!(f, "RewindHint::RemoveTile"),
+            Self::PutBack(value) => write!(f, "RewindHint::PutBack({0})", value),
+        }
+    }
+}
+
+/// Describes how to take back a move: the tile placed after it is removed first, then every other
+/// step applies to the round the move produced.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct RewindPlan {
+    hint: Vec<(Idx, RewindHint)>,
+    score: Score,
+}
+
+impl RewindPlan {
+    pub(crate) fn hints(&self) -> Vec<(Idx, RewindHint)> {
+        self.hint.clone()
+    }
+}
+
+/// The rules cards merge by when a row collapses. Only the standard rules exist so far, so
+/// there is nothing to choose between yet.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct MergeRules {}
+
+/// A card that moved while a row collapsed, in row-local positions: position 0 is the end of the
+/// row the cards slide towards.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RowMove {
+    pub(crate) from: usize,
+    pub(crate) to: usize,
+    /// The value of the merged card, if this card merged into the one at `to`.
+    pub(crate) merged: Option<Card>,
+}
+
+/// What collapsing a row did: every card that moved, in the order they were moved, and the points
+/// the merges scored.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct RowOutcome {
+    pub(crate) moves: Vec<RowMove>,
+    pub(crate) points: Score,
+}
+
+/// Slides the cards in a row towards position 0 and merges equal neighbours in place, each card
+/// merging at most once.
+pub(crate) fn collapse_row(cells: &mut [Card], _rules: &MergeRules) -> RowOutcome {
+    let mut outcome = RowOutcome::default();
+    let mut pivot = 0;
+    let mut cmp = 1;
+    while cmp < cells.len() {
+        // if the cmp element is 0, move on to the next element in the row
+        if cells[cmp] == 0 {
+            cmp += 1;
+            continue;
+        }
+        // if the pivot element is 0 and the cmp isn't, replace the pivot element with the cmp
+        // and zero the cmp
+        if cells[pivot] == 0 {
+            cells[pivot] = cells[cmp];
+            cells[cmp] = 0;
+            outcome.moves.push(RowMove {
+                from: cmp,
+                to: pivot,
+                merged: None,
+            });
+            cmp += 1;
+            continue;
+        }
+        // if the pivot element and the cmp element are equal then they must be combined; the
+        // value only goes up by 1 since we are tracking not the actual card value, but its
+        // exponent
+        if cells[pivot] == cells[cmp] {
+            let new_value = cells[pivot] + 1;
+            outcome.points += 2_u32.pow(new_value as u32);
+            cells[pivot] = new_value;
+            cells[cmp] = 0;
+            outcome.moves.push(RowMove {
+                from: cmp,
+                to: pivot,
+                merged: Some(new_value),
+            });
+        }
+        pivot += 1;
+        cmp = pivot + 1;
+    }
+    outcome
+}
+
+/// Merges equal neighbours in a row where they are, without sliding anything: of each pair the
+/// card further from position 0 merges into the other. Cards marked as `fixed` don't merge, nor
+/// does a card merge twice.
+pub(crate) fn merge_row_in_place(cells: &mut [Card], fixed: &[bool]) -> RowOutcome {
+    let mut outcome = RowOutcome::default();
+    let mut pos = 0;
+    while pos + 1 < cells.len() {
+        let (card, next) = (cells[pos], cells[pos + 1]);
+        if card == 0 || card != next || fixed[pos] || fixed[pos + 1] {
+            pos += 1;
+            continue;
+        }
+        let new_value = card + 1;
+        outcome.points += 2_u32.pow(new_value as u32);
+        cells[pos] = new_value;
+        cells[pos + 1] = 0;
+        outcome.moves.push(RowMove {
+            from: pos + 1,
+            to: pos,
+            merged: Some(new_value),
+        });
+        pos += 2;
+    }
+    outcome
 }
 
+/// A tile, stored as the exponent of the value shown on it: 1 is the 2 tile, 2 the 4 tile and so
+/// on, while 0 is an empty slot. Use `display_value` and `card_from_display` to convert.
 pub(crate) type Card = u8;
 
 pub(crate) type Score = u32;
@@ -94,43 +292,113 @@ pub(crate) type Score = u32;
 const NEW_CARD_CHOICES: [u8; 2] = [1, 2];
 const NEW_CARD_WEIGHTS: [u8; 2] = [9, 1];
 
-#[derive(Clone, Debug, PartialEq)]
+/// Every direction the board can be shifted in.
+pub(crate) const DIRECTIONS: [Direction; 4] = [
+    Direction::Left,
+    Direction::Right,
+    Direction::Up,
+    Direction::Down,
+];
+
+/// The exponent of the 2048 tile.
+pub(crate) const WINNING_CARD: Card = 11;
+
+/// The exponent of the largest tile a 4x4 board can hold, and so of the largest tile the UI has
+/// colors and labels for. It is the limit on every board size: a larger board could in theory build
+/// past it, but only with a score in the millions, so `Round::validate` refuses larger tiles
+/// whatever the board, and smaller boards are held to it even though they can't reach it.
+pub(crate) const MAX_CARD: Card = 17;
+
+/// The value shown on the board for the given card, or 0 for an empty slot.
+pub(crate) fn display_value(card: Card) -> u32 {
+    match card {
+        0 => 0,
+        card => 2u32.pow(card as u32),
+    }
+}
+
+/// The card shown on the board as the given value, if it is one.
+pub(crate) fn card_from_display(value: u32) -> Option<Card> {
+    match value {
+        0 => Some(0),
+        1 => None,
+        v if v.is_power_of_two() => Some(v.trailing_zeros() as Card),
+        _ => None,
+    }
+}
+
+// the weighted index is shared by every round rather than stored in each of them so that rounds
+// stay plain data that are cheap to clone and can be handed off to other threads
+static NEW_TILE_WEIGHTED_INDEX: OnceLock<WeightedIndex<u8>> = OnceLock::new();
+
+fn new_tile_weighted_index() -> &'static WeightedIndex<u8> {
+    NEW_TILE_WEIGHTED_INDEX.get_or_init(|| {
+        WeightedIndex::new(NEW_CARD_WEIGHTS).expect("NEW_CARD_WEIGHTS should never be empty")
+    })
+}
+
+/// The cards on the board, row by row, along with the score. Every row is as long as the others.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub(crate) struct Round {
-    slots: [[Card; 4]; 4],
+    slots: Vec<Vec<Card>>,
     score: Score,
-    new_tile_weighted_index: WeightedIndex<u8>,
 }
 
+/// An empty classic 4x4 round.
 impl Default for Round {
     fn default() -> Self {
-        Round {
-            slots: [[0; 4]; 4],
-            score: Score::default(),
-            new_tile_weighted_index: WeightedIndex::new(NEW_CARD_WEIGHTS)
-                .expect("NEW_CARD_WEIGHTS should never be empty"),
-        }
+        Self::empty(4, 4)
     }
 }
 
 // public methods
 impl Round {
+    /// Returns an empty round the given number of slots wide and high, scored 0.
+    pub(crate) fn empty(width: usize, height: usize) -> Self {
+        Self {
+            slots: vec![vec![0; width]; height],
+            score: 0,
+        }
+    }
+
+    /// Returns the number of slots across and down the board.
+    pub(crate) fn dimensions(&self) -> (usize, usize) {
+        (
+            self.slots.first().map_or(0, |row| row.len()),
+            self.slots.len(),
+        )
+    }
+
     pub(crate) fn score(&self) -> Score {
         self.score
     }
 
-    pub(crate) fn random<T: Rng>(rng: &mut T) -> Self {
-        let mut r = Round::default();
-        let (xdx1, ydx1) = (rng.gen_range(0..3), rng.gen_range(0..3));
-        let (xdx2, ydx2) = (rng.gen_range(0..3), rng.gen_range(0..3));
-        loop {
-            let (xdx2, ydx2) = (rng.gen_range(0..3), rng.gen_range(0..3));
-            if (xdx1, ydx1) == (xdx2, ydx2) {
-                continue;
-            }
-            break;
+    /// Returns the round with its score replaced.
+    pub(crate) fn with_score(mut self, score: Score) -> Self {
+        self.score = score;
+        self
+    }
+
+    /// Returns a round holding the given cards, scored as if they had been built with as few
+    /// points as possible (see `min_score`).
+    pub(crate) fn from_cards<R: Into<Vec<Card>>>(rows: impl IntoIterator<Item = R>) -> Self {
+        let slots: Vec<Vec<Card>> = rows.into_iter().map(Into::into).collect();
+        debug_assert!(
+            slots.windows(2).all(|pair| pair[0].len() == pair[1].len()),
+            "every row should be as long as the others"
+        );
+        let mut round = Self { slots, score: 0 };
+        round.score = round.min_score();
+        round
+    }
+
+    /// Returns a round of the given size holding two 2s, placed at random.
+    pub(crate) fn random<T: Rng>(rng: &mut T, width: usize, height: usize) -> Self {
+        let mut r = Round::empty(width, height);
+        // two distinct slots, anywhere on the board
+        for slot in rand::seq::index::sample(rng, width * height, 2) {
+            r.slots[slot / width][slot % width] = 1;
         }
-        r.slots[ydx1][xdx1] = 1;
-        r.slots[ydx2][xdx2] = 1;
         r
     }
 
@@ -138,73 +406,568 @@ impl Round {
         *self
             .slots
             .get(idx.1)
-            .expect(format!("invalid y coordinate {}", idx.1).as_str())
+            .unwrap_or_else(|| panic!("invalid y coordinate {}", idx.1))
             .get(idx.0)
-            .expect(format!("invalid x coordinate {}", idx.0).as_str())
+            .unwrap_or_else(|| panic!("invalid x coordinate {}", idx.0))
     }
 
     pub fn shift<T: Rng>(&mut self, mut rng: T, direction: &Direction) -> Option<AnimationHint> {
-        let mut hint = AnimationHint::new();
-        let idxs = self.indices(direction).collect::<Vec<Idx>>();
-        let rows = idxs.chunks(4);
-        for row in rows {
-            let mut pivot_iter = row.iter();
-            let mut pivot_idx = pivot_iter.next().expect("should always yield an index");
-            let mut cmp_iter = pivot_iter.clone();
-            while let Some(cmp_idx) = cmp_iter.next() {
-                let pivot = self.get(pivot_idx);
-                let cmp = self.get(cmp_idx);
-                // if the cmp element is 0, move on to the next element in the row
-                if cmp == 0 {
-                    continue;
-                }
-                // if the pivot element is 0 and the cmp isn't, replace the pivot element with the
-                // cmp and zero the cmp
-                if pivot == 0 {
-                    self.set(pivot_idx, cmp);
-                    self.set(cmp_idx, 0);
-                    hint.set(cmp_idx, Hint::ToIdx(pivot_idx.clone()));
-                    continue;
-                }
-                // if the pivot element and the cmp element are equal then they must be combined;
-                // do so and increment the score by 1 since we are tracking not the actual card
-                // value, but its exponent
-                if pivot == cmp {
-                    let new_value = pivot + 1;
-                    self.score += 2_u32.pow(new_value as u32);
-                    self.set(pivot_idx, pivot + 1);
-                    self.set(cmp_idx, 0);
-                    hint.set(cmp_idx, Hint::NewValueToIdx(new_value, pivot_idx.clone()));
-                }
-                if let Some(idx) = pivot_iter.next() {
-                    pivot_idx = idx;
-                    cmp_iter = pivot_iter.clone();
-                } else {
-                    break; // no more pivots to test!
-                }
-            }
-        }
+        let before = log::log_enabled!(log::Level::Trace).then(|| self.to_debug_string());
+        let mut hint = self.slide(direction);
         if hint.changed {
-            hint.game_over = self.is_game_over(&direction);
-            let idx = idxs
-                .chunks(4)
+            let idx = self
+                .indices(direction)
+                .collect::<Vec<Idx>>()
+                .chunks(self.line_len(direction))
                 .map(|row| row.last().expect("all rows are expected to be populated"))
                 .filter(|idx| self.get(idx) == 0)
                 .choose(&mut rng)
-                .expect("all rows are populated and at least one row has changed");
-            let new_value = NEW_CARD_CHOICES[self.new_tile_weighted_index.sample(&mut rng)];
-            self.set(idx, new_value);
-            hint.set(idx, Hint::NewTile(new_value, direction.clone()));
+                .expect("all rows are populated and at least one row has changed")
+                .clone();
+            let new_value = Self::new_card(&mut rng);
+            self.set(&idx, new_value);
+            hint.set(&idx, Hint::NewTile(new_value, direction.clone()));
+            // the new tile may take the last empty slot
+            hint.game_over = self.is_game_over();
+            if let Some(before) = before {
+                log::trace!(
+                    "shifted {}:\n{}\nto\n{}",
+                    direction,
+                    before,
+                    self.to_debug_string()
+                );
+            }
             Some(hint)
         } else {
             None
         }
     }
 
-    pub(crate) fn is_game_over(&self, direction_hint: &Direction) -> bool {
-        self.indices(direction_hint)
-            .find(|v| self.get(&v) == 0)
-            .is_none()
+    /// Draws the value of a new card, a 2 nine times out of ten and otherwise a 4.
+    pub(crate) fn new_card<T: Rng>(rng: &mut T) -> Card {
+        NEW_CARD_CHOICES[new_tile_weighted_index().sample(rng)]
+    }
+
+    /// Returns the slots a shift in the given direction may place its new card in, for a round
+    /// that has already slid (see `slide`): the last slot of every row along the direction,
+    /// where it is empty, in row order.
+    pub(crate) fn spawn_candidates(&self, direction: &Direction) -> Vec<Idx> {
+        self.indices(direction)
+            .collect::<Vec<Idx>>()
+            .chunks(self.line_len(direction))
+            .map(|row| row.last().expect("all rows are expected to be populated"))
+            .filter(|idx| self.get(idx) == 0)
+            .cloned()
+            .collect()
+    }
+
+    /// Shifts the round like `shift`, but places the given card at the given index rather than a
+    /// random card at a random index. Returns None, leaving the round untouched, if the shift
+    /// wouldn't change anything or the index isn't one the shift may place its new card in (see
+    /// `spawn_candidates`).
+    pub(crate) fn shift_placing(
+        &mut self,
+        direction: &Direction,
+        idx: &Idx,
+        value: Card,
+    ) -> Option<AnimationHint> {
+        let mut slid = self.clone();
+        let mut hint = slid.slide(direction);
+        if !hint.changed || !slid.spawn_candidates(direction).contains(idx) {
+            return None;
+        }
+        slid.set(idx, value);
+        hint.set(idx, Hint::NewTile(value, direction.clone()));
+        hint.game_over = slid.is_game_over();
+        *self = slid;
+        Some(hint)
+    }
+
+    /// Slides and merges the cards in the given direction without placing a new card.
+    pub(crate) fn slide(&mut self, direction: &Direction) -> AnimationHint {
+        self.collapse_rows(direction, |_, cells| {
+            collapse_row(cells, &MergeRules::default())
+        })
+    }
+
+    /// Merges every pair of equal neighbours where they are, rows first and then columns, without
+    /// sliding anything. A card merges at most once, so a card a row merge made doesn't merge
+    /// again in its column.
+    pub(crate) fn merge_adjacent(&mut self) -> AnimationHint {
+        let mut hint = self.collapse_rows(&Direction::Left, |row, cells| {
+            merge_row_in_place(cells, &vec![false; row.len()])
+        });
+        let merged: Vec<Idx> = hint
+            .hint
+            .iter()
+            .filter_map(|(_, h)| match h {
+                Hint::NewValueToIdx(_, to) => Some(to.clone()),
+                _ => None,
+            })
+            .collect();
+        let columns = self.collapse_rows(&Direction::Up, |row, cells| {
+            let fixed: Vec<bool> = row.iter().map(|idx| merged.contains(idx)).collect();
+            merge_row_in_place(cells, &fixed)
+        });
+        hint.changed |= columns.changed;
+        hint.hint.extend(columns.hint);
+        hint
+    }
+
+    /// Takes the smallest card off the board, picking one at random if there are several. A board
+    /// holding a single card keeps it, so that there is always something left to play with.
+    pub(crate) fn remove_smallest<T: Rng>(&mut self, mut rng: T) -> AnimationHint {
+        let mut hint = AnimationHint::new();
+        let (width, height) = self.dimensions();
+        let cards: Vec<(Idx, Card)> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Idx(x, y)))
+            .map(|idx| {
+                let card = self.get(&idx);
+                (idx, card)
+            })
+            .filter(|(_, card)| *card > 0)
+            .collect();
+        if cards.len() < 2 {
+            return hint;
+        }
+        let smallest = cards.iter().map(|(_, card)| *card).min();
+        let (idx, card) = cards
+            .into_iter()
+            .filter(|(_, card)| Some(*card) == smallest)
+            .choose(&mut rng)
+            .expect("the smallest card is on the board");
+        self.set(&idx, 0);
+        hint.set(&idx, Hint::Remove(card));
+        hint
+    }
+
+    /// Doubles the new card placed by the shift the given hint describes, updating the hint to
+    /// match.
+    pub(crate) fn double_new_tile(&mut self, hint: &mut AnimationHint) {
+        for (idx, h) in hint.hint.iter_mut() {
+            if let Hint::NewTile(value, _) = h {
+                *value += 1;
+                self.set(idx, *value);
+            }
+        }
+    }
+
+    /// Returns the cells that differ between two rounds of the same size along with their values
+    /// in each, in row order.
+    pub(crate) fn diff(prev: &Round, next: &Round) -> Vec<(Idx, Card, Card)> {
+        let mut changed = Vec::new();
+        let (width, height) = prev.dimensions();
+        for y in 0..height {
+            for x in 0..width {
+                let idx = Idx(x, y);
+                let (before, after) = (prev.get(&idx), next.get(&idx));
+                if before != after {
+                    changed.push((idx, before, after));
+                }
+            }
+        }
+        changed
+    }
+
+    /// Works out the shift that turned `prev` into `next`: a slide in one direction followed by at
+    /// most one new card in a cell the slide left empty. Returns the hint that shift would have
+    /// produced, an empty hint if the rounds hold the same cards, or None if no single shift
+    /// explains the difference. Should two directions explain it, the first in `DIRECTIONS` order
+    /// is picked.
+    pub(crate) fn explain(prev: &Round, next: &Round) -> Option<AnimationHint> {
+        if Round::diff(prev, next).is_empty() {
+            return Some(AnimationHint::new());
+        }
+        DIRECTIONS.iter().find_map(|direction| {
+            let mut slid = prev.clone();
+            let mut hint = slid.slide(direction);
+            if !hint.changed {
+                return None;
+            }
+            match Round::diff(&slid, next).as_slice() {
+                [] => (),
+                [(idx, 0, value)] => hint.set(idx, Hint::NewTile(*value, direction.clone())),
+                _ => return None,
+            }
+            hint.game_over = next.is_game_over();
+            Some(hint)
+        })
+    }
+
+    /// Plays the shift or power-up the given hint describes on a copy of the round, returning the
+    /// round it leads to, or None if playing it here wouldn't give the same hint. A shift places
+    /// the new tile the hint names, doubled or not. Growth isn't replayed; see `grow_to`.
+    pub(crate) fn replay(&self, hint: &AnimationHint) -> Option<Round> {
+        let mut next = self.clone();
+        let replayed = match hint.direction() {
+            Some(direction) => {
+                let (idx, value) = hint.hint.iter().find_map(|(idx, h)| match h {
+                    Hint::NewTile(value, _) => Some((idx, *value)),
+                    _ => None,
+                })?;
+                next.shift_placing(&direction, idx, value)?
+            }
+            None => match hint.hint.as_slice() {
+                [] => AnimationHint::new(),
+                [(idx, Hint::Remove(card))] => {
+                    let cards = self.slots.iter().flatten().filter(|card| **card > 0);
+                    let removable = cards.clone().count() > 1
+                        && cards.min() == Some(card)
+                        && self.slots.get(idx.1).and_then(|row| row.get(idx.0)) == Some(card);
+                    if !removable {
+                        return None;
+                    }
+                    next.set(idx, 0);
+                    hint.clone()
+                }
+                _ => next.merge_adjacent(),
+            },
+        };
+        (replayed.hint == hint.hint).then_some(next)
+    }
+
+    /// Returns true if shifting in the given direction would change the board. Unlike `shift`
+    /// this doesn't touch the board, the RNG, or allocate.
+    pub(crate) fn would_change(&self, direction: &Direction) -> bool {
+        let row_len = self.line_len(direction);
+        let mut seen_empty = false;
+        let mut previous: Option<Card> = None;
+        for (i, idx) in self.indices(direction).enumerate() {
+            if i % row_len == 0 {
+                seen_empty = false;
+                previous = None;
+            }
+            let card = self.get(&idx);
+            if card == 0 {
+                seen_empty = true;
+                continue;
+            }
+            // a card with an empty slot ahead of it slides, a card equal to the one ahead of it
+            // (ignoring empty slots) merges
+            if seen_empty || previous == Some(card) {
+                return true;
+            }
+            previous = Some(card);
+        }
+        false
+    }
+
+    /// Returns the directions shifting in would change the board, in `DIRECTIONS` order.
+    pub(crate) fn legal_moves(&self) -> Vec<Direction> {
+        DIRECTIONS
+            .iter()
+            .filter(|direction| self.would_change(direction))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the least score a game could have reached with the cards on the board. New cards
+    /// score nothing, so the cheapest way to build a card of 2^k is out of new 4s, which scores
+    /// (k - 2) * 2^k along the way.
+    pub(crate) fn min_score(&self) -> Score {
+        self.slots
+            .iter()
+            .flat_map(|row| row.iter())
+            .filter(|card| **card > 2)
+            .map(|card| (*card as Score - 2) * 2u32.pow(*card as u32))
+            .sum()
+    }
+
+    /// Checks that the round could come up in a game: every row is as long as the others, every
+    /// card is one the game goes up to and the score is at least what building them takes.
+    pub(crate) fn validate(&self) -> Result<()> {
+        let invalid = |reason: String| Err(Error::InvalidRound { reason });
+        let (width, _) = self.dimensions();
+        if let Some(y) = self.slots.iter().position(|row| row.len() != width) {
+            return invalid(format!(
+                "row {} is {} tiles long rather than {}",
+                y,
+                self.slots[y].len(),
+                width
+            ));
+        }
+        let mut cards = self
+            .slots
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, card)| (x, y, *card)));
+        if let Some((x, y, card)) = cards.find(|(_, _, card)| *card > MAX_CARD) {
+            return invalid(format!(
+                "card 2^{} at ({},{}) is larger than the game allows",
+                card, x, y
+            ));
+        }
+        if self.score < self.min_score() {
+            return invalid(format!(
+                "score {} is less than the {} it takes to build the cards on the board",
+                self.score,
+                self.min_score()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Writes the round down as a `[Start "..."]` tag, see `start_tag`, followed by a
+    /// `[Score "..."]` tag, eg to share a position built in the board editor.
+    pub(crate) fn to_notation(&self) -> String {
+        format!("{}\n[Score \"{}\"]\n", self.start_tag(), self.score)
+    }
+
+    /// Reads a round written by `to_notation`, which must pass `validate`. A round written
+    /// without a score is scored as if its cards had been built with as few points as possible.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn from_notation(notation: &str) -> Result<Self> {
+        let invalid = |reason: String| Error::InvalidRound { reason };
+        let mut lines = notation
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty());
+        let start = lines
+            .next()
+            .ok_or_else(|| invalid(String::from("missing start position")))?;
+        let mut round = parse_start(start).map_err(invalid)?;
+        round.score = match lines.next() {
+            Some(line) => parse_score(line).map_err(invalid)?,
+            None => round.min_score(),
+        };
+        if let Some(line) = lines.next() {
+            return Err(invalid(format!("unexpected {:?} after the score", line)));
+        }
+        round.validate()?;
+        Ok(round)
+    }
+
+    /// The `[Start "..."]` tag giving the tiles row by row as shown on the board, eg
+    /// `[Start "2,0,0,0/0,0,0,0/0,0,4,0/0,0,0,0"]`.
+    pub(crate) fn start_tag(&self) -> String {
+        let rows: Vec<String> = self
+            .slots
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|card| display_value(*card).to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect();
+        format!("[Start \"{}\"]", rows.join("/"))
+    }
+
+    /// Returns true if shifting in at least one direction would change the board.
+    pub(crate) fn has_moves(&self) -> bool {
+        DIRECTIONS
+            .iter()
+            .any(|direction| self.would_change(direction))
+    }
+
+    /// Returns the number of empty slots.
+    pub(crate) fn empty_count(&self) -> usize {
+        self.slots
+            .iter()
+            .flat_map(|row| row.iter())
+            .filter(|card| **card == 0)
+            .count()
+    }
+
+    /// Returns true once a tile worth 2048 or more is on the board.
+    pub(crate) fn has_won(&self) -> bool {
+        self.max_card() >= WINNING_CARD
+    }
+
+    /// Returns the largest card on the board.
+    pub(crate) fn max_card(&self) -> Card {
+        self.slots
+            .iter()
+            .flat_map(|row| row.iter())
+            .copied()
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Returns the plan for taking back the move described by `hint`, which must be the hint of
+    /// the shift that produced this round.
+    ///
+    /// Tiles that only slid go back to where they came from. A merged tile splits in two: the tile
+    /// that merged into it slides back out, while the tile it merged with either slides back too
+    /// or, if it never moved, stays put at the lower value. Tiles taken off the board come back.
+    pub(crate) fn rewind_plan(&self, hint: &AnimationHint) -> RewindPlan {
+        let mut plan = RewindPlan::default();
+        let merged: Vec<&Idx> = hint
+            .hint
+            .iter()
+            .filter_map(|(_, h)| match h {
+                Hint::NewValueToIdx(_, to) => Some(to),
+                _ => None,
+            })
+            .collect();
+        for (from, h) in hint.hint.iter() {
+            match h {
+                Hint::NewTile(_, _) => plan.hint.insert(0, (from.clone(), RewindHint::RemoveTile)),
+                Hint::ToIdx(to) => {
+                    // a tile that slid into a merge had the value from before the merge
+                    let value = if merged.contains(&to) {
+                        self.get(to) - 1
+                    } else {
+                        self.get(to)
+                    };
+                    plan.hint
+                        .push((to.clone(), RewindHint::ToIdx(value, from.clone())));
+                }
+                Hint::NewValueToIdx(value, to) => {
+                    plan.score += 2_u32.pow(*value as u32);
+                    let stationary = !hint
+                        .hint
+                        .iter()
+                        .any(|(_, h)| matches!(h, Hint::ToIdx(other) if other == to));
+                    if stationary {
+                        plan.hint
+                            .push((to.clone(), RewindHint::SetValue(value - 1)));
+                    }
+                    plan.hint
+                        .push((to.clone(), RewindHint::SplitToIdx(value - 1, from.clone())));
+                }
+                Hint::Remove(value) => plan.hint.push((from.clone(), RewindHint::PutBack(*value))),
+            }
+        }
+        plan
+    }
+
+    /// Returns the round as it was before the move that the given plan takes back.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn rewind(&self, plan: &RewindPlan) -> Round {
+        let mut prior = self.clone();
+        prior.score -= plan.score;
+        // every tile leaves before any arrives, since a tile can slide back into a slot that
+        // another tile is leaving
+        for (idx, h) in plan.hint.iter() {
+            if matches!(h, RewindHint::ToIdx(..) | RewindHint::RemoveTile) {
+                prior.set(idx, 0);
+            }
+        }
+        for (idx, h) in plan.hint.iter() {
+            match h {
+                RewindHint::ToIdx(value, to) | RewindHint::SplitToIdx(value, to) => {
+                    prior.set(to, *value)
+                }
+                RewindHint::SetValue(value) | RewindHint::PutBack(value) => prior.set(idx, *value),
+                RewindHint::RemoveTile => (),
+            }
+        }
+        prior
+    }
+
+    /// Grows the board to the given number of slots across and down, which must be at least as
+    /// many as it has, keeping every tile where it is counting from the top left; the new rows
+    /// and columns are empty and the score is unchanged.
+    pub(crate) fn grow_to(&mut self, width: usize, height: usize) -> AnimationHint {
+        let from = self.dimensions();
+        assert!(
+            width >= from.0 && height >= from.1,
+            "a {}x{} board can't grow to {}x{}",
+            from.0,
+            from.1,
+            width,
+            height
+        );
+        for row in self.slots.iter_mut() {
+            row.resize(width, 0);
+        }
+        self.slots.resize(height, vec![0; width]);
+        let mut hint = AnimationHint::new();
+        hint.changed = true;
+        hint.grew_from = Some(from);
+        hint
+    }
+
+    /// Takes back a growth, shrinking the board to the given number of slots across and down.
+    /// Fails, leaving the round as it is, unless every slot cut off is empty.
+    pub(crate) fn shrink_to(&mut self, width: usize, height: usize) -> Result<()> {
+        let (from_width, from_height) = self.dimensions();
+        if width > from_width || height > from_height {
+            return Err(Error::InvalidRound {
+                reason: format!(
+                    "a {}x{} board can't shrink to {}x{}",
+                    from_width, from_height, width, height
+                ),
+            });
+        }
+        let cut_off = self.slots.iter().enumerate().find_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .find(|(x, card)| **card > 0 && (*x >= width || y >= height))
+                .map(|(x, _)| (x, y))
+        });
+        if let Some((x, y)) = cut_off {
+            return Err(Error::InvalidRound {
+                reason: format!(
+                    "shrinking to {}x{} would cut off the tile at ({},{})",
+                    width, height, x, y
+                ),
+            });
+        }
+        self.slots.truncate(height);
+        for row in self.slots.iter_mut() {
+            row.truncate(width);
+        }
+        Ok(())
+    }
+
+    /// Returns the pair of equal adjacent cards with the largest value, along with that value, or
+    /// None if no two adjacent cards are equal. Ties go to the pair whose first card is in the
+    /// lowest row, then the lowest column; a horizontal pair beats a vertical one sharing that card.
+    pub(crate) fn largest_mergeable_pair(&self) -> Option<(Idx, Idx, Card)> {
+        let mut largest: Option<(Idx, Idx, Card)> = None;
+        let (width, height) = self.dimensions();
+        for y in 0..height {
+            for x in 0..width {
+                let card = self.get(&Idx(x, y));
+                if card == 0 || matches!(largest, Some((_, _, l)) if l >= card) {
+                    continue;
+                }
+                largest = [Idx(x + 1, y), Idx(x, y + 1)]
+                    .into_iter()
+                    .find(|other| other.0 < width && other.1 < height && self.get(other) == card)
+                    .map(|other| (Idx(x, y), other, card))
+                    .or(largest);
+            }
+        }
+        largest
+    }
+
+    /// Returns true if shifting in none of the four directions would change the board.
+    pub(crate) fn is_game_over(&self) -> bool {
+        !self.has_moves()
+    }
+
+    /// Draws the round as a grid for logging, a row per line with the cards' values right
+    /// aligned and `·` for empty slots, eg `| ·| 2| ·| ·|`.
+    pub(crate) fn to_debug_string(&self) -> String {
+        let text = |card: Card| match card {
+            0 => "\u{b7}".to_string(),
+            card => display_value(card).to_string(),
+        };
+        let width = self
+            .slots
+            .iter()
+            .flatten()
+            .map(|card| text(*card).chars().count())
+            .fold(2, usize::max);
+        self.slots
+            .iter()
+            .map(|row| {
+                let cells: String = row
+                    .iter()
+                    .map(|card| format!("{:>1$}|", text(*card), width))
+                    .collect();
+                format!("|{}", cells)
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Returns the rows of the board, top to bottom.
+    pub(crate) fn iter_rows(&self) -> impl Iterator<Item = &[Card]> {
+        self.slots.iter().map(|row| row.as_slice())
     }
 }
 
@@ -214,12 +977,47 @@ impl Round {
         Indices::new(self, direction.clone())
     }
 
+    /// Returns the number of slots in each row running in the given direction.
+    fn line_len(&self, direction: &Direction) -> usize {
+        let (width, height) = self.dimensions();
+        match direction {
+            Direction::Left | Direction::Right => width,
+            Direction::Up | Direction::Down => height,
+        }
+    }
+
     fn get_mut(&mut self, idx: &Idx) -> &mut Card {
         self.slots
             .get_mut(idx.1)
-            .expect(format!("invalid y coordinate {}", idx.1).as_str())
+            .unwrap_or_else(|| panic!("invalid y coordinate {}", idx.1))
             .get_mut(idx.0)
-            .expect(format!("invalid x coordinate {}", idx.0).as_str())
+            .unwrap_or_else(|| panic!("invalid x coordinate {}", idx.0))
+    }
+
+    /// Collapses every row running in the given direction with `collapse`, which is handed the
+    /// indices of the row along with its cards, and describes what moved.
+    fn collapse_rows<F>(&mut self, direction: &Direction, mut collapse: F) -> AnimationHint
+    where
+        F: FnMut(&[Idx], &mut [Card]) -> RowOutcome,
+    {
+        let mut hint = AnimationHint::new();
+        let idxs = self.indices(direction).collect::<Vec<Idx>>();
+        for row in idxs.chunks(self.line_len(direction)) {
+            let mut cells: Vec<Card> = row.iter().map(|idx| self.get(idx)).collect();
+            let outcome = collapse(row, &mut cells);
+            for (idx, value) in row.iter().zip(cells) {
+                self.set(idx, value);
+            }
+            self.score += outcome.points;
+            for m in outcome.moves {
+                let to = row[m.to].clone();
+                match m.merged {
+                    Some(value) => hint.set(&row[m.from], Hint::NewValueToIdx(value, to)),
+                    None => hint.set(&row[m.from], Hint::ToIdx(to)),
+                }
+            }
+        }
+        hint
     }
 
     fn set(&mut self, idx: &Idx, value: Card) {
@@ -227,13 +1025,58 @@ impl Round {
         *rf = value;
     }
 
-    #[cfg(test)]
-    pub(crate) fn set_value(&mut self, idx: &Idx, value: u8) {
+    pub(crate) fn set_value(&mut self, idx: &Idx, value: Card) {
         let rf = self.get_mut(idx);
         *rf = value;
     }
 }
 
+/// Reads a tag written by `Round::start_tag`.
+pub(super) fn parse_start(line: &str) -> std::result::Result<Round, String> {
+    let cells = line
+        .strip_prefix("[Start \"")
+        .and_then(|rest| rest.strip_suffix("\"]"))
+        .ok_or_else(|| format!("expected a start position, found {:?}", line))?;
+    let rows: Vec<&str> = cells.split('/').collect();
+    let width = rows[0].split(',').count();
+    let (width, height) = BoardConfig::new(width, rows.len())
+        .map(|size| (size.width, size.height))
+        .map_err(|reason| format!("unsupported board size: {}", reason))?;
+    let mut round = Round::empty(width, height);
+    for (y, row) in rows.iter().enumerate() {
+        let values: Vec<&str> = row.split(',').collect();
+        if values.len() != width {
+            return Err(format!(
+                "expected {} tiles in row {}, found {}",
+                width,
+                y,
+                values.len()
+            ));
+        }
+        for (x, value) in values.iter().enumerate() {
+            let card = value
+                .parse()
+                .ok()
+                .and_then(card_from_display)
+                .ok_or_else(|| format!("invalid tile {:?}", value))?;
+            round.set_value(&Idx(x, y), card);
+        }
+    }
+    Ok(round)
+}
+
+/// Reads a `[Score "..."]` tag written by `Round::to_notation`.
+#[cfg_attr(not(test), allow(dead_code))]
+fn parse_score(line: &str) -> std::result::Result<Score, String> {
+    let score = line
+        .strip_prefix("[Score \"")
+        .and_then(|rest| rest.strip_suffix("\"]"))
+        .ok_or_else(|| format!("expected a score, found {:?}", line))?;
+    score
+        .parse()
+        .map_err(|_| format!("invalid score {:?}", score))
+}
+
 // Indices is an iterator of Idx over a given round's 2d array of slots.
 struct Indices {
     direction: Direction,
@@ -245,7 +1088,7 @@ struct Indices {
 
 impl Indices {
     fn new(round: &Round, direction: Direction) -> Self {
-        let (x_width, y_width) = { (round.slots.len(), round.slots[0].len()) };
+        let (x_width, y_width) = round.dimensions();
 
         let (xdx, ydx) = match direction {
             Direction::Left => (0, 0),
@@ -339,17 +1182,11 @@ mod test {
     use rstest::*;
 
     use super::*;
+    use crate::engine::fixtures::{card, cards, full_without_merges, round, with_tiles, Values};
     fn rng() -> SmallRng {
         SmallRng::seed_from_u64(42)
     }
 
-    fn round(slots: [[Card; 4]; 4], score: Score) -> Round {
-        let mut r = Round::default();
-        r.slots = slots;
-        r.score = score;
-        r
-    }
-
     #[test]
     fn clone() {
         let initial = Round::default();
@@ -376,66 +1213,424 @@ mod test {
         }
     }
 
+    #[rstest]
+    #[case::empty([[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], vec![])]
+    #[case::full_no_merges(
+        [[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]],
+        vec![]
+    )]
+    #[case::empty_left_column(
+        [[0, 4, 2, 4], [0, 2, 4, 2], [0, 4, 2, 4], [0, 2, 4, 2]],
+        vec![Direction::Left]
+    )]
+    #[case::horizontal_merge(
+        [[2, 2, 4, 8], [4, 8, 16, 32], [8, 16, 32, 64], [16, 32, 64, 128]],
+        vec![Direction::Left, Direction::Right]
+    )]
+    #[case::vertical_merge(
+        [[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [2, 8, 4, 2]],
+        vec![Direction::Up, Direction::Down]
+    )]
+    #[case::single_card([[0, 0, 0, 0], [0, 2, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], DIRECTIONS.to_vec())]
+    fn legal_moves(#[case] slots: Values, #[case] expected: Vec<Direction>) {
+        assert_eq!(round!(slots).legal_moves(), expected);
+    }
+
+    #[rstest]
+    #[case::empty([[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0)]
+    #[case::new_cards_only([[2, 4, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 4, 2]], 0)]
+    #[case::one_merge_of_fours([[8, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8)]
+    #[case::winning_card([[2048, 8, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 18440)]
+    fn min_score(#[case] slots: Values, #[case] expected: Score) {
+        assert_eq!(round!(slots).min_score(), expected);
+        assert_eq!(Round::from_cards(cards(slots)).score(), expected);
+    }
+
+    #[rstest]
+    #[case::valid(round!([[8, 2, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8), true)]
+    #[case::score_too_low(round!([[8, 2, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 4), false)]
+    #[case::card_too_large(round!([[262144, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], u32::MAX), false)]
+    fn validate(#[case] round: Round, #[case] valid: bool) {
+        assert_eq!(round.validate().is_ok(), valid);
+    }
+
+    #[rstest]
+    #[case::largest(MAX_CARD, true)]
+    #[case::past_the_largest(MAX_CARD + 1, false)]
+    fn cards_are_held_to_the_same_limit_on_every_board_size(
+        #[values((3, 3), (5, 3), (8, 8))] size: (usize, usize),
+        #[case] card: Card,
+        #[case] valid: bool,
+    ) {
+        let (width, height) = size;
+        let mut round = Round::empty(width, height);
+        round.set_value(&Idx(width - 1, height - 1), card);
+        let score = round.min_score();
+        let round = round.with_score(score);
+        assert_eq!(round.validate().is_ok(), valid, "{}x{}", width, height);
+    }
+
+    #[rstest]
+    #[case::empty(round!([[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0))]
+    #[case::scored(round!([[8, 2, 0, 0], [0, 0, 0, 0], [0, 0, 0, 64], [0, 4, 0, 0]], 1000))]
+    #[case::full(full_without_merges())]
+    #[case::larger(Round::from_cards([[1, 0, 0, 0, 2], [0; 5], [0, 3, 0, 0, 0]]))]
+    fn notation_round_trips(#[case] round: Round) {
+        let notation = round.to_notation();
+        assert_eq!(
+            Round::from_notation(&notation).ok(),
+            Some(round),
+            "{}",
+            notation
+        );
+    }
+
+    #[test]
+    fn notation_without_a_score_is_scored_the_least_it_takes() {
+        let notation = "[Start \"8,0,0,0/0,0,0,0/0,0,0,0/0,0,0,2\"]";
+        let round = Round::from_notation(notation).expect("the notation should be read");
+        assert_eq!(round.score(), 8);
+        assert_eq!(round.get(&Idx(0, 0)), 3);
+        assert_eq!(round.get(&Idx(3, 3)), 1);
+    }
+
+    #[rstest]
+    #[case::missing_start("", "missing start position")]
+    #[case::bad_tile("[Start \"3,0,0,0/0,0,0,0/0,0,0,0/0,0,0,0\"]", "invalid tile \"3\"")]
+    #[case::bad_score(
+        "[Start \"8,0,0,0/0,0,0,0/0,0,0,0/0,0,0,0\"]\n[Score \"x\"]",
+        "invalid score \"x\""
+    )]
+    #[case::score_too_low(
+        "[Start \"8,0,0,0/0,0,0,0/0,0,0,0/0,0,0,0\"]\n[Score \"4\"]",
+        "score 4 is less than the 8"
+    )]
+    #[case::trailing(
+        "[Start \"0,0,0,0/0,0,0,0/0,0,0,0/0,0,0,0\"]\n[Score \"0\"]\n1 Left",
+        "unexpected \"1 Left\""
+    )]
+    #[case::ragged("[Start \"2,0,0/0,0/0,0,0\"]", "expected 3 tiles in row 1, found 2")]
+    #[case::too_small("[Start \"2,0/0,2\"]", "unsupported board size")]
+    fn bad_notation_is_refused(#[case] notation: &str, #[case] expected: &str) {
+        let e = Round::from_notation(notation).expect_err("the notation should be refused");
+        assert!(e.to_string().contains(expected), "{}", e);
+    }
+
+    #[test]
+    fn played_rounds_are_valid() {
+        let mut rng = rng();
+        let mut current = Round::random(&mut rng, 4, 4);
+        for _ in 0..2000 {
+            let direction = DIRECTIONS[rng.gen_range(0..DIRECTIONS.len())].clone();
+            let _ = current.shift(&mut rng, &direction);
+            assert!(current.validate().is_ok(), "{:?}", current);
+            if !current.has_moves() {
+                current = Round::random(&mut rng, 4, 4);
+            }
+        }
+    }
+
+    /// Returns a round of any size from its rows of values shown on the board.
+    fn sized(rows: &[Vec<u32>]) -> Round {
+        let rows = rows
+            .iter()
+            .map(|row| row.iter().map(|v| card(*v)).collect::<Vec<_>>());
+        Round::from_cards(rows)
+    }
+
+    #[rstest]
+    #[case::left(Direction::Left, [(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)])]
+    #[case::right(Direction::Right, [(4, 0), (3, 0), (2, 0), (1, 0), (0, 0)])]
+    #[case::up(Direction::Up, [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1)])]
+    #[case::down(Direction::Down, [(0, 2), (0, 1), (0, 0), (1, 2), (1, 1)])]
+    fn indices_run_along_the_rows_of_rectangular_rounds(
+        #[case] direction: Direction,
+        #[case] first: [(usize, usize); 5],
+    ) {
+        let round = Round::empty(5, 3);
+        let idxs: Vec<Idx> = round.indices(&direction).collect();
+        let first: Vec<Idx> = first.into_iter().map(|(x, y)| Idx(x, y)).collect();
+        assert_eq!(idxs[..5], first[..]);
+        let mut covered: Vec<(usize, usize)> = idxs.iter().map(|idx| (idx.0, idx.1)).collect();
+        covered.sort();
+        covered.dedup();
+        assert_eq!(covered.len(), 15, "{:?}", idxs);
+        assert!(covered.iter().all(|(x, y)| *x < 5 && *y < 3), "{:?}", idxs);
+    }
+
+    #[rstest]
+    #[case::wide_left(
+        Direction::Left,
+        vec![vec![2, 0, 2, 0, 4], vec![0, 0, 0, 0, 8], vec![4, 4, 4, 0, 0]],
+        vec![vec![4, 4, 0, 0, 0], vec![8, 0, 0, 0, 0], vec![8, 4, 0, 0, 0]],
+    )]
+    #[case::wide_down(
+        Direction::Down,
+        vec![vec![2, 0, 2, 0, 4], vec![0, 0, 0, 0, 8], vec![2, 4, 4, 0, 0]],
+        vec![vec![0, 0, 0, 0, 0], vec![0, 0, 2, 0, 4], vec![4, 4, 4, 0, 8]],
+    )]
+    #[case::wide_right(
+        Direction::Right,
+        vec![vec![2, 0, 2, 0, 4], vec![8, 0, 0, 0, 0], vec![4, 4, 4, 0, 0]],
+        vec![vec![0, 0, 0, 4, 4], vec![0, 0, 0, 0, 8], vec![0, 0, 0, 4, 8]],
+    )]
+    #[case::wide_up(
+        Direction::Up,
+        vec![vec![2, 0, 2, 0, 4], vec![0, 0, 0, 0, 8], vec![2, 4, 2, 0, 4]],
+        vec![vec![4, 4, 4, 0, 4], vec![0, 0, 0, 0, 8], vec![0, 0, 0, 0, 4]],
+    )]
+    #[case::five_by_five_left(
+        Direction::Left,
+        vec![
+            vec![2, 2, 2, 2, 2],
+            vec![0, 4, 0, 4, 8],
+            vec![2, 0, 0, 0, 2],
+            vec![0, 0, 0, 0, 0],
+            vec![8, 4, 2, 4, 8],
+        ],
+        vec![
+            vec![4, 4, 2, 0, 0],
+            vec![8, 8, 0, 0, 0],
+            vec![4, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0],
+            vec![8, 4, 2, 4, 8],
+        ],
+    )]
+    #[case::five_by_five_down(
+        Direction::Down,
+        vec![
+            vec![2, 0, 4, 0, 2],
+            vec![2, 0, 0, 0, 0],
+            vec![2, 0, 4, 2, 0],
+            vec![2, 0, 0, 0, 0],
+            vec![2, 8, 0, 4, 2],
+        ],
+        vec![
+            vec![0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0],
+            vec![2, 0, 0, 0, 0],
+            vec![4, 0, 0, 2, 0],
+            vec![4, 8, 8, 4, 4],
+        ],
+    )]
+    #[case::tall_right(
+        Direction::Right,
+        vec![vec![2, 2, 2], vec![0, 4, 4], vec![8, 0, 0], vec![0, 0, 0], vec![2, 0, 2]],
+        vec![vec![0, 2, 4], vec![0, 0, 8], vec![0, 0, 8], vec![0, 0, 0], vec![0, 0, 4]],
+    )]
+    #[case::tall_up(
+        Direction::Up,
+        vec![vec![2, 2, 2], vec![0, 4, 4], vec![8, 0, 0], vec![0, 0, 0], vec![2, 0, 2]],
+        vec![vec![2, 2, 2], vec![8, 4, 4], vec![2, 0, 2], vec![0, 0, 0], vec![0, 0, 0]],
+    )]
+    fn boards_of_any_size_slide_whole_rows(
+        #[case] direction: Direction,
+        #[case] initial: Vec<Vec<u32>>,
+        #[case] expected: Vec<Vec<u32>>,
+    ) {
+        let mut round = sized(&initial);
+        assert!(round.would_change(&direction));
+        round.slide(&direction);
+        assert_eq!(
+            Round::diff(&round, &sized(&expected)),
+            vec![],
+            "{}",
+            round.to_debug_string()
+        );
+        // every row running in the direction ends in a slot the new card may go in
+        let (width, height) = round.dimensions();
+        let rows = match direction {
+            Direction::Left | Direction::Right => height,
+            Direction::Up | Direction::Down => width,
+        };
+        let ends = Round::empty(width, height).spawn_candidates(&direction);
+        assert_eq!(ends.len(), rows);
+    }
+
+    #[rstest]
+    #[case::five_by_five(5, 5)]
+    #[case::wide(6, 3)]
+    #[case::tall(3, 8)]
+    fn boards_of_any_size_play_out(#[case] width: usize, #[case] height: usize) {
+        let mut rng = rng();
+        let mut current = Round::random(&mut rng, width, height);
+        for _ in 0..500 {
+            assert_eq!(current.dimensions(), (width, height));
+            assert!(current.empty_count() < width * height);
+            let direction = DIRECTIONS[rng.gen_range(0..DIRECTIONS.len())].clone();
+            let changes = current.would_change(&direction);
+            assert_eq!(current.shift(&mut rng, &direction).is_some(), changes);
+            assert!(current.validate().is_ok(), "{:?}", current);
+            if !current.has_moves() {
+                current = Round::random(&mut rng, width, height);
+            }
+        }
+    }
+
+    #[test]
+    fn diff_lists_changed_cells_in_row_order() {
+        let prev = round!([[2, 2, 0, 0], [0, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0]]);
+        let next = round!([[4, 0, 0, 0], [0, 0, 0, 0], [4, 0, 0, 2], [0, 0, 0, 0]], 4);
+        assert_eq!(
+            Round::diff(&prev, &next),
+            vec![(Idx(0, 0), 1, 2), (Idx(1, 0), 1, 0), (Idx(3, 2), 0, 1)]
+        );
+        assert!(Round::diff(&next, &next).is_empty());
+    }
+
+    #[rstest]
+    #[case::same_cards(
+        [[2, 2, 4, 4], [0; 4], [0; 4], [0; 4]],
+        [[2, 2, 4, 4], [0; 4], [0; 4], [0; 4]],
+        Some(vec![])
+    )]
+    #[case::merges_and_a_new_tile(
+        [[2, 2, 4, 4], [0; 4], [0; 4], [0; 4]],
+        [[4, 8, 0, 2], [0; 4], [0; 4], [0; 4]],
+        Some(vec![
+            (Idx(1, 0), Hint::NewValueToIdx(2, Idx(0, 0))),
+            (Idx(2, 0), Hint::ToIdx(Idx(1, 0))),
+            (Idx(3, 0), Hint::NewValueToIdx(3, Idx(1, 0))),
+            (Idx(3, 0), Hint::NewTile(1, Direction::Left)),
+        ])
+    )]
+    #[case::slide_without_a_new_tile(
+        [[0; 4], [0, 0, 2, 0], [0; 4], [0; 4]],
+        [[0; 4], [0; 4], [0; 4], [0, 0, 2, 0]],
+        Some(vec![(Idx(2, 1), Hint::ToIdx(Idx(2, 3)))])
+    )]
+    #[case::new_tile_left_of_a_right_shift(
+        [[0, 2, 0, 0], [0; 4], [0; 4], [0; 4]],
+        [[4, 0, 0, 2], [0; 4], [0; 4], [0; 4]],
+        Some(vec![
+            (Idx(1, 0), Hint::ToIdx(Idx(3, 0))),
+            (Idx(0, 0), Hint::NewTile(2, Direction::Right)),
+        ])
+    )]
+    #[case::two_new_tiles(
+        [[2, 0, 0, 0], [0; 4], [0; 4], [0; 4]],
+        [[2, 2, 2, 0], [0; 4], [0; 4], [0; 4]],
+        None
+    )]
+    #[case::tile_removed(
+        [[2, 4, 0, 0], [0; 4], [0; 4], [0; 4]],
+        [[2, 0, 0, 0], [0; 4], [0; 4], [0; 4]],
+        None
+    )]
+    fn explain(
+        #[case] prev: Values,
+        #[case] next: Values,
+        #[case] expected: Option<Vec<(Idx, Hint)>>,
+    ) {
+        let hint = Round::explain(&round!(prev), &round!(next));
+        assert_eq!(hint.map(|h| h.hints()), expected);
+    }
+
+    #[test]
+    fn explain_agrees_with_played_shifts() {
+        let mut rng = rng();
+        let mut current = Round::random(&mut rng, 4, 4);
+        let mut explained = 0;
+        for _ in 0..2000 {
+            let direction = DIRECTIONS[rng.gen_range(0..DIRECTIONS.len())].clone();
+            let prior = current.clone();
+            if let Some(hint) = current.shift(&mut rng, &direction) {
+                let explanation = Round::explain(&prior, &current).expect("a shift explains it");
+                // another direction may explain the same move, in which case it must produce the
+                // same round
+                if explanation != hint {
+                    let mut replayed = prior.clone();
+                    let direction = explanation.direction().expect("a new tile was placed");
+                    replayed.slide(&direction);
+                    assert!(Round::diff(&replayed, &current).len() <= 1);
+                    continue;
+                }
+                explained += 1;
+            }
+            if !current.has_moves() {
+                current = Round::random(&mut rng, 4, 4);
+            }
+        }
+        assert!(explained > 1000, "only {} moves were explained", explained);
+    }
+
+    #[rstest]
+    #[case::places_tile(Direction::Left, Idx(3, 0), true)]
+    #[case::occupied_after_sliding(Direction::Left, Idx(0, 0), false)]
+    #[case::off_the_edge(Direction::Left, Idx(1, 1), false)]
+    #[case::unchanged_board(Direction::Up, Idx(3, 3), false)]
+    fn shift_placing(#[case] direction: Direction, #[case] idx: Idx, #[case] placed: bool) {
+        let initial = round!([[2, 2, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]);
+        let mut r = initial.clone();
+        let hint = r.shift_placing(&direction, &idx, 1);
+        assert_eq!(hint.is_some(), placed);
+        if placed {
+            let expected = round!([[4, 0, 0, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 4);
+            assert_eq!(r, expected);
+            assert_eq!(r.score, expected.score);
+            let hint = hint.expect("tile should be placed");
+            assert_eq!(hint.direction(), Some(direction));
+        } else {
+            assert_eq!(r, initial);
+        }
+    }
+
     #[rstest]
     #[case::identity_left(Direction::Left,
-           [[1, 0, 0, 0], [0, 1, 0, 0], [0, 0, 1, 0], [0, 0, 0, 1]],
-           [[1, 0, 0, 1], [1, 0, 0, 0], [1, 0, 0, 0], [1, 0, 0, 0]],
+           [[2, 0, 0, 0], [0, 2, 0, 0], [0, 0, 2, 0], [0, 0, 0, 2]],
+           [[2, 0, 0, 2], [2, 0, 0, 0], [2, 0, 0, 0], [2, 0, 0, 0]],
     )]
     #[case::identity_right(Direction::Right,
-           [[1, 0, 0, 0], [0, 1, 0, 0], [0, 0, 1, 0], [0, 0, 0, 1]],
-           [[1, 0, 0, 1], [0, 0, 0, 1], [0, 0, 0, 1], [0, 0, 0, 1]],
+           [[2, 0, 0, 0], [0, 2, 0, 0], [0, 0, 2, 0], [0, 0, 0, 2]],
+           [[2, 0, 0, 2], [0, 0, 0, 2], [0, 0, 0, 2], [0, 0, 0, 2]],
     )]
     #[case::identity_up(Direction::Up,
-           [[1, 0, 0, 0], [0, 1, 0, 0], [0, 0, 1, 0], [0, 0, 0, 1]],
-           [[1, 1, 1, 1], [0, 0, 0, 0], [0, 0, 0, 0], [1, 0, 0, 0]],
+           [[2, 0, 0, 0], [0, 2, 0, 0], [0, 0, 2, 0], [0, 0, 0, 2]],
+           [[2, 2, 2, 2], [0, 0, 0, 0], [0, 0, 0, 0], [2, 0, 0, 0]],
     )]
     #[case::identity_down(Direction::Down,
-           [[1, 0, 0, 0], [0, 1, 0, 0], [0, 0, 1, 0], [0, 0, 0, 1]],
-           [[1, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [1, 1, 1, 1]],
+           [[2, 0, 0, 0], [0, 2, 0, 0], [0, 0, 2, 0], [0, 0, 0, 2]],
+           [[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [2, 2, 2, 2]],
     )]
     #[case::flipped_identity_left(Direction::Left,
-           [[0, 0, 0, 1], [0, 0, 1, 0], [0, 1, 0, 0], [1, 0, 0, 0]],
-           [[1, 0, 0, 1], [1, 0, 0, 0], [1, 0, 0, 0], [1, 0, 0, 0]],
+           [[0, 0, 0, 2], [0, 0, 2, 0], [0, 2, 0, 0], [2, 0, 0, 0]],
+           [[2, 0, 0, 2], [2, 0, 0, 0], [2, 0, 0, 0], [2, 0, 0, 0]],
     )]
     #[case::flipped_identity_right(Direction::Right,
-           [[0, 0, 0, 1], [0, 0, 1, 0], [0, 1, 0, 0], [1, 0, 0, 0]],
-           [[1, 0, 0, 1], [0, 0, 0, 1], [0, 0, 0, 1], [0, 0, 0, 1]],
+           [[0, 0, 0, 2], [0, 0, 2, 0], [0, 2, 0, 0], [2, 0, 0, 0]],
+           [[2, 0, 0, 2], [0, 0, 0, 2], [0, 0, 0, 2], [0, 0, 0, 2]],
     )]
     #[case::flipped_identity_up(Direction::Up,
-           [[0, 0, 0, 1], [0, 0, 1, 0], [0, 1, 0, 0], [1, 0, 0, 0]],
-           [[1, 1, 1, 1], [0, 0, 0, 0], [0, 0, 0, 0], [1, 0, 0, 0]],
+           [[0, 0, 0, 2], [0, 0, 2, 0], [0, 2, 0, 0], [2, 0, 0, 0]],
+           [[2, 2, 2, 2], [0, 0, 0, 0], [0, 0, 0, 0], [2, 0, 0, 0]],
     )]
     #[case::flipped_identity_down(Direction::Down,
-           [[0, 0, 0, 1], [0, 0, 1, 0], [0, 1, 0, 0], [1, 0, 0, 0]],
-           [[1, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [1, 1, 1, 1]],
+           [[0, 0, 0, 2], [0, 0, 2, 0], [0, 2, 0, 0], [2, 0, 0, 0]],
+           [[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [2, 2, 2, 2]],
     )]
     #[case::all_left(Direction::Left,
-           [[0, 0, 0, 1], [0, 0, 0, 1], [0, 0, 0, 1], [0, 0, 0, 1]],
-           [[1, 0, 0, 1], [1, 0, 0, 0], [1, 0, 0, 0], [1, 0, 0, 0]],
+           [[0, 0, 0, 2], [0, 0, 0, 2], [0, 0, 0, 2], [0, 0, 0, 2]],
+           [[2, 0, 0, 2], [2, 0, 0, 0], [2, 0, 0, 0], [2, 0, 0, 0]],
     )]
     #[case::all_right(Direction::Right,
-           [[1, 0, 0, 0], [1, 0, 0, 0], [1, 0, 0, 0], [1, 0, 0, 0]],
-           [[1, 0, 0, 1], [0, 0, 0, 1], [0, 0, 0, 1], [0, 0, 0, 1]],
+           [[2, 0, 0, 0], [2, 0, 0, 0], [2, 0, 0, 0], [2, 0, 0, 0]],
+           [[2, 0, 0, 2], [0, 0, 0, 2], [0, 0, 0, 2], [0, 0, 0, 2]],
     )]
     #[case::all_down(Direction::Down,
-           [[1, 1, 1, 1], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
-           [[1, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [1, 1, 1, 1]],
+           [[2, 2, 2, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
+           [[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [2, 2, 2, 2]],
     )]
     #[case::all_up(Direction::Up,
-           [[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [1, 1, 1, 1]],
-           [[1, 1, 1, 1], [0, 0, 0, 0], [0, 0, 0, 0], [1, 0, 0, 0]],
+           [[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [2, 2, 2, 2]],
+           [[2, 2, 2, 2], [0, 0, 0, 0], [0, 0, 0, 0], [2, 0, 0, 0]],
     )]
     #[case::pivot_is_zero_with_multiple_shift_elements(Direction::Left,
-           [[0, 1, 2, 3], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
-           [[1, 2, 3, 1], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
+           [[0, 2, 4, 8], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
+           [[2, 4, 8, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
     )]
-    fn shift(
-        #[case] direction: Direction,
-        #[case] initial: [[Card; 4]; 4],
-        #[case] expected: [[Card; 4]; 4],
-    ) {
-        let initial = round(initial, 0);
-        let expected = round(expected, 0);
+    fn shift(#[case] direction: Direction, #[case] initial: Values, #[case] expected: Values) {
+        let initial = round!(initial);
+        let expected = round!(expected);
 
         let mut shifted = initial.clone();
         let mut rng = rng();
@@ -446,48 +1641,48 @@ mod test {
     #[rstest]
     #[case::all1s(
         Direction::Left,
-        round([[1, 1, 1, 1], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0),
-        round([[2, 2, 0, 1], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8),
+        round!([[2, 2, 2, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]),
+        round!([[4, 4, 0, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8),
     )]
     #[case::combine2s_shift_remaining(
         Direction::Left,
-        round([[2, 2, 0, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0),
-        round([[3, 2, 0, 1], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8),
+        round!([[4, 4, 0, 4], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]),
+        round!([[8, 4, 0, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8),
     )]
     #[case::combine2s_shift_remaining(
         Direction::Left,
-        round([[2, 0, 2, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0),
-        round([[3, 2, 0, 1], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8),
+        round!([[4, 0, 4, 4], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]),
+        round!([[8, 4, 0, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8),
     )]
     #[case::combine2s_ignore_4(
         Direction::Left,
-        round([[4, 2, 0, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0),
-        round([[4, 3, 0, 1], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8),
+        round!([[16, 4, 0, 4], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]),
+        round!([[16, 8, 0, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8),
     )]
     #[case::noop_no_compatible_combinations(
         Direction::Left,
-        round([[2, 4, 8, 16], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0),
-        round([[2, 4, 8, 16], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0),
+        round!([[2, 4, 8, 16], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]),
+        round!([[2, 4, 8, 16], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]),
     )]
     #[case::all1s_right(
         Direction::Right,
-        round([[1, 1, 1, 1], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0),
-        round([[1, 0, 2, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8),
+        round!([[2, 2, 2, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]),
+        round!([[2, 0, 4, 4], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8),
     )]
     #[case::combine2s_shift_remaining_right(
         Direction::Right,
-        round([[2, 2, 0, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0),
-        round([[1, 0, 2, 3], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8),
+        round!([[4, 4, 0, 4], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]),
+        round!([[2, 0, 4, 8], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8),
     )]
     #[case::combine2s_ignore_4_right(
         Direction::Right,
-        round([[4, 2, 0, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0),
-        round([[1, 0, 4, 3], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8),
+        round!([[16, 4, 0, 4], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]),
+        round!([[2, 0, 16, 8], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8),
     )]
     #[case::noop_no_compatible_combinations_right(
         Direction::Right,
-        round([[2, 4, 8, 16], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 4),
-        round([[2, 4, 8, 16], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 4),
+        round!([[2, 4, 8, 16], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 4),
+        round!([[2, 4, 8, 16], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 4),
     )]
     fn combine(#[case] direction: Direction, #[case] initial: Round, #[case] expected: Round) {
         let mut shifted = initial.clone();
@@ -497,24 +1692,563 @@ mod test {
     }
 
     #[rstest]
-    #[case::slide_up(Direction::Up)]
-    #[case::slide_down(Direction::Down)]
-    #[case::slide_left(Direction::Left)]
-    #[case::slide_right(Direction::Right)]
-    fn validate_game_over(#[case] direction: Direction) {
-        let initial = round(
-            [
-                [8, 16, 32, 64],
-                [64, 0, 16, 8],
-                [8, 16, 32, 64],
-                [64, 32, 16, 8],
-            ],
-            0,
-        );
+    #[case::slide_up(Direction::Up, false)]
+    #[case::slide_down(Direction::Down, false)]
+    #[case::slide_left(Direction::Left, false)]
+    // the new tile fills the last empty slot, where it has nothing to merge with
+    #[case::slide_right(Direction::Right, true)]
+    fn validate_game_over(#[case] direction: Direction, #[case] expected: bool) {
+        let initial = round!([
+            [8, 16, 32, 64],
+            [64, 0, 16, 8],
+            [8, 16, 32, 64],
+            [64, 32, 16, 8],
+        ]);
         let mut shifted = initial.clone();
         let mut rng = rng();
         let hint = shifted.shift(&mut rng, &direction);
         assert!(hint.is_some());
-        assert_eq!(hint.unwrap().game_over, false);
+        assert_eq!(hint.unwrap().game_over, expected);
+    }
+
+    #[rstest]
+    #[case::empty_slot(
+        round!([[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 0, 4], [4, 2, 4, 2]]),
+        false
+    )]
+    #[case::horizontal_merge(
+        round!([[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 4, 2], [4, 2, 8, 4]]),
+        false
+    )]
+    #[case::vertical_merge(
+        round!([[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 4]]),
+        false
+    )]
+    #[case::no_merges(full_without_merges(), true)]
+    #[case::only_left(
+        round!([[0, 2, 4, 2], [0, 4, 2, 4], [0, 2, 4, 2], [0, 4, 2, 4]]),
+        false
+    )]
+    #[case::only_down(
+        round!([[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [0, 0, 0, 0]]),
+        false
+    )]
+    #[case::pair_in_the_corner(
+        round!([[2, 2, 4, 8], [4, 8, 16, 32], [8, 16, 32, 64], [16, 32, 64, 128]]),
+        false
+    )]
+    #[case::stuck_with_large_tiles(
+        round!([[2, 4, 8, 16], [32, 64, 128, 256], [2, 4, 8, 16], [32, 64, 128, 256]]),
+        true
+    )]
+    #[case::stuck_with_equal_tiles_apart(
+        round!([[2, 4, 2, 4], [8, 16, 8, 16], [2, 4, 2, 4], [8, 16, 8, 16]]),
+        true
+    )]
+    fn is_game_over_checks_adjacent_cards(#[case] round: Round, #[case] expected: bool) {
+        assert_eq!(round.is_game_over(), expected);
+        // the game is over exactly when shifting either way changes nothing
+        let mut rng = rng();
+        let shifted = DIRECTIONS
+            .iter()
+            .filter(|direction| round.clone().shift(&mut rng, direction).is_some())
+            .count();
+        assert_eq!(shifted == 0, expected, "{}", round.to_debug_string());
+    }
+
+    #[rstest]
+    #[case::classic(4, 4)]
+    #[case::small(3, 3)]
+    #[case::wide(5, 3)]
+    #[case::tall(3, 5)]
+    fn random_rounds_start_with_two_tiles_anywhere(#[case] width: usize, #[case] height: usize) {
+        let mut rng = rng();
+        let mut seen = vec![vec![false; width]; height];
+        for _ in 0..200 {
+            let round = Round::random(&mut rng, width, height);
+            let tiles: Vec<Idx> = round
+                .indices(&Direction::Left)
+                .filter(|idx| round.get(idx) != 0)
+                .collect();
+            assert_eq!(tiles.len(), 2, "{}", round.to_debug_string());
+            for idx in tiles {
+                assert_eq!(round.get(&idx), 1);
+                seen[idx.y()][idx.x()] = true;
+            }
+        }
+        assert!(
+            seen.iter().flatten().all(|s| *s),
+            "unused slots: {:?}",
+            seen
+        );
+    }
+
+    #[test]
+    fn growing_keeps_the_tiles_and_the_score() {
+        let mut round = Round::from_cards([[1, 0, 2], [0, 3, 0], [4, 0, 1]]).with_score(100);
+        let hint = round.grow_to(4, 4);
+        assert_eq!(hint.grew_from(), Some((3, 3)));
+        assert!(hint.changed());
+        assert_eq!(
+            round,
+            Round::from_cards([[1, 0, 2, 0], [0, 3, 0, 0], [4, 0, 1, 0], [0, 0, 0, 0]])
+                .with_score(100)
+        );
+
+        round.shrink_to(3, 3).expect("the slots cut off are empty");
+        assert_eq!(
+            round,
+            Round::from_cards([[1, 0, 2], [0, 3, 0], [4, 0, 1]]).with_score(100)
+        );
+    }
+
+    #[rstest]
+    #[case::last_column(Idx(3, 0))]
+    #[case::last_row(Idx(0, 3))]
+    #[case::corner(Idx(3, 3))]
+    fn shrinking_refuses_to_cut_off_tiles(#[case] idx: Idx) {
+        let mut round = Round::empty(3, 3);
+        round.grow_to(4, 4);
+        round.set_value(&idx, 1);
+        let grown = round.clone();
+        assert!(round.shrink_to(3, 3).is_err());
+        assert_eq!(round, grown);
+    }
+
+    #[test]
+    fn is_game_over_agrees_with_shifting_throughout_games() {
+        let mut rng = rng();
+        for (width, height) in [(4, 4), (5, 3), (3, 5)] {
+            let mut round = Round::random(&mut rng, width, height);
+            loop {
+                let moved: Vec<Direction> = DIRECTIONS
+                    .iter()
+                    .filter(|direction| round.clone().shift(&mut rng, direction).is_some())
+                    .cloned()
+                    .collect();
+                assert_eq!(
+                    round.is_game_over(),
+                    moved.is_empty(),
+                    "{}",
+                    round.to_debug_string()
+                );
+                match moved.first() {
+                    Some(direction) => {
+                        round.shift(&mut rng, direction);
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn default_round_debug_string_is_all_empty_slots() {
+        let text = Round::default().to_debug_string();
+        assert_eq!(text.lines().count(), 4);
+        let cells: Vec<&str> = text
+            .lines()
+            .flat_map(|line| line.split('|'))
+            .map(str::trim)
+            .filter(|cell| !cell.is_empty())
+            .collect();
+        assert_eq!(cells.len(), 16);
+        assert!(cells.iter().all(|cell| *cell == "\u{b7}"), "{}", text);
+    }
+
+    #[test]
+    fn debug_string_aligns_values_to_the_widest() {
+        let round = with_tiles(&[(Idx(1, 0), 2), (Idx(2, 1), 1024)]);
+        assert_eq!(
+            round.to_debug_string(),
+            [
+                "|   \u{b7}|   2|   \u{b7}|   \u{b7}|",
+                "|   \u{b7}|   \u{b7}|1024|   \u{b7}|",
+                "|   \u{b7}|   \u{b7}|   \u{b7}|   \u{b7}|",
+                "|   \u{b7}|   \u{b7}|   \u{b7}|   \u{b7}|",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn the_new_tile_taking_the_last_slot_can_end_the_game() {
+        let mut prev = round!([[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [0, 8, 16, 32]]);
+        let mut next = prev.clone();
+        let hint = next
+            .shift_placing(&Direction::Left, &Idx(3, 3), card(2))
+            .expect("the tiles should move");
+        assert!(hint.game_over(), "{}", next.to_debug_string());
+        let explained = Round::explain(&prev, &next).expect("a shift explains the rounds");
+        assert!(explained.game_over());
+
+        // a new tile matching its neighbour leaves a merge to make
+        let hint = prev
+            .shift_placing(&Direction::Left, &Idx(3, 3), card(32))
+            .expect("the tiles should move");
+        assert!(!hint.game_over(), "{}", prev.to_debug_string());
+    }
+
+    #[test]
+    fn hint_debug_string_lists_every_hint() {
+        let mut round = with_tiles(&[(Idx(1, 0), 2), (Idx(2, 0), 2), (Idx(3, 1), 4)]);
+        let hint = round
+            .shift_placing(&Direction::Left, &Idx(3, 3), card(2))
+            .expect("the tiles should move");
+        assert_eq!(
+            hint.to_debug_string(),
+            "(1,0)\u{2192}(0,0) (2,0)\u{2192}(0,0)=4 (3,1)\u{2192}(0,1) (3,3)+2"
+        );
+    }
+
+    #[rstest]
+    #[case::classic(vec![
+        vec![1, 2, 3, 4],
+        vec![5, 6, 7, 8],
+        vec![9, 10, 11, 12],
+        vec![13, 14, 15, 16],
+    ])]
+    #[case::three_by_five(vec![
+        vec![1, 2, 3],
+        vec![4, 5, 6],
+        vec![7, 8, 9],
+        vec![10, 11, 12],
+        vec![13, 14, 15],
+    ])]
+    fn iter_rows(#[case] rows: Vec<Vec<Card>>) {
+        let mut round = Round::empty(rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, card) in row.iter().enumerate() {
+                round.set_value(&Idx(x, y), *card);
+            }
+        }
+        let iterated: Vec<&[Card]> = round.iter_rows().collect();
+        assert_eq!(iterated, rows);
+    }
+
+    #[rstest]
+    #[case::empty(round!([[0; 4]; 4]), None)]
+    #[case::no_pairs(
+        round!([[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]]),
+        None
+    )]
+    #[case::gaps_do_not_count(
+        round!([[8, 0, 8, 0], [0, 0, 0, 0], [8, 0, 0, 0], [0, 0, 0, 0]]),
+        None
+    )]
+    #[case::horizontal(
+        round!([[2, 4, 0, 0], [0, 0, 0, 0], [0, 32, 32, 0], [0, 0, 0, 0]]),
+        Some((Idx(1, 2), Idx(2, 2), 5))
+    )]
+    #[case::vertical(
+        round!([[0, 0, 0, 0], [0, 0, 0, 64], [2, 2, 0, 64], [0, 0, 0, 0]]),
+        Some((Idx(3, 1), Idx(3, 2), 6))
+    )]
+    #[case::highest_value_wins(
+        round!([[4, 4, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 128, 128]]),
+        Some((Idx(2, 3), Idx(3, 3), 7))
+    )]
+    #[case::tie_goes_to_lowest_row(
+        round!([[0, 0, 0, 0], [0, 0, 16, 0], [16, 0, 16, 0], [16, 0, 0, 0]]),
+        Some((Idx(2, 1), Idx(2, 2), 4))
+    )]
+    #[case::tie_goes_to_lowest_column(
+        round!([[0, 0, 0, 0], [8, 0, 0, 8], [8, 0, 0, 8], [0, 0, 0, 0]]),
+        Some((Idx(0, 1), Idx(0, 2), 3))
+    )]
+    #[case::horizontal_beats_vertical(
+        round!([[0, 0, 0, 0], [0, 16, 16, 0], [0, 16, 0, 0], [0, 0, 0, 0]]),
+        Some((Idx(1, 1), Idx(2, 1), 4))
+    )]
+    fn largest_mergeable_pair(#[case] round: Round, #[case] expected: Option<(Idx, Idx, Card)>) {
+        assert_eq!(round.largest_mergeable_pair(), expected);
+    }
+
+    #[rstest]
+    #[case::slide_left(
+        Direction::Left,
+        [[0, 0, 0, 2], [0; 4], [0; 4], [0; 4]],
+        vec![(Idx(0, 0), RewindHint::ToIdx(1, Idx(3, 0)))]
+    )]
+    #[case::merge_in_place_left(
+        Direction::Left,
+        [[4, 4, 0, 0], [0; 4], [0; 4], [0; 4]],
+        vec![
+            (Idx(0, 0), RewindHint::SetValue(2)),
+            (Idx(0, 0), RewindHint::SplitToIdx(2, Idx(1, 0))),
+        ]
+    )]
+    #[case::slide_and_merge_left(
+        Direction::Left,
+        [[0, 4, 0, 4], [0; 4], [0; 4], [0; 4]],
+        vec![
+            (Idx(0, 0), RewindHint::ToIdx(2, Idx(1, 0))),
+            (Idx(0, 0), RewindHint::SplitToIdx(2, Idx(3, 0))),
+        ]
+    )]
+    #[case::slide_right(
+        Direction::Right,
+        [[2, 0, 0, 0], [0; 4], [0; 4], [0; 4]],
+        vec![(Idx(3, 0), RewindHint::ToIdx(1, Idx(0, 0)))]
+    )]
+    #[case::merge_in_place_right(
+        Direction::Right,
+        [[0, 0, 4, 4], [0; 4], [0; 4], [0; 4]],
+        vec![
+            (Idx(3, 0), RewindHint::SetValue(2)),
+            (Idx(3, 0), RewindHint::SplitToIdx(2, Idx(2, 0))),
+        ]
+    )]
+    #[case::slide_and_merge_right(
+        Direction::Right,
+        [[4, 0, 4, 0], [0; 4], [0; 4], [0; 4]],
+        vec![
+            (Idx(3, 0), RewindHint::ToIdx(2, Idx(2, 0))),
+            (Idx(3, 0), RewindHint::SplitToIdx(2, Idx(0, 0))),
+        ]
+    )]
+    #[case::slide_up(
+        Direction::Up,
+        [[0; 4], [0; 4], [0; 4], [2, 0, 0, 0]],
+        vec![(Idx(0, 0), RewindHint::ToIdx(1, Idx(0, 3)))]
+    )]
+    #[case::merge_in_place_up(
+        Direction::Up,
+        [[4, 0, 0, 0], [4, 0, 0, 0], [0; 4], [0; 4]],
+        vec![
+            (Idx(0, 0), RewindHint::SetValue(2)),
+            (Idx(0, 0), RewindHint::SplitToIdx(2, Idx(0, 1))),
+        ]
+    )]
+    #[case::slide_and_merge_up(
+        Direction::Up,
+        [[0; 4], [4, 0, 0, 0], [0; 4], [4, 0, 0, 0]],
+        vec![
+            (Idx(0, 0), RewindHint::ToIdx(2, Idx(0, 1))),
+            (Idx(0, 0), RewindHint::SplitToIdx(2, Idx(0, 3))),
+        ]
+    )]
+    #[case::slide_down(
+        Direction::Down,
+        [[2, 0, 0, 0], [0; 4], [0; 4], [0; 4]],
+        vec![(Idx(0, 3), RewindHint::ToIdx(1, Idx(0, 0)))]
+    )]
+    #[case::merge_in_place_down(
+        Direction::Down,
+        [[0; 4], [0; 4], [4, 0, 0, 0], [4, 0, 0, 0]],
+        vec![
+            (Idx(0, 3), RewindHint::SetValue(2)),
+            (Idx(0, 3), RewindHint::SplitToIdx(2, Idx(0, 2))),
+        ]
+    )]
+    #[case::slide_and_merge_down(
+        Direction::Down,
+        [[4, 0, 0, 0], [0; 4], [4, 0, 0, 0], [0; 4]],
+        vec![
+            (Idx(0, 3), RewindHint::ToIdx(2, Idx(0, 2))),
+            (Idx(0, 3), RewindHint::SplitToIdx(2, Idx(0, 0))),
+        ]
+    )]
+    #[case::slide_into_a_vacated_slot(
+        Direction::Left,
+        [[0, 2, 4, 0], [0; 4], [0; 4], [0; 4]],
+        vec![
+            (Idx(0, 0), RewindHint::ToIdx(1, Idx(1, 0))),
+            (Idx(1, 0), RewindHint::ToIdx(2, Idx(2, 0))),
+        ]
+    )]
+    fn rewind_plan(
+        #[case] direction: Direction,
+        #[case] slots: Values,
+        #[case] expected: Vec<(Idx, RewindHint)>,
+    ) {
+        let prior = round!(slots);
+        let mut next = prior.clone();
+        let hint = next
+            .shift(rng(), &direction)
+            .expect("the move should change the round");
+        let plan = next.rewind_plan(&hint);
+
+        let new_tile = hint
+            .hints()
+            .into_iter()
+            .find_map(|(idx, h)| matches!(h, Hint::NewTile(..)).then_some(idx))
+            .expect("every move places a new tile");
+        let hints = plan.hints();
+        assert_eq!(
+            hints.first(),
+            Some(&(new_tile, RewindHint::RemoveTile)),
+            "the new tile should be removed first"
+        );
+        let rest = &hints[1..];
+        assert_eq!(rest.len(), expected.len(), "{:?}", rest);
+        for step in expected.iter() {
+            assert!(rest.contains(step), "{:?} missing from {:?}", step, rest);
+        }
+        assert_eq!(next.rewind(&plan), prior);
+    }
+
+    #[test]
+    fn rewinding_any_move_restores_the_prior_round() {
+        let mut rng = rng();
+        let mut current = Round::random(&mut rng, 4, 4);
+        let mut rewound = 0;
+        for _ in 0..5000 {
+            let direction = DIRECTIONS[rng.gen_range(0..DIRECTIONS.len())].clone();
+            let prior = current.clone();
+            if let Some(hint) = current.shift(&mut rng, &direction) {
+                let plan = current.rewind_plan(&hint);
+                assert_eq!(current.rewind(&plan), prior, "rewinding {:?}", plan);
+                rewound += 1;
+            }
+            if !current.has_moves() {
+                current = Round::random(&mut rng, 4, 4);
+            }
+        }
+        assert!(rewound > 2500, "only {} moves were rewound", rewound);
+    }
+
+    /// The textbook way to collapse a row, kept deliberately simple to check `collapse_row`
+    /// against: take the cards in order, merge each pair of equal neighbours and pack what's left
+    /// towards position 0.
+    fn reference_collapse(cells: &mut [Card], _rules: &MergeRules) -> RowOutcome {
+        let cards: Vec<(usize, Card)> = cells
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c != 0)
+            .map(|(i, c)| (i, *c))
+            .collect();
+        let mut outcome = RowOutcome::default();
+        let mut packed = Vec::new();
+        let mut i = 0;
+        while i < cards.len() {
+            let (from, value) = cards[i];
+            let to = packed.len();
+            if from != to {
+                outcome.moves.push(RowMove {
+                    from,
+                    to,
+                    merged: None,
+                });
+            }
+            match cards.get(i + 1) {
+                Some(&(next, next_value)) if next_value == value => {
+                    outcome.points += display_value(value + 1);
+                    outcome.moves.push(RowMove {
+                        from: next,
+                        to,
+                        merged: Some(value + 1),
+                    });
+                    packed.push(value + 1);
+                    i += 2;
+                }
+                _ => {
+                    packed.push(value);
+                    i += 1;
+                }
+            }
+        }
+        packed.resize(cells.len(), 0);
+        cells.copy_from_slice(&packed);
+        outcome
+    }
+
+    #[rstest]
+    #[case::empty(&[0, 0, 0, 0], &[0, 0, 0, 0], 0)]
+    #[case::gap(&[0, 1, 0, 2], &[1, 2, 0, 0], 0)]
+    #[case::pair_across_a_gap(&[1, 0, 1, 0], &[2, 0, 0, 0], 4)]
+    #[case::merges_once(&[1, 1, 2, 0], &[2, 2, 0, 0], 4)]
+    #[case::two_pairs(&[1, 1, 1, 1], &[2, 2, 0, 0], 8)]
+    #[case::leading_pair_wins(&[2, 2, 2, 0], &[3, 2, 0, 0], 8)]
+    #[case::moved_card_merges(&[0, 3, 3, 1], &[4, 1, 0, 0], 16)]
+    fn collapse_row_slides_and_merges(
+        #[case] row: &[Card],
+        #[case] expected: &[Card],
+        #[case] points: Score,
+    ) {
+        let mut cells = row.to_vec();
+        let outcome = collapse_row(&mut cells, &MergeRules::default());
+        assert_eq!(cells, expected);
+        assert_eq!(outcome.points, points);
+    }
+
+    #[test]
+    fn collapse_row_agrees_with_the_reference() {
+        let mut rng = rng();
+        let rules = MergeRules::default();
+        for _ in 0..20_000 {
+            let len = rng.gen_range(1..=6);
+            // small values so that rows often have something to merge, with the odd large one
+            let row: Vec<Card> = (0..len)
+                .map(|_| match rng.gen_range(0..10) {
+                    0 => MAX_CARD,
+                    n => rng.gen_range(0..=n.min(4)),
+                })
+                .collect();
+            let (mut collapsed, mut expected) = (row.clone(), row.clone());
+            let outcome = collapse_row(&mut collapsed, &rules);
+            let reference = reference_collapse(&mut expected, &rules);
+            assert_eq!(collapsed, expected, "collapsing {:?}", row);
+            assert_eq!(outcome, reference, "collapsing {:?}", row);
+        }
+    }
+
+    #[test]
+    fn slide_hints_match_the_board_diff() {
+        let mut rng = rng();
+        for _ in 0..2000 {
+            let mut prev = Round::default();
+            for y in 0..4 {
+                for x in 0..4 {
+                    prev.set(&Idx(x, y), rng.gen_range(0..=3));
+                }
+            }
+            for direction in DIRECTIONS {
+                let mut slid = prev.clone();
+                let hint = slid.slide(&direction);
+                let hints = hint.hints();
+
+                let mut replayed = prev.clone();
+                for (from, _) in hints.iter() {
+                    replayed.set(from, 0);
+                }
+                for (from, h) in hints.iter() {
+                    let (to, value) = match h {
+                        Hint::ToIdx(to) => (to, prev.get(from)),
+                        Hint::NewValueToIdx(value, to) => (to, *value),
+                        other => panic!("sliding hinted {:?}", other),
+                    };
+                    let ahead = match direction {
+                        Direction::Left => to.1 == from.1 && to.0 < from.0,
+                        Direction::Right => to.1 == from.1 && to.0 > from.0,
+                        Direction::Up => to.0 == from.0 && to.1 < from.1,
+                        Direction::Down => to.0 == from.0 && to.1 > from.1,
+                    };
+                    assert!(ahead, "{:?} to {:?} sliding {:?}", from, to, direction);
+                    replayed.set(to, value);
+                }
+                assert_eq!(
+                    replayed.slots,
+                    slid.slots,
+                    "sliding {:?}:\n{}",
+                    direction,
+                    prev.to_debug_string()
+                );
+                let hinted = |idx: &Idx| {
+                    hints.iter().any(|(from, h)| match h {
+                        Hint::ToIdx(to) | Hint::NewValueToIdx(_, to) => from == idx || to == idx,
+                        _ => from == idx,
+                    })
+                };
+                for (idx, _, _) in Round::diff(&prev, &slid) {
+                    assert!(
+                        hinted(&idx),
+                        "{:?} changed without a hint sliding {:?}",
+                        idx,
+                        direction
+                    );
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,181 @@
+//! New tiles decided ahead of the moves they come with, so that several boards can be dealt the
+//! same tiles however differently they are played.
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+use super::round::{Card, Idx, Round};
+
+/// The new tile that comes with a move, decided before the move is made: its value, and a rank
+/// that picks which of the slots the move leaves open it goes in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Spawn {
+    rank: u32,
+    value: Card,
+}
+
+impl Spawn {
+    #[cfg(test)]
+    pub(crate) fn new(rank: u32, value: Card) -> Self {
+        Self { rank, value }
+    }
+
+    pub(crate) fn value(&self) -> Card {
+        self.value
+    }
+
+    /// Picks the slot the tile goes in out of the given candidates, listed the way
+    /// `Round::spawn_candidates` lists them. The rank is scaled onto the candidates, picking the
+    /// one at `rank * candidates / 2^32`, so every candidate is as likely as the next however
+    /// many there are, and boards left with the same candidates pick the same slot. Boards left
+    /// with different candidates pick the one at the same relative position in their list.
+    /// Returns None only if there are no candidates.
+    pub(crate) fn place<'a>(&self, candidates: &'a [Idx]) -> Option<&'a Idx> {
+        let position = (u64::from(self.rank) * candidates.len() as u64) >> u32::BITS;
+        candidates.get(position as usize)
+    }
+}
+
+/// Deals out the spawn for every move of a game from a seeded random number generator. Spawns
+/// are keyed by the index of the move they come with, so boards that are dealt from the same
+/// sequence get the same spawns move for move, whichever of them gets there first.
+pub(crate) struct SpawnSequence {
+    rng: StdRng,
+    dealt: Vec<Spawn>,
+}
+
+impl SpawnSequence {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            dealt: Vec::new(),
+        }
+    }
+
+    /// Returns the spawn that comes with the given move, counting from 0.
+    pub(crate) fn get(&mut self, move_index: usize) -> Spawn {
+        while self.dealt.len() <= move_index {
+            let rank = self.rng.next_u32();
+            let value = Round::new_card(&mut self.rng);
+            self.dealt.push(Spawn { rank, value });
+        }
+        self.dealt[move_index]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+    use crate::engine::board::{Board, BoardConfig};
+    use crate::engine::direction::Direction;
+    use crate::engine::fixtures::{card, round};
+
+    #[rstest]
+    #[case::first(0, 4, 0)]
+    #[case::last(u32::MAX, 4, 3)]
+    #[case::just_below_half(u32::MAX / 2, 4, 1)]
+    #[case::half(1 << 31, 4, 2)]
+    #[case::same_rank_fewer_candidates(1 << 31, 3, 1)]
+    #[case::only_one(u32::MAX, 1, 0)]
+    fn place_scales_the_rank_onto_the_candidates(
+        #[case] rank: u32,
+        #[case] candidates: usize,
+        #[case] expected: usize,
+    ) {
+        let candidates: Vec<Idx> = (0..candidates).map(|x| Idx(x, 0)).collect();
+        let spawn = Spawn::new(rank, 1);
+        assert_eq!(spawn.place(&candidates), Some(&Idx(expected, 0)));
+    }
+
+    #[test]
+    fn place_needs_a_candidate() {
+        assert_eq!(Spawn::new(7, 1).place(&[]), None);
+    }
+
+    #[rstest]
+    fn every_candidate_is_placed_about_as_often(#[values(1, 2, 3, 4)] count: usize) {
+        let candidates: Vec<Idx> = (0..count).map(|x| Idx(x, 0)).collect();
+        let mut spawns = SpawnSequence::new(5);
+        let draws = 4000;
+        let mut placed = vec![0usize; count];
+        for i in 0..draws {
+            let idx = spawns
+                .get(i)
+                .place(&candidates)
+                .expect("there are candidates");
+            placed[idx.x()] += 1;
+        }
+        let fair = draws / count;
+        for (x, n) in placed.into_iter().enumerate() {
+            assert!(n.abs_diff(fair) < fair / 10, "{} of {} at {}", n, draws, x);
+        }
+    }
+
+    #[test]
+    fn sequences_with_the_same_seed_deal_the_same_spawns() {
+        let (mut a, mut b) = (SpawnSequence::new(11), SpawnSequence::new(11));
+        // asking out of order doesn't change what is dealt
+        let late = b.get(20);
+        let dealt: Vec<Spawn> = (0..30).map(|i| a.get(i)).collect();
+        assert_eq!(dealt[20], late);
+        assert_eq!(dealt, (0..30).map(|i| b.get(i)).collect::<Vec<_>>());
+        assert!(dealt.iter().all(|s| s.value() == 1 || s.value() == 2));
+        assert_ne!(
+            dealt,
+            (0..30)
+                .map(|i| SpawnSequence::new(12).get(i))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn boards_dealt_the_same_spawns_play_out_the_same_game() {
+        let mut spawns = SpawnSequence::new(3);
+        let (mut a, mut b) = (
+            Board::new_seeded(9, BoardConfig::default()),
+            Board::new_seeded(10, BoardConfig::default()),
+        );
+        let start = round!([[2, 0, 0, 2], [0, 4, 0, 0], [0, 0, 0, 0], [8, 0, 0, 0]]);
+        a.set_initial_round(start.clone());
+        b.set_initial_round(start);
+        for direction in [
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+            Direction::Up,
+        ] {
+            let spawn = spawns.get(a.move_count());
+            assert!(a.shift_spawning(direction.clone(), &spawn).hint().is_some());
+            assert!(b.shift_spawning(direction, &spawn).hint().is_some());
+            assert_eq!(a.current(), b.current());
+        }
+    }
+
+    #[test]
+    fn boards_that_diverge_place_the_spawn_among_their_own_open_slots() {
+        // three eighths of the way through the candidates
+        let spawn = Spawn::new(3 << 29, card(4));
+        let mut a = Board::new_seeded(1, BoardConfig::default());
+        a.set_initial_round(round!([
+            [2, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 4, 0],
+            [0, 0, 0, 0]
+        ]));
+        let mut b = Board::new_seeded(1, BoardConfig::default());
+        b.set_initial_round(round!([
+            [2, 4, 8, 16],
+            [0, 0, 0, 0],
+            [0, 0, 4, 0],
+            [0, 0, 0, 0]
+        ]));
+
+        // every row leaves its right end open on the first board, the second board's first row
+        // is full
+        a.shift_spawning(Direction::Left, &spawn);
+        b.shift_spawning(Direction::Left, &spawn);
+        assert_eq!(a.current().get(&Idx(3, 1)), card(4));
+        assert_eq!(b.current().get(&Idx(3, 2)), card(4));
+    }
+}
@@ -0,0 +1,104 @@
+//! Simple strategies for picking moves, for a computer opponent to play with.
+use super::direction::Direction;
+use super::round::{Round, DIRECTIONS};
+
+/// How a computer opponent picks its moves. Both look a single move ahead at most, so they are
+/// quick enough to play between frames, and neither is hard to beat.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Strategy {
+    /// Make the move that leaves the most empty slots, preferring the one that scores more.
+    #[default]
+    Greedy,
+    /// Keep the largest tiles in the bottom left corner: shift down, or else left, or else
+    /// right, and only shift up when nothing else moves.
+    Corner,
+}
+
+/// The order the corner strategy tries directions in.
+const CORNER_DIRECTIONS: [Direction; 4] = [
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+    Direction::Up,
+];
+
+impl Strategy {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Greedy => "greedy",
+            Self::Corner => "corner",
+        }
+    }
+
+    /// Picks the move to make from the given round, or None if no move changes it.
+    pub(crate) fn choose(&self, round: &Round) -> Option<Direction> {
+        match self {
+            Self::Greedy => DIRECTIONS
+                .iter()
+                .filter(|direction| round.would_change(direction))
+                .map(|direction| {
+                    let mut slid = round.clone();
+                    slid.slide(direction);
+                    (direction, (slid.empty_count(), slid.score()))
+                })
+                // the first of several equally good moves, in the order of DIRECTIONS
+                .rev()
+                .max_by_key(|(_, value)| *value)
+                .map(|(direction, _)| direction.clone()),
+            Self::Corner => CORNER_DIRECTIONS
+                .into_iter()
+                .find(|direction| round.would_change(direction)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+    use crate::engine::fixtures::{full_without_merges, round};
+
+    #[rstest]
+    // merging both pairs of 2s leaves more slots empty than merging the 32s, which scores more
+    #[case::most_empty(
+        round!([[2, 2, 2, 2], [32, 0, 0, 0], [32, 0, 0, 0], [0, 0, 0, 0]]),
+        Some(Direction::Left)
+    )]
+    #[case::scores_more(
+        round!([[2, 2, 0, 0], [8, 0, 0, 0], [8, 0, 0, 0], [0, 0, 0, 0]]),
+        Some(Direction::Up)
+    )]
+    // nothing merges, so every move is as good as the others and the first is made
+    #[case::ties_go_to_the_first(
+        round!([[0, 2, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]),
+        Some(Direction::Left)
+    )]
+    #[case::stuck(full_without_merges(), None)]
+    fn greedy_keeps_the_board_open(#[case] round: Round, #[case] expected: Option<Direction>) {
+        assert_eq!(Strategy::Greedy.choose(&round), expected);
+    }
+
+    #[rstest]
+    #[case::down(
+        round!([[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]),
+        Some(Direction::Down)
+    )]
+    #[case::left(
+        round!([[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 4, 8]]),
+        Some(Direction::Left)
+    )]
+    #[case::right(
+        round!([[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [2, 4, 8, 0]]),
+        Some(Direction::Right)
+    )]
+    #[case::up(
+        round!([[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [2, 4, 8, 16]]),
+        Some(Direction::Up)
+    )]
+    #[case::stuck(full_without_merges(), None)]
+    fn corner_prefers_down_then_left(#[case] round: Round, #[case] expected: Option<Direction>) {
+        assert_eq!(Strategy::Corner.choose(&round), expected);
+    }
+}
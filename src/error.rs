@@ -26,6 +26,12 @@ pub(crate) enum Error {
         source: crate::tui::error::TuiError,
     },
 
+    #[error("{operation} failed: {source}")]
+    TerminalFailed {
+        operation: crate::tui::renderer::TerminalOperation,
+        source: Box<crate::tui::error::TuiError>,
+    },
+
     #[error("unable to retrieve drawbuffer: {context:?}")]
     UnableToRetrieveSlot { context: String },
 
@@ -33,8 +39,92 @@ pub(crate) enum Error {
     CannotConvertToStatic,
 
     #[error("cannot convert {idx:?} to sliding tile slot")]
-    CannotConvertToSliding { idx: Option<crate::engine::round::Idx> },
+    CannotConvertToSliding {
+        idx: Option<crate::engine::round::Idx>,
+    },
+
+    #[error(
+        "terminal too small: it is {} x {}, the required minimum size is {} x {}",
+        .have.0, .have.1, .need.0, .need.1
+    )]
+    TerminalTooSmall {
+        have: (usize, usize),
+        need: (usize, usize),
+    },
+
+    #[error("invalid move record on line {line}: {reason}")]
+    InvalidMoveRecord { line: usize, reason: String },
+
+    #[error("games that used power-ups can't be written as move records")]
+    PowerUpsNotRecordable,
+
+    #[error("games whose board grew can't be written as move records")]
+    GrowthNotRecordable,
+
+    #[error("invalid saved game {path:?}: {reason}")]
+    CorruptSave {
+        path: std::path::PathBuf,
+        reason: String,
+    },
+
+    #[error("invalid round: {reason}")]
+    InvalidRound { reason: String },
+
+    #[error("no single shift turns the previous round into the next one")]
+    NoShiftBetweenRounds,
+
+    #[error("unable to generate a {0:?} practice position")]
+    PracticePositionUnavailable(crate::engine::practice::Profile),
+
+    #[error("practice positions are only made for 4x4 boards, not {0}")]
+    PracticeNeedsClassicBoard(crate::engine::board::BoardConfig),
+
+    #[error("invalid config file {path:?}: {source}")]
+    InvalidConfig {
+        path: std::path::PathBuf,
+        source: Box<toml::de::Error>,
+    },
+
+    #[error("invalid label pack {path:?}: {reason}")]
+    InvalidPack {
+        path: std::path::PathBuf,
+        reason: String,
+    },
+
+    #[error("invalid high score file {path:?}: {reason}")]
+    InvalidHighScore {
+        path: std::path::PathBuf,
+        reason: String,
+    },
+
+    #[error("{format} file has an invalid version: {found}")]
+    InvalidFormatVersion { format: &'static str, found: String },
+
+    #[error("a cell aspect of {aspect} is out of range, it must be between {} and {}",
+        .range.start(), .range.end())]
+    InvalidCellAspect {
+        aspect: f64,
+        range: std::ops::RangeInclusive<f64>,
+    },
+
+    #[error("stdout is not a terminal; run tui48 from an interactive terminal")]
+    StdoutNotATerminal,
+
+    #[error("stdin is not a terminal; run tui48 from an interactive terminal")]
+    StdinNotATerminal,
+}
+
+/// Names the terminal operation an error from a `Renderer` or an `EventSource` came from, which
+/// the error itself rarely does.
+pub(crate) trait TerminalContext<T> {
+    fn during(self, operation: crate::tui::renderer::TerminalOperation) -> Result<T>;
+}
 
-    #[error("terminal too small, required minimum size {0} x {1}")]
-    TerminalTooSmall(usize, usize),
+impl<T> TerminalContext<T> for crate::tui::error::Result<T> {
+    fn during(self, operation: crate::tui::renderer::TerminalOperation) -> Result<T> {
+        self.map_err(|source| Error::TerminalFailed {
+            operation,
+            source: Box::new(source),
+        })
+    }
 }
@@ -0,0 +1,606 @@
+//! Exporting the frames the game draws as PNG images, for writing about the game without taking
+//! screenshots of the terminal. Frames are captured as the canvas shows them and encoded on the
+//! persistence worker, along with a manifest of when each was drawn for assembling them into an
+//! animation.
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, SystemClock};
+use crate::persist::{FrameSink, PersistEvent, PersistenceHandle};
+use crate::tui::canvas::{Canvas, CellRender};
+use crate::tui::colors::Rgb;
+use crate::tui::error::Result;
+use crate::tui::renderer::Renderer;
+use crate::tui::tuxel::WIDE_CONTINUATION;
+
+mod font;
+
+/// The width in pixels each cell of the canvas is drawn at.
+pub(crate) const CELL_WIDTH: usize = 6;
+
+/// The height in pixels each cell of the canvas is drawn at, about twice the width like the
+/// cells of most terminal fonts.
+pub(crate) const CELL_HEIGHT: usize = 10;
+
+/// The row of the cell glyphs start at, which leaves room below them for the line gap.
+const GLYPH_TOP: usize = 1;
+
+/// The colors of cells that don't set their own, those of a dark terminal.
+const DEFAULT_FOREGROUND: [u8; 3] = [204, 204, 204];
+const DEFAULT_BACKGROUND: [u8; 3] = [0, 0, 0];
+
+/// The name of the file listing the exported frames.
+const MANIFEST: &str = "manifest.json";
+
+/// A frame as the canvas showed it, to be drawn as an image.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct CapturedFrame {
+    index: usize,
+    // since the first frame
+    elapsed: Duration,
+    width: usize,
+    height: usize,
+    cells: Vec<CellRender>,
+}
+
+impl CapturedFrame {
+    fn capture(c: &Canvas, index: usize, elapsed: Duration) -> Result<Self> {
+        let (width, height) = c.dimensions();
+        let mut cells = Vec::with_capacity(width * height);
+        c.for_each_row(|_, row| {
+            cells.extend_from_slice(row);
+            Ok(())
+        })?;
+        Ok(Self {
+            index,
+            elapsed,
+            width,
+            height,
+            cells,
+        })
+    }
+
+    /// The position of the frame among those exported, counting from 0.
+    pub(crate) fn index(&self) -> usize {
+        self.index
+    }
+
+    fn file_name(&self) -> String {
+        format!("frame-{:06}.png", self.index)
+    }
+
+    /// Draws the frame, returning its width and height in pixels and three bytes per pixel, row
+    /// by row.
+    fn rasterize(&self) -> (usize, usize, Vec<u8>) {
+        let (width, height) = (self.width * CELL_WIDTH, self.height * CELL_HEIGHT);
+        let mut pixels = vec![0; width * height * 3];
+        for (i, cell) in self.cells.iter().enumerate() {
+            let (left, top) = (i % self.width * CELL_WIDTH, i / self.width * CELL_HEIGHT);
+            let (fg, bg) = cell.colors();
+            let fg = fg.as_ref().map_or(DEFAULT_FOREGROUND, rgb);
+            let bg = bg.as_ref().map_or(DEFAULT_BACKGROUND, rgb);
+            let mut set = |x: usize, y: usize, color: [u8; 3]| {
+                let at = ((top + y) * width + left + x) * 3;
+                pixels[at..at + 3].copy_from_slice(&color);
+            };
+            for y in 0..CELL_HEIGHT {
+                for x in 0..CELL_WIDTH {
+                    set(x, y, bg);
+                }
+            }
+            let c = match cell.content() {
+                None | Some(' ') | Some(WIDE_CONTINUATION) => continue,
+                Some(c) => c,
+            };
+            if let Some(arms) = font::box_arms(c) {
+                let (middle_x, middle_y) = (CELL_WIDTH / 2, CELL_HEIGHT / 2);
+                for x in 0..CELL_WIDTH {
+                    if (arms.left && x <= middle_x) || (arms.right && x >= middle_x) {
+                        set(x, middle_y, fg);
+                    }
+                }
+                for y in 0..CELL_HEIGHT {
+                    if (arms.up && y <= middle_y) || (arms.down && y >= middle_y) {
+                        set(middle_x, y, fg);
+                    }
+                }
+                continue;
+            }
+            // anything the font has no glyph for is drawn as a block
+            let glyph = font::glyph(c);
+            for y in 0..font::GLYPH_HEIGHT {
+                for x in 0..font::GLYPH_WIDTH {
+                    if glyph.as_ref().is_none_or(|glyph| glyph(x, y)) {
+                        set(x, GLYPH_TOP + y, fg);
+                    }
+                }
+            }
+        }
+        (width, height, pixels)
+    }
+
+    /// Writes the frame as a PNG image to the given writer.
+    fn encode(&self, w: impl Write) -> std::io::Result<()> {
+        let (width, height, pixels) = self.rasterize();
+        let mut encoder = png::Encoder::new(w, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&pixels)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+fn rgb(color: &Rgb) -> [u8; 3] {
+    [color.r(), color.g(), color.b()]
+}
+
+/// A Renderer that captures every frame drawn and hands it to the persistence worker to export,
+/// see `FrameDirectory`. It doesn't collect the canvas' changes but reads it whole, so it can be
+/// paired with a renderer that does with a `TeeRenderer`.
+pub(crate) struct FrameExportRenderer {
+    persistence: PersistenceHandle,
+    clock: Box<dyn Clock>,
+    started: Option<Instant>,
+    // the number of frames exported so far
+    frames: usize,
+    in_frame: bool,
+    // the last canvas drawn in the current frame
+    captured: Option<CapturedFrame>,
+    size: (u16, u16),
+}
+
+impl FrameExportRenderer {
+    pub(crate) fn new(persistence: PersistenceHandle) -> Self {
+        Self::with_clock(persistence, SystemClock)
+    }
+
+    fn with_clock(persistence: PersistenceHandle, clock: impl Clock + 'static) -> Self {
+        Self {
+            persistence,
+            clock: Box::new(clock),
+            started: None,
+            frames: 0,
+            in_frame: false,
+            captured: None,
+            size: (0, 0),
+        }
+    }
+
+    fn capture(&mut self, c: &Canvas) -> Result<()> {
+        let now = self.clock.now();
+        let elapsed = now - *self.started.get_or_insert(now);
+        self.captured = Some(CapturedFrame::capture(c, self.frames, elapsed)?);
+        let (width, height) = c.dimensions();
+        self.size = (width as u16, height as u16);
+        if !self.in_frame {
+            self.export();
+        }
+        Ok(())
+    }
+
+    fn export(&mut self) {
+        if let Some(frame) = self.captured.take() {
+            self.persistence.submit(PersistEvent::Frame(frame));
+            self.frames += 1;
+        }
+    }
+}
+
+impl Renderer for FrameExportRenderer {
+    /// The size of the canvas drawn last, having no terminal of its own.
+    fn size_hint(&self) -> Result<(u16, u16)> {
+        Ok(self.size)
+    }
+
+    fn render(&mut self, c: &Canvas) -> Result<()> {
+        self.capture(c)
+    }
+
+    fn repaint(&mut self, c: &Canvas) -> Result<()> {
+        self.capture(c)
+    }
+
+    /// The next frame is captured whole, so there's nothing to clear.
+    fn clear(&mut self, _c: &Canvas) -> Result<()> {
+        Ok(())
+    }
+
+    fn begin_frame(&mut self) -> Result<()> {
+        self.in_frame = true;
+        Ok(())
+    }
+
+    fn end_frame(&mut self) -> Result<()> {
+        self.in_frame = false;
+        self.export();
+        Ok(())
+    }
+
+    fn recover(&mut self) {}
+}
+
+/// A Renderer drawing every frame with a first renderer and, if there is one, a second renderer
+/// too, eg to export the frames played on the terminal. The first renderer is asked for the size
+/// and collects the canvas' changes, so the second has to draw from the whole canvas.
+pub(crate) struct TeeRenderer<A, B> {
+    first: A,
+    second: Option<B>,
+}
+
+impl<A: Renderer, B: Renderer> TeeRenderer<A, B> {
+    pub(crate) fn new(first: A, second: Option<B>) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<A: Renderer, B: Renderer> Renderer for TeeRenderer<A, B> {
+    fn size_hint(&self) -> Result<(u16, u16)> {
+        self.first.size_hint()
+    }
+
+    fn render(&mut self, c: &Canvas) -> Result<()> {
+        self.first.render(c)?;
+        self.second
+            .as_mut()
+            .map_or(Ok(()), |second| second.render(c))
+    }
+
+    fn repaint(&mut self, c: &Canvas) -> Result<()> {
+        self.first.repaint(c)?;
+        self.second
+            .as_mut()
+            .map_or(Ok(()), |second| second.repaint(c))
+    }
+
+    fn clear(&mut self, c: &Canvas) -> Result<()> {
+        self.first.clear(c)?;
+        self.second
+            .as_mut()
+            .map_or(Ok(()), |second| second.clear(c))
+    }
+
+    fn begin_frame(&mut self) -> Result<()> {
+        self.first.begin_frame()?;
+        let begun = self
+            .second
+            .as_mut()
+            .map_or(Ok(()), |second| second.begin_frame());
+        // a frame that fails to begin isn't ended, so the first renderer's is ended here
+        if begun.is_err() {
+            let _ = self.first.end_frame();
+        }
+        begun
+    }
+
+    fn end_frame(&mut self) -> Result<()> {
+        let ended = self.first.end_frame();
+        let second = self
+            .second
+            .as_mut()
+            .map_or(Ok(()), |second| second.end_frame());
+        ended.and(second)
+    }
+
+    fn recover(&mut self) {
+        self.first.recover();
+        if let Some(second) = &mut self.second {
+            second.recover();
+        }
+    }
+
+    fn set_title(&mut self, title: &str) -> Result<()> {
+        self.first.set_title(title)?;
+        self.second
+            .as_mut()
+            .map_or(Ok(()), |second| second.set_title(title))
+    }
+}
+
+/// Writes exported frames to a directory as numbered PNG images, with a manifest listing each
+/// one and when it was drawn, in milliseconds since the first.
+pub(crate) struct FrameDirectory {
+    dir: PathBuf,
+    // the name of every frame written and when it was drawn
+    written: Vec<(String, Duration)>,
+}
+
+impl FrameDirectory {
+    /// Creates the given directory if need be.
+    pub(crate) fn create(dir: PathBuf) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            written: Vec::new(),
+        })
+    }
+
+    fn manifest(&self) -> String {
+        let frames: Vec<String> = self
+            .written
+            .iter()
+            .map(|(name, elapsed)| {
+                format!(
+                    "    {{ \"file\": \"{}\", \"ms\": {} }}",
+                    name,
+                    elapsed.as_millis()
+                )
+            })
+            .collect();
+        format!(
+            "{{\n  \"cell_width\": {},\n  \"cell_height\": {},\n  \"frames\": [\n{}\n  ]\n}}\n",
+            CELL_WIDTH,
+            CELL_HEIGHT,
+            frames.join(",\n")
+        )
+    }
+}
+
+impl FrameSink for FrameDirectory {
+    fn write(&mut self, frame: &CapturedFrame) -> std::io::Result<()> {
+        let name = frame.file_name();
+        let mut w = BufWriter::new(File::create(self.dir.join(&name))?);
+        frame.encode(&mut w)?;
+        w.flush()?;
+        self.written.push((name, frame.elapsed));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        std::fs::write(self.dir.join(MANIFEST), self.manifest())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::engine::board::{Board, BoardConfig};
+    use crate::error::Result;
+    use crate::persist::Sinks;
+    use crate::tui::canvas::Modifier;
+    use crate::tui::drawbuffer::{DrawBufferOwner, Owner};
+    use crate::tui::error::Result as TuiResult;
+    use crate::tui::events::{Event, UserInput};
+    use crate::tui::geometry::{Bounds2D, Direction, Idx, Rectangle};
+    use crate::tui::testing::{MockEventSource, TestRenderer};
+    use crate::tui48::{init, Tui48};
+
+    /// Decodes the given PNG image into its width, height and pixels.
+    fn decode(png: &[u8]) -> (usize, usize, Vec<u8>) {
+        let decoder = png::Decoder::new(png);
+        let mut reader = decoder.read_info().expect("the image should be a PNG");
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut pixels)
+            .expect("the image should have a frame");
+        assert_eq!(info.color_type, png::ColorType::Rgb);
+        (info.width as usize, info.height as usize, pixels)
+    }
+
+    #[test]
+    fn a_frame_is_drawn_cell_by_cell() -> Result<()> {
+        let canvas = Canvas::new(4, 2);
+        let r = Rectangle(Idx(0, 0, 0), Bounds2D(3, 1));
+        let mut dbuf = canvas.get_draw_buffer(r, Owner::Named("test"))?;
+        {
+            let mut transaction = dbuf.begin_transaction();
+            for (x, c) in "H\u{2500}\u{2603}".chars().enumerate() {
+                transaction.set_content(x, 0, c);
+            }
+        }
+        dbuf.modify(Modifier::SetForegroundColor(255, 0, 0));
+        dbuf.modify(Modifier::SetBackgroundColor(0, 0, 255));
+        let frame = CapturedFrame::capture(&canvas, 0, Duration::ZERO)?;
+        let mut png = Vec::new();
+        frame.encode(&mut png)?;
+
+        let (width, height, pixels) = decode(&png);
+        assert_eq!((width, height), (4 * CELL_WIDTH, 2 * CELL_HEIGHT));
+        let pixel = |x: usize, y: usize| {
+            let at = (y * width + x) * 3;
+            [pixels[at], pixels[at + 1], pixels[at + 2]]
+        };
+        let (red, blue) = ([255, 0, 0], [0, 0, 255]);
+        // the left and right strokes of the H, with the gap between them
+        assert_eq!(pixel(0, GLYPH_TOP), red);
+        assert_eq!(pixel(4, GLYPH_TOP), red);
+        assert_eq!(pixel(2, GLYPH_TOP), blue);
+        // the crossbar, and the spacing column right of the glyph
+        assert_eq!(pixel(2, GLYPH_TOP + 3), red);
+        assert_eq!(pixel(5, GLYPH_TOP + 3), blue);
+        // the line runs across the whole cell, and nowhere else
+        assert_eq!(pixel(CELL_WIDTH, CELL_HEIGHT / 2), red);
+        assert_eq!(pixel(2 * CELL_WIDTH - 1, CELL_HEIGHT / 2), red);
+        assert_eq!(pixel(CELL_WIDTH, CELL_HEIGHT / 2 + 1), blue);
+        // a snowman isn't in the font, so it's a block
+        assert_eq!(pixel(2 * CELL_WIDTH, GLYPH_TOP), red);
+        assert_eq!(pixel(2 * CELL_WIDTH + 4, GLYPH_TOP + 6), red);
+        assert_eq!(pixel(2 * CELL_WIDTH, 0), blue);
+        // the cells left alone are in the terminal's colors
+        assert_eq!(pixel(3 * CELL_WIDTH, 0), DEFAULT_BACKGROUND);
+        assert_eq!(pixel(0, 2 * CELL_HEIGHT - 1), DEFAULT_BACKGROUND);
+        Ok(())
+    }
+
+    /// A Renderer that notes every call made on it.
+    struct CallRecorder {
+        name: &'static str,
+        calls: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl CallRecorder {
+        fn note(&self, call: &str) {
+            self.calls
+                .borrow_mut()
+                .push(format!("{} {}", self.name, call));
+        }
+    }
+
+    impl Renderer for CallRecorder {
+        fn size_hint(&self) -> TuiResult<(u16, u16)> {
+            self.note("size_hint");
+            Ok((80, 24))
+        }
+
+        fn render(&mut self, _c: &Canvas) -> TuiResult<()> {
+            self.note("render");
+            Ok(())
+        }
+
+        fn repaint(&mut self, _c: &Canvas) -> TuiResult<()> {
+            self.note("repaint");
+            Ok(())
+        }
+
+        fn clear(&mut self, _c: &Canvas) -> TuiResult<()> {
+            self.note("clear");
+            Ok(())
+        }
+
+        fn begin_frame(&mut self) -> TuiResult<()> {
+            self.note("begin_frame");
+            Ok(())
+        }
+
+        fn end_frame(&mut self) -> TuiResult<()> {
+            self.note("end_frame");
+            Ok(())
+        }
+
+        fn recover(&mut self) {
+            self.note("recover");
+        }
+
+        fn set_title(&mut self, title: &str) -> TuiResult<()> {
+            self.note(&format!("set_title {}", title));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn a_tee_forwards_every_call_to_both_renderers() -> TuiResult<()> {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let recorder = |name| CallRecorder {
+            name,
+            calls: calls.clone(),
+        };
+        let mut tee = TeeRenderer::new(recorder("first"), Some(recorder("second")));
+        let canvas = Canvas::new(2, 2);
+        assert_eq!(tee.size_hint()?, (80, 24));
+        tee.begin_frame()?;
+        tee.render(&canvas)?;
+        tee.repaint(&canvas)?;
+        tee.clear(&canvas)?;
+        tee.end_frame()?;
+        tee.set_title("t")?;
+        tee.recover();
+
+        let expected: Vec<String> = [
+            "first size_hint",
+            "first begin_frame",
+            "second begin_frame",
+            "first render",
+            "second render",
+            "first repaint",
+            "second repaint",
+            "first clear",
+            "second clear",
+            "first end_frame",
+            "second end_frame",
+            "first set_title t",
+            "second set_title t",
+            "first recover",
+            "second recover",
+        ]
+        .map(String::from)
+        .to_vec();
+        assert_eq!(*calls.borrow(), expected);
+        Ok(())
+    }
+
+    /// Keeps the index and time of every frame written to it.
+    #[derive(Clone, Default)]
+    struct FrameRecorder {
+        frames: Arc<Mutex<Vec<(usize, Duration)>>>,
+    }
+
+    impl FrameSink for FrameRecorder {
+        fn write(&mut self, frame: &CapturedFrame) -> std::io::Result<()> {
+            let mut frames = self.frames.lock().unwrap();
+            frames.push((frame.index, frame.elapsed));
+            Ok(())
+        }
+
+        fn finish(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn every_frame_of_a_game_is_exported_in_order() -> Result<()> {
+        init()?;
+        let recorder = FrameRecorder::default();
+        let persistence = PersistenceHandle::synchronous(Sinks {
+            frames: Some(Box::new(recorder.clone())),
+            ..Sinks::default()
+        });
+        let terminal = TestRenderer::new(100, 50);
+        let frame_count = terminal.frame_count();
+        let renderer = TeeRenderer::new(terminal, Some(FrameExportRenderer::new(persistence)));
+        let events = MockEventSource::new(
+            [Direction::Left, Direction::Down, Direction::Right]
+                .map(|d| Event::UserInput(UserInput::Direction(d))),
+        );
+        Tui48::new(
+            Board::new_seeded(3, BoardConfig::default()),
+            renderer,
+            events,
+        )?
+        .run()?;
+
+        let frames = recorder.frames.lock().unwrap();
+        assert!(frames.len() > 3, "{}", frames.len());
+        assert_eq!(frames.len(), frame_count.get());
+        assert!(frames.iter().enumerate().all(|(n, (index, _))| n == *index));
+        assert_eq!(frames[0].1, Duration::ZERO);
+        assert!(frames.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        assert!(frames.last().unwrap().1 > Duration::ZERO);
+        Ok(())
+    }
+
+    #[test]
+    fn frames_are_written_to_numbered_files_with_a_manifest() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("tui48-frames-{}", std::process::id()));
+        let mut sink = FrameDirectory::create(dir.clone())?;
+        let canvas = Canvas::new(3, 2);
+        for (index, ms) in [(0, 0), (1, 16), (2, 40)] {
+            let frame = CapturedFrame::capture(&canvas, index, Duration::from_millis(ms))?;
+            sink.write(&frame)?;
+        }
+        sink.finish()?;
+
+        let png = std::fs::read(dir.join("frame-000002.png"))?;
+        assert_eq!(decode(&png).0, 3 * CELL_WIDTH);
+        let manifest = std::fs::read_to_string(dir.join(MANIFEST))?;
+        std::fs::remove_dir_all(&dir)?;
+        let expected = [
+            r#"{ "file": "frame-000000.png", "ms": 0 }"#,
+            r#"{ "file": "frame-000001.png", "ms": 16 }"#,
+            r#"{ "file": "frame-000002.png", "ms": 40 }"#,
+        ];
+        let listed: Vec<&str> = manifest
+            .lines()
+            .map(|line| line.trim().trim_end_matches(','))
+            .filter(|line| line.starts_with("{ \"file\""))
+            .collect();
+        assert_eq!(listed, expected, "{}", manifest);
+        Ok(())
+    }
+}
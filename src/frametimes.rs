@@ -0,0 +1,287 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, SystemClock};
+
+/// Frames taking longer than this to animate and render can't keep up with 60 frames a second.
+pub(crate) const FRAME_BUDGET: Duration = Duration::from_millis(16);
+
+/// How many of the most recent frames are kept for the report.
+const ROLLING_FRAMES: usize = 4096;
+
+/// What one animation frame cost.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FrameSample {
+    /// Time spent advancing the animation state.
+    pub(crate) animate: Duration,
+    /// Number of canvas cells the frame changed.
+    pub(crate) changed: usize,
+    /// Time spent handing the changed cells to the renderer.
+    pub(crate) render: Duration,
+}
+
+impl FrameSample {
+    fn total(&self) -> Duration {
+        self.animate + self.render
+    }
+}
+
+struct FrameTimes {
+    clock: Box<dyn Clock>,
+    samples: VecDeque<FrameSample>,
+}
+
+/// Times animation frames as they are played. Clones share the same samples, so a handle kept by
+/// the caller can report on the frames of a game that has since been consumed.
+#[derive(Clone)]
+pub(crate) struct FrameTimer {
+    times: Option<Rc<RefCell<FrameTimes>>>,
+}
+
+impl FrameTimer {
+    pub(crate) fn new() -> Self {
+        Self::with_clock(SystemClock)
+    }
+
+    /// A timer that records nothing.
+    pub(crate) fn disabled() -> Self {
+        Self { times: None }
+    }
+
+    pub(crate) fn with_clock(clock: impl Clock + 'static) -> Self {
+        Self {
+            times: Some(Rc::new(RefCell::new(FrameTimes {
+                clock: Box::new(clock),
+                samples: VecDeque::new(),
+            }))),
+        }
+    }
+
+    /// The current time according to the timer's clock, or None if the timer is disabled.
+    pub(crate) fn now(&self) -> Option<Instant> {
+        self.times.as_ref().map(|times| times.borrow().clock.now())
+    }
+
+    /// Records a frame, forgetting the oldest one once the window is full.
+    pub(crate) fn record(&self, sample: FrameSample) {
+        if let Some(times) = &self.times {
+            let samples = &mut times.borrow_mut().samples;
+            if samples.len() == ROLLING_FRAMES {
+                samples.pop_front();
+            }
+            samples.push_back(sample);
+        }
+    }
+
+    /// Summarizes the recorded frames.
+    pub(crate) fn report(&self) -> FrameReport {
+        let samples: Vec<FrameSample> = self
+            .times
+            .as_ref()
+            .map(|times| times.borrow().samples.iter().cloned().collect())
+            .unwrap_or_default();
+        FrameReport::new(&samples, FRAME_BUDGET)
+    }
+}
+
+/// The median, 95th percentile and maximum of one phase of the recorded frames.
+#[derive(Debug, PartialEq)]
+pub(crate) struct PhaseStats<T> {
+    pub(crate) p50: T,
+    pub(crate) p95: T,
+    pub(crate) max: T,
+}
+
+impl<T: Copy + Ord> PhaseStats<T> {
+    /// Returns None if there are no values to summarize.
+    fn new(mut values: Vec<T>) -> Option<Self> {
+        values.sort();
+        let max = *values.last()?;
+        // nearest rank, so every statistic is a value that was actually recorded
+        let rank = |p: usize| values[(values.len() * p).div_ceil(100).max(1) - 1];
+        Some(Self {
+            p50: rank(50),
+            p95: rank(95),
+            max,
+        })
+    }
+}
+
+/// A summary of the recorded frames, broken down by phase.
+#[derive(Debug, PartialEq)]
+pub(crate) struct FrameReport {
+    pub(crate) frames: usize,
+    pub(crate) budget: Duration,
+    pub(crate) over_budget: usize,
+    pub(crate) animate: Option<PhaseStats<Duration>>,
+    pub(crate) changed: Option<PhaseStats<usize>>,
+    pub(crate) render: Option<PhaseStats<Duration>>,
+}
+
+impl FrameReport {
+    fn new(samples: &[FrameSample], budget: Duration) -> Self {
+        Self {
+            frames: samples.len(),
+            budget,
+            over_budget: samples.iter().filter(|s| s.total() > budget).count(),
+            animate: PhaseStats::new(samples.iter().map(|s| s.animate).collect()),
+            changed: PhaseStats::new(samples.iter().map(|s| s.changed).collect()),
+            render: PhaseStats::new(samples.iter().map(|s| s.render).collect()),
+        }
+    }
+}
+
+fn millis(d: &Duration) -> String {
+    format!("{:.2}ms", d.as_secs_f64() * 1000.0)
+}
+
+impl std::fmt::Display for FrameReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let (animate, changed, render) = match (&self.animate, &self.changed, &self.render) {
+            (Some(animate), Some(changed), Some(render)) => (animate, changed, render),
+            _ => return writeln!(f, "no animation frames were rendered"),
+        };
+        writeln!(
+            f,
+            "{} frames, {} over the {} budget",
+            self.frames,
+            self.over_budget,
+            millis(&self.budget)
+        )?;
+        writeln!(f, "{:<8}{:>10}{:>10}{:>10}", "phase", "p50", "p95", "max")?;
+        for (phase, stats) in [("animate", animate), ("render", render)] {
+            writeln!(
+                f,
+                "{:<8}{:>10}{:>10}{:>10}",
+                phase,
+                millis(&stats.p50),
+                millis(&stats.p95),
+                millis(&stats.max)
+            )?;
+        }
+        writeln!(
+            f,
+            "{:<8}{:>10}{:>10}{:>10}",
+            "cells", changed.p50, changed.p95, changed.max
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+    use crate::clock::FakeClock;
+
+    fn sample(animate_ms: u64, changed: usize, render_ms: u64) -> FrameSample {
+        FrameSample {
+            animate: Duration::from_millis(animate_ms),
+            changed,
+            render: Duration::from_millis(render_ms),
+        }
+    }
+
+    #[test]
+    fn report_aggregates_each_phase() {
+        let timer = FrameTimer::with_clock(FakeClock::new());
+        // recorded out of order to make sure the statistics don't depend on it
+        for ms in (1..=20).rev() {
+            timer.record(sample(1, ms as usize * 10, ms));
+        }
+        let report = timer.report();
+        assert_eq!(report.frames, 20);
+        // 16ms through 20ms of rendering plus 1ms of animating
+        assert_eq!(report.over_budget, 5);
+        assert_eq!(
+            report.render,
+            Some(PhaseStats {
+                p50: Duration::from_millis(10),
+                p95: Duration::from_millis(19),
+                max: Duration::from_millis(20),
+            })
+        );
+        assert_eq!(
+            report.changed,
+            Some(PhaseStats {
+                p50: 100,
+                p95: 190,
+                max: 200,
+            })
+        );
+        let one = Duration::from_millis(1);
+        assert_eq!(
+            report.animate,
+            Some(PhaseStats {
+                p50: one,
+                p95: one,
+                max: one,
+            })
+        );
+    }
+
+    #[rstest]
+    #[case::one(&[7], (7, 7, 7))]
+    #[case::two(&[7, 3], (3, 7, 7))]
+    fn percentiles_of_few_frames_are_recorded_values(
+        #[case] changed: &[usize],
+        #[case] expected: (usize, usize, usize),
+    ) {
+        let timer = FrameTimer::new();
+        for c in changed {
+            timer.record(sample(0, *c, 0));
+        }
+        let stats = timer.report().changed.expect("frames were recorded");
+        assert_eq!((stats.p50, stats.p95, stats.max), expected);
+    }
+
+    #[test]
+    fn timer_keeps_only_the_most_recent_frames() {
+        let timer = FrameTimer::new();
+        for changed in 0..ROLLING_FRAMES + 10 {
+            timer.record(sample(0, changed, 0));
+        }
+        let report = timer.report();
+        assert_eq!(report.frames, ROLLING_FRAMES);
+        assert_eq!(report.changed.map(|s| s.max), Some(ROLLING_FRAMES + 9));
+    }
+
+    #[rstest]
+    #[case::new(FrameTimer::new())]
+    #[case::disabled(FrameTimer::disabled())]
+    fn report_without_frames_says_so(#[case] timer: FrameTimer) {
+        let report = timer.report();
+        assert_eq!(report.frames, 0);
+        assert_eq!(report.over_budget, 0);
+        assert_eq!(report.render, None);
+        assert_eq!(report.to_string(), "no animation frames were rendered\n");
+    }
+
+    #[test]
+    fn disabled_timer_records_nothing() {
+        let timer = FrameTimer::disabled();
+        assert_eq!(timer.now(), None);
+        timer.record(sample(1, 1, 1));
+        assert_eq!(timer.report().frames, 0);
+    }
+
+    #[test]
+    fn report_lists_every_phase() {
+        let timer = FrameTimer::new();
+        timer.record(sample(1, 240, 2));
+        let text = timer.report().to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "1 frames, 0 over the 16.00ms budget");
+        assert_eq!(
+            lines[1..],
+            [
+                "phase          p50       p95       max",
+                "animate     1.00ms    1.00ms    1.00ms",
+                "render      2.00ms    2.00ms    2.00ms",
+                "cells          240       240       240",
+            ]
+        );
+    }
+}
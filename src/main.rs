@@ -1,33 +1,229 @@
 use std::io::stdout;
+use std::path::PathBuf;
 
 use anyhow::Result;
 use clap::Parser;
-use rand::thread_rng;
-
+use rand::{thread_rng, Rng};
+mod bell;
+mod bench;
+mod clock;
+mod config;
 mod engine;
 mod error;
+mod export;
+mod frametimes;
+mod migrate;
+mod milestones;
+mod outlook;
+mod packs;
+mod paths;
+mod persist;
+mod prefs;
+mod quality;
+mod scripts;
+mod session;
+mod startup;
+mod themes;
 mod tui;
 mod tui48;
 
-use engine::board::Board;
-use tui::crossterm::{Crossterm, CrosstermEvents};
-use tui48::{init, Tui48};
+use config::GameConfig;
+use engine::board::BoardConfig;
+use engine::practice::Profile;
+use engine::strategy::Strategy;
+use frametimes::FrameTimer;
+use packs::PackChoice;
+use quality::{AnimationSpeed, EasingFn, QualityLevel};
+use startup::Ttys;
+use tui::canvas::Canvas;
+use tui::capabilities::SyncMode;
+use tui::crossterm::CrosstermEvents;
+use tui48::mirror::MirrorMatch;
+use tui48::{canvas_depth, init, set_strict_checks, Assist, LaunchOptions, Mode, Tui48};
 
 #[derive(Debug, Parser)]
 struct Cli {
     #[clap(flatten)]
     verbose: clap_verbosity_flag::Verbosity,
+
+    /// Deal new tiles from a random number generator seeded with the given seed, so that the same
+    /// moves play out the same game. The seed is shown next to the score for sharing.
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Show an estimate of the chance of reaching 2048, computed on a background thread.
+    #[arg(long)]
+    outlook: bool,
+
+    /// Briefly flash the edge of the screen on invalid moves and when the game ends.
+    #[arg(long)]
+    visual_bell: bool,
+
+    /// Point things out on the board after every move without suggesting the move itself.
+    #[arg(long, value_enum)]
+    assist: Option<Assist>,
+
+    /// Briefly list the points each merge scored after moves that merge several pairs.
+    #[arg(long)]
+    score_breakdown: bool,
+
+    /// Start every game from a synthesized position to practice rather than an empty board.
+    #[arg(long, value_enum)]
+    practice: Option<Profile>,
+
+    /// Play on a board the given number of slots across and down, eg 5x5, rather than the
+    /// classic 4x4. Each side may be from 3 to 8 slots.
+    #[arg(long, value_name = "NxM")]
+    size: Option<BoardConfig>,
+
+    /// Play by the rules of the given mode: classic, arcade where merging tiles earns power-ups
+    /// used with the space bar, or growth where the board starts 3x3, unless another size is
+    /// given, and grows as the score reaches milestones.
+    #[arg(long, value_enum)]
+    mode: Option<Mode>,
+
+    /// Label tiles with one of the built-in label packs (numbers, letters or elements) or with
+    /// the pack in the given TOML file. Defaults to the pack last switched to while playing.
+    #[arg(long)]
+    pack: Option<PackChoice>,
+
+    /// Animate moves at the given quality, from full down to instant, rather than making
+    /// animations coarser whenever the terminal can't keep up with them.
+    #[arg(long, value_enum)]
+    animation_quality: Option<QualityLevel>,
+
+    /// Animate moves fast, at normal speed, slowly, or not at all, showing every move in one go
+    /// whatever the animation quality.
+    #[arg(long, value_enum)]
+    animation_speed: Option<AnimationSpeed>,
+
+    /// Slide tiles at a steady pace (linear), or starting slow, arriving slow, or both, covering
+    /// every slide in as many frames as the cells it crosses whatever the curve.
+    #[arg(long, value_enum)]
+    easing: Option<EasingFn>,
+
+    /// Lay tiles out wide enough to look square, where they otherwise look taller than wide.
+    /// Defaults to the preference saved in the preferences file.
+    #[arg(long)]
+    square_tiles: bool,
+
+    /// How many times as tall as they are wide the terminal's cells are, which square tiles are
+    /// sized for. Defaults to 2, which suits most fonts.
+    #[arg(long)]
+    cell_aspect: Option<f64>,
+
+    /// How long the computer waits between its moves while it plays for you, which a turns on
+    /// and off, in milliseconds. Defaults to 200.
+    #[arg(long, value_name = "MS")]
+    ai_delay_ms: Option<u64>,
+
+    /// Leave the terminal's window title alone rather than showing the score in it. Otherwise the
+    /// title the terminal had is saved and put back on exit, for terminals that can.
+    #[arg(long)]
+    no_title: bool,
+
+    /// Read settings from the given TOML file rather than the default config file. Flags given
+    /// on the command line take precedence over it.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Rather than starting a game, play a scripted set of moves and print how long the
+    /// terminal took to animate them.
+    #[arg(long)]
+    bench_animation: bool,
+
+    /// Rather than starting a game, race a computer opponent playing the given strategy on a
+    /// board beside yours that is dealt the same tiles.
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "greedy")]
+    mirror: Option<Strategy>,
+
+    /// Build the position the first game starts from in the board editor. The editor can be
+    /// opened during a game with E too, and writes positions down to position.txt in the state
+    /// directory with w.
+    #[arg(long)]
+    edit: bool,
+
+    /// Whether to wrap frames in synchronized updates, which some older terminals garble: auto
+    /// asks the terminal whether it supports them. The TUI48_SYNCHRONIZED_UPDATES environment
+    /// variable can force them on or off too, for when the flag isn't given.
+    #[arg(long, value_enum, default_value_t = SyncMode::Auto)]
+    synchronized_updates: SyncMode,
+
+    /// Save every frame drawn as a PNG image in the given directory, with a manifest.json
+    /// listing them and when each was drawn, eg for putting together an animation of a game.
+    #[arg(long, value_name = "DIR")]
+    export_frames: Option<PathBuf>,
+
+    /// Panic as soon as the board turns out to disagree with the game, rather than only logging
+    /// it. Only has an effect on debug builds.
+    #[arg(long, hide = true)]
+    strict: bool,
+}
+
+impl Cli {
+    /// Overrides the settings read from the config file with the flags given on the command line.
+    fn apply(&self, config: &mut GameConfig) {
+        config.seed = self.seed.or(config.seed);
+        config.outlook |= self.outlook;
+        config.visual_bell |= self.visual_bell;
+        config.assist = self.assist.or(config.assist);
+        config.score_breakdown |= self.score_breakdown;
+        config.no_title |= self.no_title;
+        config.practice = self.practice.or(config.practice);
+        config.size = self.size.or(config.size);
+        config.mode = self.mode.or(config.mode);
+        config.pack = self.pack.clone().or(config.pack.take());
+        config.animation_quality = self.animation_quality.or(config.animation_quality);
+        config.animation_speed = self.animation_speed.or(config.animation_speed);
+        config.easing = self.easing.or(config.easing);
+        config.square_tiles |= self.square_tiles;
+        config.cell_aspect = self.cell_aspect.or(config.cell_aspect);
+        config.ai_delay_ms = self.ai_delay_ms.or(config.ai_delay_ms);
+    }
+}
+
+/// Times the animation of a scripted set of moves on the terminal and prints a summary.
+fn bench_animation(sync: SyncMode) -> Result<()> {
+    startup::validate(Ttys::detect())?;
+    init()?;
+    let (canvas, renderer) = Canvas::new_from_writer(stdout(), canvas_depth(false))?;
+    // nothing reads key presses during the benchmark
+    let renderer = renderer.detect_capabilities(sync, &CrosstermEvents::default());
+    let capabilities = renderer.capabilities();
+    let report = bench::run(canvas, renderer, FrameTimer::new())?;
+    // the terminal has been restored, so the report ends up in the scrollback
+    print!("{}", report);
+    println!("{}", capabilities);
+    Ok(())
+}
+
+/// Plays mirror matches against the given strategy, the first dealt from the given seed if any,
+/// and prints how the last one ended.
+fn mirror_match(strategy: Strategy, seed: Option<u64>, sync: SyncMode) -> Result<()> {
+    startup::validate(Ttys::detect())?;
+    init()?;
+    let (_, renderer) = Canvas::new_from_writer(stdout(), canvas_depth(false))?;
+    let events = CrosstermEvents::default();
+    let renderer = renderer.detect_capabilities(sync, &events);
+    let seed = seed.unwrap_or_else(|| thread_rng().gen());
+    let outcome = MirrorMatch::new(seed, strategy, renderer, events)?.run()?;
+    // the terminal has been restored, so the outcome ends up in the scrollback
+    if let Some(outcome) = outcome {
+        println!("{}", outcome);
+    }
+    Ok(())
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    set_strict_checks(cli.strict);
+    if cli.bench_animation {
+        return bench_animation(cli.synchronized_updates);
+    }
+    if let Some(strategy) = cli.mirror {
+        return mirror_match(strategy, cli.seed, cli.synchronized_updates);
+    }
 
-    let rng = thread_rng();
-    let board = Board::new(rng);
-    let w = stdout().lock();
-    let renderer = Crossterm::new(Box::new(w))?;
-    let event_source = CrosstermEvents::default();
-    let tui48 = Tui48::new(board, renderer, event_source)?;
     fern::Dispatch::new()
         .format(|out, message, record| {
             out.finish(format_args!(
@@ -38,12 +234,21 @@ fn main() -> Result<()> {
             ))
         })
         .level(cli.verbose.log_level_filter())
-        .chain(fern::log_file("./output.log")?)
+        .chain(fern::log_file(paths::log_file()?)?)
         .apply()?;
 
-    init()?;
+    let config_path = cli.config.clone().unwrap_or_else(paths::config_file);
+    let options = LaunchOptions {
+        edit: cli.edit,
+        synchronized_updates: cli.synchronized_updates,
+        export_frames: cli.export_frames.clone(),
+    };
+    let tui48 = Tui48::new_from_config_file(&config_path, |config| cli.apply(config), options)?;
 
-    tui48.run()?;
+    // the terminal has been restored by the time run returns, so the summary ends up in the
+    // scrollback rather than the alternate screen
+    let session = tui48.run()?;
+    session.write_summary(&mut stdout().lock())?;
 
     Ok(())
 }
@@ -0,0 +1,213 @@
+use crate::error::{Error, Result};
+
+/// The key every versioned file keeps the version of its format under.
+pub(crate) const VERSION_KEY: &str = "version";
+
+/// Upgrades the contents of a file from one version of its format to the next.
+pub(crate) type Migration<T> = fn(&mut T);
+
+/// The top-level object of a JSON file.
+pub(crate) type JsonObject = serde_json::Map<String, serde_json::Value>;
+
+/// The contents of a file, parsed into the table or object at the top of it, which keeps the
+/// version of its format under `VERSION_KEY`.
+pub(crate) trait Versioned {
+    /// The version the contents were written with, None if they have none or how it was written
+    /// if it isn't a version.
+    fn version(&self) -> Option<std::result::Result<u32, String>>;
+
+    fn set_version(&mut self, version: u32);
+}
+
+impl Versioned for toml::Table {
+    fn version(&self) -> Option<std::result::Result<u32, String>> {
+        let value = self.get(VERSION_KEY)?;
+        let version = match value {
+            toml::Value::Integer(v) => u32::try_from(*v).ok(),
+            _ => None,
+        };
+        Some(version.filter(|v| *v >= 1).ok_or_else(|| value.to_string()))
+    }
+
+    fn set_version(&mut self, version: u32) {
+        self.insert(VERSION_KEY.to_string(), i64::from(version).into());
+    }
+}
+
+impl Versioned for JsonObject {
+    fn version(&self) -> Option<std::result::Result<u32, String>> {
+        let value = self.get(VERSION_KEY)?;
+        let version = value.as_u64().and_then(|v| u32::try_from(v).ok());
+        Some(version.filter(|v| *v >= 1).ok_or_else(|| value.to_string()))
+    }
+
+    fn set_version(&mut self, version: u32) {
+        self.insert(VERSION_KEY.to_string(), version.into());
+    }
+}
+
+/// A format the game writes and later reads back, along with the migrations that bring files
+/// written by older releases up to date. Files written before the format was versioned have no
+/// version and count as version 1. Formats are TOML unless they say otherwise.
+pub(crate) struct Format<T: 'static = toml::Table> {
+    pub(crate) name: &'static str,
+    /// The migration at index i upgrades version i + 1 to version i + 2, so every new version of
+    /// the format appends one.
+    pub(crate) migrations: &'static [Migration<T>],
+}
+
+impl<T: Versioned> Format<T> {
+    /// The version files are written with.
+    pub(crate) const fn current_version(&self) -> u32 {
+        self.migrations.len() as u32 + 1
+    }
+
+    /// Brings the contents of a file up to the current version in place and returns the version
+    /// it was written with. The contents of a file written by a newer release are left alone, so
+    /// that what this release doesn't understand can be kept and written back as it was.
+    pub(crate) fn upgrade(&self, contents: &mut T) -> Result<u32> {
+        let version = match contents.version() {
+            None => 1,
+            Some(Ok(version)) => version,
+            Some(Err(found)) => {
+                return Err(Error::InvalidFormatVersion {
+                    format: self.name,
+                    found,
+                })
+            }
+        };
+        let current = self.current_version();
+        if version > current {
+            log::warn!(
+                "{} file is version {} but this release only knows up to version {}; reading what \
+                 it can",
+                self.name,
+                version,
+                current
+            );
+            return Ok(version);
+        }
+        for migration in &self.migrations[version as usize - 1..] {
+            migration(contents);
+        }
+        contents.set_version(current);
+        Ok(version)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::path::PathBuf;
+
+    use rstest::*;
+
+    use super::*;
+
+    /// Returns the fixture files captured for the given format, one per version or variation of
+    /// a version that releases have written.
+    pub(crate) fn fixtures<T>(format: &Format<T>) -> Vec<PathBuf> {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(format.name);
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .unwrap_or_else(|e| panic!("no fixtures in {:?}: {}", dir, e))
+            .map(|entry| entry.expect("fixtures should be listable").path())
+            .collect();
+        paths.sort();
+        assert!(!paths.is_empty(), "no fixtures in {:?}", dir);
+        paths
+    }
+
+    fn rename_color_to_colour(table: &mut toml::Table) {
+        if let Some(color) = table.remove("color") {
+            table.insert("colour".to_string(), color);
+        }
+    }
+
+    fn add_size(table: &mut toml::Table) {
+        table.insert("size".to_string(), 4.into());
+    }
+
+    const EXAMPLE: Format = Format {
+        name: "example",
+        migrations: &[rename_color_to_colour, add_size],
+    };
+
+    fn table(text: &str) -> toml::Table {
+        toml::from_str(text).expect("test tables should parse")
+    }
+
+    #[rstest]
+    #[case::unversioned("color = \"red\"", 1)]
+    #[case::v1("version = 1\ncolor = \"red\"", 1)]
+    #[case::v2("version = 2\ncolour = \"red\"", 2)]
+    #[case::current("version = 3\ncolour = \"red\"\nsize = 4", 3)]
+    fn upgrade_applies_the_migrations_a_file_is_missing(
+        #[case] text: &str,
+        #[case] written_with: u32,
+    ) -> Result<()> {
+        let mut upgraded = table(text);
+        assert_eq!(EXAMPLE.upgrade(&mut upgraded)?, written_with);
+        assert_eq!(upgraded, table("version = 3\ncolour = \"red\"\nsize = 4"));
+        Ok(())
+    }
+
+    #[test]
+    fn upgrade_leaves_files_from_newer_releases_alone() -> Result<()> {
+        let text = "version = 7\ncolour = \"red\"\nshape = \"round\"";
+        let mut upgraded = table(text);
+        assert_eq!(EXAMPLE.upgrade(&mut upgraded)?, 7);
+        assert_eq!(upgraded, table(text));
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::zero("version = 0")]
+    #[case::negative("version = -1")]
+    #[case::text("version = \"2\"")]
+    fn upgrade_refuses_versions_that_cant_be(#[case] text: &str) {
+        match EXAMPLE.upgrade(&mut table(text)) {
+            Err(Error::InvalidFormatVersion { format, .. }) => assert_eq!(format, "example"),
+            other => panic!("expected the version to be refused, got {:?}", other),
+        }
+    }
+
+    fn add_size_json(object: &mut JsonObject) {
+        object.insert("size".to_string(), 4.into());
+    }
+
+    const EXAMPLE_JSON: Format<JsonObject> = Format {
+        name: "example",
+        migrations: &[add_size_json],
+    };
+
+    fn object(text: &str) -> JsonObject {
+        serde_json::from_str(text).expect("test objects should parse")
+    }
+
+    #[rstest]
+    #[case::unversioned(r#"{"colour": "red"}"#, Ok(1))]
+    #[case::current(r#"{"version": 2, "colour": "red", "size": 4}"#, Ok(2))]
+    #[case::zero(r#"{"version": 0}"#, Err("0"))]
+    #[case::fraction(r#"{"version": 1.5}"#, Err("1.5"))]
+    #[case::text(r#"{"version": "2"}"#, Err("\"2\""))]
+    fn json_files_are_upgraded_too(
+        #[case] text: &str,
+        #[case] expected: std::result::Result<u32, &str>,
+    ) {
+        let mut upgraded = object(text);
+        match (EXAMPLE_JSON.upgrade(&mut upgraded), expected) {
+            (Ok(written_with), Ok(expected)) => {
+                assert_eq!(written_with, expected);
+                assert_eq!(
+                    upgraded,
+                    object(r#"{"version": 2, "colour": "red", "size": 4}"#)
+                );
+            }
+            (Err(Error::InvalidFormatVersion { found, .. }), Err(expected)) => {
+                assert_eq!(found, expected)
+            }
+            (upgraded, expected) => panic!("expected {:?}, got {:?}", expected, upgraded),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::engine::round::Score;
+use crate::tui::canvas::Modifier;
+
+/// Scores at which the score box steps to its next accent color.
+const THRESHOLDS: [Score; 6] = [1_000, 5_000, 10_000, 25_000, 50_000, 100_000];
+
+/// Score box background for each tier, starting with the one used before the first milestone.
+const ACCENTS: [(u8, u8, u8); THRESHOLDS.len() + 1] = [
+    (75, 50, 25),
+    (60, 90, 30),
+    (30, 90, 90),
+    (40, 60, 120),
+    (100, 40, 120),
+    (140, 30, 60),
+    (170, 130, 20),
+];
+
+/// Returns the number of milestones the given score has reached.
+pub(crate) fn tier(score: Score) -> usize {
+    THRESHOLDS.iter().take_while(|t| score >= **t).count()
+}
+
+/// Returns the score box accent for the given score.
+pub(crate) fn accent(score: Score) -> Modifier {
+    let (r, g, b) = ACCENTS[tier(score)];
+    Modifier::SetBackgroundColor(r, g, b)
+}
+
+/// Returns the note shown when the given milestone is reached, eg "10,000!".
+pub(crate) fn label(milestone: Score) -> String {
+    let digits = milestone.to_string();
+    let mut label = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            label.push(',');
+        }
+        label.push(digit);
+    }
+    label.push('!');
+    label
+}
+
+/// Tracks which milestones have been announced during a game.
+///
+/// The accent follows the current score, so dropping back below a milestone (eg after an undo)
+/// reverts the color, but a milestone is only ever announced once per game: reaching it again
+/// doesn't announce it a second time.
+#[derive(Debug, Default)]
+pub(crate) struct Milestones {
+    announced: usize,
+}
+
+impl Milestones {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the milestones the given score reaches for the first time this game, lowest first.
+    pub(crate) fn cross(&mut self, score: Score) -> Vec<Score> {
+        let reached = tier(score);
+        if reached <= self.announced {
+            return Vec::new();
+        }
+        let crossed = THRESHOLDS[self.announced..reached].to_vec();
+        self.announced = reached;
+        crossed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+
+    #[rstest]
+    #[case::start(0, 0)]
+    #[case::just_below(999, 0)]
+    #[case::exactly(1_000, 1)]
+    #[case::between(9_999, 2)]
+    #[case::top(100_000, 6)]
+    #[case::beyond(1_000_000, 6)]
+    fn tier_counts_reached_thresholds(#[case] score: Score, #[case] expected: usize) {
+        assert_eq!(tier(score), expected);
+    }
+
+    #[test]
+    fn accent_for_each_tier() {
+        let scores = std::iter::once(0).chain(THRESHOLDS.iter().copied());
+        for (expected, score) in scores.enumerate() {
+            let (r, g, b) = ACCENTS[expected];
+            assert!(
+                accent(score) == Modifier::SetBackgroundColor(r, g, b),
+                "accent at {}",
+                score
+            );
+        }
+        for (i, a) in ACCENTS.iter().enumerate() {
+            assert!(
+                !ACCENTS[i + 1..].contains(a),
+                "tier {} repeats an accent",
+                i
+            );
+        }
+    }
+
+    #[rstest]
+    #[case::thousand(1_000, "1,000!")]
+    #[case::ten_thousand(10_000, "10,000!")]
+    #[case::hundred_thousand(100_000, "100,000!")]
+    #[case::small(500, "500!")]
+    fn label_groups_thousands(#[case] milestone: Score, #[case] expected: &str) {
+        assert_eq!(label(milestone), expected);
+    }
+
+    #[test]
+    fn crossing_is_announced_once() {
+        let mut milestones = Milestones::new();
+        assert!(milestones.cross(999).is_empty());
+        assert_eq!(milestones.cross(1_004), vec![1_000]);
+        assert!(milestones.cross(1_500).is_empty());
+    }
+
+    #[test]
+    fn multi_threshold_jump_announces_each_in_order() {
+        let mut milestones = Milestones::new();
+        assert_eq!(milestones.cross(1_200), vec![1_000]);
+        assert_eq!(milestones.cross(12_000), vec![5_000, 10_000]);
+    }
+
+    #[test]
+    fn dropping_back_reverts_the_accent_without_reannouncing() {
+        let mut milestones = Milestones::new();
+        assert_eq!(milestones.cross(5_100), vec![1_000, 5_000]);
+
+        // eg undoing the move that crossed 5,000
+        assert!(milestones.cross(4_900).is_empty());
+        assert!(accent(4_900) == accent(1_000));
+
+        assert!(milestones.cross(5_100).is_empty());
+        assert!(accent(5_100) == accent(5_000));
+        assert_eq!(milestones.cross(10_000), vec![10_000]);
+    }
+
+    #[test]
+    fn new_game_announces_again() {
+        let mut milestones = Milestones::new();
+        assert_eq!(milestones.cross(1_000), vec![1_000]);
+        milestones = Milestones::new();
+        assert_eq!(milestones.cross(1_000), vec![1_000]);
+    }
+}
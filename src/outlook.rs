@@ -0,0 +1,194 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use rand::thread_rng;
+
+use crate::engine::playout::count_wins;
+use crate::engine::round::Round;
+use crate::tui::events::Event;
+
+/// Total number of playouts run for each position.
+const ROLLOUTS: usize = 400;
+
+/// Number of playouts run between progress updates and cancellation checks.
+const ROLLOUT_BATCH: usize = 20;
+
+struct Job {
+    generation: u64,
+    round: Round,
+}
+
+/// Estimates the chance of winning from the current position on a background thread, posting
+/// incremental results as `Event::Estimate` through the given event sender.
+///
+/// Every submitted round bumps a generation counter; the worker abandons rollouts for a position
+/// as soon as it notices the generation has moved on and never posts results for stale positions.
+/// Each estimate carries the generation it was made for, since one posted just before the board
+/// moved on is still queued behind the move; see `Outlook::generation`.
+pub(crate) struct Outlook {
+    generation: Arc<AtomicU64>,
+    jobs: Option<Sender<Job>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Outlook {
+    /// An outlook that never estimates anything and doesn't spawn a thread.
+    pub(crate) fn disabled() -> Self {
+        Self {
+            generation: Arc::new(AtomicU64::new(0)),
+            jobs: None,
+            handle: None,
+        }
+    }
+
+    pub(crate) fn new(enabled: bool, events: Sender<Event>) -> Self {
+        if !enabled {
+            return Self::disabled();
+        }
+        let generation = Arc::new(AtomicU64::new(0));
+        let (jobs, receiver) = channel();
+        let worker_generation = generation.clone();
+        let handle = std::thread::spawn(move || work(receiver, worker_generation, events));
+        Self {
+            generation,
+            jobs: Some(jobs),
+            handle: Some(handle),
+        }
+    }
+
+    /// Returns true if there is a worker thread producing estimates.
+    pub(crate) fn is_running(&self) -> bool {
+        self.handle.is_some()
+    }
+
+    /// Returns the generation of the round submitted last; estimates posted for any other are
+    /// stale.
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Starts estimating the given round, cancelling any estimation in progress.
+    pub(crate) fn submit(&self, round: Round) {
+        let generation = self.generation.fetch_add(1, Ordering::AcqRel) + 1;
+        if let Some(jobs) = &self.jobs {
+            // the worker only hangs up if it panicked, in which case there's nothing left to
+            // estimate with
+            let _ = jobs.send(Job { generation, round });
+        }
+    }
+}
+
+impl Drop for Outlook {
+    fn drop(&mut self) {
+        // cancel whatever is in flight and hang up so the worker exits its receive loop
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        let _ = self.jobs.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn work(jobs: Receiver<Job>, generation: Arc<AtomicU64>, events: Sender<Event>) {
+    let mut rng = thread_rng();
+    while let Ok(mut job) = jobs.recv() {
+        // only the most recent position is worth estimating
+        while let Ok(newer) = jobs.try_recv() {
+            job = newer;
+        }
+
+        let mut wins = 0;
+        let mut total = 0;
+        while total < ROLLOUTS {
+            if generation.load(Ordering::Acquire) != job.generation {
+                break;
+            }
+            wins += count_wins(&job.round, ROLLOUT_BATCH, &mut rng);
+            total += ROLLOUT_BATCH;
+            let estimate = wins as f32 / total as f32;
+            if !post_if_current(&generation, job.generation, estimate, &events) {
+                break;
+            }
+        }
+    }
+}
+
+/// Posts the estimate only if `job_generation` is still the current generation. Returns false if
+/// the estimate was discarded or could not be delivered.
+fn post_if_current(
+    generation: &AtomicU64,
+    job_generation: u64,
+    estimate: f32,
+    events: &Sender<Event>,
+) -> bool {
+    if generation.load(Ordering::Acquire) != job_generation {
+        log::trace!(
+            "discarding stale estimate for generation {}",
+            job_generation
+        );
+        return false;
+    }
+    events
+        .send(Event::Estimate {
+            generation: job_generation,
+            estimate,
+        })
+        .is_ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn round_is_send() {
+        assert_send::<Round>();
+    }
+
+    #[test]
+    fn stale_estimates_are_discarded() {
+        let (sender, receiver) = channel();
+        let generation = AtomicU64::new(2);
+
+        assert!(!post_if_current(&generation, 1, 0.5, &sender));
+        assert!(receiver.try_recv().is_err());
+
+        assert!(post_if_current(&generation, 2, 0.5, &sender));
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(Event::Estimate { generation: 2, estimate }) if estimate == 0.5
+        ));
+    }
+
+    #[test]
+    fn disabled_outlook_spawns_no_thread() {
+        let (sender, receiver) = channel();
+        let outlook = Outlook::new(false, sender);
+        assert!(!outlook.is_running());
+
+        outlook.submit(Round::default());
+        drop(outlook);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn enabled_outlook_posts_estimates() {
+        let (sender, receiver) = channel();
+        let outlook = Outlook::new(true, sender);
+        assert!(outlook.is_running());
+
+        outlook.submit(Round::default());
+        let event = receiver
+            .recv_timeout(std::time::Duration::from_secs(10))
+            .expect("should receive an estimate");
+        assert!(matches!(
+            event,
+            Event::Estimate { generation, estimate }
+                if generation == outlook.generation() && (0.0..=1.0).contains(&estimate)
+        ));
+    }
+}
@@ -0,0 +1,368 @@
+//! Label packs replace the numbers shown on tiles with other short labels, eg letters or the
+//! symbols of the chemical elements. Only tile faces use them; the score and everything written
+//! out keeps showing numbers.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::engine::round::{display_value, Card, MAX_CARD};
+use crate::error::{Error, Result};
+use crate::tui48::TILE_INTERIOR_WIDTH;
+
+const ELEMENTS: [&str; MAX_CARD as usize] = [
+    "H", "He", "Li", "Be", "B", "C", "N", "O", "F", "Ne", "Na", "Mg", "Al", "Si", "P", "S", "Cl",
+];
+
+/// The packs that come with the game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum BuiltinPack {
+    /// The values themselves, as tiles have always shown them.
+    Numbers,
+    /// A for 2, B for 4 and so on.
+    Letters,
+    /// The symbol of the element with the exponent as its atomic number, H for 2, He for 4 and so
+    /// on.
+    Elements,
+}
+
+impl BuiltinPack {
+    pub(crate) const ALL: [BuiltinPack; 3] = [Self::Numbers, Self::Letters, Self::Elements];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Numbers => "numbers",
+            Self::Letters => "letters",
+            Self::Elements => "elements",
+        }
+    }
+}
+
+/// Which pack to use: one of the built-in packs named as on the command line, or a TOML file with
+/// a pack of its own. Anything that isn't the name of a built-in pack is taken to be a path.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(from = "String", into = "String")]
+pub(crate) enum PackChoice {
+    Builtin(BuiltinPack),
+    File(PathBuf),
+}
+
+impl FromStr for PackChoice {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(BuiltinPack::ALL
+            .into_iter()
+            .find(|pack| pack.name() == s)
+            .map_or_else(|| Self::File(PathBuf::from(s)), Self::Builtin))
+    }
+}
+
+impl From<String> for PackChoice {
+    fn from(s: String) -> Self {
+        match s.parse() {
+            Ok(choice) => choice,
+            Err(never) => match never {},
+        }
+    }
+}
+
+impl From<PackChoice> for String {
+    fn from(choice: PackChoice) -> Self {
+        choice.to_string()
+    }
+}
+
+impl std::fmt::Display for PackChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Builtin(pack) => f.pad(pack.name()),
+            Self::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A pack as written in its TOML file, eg
+///
+/// ```toml
+/// name = "animals"
+///
+/// [labels]
+/// 1 = "🐭"
+/// 2 = "🐹"
+/// ```
+///
+/// Labels are keyed by exponent, so `1` labels the 2 tile and `11` the 2048 tile.
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PackFile {
+    name: Option<String>,
+    labels: BTreeMap<String, String>,
+}
+
+/// The label for every tile from 2 up to the largest tile there are colors for.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct LabelPack {
+    name: String,
+    // the label for exponent n is at index n - 1
+    labels: Vec<String>,
+}
+
+impl LabelPack {
+    pub(crate) fn builtin(pack: BuiltinPack) -> Self {
+        let label = |card: Card| match pack {
+            BuiltinPack::Numbers => display_value(card).to_string(),
+            BuiltinPack::Letters => char::from(b'A' + card - 1).to_string(),
+            BuiltinPack::Elements => ELEMENTS[card as usize - 1].to_string(),
+        };
+        Self {
+            name: pack.name().to_string(),
+            labels: (1..=MAX_CARD).map(label).collect(),
+        }
+    }
+
+    /// Returns the chosen pack, reading and validating it first if it comes from a file.
+    pub(crate) fn load(choice: &PackChoice) -> Result<Self> {
+        match choice {
+            PackChoice::Builtin(pack) => Ok(Self::builtin(*pack)),
+            PackChoice::File(path) => Self::from_file(path),
+        }
+    }
+
+    fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|reason| Error::InvalidPack {
+            path: path.to_path_buf(),
+            reason,
+        })
+    }
+
+    /// Parses and validates a pack, describing the first problem found if it isn't valid.
+    fn parse(text: &str) -> std::result::Result<Self, String> {
+        let file: PackFile = toml::from_str(text).map_err(|e| e.message().to_string())?;
+        let mut labels: Vec<Option<String>> = vec![None; MAX_CARD as usize];
+        for (key, label) in file.labels {
+            let slot = key
+                .parse::<Card>()
+                .ok()
+                .and_then(|card| card.checked_sub(1))
+                .and_then(|i| labels.get_mut(i as usize))
+                .ok_or_else(|| {
+                    format!(
+                        "label key {:?} isn't an exponent between 1 and {}",
+                        key, MAX_CARD
+                    )
+                })?;
+            *slot = Some(label);
+        }
+        let labels = labels
+            .into_iter()
+            .zip(1..)
+            .map(|(label, card)| {
+                let label = label.ok_or_else(|| {
+                    format!("no label for exponent {} ({})", card, display_value(card))
+                })?;
+                validate_label(card, &label)?;
+                Ok(label)
+            })
+            .collect::<std::result::Result<_, String>>()?;
+        Ok(Self {
+            name: file.name.unwrap_or_else(|| "custom".to_string()),
+            labels,
+        })
+    }
+
+    /// Replaces the label for the given card.
+    #[cfg(test)]
+    pub(crate) fn with_label(mut self, card: Card, label: &str) -> Self {
+        self.labels[card as usize - 1] = label.to_string();
+        self
+    }
+
+    /// Returns the pack with the given labels in place of its own, by card. The labels are taken
+    /// to have been validated.
+    pub(crate) fn relabeled(&self, labels: &[(Card, String)]) -> Self {
+        let mut pack = self.clone();
+        for (card, label) in labels {
+            if let Some(slot) = card
+                .checked_sub(1)
+                .and_then(|i| pack.labels.get_mut(i as usize))
+            {
+                *slot = label.clone();
+            }
+        }
+        pack
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the label for the given card, falling back to its value past the end of the pack.
+    pub(crate) fn label(&self, card: Card) -> String {
+        card.checked_sub(1)
+            .and_then(|i| self.labels.get(i as usize))
+            .cloned()
+            .unwrap_or_else(|| display_value(card).to_string())
+    }
+
+    /// Returns the label for the given card along with the value it stands for, eg "Ne = 1024",
+    /// or just the value if the label is the value.
+    pub(crate) fn legend(&self, card: Card) -> String {
+        let (label, value) = (self.label(card), display_value(card));
+        if label == value.to_string() {
+            return label;
+        }
+        format!("{} = {}", label, value)
+    }
+}
+
+/// Makes sure a label can be drawn on a single line inside a tile.
+pub(crate) fn validate_label(card: Card, label: &str) -> std::result::Result<(), String> {
+    let which = || format!("exponent {} ({})", card, display_value(card));
+    if label.trim().is_empty() {
+        return Err(format!("the label for {} is empty", which()));
+    }
+    // control characters have no width and combining marks none of their own, so neither can be
+    // centered reliably
+    if label.chars().any(|c| c.width().unwrap_or(0) == 0) {
+        return Err(format!(
+            "the label {:?} for {} has characters that take up no cells",
+            label,
+            which()
+        ));
+    }
+    let width = label.width();
+    if width > TILE_INTERIOR_WIDTH {
+        return Err(format!(
+            "the label {:?} for {} is {} cells wide but tiles fit at most {}",
+            label,
+            which(),
+            width,
+            TILE_INTERIOR_WIDTH
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+    use crate::config::test::config_file;
+
+    /// A pack labelling every exponent with the given label, apart from the overrides. Labels are
+    /// written out verbatim, so they must not need escaping in TOML.
+    fn pack_text(label: &str, overrides: &[(&str, &str)]) -> String {
+        let mut text = String::from("name = \"test\"\n[labels]\n");
+        for card in 1..=MAX_CARD {
+            let key = card.to_string();
+            if let Some((_, label)) = overrides.iter().find(|(k, _)| *k == key) {
+                if !label.is_empty() {
+                    text.push_str(&format!("{} = \"{}\"\n", key, label));
+                }
+                continue;
+            }
+            text.push_str(&format!("{} = \"{}\"\n", key, label));
+        }
+        for (key, label) in overrides {
+            if key.parse::<Card>().map_or(true, |card| card > MAX_CARD) {
+                text.push_str(&format!("\"{}\" = \"{}\"\n", key, label));
+            }
+        }
+        text
+    }
+
+    #[rstest]
+    #[case::numbers(BuiltinPack::Numbers, [(1, "2"), (11, "2048")])]
+    #[case::letters(BuiltinPack::Letters, [(1, "A"), (11, "K")])]
+    #[case::elements(BuiltinPack::Elements, [(1, "H"), (11, "Na")])]
+    fn builtin_packs_label_every_exponent(
+        #[case] pack: BuiltinPack,
+        #[case] expected: [(Card, &str); 2],
+    ) {
+        let pack = LabelPack::builtin(pack);
+        assert_eq!(pack.labels.len(), MAX_CARD as usize);
+        for (card, label) in expected {
+            assert_eq!(pack.label(card), label);
+        }
+    }
+
+    #[rstest]
+    #[case::letters(BuiltinPack::Letters)]
+    #[case::elements(BuiltinPack::Elements)]
+    fn builtin_packs_other_than_numbers_fit_tiles(#[case] pack: BuiltinPack) {
+        let pack = LabelPack::builtin(pack);
+        for (label, card) in pack.labels.iter().zip(1..) {
+            assert_eq!(validate_label(card, label), Ok(()));
+        }
+    }
+
+    #[test]
+    fn parse_reads_labels_by_exponent() {
+        let pack = LabelPack::parse(&pack_text("x", &[("1", "\u{1f42d}"), ("10", "\u{1f418}")]))
+            .expect("the pack should be valid");
+        assert_eq!(pack.name(), "test");
+        assert_eq!(pack.label(1), "\u{1f42d}");
+        assert_eq!(pack.label(2), "x");
+        assert_eq!(pack.label(10), "\u{1f418}");
+        assert_eq!(pack.legend(10), "\u{1f418} = 1024");
+        // past the end of the pack tiles fall back to their value
+        assert_eq!(pack.label(MAX_CARD + 1), "262144");
+    }
+
+    #[rstest]
+    #[case::missing(&[("5", "")], "no label for exponent 5 (32)")]
+    #[case::too_wide(
+        &[("10", "\u{1f418}\u{1f418}\u{1f418}")],
+        "the label \"\u{1f418}\u{1f418}\u{1f418}\" for exponent 10 (1024) is 6 cells wide but \
+         tiles fit at most 4"
+    )]
+    #[case::empty(&[("3", " ")], "the label for exponent 3 (8) is empty")]
+    #[case::zero_width(
+        &[("2", "e\u{301}")],
+        "the label \"e\\u{301}\" for exponent 2 (4) has characters that take up no cells"
+    )]
+    #[case::unknown_exponent(&[("18", "x")], "label key \"18\" isn't an exponent between 1 and 17")]
+    #[case::not_an_exponent(&[("two", "x")], "label key \"two\" isn't an exponent between 1 and 17")]
+    fn parse_names_the_problem(#[case] overrides: &[(&str, &str)], #[case] expected: &str) {
+        assert_eq!(
+            LabelPack::parse(&pack_text("x", overrides)),
+            Err(expected.to_string())
+        );
+    }
+
+    #[test]
+    fn parse_rejects_unknown_fields() {
+        let err = LabelPack::parse("colors = \"red\"\n[labels]\n").expect_err("not a pack");
+        assert!(err.contains("colors"), "{}", err);
+    }
+
+    #[test]
+    fn load_reports_the_file_of_an_invalid_pack() -> Result<()> {
+        let path = config_file(&pack_text("x", &[("5", "")]));
+        let pack = LabelPack::load(&PackChoice::File(path.clone()));
+        std::fs::remove_file(&path)?;
+        match pack {
+            Err(Error::InvalidPack { path: p, reason }) => {
+                assert_eq!(p, path);
+                assert_eq!(reason, "no label for exponent 5 (32)");
+            }
+            other => panic!("expected an invalid pack, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::builtin("letters", PackChoice::Builtin(BuiltinPack::Letters))]
+    #[case::file(
+        "packs/animals.toml",
+        PackChoice::File(PathBuf::from("packs/animals.toml"))
+    )]
+    fn pack_choice_round_trips_through_its_name(#[case] name: &str, #[case] choice: PackChoice) {
+        assert_eq!(name.parse::<PackChoice>(), Ok(choice.clone()));
+        assert_eq!(choice.to_string(), name);
+    }
+}
@@ -0,0 +1,181 @@
+use std::path::PathBuf;
+
+/// Name of the per-user directory everything the program writes goes into.
+const APP_DIR: &str = "tui48";
+
+const LOG_FILE: &str = "output.log";
+
+const CONFIG_FILE: &str = "config.toml";
+
+const PREFS_FILE: &str = "prefs.toml";
+
+const POSITION_FILE: &str = "position.txt";
+
+const JOURNAL_FILE: &str = "journal.txt";
+
+const SCRIPTS_DIR: &str = "scripts";
+
+const HIGH_SCORE_FILE: &str = "highscore.json";
+
+const SAVE_FILE: &str = "save.json";
+
+const RESUME_FILE: &str = "resume.json";
+
+const STATS_FILE: &str = "stats.txt";
+
+/// Returns the directory for files the program writes while it runs: `$XDG_STATE_HOME/tui48` on
+/// Linux, `~/Library/Application Support/tui48` on macOS and `%LOCALAPPDATA%\tui48` on Windows.
+/// Falls back to the current directory if the platform provides none of these.
+pub(crate) fn state_dir() -> PathBuf {
+    select_dir(dirs::state_dir(), dirs::data_local_dir())
+}
+
+/// Returns the path of the log file, creating its directory if needed.
+pub(crate) fn log_file() -> std::io::Result<PathBuf> {
+    let dir = state_dir();
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(LOG_FILE))
+}
+
+/// Returns the path of the file preferences changed while playing are saved to, creating its
+/// directory if needed.
+pub(crate) fn prefs_file() -> std::io::Result<PathBuf> {
+    let dir = state_dir();
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(PREFS_FILE))
+}
+
+/// Returns the path of the file the board editor writes positions down to, creating its
+/// directory if needed.
+pub(crate) fn position_file() -> std::io::Result<PathBuf> {
+    let dir = state_dir();
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(POSITION_FILE))
+}
+
+/// Returns the path of the file every move made is added to as it's made, creating its directory
+/// if needed.
+pub(crate) fn journal_file() -> std::io::Result<PathBuf> {
+    let dir = state_dir();
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(JOURNAL_FILE))
+}
+
+/// Returns the path of the file the best score is kept in across sessions:
+/// `~/.local/share/tui48/highscore.json` on Linux, creating its directory if needed. Unlike the
+/// files above it's worth keeping, so it goes with the user's data rather than the state.
+pub(crate) fn high_score_file() -> std::io::Result<PathBuf> {
+    let dir = data_dir();
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(HIGH_SCORE_FILE))
+}
+
+/// Returns the path of the file games are saved to and loaded from, next to the high score file:
+/// `~/.local/share/tui48/save.json` on Linux, creating its directory if needed.
+pub(crate) fn save_file() -> std::io::Result<PathBuf> {
+    let dir = data_dir();
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(SAVE_FILE))
+}
+
+/// Returns the path of the file the game being played is left in on quitting, to be resumed the
+/// next time: `~/.local/share/tui48/resume.json` on Linux, creating its directory if needed. It's
+/// kept apart from the save file so that quitting never overwrites a game saved on purpose.
+pub(crate) fn resume_file() -> std::io::Result<PathBuf> {
+    let dir = data_dir();
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(RESUME_FILE))
+}
+
+/// Returns the path of the file a summary of every session is added to on quitting, next to the
+/// high score file: `~/.local/share/tui48/stats.txt` on Linux, creating its directory if needed.
+pub(crate) fn stats_file() -> std::io::Result<PathBuf> {
+    let dir = data_dir();
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(STATS_FILE))
+}
+
+fn data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .map(|dir| dir.join(APP_DIR))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Returns the path of the config file: `$XDG_CONFIG_HOME/tui48/config.toml` on Linux,
+/// `~/Library/Application Support/tui48/config.toml` on macOS and
+/// `%APPDATA%\tui48\config.toml` on Windows. Falls back to the current directory if the platform
+/// provides none of these.
+pub(crate) fn config_file() -> PathBuf {
+    dirs::config_dir()
+        .map(|dir| dir.join(APP_DIR))
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(CONFIG_FILE)
+}
+
+/// Returns the directory user scripts are loaded from, next to the config file.
+pub(crate) fn scripts_dir() -> PathBuf {
+    config_file().with_file_name(SCRIPTS_DIR)
+}
+
+// only Linux has a dedicated state directory; elsewhere the local (non-roaming) data directory is
+// the closest match
+fn select_dir(state: Option<PathBuf>, data_local: Option<PathBuf>) -> PathBuf {
+    state
+        .or(data_local)
+        .map(|dir| dir.join(APP_DIR))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use rstest::*;
+
+    use super::*;
+
+    #[rstest]
+    #[case::state(Some("/state"), Some("/local"), "/state/tui48")]
+    #[case::data_local(None, Some("/local"), "/local/tui48")]
+    #[case::current_dir(None, None, ".")]
+    fn select_dir_prefers_state_dir(
+        #[case] state: Option<&str>,
+        #[case] data_local: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        let dir = select_dir(state.map(PathBuf::from), data_local.map(PathBuf::from));
+        assert_eq!(dir, Path::new(expected));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn state_dir_on_linux() {
+        let base = match std::env::var_os("XDG_STATE_HOME").map(PathBuf::from) {
+            Some(dir) if dir.is_absolute() => dir,
+            _ => match dirs::home_dir() {
+                Some(home) => home.join(".local").join("state"),
+                None => return,
+            },
+        };
+        assert_eq!(state_dir(), base.join("tui48"));
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn state_dir_on_macos() {
+        let home = dirs::home_dir().expect("macOS always has a home directory");
+        assert_eq!(
+            state_dir(),
+            home.join("Library")
+                .join("Application Support")
+                .join("tui48")
+        );
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn state_dir_on_windows() {
+        let local = std::env::var_os("LOCALAPPDATA").expect("LOCALAPPDATA should be set");
+        assert_eq!(state_dir(), PathBuf::from(local).join("tui48"));
+    }
+}
@@ -0,0 +1,676 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::mpsc::{
+    channel, sync_channel, Receiver, RecvTimeoutError, SyncSender, TrySendError,
+};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::export::CapturedFrame;
+
+/// Number of messages that can be waiting for the worker before writers start holding them back
+/// themselves.
+const CHANNEL_BOUND: usize = 64;
+
+/// How often the worker writes out what it has gathered.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long a panicking program waits for pending writes before carrying on with the panic.
+const PANIC_SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// How long a writer backs off between attempts to hand messages to a full channel while shutting
+/// down.
+const SHUTDOWN_RETRY: Duration = Duration::from_millis(1);
+
+/// Something that wants to be written out.
+///
+/// Preferences, stats, status and the high score are snapshots, so only the latest of each that
+/// hasn't been written yet is kept. Journal entries and exported frames are written in the order
+/// they were submitted and are never dropped.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum PersistEvent {
+    PrefsChanged(String),
+    MoveJournal(String),
+    StatsUpdate(String),
+    StatusLine(String),
+    HighScore(String),
+    Frame(CapturedFrame),
+}
+
+/// Where the contents of one kind of event end up.
+pub(crate) trait Sink: Send {
+    fn write(&mut self, contents: &str) -> std::io::Result<()>;
+}
+
+/// Where exported frames end up.
+pub(crate) trait FrameSink: Send {
+    fn write(&mut self, frame: &CapturedFrame) -> std::io::Result<()>;
+
+    /// Called after every batch of frames written, eg to bring an index of them up to date.
+    fn finish(&mut self) -> std::io::Result<()>;
+}
+
+/// A sink writing to a file, either replacing its contents or appending to them.
+pub(crate) struct FileSink {
+    path: PathBuf,
+    append: bool,
+}
+
+impl FileSink {
+    /// A sink whose file only ever holds the latest contents written to it. The contents are
+    /// written next to it first and then moved over it, so that the file is never seen half
+    /// written.
+    pub(crate) fn replacing(path: PathBuf) -> Self {
+        Self {
+            path,
+            append: false,
+        }
+    }
+
+    /// A sink adding everything written to it to the end of its file, one entry per line.
+    pub(crate) fn appending(path: PathBuf) -> Self {
+        Self { path, append: true }
+    }
+}
+
+impl Sink for FileSink {
+    fn write(&mut self, contents: &str) -> std::io::Result<()> {
+        if !self.append {
+            let mut temporary = self.path.clone().into_os_string();
+            temporary.push(".tmp");
+            std::fs::write(&temporary, contents)?;
+            return std::fs::rename(&temporary, &self.path);
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", contents)
+    }
+}
+
+/// The sink for each kind of event. Events without a sink are discarded.
+#[derive(Default)]
+pub(crate) struct Sinks {
+    pub(crate) prefs: Option<Box<dyn Sink>>,
+    pub(crate) journal: Option<Box<dyn Sink>>,
+    pub(crate) stats: Option<Box<dyn Sink>>,
+    pub(crate) status: Option<Box<dyn Sink>>,
+    pub(crate) high_score: Option<Box<dyn Sink>>,
+    pub(crate) frames: Option<Box<dyn FrameSink>>,
+}
+
+impl Sinks {
+    /// Writes everything pending to its sink. A failing sink is logged and keeps what it failed to
+    /// write for the next attempt; it doesn't keep the other sinks from being written.
+    fn flush(&mut self, pending: &mut Pending) {
+        write_latest("prefs", &mut self.prefs, &mut pending.prefs);
+        write_latest("stats", &mut self.stats, &mut pending.stats);
+        write_latest("status", &mut self.status, &mut pending.status);
+        write_latest("high score", &mut self.high_score, &mut pending.high_score);
+        write_frames(&mut self.frames, &mut pending.frames);
+
+        let sink = match &mut self.journal {
+            Some(sink) => sink,
+            None => {
+                pending.journal.clear();
+                return;
+            }
+        };
+        let written = pending
+            .journal
+            .iter()
+            .take_while(|entry| match sink.write(entry) {
+                Ok(()) => true,
+                Err(e) => {
+                    log::warn!("failed to write journal entry: {}", e);
+                    false
+                }
+            })
+            .count();
+        pending.journal.drain(..written);
+    }
+}
+
+fn write_frames(sink: &mut Option<Box<dyn FrameSink>>, frames: &mut Vec<CapturedFrame>) {
+    let sink = match sink {
+        Some(sink) => sink,
+        None => {
+            frames.clear();
+            return;
+        }
+    };
+    if frames.is_empty() {
+        return;
+    }
+    let written = frames
+        .iter()
+        .take_while(|frame| match sink.write(frame) {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("failed to export frame {}: {}", frame.index(), e);
+                false
+            }
+        })
+        .count();
+    frames.drain(..written);
+    if let Err(e) = sink.finish() {
+        log::warn!("failed to finish exporting frames: {}", e);
+    }
+}
+
+fn write_latest(name: &str, sink: &mut Option<Box<dyn Sink>>, latest: &mut Option<String>) {
+    let (sink, contents) = match (sink, latest.as_ref()) {
+        (Some(sink), Some(contents)) => (sink, contents),
+        (None, _) => {
+            *latest = None;
+            return;
+        }
+        _ => return,
+    };
+    match sink.write(contents) {
+        Ok(()) => *latest = None,
+        Err(e) => log::warn!("failed to write {}: {}", name, e),
+    }
+}
+
+/// Events gathered but not written yet, coalesced per kind.
+#[derive(Debug, Default, PartialEq)]
+struct Pending {
+    prefs: Option<String>,
+    journal: Vec<String>,
+    stats: Option<String>,
+    status: Option<String>,
+    high_score: Option<String>,
+    frames: Vec<CapturedFrame>,
+}
+
+impl Pending {
+    fn push(&mut self, event: PersistEvent) {
+        match event {
+            PersistEvent::PrefsChanged(s) => self.prefs = Some(s),
+            PersistEvent::MoveJournal(s) => self.journal.push(s),
+            PersistEvent::StatsUpdate(s) => self.stats = Some(s),
+            PersistEvent::StatusLine(s) => self.status = Some(s),
+            PersistEvent::HighScore(s) => self.high_score = Some(s),
+            PersistEvent::Frame(frame) => self.frames.push(frame),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Takes everything out as events, journal entries and frames in the order they were
+    /// submitted.
+    fn take_events(&mut self) -> Vec<PersistEvent> {
+        let Pending {
+            prefs,
+            journal,
+            stats,
+            status,
+            high_score,
+            frames,
+        } = std::mem::take(self);
+        journal
+            .into_iter()
+            .map(PersistEvent::MoveJournal)
+            .chain(frames.into_iter().map(PersistEvent::Frame))
+            .chain(prefs.map(PersistEvent::PrefsChanged))
+            .chain(stats.map(PersistEvent::StatsUpdate))
+            .chain(status.map(PersistEvent::StatusLine))
+            .chain(high_score.map(PersistEvent::HighScore))
+            .collect()
+    }
+}
+
+enum Message {
+    Event(PersistEvent),
+    /// Write out everything and stop, acknowledging once done.
+    Shutdown(std::sync::mpsc::Sender<()>),
+}
+
+enum Mode {
+    /// Events are handed to a worker thread owning the sinks. Events that don't fit in the
+    /// channel are held back here, coalesced like on the worker, until there is room again.
+    Background {
+        messages: Option<SyncSender<Message>>,
+        backlog: Pending,
+        handle: Option<JoinHandle<()>>,
+    },
+    /// Events are written out as they are submitted, on the submitting thread.
+    Synchronous(Sinks),
+}
+
+/// What the features persisting files write to. Clones share the same sinks.
+///
+/// Submitting never blocks on a background handle: if the worker is stuck on a slow sink and its
+/// channel fills up, events are held back by the handle until the worker catches up.
+#[derive(Clone)]
+pub(crate) struct PersistenceHandle {
+    mode: Arc<Mutex<Mode>>,
+}
+
+impl PersistenceHandle {
+    /// A handle writing out on a background thread, at most every `FLUSH_INTERVAL`. If no thread
+    /// can be started, it writes out as events are submitted instead.
+    pub(crate) fn background(sinks: Sinks) -> Self {
+        Self::background_with_interval(sinks, FLUSH_INTERVAL)
+    }
+
+    fn background_with_interval(sinks: Sinks, interval: Duration) -> Self {
+        let (messages, receiver) = sync_channel(CHANNEL_BOUND);
+        // the sinks are only handed over once the worker is running, so that they're kept if it
+        // can't be started
+        let (hand_over, handed_over) = channel();
+        let spawned = std::thread::Builder::new().spawn(move || {
+            if let Ok(sinks) = handed_over.recv() {
+                work(receiver, sinks, interval);
+            }
+        });
+        match spawned {
+            Ok(handle) => {
+                let _ = hand_over.send(sinks);
+                Self::with_mode(Mode::Background {
+                    messages: Some(messages),
+                    backlog: Pending::default(),
+                    handle: Some(handle),
+                })
+            }
+            Err(e) => {
+                log::warn!("failed to start the persistence worker: {}", e);
+                Self::synchronous(sinks)
+            }
+        }
+    }
+
+    /// A handle writing out every event as soon as it is submitted, without spawning a thread.
+    pub(crate) fn synchronous(sinks: Sinks) -> Self {
+        Self::with_mode(Mode::Synchronous(sinks))
+    }
+
+    fn with_mode(mode: Mode) -> Self {
+        Self {
+            mode: Arc::new(Mutex::new(mode)),
+        }
+    }
+
+    pub(crate) fn submit(&self, event: PersistEvent) {
+        let mut mode = self.mode.lock().unwrap_or_else(|e| e.into_inner());
+        match &mut *mode {
+            Mode::Synchronous(sinks) => {
+                let mut pending = Pending::default();
+                pending.push(event);
+                sinks.flush(&mut pending);
+            }
+            Mode::Background {
+                messages, backlog, ..
+            } => {
+                backlog.push(event);
+                if let Some(messages) = messages {
+                    drain(messages, backlog);
+                }
+            }
+        }
+    }
+
+    /// Writes out everything submitted so far and stops the worker, waiting at most `timeout` for
+    /// it. Returns false if the writes didn't finish in time. Events submitted afterwards are
+    /// discarded.
+    pub(crate) fn shutdown(&self, timeout: Duration) -> bool {
+        let mut mode = self.mode.lock().unwrap_or_else(|e| e.into_inner());
+        shutdown(&mut mode, timeout)
+    }
+
+    /// Makes a panic write out what it can before the previously installed hook runs, so that
+    /// whatever was submitted before the crash isn't lost with it.
+    pub(crate) fn install_panic_hook(&self) {
+        let handle = self.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            // the panic may have happened while this very handle was in use
+            if let Ok(mut mode) = handle.mode.try_lock() {
+                shutdown(&mut mode, PANIC_SHUTDOWN_TIMEOUT);
+            }
+            previous(info);
+        }));
+    }
+}
+
+fn shutdown(mode: &mut Mode, timeout: Duration) -> bool {
+    let (messages, backlog, handle) = match mode {
+        Mode::Synchronous(_) => return true,
+        Mode::Background {
+            messages,
+            backlog,
+            handle,
+        } => (messages, backlog, handle),
+    };
+    let messages = match messages.take() {
+        Some(m) => m,
+        None => return true,
+    };
+    let deadline = Instant::now() + timeout;
+
+    let (ack, acked) = channel();
+    let mut message = Message::Shutdown(ack);
+    loop {
+        drain(&messages, backlog);
+        if backlog.is_empty() {
+            match messages.try_send(message) {
+                Ok(()) => break,
+                Err(TrySendError::Full(m)) => message = m,
+                Err(TrySendError::Disconnected(_)) => return false,
+            }
+        }
+        if Instant::now() >= deadline {
+            log::warn!("timed out handing pending writes to the persistence worker");
+            return false;
+        }
+        std::thread::sleep(SHUTDOWN_RETRY);
+    }
+
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if acked.recv_timeout(remaining).is_err() {
+        log::warn!("timed out waiting for the persistence worker to finish writing");
+        return false;
+    }
+    if let Some(handle) = handle.take() {
+        let _ = handle.join();
+    }
+    true
+}
+
+/// Hands as much of the backlog to the worker as fits in the channel, in order.
+fn drain(messages: &SyncSender<Message>, backlog: &mut Pending) {
+    let mut events = backlog.take_events().into_iter();
+    for event in events.by_ref() {
+        match messages.try_send(Message::Event(event)) {
+            Ok(()) => continue,
+            Err(TrySendError::Full(Message::Event(event))) => {
+                backlog.push(event);
+                break;
+            }
+            // the worker only hangs up if a sink panicked, in which case there's nowhere to write
+            Err(_) => return,
+        }
+    }
+    events.for_each(|event| backlog.push(event));
+}
+
+fn work(messages: Receiver<Message>, mut sinks: Sinks, interval: Duration) {
+    let mut pending = Pending::default();
+    let mut next_flush = Instant::now() + interval;
+    loop {
+        let timeout = next_flush.saturating_duration_since(Instant::now());
+        match messages.recv_timeout(timeout) {
+            Ok(Message::Event(event)) => pending.push(event),
+            Ok(Message::Shutdown(ack)) => {
+                sinks.flush(&mut pending);
+                let _ = ack.send(());
+                return;
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                sinks.flush(&mut pending);
+                next_flush = Instant::now() + interval;
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                sinks.flush(&mut pending);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc::Sender;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+    // long enough that only shutting down writes anything out
+    const NEVER: Duration = Duration::from_secs(3600);
+
+    /// Keeps everything written to it in memory.
+    #[derive(Clone, Default)]
+    struct Recorder {
+        writes: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Recorder {
+        fn boxed(&self) -> Option<Box<dyn Sink>> {
+            Some(Box::new(self.clone()))
+        }
+
+        fn writes(&self) -> Vec<String> {
+            self.writes.lock().unwrap().clone()
+        }
+    }
+
+    impl Sink for Recorder {
+        fn write(&mut self, contents: &str) -> std::io::Result<()> {
+            self.writes.lock().unwrap().push(contents.to_string());
+            Ok(())
+        }
+    }
+
+    /// Fails every write.
+    struct Broken;
+
+    impl Sink for Broken {
+        fn write(&mut self, _: &str) -> std::io::Result<()> {
+            Err(std::io::Error::other("broken"))
+        }
+    }
+
+    /// Blocks its first write until released, announcing that it is stuck.
+    struct Wedged {
+        stuck: Sender<()>,
+        release: Mutex<Receiver<()>>,
+    }
+
+    impl Sink for Wedged {
+        fn write(&mut self, _: &str) -> std::io::Result<()> {
+            let _ = self.stuck.send(());
+            let _ = self.release.lock().unwrap().recv();
+            Ok(())
+        }
+    }
+
+    fn entries(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("move {}", i)).collect()
+    }
+
+    #[test]
+    fn snapshots_coalesce_but_journal_entries_do_not() {
+        let (prefs, journal, stats, status, high_score) = Default::default();
+        let sinks = Sinks {
+            prefs: Recorder::boxed(&prefs),
+            journal: Recorder::boxed(&journal),
+            stats: Recorder::boxed(&stats),
+            status: Recorder::boxed(&status),
+            high_score: Recorder::boxed(&high_score),
+            ..Default::default()
+        };
+        let persistence = PersistenceHandle::background_with_interval(sinks, NEVER);
+        for i in 0..3 {
+            persistence.submit(PersistEvent::PrefsChanged(format!("prefs {}", i)));
+            persistence.submit(PersistEvent::MoveJournal(format!("move {}", i)));
+            persistence.submit(PersistEvent::StatsUpdate(format!("stats {}", i)));
+            persistence.submit(PersistEvent::StatusLine(format!("status {}", i)));
+            persistence.submit(PersistEvent::HighScore(format!("best {}", i)));
+        }
+        assert!(persistence.shutdown(TIMEOUT));
+
+        assert_eq!(prefs.writes(), vec!["prefs 2"]);
+        assert_eq!(journal.writes(), entries(3));
+        assert_eq!(stats.writes(), vec!["stats 2"]);
+        assert_eq!(status.writes(), vec!["status 2"]);
+        assert_eq!(high_score.writes(), vec!["best 2"]);
+    }
+
+    #[test]
+    fn journal_survives_a_flood_of_moves_in_order() {
+        let journal = Recorder::default();
+        let sinks = Sinks {
+            journal: journal.boxed(),
+            ..Default::default()
+        };
+        let persistence = PersistenceHandle::background_with_interval(sinks, Duration::ZERO);
+        let expected = entries(CHANNEL_BOUND * 50);
+        for entry in &expected {
+            persistence.submit(PersistEvent::MoveJournal(entry.clone()));
+        }
+        assert!(persistence.shutdown(TIMEOUT));
+        assert_eq!(journal.writes(), expected);
+    }
+
+    #[test]
+    fn shutdown_flushes_everything() {
+        let (prefs, journal) = (Recorder::default(), Recorder::default());
+        let sinks = Sinks {
+            prefs: prefs.boxed(),
+            journal: journal.boxed(),
+            ..Default::default()
+        };
+        let persistence = PersistenceHandle::background_with_interval(sinks, NEVER);
+        persistence.submit(PersistEvent::PrefsChanged("prefs".to_string()));
+        persistence.submit(PersistEvent::MoveJournal("move 0".to_string()));
+        assert!(prefs.writes().is_empty());
+
+        assert!(persistence.shutdown(TIMEOUT));
+        assert_eq!(prefs.writes(), vec!["prefs"]);
+        assert_eq!(journal.writes(), entries(1));
+
+        // nothing is left to write to after shutting down
+        persistence.submit(PersistEvent::PrefsChanged("late".to_string()));
+        assert!(persistence.shutdown(TIMEOUT));
+        assert_eq!(prefs.writes(), vec!["prefs"]);
+    }
+
+    #[test]
+    fn failing_sink_does_not_affect_the_others() {
+        let journal = Recorder::default();
+        let mut sinks = Sinks {
+            prefs: Some(Box::new(Broken)),
+            journal: journal.boxed(),
+            ..Default::default()
+        };
+        let mut pending = Pending::default();
+        pending.push(PersistEvent::PrefsChanged("prefs".to_string()));
+        pending.push(PersistEvent::MoveJournal("move 0".to_string()));
+        sinks.flush(&mut pending);
+
+        assert_eq!(journal.writes(), entries(1));
+        // kept for the next attempt
+        assert_eq!(pending.prefs.as_deref(), Some("prefs"));
+        assert!(pending.journal.is_empty());
+    }
+
+    #[test]
+    fn failed_journal_entries_are_kept_in_order() {
+        let mut sinks = Sinks {
+            journal: Some(Box::new(Broken)),
+            ..Default::default()
+        };
+        let mut pending = Pending::default();
+        for entry in entries(3) {
+            pending.push(PersistEvent::MoveJournal(entry));
+        }
+        sinks.flush(&mut pending);
+        assert_eq!(pending.journal, entries(3));
+    }
+
+    #[test]
+    fn wedged_sink_does_not_stall_submitting() {
+        let (stuck, is_stuck) = channel();
+        let (release, released) = channel();
+        let journal = Recorder::default();
+        let sinks = Sinks {
+            status: Some(Box::new(Wedged {
+                stuck,
+                release: Mutex::new(released),
+            })),
+            journal: journal.boxed(),
+            ..Default::default()
+        };
+        let persistence = PersistenceHandle::background_with_interval(sinks, Duration::ZERO);
+        persistence.submit(PersistEvent::StatusLine("status".to_string()));
+        is_stuck
+            .recv_timeout(TIMEOUT)
+            .expect("the worker should write the status");
+
+        let expected = entries(CHANNEL_BOUND * 10);
+        let started = Instant::now();
+        for entry in &expected {
+            persistence.submit(PersistEvent::MoveJournal(entry.clone()));
+        }
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(journal.writes().is_empty());
+
+        release.send(()).unwrap();
+        assert!(persistence.shutdown(TIMEOUT));
+        assert_eq!(journal.writes(), expected);
+    }
+
+    #[test]
+    fn shutdown_gives_up_on_a_wedged_sink() {
+        let (stuck, is_stuck) = channel();
+        let (_release, released) = channel();
+        let sinks = Sinks {
+            status: Some(Box::new(Wedged {
+                stuck,
+                release: Mutex::new(released),
+            })),
+            ..Default::default()
+        };
+        let persistence = PersistenceHandle::background_with_interval(sinks, Duration::ZERO);
+        persistence.submit(PersistEvent::StatusLine("status".to_string()));
+        is_stuck
+            .recv_timeout(TIMEOUT)
+            .expect("the worker should write the status");
+
+        assert!(!persistence.shutdown(Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn synchronous_handle_writes_on_submit() {
+        let (prefs, journal) = (Recorder::default(), Recorder::default());
+        let sinks = Sinks {
+            prefs: prefs.boxed(),
+            journal: journal.boxed(),
+            ..Default::default()
+        };
+        let persistence = PersistenceHandle::synchronous(sinks);
+        persistence.submit(PersistEvent::PrefsChanged("prefs 0".to_string()));
+        persistence.submit(PersistEvent::PrefsChanged("prefs 1".to_string()));
+        persistence.submit(PersistEvent::MoveJournal("move 0".to_string()));
+
+        assert_eq!(prefs.writes(), vec!["prefs 0", "prefs 1"]);
+        assert_eq!(journal.writes(), entries(1));
+        assert!(persistence.shutdown(TIMEOUT));
+    }
+
+    #[test]
+    fn file_sinks_replace_or_append() -> std::io::Result<()> {
+        let dir = std::env::temp_dir().join(format!("tui48-persist-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let (latest, log) = (dir.join("latest"), dir.join("log"));
+        let (mut replacing, mut appending) = (
+            FileSink::replacing(latest.clone()),
+            FileSink::appending(log.clone()),
+        );
+        for i in 0..2 {
+            replacing.write(&format!("contents {}", i))?;
+            appending.write(&format!("move {}", i))?;
+        }
+        assert_eq!(std::fs::read_to_string(&latest)?, "contents 1");
+        assert_eq!(std::fs::read_to_string(&log)?, "move 0\nmove 1\n");
+        // the replaced file is written next to it first and leaves nothing behind
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 2);
+        std::fs::remove_dir_all(&dir)
+    }
+}
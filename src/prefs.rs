@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::migrate::Format;
+use crate::packs::PackChoice;
+use crate::themes::BuiltinTheme;
+
+/// The preferences file's format. Append a migration here whenever a setting is renamed or
+/// reinterpreted, and capture a file of the new version under `tests/fixtures/prefs`.
+pub(crate) const PREFS_FORMAT: Format = Format {
+    name: "prefs",
+    migrations: &[],
+};
+
+/// Choices made while playing that carry over to the next time the game is started. Unlike the
+/// config file, which is only ever read, the game writes these itself.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub(crate) struct Preferences {
+    /// The version of `PREFS_FORMAT` the preferences were written with.
+    pub(crate) version: u32,
+    /// The label pack last switched to.
+    pub(crate) pack: Option<PackChoice>,
+    /// The theme last applied.
+    pub(crate) theme: Option<BuiltinTheme>,
+    /// Whether the lines between the board's slots were last shown.
+    pub(crate) grid: Option<bool>,
+    /// Whether tiles are laid out to look square.
+    pub(crate) square_tiles: Option<bool>,
+    /// Whether each script in the scripts directory is run, keyed by file name. Scripts not
+    /// listed are run.
+    pub(crate) scripts: Option<BTreeMap<String, bool>>,
+    /// Settings this release doesn't know, eg ones written by a newer release, kept so that they
+    /// are written back as they were.
+    #[serde(flatten)]
+    pub(crate) unknown: toml::Table,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            version: PREFS_FORMAT.current_version(),
+            pack: None,
+            theme: None,
+            grid: None,
+            square_tiles: None,
+            scripts: None,
+            unknown: toml::Table::new(),
+        }
+    }
+}
+
+impl Preferences {
+    /// Reads the preferences from the given file, falling back to the defaults if there is no
+    /// such file. Files written by older releases are upgraded to the current version, which is
+    /// what they are written back as.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let invalid = |source| Error::InvalidConfig {
+            path: path.to_path_buf(),
+            source: Box::new(source),
+        };
+        let mut table: toml::Table = toml::from_str(&text).map_err(invalid)?;
+        PREFS_FORMAT.upgrade(&mut table)?;
+        toml::Value::Table(table).try_into().map_err(invalid)
+    }
+
+    pub(crate) fn to_toml(&self) -> String {
+        toml::to_string(self).expect("preferences are always representable in TOML")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use rstest::*;
+
+    use super::*;
+    use crate::config::test::config_file;
+    use crate::migrate::test::fixtures;
+    use crate::packs::BuiltinPack;
+
+    #[rstest]
+    #[case::none(None)]
+    #[case::builtin(Some(PackChoice::Builtin(BuiltinPack::Elements)))]
+    #[case::file(Some(PackChoice::File(PathBuf::from("packs/animals.toml"))))]
+    fn preferences_round_trip_through_their_file(#[case] pack: Option<PackChoice>) -> Result<()> {
+        let prefs = Preferences {
+            pack,
+            ..Preferences::default()
+        };
+        let path = config_file(&prefs.to_toml());
+        let loaded = Preferences::load(&path);
+        std::fs::remove_file(&path)?;
+        assert_eq!(loaded?, prefs);
+        Ok(())
+    }
+
+    #[test]
+    fn load_defaults_missing_file() -> Result<()> {
+        let path = std::env::temp_dir().join("tui48-no-such-prefs.toml");
+        assert_eq!(Preferences::load(&path)?, Preferences::default());
+        Ok(())
+    }
+
+    #[test]
+    fn every_fixture_loads_as_the_current_version() -> Result<()> {
+        for path in fixtures(&PREFS_FORMAT) {
+            let prefs = Preferences::load(&path)?;
+            assert_eq!(prefs.version, PREFS_FORMAT.current_version(), "{:?}", path);
+            assert!(prefs.unknown.is_empty(), "{:?}: {:?}", path, prefs.unknown);
+            assert_eq!(
+                prefs.pack,
+                Some(PackChoice::Builtin(BuiltinPack::Letters)),
+                "{:?}",
+                path
+            );
+            // what gets written back reads the same
+            let rewritten = config_file(&prefs.to_toml());
+            let reloaded = Preferences::load(&rewritten);
+            std::fs::remove_file(&rewritten)?;
+            assert_eq!(reloaded?, prefs, "{:?}", path);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn prefs_from_a_newer_release_keep_what_they_dont_understand() -> Result<()> {
+        let newer = PREFS_FORMAT.current_version() + 1;
+        let path = config_file(&format!(
+            "version = {}\npack = \"elements\"\ntheme = \"dusk\"\n\n[sounds]\nvolume = 3\n",
+            newer
+        ));
+        let loaded = Preferences::load(&path);
+        std::fs::remove_file(&path)?;
+        let mut prefs = loaded?;
+        assert_eq!(prefs.version, newer);
+        assert_eq!(prefs.pack, Some(PackChoice::Builtin(BuiltinPack::Elements)));
+
+        prefs.pack = Some(PackChoice::Builtin(BuiltinPack::Numbers));
+        let saved: toml::Table = toml::from_str(&prefs.to_toml()).expect("prefs are valid TOML");
+        let expected: toml::Table = toml::from_str(&format!(
+            "version = {}\npack = \"numbers\"\ntheme = \"dusk\"\n\n[sounds]\nvolume = 3\n",
+            newer
+        ))
+        .expect("expected prefs are valid TOML");
+        assert_eq!(saved, expected);
+        Ok(())
+    }
+}
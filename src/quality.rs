@@ -0,0 +1,386 @@
+//! Adapts how finely moves are animated to how fast the terminal renders frames, so that a slow
+//! terminal gets coarser animations rather than sluggish ones.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::frametimes::FRAME_BUDGET;
+
+/// How many of the most recent frames the average render time is taken over.
+const AVERAGE_FRAMES: usize = 4;
+
+/// The average render time has to exceed the budget by this factor to count as slow.
+const SLOW_FACTOR: u32 = 2;
+
+/// How many frames in a row have to be slow before animations get coarser.
+const SLOW_FRAMES: usize = 5;
+
+/// How many frames in a row have to fit the budget before animations get finer again. Far more
+/// than it takes to get coarser, so that a terminal on the edge doesn't flip back and forth.
+const FAST_FRAMES: usize = 60;
+
+/// How finely moves are animated, from finest to coarsest.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum, serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum QualityLevel {
+    /// Tiles are rendered at every cell they slide through.
+    #[default]
+    Full,
+    /// Tiles are rendered every other cell.
+    TwoCell,
+    /// Tiles are rendered every fourth cell.
+    FourCell,
+    /// Moves are shown in one go.
+    Instant,
+}
+
+impl QualityLevel {
+    /// The number of cells tiles slide between rendered frames, or None if moves aren't animated
+    /// at all.
+    pub(crate) fn step(&self) -> Option<usize> {
+        match self {
+            Self::Full => Some(1),
+            Self::TwoCell => Some(2),
+            Self::FourCell => Some(4),
+            Self::Instant => None,
+        }
+    }
+
+    fn coarser(&self) -> Self {
+        match self {
+            Self::Full => Self::TwoCell,
+            Self::TwoCell => Self::FourCell,
+            Self::FourCell | Self::Instant => Self::Instant,
+        }
+    }
+
+    fn finer(&self) -> Self {
+        match self {
+            Self::Full | Self::TwoCell => Self::Full,
+            Self::FourCell => Self::TwoCell,
+            Self::Instant => Self::FourCell,
+        }
+    }
+}
+
+/// How fast moves are animated, whatever the quality they're animated at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum AnimationSpeed {
+    /// Frames are shown for half as long as normal.
+    Fast,
+    #[default]
+    Normal,
+    /// Frames are shown for three times as long as normal.
+    Slow,
+    /// Moves are shown in one go.
+    Off,
+}
+
+impl AnimationSpeed {
+    /// How long an animation that normally takes the given time takes at this speed, or None if
+    /// it isn't played at all.
+    pub(crate) fn scale(&self, normal: Duration) -> Option<Duration> {
+        match self {
+            Self::Fast => Some(normal / 2),
+            Self::Normal => Some(normal),
+            Self::Slow => Some(normal * 3),
+            Self::Off => None,
+        }
+    }
+}
+
+/// How tiles pick up and lose speed as they slide. Every slide takes as many frames as the cells it
+/// covers, whatever the curve, so only where the tile is along the way changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum EasingFn {
+    /// Tiles move one cell every frame.
+    #[default]
+    Linear,
+    /// Tiles start slow and speed up.
+    EaseIn,
+    /// Tiles start fast and slow down as they arrive.
+    EaseOut,
+    /// Tiles speed up and then slow down again.
+    EaseInOut,
+}
+
+impl EasingFn {
+    /// How far along, from 0 to 1, a slide is the given fraction of the way through its frames.
+    pub(crate) fn progress(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::EaseInOut if t < 0.5 => 2.0 * t * t,
+            Self::EaseInOut => 1.0 - 2.0 * (1.0 - t) * (1.0 - t),
+        }
+    }
+
+    /// The number of cells a slide over the given number of cells has covered by the given frame,
+    /// counting from 1.
+    pub(crate) fn travelled(&self, frame: usize, frames: usize) -> usize {
+        if frames == 0 {
+            return 0;
+        }
+        let progress = self.progress(frame as f64 / frames as f64);
+        ((frames as f64 * progress).round() as usize).min(frames)
+    }
+}
+
+/// Picks the quality level from the time recent frames took to render: a level coarser once the
+/// average has been well over the frame budget for a few frames, a level finer once it has been
+/// within the budget for a good while. A pinned level never changes.
+#[derive(Clone, Debug)]
+pub(crate) struct AdaptiveQuality {
+    level: QualityLevel,
+    pinned: bool,
+    budget: Duration,
+    recent: VecDeque<Duration>,
+    // frames in a row the average was slow, or fast, at the current level
+    slow: usize,
+    fast: usize,
+}
+
+impl Default for AdaptiveQuality {
+    fn default() -> Self {
+        Self::new(FRAME_BUDGET)
+    }
+}
+
+impl AdaptiveQuality {
+    /// Starts out at full quality, adapting to frames that should render within the given budget.
+    pub(crate) fn new(budget: Duration) -> Self {
+        Self {
+            level: QualityLevel::Full,
+            pinned: false,
+            budget,
+            recent: VecDeque::with_capacity(AVERAGE_FRAMES),
+            slow: 0,
+            fast: 0,
+        }
+    }
+
+    /// Stays at the given level however long frames take.
+    pub(crate) fn pinned(level: QualityLevel) -> Self {
+        Self {
+            level,
+            pinned: true,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn level(&self) -> QualityLevel {
+        self.level
+    }
+
+    /// Takes the time a frame took to render into account and returns the level to animate the
+    /// next one at.
+    pub(crate) fn observe(&mut self, frame_time: Duration) -> QualityLevel {
+        if self.pinned {
+            return self.level;
+        }
+        if self.recent.len() == AVERAGE_FRAMES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(frame_time);
+        let average = self.recent.iter().sum::<Duration>() / self.recent.len() as u32;
+
+        if average > self.budget * SLOW_FACTOR {
+            self.slow += 1;
+            self.fast = 0;
+        } else if average <= self.budget {
+            self.fast += 1;
+            self.slow = 0;
+        } else {
+            // neither slow nor fast enough to count towards changing the level
+            self.slow = 0;
+            self.fast = 0;
+        }
+
+        let level = if self.slow == SLOW_FRAMES {
+            self.level.coarser()
+        } else if self.fast == FAST_FRAMES {
+            self.level.finer()
+        } else {
+            self.level
+        };
+        if level != self.level {
+            // frames at the new level owe nothing to those at the old one
+            self.level = level;
+            self.recent.clear();
+            self.slow = 0;
+            self.fast = 0;
+        }
+        self.level
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+
+    const BUDGET: Duration = Duration::from_millis(10);
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    /// Observes each frame time the given number of times and returns the level after each one.
+    fn observe_all(quality: &mut AdaptiveQuality, frames: &[(u64, usize)]) -> Vec<QualityLevel> {
+        frames
+            .iter()
+            .flat_map(|(time, count)| std::iter::repeat_n(ms(*time), *count))
+            .map(|time| quality.observe(time))
+            .collect()
+    }
+
+    #[rstest]
+    #[case::not_for_long_enough(&[(30, SLOW_FRAMES - 1)], QualityLevel::Full)]
+    #[case::for_long_enough(&[(30, SLOW_FRAMES)], QualityLevel::TwoCell)]
+    #[case::just_over_budget(&[(15, 100)], QualityLevel::Full)]
+    #[case::exactly_twice_the_budget(&[(20, 100)], QualityLevel::Full)]
+    // a fast frame starts the count over
+    #[case::interrupted(
+        &[(30, SLOW_FRAMES - 2), (1, AVERAGE_FRAMES), (30, SLOW_FRAMES - 2)],
+        QualityLevel::Full
+    )]
+    #[case::twice_as_long(&[(30, 2 * SLOW_FRAMES)], QualityLevel::FourCell)]
+    #[case::all_the_way(&[(30, 10 * SLOW_FRAMES)], QualityLevel::Instant)]
+    fn slow_frames_coarsen_animations(
+        #[case] frames: &[(u64, usize)],
+        #[case] expected: QualityLevel,
+    ) {
+        let mut quality = AdaptiveQuality::new(BUDGET);
+        observe_all(&mut quality, frames);
+        assert_eq!(quality.level(), expected);
+    }
+
+    #[test]
+    fn the_average_smooths_out_a_single_slow_frame() {
+        let mut quality = AdaptiveQuality::new(BUDGET);
+        let levels = observe_all(&mut quality, &[(1, 10), (60, 1), (1, 10)]);
+        assert!(levels.iter().all(|level| *level == QualityLevel::Full));
+    }
+
+    #[test]
+    fn levels_step_one_at_a_time() {
+        let mut quality = AdaptiveQuality::new(BUDGET);
+        let mut levels = observe_all(&mut quality, &[(1000, 10 * SLOW_FRAMES)]);
+        levels.dedup();
+        assert_eq!(
+            levels,
+            vec![
+                QualityLevel::Full,
+                QualityLevel::TwoCell,
+                QualityLevel::FourCell,
+                QualityLevel::Instant
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::not_for_long_enough(FAST_FRAMES - 1, QualityLevel::FourCell)]
+    #[case::for_long_enough(FAST_FRAMES, QualityLevel::TwoCell)]
+    #[case::for_twice_as_long(2 * FAST_FRAMES, QualityLevel::Full)]
+    #[case::for_ever(100 * FAST_FRAMES, QualityLevel::Full)]
+    fn fast_frames_recover_finer_animations(#[case] fast: usize, #[case] expected: QualityLevel) {
+        let mut quality = AdaptiveQuality::new(BUDGET);
+        observe_all(&mut quality, &[(30, 2 * SLOW_FRAMES)]);
+        assert_eq!(quality.level(), QualityLevel::FourCell);
+        observe_all(&mut quality, &[(1, fast)]);
+        assert_eq!(quality.level(), expected);
+    }
+
+    #[test]
+    fn frames_between_the_thresholds_hold_the_level() {
+        let mut quality = AdaptiveQuality::new(BUDGET);
+        observe_all(&mut quality, &[(30, SLOW_FRAMES)]);
+        // well over the budget, but not by enough to get coarser again, nor fast enough to
+        // recover
+        let levels = observe_all(&mut quality, &[(15, 10 * FAST_FRAMES)]);
+        assert!(levels.iter().all(|level| *level == QualityLevel::TwoCell));
+    }
+
+    #[test]
+    fn a_terminal_on_the_edge_does_not_oscillate() {
+        let mut quality = AdaptiveQuality::new(BUDGET);
+        // single slow frames, which keep the average slow for too few frames to count
+        let burst = [(100, 1), (1, AVERAGE_FRAMES + 1)];
+        let mut levels = Vec::new();
+        for _ in 0..50 {
+            levels.extend(observe_all(&mut quality, &burst));
+        }
+        assert!(levels.iter().all(|level| *level == QualityLevel::Full));
+
+        // once it does get coarser, recovering takes far longer than the fast stretches last
+        observe_all(&mut quality, &[(100, SLOW_FRAMES)]);
+        let mut levels = Vec::new();
+        for _ in 0..50 {
+            levels.extend(observe_all(&mut quality, &[(1, FAST_FRAMES - 1), (50, 1)]));
+        }
+        assert!(levels.iter().all(|level| *level == QualityLevel::TwoCell));
+    }
+
+    #[rstest]
+    fn a_pinned_level_never_changes(
+        #[values(
+            QualityLevel::Full,
+            QualityLevel::TwoCell,
+            QualityLevel::FourCell,
+            QualityLevel::Instant
+        )]
+        level: QualityLevel,
+    ) {
+        let mut quality = AdaptiveQuality::pinned(level);
+        let levels = observe_all(
+            &mut quality,
+            &[(1000, 10 * SLOW_FRAMES), (0, 10 * FAST_FRAMES)],
+        );
+        assert!(levels.iter().all(|l| *l == level));
+    }
+
+    #[rstest]
+    #[case::full(QualityLevel::Full, Some(1))]
+    #[case::two_cell(QualityLevel::TwoCell, Some(2))]
+    #[case::four_cell(QualityLevel::FourCell, Some(4))]
+    #[case::instant(QualityLevel::Instant, None)]
+    fn levels_step_tiles_further_as_they_get_coarser(
+        #[case] level: QualityLevel,
+        #[case] step: Option<usize>,
+    ) {
+        assert_eq!(level.step(), step);
+    }
+
+    #[rstest]
+    #[case::fast(AnimationSpeed::Fast, Some(ms(5)))]
+    #[case::normal(AnimationSpeed::Normal, Some(ms(10)))]
+    #[case::slow(AnimationSpeed::Slow, Some(ms(30)))]
+    #[case::off(AnimationSpeed::Off, None)]
+    fn speeds_scale_how_long_frames_are_shown(
+        #[case] speed: AnimationSpeed,
+        #[case] delay: Option<Duration>,
+    ) {
+        assert_eq!(speed.scale(ms(10)), delay);
+    }
+
+    #[rstest]
+    #[case::linear(EasingFn::Linear, vec![1, 2, 3, 4, 5, 6])]
+    #[case::ease_in(EasingFn::EaseIn, vec![0, 1, 2, 3, 4, 6])]
+    #[case::ease_out(EasingFn::EaseOut, vec![2, 3, 5, 5, 6, 6])]
+    #[case::ease_in_out(EasingFn::EaseInOut, vec![0, 1, 3, 5, 6, 6])]
+    fn easing_covers_the_whole_slide_in_as_many_frames_as_cells(
+        #[case] easing: EasingFn,
+        #[case] travelled: Vec<usize>,
+    ) {
+        let frames = travelled.len();
+        let covered: Vec<usize> = (1..=frames).map(|f| easing.travelled(f, frames)).collect();
+        assert_eq!(covered, travelled);
+        assert_eq!(easing.travelled(0, frames), 0);
+    }
+}
@@ -0,0 +1,167 @@
+//! User scripts, written in Rhai and dropped into the scripts directory, that customize the game
+//! without rebuilding it. A script can define any of
+//!
+//! ```rhai
+//! // veto a move: `board` is the rows of tile values, 0 where a slot is empty, and `direction`
+//! // one of "left", "right", "up" or "down"
+//! fn on_move(board, direction) { direction != "up" }
+//!
+//! // label the tile of the given value, or return () to keep the label pack's
+//! fn tile_label(value) { if value == 2048 { "WIN" } }
+//!
+//! // hear about the game just finished, given its score, moves and max_tile
+//! fn on_game_over(stats) { print(`scored ${stats.score}`); }
+//! ```
+//!
+//! Scripts only see copies of the board and run with tight limits on how much they can do. A
+//! script that fails, or runs out of time, is turned off for the rest of the session and the
+//! player is told why. Builds without the `scripting` feature carry no interpreter at all: every
+//! hook allows everything and changes nothing.
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::engine::direction::Direction;
+use crate::engine::round::{display_value, Card, Round, Score, MAX_CARD};
+use crate::packs::validate_label;
+
+#[cfg(feature = "scripting")]
+mod host;
+
+#[cfg(feature = "scripting")]
+use host::Host;
+
+/// What a finished game is summed up as for `on_game_over`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GameSummary {
+    pub(crate) score: Score,
+    pub(crate) moves: usize,
+    pub(crate) max_tile: u32,
+}
+
+/// The scripts loaded for the session, along with what the player hasn't yet been told about
+/// them.
+pub(crate) struct Scripts {
+    host: Option<Host>,
+    notices: Vec<String>,
+}
+
+impl Scripts {
+    /// No scripts at all.
+    pub(crate) fn none() -> Self {
+        Self {
+            host: None,
+            notices: Vec::new(),
+        }
+    }
+
+    /// Loads every `.rhai` file in the given directory, in name order, except those turned off in
+    /// the given flags, which are keyed by file name. Scripts that can't be read or don't compile
+    /// are left out with a notice.
+    pub(crate) fn load(dir: &Path, enabled: &BTreeMap<String, bool>) -> Self {
+        let mut notices = Vec::new();
+        let host = Host::load(dir, enabled, &mut notices);
+        Self { host, notices }
+    }
+
+    /// Returns whether every script lets the given move be made on the given round.
+    pub(crate) fn allows_move(&mut self, round: &Round, direction: &Direction) -> bool {
+        match &mut self.host {
+            Some(host) => host.allows_move(round, direction, &mut self.notices),
+            None => true,
+        }
+    }
+
+    /// Returns the labels scripts give tiles in place of the label pack's, by card. Labels that
+    /// don't fit on a tile are left out with a notice.
+    pub(crate) fn tile_labels(&mut self) -> Vec<(Card, String)> {
+        let host = match &mut self.host {
+            Some(host) => host,
+            None => return Vec::new(),
+        };
+        let mut labels = Vec::new();
+        for card in 1..=MAX_CARD {
+            let label = match host.tile_label(display_value(card), &mut self.notices) {
+                Some(label) => label,
+                None => continue,
+            };
+            match validate_label(card, &label) {
+                Ok(()) => labels.push((card, label)),
+                Err(reason) => self
+                    .notices
+                    .push(format!("script label left out: {}", reason)),
+            }
+        }
+        labels
+    }
+
+    /// Tells every script the game is over.
+    pub(crate) fn game_over(&mut self, summary: &GameSummary) {
+        if let Some(host) = &mut self.host {
+            host.game_over(summary, &mut self.notices);
+        }
+    }
+
+    /// Returns what the player should be told about the scripts since they were last told, most
+    /// recent last.
+    pub(crate) fn take_notices(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.notices)
+    }
+}
+
+/// Stands in for the interpreter in builds without the `scripting` feature; it can't be created,
+/// so every hook is a no-op.
+#[cfg(not(feature = "scripting"))]
+enum Host {}
+
+#[cfg(not(feature = "scripting"))]
+impl Host {
+    fn load(dir: &Path, _enabled: &BTreeMap<String, bool>, _: &mut Vec<String>) -> Option<Self> {
+        log::debug!(
+            "not loading scripts from {}: built without scripting",
+            dir.display()
+        );
+        None
+    }
+
+    fn allows_move(&mut self, _: &Round, _: &Direction, _: &mut Vec<String>) -> bool {
+        match *self {}
+    }
+
+    fn tile_label(&mut self, _: u32, _: &mut Vec<String>) -> Option<String> {
+        match *self {}
+    }
+
+    fn game_over(&mut self, _: &GameSummary, _: &mut Vec<String>) {
+        match *self {}
+    }
+}
+
+#[cfg(all(test, not(feature = "scripting")))]
+mod test {
+    use super::*;
+    use crate::engine::fixtures::with_tiles;
+    use crate::engine::round::Idx;
+
+    #[test]
+    fn without_scripting_scripts_are_never_loaded_and_every_hook_is_a_no_op() {
+        let dir = std::env::temp_dir().join(format!("tui48-scripts-off-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("veto.rhai"),
+            "fn on_move(board, direction) { false }",
+        )
+        .unwrap();
+
+        let mut scripts = Scripts::load(&dir, &BTreeMap::new());
+        let round = with_tiles(&[(Idx(0, 0), 2)]);
+        assert!(scripts.allows_move(&round, &Direction::Up));
+        assert_eq!(scripts.tile_labels(), vec![]);
+        scripts.game_over(&GameSummary {
+            score: 4,
+            moves: 1,
+            max_tile: 2,
+        });
+        assert_eq!(scripts.take_notices(), Vec::<String>::new());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,346 @@
+//! Runs scripts in a Rhai interpreter locked down to what the hooks need.
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use rhai::{Array, Dynamic, Engine, FuncArgs, Map, Scope, AST, INT};
+
+use super::GameSummary;
+use crate::engine::direction::Direction;
+use crate::engine::round::{display_value, Round};
+
+/// Scripts are files with this extension.
+const EXTENSION: &str = "rhai";
+
+/// The most operations a single call into a script may take, enough for anything a hook should do
+/// and few enough that a runaway loop ends long before it's noticed.
+const MAX_OPERATIONS: u64 = 200_000;
+
+/// The longest a single call into a script may take.
+const TIME_LIMIT: Duration = Duration::from_millis(100);
+
+struct Script {
+    name: String,
+    ast: AST,
+    enabled: bool,
+}
+
+impl Script {
+    fn defines(&self, function: &str, params: usize) -> bool {
+        self.enabled
+            && self
+                .ast
+                .iter_functions()
+                .any(|f| f.name == function && f.params.len() == params)
+    }
+}
+
+/// The interpreter along with the scripts loaded into it.
+pub(super) struct Host {
+    engine: Engine,
+    scripts: Vec<Script>,
+    // when the call into a script currently running has to end by
+    deadline: Rc<Cell<Instant>>,
+}
+
+impl Host {
+    pub(super) fn load(
+        dir: &Path,
+        enabled: &BTreeMap<String, bool>,
+        notices: &mut Vec<String>,
+    ) -> Option<Self> {
+        let mut paths: Vec<_> = match std::fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                notices.push(format!("couldn't list scripts in {}: {}", dir.display(), e));
+                return None;
+            }
+        };
+        paths.sort();
+
+        let mut host = Self::new();
+        for path in paths {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if enabled.get(&name) == Some(&false) {
+                log::info!("script {} is turned off in the preferences", name);
+                continue;
+            }
+            let compiled = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| host.engine.compile(text).map_err(|e| e.to_string()));
+            match compiled {
+                Ok(ast) => {
+                    log::info!("loaded script {}", name);
+                    host.scripts.push(Script {
+                        name,
+                        ast,
+                        enabled: true,
+                    });
+                }
+                Err(e) => notices.push(format!("script {} wasn't loaded: {}", name, e)),
+            }
+        }
+        (!host.scripts.is_empty()).then_some(host)
+    }
+
+    fn new() -> Self {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(32);
+        engine.set_max_expr_depths(64, 32);
+        engine.set_max_string_size(1024);
+        engine.set_max_array_size(1024);
+        engine.set_max_map_size(64);
+        engine.disable_symbol("eval");
+        engine.on_print(|text| log::info!("script: {}", text));
+        engine.on_debug(|text, _, at| log::debug!("script at {}: {}", at, text));
+
+        let deadline = Rc::new(Cell::new(Instant::now()));
+        let until = deadline.clone();
+        engine.on_progress(move |_| {
+            (Instant::now() > until.get()).then(|| Dynamic::from("out of time".to_string()))
+        });
+        Self {
+            engine,
+            scripts: Vec::new(),
+            deadline,
+        }
+    }
+
+    pub(super) fn allows_move(
+        &mut self,
+        round: &Round,
+        direction: &Direction,
+        notices: &mut Vec<String>,
+    ) -> bool {
+        let board: Array = round
+            .iter_rows()
+            .map(|row| {
+                let row: Array = row
+                    .iter()
+                    .map(|card| Dynamic::from(display_value(*card) as INT))
+                    .collect();
+                Dynamic::from(row)
+            })
+            .collect();
+        let direction = direction.to_string();
+        self.call("on_move", 2, notices, || (board.clone(), direction.clone()))
+            .into_iter()
+            .all(|allowed| allowed.as_bool().unwrap_or(true))
+    }
+
+    pub(super) fn tile_label(&mut self, value: u32, notices: &mut Vec<String>) -> Option<String> {
+        // the last script to label the tile has the final say
+        self.call("tile_label", 1, notices, || (value as INT,))
+            .into_iter()
+            .filter(|label| !label.is_unit())
+            .filter_map(|label| label.into_string().ok())
+            .last()
+    }
+
+    pub(super) fn game_over(&mut self, summary: &GameSummary, notices: &mut Vec<String>) {
+        let mut stats = Map::new();
+        stats.insert("score".into(), Dynamic::from(summary.score as INT));
+        stats.insert("moves".into(), Dynamic::from(summary.moves as INT));
+        stats.insert("max_tile".into(), Dynamic::from(summary.max_tile as INT));
+        let _ = self.call("on_game_over", 1, notices, || (stats.clone(),));
+    }
+
+    /// Calls the given function in every enabled script that defines it, returning what each
+    /// call returned. A call that fails, returns the wrong kind of value or takes too long turns
+    /// its script off with a notice.
+    fn call<A: FuncArgs>(
+        &mut self,
+        function: &str,
+        params: usize,
+        notices: &mut Vec<String>,
+        args: impl Fn() -> A,
+    ) -> Vec<Dynamic> {
+        let mut returned = Vec::new();
+        for script in self.scripts.iter_mut() {
+            if !script.defines(function, params) {
+                continue;
+            }
+            self.deadline.set(Instant::now() + TIME_LIMIT);
+            let result =
+                self.engine
+                    .call_fn::<Dynamic>(&mut Scope::new(), &script.ast, function, args());
+            let result = result.map_err(|e| e.to_string()).and_then(|value| {
+                match (function, value.type_name()) {
+                    ("on_move", "bool") | ("on_game_over", _) => Ok(value),
+                    ("tile_label", "string" | "()") => Ok(value),
+                    (_, kind) => Err(format!("{} returned {} instead", function, kind)),
+                }
+            });
+            match result {
+                Ok(value) => returned.push(value),
+                Err(e) => {
+                    script.enabled = false;
+                    log::warn!("turned off script {}: {}", script.name, e);
+                    notices.push(format!("script {} failed and is off: {}", script.name, e));
+                }
+            }
+        }
+        returned
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::super::Scripts;
+    use super::*;
+    use crate::engine::fixtures::with_tiles;
+    use crate::engine::round::Idx;
+
+    /// Writes each of the given scripts into a directory of its own, named after the test.
+    fn scripts_dir(test: &str, scripts: &[(&str, &str)]) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("tui48-scripts-{}-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, text) in scripts {
+            std::fs::write(dir.join(name), text).unwrap();
+        }
+        dir
+    }
+
+    fn round() -> Round {
+        with_tiles(&[(Idx(0, 0), 2), (Idx(1, 0), 2)])
+    }
+
+    #[rstest]
+    #[case::left(Direction::Left, true)]
+    #[case::right(Direction::Right, true)]
+    #[case::up(Direction::Up, false)]
+    #[case::down(Direction::Down, true)]
+    fn a_veto_script_blocks_exactly_its_direction(
+        #[case] direction: Direction,
+        #[case] allowed: bool,
+    ) {
+        let name = format!("veto-{}", direction);
+        let dir = scripts_dir(
+            &name,
+            &[(
+                "never-up.rhai",
+                r#"fn on_move(board, direction) { direction != "up" }"#,
+            )],
+        );
+        let mut scripts = Scripts::load(&dir, &BTreeMap::new());
+        assert_eq!(scripts.allows_move(&round(), &direction), allowed);
+        assert_eq!(scripts.take_notices(), Vec::<String>::new());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn scripts_see_the_values_on_the_board() {
+        let dir = scripts_dir(
+            "board",
+            &[(
+                "corner.rhai",
+                "fn on_move(board, direction) { board[0] == [2, 2, 0, 0] && board[3][3] == 0 }",
+            )],
+        );
+        let mut scripts = Scripts::load(&dir, &BTreeMap::new());
+        assert!(scripts.allows_move(&round(), &Direction::Left));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn an_endless_script_is_stopped_and_turned_off() {
+        let dir = scripts_dir(
+            "endless",
+            &[("spin.rhai", "fn on_move(board, direction) { loop {} }")],
+        );
+        let mut scripts = Scripts::load(&dir, &BTreeMap::new());
+        let started = Instant::now();
+        assert!(scripts.allows_move(&round(), &Direction::Up));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let notices = scripts.take_notices();
+        assert_eq!(notices.len(), 1);
+        assert!(notices[0].contains("spin.rhai"), "{}", notices[0]);
+
+        // turned off, it isn't run again
+        assert!(scripts.allows_move(&round(), &Direction::Up));
+        assert_eq!(scripts.take_notices(), Vec::<String>::new());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[rstest]
+    #[case::does_not_compile("fn on_move(board, direction) {")]
+    #[case::wrong_kind_of_value("fn on_move(board, direction) { 3 }")]
+    #[case::error("fn on_move(board, direction) { throw \"no\" }")]
+    fn a_failing_script_is_turned_off_without_vetoing(#[case] text: &str) {
+        let name = format!("failing-{}", text.len());
+        let dir = scripts_dir(&name, &[("broken.rhai", text)]);
+        let mut scripts = Scripts::load(&dir, &BTreeMap::new());
+        assert!(scripts.allows_move(&round(), &Direction::Up));
+        assert_eq!(scripts.take_notices().len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn scripts_turned_off_in_the_preferences_are_not_loaded() {
+        let dir = scripts_dir(
+            "disabled",
+            &[(
+                "never-up.rhai",
+                r#"fn on_move(board, direction) { direction != "up" }"#,
+            )],
+        );
+        let enabled = BTreeMap::from([("never-up.rhai".to_string(), false)]);
+        let mut scripts = Scripts::load(&dir, &enabled);
+        assert!(scripts.allows_move(&round(), &Direction::Up));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn label_scripts_relabel_only_the_tiles_they_label() {
+        let dir = scripts_dir(
+            "labels",
+            &[
+                (
+                    "win.rhai",
+                    r#"fn tile_label(value) { if value == 2048 { "WIN" } }"#,
+                ),
+                (
+                    "wide.rhai",
+                    r#"fn tile_label(value) { if value == 4 { "far too wide" } }"#,
+                ),
+            ],
+        );
+        let mut scripts = Scripts::load(&dir, &BTreeMap::new());
+        assert_eq!(scripts.tile_labels(), vec![(11, "WIN".to_string())]);
+        assert_eq!(scripts.take_notices().len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn game_over_hands_scripts_the_summary() {
+        let dir = scripts_dir(
+            "game-over",
+            &[(
+                "check.rhai",
+                "fn on_game_over(stats) { if stats.score != 1234 { throw \"wrong score\" } }",
+            )],
+        );
+        let mut scripts = Scripts::load(&dir, &BTreeMap::new());
+        scripts.game_over(&GameSummary {
+            score: 1234,
+            moves: 100,
+            max_tile: 128,
+        });
+        assert_eq!(scripts.take_notices(), Vec::<String>::new());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,275 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use crate::engine::round::Score;
+
+/// Aggregate statistics for every game played since the program started.
+///
+/// Games that end without any moves having been made aren't counted at all; games left before they
+/// were over, either by quitting or by starting a new game, are counted as abandoned.
+pub(crate) struct Session {
+    started: Instant,
+    duration: Option<Duration>,
+    completed: usize,
+    abandoned: usize,
+    moves: usize,
+    merges: usize,
+    best_score: Score,
+    total_score: u64,
+    // moves made in the game currently being played; reset whenever a game ends
+    game_moves: usize,
+    // games started from practice positions rather than an empty board
+    practice: bool,
+    // games played in the arcade mode, where power-ups help
+    arcade: bool,
+    // games played in the growth mode, where the board grows
+    growth: bool,
+}
+
+impl Session {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            duration: None,
+            completed: 0,
+            abandoned: 0,
+            moves: 0,
+            merges: 0,
+            best_score: 0,
+            total_score: 0,
+            game_moves: 0,
+            practice: false,
+            arcade: false,
+            growth: false,
+        }
+    }
+
+    /// A session whose games start from practice positions. Its summary is labelled as such so
+    /// that its scores aren't mistaken for ones reached from an empty board.
+    pub(crate) fn practice() -> Self {
+        Self {
+            practice: true,
+            ..Self::new()
+        }
+    }
+
+    /// Labels the summary as that of an arcade session, so that its scores aren't mistaken for
+    /// ones reached without power-ups.
+    pub(crate) fn set_arcade(&mut self) {
+        self.arcade = true;
+    }
+
+    /// Labels the summary as that of a growth session, so that its scores aren't mistaken for
+    /// ones reached on a board that stays the same size.
+    pub(crate) fn set_growth(&mut self) {
+        self.growth = true;
+    }
+
+    /// Records a move in the current game that merged the given number of tile pairs.
+    pub(crate) fn record_move(&mut self, merges: usize) {
+        self.moves += 1;
+        self.merges += merges;
+        self.game_moves += 1;
+    }
+
+    /// Records the current game as played to completion with the given final score.
+    pub(crate) fn finish_game(&mut self, score: Score) {
+        self.completed += 1;
+        self.end_game(score);
+    }
+
+    /// Records the current game as abandoned with the given score, unless no moves were made in it
+    /// since it started.
+    pub(crate) fn abandon_game(&mut self, score: Score) {
+        if self.game_moves == 0 {
+            return;
+        }
+        self.abandoned += 1;
+        self.end_game(score);
+    }
+
+    /// Stops the session clock.
+    pub(crate) fn close(&mut self) {
+        self.duration.get_or_insert(self.started.elapsed());
+    }
+
+    pub(crate) fn games_played(&self) -> usize {
+        self.completed + self.abandoned
+    }
+
+    pub(crate) fn average_score(&self) -> Option<Score> {
+        match self.games_played() {
+            0 => None,
+            n => Some((self.total_score / n as u64) as Score),
+        }
+    }
+
+    /// Writes a short human-readable summary of the session.
+    pub(crate) fn write_summary<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        let duration = self.duration.unwrap_or_else(|| self.started.elapsed());
+        let kinds = [
+            (self.practice, "practice "),
+            (self.arcade, "arcade "),
+            (self.growth, "growth "),
+        ];
+        let kind: String = kinds
+            .iter()
+            .filter(|(applies, _)| *applies)
+            .map(|(_, kind)| *kind)
+            .collect();
+        writeln!(w, "{}session summary", kind)?;
+        match self.average_score() {
+            None => writeln!(w, "  games played   0")?,
+            Some(average) => {
+                writeln!(
+                    w,
+                    "  games played   {} ({} abandoned)",
+                    self.games_played(),
+                    self.abandoned
+                )?;
+                writeln!(w, "  moves          {}", self.moves)?;
+                writeln!(w, "  merges         {}", self.merges)?;
+                writeln!(w, "  best score     {}", self.best_score)?;
+                writeln!(w, "  average score  {}", average)?;
+            }
+        }
+        writeln!(w, "  duration       {}", format_duration(duration))?;
+        Ok(())
+    }
+
+    /// The summary `write_summary` writes, eg for keeping a record of the session.
+    pub(crate) fn summary(&self) -> String {
+        let mut out = Vec::new();
+        self.write_summary(&mut out)
+            .expect("writing to a Vec shouldn't fail");
+        String::from_utf8(out).expect("the summary should be valid utf-8")
+    }
+
+    fn end_game(&mut self, score: Score) {
+        self.best_score = self.best_score.max(score);
+        self.total_score += score as u64;
+        self.game_moves = 0;
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn multi_game_aggregates() {
+        let mut session = Session::new();
+
+        // completed game
+        session.record_move(0);
+        session.record_move(2);
+        session.record_move(1);
+        session.finish_game(300);
+
+        // new game started without moving; shouldn't count
+        session.abandon_game(0);
+
+        // abandoned game
+        session.record_move(1);
+        session.abandon_game(100);
+
+        // game quit right after it started; shouldn't count
+        session.abandon_game(0);
+
+        session.duration = Some(Duration::from_secs(3725));
+        assert_eq!(session.games_played(), 2);
+        assert_eq!(session.average_score(), Some(200));
+        assert_eq!(
+            session.summary(),
+            "session summary
+  games played   2 (1 abandoned)
+  moves          4
+  merges         4
+  best score     300
+  average score  200
+  duration       01:02:05
+"
+        );
+    }
+
+    #[test]
+    fn single_game_summary() {
+        let mut session = Session::new();
+        session.record_move(1);
+        session.finish_game(4);
+        session.duration = Some(Duration::from_secs(42));
+
+        assert_eq!(
+            session.summary(),
+            "session summary
+  games played   1 (0 abandoned)
+  moves          1
+  merges         1
+  best score     4
+  average score  4
+  duration       00:00:42
+"
+        );
+    }
+
+    #[test]
+    fn practice_session_summary() {
+        let mut session = Session::practice();
+        session.record_move(0);
+        session.finish_game(2000);
+        session.duration = Some(Duration::from_secs(1));
+
+        let summary = session.summary();
+        assert!(
+            summary.starts_with("practice session summary\n"),
+            "{}",
+            summary
+        );
+        assert!(summary.contains("best score     2000"), "{}", summary);
+    }
+
+    #[test]
+    fn arcade_session_summary() {
+        let mut session = Session::practice();
+        session.set_arcade();
+        session.record_move(0);
+        session.finish_game(16);
+        session.duration = Some(Duration::from_secs(1));
+
+        let summary = session.summary();
+        assert!(
+            summary.starts_with("practice arcade session summary\n"),
+            "{}",
+            summary
+        );
+    }
+
+    #[test]
+    fn empty_session_summary() {
+        let mut session = Session::new();
+        session.abandon_game(0);
+        session.duration = Some(Duration::ZERO);
+
+        assert_eq!(
+            session.summary(),
+            "session summary
+  games played   0
+  duration       00:00:00
+"
+        );
+    }
+
+    #[test]
+    fn close_stops_the_clock() {
+        let mut session = Session::new();
+        session.close();
+        let duration = session.duration;
+        session.close();
+        assert_eq!(session.duration, duration);
+    }
+}
@@ -0,0 +1,63 @@
+use std::io::IsTerminal;
+
+use crate::error::{Error, Result};
+
+/// Whether the standard streams are connected to a terminal.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Ttys {
+    pub(crate) stdin: bool,
+    pub(crate) stdout: bool,
+}
+
+impl Ttys {
+    pub(crate) fn detect() -> Self {
+        Self {
+            stdin: std::io::stdin().is_terminal(),
+            stdout: std::io::stdout().is_terminal(),
+        }
+    }
+}
+
+/// The renderer and event source combination to construct.
+#[derive(Debug, PartialEq)]
+pub(crate) enum RunPlan {
+    /// Draw to the terminal on stdout and read key presses from the terminal on stdin.
+    Interactive,
+}
+
+/// Decides how to run given where the standard streams point. This must be called before anything
+/// touches terminal modes so that a refusal leaves the shell as it was.
+///
+/// Every renderer and event source so far needs a terminal, so both streams must be terminals;
+/// headless renderers and non-interactive event sources belong here as they are added.
+pub(crate) fn validate(ttys: Ttys) -> Result<RunPlan> {
+    if !ttys.stdout {
+        return Err(Error::StdoutNotATerminal);
+    }
+    if !ttys.stdin {
+        return Err(Error::StdinNotATerminal);
+    }
+    Ok(RunPlan::Interactive)
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+
+    #[rstest]
+    #[case::interactive(true, true, Some(RunPlan::Interactive))]
+    #[case::redirected_stdout(true, false, None)]
+    #[case::redirected_stdin(false, true, None)]
+    #[case::detached(false, false, None)]
+    fn validate_matrix(#[case] stdin: bool, #[case] stdout: bool, #[case] plan: Option<RunPlan>) {
+        let result = validate(Ttys { stdin, stdout });
+        match (result, plan) {
+            (Ok(actual), Some(expected)) => assert_eq!(actual, expected),
+            (Err(Error::StdoutNotATerminal), None) => assert!(!stdout),
+            (Err(Error::StdinNotATerminal), None) => assert!(stdout && !stdin),
+            (result, plan) => panic!("expected {:?}, got {:?}", plan, result),
+        }
+    }
+}
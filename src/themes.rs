@@ -0,0 +1,160 @@
+//! Themes color the tiles. Every theme spreads the tiles around the color wheel the same way and
+//! differs in how light and how saturated it makes them; the board and the boxes above it look
+//! the same whatever the theme.
+use palette::{FromColor, Lch, Srgb};
+
+use crate::engine::round::{Card, MAX_CARD};
+use crate::tui::canvas::Modifier;
+
+/// The themes that come with the game.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum BuiltinTheme {
+    /// Bright tiles with dark labels, as tiles have always been colored.
+    Classic,
+    /// Deep tiles with light labels.
+    Dusk,
+    /// Pale, washed out tiles with dark labels.
+    Paper,
+}
+
+impl BuiltinTheme {
+    pub(crate) const ALL: [BuiltinTheme; 3] = [Self::Classic, Self::Dusk, Self::Paper];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::Dusk => "dusk",
+            Self::Paper => "paper",
+        }
+    }
+
+    /// The lightness of the tiles and of their labels, and how saturated the tiles are compared
+    /// to the classic theme.
+    fn palette(&self) -> (f32, f32, f32) {
+        match self {
+            Self::Classic => (80.0, 20.0, 1.0),
+            Self::Dusk => (35.0, 90.0, 0.8),
+            Self::Paper => (92.0, 25.0, 0.35),
+        }
+    }
+}
+
+type Color = (u8, u8, u8);
+
+/// The background and foreground color of every tile from 2 up to the largest tile a board can
+/// hold.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Theme {
+    theme: BuiltinTheme,
+    // the colors for exponent n are at index n - 1
+    card_colors: Vec<(Color, Color)>,
+}
+
+impl Theme {
+    /// Generates the colors of the given theme.
+    pub(crate) fn builtin(theme: BuiltinTheme) -> Self {
+        let (bg_lightness, fg_lightness, chroma) = theme.palette();
+        let fg_hue = 28.0 + 180.0;
+        let incr = |inc: u8, num: f32, div: u8| -> f32 { inc as f32 * num / div as f32 };
+        let bg_hue = |i: u8| -> f32 { incr(i, 360.0, MAX_CARD) };
+        let bg_chroma = |i: u8| -> f32 { (30.0 + incr(i, 60.0, i)) * chroma };
+        let fg_chroma = |i: u8| -> f32 { 90.0 - incr(i, 40.0, MAX_CARD / 2) };
+        let rgb = |lch: Lch| -> Color {
+            let rgb = Srgb::from_color(lch).into_format::<u8>();
+            (rgb.red, rgb.green, rgb.blue)
+        };
+        Self {
+            theme,
+            card_colors: (1..=MAX_CARD)
+                .map(|i| {
+                    (
+                        rgb(Lch::new(bg_lightness, bg_chroma(i), bg_hue(i))),
+                        rgb(Lch::new(fg_lightness, fg_chroma(i), fg_hue)),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn theme(&self) -> BuiltinTheme {
+        self.theme
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        self.theme.name()
+    }
+
+    /// Whether the tiles are darker than their labels.
+    pub(crate) fn is_dark(&self) -> bool {
+        let (bg_lightness, fg_lightness, _) = self.theme.palette();
+        bg_lightness < fg_lightness
+    }
+
+    /// The modifiers that color a tile of the given card, background first. Tiles larger than a
+    /// 4x4 board can hold, which only larger boards get to, are colored like the largest.
+    pub(crate) fn colors(&self, card: Card) -> (Modifier, Modifier) {
+        let largest = self.card_colors.len() - 1;
+        let ((br, bg, bb), (fr, fg, fb)) = match card.checked_sub(1) {
+            Some(i) => self.card_colors[(i as usize).min(largest)],
+            None => ((255, 255, 255), (90, 0, 0)),
+        };
+        (
+            Modifier::SetBackgroundColor(br, bg, bb),
+            Modifier::SetForegroundColor(fr, fg, fb),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+
+    fn background(theme: &Theme, card: Card) -> Color {
+        match theme.colors(card).0 {
+            Modifier::SetBackgroundColor(r, g, b) => (r, g, b),
+            _ => panic!("the background comes first"),
+        }
+    }
+
+    #[test]
+    fn every_theme_tells_tiles_apart() {
+        for theme in BuiltinTheme::ALL.map(Theme::builtin) {
+            let mut seen: Vec<Color> = (1..=MAX_CARD).map(|c| background(&theme, c)).collect();
+            seen.sort();
+            seen.dedup();
+            assert_eq!(seen.len(), MAX_CARD as usize, "{}", theme.name());
+        }
+    }
+
+    #[test]
+    fn tiles_past_the_largest_are_colored_like_it() {
+        for theme in BuiltinTheme::ALL.map(Theme::builtin) {
+            for card in MAX_CARD + 1..=MAX_CARD + 3 {
+                assert!(
+                    theme.colors(card) == theme.colors(MAX_CARD),
+                    "{}",
+                    theme.name()
+                );
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::classic(BuiltinTheme::Classic, false)]
+    #[case::dusk(BuiltinTheme::Dusk, true)]
+    #[case::paper(BuiltinTheme::Paper, false)]
+    fn themes_know_whether_they_are_dark(#[case] theme: BuiltinTheme, #[case] dark: bool) {
+        assert_eq!(Theme::builtin(theme).is_dark(), dark);
+    }
+
+    #[test]
+    fn themes_are_saved_by_name() {
+        for theme in BuiltinTheme::ALL {
+            let value = toml::Value::try_from(theme).expect("themes serialize");
+            assert_eq!(value.as_str(), Some(theme.name()));
+        }
+    }
+}
@@ -1,27 +1,81 @@
-use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender};
+use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Mutex, MutexGuard};
 
+use parking_lot::{Mutex as ReceiverMutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
 use super::colors::Rgb;
-use super::drawbuffer::{DBTuxel, DrawBuffer, DrawBufferOwner};
+use super::dirty::DirtySet;
+use super::drawbuffer::{DBTuxel, DrawBuffer, DrawBufferOwner, Owner};
 use super::textbuffer::TextBuffer;
 use super::error::{InnerError, Result, TuiError};
 use super::geometry::{Bounds2D, Geometry, Idx, Rectangle};
 use super::tuxel::Tuxel;
 
-const CANVAS_DEPTH: usize = 8;
+/// The number of layers a canvas has unless it is created with another depth.
+pub(crate) const DEFAULT_CANVAS_DEPTH: usize = 9;
+
+/// The most layers a canvas can have. Every cell keeps a slot for each layer, so depth costs memory
+/// across the whole canvas.
+pub(crate) const MAX_CANVAS_DEPTH: usize = 16;
+
+/// Reports the size of whatever a canvas is drawn to, so that the canvas can follow it without
+/// depending on how the size is found out.
+pub(crate) trait CanvasSizeSource {
+    fn terminal_size(&self) -> Result<(usize, usize)>;
+}
 
 struct CanvasInner {
     grid: Vec<Vec<Stack>>,
     rectangle: Rectangle,
+    depth: usize,
 
-    idx_receiver: Receiver<Idx>,
-    idx_sender: SyncSender<Idx>,
+    // the cells changed since get_changed was last called, marked by the tuxels drawn on them
+    changes: DirtySet,
 
-    tuxel_receiver: Receiver<Tuxel>,
+    // receivers can't be shared between threads, so this one's wrapped to let readers share the
+    // canvas; it's only ever used under the canvas write lock, through `get_mut`
+    tuxel_receiver: ReceiverMutex<Receiver<Tuxel>>,
     tuxel_sender: Sender<Tuxel>,
+
+    // how many cells the last call to get_changed returned
+    last_changed: usize,
+    // how many times get_changed has been called
+    changes_collected: usize,
 }
 
 impl CanvasInner {
+    fn new(width: usize, height: usize, depth: usize) -> Self {
+        let rectangle = Rectangle(Idx(0, 0, 0), Bounds2D(width, height));
+        let mut grid: Vec<Vec<Stack>> = Vec::with_capacity(height);
+        for y in 0..height {
+            let mut row: Vec<Stack> = Vec::with_capacity(width);
+            for x in 0..width {
+                row.push(Stack::new(x, y, depth));
+            }
+            grid.push(row);
+        }
+
+        let (tuxel_sender, tuxel_receiver) = channel();
+        Self {
+            grid,
+            rectangle,
+            depth,
+            changes: DirtySet::new(width, height),
+            tuxel_sender,
+            tuxel_receiver: ReceiverMutex::new(tuxel_receiver),
+            last_changed: 0,
+            changes_collected: 0,
+        }
+    }
+
+    /// Returns the owner of some cell still drawn on, if any.
+    fn any_owner(&self) -> Option<Owner> {
+        self.grid
+            .iter()
+            .flatten()
+            .find_map(|stack| (0..self.depth).find_map(|z| stack.owner(z)))
+    }
+
     fn dimensions(&self) -> (usize, usize) {
         (self.rectangle.1 .0, self.rectangle.1 .1)
     }
@@ -30,62 +84,102 @@ impl CanvasInner {
         self.rectangle.1.clone()
     }
 
-    fn get_changed(&self) -> Vec<Stack> {
-        let mut stacks = Vec::new();
-        loop {
-            match self.idx_receiver.try_recv() {
-                Ok(idx) => stacks.push(self.grid[idx.1][idx.0].clone()),
-                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
-                    unreachable!();
-                }
-                Err(std::sync::mpsc::TryRecvError::Empty) => break,
-            }
-        }
+    fn get_changed(&mut self) -> Vec<Stack> {
+        let stacks: Vec<Stack> = self
+            .changes
+            .drain()
+            .into_iter()
+            .map(|(x, y)| self.grid[y][x].clone())
+            .collect();
+        self.last_changed = stacks.len();
+        self.changes_collected += 1;
         stacks
     }
 
+    /// Resolves every cell of the given row to what it shows, locking each of its stacks once.
+    fn snapshot_row(&self, y: usize) -> Vec<CellRender> {
+        self.grid
+            .get(y)
+            .map_or_else(Vec::new, |row| row.iter().map(Stack::render).collect())
+    }
+
+    // buffers reclaim their cells as they're dropped, so this mustn't panic: a panic while
+    // unwinding aborts
     fn reclaim(&mut self) {
-        loop {
-            match self.tuxel_receiver.try_recv() {
-                Ok(tuxel) => {
-                    let idx = tuxel.idx();
-                    let _ = self.grid[idx.y()][idx.x()].replace(idx.z(), Cell::Empty);
-                    self.idx_sender
-                        .send(idx)
-                        .expect("idx sender should have plenty of room for more idxes");
-                }
-                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
-                    unreachable!();
+        // the canvas holds a sender of its own, so the channel can't be disconnected
+        while let Ok(tuxel) = self.tuxel_receiver.get_mut().try_recv() {
+            let idx = tuxel.idx();
+            // a tuxel from before a resize may point outside the canvas
+            let row = self.grid.get_mut(idx.y());
+            if let Some(stack) = row.and_then(|row| row.get_mut(idx.x())) {
+                let _ = stack.replace(idx.z(), Cell::Empty);
+                self.changes.mark(&idx);
+            }
+        }
+    }
+
+    /// Empties every cell still owned by a buffer that's gone, returning how many there were.
+    fn sanitize(&mut self) -> usize {
+        self.reclaim();
+        let mut swept = 0;
+        for stack in self.grid.iter_mut().flatten() {
+            let mut inner = stack.lock();
+            let idx = inner.idx.clone();
+            for cell in inner.cells.iter_mut() {
+                if cell.is_orphaned() {
+                    *cell = Cell::Empty;
+                    self.changes.mark(&idx);
+                    swept += 1;
                 }
-                Err(std::sync::mpsc::TryRecvError::Empty) => break,
             }
         }
+        swept
+    }
+
+    #[cfg(test)]
+    fn orphaned_cells(&self) -> usize {
+        self.grid
+            .iter()
+            .flatten()
+            .map(|stack| {
+                let inner = stack.lock();
+                inner.cells.iter().filter(|c| c.is_orphaned()).count()
+            })
+            .sum()
     }
 
     fn acquire_cell(&mut self, idx: &Idx) -> Result<Cell> {
-        Ok(self
-            .grid
+        self.grid
             .get_mut(idx.y())
             .ok_or(InnerError::OutOfBoundsY(idx.y()))?
             .get_mut(idx.x())
             .ok_or(InnerError::OutOfBoundsX(idx.x()))?
-            .acquire(idx.z()))
+            .acquire(idx.z())
     }
 
     fn replace_cell(&mut self, idx: &Idx, cell: Cell) -> Result<()> {
-        Ok(self
-            .grid
+        self.grid
             .get_mut(idx.y())
             .ok_or(InnerError::OutOfBoundsY(idx.y()))?
             .get_mut(idx.x())
             .ok_or(InnerError::OutOfBoundsX(idx.x()))?
-            .replace(idx.z(), cell))
+            .replace(idx.z(), cell)
+    }
+
+    fn layer_exists_or_err(&self, z: usize) -> Result<()> {
+        if z < self.depth {
+            Ok(())
+        } else {
+            Err(InnerError::OutOfBoundsZ(z).into())
+        }
     }
 
     fn swap_tuxels(&mut self, from_idx: Idx, to_idx: Idx) -> Result<()> {
         log::trace!("swapping {0} and {1}", from_idx, to_idx);
         self.rectangle.contains_or_err(Geometry::Idx(&from_idx))?;
         self.rectangle.contains_or_err(Geometry::Idx(&to_idx))?;
+        self.layer_exists_or_err(from_idx.z())?;
+        self.layer_exists_or_err(to_idx.z())?;
         let mut from_cell = self.acquire_cell(&from_idx)?;
         let mut to_cell = match self.acquire_cell(&to_idx) {
             Err(e) => {
@@ -131,18 +225,41 @@ impl CanvasInner {
 
         self.replace_cell(&from_idx, to_cell)?;
         self.replace_cell(&to_idx, from_cell)?;
-        self.idx_sender.send(from_idx)?;
-        self.idx_sender.send(to_idx)?;
+        self.changes.mark(&from_idx);
+        self.changes.mark(&to_idx);
 
         Ok(())
     }
 
+    /// Moves a tuxel into a cell that no buffer owns, refusing to displace another buffer's
+    /// tuxel the way a swap would.
+    fn move_tuxel(&mut self, from_idx: Idx, to_idx: Idx, requested: &Owner) -> Result<()> {
+        let current = self
+            .grid
+            .get(to_idx.y())
+            .and_then(|row| row.get(to_idx.x()))
+            .and_then(|stack| stack.owner(to_idx.z()));
+        if let Some(current) = current {
+            return Err(InnerError::CellAlreadyOwned {
+                idx: to_idx,
+                requested: requested.clone(),
+                current,
+            }
+            .into());
+        }
+        self.swap_tuxels(from_idx, to_idx)
+    }
+
     fn swap_rectangles(&mut self, rect1: &Rectangle, rect2: &Rectangle) -> Result<()> {
         if rect1 == rect2 {
             return Ok(());
         } else if rect1.width() != rect2.width() || rect1.height() != rect2.height() {
             return Err(InnerError::RectangleDimensionsMustMatch.into());
         }
+        // checked up front so that a swap onto a missing layer doesn't leave the rectangles
+        // half swapped
+        self.layer_exists_or_err(rect1.z())?;
+        self.layer_exists_or_err(rect2.z())?;
 
         let rect1_indices = rect1.clone().into_iter();
         let rect2_indices = rect2.clone().into_iter();
@@ -154,6 +271,15 @@ impl CanvasInner {
         Ok(())
     }
 
+    fn contains_or_err(&self, r: &Rectangle, owner: &Owner) -> Result<()> {
+        self.rectangle
+            .contains_or_err(Geometry::Rectangle(r))
+            .and_then(|_| self.layer_exists_or_err(r.z()))
+            .inspect_err(|e| {
+                log::debug!("{} requested {} outside the canvas: {}", owner, r, e.inner)
+            })
+    }
+
     fn layer_occupied(&self, zdx: usize) -> bool {
         for row in self.grid.iter() {
             for stack in row.iter() {
@@ -168,18 +294,25 @@ impl CanvasInner {
 
 impl std::fmt::Display for CanvasInner {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for i in 0..CANVAS_DEPTH {
+        for i in 0..self.depth {
             if !self.layer_occupied(i) {
                 continue;
             }
             write!(f, "canvas layer {}:\n", i)?;
+            let mut owners: Vec<Owner> = Vec::new();
             for row in self.grid.iter() {
                 for stack in row.iter() {
                     write!(f, "{}", stack.display_cell_type(i))?;
+                    if let Some(owner) = stack.owner(i) {
+                        if !owners.contains(&owner) {
+                            owners.push(owner);
+                        }
+                    }
                 }
                 write!(f, "\n")?;
             }
-            write!(f, "\n")?;
+            let owners: Vec<String> = owners.iter().map(|o| o.to_string()).collect();
+            writeln!(f, "owners: {}", owners.join(", "))?;
             write!(f, "\n")?;
         }
         Ok(())
@@ -187,79 +320,195 @@ impl std::fmt::Display for CanvasInner {
 }
 
 /// A 2d grid of `Cell`s.
+///
+/// Queries that only look at the canvas, like its dimensions, can run concurrently. Anything that
+/// acquires, releases or moves cells, including `get_changed` which collects the changed cells,
+/// takes exclusive access.
 #[derive(Clone)]
 pub(crate) struct Canvas {
-    inner: Arc<Mutex<CanvasInner>>,
+    inner: Arc<RwLock<CanvasInner>>,
 }
 
 impl std::fmt::Display for Canvas {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.lock())
+        write!(f, "{}", self.read())
     }
 }
 
 impl Canvas {
+    /// Creates a canvas of `DEFAULT_CANVAS_DEPTH` layers.
     pub(crate) fn new(width: usize, height: usize) -> Self {
-        let rectangle = Rectangle(Idx(0, 0, 0), Bounds2D(width, height));
-        let mut grid: Vec<Vec<Stack>> = Vec::with_capacity(height);
-        for y in 0..height {
-            let mut row: Vec<Stack> = Vec::with_capacity(width);
-            for x in 0..width {
-                row.push(Stack::new(x, y));
+        Self::build(width, height, DEFAULT_CANVAS_DEPTH)
+    }
+
+    /// Creates a canvas with the given number of layers, which must be at least one and no more
+    /// than `MAX_CANVAS_DEPTH`.
+    pub(crate) fn with_depth(width: usize, height: usize, depth: usize) -> Result<Self> {
+        Self::depth_allowed_or_err(depth)?;
+        Ok(Self::build(width, height, depth))
+    }
+
+    /// Checks that a canvas can have the given number of layers, without creating one.
+    pub(crate) fn depth_allowed_or_err(depth: usize) -> Result<()> {
+        if (1..=MAX_CANVAS_DEPTH).contains(&depth) {
+            Ok(())
+        } else {
+            Err(InnerError::InvalidCanvasDepth {
+                requested: depth,
+                max: MAX_CANVAS_DEPTH,
             }
-            grid.push(row);
+            .into())
         }
+    }
 
-        let (idx_sender, idx_receiver) = sync_channel(width * height * 20);
-        let (tuxel_sender, tuxel_receiver) = channel();
-        let c = Self {
-            inner: Arc::new(Mutex::new(CanvasInner {
-                grid,
-                rectangle,
-                idx_sender,
-                idx_receiver,
-                tuxel_sender,
-                tuxel_receiver,
-            })),
-        };
+    fn build(width: usize, height: usize, depth: usize) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(CanvasInner::new(width, height, depth))),
+        }
+    }
+
+    /// Resizes the canvas in place, keeping its depth, so that every clone of it sees the new
+    /// size. Cells still owned by a buffer would be left pointing outside the canvas, so every
+    /// buffer has to be dropped first. The resized canvas starts out blank.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn resize(&mut self, width: usize, height: usize) -> Result<()> {
+        let mut inner = self.write();
+        if let Some(owner) = inner.any_owner() {
+            return Err(InnerError::CanvasInUse(owner).into());
+        }
+        let depth = inner.depth;
+        *inner = CanvasInner::new(width, height, depth);
+        Ok(())
+    }
+
+    /// Resizes the canvas in place to the size `source` reports; see `resize`.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn resize_to_terminal(&mut self, source: &impl CanvasSizeSource) -> Result<()> {
+        let (width, height) = source.terminal_size()?;
+        self.resize(width, height)
+    }
 
-        c
+    fn read(&self) -> RwLockReadGuard<'_, CanvasInner> {
+        self.inner.read()
     }
 
-    fn lock(&self) -> MutexGuard<CanvasInner> {
-        self.inner
-            .lock()
-            .expect("TODO: handle mutex lock errors more gracefully")
+    fn write(&self) -> RwLockWriteGuard<'_, CanvasInner> {
+        self.inner.write()
     }
 
-    pub(crate) fn get_draw_buffer(&self, r: Rectangle) -> Result<DrawBuffer> {
+    /// Acquires the cells covered by the given rectangle on behalf of `owner`.
+    pub(crate) fn get_draw_buffer(&self, r: Rectangle, owner: Owner) -> Result<DrawBuffer> {
         let c = self.clone();
         let mut dbuf = {
-            let mut inner = self.lock();
+            let mut inner = self.write();
             inner.reclaim();
-            inner.rectangle.contains_or_err(Geometry::Rectangle(&r))?;
-            DrawBuffer::new(inner.tuxel_sender.clone(), r.clone(), c)
+            inner.contains_or_err(&r, &owner)?;
+            DrawBuffer::new(inner.tuxel_sender.clone(), r.clone(), c, owner)
         };
         self.populate_drawbuffer(&mut dbuf)?;
         Ok(dbuf)
     }
 
-    pub(crate) fn get_text_buffer(&self, r: Rectangle) -> Result<TextBuffer> {
+    /// Acquires the cells covered by the given rectangle on behalf of `owner`.
+    pub(crate) fn get_text_buffer(&self, r: Rectangle, owner: Owner) -> Result<TextBuffer> {
         let c = self.clone();
         let mut dbuf = {
-            let mut inner = self.lock();
+            let mut inner = self.write();
             inner.reclaim();
-            inner.rectangle.contains_or_err(Geometry::Rectangle(&r))?;
-            TextBuffer::new(inner.tuxel_sender.clone(), r.clone(), c)
+            inner.contains_or_err(&r, &owner)?;
+            TextBuffer::new(inner.tuxel_sender.clone(), r.clone(), c, owner)
         };
         self.populate_drawbuffer(&mut dbuf)?;
         Ok(dbuf)
     }
 
+    /// Like `get_draw_buffer` but skips the bounds check and acquires the canvas lock only once.
+    /// Meant for hot paths where the rectangle is computed from constants already known to fit
+    /// the canvas; parts of the rectangle outside the canvas are silently left out of the buffer
+    /// in release builds.
+    pub(crate) fn get_draw_buffer_unchecked(
+        &self,
+        r: Rectangle,
+        owner: Owner,
+    ) -> Result<DrawBuffer> {
+        let mut inner = self.write();
+        Self::debug_assert_contains(&inner, &r, &owner);
+        inner.reclaim();
+        let mut dbuf = DrawBuffer::new(inner.tuxel_sender.clone(), r, self.clone(), owner);
+        let populated = Self::populate_drawbuffer_locked(&mut inner, &mut dbuf);
+        // release the lock before a partially populated buffer is dropped, since dropping it
+        // hands its cells back to the canvas
+        drop(inner);
+        populated.map(|_| dbuf)
+    }
+
+    /// Like `get_text_buffer` but skips the bounds check and acquires the canvas lock only once.
+    /// See `get_draw_buffer_unchecked`.
+    pub(crate) fn get_text_buffer_unchecked(
+        &self,
+        r: Rectangle,
+        owner: Owner,
+    ) -> Result<TextBuffer> {
+        let mut inner = self.write();
+        Self::debug_assert_contains(&inner, &r, &owner);
+        inner.reclaim();
+        let mut dbuf = TextBuffer::new(inner.tuxel_sender.clone(), r, self.clone(), owner);
+        let populated = Self::populate_drawbuffer_locked(&mut inner, &mut dbuf);
+        // release the lock before a partially populated buffer is dropped, since dropping it
+        // hands its cells back to the canvas
+        drop(inner);
+        populated.map(|_| dbuf)
+    }
+
+    fn debug_assert_contains(inner: &CanvasInner, r: &Rectangle, owner: &Owner) {
+        debug_assert!(
+            inner
+                .rectangle
+                .contains_or_err(Geometry::Rectangle(r))
+                .is_ok(),
+            "{} requested {} outside the canvas {}",
+            owner,
+            r,
+            inner.rectangle
+        );
+    }
+
     fn populate_drawbuffer<T: DrawBufferOwner>(&self, dbo: &mut T) -> Result<()> {
+        let mut inner = self.write();
+        Self::populate_drawbuffer_locked(&mut inner, dbo)
+    }
+
+    /// Hands the cells covered by the buffer's rectangle over to it. Each tuxel is placed at the
+    /// row and column its canvas index maps to within the rectangle rather than in the order the
+    /// grid happens to be walked, so a tuxel that doesn't map into the rectangle is an error rather
+    /// than a silently sheared buffer.
+    fn populate_drawbuffer_locked<T: DrawBufferOwner>(
+        inner: &mut CanvasInner,
+        dbo: &mut T,
+    ) -> Result<()> {
         let r = dbo.rectangle();
-        let mut inner = self.lock();
-        let sender = inner.idx_sender.clone();
+        let mut staged: Vec<Vec<Option<Tuxel>>> = (0..r.height())
+            .map(|_| (0..r.width()).map(|_| None).collect())
+            .collect();
+        let populated = Self::stage_tuxels(inner, dbo, &r, &mut staged);
+
+        // install whatever was staged even on failure, so that dropping the buffer hands those
+        // cells back to the canvas
+        let mut dinner = dbo.lock();
+        for (row, staged_row) in dinner.buf.iter_mut().zip(staged) {
+            row.extend(staged_row.into_iter().flatten());
+        }
+        populated
+    }
+
+    fn stage_tuxels<T: DrawBufferOwner>(
+        inner: &mut CanvasInner,
+        dbo: &mut T,
+        r: &Rectangle,
+        staged: &mut [Vec<Option<Tuxel>>],
+    ) -> Result<()> {
+        let requested = dbo.owner();
+        let changes = inner.changes.clone();
         for (y, row) in inner
             .grid
             .iter_mut()
@@ -269,66 +518,148 @@ impl Canvas {
         {
             for (x, cellstack) in row.iter_mut().enumerate().skip(r.x()).take(r.width()) {
                 let canvas_idx = Idx(x, y, r.0 .2);
-                let cell = cellstack.acquire(canvas_idx.z());
+                let cell = cellstack.acquire(canvas_idx.z())?;
                 let tuxel = match cell {
-                    Cell::Empty => Tuxel::new(Idx(x, y, r.z()), sender.clone()),
-                    _ => return Err(InnerError::CellAlreadyOwned.into()),
+                    Cell::Empty => Tuxel::new(Idx(x, y, r.z()), changes.clone()),
+                    Cell::DBTuxel(ref current) => {
+                        let err = InnerError::CellAlreadyOwned {
+                            idx: canvas_idx.clone(),
+                            requested,
+                            current: current.owner().clone(),
+                        };
+                        // leave the cell with its current owner
+                        cellstack.replace(canvas_idx.z(), cell)?;
+                        return Err(err.into());
+                    }
+                };
+                let db_tuxel = match Self::place(dbo, staged, tuxel) {
+                    Ok(t) => t,
+                    Err(e) => {
+                        cellstack.replace(canvas_idx.z(), Cell::Empty)?;
+                        return Err(e);
+                    }
                 };
-                let db_tuxel = Self::push(dbo, tuxel);
-                cellstack.replace(canvas_idx.z(), Cell::DBTuxel(db_tuxel));
+                cellstack.replace(canvas_idx.z(), Cell::DBTuxel(db_tuxel))?;
             }
         }
         Ok(())
     }
 
-    pub(crate) fn get_layer(&mut self, z: usize) -> Result<DrawBuffer> {
-        let rectangle = { self.lock().rectangle.clone() };
-        self.get_draw_buffer(Rectangle(Idx(0, 0, z), rectangle.1.clone()))
+    pub(crate) fn get_layer(&mut self, z: usize, owner: Owner) -> Result<DrawBuffer> {
+        let rectangle = { self.read().rectangle.clone() };
+        self.get_draw_buffer_unchecked(Rectangle(Idx(0, 0, z), rectangle.1.clone()), owner)
     }
 
     pub(crate) fn bounds(&self) -> Bounds2D {
-        self.lock().bounds()
+        self.read().bounds()
     }
 
     pub(crate) fn dimensions(&self) -> (usize, usize) {
-        self.lock().dimensions()
+        self.read().dimensions()
+    }
+
+    /// The number of layers the canvas has.
+    pub(crate) fn depth(&self) -> usize {
+        self.read().depth
     }
 
     pub(crate) fn get_changed(&self) -> Vec<Stack> {
-        self.lock().get_changed()
+        self.write().get_changed()
+    }
+
+    /// Calls the given function with every row of the canvas from the top, resolved to what each
+    /// of its cells shows. It's much cheaper than querying every stack for its content and colors
+    /// on its own, for repainting the whole canvas. The canvas can't be drawn on until it returns.
+    pub(crate) fn for_each_row<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(usize, &[CellRender]) -> Result<()>,
+    {
+        let inner = self.read();
+        let (_, height) = inner.dimensions();
+        for y in 0..height {
+            f(y, &inner.snapshot_row(y))?;
+        }
+        Ok(())
+    }
+
+    /// The number of changed cells the last call to `get_changed` returned.
+    pub(crate) fn last_changed(&self) -> usize {
+        self.read().last_changed
+    }
+
+    /// The number of times changes have been collected with `get_changed`. A cell reported as
+    /// changed more than once between two collections is still only repainted once, which lets
+    /// buffers avoid reporting the same cells again.
+    pub(crate) fn changes_collected(&self) -> usize {
+        self.read().changes_collected
     }
 
-    pub(crate) fn swap_tuxels(&self, t1: Idx, t2: Idx) -> Result<()> {
-        self.lock().swap_tuxels(t1, t2)
+    pub(crate) fn move_tuxel(&self, from: Idx, to: Idx, requested: &Owner) -> Result<()> {
+        self.write().move_tuxel(from, to, requested)
     }
 
     pub(crate) fn swap_rectangles(&self, r1: &Rectangle, r2: &Rectangle) -> Result<()> {
-        self.lock().swap_rectangles(r1, r2)
+        self.write().swap_rectangles(r1, r2)
     }
 
-    #[cfg(test)]
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
     pub(crate) fn layer_occupied(&self, zdx: usize) -> bool {
-        self.lock().layer_occupied(zdx)
+        self.read().layer_occupied(zdx)
     }
 
     pub(crate) fn reclaim(&mut self) -> Result<()> {
-        self.lock().reclaim();
+        self.write().reclaim();
         Ok(())
     }
+
+    /// Empties the cells left owned by buffers that were dropped without handing them back, eg
+    /// because a panic interrupted them, so that they can be drawn on again. Returns how many
+    /// cells were emptied.
+    pub(crate) fn sanitize(&self) -> usize {
+        let swept = self.write().sanitize();
+        if swept > 0 {
+            log::warn!("emptied {} cells left by buffers that are gone", swept);
+        }
+        swept
+    }
+
+    /// The number of cells owned by buffers that are gone, see `sanitize`.
+    #[cfg(test)]
+    pub(crate) fn orphaned_cells(&self) -> usize {
+        self.read().orphaned_cells()
+    }
 }
 
 // DrawBufferOwner functions
 impl Canvas {
-    fn push<T: DrawBufferOwner>(dbo: &mut T, t: Tuxel) -> DBTuxel {
-        let mut inner = dbo.lock();
+    /// Puts the tuxel in the slot of `staged` its canvas index maps to within the buffer's
+    /// rectangle.
+    fn place<T: DrawBufferOwner>(
+        dbo: &T,
+        staged: &mut [Vec<Option<Tuxel>>],
+        t: Tuxel,
+    ) -> Result<DBTuxel> {
+        let (rectangle, owner) = {
+            let inner = dbo.lock();
+            (inner.rectangle.clone(), inner.owner.clone())
+        };
         let canvas_idx = t.idx();
-        let buf_idx = Idx(
-            canvas_idx.0 - inner.rectangle.x(),
-            canvas_idx.1 - inner.rectangle.y(),
-            0,
-        );
-        inner.buf.iter_mut().nth(buf_idx.1).expect("meow").push(t);
-        DBTuxel::new(dbo.inner(), canvas_idx, buf_idx)
+        let misplaced = || InnerError::TuxelOutsideBuffer {
+            idx: canvas_idx.clone(),
+            owner: owner.clone(),
+            rectangle: rectangle.clone(),
+        };
+        let buf_idx = match canvas_idx.checked_sub(rectangle.x(), rectangle.y()) {
+            Ok(Idx(x, y, _)) => Idx(x, y, 0),
+            Err(_) => return Err(misplaced().into()),
+        };
+        let slot = staged
+            .get_mut(buf_idx.y())
+            .and_then(|row| row.get_mut(buf_idx.x()))
+            .filter(|slot| slot.is_none())
+            .ok_or_else(misplaced)?;
+        *slot = Some(t);
+        Ok(DBTuxel::new(dbo.inner(), canvas_idx, buf_idx, owner))
     }
 }
 
@@ -347,6 +678,11 @@ impl Cell {
         }
     }
 
+    /// Whether the cell is owned by a buffer that's gone.
+    fn is_orphaned(&self) -> bool {
+        matches!(self, Cell::DBTuxel(dbt) if dbt.is_orphaned())
+    }
+
     pub(crate) fn active(&self) -> Result<bool> {
         match self {
             Cell::DBTuxel(b) => b.active(),
@@ -382,56 +718,95 @@ impl std::fmt::Display for Cell {
 /// A stack of `Cells`. Enables z-ordering of elements with occlusion and update detection. Tuxels
 /// are wrapped in a Arc<Mutex<_>> to allow them to be referenced by the higher level Widget
 /// abstraction at the same time.
+///
+/// There's one cell per layer of the canvas. They're kept in a boxed slice rather than a `Vec` to
+/// save the capacity field, since every cell of the canvas has a stack.
 #[derive(Default)]
 struct StackInner {
-    cells: [Cell; CANVAS_DEPTH],
+    cells: Box<[Cell]>,
     idx: Idx,
 }
 
+impl StackInner {
+    fn top(&self) -> Option<&Cell> {
+        self.cells
+            // low-index elements of a stack are below high-index elements. we want to find the
+            // first active tuxel on top of the stack so we iterate over elements in reverse
+            .iter()
+            .rev()
+            .find(|c| matches!(c.active(), Ok(true)))
+    }
+
+    /// Resolves the stack to what it shows: the content and colors of the topmost active cell,
+    /// or a blank without colors if no cell is active.
+    fn resolve(&self) -> CellRender {
+        match self.top() {
+            Some(cell) => {
+                let (fg, bg) = cell.colors();
+                CellRender {
+                    content: cell.get_content().ok(),
+                    fg,
+                    bg,
+                }
+            }
+            None => CellRender {
+                content: Some(' '),
+                fg: None,
+                bg: None,
+            },
+        }
+    }
+}
+
+/// What a cell of the canvas shows, resolved through its stack.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct CellRender {
+    content: Option<char>,
+    fg: Option<Rgb>,
+    bg: Option<Rgb>,
+}
+
+impl CellRender {
+    /// The character shown, or None if the cell on top couldn't say.
+    pub(crate) fn content(&self) -> Option<char> {
+        self.content
+    }
+
+    pub(crate) fn colors(&self) -> (Option<Rgb>, Option<Rgb>) {
+        (self.fg.clone(), self.bg.clone())
+    }
+}
+
 #[derive(Clone, Default)]
 pub(crate) struct Stack {
     inner: Arc<Mutex<StackInner>>,
 }
 
 impl Stack {
-    fn new(x: usize, y: usize) -> Self {
+    fn new(x: usize, y: usize, depth: usize) -> Self {
         Self {
             inner: Arc::new(Mutex::new(StackInner {
                 idx: Idx(x, y, 0),
-                cells: [
-                    Cell::Empty,
-                    Cell::Empty,
-                    Cell::Empty,
-                    Cell::Empty,
-                    Cell::Empty,
-                    Cell::Empty,
-                    Cell::Empty,
-                    Cell::Empty,
-                ],
+                cells: std::iter::repeat_with(Cell::default).take(depth).collect(),
             })),
         }
     }
 
-    fn acquire(&mut self, z: usize) -> Cell {
-        self.lock().cells[z].take()
-    }
-
-    fn replace(&mut self, z: usize, cell: Cell) {
-        let _ = self.lock().cells[z].replace(cell);
+    fn acquire(&mut self, z: usize) -> Result<Cell> {
+        match self.lock().cells.get_mut(z) {
+            Some(cell) => Ok(cell.take()),
+            None => Err(InnerError::OutOfBoundsZ(z).into()),
+        }
     }
 
-    fn top(&self) -> Option<usize> {
-        self.lock()
-            .cells
-            // low-index elements of a stack are below high-index elements. we want to find the
-            // first active tuxel on top of the stack so we iterate over elements in reverse
-            .iter()
-            .enumerate()
-            .rev()
-            .find_map(|(idx, c)| match c.active() {
-                Ok(b) if b == true => Some(idx),
-                _ => None,
-            })
+    fn replace(&mut self, z: usize, cell: Cell) -> Result<()> {
+        match self.lock().cells.get_mut(z) {
+            Some(current) => {
+                let _ = current.replace(cell);
+                Ok(())
+            }
+            None => Err(InnerError::OutOfBoundsZ(z).into()),
+        }
     }
 
     fn layer_occupied(&self, zdx: usize) -> bool {
@@ -446,15 +821,21 @@ impl Stack {
     }
 
     fn lock(&self) -> MutexGuard<StackInner> {
-        self.inner
-            .lock()
-            .expect("TODO: handle mutex lock errors more gracefully")
+        // cells are reclaimed as buffers are dropped, which mustn't panic
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
     }
 
-    fn display_cell_type(&self, zdx: usize) -> &str {
-        match &self.lock().cells[zdx] {
-            Cell::Empty => "E",
-            Cell::DBTuxel(_) => "D",
+    fn display_cell_type(&self, zdx: usize) -> char {
+        match self.lock().cells.get(zdx) {
+            None | Some(Cell::Empty) => 'E',
+            Some(Cell::DBTuxel(dbt)) => dbt.owner().initial(),
+        }
+    }
+
+    fn owner(&self, zdx: usize) -> Option<Owner> {
+        match self.lock().cells.get(zdx) {
+            None | Some(Cell::Empty) => None,
+            Some(Cell::DBTuxel(dbt)) => Some(dbt.owner().clone()),
         }
     }
 }
@@ -466,37 +847,30 @@ impl Stack {
     }
 
     pub(crate) fn colors(&self) -> (Option<Rgb>, Option<Rgb>) {
-        if let Some(idx) = self.top() {
-            self.lock()
-                .cells
-                .get(idx)
-                .expect("if Stack.top() returns an index that element must exist")
-                .colors()
-        } else {
-            (None, None)
-        }
+        self.render().colors()
     }
 
     pub(crate) fn content(&self) -> Option<char> {
-        if let Some(idx) = self.top() {
-            self.lock()
-                .cells
-                .get(idx)
-                .expect("if Stack.top() returns an index that element must exist")
-                .get_content()
-                .ok()
-        } else {
-            Some(' ')
-        }
+        self.render().content()
+    }
+
+    /// Resolves the stack to what it shows, see `StackInner::resolve`.
+    pub(crate) fn render(&self) -> CellRender {
+        self.lock().resolve()
     }
 }
 
+/// What `Modifier::Dim` multiplies the lightness of each color by.
+const DIM_FACTOR: f32 = 0.5;
+
 #[derive(Clone, PartialEq)]
 pub(crate) enum Modifier {
     SetForegroundColor(u8, u8, u8),
     SetBackgroundColor(u8, u8, u8),
     SetBGLightness(f32),
     SetFGLightness(f32),
+    /// Halves the lightness of both colors, leaving their hues alone.
+    Dim,
 }
 
 impl Modifier {
@@ -517,6 +891,10 @@ impl Modifier {
             (fgcolor, Some(bgcolor), Modifier::SetBGLightness(l)) => {
                 (fgcolor, Some(bgcolor.set_lightness(*l)))
             }
+            (fgcolor, bgcolor, Modifier::Dim) => (
+                fgcolor.map(|c| c.scale_lightness(DIM_FACTOR)),
+                bgcolor.map(|c| c.scale_lightness(DIM_FACTOR)),
+            ),
             _ => (fgcolor, bgcolor),
         }
     }
@@ -537,8 +915,8 @@ mod test {
     //#[case::toobig((1000, 1000))]
     fn canvas_size(#[case] dims: (usize, usize)) {
         let canvas = Canvas::new(dims.0, dims.1);
-        assert_eq!(canvas.lock().grid.len(), dims.1);
-        for row in &canvas.lock().grid {
+        assert_eq!(canvas.read().grid.len(), dims.1);
+        for row in &canvas.read().grid {
             assert_eq!(row.len(), dims.0);
         }
     }
@@ -548,7 +926,7 @@ mod test {
     #[case::realistic((274, 75))]
     fn get_layer_validate_draw_buffer_size(#[case] dims: (usize, usize)) -> Result<()> {
         let mut canvas = Canvas::new(dims.0, dims.1);
-        let dbuf = canvas.get_layer(0)?;
+        let dbuf = canvas.get_layer(0, Owner::Named("test"))?;
         let inner = dbuf.lock();
         assert_eq!(inner.buf.len(), dims.1);
         for row in &inner.buf {
@@ -570,7 +948,7 @@ mod test {
         #[case] rect: Rectangle,
     ) -> Result<()> {
         let canvas = Canvas::new(canvas_dims.0, canvas_dims.1);
-        let buffer = canvas.get_draw_buffer(rect.clone())?;
+        let buffer = canvas.get_draw_buffer(rect.clone(), Owner::Named("test"))?;
 
         let inner = buffer.lock();
         assert_eq!(
@@ -585,6 +963,232 @@ mod test {
         Ok(())
     }
 
+    #[rstest]
+    #[case::base((5, 5), rectangle(0, 0, 0, 5, 5))]
+    #[case::realistic_smaller_buffer((274, 75), rectangle(10, 10, 3, 10, 10))]
+    fn validate_get_draw_buffer_unchecked(
+        #[case] canvas_dims: (usize, usize),
+        #[case] rect: Rectangle,
+    ) -> Result<()> {
+        let canvas = Canvas::new(canvas_dims.0, canvas_dims.1);
+        let buffer = canvas.get_draw_buffer_unchecked(rect.clone(), Owner::Named("test"))?;
+        {
+            let inner = buffer.lock();
+            assert_eq!(inner.buf.len(), rect.height());
+            for row in &inner.buf {
+                assert_eq!(row.len(), rect.width());
+            }
+        }
+
+        // ownership is still enforced
+        let r = canvas.get_text_buffer_unchecked(rect.clone(), Owner::Named("other"));
+        assert!(r.is_err());
+        drop(buffer);
+        canvas.get_text_buffer_unchecked(rect, Owner::Named("other"))?;
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::top_left(rectangle(0, 0, 1, 2, 2))]
+    #[case::top(rectangle(2, 0, 1, 2, 2))]
+    #[case::top_right(rectangle(3, 0, 1, 2, 2))]
+    #[case::right(rectangle(3, 2, 1, 2, 2))]
+    #[case::bottom_right(rectangle(3, 3, 1, 2, 2))]
+    #[case::bottom(rectangle(2, 3, 1, 2, 2))]
+    #[case::bottom_left(rectangle(0, 3, 1, 2, 2))]
+    #[case::left(rectangle(0, 2, 1, 2, 2))]
+    #[case::whole_canvas(rectangle(0, 0, 1, 5, 5))]
+    fn get_draw_buffer_at_canvas_edges(#[case] rect: Rectangle) -> Result<()> {
+        let canvas = Canvas::new(5, 5);
+        let buffer = canvas.get_draw_buffer(rect.clone(), Owner::Named("edge"))?;
+        {
+            let inner = buffer.lock();
+            assert_eq!(inner.buf.len(), rect.height());
+            for (y, row) in inner.buf.iter().enumerate() {
+                assert_eq!(row.len(), rect.width());
+                for (x, tuxel) in row.iter().enumerate() {
+                    assert_eq!(tuxel.idx(), Idx(rect.x() + x, rect.y() + y, rect.z()));
+                }
+            }
+        }
+
+        let mut inner = canvas.write();
+        for y in rect.y()..rect.extents().1 {
+            for x in rect.x()..rect.extents().0 {
+                let idx = Idx(x, y, rect.z());
+                let cell = inner.acquire_cell(&idx)?;
+                match &cell {
+                    Cell::DBTuxel(t) => {
+                        assert_eq!(*t.canvas_idx(), idx);
+                        let buf_idx = t.buf_idx();
+                        assert_eq!(
+                            (rect.x() + buf_idx.x(), rect.y() + buf_idx.y()),
+                            (x, y),
+                            "{} should map back to {}",
+                            buf_idx,
+                            idx
+                        );
+                    }
+                    Cell::Empty => panic!("{} should belong to the buffer", idx),
+                }
+                inner.replace_cell(&idx, cell)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn misplaced_tuxel_errors_instead_of_shearing_the_buffer() -> Result<()> {
+        let canvas = Canvas::new(5, 5);
+        let rect = rectangle(1, 1, 0, 2, 2);
+        let buffer = DrawBuffer::new(
+            canvas.read().tuxel_sender.clone(),
+            rect.clone(),
+            canvas.clone(),
+            Owner::Named("sheared"),
+        );
+        let changes = canvas.read().changes.clone();
+        let mut staged: Vec<Vec<Option<Tuxel>>> = vec![vec![None, None], vec![None, None]];
+        let mut place = |x, y| {
+            let tuxel = Tuxel::new(Idx(x, y, 0), changes.clone());
+            Canvas::place(&buffer, &mut staged, tuxel)
+        };
+
+        // the cell after the end of the first row used to be appended to it
+        match place(3, 1) {
+            Err(e) => assert!(
+                matches!(
+                    e.inner,
+                    InnerError::TuxelOutsideBuffer { ref idx, ref owner, .. }
+                        if *idx == Idx(3, 1, 0) && *owner == Owner::Named("sheared")
+                ),
+                "unexpected error {}",
+                e
+            ),
+            Ok(_) => panic!("a tuxel right of the buffer should not be placed"),
+        }
+        // as did one above or left of it, which underflowed
+        assert!(place(0, 1).is_err());
+        assert!(place(1, 0).is_err());
+
+        // and a cell can only be placed once
+        place(2, 2)?;
+        assert!(place(2, 2).is_err());
+        assert!(staged[1][1].is_some());
+        assert_eq!(staged.iter().flatten().filter(|t| t.is_some()).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "outside the canvas")]
+    fn get_draw_buffer_unchecked_asserts_bounds_in_debug_builds() {
+        let canvas = Canvas::new(5, 5);
+        let _ = canvas.get_draw_buffer_unchecked(rectangle(3, 3, 0, 5, 5), Owner::Named("test"));
+    }
+
+    /// Compares the cost of acquiring the tile-sized buffers set up at the start of every
+    /// animation. Run with `cargo test --release -- --ignored --nocapture bench_`.
+    #[test]
+    #[ignore]
+    fn bench_get_text_buffer_unchecked() -> Result<()> {
+        use std::time::{Duration, Instant};
+
+        const ITERATIONS: usize = 20_000;
+        let canvas = Canvas::new(100, 50);
+        let r = rectangle(10, 10, 3, 6, 5);
+
+        let mut checked = Duration::ZERO;
+        let mut unchecked = Duration::ZERO;
+        for _ in 0..ITERATIONS {
+            let start = Instant::now();
+            let buf = canvas.get_text_buffer(r.clone(), Owner::Named("bench"))?;
+            checked += start.elapsed();
+            drop(buf);
+
+            let start = Instant::now();
+            let buf = canvas.get_text_buffer_unchecked(r.clone(), Owner::Named("bench"))?;
+            unchecked += start.elapsed();
+            drop(buf);
+
+            // collect the changed cells like a render would
+            let _ = canvas.get_changed();
+        }
+
+        println!(
+            "{} acquisitions: checked {:?}, unchecked {:?} ({:.1}% faster)",
+            ITERATIONS,
+            checked,
+            unchecked,
+            100.0 * (1.0 - unchecked.as_secs_f64() / checked.as_secs_f64())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn canvas_can_be_shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Canvas>();
+    }
+
+    /// Measures how much rendering-style reads get done while the game is setting up animations
+    /// on the same canvas from another thread. Run with
+    /// `cargo test --release -- --ignored --nocapture bench_`.
+    #[test]
+    #[ignore]
+    fn bench_concurrent_render_and_update() -> Result<()> {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+        use std::time::{Duration, Instant};
+
+        const READERS: usize = 3;
+        const RUN_FOR: Duration = Duration::from_secs(2);
+        let canvas = Canvas::new(100, 50);
+        let done = Arc::new(AtomicBool::new(false));
+        let reads = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..READERS)
+            .map(|_| {
+                let canvas = canvas.clone();
+                let done = done.clone();
+                let reads = reads.clone();
+                std::thread::spawn(move || {
+                    while !done.load(Ordering::Relaxed) {
+                        let _ = canvas.dimensions();
+                        let _ = canvas.bounds();
+                        let _ = canvas.layer_occupied(3);
+                        reads.fetch_add(1, Ordering::Relaxed);
+                    }
+                })
+            })
+            .collect();
+
+        let mut updates = 0;
+        let start = Instant::now();
+        while start.elapsed() < RUN_FOR {
+            let tile = rectangle(10, 10, 3, 6, 5);
+            let mut buf = canvas.get_text_buffer(tile.clone(), Owner::Named("bench"))?;
+            buf.write("2048", None, None)?;
+            buf.flush()?;
+            canvas.swap_rectangles(&tile, &rectangle(20, 10, 3, 6, 5))?;
+            drop(buf);
+            let _ = canvas.get_changed();
+            updates += 1;
+        }
+        done.store(true, Ordering::Relaxed);
+        for handle in handles {
+            handle.join().expect("reader thread shouldn't panic");
+        }
+
+        println!(
+            "{:?} with {} readers: {} reads, {} updates",
+            RUN_FOR,
+            READERS,
+            reads.load(Ordering::Relaxed),
+            updates
+        );
+        Ok(())
+    }
+
     fn is_dbtuxel(cell: &Cell) -> bool {
         match cell {
             Cell::DBTuxel(..) => true,
@@ -608,7 +1212,7 @@ mod test {
         #[case] rect: Rectangle,
     ) -> Result<()> {
         let canvas = Canvas::new(canvas_dims.0, canvas_dims.1);
-        let dbuf = canvas.get_draw_buffer(rect.clone())?;
+        let dbuf = canvas.get_draw_buffer(rect.clone(), Owner::Named("test"))?;
 
         let mut idxs: Vec<Idx> = Vec::new();
         {
@@ -616,7 +1220,7 @@ mod test {
             for row in &inner.buf {
                 for tuxel in row {
                     let idx = tuxel.idx();
-                    let inner = canvas.lock();
+                    let inner = canvas.read();
                     let cell = &inner.grid[idx.1][idx.0].lock().cells[idx.2];
                     assert!(is_dbtuxel(cell));
                     idxs.push(idx);
@@ -627,15 +1231,15 @@ mod test {
         drop(dbuf);
 
         for idx in idxs.iter() {
-            let inner = canvas.lock();
+            let inner = canvas.read();
             let cell = &inner.grid[idx.1][idx.0].lock().cells[idx.2];
             assert!(is_empty(cell));
         }
 
-        canvas.lock().reclaim();
+        canvas.write().reclaim();
 
         for idx in idxs.iter() {
-            let inner = canvas.lock();
+            let inner = canvas.read();
             let cell = &inner.grid[idx.1][idx.0].lock().cells[idx.2];
             assert!(is_empty(cell));
         }
@@ -643,6 +1247,67 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn overlapping_buffers_name_both_owners() -> Result<()> {
+        let canvas = Canvas::new(20, 20);
+        let _board = canvas.get_draw_buffer(rectangle(2, 2, 1, 10, 10), Owner::Named("board"))?;
+
+        let owner = Owner::At("tile", 1, 2);
+        let err = match canvas.get_text_buffer(rectangle(8, 4, 1, 6, 3), owner) {
+            Err(e) => e,
+            Ok(_) => panic!("overlapping acquisition should fail"),
+        };
+        match &err.inner {
+            InnerError::CellAlreadyOwned {
+                idx,
+                requested,
+                current,
+            } => {
+                assert_eq!(idx, &Idx(8, 4, 1));
+                assert_eq!(requested, &Owner::At("tile", 1, 2));
+                assert_eq!(current, &Owner::Named("board"));
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        assert_eq!(
+            err.inner.to_string(),
+            "cell idx(8,4,1) requested by tile(1,2) is already owned by board"
+        );
+
+        // the failed acquisition must leave the board's cells alone
+        let probe = canvas.get_draw_buffer(rectangle(8, 4, 1, 1, 1), Owner::Named("probe"));
+        assert!(probe.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn display_shows_owner_initials() -> Result<()> {
+        let canvas = Canvas::new(4, 2);
+        let _board = canvas.get_draw_buffer(rectangle(0, 0, 1, 2, 2), Owner::Named("board"))?;
+        let _score = canvas.get_text_buffer(rectangle(2, 0, 1, 2, 1), Owner::Named("score"))?;
+
+        assert_eq!(
+            canvas.to_string(),
+            "canvas layer 1:\nbbss\nbbEE\nowners: board, score\n\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn last_changed_counts_what_get_changed_returned() -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        assert_eq!(canvas.last_changed(), 0);
+        let mut dbuf = canvas.get_draw_buffer(rectangle(1, 1, 0, 3, 2), Owner::Named("test"))?;
+        dbuf.fill('x')?;
+
+        let changed = canvas.get_changed().len();
+        assert!(changed >= 6, "{} cells changed", changed);
+        assert_eq!(canvas.last_changed(), changed);
+        let _ = canvas.get_changed();
+        assert_eq!(canvas.last_changed(), 0);
+        Ok(())
+    }
+
     #[rstest]
     #[case::base((50, 50), rectangle(0, 0, 0, 2, 2), (1, geometry::Direction::Down))]
     fn validate_drawbuffer_translation_cleanup(
@@ -664,7 +1329,7 @@ mod test {
             canvas_changed_idxs,
         );
 
-        let mut dbuf = canvas.get_draw_buffer(initial_db_rect.clone())?;
+        let mut dbuf = canvas.get_draw_buffer(initial_db_rect.clone(), Owner::Named("test"))?;
         let (dbuf_width, dbuf_height) = dbuf.rectangle().dimensions();
 
         // verify creation of drawbuffer itself doesn't result in changed indices
@@ -788,4 +1453,288 @@ mod test {
 
         Ok(())
     }
+
+    fn expect_out_of_bounds_z<T>(r: Result<T>, z: usize) {
+        match r.map(|_| ()) {
+            Err(TuiError {
+                inner: InnerError::OutOfBoundsZ(got),
+                ..
+            }) => assert_eq!(got, z),
+            Err(e) => panic!("expected layer {} to be out of bounds, got {:?}", z, e),
+            Ok(()) => panic!("expected layer {} to be out of bounds", z),
+        }
+    }
+
+    #[rstest]
+    #[case::shallow(4)]
+    #[case::deep(12)]
+    fn canvas_depth_bounds_its_layers(#[case] depth: usize) -> Result<()> {
+        let canvas = Canvas::with_depth(5, 5, depth)?;
+        assert_eq!(canvas.depth(), depth);
+        let top = depth - 1;
+
+        let mut bottom_buf = canvas.get_draw_buffer(rectangle(0, 0, 0, 2, 2), Owner::Named("b"))?;
+        let mut top_buf = canvas.get_text_buffer(rectangle(0, 0, top, 2, 2), Owner::Named("t"))?;
+        bottom_buf.fill('b')?;
+        top_buf.fill('t')?;
+        assert!(canvas.layer_occupied(0) && canvas.layer_occupied(top));
+        let changed = canvas.get_changed();
+        assert!(changed.iter().all(|s| s.content() == Some('t')));
+
+        // swapping the two layers puts the bottom buffer's cells on top
+        canvas.swap_rectangles(&rectangle(0, 0, 0, 2, 2), &rectangle(0, 0, top, 2, 2))?;
+        let contents: BTreeSet<Option<char>> =
+            canvas.get_changed().iter().map(|s| s.content()).collect();
+        assert_eq!(contents, BTreeSet::from([Some('b')]));
+
+        // nothing can be put on or moved to the layer past the top one
+        let past = rectangle(0, 0, depth, 2, 2);
+        expect_out_of_bounds_z(
+            canvas.get_draw_buffer(past.clone(), Owner::Named("p")),
+            depth,
+        );
+        expect_out_of_bounds_z(
+            canvas.get_draw_buffer_unchecked(past.clone(), Owner::Named("p")),
+            depth,
+        );
+        expect_out_of_bounds_z(
+            canvas.swap_rectangles(&rectangle(0, 0, 0, 2, 2), &past),
+            depth,
+        );
+        expect_out_of_bounds_z(top_buf.switch_layer(depth), depth);
+        // a failed switch leaves the buffer where it was
+        assert_eq!(top_buf.rectangle().z(), top);
+        assert!(!canvas.layer_occupied(depth));
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::none(0)]
+    #[case::too_many(MAX_CANVAS_DEPTH + 1)]
+    fn with_depth_refuses_unsupported_depths(#[case] depth: usize) {
+        match Canvas::with_depth(5, 5, depth) {
+            Err(TuiError {
+                inner: InnerError::InvalidCanvasDepth { requested, max },
+                ..
+            }) => assert_eq!((requested, max), (depth, MAX_CANVAS_DEPTH)),
+            Err(e) => panic!("expected the depth to be refused, got {:?}", e),
+            Ok(_) => panic!("expected the depth to be refused"),
+        }
+    }
+
+    #[test]
+    fn new_canvases_have_the_default_depth() {
+        assert_eq!(Canvas::new(5, 5).depth(), DEFAULT_CANVAS_DEPTH);
+    }
+
+    struct FixedSize(usize, usize);
+
+    impl CanvasSizeSource for FixedSize {
+        fn terminal_size(&self) -> Result<(usize, usize)> {
+            Ok((self.0, self.1))
+        }
+    }
+
+    #[test]
+    fn resize_to_terminal_resizes_every_clone_in_place() -> Result<()> {
+        let canvas = Canvas::with_depth(10, 5, 4)?;
+        let mut resized = canvas.clone();
+        {
+            let mut buf = canvas.get_draw_buffer(rectangle(6, 2, 0, 4, 3), Owner::Named("b"))?;
+            buf.fill('b')?;
+        }
+        resized.resize_to_terminal(&FixedSize(20, 8))?;
+
+        assert_eq!(canvas.dimensions(), (20, 8));
+        assert_eq!(canvas.depth(), 4);
+        // changes made before the resize are forgotten with the cells they were made to
+        assert!(canvas.get_changed().is_empty());
+        let mut buf = canvas.get_draw_buffer(rectangle(16, 5, 3, 4, 3), Owner::Named("b"))?;
+        buf.fill('b')?;
+        assert_eq!(canvas.get_changed().len(), 12);
+        Ok(())
+    }
+
+    #[test]
+    fn resize_refuses_while_cells_are_drawn_on() -> Result<()> {
+        let mut canvas = Canvas::new(10, 5);
+        let _buf = canvas.get_draw_buffer(rectangle(0, 0, 1, 2, 2), Owner::Named("kept"))?;
+        match canvas.resize_to_terminal(&FixedSize(4, 4)) {
+            Err(TuiError {
+                inner: InnerError::CanvasInUse(owner),
+                ..
+            }) => assert_eq!(owner, Owner::Named("kept")),
+            Err(e) => panic!("expected the resize to be refused, got {:?}", e),
+            Ok(_) => panic!("expected the resize to be refused"),
+        }
+        assert_eq!(canvas.dimensions(), (10, 5));
+        Ok(())
+    }
+
+    #[test]
+    fn dim_halves_the_lightness_of_both_colors() {
+        let fg = Rgb::new(200, 200, 200);
+        let bg = Rgb::new(40, 80, 120);
+        let (dim_fg, dim_bg) = Modifier::Dim.apply((Some(fg.clone()), Some(bg.clone())));
+        let (dim_fg, dim_bg) = (dim_fg.expect("fg is kept"), dim_bg.expect("bg is kept"));
+        assert!(dim_fg.lightness() < fg.lightness());
+        assert!((dim_fg.lightness() - fg.lightness() * DIM_FACTOR).abs() < 0.01);
+        assert!((dim_bg.lightness() - bg.lightness() * DIM_FACTOR).abs() < 0.01);
+        // blue stays the brightest channel, so the hue is kept
+        assert!(dim_bg.b() > dim_bg.g() && dim_bg.g() > dim_bg.r());
+
+        assert!(matches!(Modifier::Dim.apply((None, None)), (None, None)));
+    }
+
+    /// A canvas drawn on in every layer by overlapping buffers, each colored differently and
+    /// some of them dimmed or bordered, along with the buffers, which keep the cells drawn on.
+    fn busy_canvas(width: usize, height: usize) -> Result<(Canvas, Vec<DrawBuffer>)> {
+        let canvas = Canvas::new(width, height);
+        let mut buffers = Vec::new();
+        for z in 0..canvas.depth() {
+            // each layer's buffers are shifted by a cell against the layer's below
+            for n in 0..((width - 16) / 12 + 1) {
+                let r = rectangle(n * 12 + z, z.min(height - 6), z, 8, 6);
+                let mut buf = canvas.get_draw_buffer(r, Owner::Named("busy"))?;
+                buf.modify(Modifier::SetBackgroundColor(z as u8 * 20, n as u8, 100));
+                buf.modify(Modifier::SetForegroundColor(255, z as u8 * 10, n as u8));
+                if (z + n) % 3 == 0 {
+                    buf.modify(Modifier::Dim);
+                }
+                if (z + n) % 2 == 0 {
+                    buf.draw_border()?;
+                } else {
+                    buf.fill((b'a' + z as u8) as char)?;
+                }
+                buffers.push(buf);
+            }
+        }
+        let _ = canvas.get_changed();
+        Ok((canvas, buffers))
+    }
+
+    #[test]
+    fn rows_resolve_like_their_stacks() -> Result<()> {
+        let (canvas, _buffers) = busy_canvas(60, 20)?;
+        let stacks: Vec<Vec<Stack>> = canvas.read().grid.clone();
+        let mut rows = 0;
+        canvas.for_each_row(|y, cells| {
+            assert_eq!(cells.len(), 60);
+            for (x, cell) in cells.iter().enumerate() {
+                let stack = &stacks[y][x];
+                assert_eq!(cell.content(), stack.content(), "content at ({}, {})", x, y);
+                assert_eq!(cell.colors(), stack.colors(), "colors at ({}, {})", x, y);
+            }
+            rows += 1;
+            Ok(())
+        })?;
+        assert_eq!(rows, 20);
+
+        // layers drawn over each other show the topmost, and cells nothing is drawn on are blank
+        let mut shown = BTreeSet::new();
+        canvas.for_each_row(|_, cells| {
+            shown.extend(cells.iter().filter_map(CellRender::content));
+            Ok(())
+        })?;
+        assert!(shown.contains(&' '));
+        assert!(shown.contains(&'b') && shown.contains(&'h'));
+        Ok(())
+    }
+
+    /// Compares repainting a whole canvas by querying every stack with reading it row by row.
+    /// Run with `cargo test --release -- --ignored --nocapture bench_`.
+    #[test]
+    #[ignore]
+    fn bench_full_repaint() -> Result<()> {
+        use std::time::{Duration, Instant};
+
+        const ITERATIONS: usize = 200;
+        let (canvas, _buffers) = busy_canvas(400, 120)?;
+        let stacks: Vec<Stack> = canvas.read().grid.iter().flatten().cloned().collect();
+
+        let mut per_cell = Duration::ZERO;
+        let mut by_row = Duration::ZERO;
+        let mut printed = 0;
+        for _ in 0..ITERATIONS {
+            let start = Instant::now();
+            for stack in stacks.iter() {
+                let _ = stack.coordinates();
+                let _ = stack.colors();
+                printed += stack.content().is_some() as usize;
+            }
+            per_cell += start.elapsed();
+
+            let start = Instant::now();
+            canvas.for_each_row(|_, cells| {
+                printed += cells.iter().filter(|c| c.content().is_some()).count();
+                Ok(())
+            })?;
+            by_row += start.elapsed();
+        }
+
+        println!(
+            "{} repaints of {} cells: per cell {:?}, by row {:?} ({:.1}% faster)",
+            ITERATIONS,
+            printed / ITERATIONS / 2,
+            per_cell,
+            by_row,
+            100.0 * (1.0 - by_row.as_secs_f64() / per_cell.as_secs_f64())
+        );
+        Ok(())
+    }
+
+    /// Measures collecting the changes of a board's worth of tiles sliding across the canvas, a
+    /// frame at a time. Run with `cargo test --release -- --ignored --nocapture bench_`.
+    #[test]
+    #[ignore]
+    fn bench_changes_of_a_full_board_animation() -> Result<()> {
+        use std::time::{Duration, Instant};
+
+        const FRAMES: usize = 2_000;
+        let canvas = Canvas::new(100, 40);
+        let mut tiles = Vec::new();
+        for y in 0..4 {
+            for x in 0..4 {
+                let r = rectangle(x * 14 + 1, y * 8 + 1, 4, 12, 6);
+                let mut buf = canvas.get_draw_buffer(r, Owner::Named("bench"))?;
+                let (r, g) = (x as u8 * 60, y as u8 * 60);
+                buf.modify(Modifier::SetBackgroundColor(r, g, 100));
+                buf.fill('x')?;
+                tiles.push(buf);
+            }
+        }
+        let _ = canvas.get_changed();
+
+        let mut drawing = Duration::ZERO;
+        let mut collecting = Duration::ZERO;
+        let mut changed = 0;
+        for frame in 0..FRAMES {
+            let dir = if frame % 2 == 0 {
+                geometry::Direction::Right
+            } else {
+                geometry::Direction::Left
+            };
+            let start = Instant::now();
+            for buf in tiles.iter_mut() {
+                buf.translate(dir.clone())?;
+                buf.fill(if frame % 2 == 0 { 'x' } else { 'o' })?;
+            }
+            drawing += start.elapsed();
+
+            let start = Instant::now();
+            changed += canvas.get_changed().len();
+            collecting += start.elapsed();
+        }
+
+        println!(
+            "{} frames of {} sliding tiles: drawing {:?}, collecting {:?} per frame, {} cells \
+             changed per frame",
+            FRAMES,
+            tiles.len(),
+            drawing / FRAMES as u32,
+            collecting / FRAMES as u32,
+            changed / FRAMES
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,455 @@
+//! What the terminal can do beyond the basics, asked of it at startup. Replies to the queries
+//! arrive on the same input as key presses, so whatever in it isn't a reply is handed back as
+//! input rather than lost.
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+
+use super::error::Result;
+
+/// Asks whether the terminal knows synchronized updates (DECRQM for private mode 2026).
+const SYNC_UPDATE_QUERY: &[u8] = b"\x1b[?2026$p";
+
+/// Asks for the primary device attributes, which every terminal answers. Its reply comes after
+/// the replies to any query sent before it, so once it arrives there are no more to wait for.
+const DEVICE_ATTRIBUTES_QUERY: &[u8] = b"\x1b[c";
+
+/// How long to wait for the terminal to reply before assuming it never will.
+pub(crate) const QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// The environment variable that forces synchronized updates on or off, see `SyncMode`.
+pub(crate) const SYNC_UPDATES_VAR: &str = "TUI48_SYNCHRONIZED_UPDATES";
+
+/// Whether to wrap frames in synchronized updates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub(crate) enum SyncMode {
+    /// Ask the terminal whether it supports them.
+    #[default]
+    Auto,
+    On,
+    Off,
+}
+
+impl SyncMode {
+    fn from_var(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "on" | "1" | "true" => Some(Self::On),
+            "off" | "0" | "false" => Some(Self::Off),
+            _ => None,
+        }
+    }
+}
+
+/// The terminal's reply to DECRQM about a mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ModeReport {
+    NotRecognized,
+    Set,
+    Reset,
+    PermanentlySet,
+    PermanentlyReset,
+}
+
+impl ModeReport {
+    fn from_value(value: u16) -> Option<Self> {
+        match value {
+            0 => Some(Self::NotRecognized),
+            1 => Some(Self::Set),
+            2 => Some(Self::Reset),
+            3 => Some(Self::PermanentlySet),
+            4 => Some(Self::PermanentlyReset),
+            _ => None,
+        }
+    }
+
+    /// Whether the mode can be turned on, which a permanently reset one can't.
+    fn supported(&self) -> bool {
+        matches!(self, Self::Set | Self::Reset | Self::PermanentlySet)
+    }
+}
+
+/// How a capability came to be known.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum CapabilitySource {
+    /// Taken for granted, without asking.
+    Assumed,
+    /// The terminal said so.
+    Detected,
+    /// The terminal didn't reply in time, or didn't recognize the query.
+    NoReply,
+    /// Given with a flag or the environment.
+    Forced,
+}
+
+/// What the renderer may rely on the terminal for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct RenderCapabilities {
+    /// Whether frames can be wrapped in synchronized updates, which the terminal holds back and
+    /// then shows all at once. Without them a frame is still sent in a single write.
+    pub(crate) synchronized_updates: bool,
+    pub(crate) source: CapabilitySource,
+}
+
+impl Default for RenderCapabilities {
+    /// Synchronized updates are harmless on most terminals, so they are used unless asked not to.
+    fn default() -> Self {
+        Self {
+            synchronized_updates: true,
+            source: CapabilitySource::Assumed,
+        }
+    }
+}
+
+impl RenderCapabilities {
+    /// Decides on the capabilities given the mode asked for with a flag, which takes precedence,
+    /// the value of `SYNC_UPDATES_VAR`, and the terminal's reply to the query, asked for only if
+    /// neither forces a mode.
+    pub(crate) fn resolve(
+        flag: SyncMode,
+        var: Option<&str>,
+        query: impl FnOnce() -> Option<ModeReport>,
+    ) -> Self {
+        let var = var.and_then(|value| {
+            let mode = SyncMode::from_var(value);
+            if mode.is_none() {
+                log::warn!("ignoring {}={:?}", SYNC_UPDATES_VAR, value);
+            }
+            mode
+        });
+        let forced = |synchronized_updates| Self {
+            synchronized_updates,
+            source: CapabilitySource::Forced,
+        };
+        match (flag, var) {
+            (SyncMode::On, _) | (SyncMode::Auto, Some(SyncMode::On)) => forced(true),
+            (SyncMode::Off, _) | (SyncMode::Auto, Some(SyncMode::Off)) => forced(false),
+            (SyncMode::Auto, _) => match query() {
+                Some(report) if report != ModeReport::NotRecognized => Self {
+                    synchronized_updates: report.supported(),
+                    source: CapabilitySource::Detected,
+                },
+                _ => Self {
+                    synchronized_updates: false,
+                    source: CapabilitySource::NoReply,
+                },
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for RenderCapabilities {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let supported = if self.synchronized_updates {
+            "yes"
+        } else {
+            "no"
+        };
+        let source = match self.source {
+            CapabilitySource::Assumed => "assumed",
+            CapabilitySource::Detected => "detected",
+            CapabilitySource::NoReply => "no reply",
+            CapabilitySource::Forced => "forced",
+        };
+        write!(f, "synchronized updates: {} ({})", supported, source)
+    }
+}
+
+/// The replies found in what the terminal sent, and everything else it sent, in order.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Replies {
+    pub(crate) sync_updates: Option<ModeReport>,
+    pub(crate) device_attributes: bool,
+    pub(crate) input: Vec<u8>,
+}
+
+/// Picks the replies to the queries out of the given bytes. Anything that isn't a reply is kept
+/// as input, including a reply cut short at the end, which is what the start of a key press
+/// looks like too. A reply that can't be made sense of is dropped.
+pub(crate) fn parse_replies(bytes: &[u8]) -> Replies {
+    let mut replies = Replies::default();
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        match parse_reply(rest) {
+            Some((reply, len)) => {
+                match reply {
+                    Reply::Mode(2026, report) => replies.sync_updates = Some(report),
+                    Reply::DeviceAttributes => replies.device_attributes = true,
+                    Reply::Mode(..) | Reply::Garbled => (),
+                }
+                rest = &rest[len..];
+            }
+            None => {
+                replies.input.push(rest[0]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    replies
+}
+
+enum Reply {
+    Mode(u16, ModeReport),
+    DeviceAttributes,
+    Garbled,
+}
+
+/// Parses the reply at the start of the given bytes, returning it and its length, or None if
+/// they don't start with a whole one. Both replies start with CSI ?, which no key sends.
+fn parse_reply(bytes: &[u8]) -> Option<(Reply, usize)> {
+    let body = bytes.strip_prefix(b"\x1b[?")?;
+    let end = body.iter().position(|b| !matches!(b, b'0'..=b'9' | b';'))?;
+    let params: Vec<Option<u16>> = std::str::from_utf8(&body[..end])
+        .ok()?
+        .split(';')
+        .map(|param| param.parse().ok())
+        .collect();
+    let (reply, len) = match &body[end..] {
+        [b'$', b'y', ..] => {
+            let reply = match params[..] {
+                [Some(mode), Some(value)] => ModeReport::from_value(value)
+                    .map_or(Reply::Garbled, |report| Reply::Mode(mode, report)),
+                _ => Reply::Garbled,
+            };
+            (reply, end + 2)
+        }
+        [b'c', ..] => (Reply::DeviceAttributes, end + 1),
+        // cut short
+        [] | [b'$'] => return None,
+        // a sequence of some other kind, up to its final byte
+        [b, ..] if (0x40..=0x7e).contains(b) => (Reply::Garbled, end + 1),
+        _ => return None,
+    };
+    Some((reply, len + 3))
+}
+
+/// Asks the terminal about synchronized updates and waits up to the given time for its reply,
+/// read from the given reader, which it must be in raw mode for. Whatever else is read is handed
+/// to `forward` in one go, once the replies are in or, if the wait timed out, once anything more
+/// is read, so key presses made meanwhile aren't lost. Nothing is read after that.
+pub(crate) fn query_sync_updates<W, R, F>(
+    w: &mut W,
+    reader: R,
+    timeout: Duration,
+    forward: F,
+) -> Result<Option<ModeReport>>
+where
+    W: Write,
+    R: Read + Send + 'static,
+    F: FnOnce(Vec<u8>) + Send + 'static,
+{
+    w.write_all(SYNC_UPDATE_QUERY)
+        .and_then(|_| w.write_all(DEVICE_ATTRIBUTES_QUERY))
+        .and_then(|_| w.flush())
+        .with_context(|| "query the terminal")?;
+    let (sender, receiver) = channel();
+    let gave_up = Arc::new(AtomicBool::new(false));
+    let waiting = gave_up.clone();
+    std::thread::Builder::new()
+        .name("terminal query".into())
+        .spawn(move || {
+            let mut reader = reader;
+            let mut bytes = Vec::new();
+            let mut chunk = [0; 256];
+            let replies = loop {
+                match reader.read(&mut chunk) {
+                    Ok(0) | Err(_) => break parse_replies(&bytes),
+                    Ok(n) => bytes.extend_from_slice(&chunk[..n]),
+                }
+                let replies = parse_replies(&bytes);
+                if replies.device_attributes || waiting.load(Ordering::SeqCst) {
+                    break replies;
+                }
+            };
+            // the wait may be over already
+            let _ = sender.send(replies.sync_updates);
+            if !replies.input.is_empty() {
+                forward(replies.input);
+            }
+        })
+        .with_context(|| "spawn the terminal query thread")?;
+    let report = receiver.recv_timeout(timeout).ok().flatten();
+    gave_up.store(true, Ordering::SeqCst);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc::Receiver;
+
+    use rstest::*;
+
+    use super::*;
+
+    /// Replies captured from terminals: kitty, which supports synchronized updates; xterm, which
+    /// doesn't recognize the mode; and the Linux console, which doesn't answer DECRQM at all.
+    const KITTY: &[u8] = b"\x1b[?2026;2$y\x1b[?62;c";
+    const XTERM: &[u8] = b"\x1b[?2026;0$y\x1b[?64;1;2;6;9;15;16;17;18;21;22;28c";
+    const LINUX_CONSOLE: &[u8] = b"\x1b[?6c";
+
+    #[rstest]
+    #[case::kitty(KITTY, Some(ModeReport::Reset), true, b"")]
+    #[case::xterm(XTERM, Some(ModeReport::NotRecognized), true, b"")]
+    #[case::linux_console(LINUX_CONSOLE, None, true, b"")]
+    #[case::permanently_set(
+        b"\x1b[?2026;3$y\x1b[?1;2c",
+        Some(ModeReport::PermanentlySet),
+        true,
+        b""
+    )]
+    #[case::other_mode(b"\x1b[?1049;1$y\x1b[?62c", None, true, b"")]
+    #[case::garbled_value(b"\x1b[?2026;9$y\x1b[?62c", None, true, b"")]
+    #[case::garbled_params(b"\x1b[?;;$y\x1b[?62c", None, true, b"")]
+    #[case::garbage(b"\x1b[?2026;\xff\x00", None, false, b"\x1b[?2026;\xff\x00")]
+    #[case::cut_short(
+        b"\x1b[?2026;2$y\x1b[?62;",
+        Some(ModeReport::Reset),
+        false,
+        b"\x1b[?62;"
+    )]
+    #[case::keypress_interleaved(
+        b"j\x1b[?2026;1$y\x1b[A\x1b[?62;22c\r",
+        Some(ModeReport::Set),
+        true,
+        b"j\x1b[A\r"
+    )]
+    #[case::escape_pressed(b"\x1b\x1b[?2026;2$y\x1b[?62c", Some(ModeReport::Reset), true, b"\x1b")]
+    fn replies_are_picked_out_of_input(
+        #[case] bytes: &[u8],
+        #[case] sync_updates: Option<ModeReport>,
+        #[case] device_attributes: bool,
+        #[case] input: &[u8],
+    ) {
+        let expected = Replies {
+            sync_updates,
+            device_attributes,
+            input: input.to_vec(),
+        };
+        assert_eq!(parse_replies(bytes), expected);
+    }
+
+    #[rstest]
+    #[case::flag_beats_var(SyncMode::Off, Some("on"), Some(false))]
+    #[case::flag_on(SyncMode::On, None, Some(true))]
+    #[case::var_off(SyncMode::Auto, Some("off"), Some(false))]
+    #[case::var_on(SyncMode::Auto, Some(" ON "), Some(true))]
+    #[case::var_auto(SyncMode::Auto, Some("auto"), None)]
+    #[case::var_garbled(SyncMode::Auto, Some("sometimes"), None)]
+    #[case::neither(SyncMode::Auto, None, None)]
+    fn overrides_take_precedence_over_the_terminal(
+        #[case] flag: SyncMode,
+        #[case] var: Option<&str>,
+        #[case] forced: Option<bool>,
+    ) {
+        let mut asked = false;
+        let capabilities = RenderCapabilities::resolve(flag, var, || {
+            asked = true;
+            Some(ModeReport::NotRecognized)
+        });
+        match forced {
+            Some(synchronized_updates) => {
+                assert!(!asked);
+                assert_eq!(
+                    capabilities,
+                    RenderCapabilities {
+                        synchronized_updates,
+                        source: CapabilitySource::Forced
+                    }
+                );
+            }
+            None => {
+                assert!(asked);
+                assert!(!capabilities.synchronized_updates);
+                assert_eq!(capabilities.source, CapabilitySource::NoReply);
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::reset(Some(ModeReport::Reset), true, CapabilitySource::Detected)]
+    #[case::permanently_reset(
+        Some(ModeReport::PermanentlyReset),
+        false,
+        CapabilitySource::Detected
+    )]
+    #[case::no_reply(None, false, CapabilitySource::NoReply)]
+    fn the_reply_decides_when_nothing_is_forced(
+        #[case] report: Option<ModeReport>,
+        #[case] synchronized_updates: bool,
+        #[case] source: CapabilitySource,
+    ) {
+        let capabilities = RenderCapabilities::resolve(SyncMode::Auto, None, || report);
+        assert_eq!(
+            capabilities,
+            RenderCapabilities {
+                synchronized_updates,
+                source
+            }
+        );
+    }
+
+    /// A reader that reads the chunks sent to it, as a terminal's input would arrive, and ends
+    /// once the sender is dropped.
+    struct ChunkReader(Receiver<Vec<u8>>);
+
+    impl Read for ChunkReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.recv() {
+                Ok(chunk) => {
+                    buf[..chunk.len()].copy_from_slice(&chunk);
+                    Ok(chunk.len())
+                }
+                Err(_) => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn a_reply_split_across_reads_is_put_back_together() -> Result<()> {
+        let (chunks, reader) = channel();
+        for chunk in [&b"k\x1b[?20"[..], b"26;2$y\x1b[?6", b"2c"] {
+            chunks.send(chunk.to_vec()).expect("the reader is waiting");
+        }
+        let (forwarded, input) = channel();
+        let mut queries = Vec::new();
+        let report = query_sync_updates(
+            &mut queries,
+            ChunkReader(reader),
+            Duration::from_secs(10),
+            move |bytes| forwarded.send(bytes).expect("the test is waiting"),
+        )?;
+
+        assert_eq!(report, Some(ModeReport::Reset));
+        assert_eq!(queries, b"\x1b[?2026$p\x1b[c");
+        assert_eq!(input.recv().ok(), Some(b"k".to_vec()));
+        Ok(())
+    }
+
+    #[test]
+    fn input_read_after_giving_up_is_still_handed_back() -> Result<()> {
+        let (chunks, reader) = channel();
+        let (forwarded, input) = channel();
+        let report = query_sync_updates(
+            &mut Vec::new(),
+            ChunkReader(reader),
+            Duration::from_millis(10),
+            move |bytes| forwarded.send(bytes).expect("the test is waiting"),
+        )?;
+        assert_eq!(report, None);
+
+        // the terminal never replies, but the key pressed next isn't lost
+        chunks.send(b"h".to_vec()).expect("the reader is waiting");
+        assert_eq!(input.recv().ok(), Some(b"h".to_vec()));
+        // and nothing more is read, the reader being dropped once the thread is done
+        let dropped = (0..1000).any(|_| {
+            std::thread::sleep(Duration::from_millis(1));
+            chunks.send(Vec::new()).is_err()
+        });
+        assert!(dropped);
+        Ok(())
+    }
+}
@@ -1,8 +1,8 @@
 use palette::rgb::Rgb as PaletteRgb;
 use palette::stimulus::FromStimulus;
-use palette::LightenAssign;
+use palette::{FromColor, Hsl, LightenAssign};
 
-#[derive(Clone, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub(crate) struct Rgb {
     color: PaletteRgb,
 }
@@ -47,6 +47,37 @@ impl Rgb {
         new_color.color.lighten_assign(lightness);
         new_color
     }
+
+    /// The HSL lightness of this color, from 0.0 for black to 1.0 for white.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn lightness(&self) -> f32 {
+        Hsl::from_color(self.color).lightness
+    }
+
+    /// Multiplies the HSL lightness of this color by `factor`, keeping its hue and saturation.
+    /// Unlike `set_lightness`, which lightens towards white, factors below 1.0 darken the color.
+    pub(crate) fn scale_lightness(&self, factor: f32) -> Rgb {
+        let mut hsl = Hsl::from_color(self.color);
+        hsl.lightness = (hsl.lightness * factor).clamp(0.0, 1.0);
+        Self {
+            color: PaletteRgb::from_color(hsl),
+        }
+    }
+
+    /// Linearly interpolates between this color and `other`; `t` of 0.0 yields this color and 1.0
+    /// yields `other`.
+    #[inline(always)]
+    pub(crate) fn interpolate(&self, other: &Rgb, t: f32) -> Rgb {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: f32, b: f32| -> f32 { a + (b - a) * t };
+        Self {
+            color: PaletteRgb::new(
+                mix(self.color.red, other.color.red),
+                mix(self.color.green, other.color.green),
+                mix(self.color.blue, other.color.blue),
+            ),
+        }
+    }
 }
 
 impl From<Rgb> for crossterm::style::Color {
@@ -1,21 +1,39 @@
+use std::cell::RefCell;
 use std::io::Write;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use crossterm::{
     cursor,
-    event::{self, Event as CrossTermEvent, KeyCode, KeyEvent},
+    event::{self, Event as CrossTermEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     style,
     terminal, ExecutableCommand, QueueableCommand,
 };
 
-use super::canvas::Canvas;
+use super::canvas::{Canvas, CanvasSizeSource};
+use super::capabilities::{self, RenderCapabilities, SyncMode, SYNC_UPDATES_VAR};
 use super::error::Result;
-use super::events::{Event, EventSource, UserInput};
-use super::geometry::Direction;
+use super::events::{Event, EventSource, ResizeDebouncer, UserInput};
+use super::keymap::{Key, KeyBinding, Keymap};
 use super::renderer::Renderer;
+use super::tuxel::WIDE_CONTINUATION;
 
+/// Saves the terminal's window title on its title stack (XTWINOPS 22).
+const PUSH_TITLE: &str = "\x1b[22;0t";
+
+/// Restores the window title last saved on the terminal's title stack (XTWINOPS 23).
+const POP_TITLE: &str = "\x1b[23;0t";
+
+/// Terminals without a title stack ignore both sequences, and would be left with whatever title
+/// the game set last; `with_titles(false)` leaves the title alone altogether for them.
 pub(crate) struct Crossterm<T: Write> {
     w: Box<T>,
+    titles: bool,
+    title_pushed: bool,
+    // whether a frame was begun and not yet ended, in which case drawing only queues
+    in_frame: bool,
+    capabilities: RenderCapabilities,
 }
 
 impl<T: Write> Crossterm<T> {
@@ -25,114 +43,422 @@ impl<T: Write> Crossterm<T> {
             .with_context(|| "queue entering alternate screen")?;
         w.execute(cursor::Hide)
             .with_context(|| "queue hiding cursor")?;
-        Ok(Self { w })
+        Ok(Self::over(w))
     }
-}
 
-impl<T: Write> Drop for Crossterm<T> {
-    fn drop(&mut self) {
-        self.recover();
+    fn over(w: Box<T>) -> Self {
+        Self {
+            w,
+            titles: true,
+            title_pushed: false,
+            in_frame: false,
+            capabilities: RenderCapabilities::default(),
+        }
     }
-}
 
-impl<T: Write> Renderer for Crossterm<T> {
-    fn clear(&mut self, c: &Canvas) -> Result<()> {
-        let (width, height) = c.dimensions();
-        self.w
-            .execute(terminal::BeginSynchronizedUpdate)
-            .with_context(|| "queue synchronized update")?;
-        self.w
-            .queue(cursor::SavePosition)
-            .with_context(|| "queue save cursor position")?;
-        for x in 0..width {
-            for y in 0..height {
-                self.w
-                    .queue(cursor::MoveTo(x as u16, y as u16))
-                    .with_context(|| "queue moving cursor")?;
-                self.w
-                    .queue(style::Print(" "))
-                    .with_context(|| "queue printing tuxel text")?;
-            }
-        }
-        self.w
-            .queue(cursor::RestorePosition)
-            .with_context(|| "queue restore position")?;
-        self.w
-            .execute(terminal::EndSynchronizedUpdate)
-            .with_context(|| "queue end synchronized update")?;
+    /// Whether the game may set the window title. The title the terminal had before is saved the
+    /// first time one is set and put back by `recover`.
+    pub(crate) fn with_titles(mut self, titles: bool) -> Self {
+        self.titles = titles;
+        self
+    }
+
+    /// Relies on the terminal for what the given capabilities say it can do.
+    pub(crate) fn with_capabilities(mut self, capabilities: RenderCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    pub(crate) fn capabilities(&self) -> RenderCapabilities {
+        self.capabilities
+    }
+
+    /// Finds out what the terminal can do, unless the given mode or the environment says, and
+    /// relies on it from then on. Key presses read along with the terminal's replies are posted
+    /// to the given event source, so the game still gets them.
+    pub(crate) fn detect_capabilities(mut self, mode: SyncMode, events: &CrosstermEvents) -> Self {
+        let var = std::env::var(SYNC_UPDATES_VAR).ok();
+        let sender = events.sender();
+        let keymap = events.keymap.clone();
+        let capabilities = RenderCapabilities::resolve(mode, var.as_deref(), || {
+            let forward = move |bytes: Vec<u8>| {
+                for input in decode_keys(&bytes).filter_map(|ke| handle_key_event(&keymap, ke)) {
+                    let _ = sender.send(Event::UserInput(input));
+                }
+            };
+            capabilities::query_sync_updates(
+                self.w.as_mut(),
+                std::io::stdin(),
+                capabilities::QUERY_TIMEOUT,
+                forward,
+            )
+            .unwrap_or_else(|e| {
+                log::warn!("unable to query the terminal: {}", e);
+                None
+            })
+        });
+        self.with_capabilities(capabilities)
+    }
+
+    /// Forces any queued commands out to the terminal. Useful for callers that need guaranteed
+    /// delivery before doing something that blocks, like waiting for the next event.
+    pub(crate) fn flush_immediate(&mut self) -> Result<()> {
         self.w.flush().with_context(|| "flush writer")?;
         Ok(())
     }
 
-    fn render(&mut self, c: &Canvas) -> Result<()> {
-        self.w
-            .execute(terminal::BeginSynchronizedUpdate)
-            .with_context(|| "execute synchronized update")?;
-        self.w
-            .execute(cursor::SavePosition)
-            .with_context(|| "execute save cursor position")?;
+    /// Queues what the given function draws as a frame of its own, unless a frame has already
+    /// been begun.
+    fn framed(&mut self, draw: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        if self.in_frame {
+            return draw(self);
+        }
+        self.begin_frame()?;
+        let drawn = draw(self);
+        let ended = self.end_frame();
+        drawn.and(ended)
+    }
+
+    fn queue_changed(&mut self, c: &Canvas) -> Result<()> {
         for stack in c.get_changed() {
             let (fgcolor, bgcolor) = stack.colors();
             let output = match stack.content() {
+                // the double-width character to the left already covers this cell
+                Some(WIDE_CONTINUATION) | None => continue,
                 Some(c) => c,
-                None => continue,
             };
             let (x, y) = stack.coordinates();
             self.w
-                .execute(cursor::MoveTo(x as u16, y as u16))
-                .with_context(|| "execute moving cursor")?;
+                .queue(cursor::MoveTo(x as u16, y as u16))
+                .with_context(|| "queue moving cursor")?;
             if let Some(bg) = bgcolor {
-                self.w.execute(style::SetBackgroundColor(bg.into()))?;
+                self.w.queue(style::SetBackgroundColor(bg.into()))?;
             }
             if let Some(fg) = fgcolor {
-                self.w.execute(style::SetForegroundColor(fg.into()))?;
+                self.w.queue(style::SetForegroundColor(fg.into()))?;
             }
             self.w
-                .execute(style::Print(output))
-                .with_context(|| "execute printing cell text")?;
+                .queue(style::Print(output))
+                .with_context(|| "queue printing cell text")?;
             self.w
-                .execute(style::ResetColor)
-                .with_context(|| "execute color reset")?;
+                .queue(style::ResetColor)
+                .with_context(|| "queue color reset")?;
             self.w
-                .execute(style::SetAttribute(style::Attribute::Reset))
-                .with_context(|| "execute attribute reset")?;
+                .queue(style::SetAttribute(style::Attribute::Reset))
+                .with_context(|| "queue attribute reset")?;
         }
+        Ok(())
+    }
+
+    fn queue_rows(&mut self, c: &Canvas) -> Result<()> {
+        // everything is drawn below, whatever changed
+        let _ = c.get_changed();
+        // rows are printed left to right, so the cursor only has to be moved at the start of a
+        // row or past a cell that isn't printed, and colors only when they change
+        let mut colors = (None, None);
+        let w = &mut self.w;
+        c.for_each_row(|y, cells| {
+            let mut placed = false;
+            for (x, cell) in cells.iter().enumerate() {
+                let output = match cell.content() {
+                    // the double-width character to the left already covers this cell
+                    Some(WIDE_CONTINUATION) | None => {
+                        placed = false;
+                        continue;
+                    }
+                    Some(c) => c,
+                };
+                if !placed {
+                    w.queue(cursor::MoveTo(x as u16, y as u16))
+                        .with_context(|| "queue moving cursor")?;
+                    placed = true;
+                }
+                let cell_colors = cell.colors();
+                if cell_colors != colors {
+                    w.queue(style::ResetColor)
+                        .with_context(|| "queue color reset")?;
+                    if let Some(bg) = cell_colors.1.clone() {
+                        w.queue(style::SetBackgroundColor(bg.into()))?;
+                    }
+                    if let Some(fg) = cell_colors.0.clone() {
+                        w.queue(style::SetForegroundColor(fg.into()))?;
+                    }
+                    colors = cell_colors;
+                }
+                w.queue(style::Print(output))
+                    .with_context(|| "queue printing cell text")?;
+            }
+            Ok(())
+        })?;
         self.w
-            .execute(cursor::RestorePosition)
-            .with_context(|| "execute restore position")?;
+            .queue(style::ResetColor)
+            .with_context(|| "queue color reset")?;
         self.w
-            .execute(terminal::EndSynchronizedUpdate)
-            .with_context(|| "execute end synchronized update")?;
+            .queue(style::SetAttribute(style::Attribute::Reset))
+            .with_context(|| "queue attribute reset")?;
         Ok(())
     }
+}
+
+impl Canvas {
+    /// Takes over the terminal for drawing to the given writer and creates a canvas the size of
+    /// the terminal with room for `depth` layers, so that the two agree on the size from the
+    /// start.
+    pub(crate) fn new_from_writer<W: Write>(w: W, depth: usize) -> Result<(Canvas, Crossterm<W>)> {
+        Canvas::depth_allowed_or_err(depth)?;
+        let (width, height) = size()?;
+        let canvas = Canvas::with_depth(width as usize, height as usize, depth)?;
+        let renderer = Crossterm::new(Box::new(w))?;
+        Ok((canvas, renderer))
+    }
+}
+
+impl<T: Write> CanvasSizeSource for Crossterm<T> {
+    fn terminal_size(&self) -> Result<(usize, usize)> {
+        let (width, height) = size()?;
+        Ok((width as usize, height as usize))
+    }
+}
+
+impl<T: Write> Drop for Crossterm<T> {
+    fn drop(&mut self) {
+        self.recover();
+    }
+}
+
+impl<T: Write> Renderer for Crossterm<T> {
+    fn clear(&mut self, c: &Canvas) -> Result<()> {
+        let (width, height) = c.dimensions();
+        self.framed(|r| {
+            for x in 0..width {
+                for y in 0..height {
+                    r.w.queue(cursor::MoveTo(x as u16, y as u16))
+                        .with_context(|| "queue moving cursor")?;
+                    r.w.queue(style::Print(" "))
+                        .with_context(|| "queue printing tuxel text")?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn render(&mut self, c: &Canvas) -> Result<()> {
+        self.framed(|r| r.queue_changed(c))
+    }
+
+    fn repaint(&mut self, c: &Canvas) -> Result<()> {
+        self.framed(|r| r.queue_rows(c))
+    }
+
+    /// Starts a synchronized update, which the terminal holds back until the frame ends, if it
+    /// supports them.
+    fn begin_frame(&mut self) -> Result<()> {
+        debug_assert!(!self.in_frame, "frames don't nest");
+        self.in_frame = true;
+        if self.capabilities.synchronized_updates {
+            self.w
+                .queue(terminal::BeginSynchronizedUpdate)
+                .with_context(|| "queue synchronized update")?;
+        }
+        self.w
+            .queue(cursor::SavePosition)
+            .with_context(|| "queue save cursor position")?;
+        Ok(())
+    }
+
+    /// Ends the synchronized update and sends the whole frame to the terminal in one flush, which
+    /// keeps it from tearing much even on terminals without synchronized updates.
+    fn end_frame(&mut self) -> Result<()> {
+        self.in_frame = false;
+        self.w
+            .queue(cursor::RestorePosition)
+            .with_context(|| "queue restore position")?;
+        if self.capabilities.synchronized_updates {
+            self.w
+                .queue(terminal::EndSynchronizedUpdate)
+                .with_context(|| "queue end synchronized update")?;
+        }
+        self.flush_immediate()
+    }
 
     fn size_hint(&self) -> Result<(u16, u16)> {
         size()
     }
 
-    fn recover(&mut self) {
-        self.w.execute(cursor::Show).expect("showing cursor again");
+    fn set_title(&mut self, title: &str) -> Result<()> {
+        if !self.titles {
+            return Ok(());
+        }
+        // crossterm has no command for the title stack, so the sequence is written directly
+        if !self.title_pushed {
+            self.w
+                .write_all(PUSH_TITLE.as_bytes())
+                .with_context(|| "queue saving the window title")?;
+            self.title_pushed = true;
+        }
         self.w
-            .execute(terminal::LeaveAlternateScreen)
-            .expect("leaving alternate screen");
-        terminal::disable_raw_mode().expect("disabling raw mode");
+            .queue(terminal::SetTitle(title))
+            .with_context(|| "queue setting the window title")?;
+        self.flush_immediate()
+    }
+
+    fn recover(&mut self) {
+        // recovering runs as the renderer is dropped, possibly while unwinding where a panic
+        // would abort, so every step is tried and failures are only logged
+        let warn = |step: &str, result: std::io::Result<()>| {
+            if let Err(e) = result {
+                log::warn!("failed {} while restoring the terminal: {}", step, e);
+            }
+        };
+        // reset colors before leaving the alternate screen; some terminals, notably conhost,
+        // otherwise carry the last colors drawn over to the main screen
+        warn(
+            "resetting colors",
+            self.w.execute(style::ResetColor).map(drop),
+        );
+        warn(
+            "resetting attributes",
+            self.w
+                .execute(style::SetAttribute(style::Attribute::Reset))
+                .map(drop),
+        );
+        warn("showing the cursor", self.w.execute(cursor::Show).map(drop));
+        if self.title_pushed {
+            warn(
+                "restoring the window title",
+                self.w
+                    .write_all(POP_TITLE.as_bytes())
+                    .and_then(|_| self.w.flush()),
+            );
+            self.title_pushed = false;
+        }
+        warn(
+            "leaving the alternate screen",
+            self.w.execute(terminal::LeaveAlternateScreen).map(drop),
+        );
+        warn("disabling raw mode", terminal::disable_raw_mode());
     }
 }
 
-#[derive(Default)]
-pub(crate) struct CrosstermEvents {}
+/// Takes the terminal out of raw mode without writing anything to it, for when output no longer
+/// goes through and the escape sequences `recover` writes would never arrive.
+pub(crate) fn emergency_recover() {
+    if let Err(e) = terminal::disable_raw_mode() {
+        log::error!("unable to disable raw mode: {}", e);
+    }
+}
+
+// how long to wait for terminal events before checking for internally posted events
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// resize events arriving within this long of the first one in a burst are coalesced
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Multiplexes terminal events with events posted by background tasks through the `Sender`
+/// returned by `CrosstermEvents::sender`. Terminal events always take precedence. Bursts of
+/// terminal resize events are reported as a single `Event::Resize`.
+pub(crate) struct CrosstermEvents {
+    sender: Sender<Event>,
+    receiver: Receiver<Event>,
+    debouncer: RefCell<ResizeDebouncer>,
+    keymap: Keymap,
+    // an event that arrived while a resize was pending; it is reported right after the resize
+    deferred: RefCell<Option<Event>>,
+}
+
+impl Default for CrosstermEvents {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            sender,
+            receiver,
+            debouncer: RefCell::new(ResizeDebouncer::new(RESIZE_DEBOUNCE)),
+            keymap: Keymap::default(),
+            deferred: RefCell::new(None),
+        }
+    }
+}
+
+impl CrosstermEvents {
+    /// Returns a sender that can be used to post low-priority events from other threads.
+    pub(crate) fn sender(&self) -> Sender<Event> {
+        self.sender.clone()
+    }
+
+    /// Maps key presses to actions with the given keymap rather than the default one.
+    pub(crate) fn with_keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
+    fn read_terminal_event(&self) -> Result<Option<Event>> {
+        Ok(
+            match event::read().with_context(|| "read crossterm events")? {
+                CrossTermEvent::Resize(_, _) => Some(Event::Resize),
+                CrossTermEvent::Key(ke) => handle_key_event(&self.keymap, ke).map(Event::UserInput),
+                _ => None,
+            },
+        )
+    }
+
+    /// Reads the next terminal event, holding back resizes until their burst is over.
+    fn next_terminal_event(&self) -> Result<Option<Event>> {
+        let mut debouncer = self.debouncer.borrow_mut();
+        match self.read_terminal_event()? {
+            Some(Event::Resize) => {
+                debouncer.resized();
+                Ok(None)
+            }
+            Some(e) if debouncer.take_pending() => {
+                *self.deferred.borrow_mut() = Some(e);
+                Ok(Some(Event::Resize))
+            }
+            e => Ok(e),
+        }
+    }
+}
 
 impl EventSource for CrosstermEvents {
     fn next_event(&self) -> Result<Event> {
         loop {
-            match event::read().with_context(|| "read crossterm events")? {
-                CrossTermEvent::Resize(_, _) => return Ok(Event::Resize),
-                CrossTermEvent::Key(ke) => match handle_key_event(ke) {
-                    Some(ke) => return Ok(Event::UserInput(ke)),
+            if let Some(e) = self.poll_event(POLL_INTERVAL)? {
+                return Ok(e);
+            }
+        }
+    }
+
+    fn poll_event(&self, timeout: Duration) -> Result<Option<Event>> {
+        if let Some(e) = self.deferred.borrow_mut().take() {
+            return Ok(Some(e));
+        }
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.debouncer.borrow_mut().take_due() {
+                return Ok(Some(Event::Resize));
+            }
+            if event::poll(Duration::ZERO).with_context(|| "poll crossterm events")? {
+                match self.next_terminal_event()? {
+                    Some(e) => return Ok(Some(e)),
                     None => continue,
-                },
-                _ => continue,
+                }
+            }
+            if let Ok(e) = self.receiver.try_recv() {
+                return Ok(Some(e));
+            }
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return Ok(None);
+            }
+            // wake up in time to deliver a pending resize, and to check for posted events
+            let timeout = match self.debouncer.borrow().remaining() {
+                Some(remaining) => remaining.min(POLL_INTERVAL).min(left),
+                None => POLL_INTERVAL.min(left),
             };
+            if event::poll(timeout).with_context(|| "poll crossterm events")? {
+                if let Some(e) = self.next_terminal_event()? {
+                    return Ok(Some(e));
+                }
+            }
         }
     }
 }
@@ -141,16 +467,418 @@ fn size() -> Result<(u16, u16)> {
     Ok(terminal::size().with_context(|| "get terminal size")?)
 }
 
-fn handle_key_event(ke: KeyEvent) -> Option<UserInput> {
-    match ke {
-        KeyEvent { code, .. } => match code {
-            KeyCode::Left | KeyCode::Char('h') => Some(UserInput::Direction(Direction::Left)),
-            KeyCode::Right | KeyCode::Char('l') => Some(UserInput::Direction(Direction::Right)),
-            KeyCode::Up | KeyCode::Char('k') => Some(UserInput::Direction(Direction::Up)),
-            KeyCode::Down | KeyCode::Char('j') => Some(UserInput::Direction(Direction::Down)),
-            KeyCode::Char('q') => Some(UserInput::Quit),
-            KeyCode::Char('n') => Some(UserInput::NewGame),
-            _ => None,
-        },
+/// Decodes the key presses in raw terminal input read outside of crossterm: text, Enter, Esc,
+/// Backspace, the arrow keys and control characters, which is all the keymap binds. Other
+/// sequences are dropped.
+fn decode_keys(bytes: &[u8]) -> impl Iterator<Item = KeyEvent> {
+    let text = String::from_utf8_lossy(bytes).into_owned();
+    let mut chars = text.chars().collect::<Vec<char>>().into_iter().peekable();
+    std::iter::from_fn(move || loop {
+        let c = chars.next()?;
+        let (code, modifiers) = match c {
+            '\x1b' => match chars.next_if(|c| *c == '[' || *c == 'O') {
+                Some(_) => {
+                    // any parameters, eg of a modified key, up to the final character
+                    while chars.next_if(|c| c.is_ascii_digit() || *c == ';').is_some() {}
+                    match chars.next() {
+                        Some('A') => (KeyCode::Up, KeyModifiers::NONE),
+                        Some('B') => (KeyCode::Down, KeyModifiers::NONE),
+                        Some('C') => (KeyCode::Right, KeyModifiers::NONE),
+                        Some('D') => (KeyCode::Left, KeyModifiers::NONE),
+                        _ => continue,
+                    }
+                }
+                None => (KeyCode::Esc, KeyModifiers::NONE),
+            },
+            '\r' | '\n' => (KeyCode::Enter, KeyModifiers::NONE),
+            '\x7f' => (KeyCode::Backspace, KeyModifiers::NONE),
+            '\x01'..='\x1a' => {
+                let letter = (c as u8 - 1 + b'a') as char;
+                (KeyCode::Char(letter), KeyModifiers::CONTROL)
+            }
+            c if c.is_control() => continue,
+            c => (KeyCode::Char(c), KeyModifiers::NONE),
+        };
+        return Some(KeyEvent::new(code, modifiers));
+    })
+}
+
+fn handle_key_event(keymap: &Keymap, ke: KeyEvent) -> Option<UserInput> {
+    // Windows reports key releases as well as presses; only act on the press
+    if ke.kind == KeyEventKind::Release {
+        return None;
+    }
+    let key = match ke.code {
+        KeyCode::Char(c) => Key::Char(c),
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Esc => Key::Esc,
+        KeyCode::Backspace => Key::Backspace,
+        _ => return None,
+    };
+    let ctrl = ke.modifiers.contains(KeyModifiers::CONTROL);
+    keymap.action_for(&KeyBinding { key, ctrl })
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+    use crate::tui::canvas::MAX_CANVAS_DEPTH;
+    use crate::tui::drawbuffer::{DrawBufferOwner, Owner};
+    use crate::tui::error::InnerError;
+    use crate::tui::geometry::Direction;
+    use crate::tui::geometry::{Bounds2D, Idx, Rectangle};
+
+    #[derive(Default)]
+    struct FlushCounter {
+        written: usize,
+        flushes: usize,
+    }
+
+    impl Write for FlushCounter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn render_flushes_exactly_once() -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let rectangle = Rectangle(Idx(1, 1, 0), Bounds2D(3, 3));
+        let mut dbuf = canvas.get_draw_buffer(rectangle, Owner::Named("test"))?;
+        dbuf.fill('x')?;
+
+        let mut renderer = Crossterm::over(Box::new(FlushCounter::default()));
+        renderer.render(&canvas)?;
+        assert_eq!(renderer.w.flushes, 1);
+        assert!(renderer.w.written > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn a_frame_is_one_synchronized_update_sent_in_one_flush() -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let mut renderer = Crossterm::over(Box::new(Vec::new()));
+        for frame in 0..3 {
+            let rectangle = Rectangle(Idx(frame, 1, 0), Bounds2D(3, 3));
+            let mut dbuf = canvas.get_draw_buffer(rectangle, Owner::Named("test"))?;
+            dbuf.fill('x')?;
+            renderer.begin_frame()?;
+            // drawing more than once within a frame only queues
+            renderer.render(&canvas)?;
+            renderer.render(&canvas)?;
+            renderer.end_frame()?;
+        }
+        renderer.repaint(&canvas)?;
+
+        let output = String::from_utf8_lossy(&renderer.w).to_string();
+        let begin = "\x1b[?2026h";
+        let end = "\x1b[?2026l";
+        assert_eq!(output.matches(begin).count(), 4, "{:?}", output);
+        assert_eq!(output.matches(end).count(), 4, "{:?}", output);
+        // each update ends before the next begins
+        let markers: Vec<&str> = output
+            .match_indices("\x1b[?2026")
+            .map(|(at, _)| &output[at..at + 8])
+            .collect();
+        assert!(
+            markers.chunks(2).all(|pair| pair == [begin, end]),
+            "{:?}",
+            markers
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::supported(true)]
+    #[case::unsupported(false)]
+    fn synchronized_updates_are_only_sent_to_terminals_that_support_them(
+        #[case] synchronized_updates: bool,
+    ) -> Result<()> {
+        use crate::tui::capabilities::CapabilitySource;
+
+        let canvas = Canvas::new(10, 10);
+        let rectangle = Rectangle(Idx(1, 1, 0), Bounds2D(3, 3));
+        let mut dbuf = canvas.get_draw_buffer(rectangle, Owner::Named("test"))?;
+        dbuf.fill('x')?;
+
+        let mut renderer =
+            Crossterm::over(Box::new(Vec::new())).with_capabilities(RenderCapabilities {
+                synchronized_updates,
+                source: CapabilitySource::Detected,
+            });
+        renderer.begin_frame()?;
+        renderer.render(&canvas)?;
+        renderer.end_frame()?;
+        renderer.repaint(&canvas)?;
+        renderer.clear(&canvas)?;
+
+        let output = String::from_utf8_lossy(&renderer.w).to_string();
+        let expected = if synchronized_updates { 3 } else { 0 };
+        let (begun, ended) = ("\x1b[?2026h", "\x1b[?2026l");
+        assert_eq!(output.matches(begun).count(), expected, "{:?}", output);
+        assert_eq!(output.matches(ended).count(), expected, "{:?}", output);
+        assert_eq!(output.matches('x').count(), 18, "{:?}", output);
+        Ok(())
+    }
+
+    #[test]
+    fn keys_pressed_during_a_query_are_decoded() {
+        let keys: Vec<(KeyCode, KeyModifiers)> =
+            decode_keys(b"h\x1b[A\x1bOB\r\x7f\x03\x1b\x1b[5~q")
+                .map(|ke| (ke.code, ke.modifiers))
+                .collect();
+        assert_eq!(
+            keys,
+            vec![
+                (KeyCode::Char('h'), KeyModifiers::NONE),
+                (KeyCode::Up, KeyModifiers::NONE),
+                (KeyCode::Down, KeyModifiers::NONE),
+                (KeyCode::Enter, KeyModifiers::NONE),
+                (KeyCode::Backspace, KeyModifiers::NONE),
+                (KeyCode::Char('c'), KeyModifiers::CONTROL),
+                (KeyCode::Esc, KeyModifiers::NONE),
+                // page up isn't decoded, but what follows it is
+                (KeyCode::Char('q'), KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_frame_flushes_once_it_ends() -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let rectangle = Rectangle(Idx(1, 1, 0), Bounds2D(3, 3));
+        let mut dbuf = canvas.get_draw_buffer(rectangle, Owner::Named("test"))?;
+        dbuf.fill('x')?;
+
+        let mut renderer = Crossterm::over(Box::new(FlushCounter::default()));
+        renderer.begin_frame()?;
+        renderer.render(&canvas)?;
+        renderer.repaint(&canvas)?;
+        assert_eq!(renderer.w.flushes, 0);
+        renderer.end_frame()?;
+        assert_eq!(renderer.w.flushes, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn repaint_moves_the_cursor_once_a_row() -> Result<()> {
+        let canvas = Canvas::new(6, 3);
+        let rectangle = Rectangle(Idx(1, 1, 0), Bounds2D(3, 1));
+        let mut dbuf = canvas.get_draw_buffer(rectangle, Owner::Named("test"))?;
+        dbuf.fill('x')?;
+
+        let mut renderer = Crossterm::over(Box::new(Vec::new()));
+        renderer.repaint(&canvas)?;
+        let output = String::from_utf8_lossy(&renderer.w).to_string();
+        // blank cells are printed too, so every row is printed in one go
+        assert_eq!(output.matches('H').count(), 3, "{:?}", output);
+        assert_eq!(output.matches('x').count(), 3, "{:?}", output);
+        assert_eq!(output.matches(' ').count(), 15, "{:?}", output);
+        assert!(canvas.get_changed().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn recover_resets_colors_before_leaving_alternate_screen() {
+        let mut renderer = Crossterm::over(Box::new(Vec::new()));
+        renderer.recover();
+        let output = String::from_utf8_lossy(&renderer.w).to_string();
+
+        let reset = output.find("\x1b[0m").expect("colors should be reset");
+        let leave = output
+            .find("\x1b[?1049l")
+            .expect("alternate screen should be left");
+        assert!(reset < leave, "{:?}", output);
+    }
+
+    /// A writer whose output can still be read once the renderer writing to it has been dropped.
+    #[derive(Clone, Default)]
+    struct SharedOutput(std::rc::Rc<RefCell<Vec<u8>>>);
+
+    impl SharedOutput {
+        fn text(&self) -> String {
+            String::from_utf8_lossy(&self.0.borrow()).to_string()
+        }
+    }
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn title_is_saved_once_before_it_is_first_set() -> Result<()> {
+        let mut renderer = Crossterm::over(Box::new(Vec::new()));
+        renderer.set_title("tui48 \u{2014} 0")?;
+        renderer.set_title("tui48 \u{2014} 4")?;
+        let output = String::from_utf8_lossy(&renderer.w).to_string();
+
+        assert_eq!(output.matches(PUSH_TITLE).count(), 1, "{:?}", output);
+        let push = output.find(PUSH_TITLE).expect("title should be saved");
+        let set = output.find("\x1b]0;").expect("title should be set");
+        assert!(push < set, "{:?}", output);
+        assert!(!output.contains(POP_TITLE), "{:?}", output);
+        Ok(())
+    }
+
+    #[test]
+    fn recover_restores_the_saved_title_once() -> Result<()> {
+        let output = SharedOutput::default();
+        let mut renderer = Crossterm::over(Box::new(output.clone()));
+        renderer.set_title("tui48 \u{2014} 0")?;
+        // recovering on the way out of a failed game, then again when the renderer is dropped
+        renderer.recover();
+        drop(renderer);
+
+        let output = output.text();
+        assert_eq!(output.matches(POP_TITLE).count(), 1, "{:?}", output);
+        let leave = output
+            .find("\x1b[?1049l")
+            .expect("alternate screen should be left");
+        assert!(output.find(POP_TITLE) < Some(leave), "{:?}", output);
+        Ok(())
+    }
+
+    #[test]
+    fn dropping_the_renderer_restores_the_saved_title() -> Result<()> {
+        // what happens when a panic unwinds past the game
+        let output = SharedOutput::default();
+        let mut renderer = Crossterm::over(Box::new(output.clone()));
+        renderer.set_title("tui48 \u{2014} 0")?;
+        drop(renderer);
+        assert_eq!(output.text().matches(POP_TITLE).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn recover_leaves_the_title_alone_if_none_was_set() {
+        let mut renderer = Crossterm::over(Box::new(Vec::new()));
+        renderer.recover();
+        let output = String::from_utf8_lossy(&renderer.w).to_string();
+        assert!(!output.contains(POP_TITLE), "{:?}", output);
+    }
+
+    #[test]
+    fn disabled_titles_emit_nothing() -> Result<()> {
+        let output = SharedOutput::default();
+        let mut renderer = Crossterm::over(Box::new(output.clone())).with_titles(false);
+        renderer.set_title("tui48 \u{2014} 0")?;
+        renderer.recover();
+        drop(renderer);
+
+        let output = output.text();
+        for sequence in [PUSH_TITLE, POP_TITLE, "\x1b]0;"] {
+            assert!(!output.contains(sequence), "{:?}", output);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn new_from_writer_refuses_more_layers_than_canvases_can_have() {
+        // refused before the terminal is touched, so this runs without one
+        match Canvas::new_from_writer(Vec::new(), MAX_CANVAS_DEPTH + 1) {
+            Err(e) => match e.inner {
+                InnerError::InvalidCanvasDepth { requested, max } => {
+                    assert_eq!((requested, max), (MAX_CANVAS_DEPTH + 1, MAX_CANVAS_DEPTH))
+                }
+                other => panic!("expected the depth to be refused, got {:?}", other),
+            },
+            Ok(_) => panic!("expected the depth to be refused"),
+        }
+    }
+
+    #[test]
+    #[ignore = "takes over the terminal, so needs one"]
+    fn new_from_writer_sizes_the_canvas_to_the_terminal() -> Result<()> {
+        let (canvas, renderer) = Canvas::new_from_writer(Vec::new(), 12)?;
+        let (width, height) = renderer.size_hint()?;
+        assert_eq!(canvas.dimensions(), (width as usize, height as usize));
+        assert_eq!(canvas.depth(), 12);
+        Ok(())
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers, kind: KeyEventKind) -> KeyEvent {
+        KeyEvent::new_with_kind(code, modifiers, kind)
+    }
+
+    #[test]
+    fn ctrl_c_quits() {
+        let ctrl_c = key(
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL,
+            KeyEventKind::Press,
+        );
+        assert!(matches!(
+            handle_key_event(&Keymap::default(), ctrl_c),
+            Some(UserInput::Quit)
+        ));
+
+        // without Ctrl it's an ordinary key
+        let c = key(KeyCode::Char('c'), KeyModifiers::NONE, KeyEventKind::Press);
+        assert!(matches!(
+            handle_key_event(&Keymap::default(), c),
+            Some(UserInput::Continue)
+        ));
+    }
+
+    #[test]
+    fn key_releases_are_ignored() {
+        let press = key(KeyCode::Left, KeyModifiers::NONE, KeyEventKind::Press);
+        assert!(matches!(
+            handle_key_event(&Keymap::default(), press),
+            Some(UserInput::Direction(Direction::Left))
+        ));
+
+        let release = key(KeyCode::Left, KeyModifiers::NONE, KeyEventKind::Release);
+        assert!(handle_key_event(&Keymap::default(), release).is_none());
+        let release = key(
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL,
+            KeyEventKind::Release,
+        );
+        assert!(handle_key_event(&Keymap::default(), release).is_none());
+    }
+
+    #[test]
+    fn keys_are_mapped_through_the_keymap() {
+        let mut keymap = Keymap::default();
+        keymap.bind(KeyBinding::plain(Key::Char('x')), UserInput::NewGame);
+        keymap.unbind(&KeyBinding::plain(Key::Char('h')));
+
+        let x = key(KeyCode::Char('x'), KeyModifiers::NONE, KeyEventKind::Press);
+        assert_eq!(handle_key_event(&keymap, x), Some(UserInput::NewGame));
+        let h = key(KeyCode::Char('h'), KeyModifiers::NONE, KeyEventKind::Press);
+        assert_eq!(handle_key_event(&keymap, h), None);
+        let enter = key(KeyCode::Enter, KeyModifiers::NONE, KeyEventKind::Press);
+        assert_eq!(handle_key_event(&keymap, enter), Some(UserInput::Confirm));
+        let space = key(KeyCode::Char(' '), KeyModifiers::NONE, KeyEventKind::Press);
+        assert_eq!(handle_key_event(&keymap, space), Some(UserInput::PowerUp));
+        let tab = key(KeyCode::Tab, KeyModifiers::NONE, KeyEventKind::Press);
+        assert_eq!(handle_key_event(&keymap, tab), None);
+    }
+
+    #[test]
+    fn flush_immediate_flushes() -> Result<()> {
+        let mut renderer = Crossterm::over(Box::new(FlushCounter::default()));
+        renderer.flush_immediate()?;
+        assert_eq!(renderer.w.flushes, 1);
+        Ok(())
     }
 }
@@ -0,0 +1,104 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use super::geometry::Idx;
+
+const BITS: usize = u64::BITS as usize;
+
+/// The cells of a canvas changed since the changes were last collected, as a bit per cell. Tuxels
+/// mark their cell with a single atomic operation, which they can do while their buffer and the
+/// canvas are locked, and draining the set sweeps it once. A cell changed any number of times, on
+/// any number of layers, is collected once, and there's no limit to how much can change between
+/// two collections.
+#[derive(Clone)]
+pub(crate) struct DirtySet {
+    inner: Arc<DirtySetInner>,
+}
+
+struct DirtySetInner {
+    width: usize,
+    height: usize,
+    words: Box<[AtomicU64]>,
+}
+
+impl DirtySet {
+    pub(crate) fn new(width: usize, height: usize) -> Self {
+        let words = (width * height).div_ceil(BITS);
+        Self {
+            inner: Arc::new(DirtySetInner {
+                width,
+                height,
+                words: std::iter::repeat_with(AtomicU64::default)
+                    .take(words)
+                    .collect(),
+            }),
+        }
+    }
+
+    /// Marks the cell at the given index as changed, whatever its layer. Indexes outside the
+    /// canvas, eg of a tuxel from before a resize, are ignored.
+    pub(crate) fn mark(&self, idx: &Idx) {
+        let inner = &self.inner;
+        if idx.x() >= inner.width || idx.y() >= inner.height {
+            return;
+        }
+        let bit = idx.y() * inner.width + idx.x();
+        inner.words[bit / BITS].fetch_or(1 << (bit % BITS), Ordering::AcqRel);
+    }
+
+    /// Returns the coordinates of every cell marked since the set was last drained, row by row,
+    /// and unmarks them.
+    pub(crate) fn drain(&self) -> Vec<(usize, usize)> {
+        let inner = &self.inner;
+        let mut marked = Vec::new();
+        for (n, word) in inner.words.iter().enumerate() {
+            let mut bits = word.swap(0, Ordering::AcqRel);
+            while bits != 0 {
+                let bit = n * BITS + bits.trailing_zeros() as usize;
+                marked.push((bit % inner.width, bit / inner.width));
+                bits &= bits - 1;
+            }
+        }
+        marked
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+
+    #[test]
+    fn cells_are_collected_once_however_often_they_change() {
+        let set = DirtySet::new(4, 3);
+        set.mark(&Idx(1, 2, 0));
+        set.mark(&Idx(1, 2, 5));
+        set.mark(&Idx(3, 0, 1));
+        set.mark(&Idx(1, 2, 0));
+        assert_eq!(set.drain(), vec![(3, 0), (1, 2)]);
+        assert_eq!(set.drain(), vec![]);
+    }
+
+    #[rstest]
+    #[case::past_the_right_edge(Idx(10, 0, 0))]
+    #[case::past_the_bottom(Idx(0, 7, 0))]
+    fn cells_outside_the_canvas_are_ignored(#[case] idx: Idx) {
+        let set = DirtySet::new(10, 7);
+        set.mark(&idx);
+        assert_eq!(set.drain(), vec![]);
+    }
+
+    #[test]
+    fn every_cell_of_a_canvas_wider_than_a_word_can_be_marked() {
+        let (width, height) = (67, 5);
+        let set = DirtySet::new(width, height);
+        let all: Vec<_> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .collect();
+        for (x, y) in all.iter().rev() {
+            set.mark(&Idx(*x, *y, 0));
+        }
+        assert_eq!(set.drain(), all);
+    }
+}
@@ -1,5 +1,5 @@
 use std::sync::mpsc::Sender;
-use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
 
 use super::canvas::{Canvas, Modifier};
 use super::colors::Rgb;
@@ -7,12 +7,82 @@ use super::error::{InnerError, Result};
 use super::geometry::{Direction, Idx, Position, Rectangle};
 use super::tuxel::Tuxel;
 
+/// Names the component a buffer was acquired for so that acquisition failures and canvas dumps can
+/// say who is involved.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Owner {
+    /// A component of which there is only one, eg "board" or "score".
+    Named(&'static str),
+    /// One of several components of the same kind told apart by their coordinates, eg a tile.
+    At(&'static str, usize, usize),
+}
+
+impl Owner {
+    /// A single character standing in for the owner in canvas dumps.
+    pub(crate) fn initial(&self) -> char {
+        let name = match self {
+            Owner::Named(name) => name,
+            Owner::At(name, _, _) => name,
+        };
+        name.chars().next().unwrap_or('?')
+    }
+}
+
+impl std::fmt::Display for Owner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Owner::Named(name) => write!(f, "{}", name),
+            Owner::At(name, x, y) => write!(f, "{}({},{})", name, x, y),
+        }
+    }
+}
+
+/// Number of frames in one full oscillation of a pulse, unless configured otherwise.
+pub(crate) const PULSE_PERIOD: usize = 20;
+
 pub(crate) trait DrawBufferOwner {
     fn lock<'a>(&'a self) -> MutexGuard<'a, DrawBufferInner>;
     fn inner(&self) -> Arc<Mutex<DrawBufferInner>>;
 
+    /// Adds to the modifiers coloring the buffer. Cells already drawn are repainted in the new
+    /// colors.
     fn modify(&mut self, modifier: Modifier) {
-        self.lock().modifiers.push(modifier)
+        let mut inner = self.lock();
+        inner.modifiers.push(modifier);
+        inner.recolored();
+    }
+
+    /// Dims the whole buffer, unless it is already dimmed and hasn't been recolored since.
+    fn dim(&mut self) {
+        let mut inner = self.lock();
+        if inner.modifiers.last() == Some(&Modifier::Dim) {
+            return;
+        }
+        inner.modifiers.push(Modifier::Dim);
+        inner.recolored();
+    }
+
+    /// Scales the lightness of both colors of every cell by the given factor, from 0.0 for black to
+    /// 1.0 for the colors as they are, on top of everything else coloring the buffer; None stops
+    /// fading it. The modifiers are left alone, so the buffer looks as it did once the fade ends.
+    fn fade(&mut self, factor: Option<f32>) {
+        let mut inner = self.lock();
+        if inner.fade == factor {
+            return;
+        }
+        inner.fade = factor;
+        inner.recolored();
+    }
+
+    /// Sets the foreground color of the border independently of the rest of the buffer.
+    fn highlight_border(&mut self, color: Rgb) {
+        let mut inner = self.lock();
+        inner.border_modifiers.push(Modifier::SetForegroundColor(
+            color.r(),
+            color.g(),
+            color.b(),
+        ));
+        inner.recolored();
     }
 
     fn draw_border(&mut self) -> Result<()> {
@@ -23,6 +93,27 @@ pub(crate) trait DrawBufferOwner {
         self.lock().fill(c)
     }
 
+    fn fill_edge(&mut self, c: char) -> Result<()> {
+        self.lock().fill_edge(c)
+    }
+
+    /// Sets the colors of the cell at the given coordinates, relative to the buffer, without
+    /// changing its content; None leaves that color as it is. The buffer's modifiers still apply on
+    /// top, like they do for colors written through a `TextBuffer`.
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn set_color_at(&mut self, x: usize, y: usize, fg: Option<Rgb>, bg: Option<Rgb>) -> Result<()> {
+        let mut inner = self.lock();
+        let tuxel = inner.get_tuxel_mut(Position::Coordinates(x, y))?;
+        if let Some(fg) = fg {
+            tuxel.set_fgcolor(fg);
+        }
+        if let Some(bg) = bg {
+            tuxel.set_bgcolor(bg);
+        }
+        tuxel.touch();
+        Ok(())
+    }
+
     fn translate(&self, dir: Direction) -> Result<()> {
         self.lock().translate(dir)
     }
@@ -34,6 +125,16 @@ pub(crate) trait DrawBufferOwner {
     fn rectangle(&self) -> Rectangle {
         self.lock().rectangle()
     }
+
+    /// Starts oscillating the buffer's background between its current color and `color` for the
+    /// given number of full cycles. Nothing changes until the returned handle is ticked.
+    fn pulse_animation(&self, color: Rgb, cycles: usize) -> PulseHandle {
+        PulseHandle::new(self.inner(), color, cycles)
+    }
+
+    fn owner(&self) -> Owner {
+        self.lock().owner.clone()
+    }
 }
 
 pub(crate) struct DrawBufferInner {
@@ -41,7 +142,17 @@ pub(crate) struct DrawBufferInner {
     pub(crate) border: bool,
     pub(crate) buf: Vec<Vec<Tuxel>>,
     pub(crate) modifiers: Vec<Modifier>,
+    /// Modifiers applied to border cells on top of `modifiers`.
+    pub(crate) border_modifiers: Vec<Modifier>,
+    /// Background color overriding `modifiers` while a pulse is running.
+    pub(crate) pulse: Option<Rgb>,
+    /// Factor scaling the lightness of every color, border included, while the buffer fades in.
+    pub(crate) fade: Option<f32>,
     pub(crate) canvas: Canvas,
+    pub(crate) owner: Owner,
+    /// The last collection of changes the buffer's cells were reported as recolored for; see
+    /// `Canvas::changes_collected`.
+    pub(crate) recolored_for: Option<usize>,
 }
 
 impl std::fmt::Display for DrawBufferInner {
@@ -86,6 +197,20 @@ impl DrawBufferInner {
         self.rectangle.clone()
     }
 
+    /// Reports the cells drawn on as changed after the buffer's colors changed, so that they are
+    /// repainted. Cells that aren't drawn on don't show the buffer's colors, and cells reported
+    /// since changes were last collected are going to be repainted anyway.
+    fn recolored(&mut self) {
+        let collected = self.canvas.changes_collected();
+        if self.recolored_for == Some(collected) {
+            return;
+        }
+        self.recolored_for = Some(collected);
+        for tuxel in self.buf.iter().flatten().filter(|tuxel| tuxel.active()) {
+            tuxel.touch();
+        }
+    }
+
     fn fill(&mut self, c: char) -> Result<()> {
         let (skipx, takex, skipy, takey) = if self.border {
             (
@@ -110,6 +235,20 @@ impl DrawBufferInner {
         Ok(())
     }
 
+    /// Fills only the outermost rows and columns of the buffer. Unlike `draw_border` this works for
+    /// buffers of any size, including those only one cell wide or tall.
+    fn fill_edge(&mut self, c: char) -> Result<()> {
+        let (width, height) = (self.rectangle.width(), self.rectangle.height());
+        for (y, row) in self.buf.iter_mut().enumerate() {
+            for (x, tuxel) in row.iter_mut().enumerate() {
+                if x == 0 || y == 0 || x + 1 == width || y + 1 == height {
+                    tuxel.set_content(c);
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn draw_border(&mut self) -> Result<()> {
         let box_corner = boxy::Char::upper_left(boxy::Weight::Doubled);
         let box_horizontal = boxy::Char::horizontal(boxy::Weight::Doubled);
@@ -188,7 +327,10 @@ impl DrawBufferInner {
         let old = self.rectangle.clone();
         self.rectangle.0 .2 = zdx;
 
-        self.canvas.swap_rectangles(&self.rectangle, &old)?;
+        if let Err(e) = self.canvas.swap_rectangles(&self.rectangle, &old) {
+            self.rectangle = old;
+            return Err(e);
+        }
 
         for tuxel in self.buf.iter_mut().map(|v| v.iter_mut()).flatten() {
             let mut idx = tuxel.idx();
@@ -202,6 +344,10 @@ impl DrawBufferInner {
         self.rectangle.translate(1, &dir)?;
         let canvas_bounds = self.canvas.bounds();
         log::trace!("translating DrawBuffer {}", dir);
+        // tuxels are moved in the order that has each one land in a cell the buffer has just
+        // vacated or in one outside of it, so an owned destination always belongs to another
+        // buffer
+
         match dir {
             Direction::Left => {
                 for t in self.buf.iter_mut().flatten() {
@@ -214,7 +360,8 @@ impl DrawBufferInner {
                             InnerError::DrawBufferTranslationFailed(String::from("")).into()
                         );
                     }
-                    self.canvas.swap_tuxels(current_idx, new_idx.clone())?;
+                    self.canvas
+                        .move_tuxel(current_idx, new_idx.clone(), &self.owner)?;
                     t.set_idx(&new_idx);
                 }
             }
@@ -229,7 +376,8 @@ impl DrawBufferInner {
                             InnerError::DrawBufferTranslationFailed(String::from("")).into()
                         );
                     }
-                    self.canvas.swap_tuxels(current_idx, new_idx.clone())?;
+                    self.canvas
+                        .move_tuxel(current_idx, new_idx.clone(), &self.owner)?;
                     t.set_idx(&new_idx);
                 }
             }
@@ -245,7 +393,8 @@ impl DrawBufferInner {
                         );
                     }
 
-                    self.canvas.swap_tuxels(current_idx, new_idx.clone())?;
+                    self.canvas
+                        .move_tuxel(current_idx, new_idx.clone(), &self.owner)?;
                     t.set_idx(&new_idx);
                 }
             }
@@ -260,7 +409,8 @@ impl DrawBufferInner {
                             InnerError::DrawBufferTranslationFailed(String::from("")).into()
                         );
                     }
-                    self.canvas.swap_tuxels(current_idx, new_idx.clone())?;
+                    self.canvas
+                        .move_tuxel(current_idx, new_idx.clone(), &self.owner)?;
                     t.set_idx(&new_idx);
                 }
             }
@@ -280,6 +430,14 @@ impl DrawBufferInner {
         self.buf[y][x].colors()
     }
 
+    fn is_border_cell(&self, x: usize, y: usize) -> bool {
+        self.border
+            && (x == 0
+                || y == 0
+                || x + 1 == self.rectangle.width()
+                || y + 1 == self.rectangle.height())
+    }
+
     fn tuxel_content(&self, x: usize, y: usize) -> Result<char> {
         Ok(self.get_tuxel(Position::Coordinates(x, y))?.content())
     }
@@ -297,7 +455,12 @@ impl std::fmt::Display for DrawBuffer {
 }
 
 impl DrawBuffer {
-    pub(crate) fn new(sender: Sender<Tuxel>, rectangle: Rectangle, canvas: Canvas) -> Self {
+    pub(crate) fn new(
+        sender: Sender<Tuxel>,
+        rectangle: Rectangle,
+        canvas: Canvas,
+        owner: Owner,
+    ) -> Self {
         let mut buf: Vec<_> = Vec::with_capacity(rectangle.height());
         for _ in 0..rectangle.height() {
             let row: Vec<Tuxel> = Vec::with_capacity(rectangle.width());
@@ -309,19 +472,91 @@ impl DrawBuffer {
                 border: false,
                 buf,
                 modifiers: Vec::new(),
+                border_modifiers: Vec::new(),
+                pulse: None,
+                fade: None,
                 canvas,
+                owner,
+                recolored_for: None,
             })),
             sender,
         }
     }
 }
 
+impl DrawBuffer {
+    /// Starts staging changes to the buffer's content that only reach the canvas once the returned
+    /// transaction is committed, which it is when dropped unless aborted first.
+    pub(crate) fn begin_transaction(&mut self) -> DrawBufferTransaction<'_> {
+        DrawBufferTransaction {
+            buffer: self,
+            staged: Vec::new(),
+            aborted: false,
+        }
+    }
+}
+
+/// Content written to a `DrawBuffer` as one unit. See `DrawBuffer::begin_transaction`.
+pub(crate) struct DrawBufferTransaction<'a> {
+    buffer: &'a mut DrawBuffer,
+    staged: Vec<(usize, usize, char)>,
+    aborted: bool,
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+impl DrawBufferTransaction<'_> {
+    /// Stages setting the content of the cell at the given coordinates, relative to the buffer.
+    pub(crate) fn set_content(&mut self, x: usize, y: usize, c: char) {
+        self.staged.push((x, y, c));
+    }
+
+    /// Discards everything staged so far; nothing is committed when the transaction is dropped.
+    pub(crate) fn abort(&mut self) {
+        self.aborted = true;
+    }
+
+    /// Applies everything staged, in order. If any of it is outside the buffer, nothing is applied
+    /// and the first such cell is reported. Dropping the transaction commits it too but can only
+    /// log such errors.
+    pub(crate) fn commit(mut self) -> Result<()> {
+        self.apply()
+    }
+
+    fn apply(&mut self) -> Result<()> {
+        let staged = std::mem::take(&mut self.staged);
+        if self.aborted {
+            return Ok(());
+        }
+        let mut inner = self.buffer.lock();
+        // checked up front so that the buffer is never left half written
+        for (x, y, _) in &staged {
+            inner.get_tuxel(Position::Coordinates(*x, *y))?;
+        }
+        for (x, y, c) in staged {
+            inner
+                .get_tuxel_mut(Position::Coordinates(x, y))?
+                .set_content(c);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for DrawBufferTransaction<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.apply() {
+            log::warn!("failed to commit draw buffer transaction: {}", e);
+        }
+    }
+}
+
 impl DrawBufferOwner for DrawBuffer {
     fn lock<'a>(&'a self) -> MutexGuard<'a, DrawBufferInner> {
+        // a buffer poisoned by a panic is still torn down when it's dropped, which mustn't panic
+        // again
         self.inner
             .as_ref()
             .lock()
-            .expect("TODO: handle thread panicking better than this")
+            .unwrap_or_else(|e| e.into_inner())
     }
 
     fn inner(&self) -> Arc<Mutex<DrawBufferInner>> {
@@ -344,36 +579,155 @@ impl Drop for DrawBuffer {
     }
 }
 
+/// Drives a pulse started with `DrawBufferOwner::pulse_animation`, one frame per tick.
+///
+/// The pulse overrides the buffer's background without touching its modifiers, so the original
+/// color comes back as soon as the pulse stops, is dropped, or runs out of cycles.
+pub(crate) struct PulseHandle {
+    inner: Arc<Mutex<DrawBufferInner>>,
+    original: Rgb,
+    color: Rgb,
+    cycles: usize,
+    period: usize,
+    frame: usize,
+}
+
+impl PulseHandle {
+    fn new(inner: Arc<Mutex<DrawBufferInner>>, color: Rgb, cycles: usize) -> Self {
+        let original = {
+            let guard = inner.lock().unwrap_or_else(|e| e.into_inner());
+            guard
+                .modifiers
+                .iter()
+                .fold((None, None), |cs, modifier| modifier.apply(cs))
+                .1
+                .unwrap_or_default()
+        };
+        Self {
+            inner,
+            original,
+            color,
+            cycles,
+            period: PULSE_PERIOD,
+            frame: 0,
+        }
+    }
+
+    /// Sets the number of frames in one full oscillation.
+    pub(crate) fn with_period(mut self, period: usize) -> Self {
+        self.period = period.max(1);
+        self
+    }
+
+    /// Advances the pulse by one frame. Returns false, having restored the original background,
+    /// once every cycle has completed.
+    pub(crate) fn tick(&mut self) -> bool {
+        let frames = self.cycles * self.period;
+        self.frame = (self.frame + 1).min(frames);
+        if self.frame == frames {
+            self.stop();
+            return false;
+        }
+        // sin² rises from 0 to 1 and back over a period, starting and ending on the original color
+        let phase = self.frame as f32 / self.period as f32;
+        let t = (std::f32::consts::PI * phase).sin().powi(2);
+        self.set(Some(self.original.interpolate(&self.color, t)));
+        true
+    }
+
+    /// Ends the pulse early, restoring the original background.
+    pub(crate) fn stop(&mut self) {
+        self.frame = self.cycles * self.period;
+        self.set(None);
+    }
+
+    fn set(&self, pulse: Option<Rgb>) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.pulse.is_none() && pulse.is_none() {
+            return;
+        }
+        inner.pulse = pulse;
+        for tuxel in inner.buf.iter().flatten() {
+            tuxel.touch();
+        }
+    }
+}
+
+impl Drop for PulseHandle {
+    fn drop(&mut self) {
+        self.set(None);
+    }
+}
+
 pub(crate) struct DBTuxel {
-    parent: Arc<Mutex<DrawBufferInner>>,
+    // weak so that a cell left behind by a buffer that's gone can be told apart, see
+    // `Canvas::sanitize`
+    parent: Weak<Mutex<DrawBufferInner>>,
     canvas_idx: Idx,
     buf_idx: Idx,
+    // kept outside the parent so it can be reported without locking the parent DrawBuffer
+    owner: Owner,
 }
 
 impl DBTuxel {
-    pub(crate) fn new(parent: Arc<Mutex<DrawBufferInner>>, canvas_idx: Idx, buf_idx: Idx) -> Self {
+    pub(crate) fn new(
+        parent: Arc<Mutex<DrawBufferInner>>,
+        canvas_idx: Idx,
+        buf_idx: Idx,
+        owner: Owner,
+    ) -> Self {
         Self {
-            parent,
+            parent: Arc::downgrade(&parent),
             canvas_idx,
             buf_idx,
+            owner,
         }
     }
-    fn lock(&self) -> MutexGuard<DrawBufferInner> {
-        self.parent
-            .lock()
-            .expect("TODO: handle mutex lock errors more gracefully")
+
+    pub(crate) fn owner(&self) -> &Owner {
+        &self.owner
+    }
+
+    /// Whether the buffer the tuxel belonged to has been dropped without handing it back.
+    pub(crate) fn is_orphaned(&self) -> bool {
+        self.parent.strong_count() == 0
+    }
+
+    #[cfg(test)]
+    pub(crate) fn canvas_idx(&self) -> &Idx {
+        &self.canvas_idx
+    }
+
+    #[cfg(test)]
+    pub(crate) fn buf_idx(&self) -> &Idx {
+        &self.buf_idx
+    }
+    /// Calls the given function with the tuxel's buffer locked, even if a panic poisoned it, or
+    /// fails if the buffer is gone.
+    fn with_parent<T>(&self, f: impl FnOnce(&DrawBufferInner) -> T) -> Result<T> {
+        let parent = self
+            .parent
+            .upgrade()
+            .ok_or_else(|| InnerError::OrphanedCell(self.canvas_idx.clone()))?;
+        let inner = parent.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(f(&inner))
     }
 
     pub(crate) fn content(&self) -> Result<char> {
-        self.lock().tuxel_content(self.buf_idx.0, self.buf_idx.1)
+        self.with_parent(|inner| inner.tuxel_content(self.buf_idx.0, self.buf_idx.1))?
     }
 
     pub(crate) fn active(&self) -> Result<bool> {
-        self.lock().tuxel_is_active(self.buf_idx.0, self.buf_idx.1)
+        self.with_parent(|inner| inner.tuxel_is_active(self.buf_idx.0, self.buf_idx.1))?
     }
 
     pub(crate) fn set_canvas_idx(&mut self, new_idx: &Idx) -> Result<()> {
         self.canvas_idx = new_idx.clone();
+        // an orphaned tuxel has no buffer left to tell, it's only waiting to be swept up
+        let parent = match self.parent.upgrade() {
+            Some(parent) => parent,
+            None => return Ok(()),
+        };
         // NOTE: in the early stages of development the only case i can think of where this would
         // block is when swapping tuxels for a specific draw buffer. since the actual high-level
         // operation in such cases requires the DrawBufferInner corresponding to this DBTuxel to
@@ -388,7 +742,7 @@ impl DBTuxel {
         let max_retries = 1usize;
         let mut dbi = match (retry_count..max_retries).into_iter().find_map(
             |i| -> Option<MutexGuard<DrawBufferInner>> {
-                match self.parent.try_lock() {
+                match parent.try_lock() {
                     Ok(guard) => Some(guard),
                     Err(std::sync::TryLockError::WouldBlock) => {
                         std::thread::sleep(std::time::Duration::from_millis(i as u64 * 5));
@@ -420,23 +774,44 @@ impl DBTuxel {
     }
 
     pub(crate) fn colors(&self) -> (Option<Rgb>, Option<Rgb>) {
-        let inner = self.lock();
-        let colors = inner.tuxel_colors(self.buf_idx.x(), self.buf_idx.y());
-        inner
-            .modifiers
-            .iter()
-            .fold(colors, |cs, modifier| modifier.apply(cs))
+        let (x, y) = (self.buf_idx.x(), self.buf_idx.y());
+        self.with_parent(|inner| {
+            let colors = inner.tuxel_colors(x, y);
+            let mut colors = inner
+                .modifiers
+                .iter()
+                .fold(colors, |cs, modifier| modifier.apply(cs));
+            if let Some(pulse) = &inner.pulse {
+                colors.1 = Some(pulse.clone());
+            }
+            if inner.is_border_cell(x, y) {
+                colors = inner
+                    .border_modifiers
+                    .iter()
+                    .fold(colors, |cs, modifier| modifier.apply(cs));
+            }
+            match inner.fade {
+                Some(factor) => (
+                    colors.0.map(|c| c.scale_lightness(factor)),
+                    colors.1.map(|c| c.scale_lightness(factor)),
+                ),
+                None => colors,
+            }
+        })
+        .unwrap_or_default()
     }
 }
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashMap;
     use std::sync::mpsc::channel;
     use std::sync::mpsc::Receiver;
 
     use super::*;
     use rstest::*;
 
+    use super::super::canvas::Stack;
     use super::super::geometry::Bounds2D;
 
     fn rectangle(x: usize, y: usize, z: usize, width: usize, height: usize) -> Rectangle {
@@ -471,14 +846,14 @@ mod test {
         ) -> Result<Box<dyn DrawBufferOwner>> {
             Ok(match self {
                 DBType::DrawBuffer => {
-                    let mut dbuf = canvas.get_draw_buffer(rect.clone())?;
+                    let mut dbuf = canvas.get_draw_buffer(rect.clone(), Owner::Named("test"))?;
                     if let Some(sender) = sender {
                         dbuf.sender = sender.clone();
                     }
                     Box::new(dbuf)
                 }
                 DBType::TextBuffer => {
-                    let mut tbuf = canvas.get_text_buffer(rect.clone())?;
+                    let mut tbuf = canvas.get_text_buffer(rect.clone(), Owner::Named("test"))?;
                     if let Some(sender) = sender {
                         tbuf.set_sender(sender.clone());
                     }
@@ -525,6 +900,43 @@ mod test {
         Ok(())
     }
 
+    fn fgcolors(canvas: &Canvas) -> HashMap<(usize, usize), (u8, u8, u8)> {
+        canvas
+            .get_changed()
+            .into_iter()
+            .filter_map(|stack| {
+                let c = stack.colors().0?;
+                Some((stack.coordinates(), (c.r(), c.g(), c.b())))
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case::square(rectangle(0, 0, 0, 5, 5))]
+    #[case::offset(rectangle(3, 2, 0, 8, 4))]
+    fn highlight_border_colors_only_border(
+        #[case] rect: Rectangle,
+        #[values(DBType::TextBuffer, DBType::DrawBuffer)] dbtype: DBType,
+    ) -> Result<()> {
+        let canvas = Canvas::new(20, 20);
+        let mut dbuf = dbtype.to_draw_buffer(&rect, &canvas, None)?;
+        dbuf.draw_border()?;
+        dbuf.fill(' ')?;
+        dbuf.modify(Modifier::SetForegroundColor(255, 0, 0));
+        dbuf.highlight_border(Rgb::new(0, 0, 255));
+
+        let colors = fgcolors(&canvas);
+        let (left, top) = (rect.x(), rect.y());
+        let (right, bottom) = (left + rect.width() - 1, top + rect.height() - 1);
+        for idx in [(left, top), (right, top), (right, bottom), (left, bottom)] {
+            assert_eq!(colors.get(&idx), Some(&(0, 0, 255)));
+        }
+        assert_eq!(colors.get(&(left + 1, top)), Some(&(0, 0, 255)));
+        assert_eq!(colors.get(&(left, top + 1)), Some(&(0, 0, 255)));
+        assert_eq!(colors.get(&(left + 1, top + 1)), Some(&(255, 0, 0)));
+        Ok(())
+    }
+
     #[rstest]
     // #[case::base(
     //      canvas_width, canvas_height,
@@ -577,4 +989,372 @@ mod test {
         assert!(r.is_err());
         Ok(())
     }
+
+    fn background(canvas: &Canvas, x: usize, y: usize) -> Option<(u8, u8, u8)> {
+        canvas
+            .get_changed()
+            .into_iter()
+            .filter(|stack| stack.coordinates() == (x, y))
+            .last()
+            .and_then(|stack| stack.colors().1)
+            .map(|c| (c.r(), c.g(), c.b()))
+    }
+
+    fn assert_close(actual: Option<(u8, u8, u8)>, expected: (u8, u8, u8)) {
+        let (r, g, b) = actual.expect("the cell should have a background");
+        let close = |a: u8, e: u8| a.abs_diff(e) <= 1;
+        assert!(
+            close(r, expected.0) && close(g, expected.1) && close(b, expected.2),
+            "{:?} should be close to {:?}",
+            (r, g, b),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn set_color_at_recolors_a_single_cell(
+        #[values(DBType::DrawBuffer, DBType::TextBuffer)] dbt: DBType,
+    ) -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let mut dbuf = dbt.to_draw_buffer(&rectangle(2, 2, 1, 3, 3), &canvas, None)?;
+        dbuf.fill('X')?;
+        let _ = canvas.get_changed();
+
+        dbuf.set_color_at(0, 0, Some(Rgb::new(255, 0, 0)), None)?;
+        let changed = canvas.get_changed();
+        let stack = changed
+            .iter()
+            .find(|stack| stack.coordinates() == (2, 2))
+            .expect("the recolored cell should be reported as changed");
+        assert_eq!(stack.content(), Some('X'));
+        let (fg, bg) = stack.colors();
+        let fg = fg.expect("the cell should have a foreground");
+        assert_eq!((fg.r(), fg.g(), fg.b()), (255, 0, 0));
+        assert!(bg.is_none());
+        assert!(changed.iter().all(|stack| stack.coordinates() == (2, 2)));
+
+        assert!(dbuf.set_color_at(3, 0, None, None).is_err());
+        Ok(())
+    }
+
+    #[rstest]
+    fn dim_darkens_the_buffer_once(
+        #[values(DBType::DrawBuffer, DBType::TextBuffer)] dbt: DBType,
+    ) -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let mut dbuf = dbt.to_draw_buffer(&rectangle(2, 2, 1, 3, 3), &canvas, None)?;
+        dbuf.fill('X')?;
+        dbuf.modify(Modifier::SetForegroundColor(200, 200, 200));
+        dbuf.modify(Modifier::SetBackgroundColor(20, 40, 60));
+        let _ = canvas.get_changed();
+
+        let colors = |canvas: &Canvas| {
+            let changed = canvas.get_changed();
+            assert_eq!(changed.len(), 9, "every cell should be redrawn");
+            let (fg, bg) = changed[0].colors();
+            (
+                fg.expect("the cell should have a foreground").lightness(),
+                bg.expect("the cell should have a background").lightness(),
+            )
+        };
+        let original = (
+            Rgb::new(200, 200, 200).lightness(),
+            Rgb::new(20, 40, 60).lightness(),
+        );
+        dbuf.dim();
+        let dimmed = colors(&canvas);
+        assert!(dimmed.0 < original.0, "{:?} vs {:?}", dimmed, original);
+        assert!(dimmed.1 < original.1, "{:?} vs {:?}", dimmed, original);
+
+        dbuf.dim();
+        assert!(canvas.get_changed().is_empty(), "dimming twice is a no-op");
+        Ok(())
+    }
+
+    #[test]
+    fn modify_repaints_only_the_cells_drawn_on() -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let mut dbuf = canvas.get_draw_buffer(rectangle(2, 2, 1, 4, 2), Owner::Named("test"))?;
+        dbuf.modify(Modifier::SetBackgroundColor(20, 40, 60));
+        {
+            let mut transaction = dbuf.begin_transaction();
+            transaction.set_content(0, 0, 'a');
+            transaction.set_content(2, 1, 'b');
+        }
+        let looks = |changed: Vec<Stack>| {
+            let mut looks: Vec<_> = changed
+                .iter()
+                .map(|stack| {
+                    let bg = stack.colors().1.expect("drawn cells have a background");
+                    (stack.coordinates(), stack.content(), bg.lightness())
+                })
+                .collect();
+            looks.sort_by_key(|look| look.0);
+            looks
+        };
+        let drawn = looks(canvas.get_changed());
+        assert_eq!(drawn.len(), 2);
+
+        // applied twice before anything is repainted, the cells are only reported once
+        dbuf.modify(Modifier::SetBGLightness(0.5));
+        dbuf.modify(Modifier::SetBGLightness(0.5));
+        let recolored = looks(canvas.get_changed());
+        assert_eq!(recolored.len(), 2, "{:?}", recolored);
+        for (before, after) in drawn.iter().zip(recolored.iter()) {
+            assert_eq!((before.0, before.1), (after.0, after.1));
+            assert!(after.2 > before.2, "{:?} vs {:?}", after, before);
+        }
+
+        dbuf.modify(Modifier::SetBGLightness(0.5));
+        assert_eq!(canvas.get_changed().len(), 2);
+        assert!(canvas.get_changed().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn fading_scales_every_color_until_it_stops() -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let mut dbuf = canvas.get_draw_buffer(rectangle(2, 2, 1, 4, 3), Owner::Named("test"))?;
+        dbuf.modify(Modifier::SetForegroundColor(200, 120, 40));
+        dbuf.modify(Modifier::SetBackgroundColor(40, 120, 200));
+        dbuf.highlight_border(Rgb::new(90, 200, 90));
+        dbuf.draw_border()?;
+        dbuf.fill('x')?;
+        let looks = |changed: Vec<Stack>| {
+            let mut looks: Vec<_> = changed
+                .iter()
+                .map(|stack| {
+                    let (fg, bg) = stack.colors();
+                    let lightness =
+                        |c: Option<Rgb>| c.expect("drawn cells are colored").lightness();
+                    (stack.coordinates(), lightness(fg), lightness(bg))
+                })
+                .collect();
+            looks.sort_by_key(|look| look.0);
+            looks
+        };
+        let drawn = looks(canvas.get_changed());
+        assert_eq!(drawn.len(), 12);
+
+        dbuf.fade(Some(0.0));
+        let faded = looks(canvas.get_changed());
+        assert_eq!(faded.len(), drawn.len());
+        assert!(
+            faded.iter().all(|l| l.1 == 0.0 && l.2 == 0.0),
+            "{:?}",
+            faded
+        );
+
+        dbuf.fade(Some(0.5));
+        let halfway = looks(canvas.get_changed());
+        for (before, after) in drawn.iter().zip(halfway.iter()) {
+            assert!(
+                (after.1 - before.1 / 2.0).abs() < 0.01,
+                "{:?}",
+                (before, after)
+            );
+            assert!(
+                (after.2 - before.2 / 2.0).abs() < 0.01,
+                "{:?}",
+                (before, after)
+            );
+        }
+
+        dbuf.fade(None);
+        assert_eq!(looks(canvas.get_changed()), drawn);
+        dbuf.fade(None);
+        assert!(canvas.get_changed().is_empty(), "stopping twice is a no-op");
+        Ok(())
+    }
+
+    fn transaction_buffer(canvas: &Canvas) -> Result<DrawBuffer> {
+        let mut dbuf = canvas.get_draw_buffer(rectangle(2, 2, 1, 5, 2), Owner::Named("test"))?;
+        dbuf.fill('-')?;
+        let _ = canvas.get_changed();
+        Ok(dbuf)
+    }
+
+    fn contents(canvas: &Canvas) -> Vec<((usize, usize), Option<char>)> {
+        let mut contents: Vec<_> = canvas
+            .get_changed()
+            .iter()
+            .map(|stack| (stack.coordinates(), stack.content()))
+            .collect();
+        contents.sort();
+        contents.dedup();
+        contents
+    }
+
+    #[test]
+    fn dropped_transaction_commits() -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let mut dbuf = transaction_buffer(&canvas)?;
+        {
+            let mut transaction = dbuf.begin_transaction();
+            for (x, c) in "hello".chars().enumerate() {
+                transaction.set_content(x, 1, c);
+            }
+            assert!(canvas.get_changed().is_empty());
+        }
+
+        let expected: Vec<_> = "hello"
+            .chars()
+            .enumerate()
+            .map(|(x, c)| ((2 + x, 3), Some(c)))
+            .collect();
+        assert_eq!(contents(&canvas), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn aborted_transaction_changes_nothing() -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let mut dbuf = transaction_buffer(&canvas)?;
+        {
+            let mut transaction = dbuf.begin_transaction();
+            for (x, c) in "hello".chars().enumerate() {
+                transaction.set_content(x, 1, c);
+            }
+            transaction.abort();
+        }
+
+        assert!(canvas.get_changed().is_empty());
+        let inner = dbuf.lock();
+        assert!(inner.buf.iter().flatten().all(|t| t.content() == '-'));
+        Ok(())
+    }
+
+    #[test]
+    fn committing_reports_cells_outside_the_buffer() -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let mut dbuf = transaction_buffer(&canvas)?;
+        let before = contents(&canvas);
+        let mut transaction = dbuf.begin_transaction();
+        transaction.set_content(0, 0, 'a');
+        transaction.set_content(5, 0, 'b');
+        assert!(transaction.commit().is_err());
+        assert_eq!(contents(&canvas), before, "nothing should have been written");
+        Ok(())
+    }
+
+    #[rstest]
+    fn pulse_returns_to_the_original_background(
+        #[values(DBType::DrawBuffer, DBType::TextBuffer)] dbt: DBType,
+    ) -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let mut dbuf = dbt.to_draw_buffer(&rectangle(2, 2, 1, 3, 3), &canvas, None)?;
+        dbuf.fill(' ')?;
+        dbuf.modify(Modifier::SetBackgroundColor(20, 40, 60));
+        assert_close(background(&canvas, 3, 3), (20, 40, 60));
+
+        let mut pulse = dbuf.pulse_animation(Rgb::new(220, 240, 255), 1);
+        let mut ticks = Vec::new();
+        for frame in 1..=PULSE_PERIOD {
+            ticks.push(pulse.tick());
+            if frame == PULSE_PERIOD / 2 {
+                assert_close(background(&canvas, 3, 3), (220, 240, 255));
+            }
+        }
+        assert_eq!(
+            ticks.iter().filter(|pulsing| **pulsing).count(),
+            PULSE_PERIOD - 1
+        );
+        assert!(
+            !ticks[PULSE_PERIOD - 1],
+            "the pulse should be over after one cycle"
+        );
+        assert_close(background(&canvas, 3, 3), (20, 40, 60));
+        assert!(!pulse.tick());
+        Ok(())
+    }
+
+    #[test]
+    fn pulse_period_is_configurable() -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let mut dbuf = canvas.get_draw_buffer(rectangle(0, 0, 1, 2, 2), Owner::Named("test"))?;
+        dbuf.fill(' ')?;
+        let mut pulse = dbuf
+            .pulse_animation(Rgb::new(255, 255, 255), 2)
+            .with_period(4);
+        let ticks = std::iter::from_fn(|| Some(pulse.tick()))
+            .take_while(|pulsing| *pulsing)
+            .count();
+        assert_eq!(ticks, 7);
+        Ok(())
+    }
+
+    #[test]
+    fn dropping_a_pulse_restores_the_background() -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let mut dbuf = canvas.get_draw_buffer(rectangle(0, 0, 1, 2, 2), Owner::Named("test"))?;
+        dbuf.fill(' ')?;
+        dbuf.modify(Modifier::SetBackgroundColor(10, 10, 10));
+        let mut pulse = dbuf.pulse_animation(Rgb::new(250, 250, 250), 3);
+        for _ in 0..5 {
+            pulse.tick();
+        }
+        assert_ne!(background(&canvas, 0, 0), Some((10, 10, 10)));
+        drop(pulse);
+        assert_close(background(&canvas, 0, 0), (10, 10, 10));
+        Ok(())
+    }
+
+    /// Poisons the given buffer the way a panic halfway through redrawing it would, with the
+    /// tuxels of its first row taken out and lost with the panic.
+    fn interrupt(dbuf: &DrawBuffer) {
+        let interrupted = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut inner = dbuf.lock();
+            let _lost: Vec<Tuxel> = inner.buf[0].drain(..).collect();
+            panic!("interrupted while redrawing");
+        }));
+        assert!(interrupted.is_err());
+        assert!(dbuf.inner.is_poisoned());
+    }
+
+    #[test]
+    fn dropping_a_poisoned_buffer_while_unwinding_does_not_abort() -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let mut dbuf = canvas.get_draw_buffer(rectangle(2, 2, 1, 5, 2), Owner::Named("test"))?;
+        dbuf.fill('x')?;
+        interrupt(&dbuf);
+
+        // the buffer is torn down while another panic unwinds, which would abort the process if
+        // tearing it down panicked too
+        let unwound = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+            let _dbuf = dbuf;
+            panic!("an error elsewhere");
+        }));
+        assert!(unwound.is_err());
+
+        // the tuxels lost with the first panic never made it back to the canvas
+        assert_eq!(canvas.orphaned_cells(), 5);
+        assert_eq!(canvas.sanitize(), 5);
+        assert_eq!(canvas.orphaned_cells(), 0);
+        assert!(canvas
+            .get_draw_buffer(rectangle(2, 2, 1, 5, 2), Owner::Named("after"))
+            .is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn orphaned_cells_show_what_is_below_them() -> Result<()> {
+        let canvas = Canvas::new(10, 10);
+        let mut below = canvas.get_draw_buffer(rectangle(2, 2, 0, 5, 1), Owner::Named("below"))?;
+        below.fill('b')?;
+        let mut dbuf = canvas.get_draw_buffer(rectangle(2, 2, 1, 5, 1), Owner::Named("test"))?;
+        dbuf.fill('x')?;
+        interrupt(&dbuf);
+        drop(dbuf);
+
+        // the buffer below shows through until they're swept up
+        let mut shown = String::new();
+        canvas.for_each_row(|y, cells| {
+            if y == 2 {
+                shown = cells[2..7].iter().filter_map(|c| c.content()).collect();
+            }
+            Ok(())
+        })?;
+        assert_eq!(shown, "bbbbb");
+        Ok(())
+    }
 }
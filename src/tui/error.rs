@@ -22,12 +22,6 @@ impl std::fmt::Display for TuiError {
 
 impl std::error::Error for TuiError {}
 
-impl From<std::sync::mpsc::SendError<crate::tui::geometry::Idx>> for TuiError {
-    fn from(inner: std::sync::mpsc::SendError<crate::tui::geometry::Idx>) -> TuiError {
-        InnerError::IdxSendError(inner).into()
-    }
-}
-
 impl From<std::sync::mpsc::SendError<crate::tui::tuxel::Tuxel>> for TuiError {
     fn from(inner: std::sync::mpsc::SendError<crate::tui::tuxel::Tuxel>) -> TuiError {
         InnerError::TuxelSendError(inner).into()
@@ -57,8 +51,19 @@ impl From<InnerError> for TuiError {
 
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum InnerError {
-    #[error("cell already owned")]
-    CellAlreadyOwned,
+    #[error("cell {idx} requested by {requested} is already owned by {current}")]
+    CellAlreadyOwned {
+        idx: super::geometry::Idx,
+        requested: super::drawbuffer::Owner,
+        current: super::drawbuffer::Owner,
+    },
+
+    #[error("cell {idx} doesn't map into {owner}'s buffer at {rectangle}")]
+    TuxelOutsideBuffer {
+        idx: super::geometry::Idx,
+        owner: super::drawbuffer::Owner,
+        rectangle: super::geometry::Rectangle,
+    },
 
     #[error("out of bounds x: {0}")]
     OutOfBoundsX(usize),
@@ -66,21 +71,12 @@ pub(crate) enum InnerError {
     #[error("out of bounds y: {0}")]
     OutOfBoundsY(usize),
 
-    #[error("idx channel send failed")]
-    IdxSendError(#[from] std::sync::mpsc::SendError<crate::tui::geometry::Idx>),
+    #[error("out of bounds z: {0}")]
+    OutOfBoundsZ(usize),
 
     #[error("tuxel channel send failed")]
     TuxelSendError(#[from] std::sync::mpsc::SendError<crate::tui::tuxel::Tuxel>),
 
-    #[error(
-        "invalid translation \n\tmagnitude: {mag:?} \n\tdirection: {dir:?} \n\trectangle: {rect:?}"
-    )]
-    InvalidVectorTranslation {
-        mag: usize,
-        dir: super::geometry::Direction,
-        rect: super::geometry::Rectangle,
-    },
-
     #[error("drawbuffer translation failed: {0}")]
     DrawBufferTranslationFailed(String),
 
@@ -98,4 +94,27 @@ pub(crate) enum InnerError {
 
     #[error("rectangle dimensions must match")]
     RectangleDimensionsMustMatch,
+
+    #[error("no room left for {text:?} in a text buffer {lines} lines tall")]
+    TextBufferOverflow { text: String, lines: usize },
+
+    #[error("{requested} layers needed but the canvas only has {available}")]
+    CanvasTooShallow { requested: usize, available: usize },
+
+    #[error("canvases can have between 1 and {max} layers, not {requested}")]
+    InvalidCanvasDepth { requested: usize, max: usize },
+
+    #[error("can't resize the canvas while {0} still draws on it")]
+    CanvasInUse(super::drawbuffer::Owner),
+
+    #[error("the buffer that owned cell {0} is gone")]
+    OrphanedCell(super::geometry::Idx),
+
+    #[error("{idx} {op} ({dx}, {dy}) is out of the range of coordinates")]
+    CoordinateOverflow {
+        idx: super::geometry::Idx,
+        op: char,
+        dx: usize,
+        dy: usize,
+    },
 }
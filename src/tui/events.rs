@@ -1,17 +1,195 @@
+use std::time::{Duration, Instant};
+
 use super::error::Result;
 use super::geometry::Direction;
+use crate::clock::{Clock, SystemClock};
 
 pub(crate) trait EventSource {
     fn next_event(&self) -> Result<Event>;
+
+    /// Waits at most the given time for the next event, returning None if none came. Sources
+    /// that can't tell when one is coming wait for it however long it takes.
+    fn poll_event(&self, _timeout: Duration) -> Result<Option<Event>> {
+        self.next_event().map(Some)
+    }
 }
 
 pub(crate) enum Event {
     UserInput(UserInput),
     Resize,
+    /// Estimated probability of winning from the position of the given generation, posted by the
+    /// background outlook estimator.
+    Estimate {
+        generation: u64,
+        estimate: f32,
+    },
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub(crate) enum UserInput {
     Direction(Direction),
+    /// Step back and forth through the moves made so far, or back out to the game.
+    Replay,
     NewGame,
     Quit,
+    /// Show how often tiles have come to rest in each slot.
+    ShowHeatmap,
+    /// Label tiles with the next label pack.
+    CyclePack,
+    /// Look through the themes before choosing one.
+    PreviewThemes,
+    /// Go ahead with what is on screen, eg apply the theme being previewed.
+    Confirm,
+    /// Back out of what is on screen without changing anything.
+    Cancel,
+    /// Use the power-up charged in the arcade mode.
+    PowerUp,
+    /// Show or hide the lines between the board's slots.
+    ToggleGrid,
+    /// Build a position to start a game from in the board editor.
+    EditBoard,
+    /// Raise the value of the tile under the editor's cursor.
+    Increase,
+    /// Lower the value of the tile under the editor's cursor.
+    Decrease,
+    /// Type the given digit, eg into the editor's score.
+    Digit(u8),
+    /// Take back the last character typed.
+    Erase,
+    /// Write the position built in the editor down.
+    WriteNotation,
+    /// Take back the latest move.
+    Undo,
+    /// Make the move taken back most recently again.
+    Redo,
+    /// Keep playing a game that has been won.
+    Continue,
+    /// Save the game being played, to load it again later.
+    SaveGame,
+    /// Play the game saved last in place of the one being played.
+    LoadGame,
+    /// Let the computer make the moves, or take them back over from it.
+    ToggleAutoPlay,
+}
+
+/// Coalesces bursts of resize events into one. Dragging a window edge, and conhost in general,
+/// produces a storm of resize events, each of which would otherwise rebuild the whole board.
+///
+/// The first resize of a burst opens a window; resizes arriving while it is open are absorbed and
+/// a single resize is due once it closes.
+pub(crate) struct ResizeDebouncer {
+    clock: Box<dyn Clock>,
+    window: Duration,
+    pending_since: Option<Instant>,
+}
+
+impl ResizeDebouncer {
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            clock: Box::new(SystemClock),
+            window,
+            pending_since: None,
+        }
+    }
+
+    #[cfg(test)]
+    fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Records a resize event.
+    pub(crate) fn resized(&mut self) {
+        self.pending_since.get_or_insert(self.clock.now());
+    }
+
+    /// Returns how long until the pending resize is due, or None if there is no pending resize.
+    pub(crate) fn remaining(&self) -> Option<Duration> {
+        self.pending_since.map(|since| {
+            self.window
+                .saturating_sub(self.clock.now().duration_since(since))
+        })
+    }
+
+    /// Returns true and clears the pending resize if its window has closed.
+    pub(crate) fn take_due(&mut self) -> bool {
+        match self.remaining() {
+            Some(remaining) if remaining.is_zero() => self.take_pending(),
+            _ => false,
+        }
+    }
+
+    /// Returns true and clears the pending resize, whether or not its window has closed. Used when
+    /// another event arrives, which shouldn't overtake the resize that preceded it.
+    pub(crate) fn take_pending(&mut self) -> bool {
+        self.pending_since.take().is_some()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::clock::FakeClock;
+
+    const WINDOW: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn nothing_pending_without_resizes() {
+        let mut debouncer = ResizeDebouncer::new(WINDOW).with_clock(FakeClock::new());
+        assert_eq!(debouncer.remaining(), None);
+        assert!(!debouncer.take_due());
+        assert!(!debouncer.take_pending());
+    }
+
+    #[test]
+    fn burst_is_coalesced_into_one_resize() {
+        let clock = FakeClock::new();
+        let mut debouncer = ResizeDebouncer::new(WINDOW).with_clock(clock.clone());
+
+        for _ in 0..10 {
+            debouncer.resized();
+            assert!(!debouncer.take_due());
+            clock.advance(Duration::from_millis(4));
+        }
+        assert_eq!(debouncer.remaining(), Some(Duration::from_millis(10)));
+
+        clock.advance(Duration::from_millis(9));
+        assert!(!debouncer.take_due());
+        clock.advance(Duration::from_millis(1));
+        assert!(debouncer.take_due());
+
+        // the burst produced exactly one resize
+        assert!(!debouncer.take_due());
+        assert_eq!(debouncer.remaining(), None);
+    }
+
+    #[test]
+    fn resizes_after_the_window_start_a_new_burst() {
+        let clock = FakeClock::new();
+        let mut debouncer = ResizeDebouncer::new(WINDOW).with_clock(clock.clone());
+
+        debouncer.resized();
+        clock.advance(WINDOW);
+        assert!(debouncer.take_due());
+
+        clock.advance(Duration::from_millis(100));
+        debouncer.resized();
+        assert_eq!(debouncer.remaining(), Some(WINDOW));
+        clock.advance(WINDOW * 2);
+        assert_eq!(debouncer.remaining(), Some(Duration::ZERO));
+        assert!(debouncer.take_due());
+    }
+
+    #[test]
+    fn take_pending_flushes_early() {
+        let clock = FakeClock::new();
+        let mut debouncer = ResizeDebouncer::new(WINDOW).with_clock(clock.clone());
+
+        debouncer.resized();
+        clock.advance(Duration::from_millis(1));
+        assert!(debouncer.take_pending());
+        assert!(!debouncer.take_pending());
+        clock.advance(WINDOW);
+        assert!(!debouncer.take_due());
+    }
 }
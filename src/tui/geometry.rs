@@ -1,4 +1,5 @@
 use super::error::{InnerError, Result};
+use crate::engine::direction::Direction as BoardDirection;
 
 /// Idx encapsulates the x, y, and z coordinates of a Tuxel-based shape.
 #[derive(Clone, Debug, Default, Eq, Ord, PartialOrd, PartialEq)]
@@ -25,6 +26,64 @@ impl Idx {
     pub(crate) fn z(&self) -> usize {
         self.2
     }
+
+    /// Returns this Idx moved right by `dx` and down by `dy`, or an error naming the move if that
+    /// leaves the range of coordinates.
+    pub(crate) fn checked_add(&self, dx: usize, dy: usize) -> Result<Idx> {
+        match (self.0.checked_add(dx), self.1.checked_add(dy)) {
+            (Some(x), Some(y)) => Ok(Idx(x, y, self.2)),
+            _ => Err(self.overflow('+', dx, dy)),
+        }
+    }
+
+    /// Returns this Idx moved left by `dx` and up by `dy`, or an error naming the move if that
+    /// goes past the top or left edge.
+    pub(crate) fn checked_sub(&self, dx: usize, dy: usize) -> Result<Idx> {
+        match (self.0.checked_sub(dx), self.1.checked_sub(dy)) {
+            (Some(x), Some(y)) => Ok(Idx(x, y, self.2)),
+            _ => Err(self.overflow('-', dx, dy)),
+        }
+    }
+
+    fn overflow(&self, op: char, dx: usize, dy: usize) -> super::error::TuiError {
+        InnerError::CoordinateOverflow {
+            idx: self.clone(),
+            op,
+            dx,
+            dy,
+        }
+        .into()
+    }
+
+    /// Returns the left, right, up, and down neighbors of this Idx on the same layer, skipping
+    /// those that fall outside the given bounds.
+    #[cfg(test)]
+    pub(crate) fn neighbors(&self, bounds: &Bounds2D) -> Vec<Idx> {
+        self.offsets(bounds, &[(-1, 0), (1, 0), (0, -1), (0, 1)])
+    }
+
+    /// Returns the up to four diagonal neighbors of this Idx on the same layer that fall within
+    /// the given bounds.
+    #[cfg(test)]
+    pub(crate) fn diagonal_neighbors(&self, bounds: &Bounds2D) -> Vec<Idx> {
+        self.offsets(bounds, &[(-1, -1), (1, -1), (1, 1), (-1, 1)])
+    }
+
+    #[cfg(test)]
+    fn offsets(&self, bounds: &Bounds2D, offsets: &[(isize, isize)]) -> Vec<Idx> {
+        offsets
+            .iter()
+            .filter_map(|(dx, dy)| {
+                let x = self.0.checked_add_signed(*dx)?;
+                let y = self.1.checked_add_signed(*dy)?;
+                if x < bounds.width() && y < bounds.height() {
+                    Some(Idx(x, y, self.2))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -93,58 +152,67 @@ impl Rectangle {
     pub(crate) fn relative_idx(&self, pos: &Position) -> (usize, usize) {
         match pos {
             Position::TopLeft => (0, 0),
-            Position::TopRight => (self.width() - 1, 0),
-            Position::BottomLeft => (0, self.height() - 1),
-            Position::BottomRight => (self.width() - 1, self.height() - 1),
+            Position::TopRight => (self.width().saturating_sub(1), 0),
+            Position::BottomLeft => (0, self.height().saturating_sub(1)),
+            Position::BottomRight => (
+                self.width().saturating_sub(1),
+                self.height().saturating_sub(1),
+            ),
             Position::Coordinates(x, y) => (*x, *y),
             Position::Idx(Idx(x, y, _z)) => (*x, *y),
         }
     }
 
+    /// Moves the rectangle by `mag` cells in the given direction. Moves left or up stop at the
+    /// edge of the coordinates, moves right or down that would leave them fail and leave the
+    /// rectangle where it was.
     #[inline(always)]
     pub(crate) fn translate(&mut self, mag: usize, dir: &Direction) -> Result<()> {
-        match dir {
-            Direction::Left if self.x() > mag => self.0 .0 -= mag,
-            Direction::Left if self.x() <= mag => self.0 .0 = 0,
-            Direction::Right => self.0 .0 += mag,
-            Direction::Up if self.y() > mag => self.0 .1 -= mag,
-            Direction::Up if self.y() <= mag => self.0 .1 = 0,
-            Direction::Down => self.0 .1 += mag,
-            _ => {
-                return Err(InnerError::InvalidVectorTranslation {
-                    mag,
-                    dir: dir.clone(),
-                    rect: self.clone(),
-                }
-                .into())
-            }
-        }
+        let moved = match dir {
+            Direction::Left => self
+                .0
+                .checked_sub(mag, 0)
+                .unwrap_or(Idx(0, self.y(), self.z())),
+            Direction::Up => self
+                .0
+                .checked_sub(0, mag)
+                .unwrap_or(Idx(self.x(), 0, self.z())),
+            Direction::Right => self.0.checked_add(mag, 0)?,
+            Direction::Down => self.0.checked_add(0, mag)?,
+        };
+        self.0 = moved;
         Ok(())
     }
 
+    /// Where the rectangle ends, one past its right and bottom edges. Saturates rather than
+    /// wrapping around for rectangles reaching the end of the coordinates.
     #[inline(always)]
     pub(crate) fn extents(&self) -> (usize, usize) {
-        (self.0 .0 + self.1 .0, self.0 .1 + self.1 .1)
+        (
+            self.0 .0.saturating_add(self.1 .0),
+            self.0 .1.saturating_add(self.1 .1),
+        )
     }
 
     #[inline(always)]
     pub(crate) fn contains_or_err(&self, geo: Geometry) -> Result<()> {
         match geo {
             Geometry::Idx(idx) => {
-                if idx.x() < self.x() || idx.x() > self.x() + self.width() {
+                let (x_extent, y_extent) = self.extents();
+                if idx.x() < self.x() || idx.x() >= x_extent {
                     return Err(InnerError::OutOfBoundsX(idx.x()).into());
                 }
-                if idx.y() < self.y() || idx.y() > self.y() + self.height() {
+                if idx.y() < self.y() || idx.y() >= y_extent {
                     return Err(InnerError::OutOfBoundsY(idx.y()).into());
                 }
                 Ok(())
             }
             Geometry::Rectangle(rect) => {
                 let (x_extent, y_extent) = rect.extents();
-                if x_extent > self.width() {
+                if rect.x() < self.x() || x_extent > self.extents().0 {
                     return Err(InnerError::OutOfBoundsX(x_extent).into());
                 }
-                if y_extent > self.height() {
+                if rect.y() < self.y() || y_extent > self.extents().1 {
                     return Err(InnerError::OutOfBoundsY(y_extent).into());
                 }
                 Ok(())
@@ -152,41 +220,36 @@ impl Rectangle {
         }
     }
 
+    /// Grows the rectangle by the given margins on every side. The rectangle is as much bigger
+    /// when it's too close to the top or left edge to move its origin that far, it only grows
+    /// further right or down instead.
     #[inline(always)]
     pub(crate) fn expand_by(&self, x_margin: usize, y_margin: usize) -> Rectangle {
-        let (x, width) = if self.0 .0 >= x_margin {
-            (self.0 .0 - x_margin, self.1 .0 + x_margin * 2)
-        } else if self.0 .0 == 0 {
-            (0, self.1 .0 + x_margin - self.0 .0 + x_margin)
-        } else {
-            (0, self.1 .0 + x_margin * 2)
-        };
-
-        let (y, height) = if self.0 .1 >= y_margin {
-            (self.0 .1 - y_margin, self.1 .1 + y_margin * 2)
-        } else if self.0 .1 == 0 {
-            (0, self.1 .1 + y_margin - self.0 .1 + y_margin)
-        } else {
-            (0, self.1 .1 + y_margin * 2)
-        };
-
-        Rectangle(Idx(x, y, self.0 .2), Bounds2D(width, height))
+        let x = self.0.checked_sub(x_margin, 0).map_or(0, |idx| idx.x());
+        let y = self.0.checked_sub(0, y_margin).map_or(0, |idx| idx.y());
+        let grow = |length: usize, margin: usize| length.saturating_add(margin.saturating_mul(2));
+        Rectangle(
+            Idx(x, y, self.0 .2),
+            Bounds2D(grow(self.1 .0, x_margin), grow(self.1 .1, y_margin)),
+        )
     }
 
+    /// Shrinks the rectangle by the given margins on every side. A rectangle narrower than both
+    /// margins together ends up empty, and one narrower than a single margin stays where it is.
     #[inline(always)]
     pub(crate) fn shrink_by(&self, x_margin: usize, y_margin: usize) -> Rectangle {
-        let (x, width) = if self.1 .0 >= x_margin {
-            (self.0 .0 + x_margin, self.1 .0 - x_margin * 2)
-        } else {
-            (self.0 .0, 0)
-        };
-
-        let (y, height) = if self.1 .1 >= y_margin {
-            (self.0 .1 + y_margin, self.1 .1 - y_margin * 2)
-        } else {
-            (self.0 .1, 0)
+        let shrink = |origin: usize, length: usize, margin: usize| {
+            if length >= margin {
+                (
+                    origin.saturating_add(margin),
+                    length.saturating_sub(margin.saturating_mul(2)),
+                )
+            } else {
+                (origin, 0)
+            }
         };
-
+        let (x, width) = shrink(self.0 .0, self.1 .0, x_margin);
+        let (y, height) = shrink(self.0 .1, self.1 .1, y_margin);
         Rectangle(Idx(x, y, self.0 .2), Bounds2D(width, height))
     }
 }
@@ -205,8 +268,9 @@ impl IntoIterator for Rectangle {
         if self.width() == 0 || self.height() == 0 {
             return indices.into_iter();
         }
-        for x in self.x()..(self.x() + self.width()) {
-            for y in self.y()..(self.y() + self.height()) {
+        let (x_extent, y_extent) = self.extents();
+        for x in self.x()..x_extent {
+            for y in self.y()..y_extent {
                 indices.push(Idx(x, y, self.z()));
             }
         }
@@ -219,7 +283,10 @@ impl std::ops::Add for &Rectangle {
     fn add(self, other: &Rectangle) -> Self::Output {
         Rectangle(
             Idx(other.0 .0, other.0 .1, other.0 .2),
-            Bounds2D(self.1 .0 + other.1 .0, self.1 .1 + other.1 .1),
+            Bounds2D(
+                self.1 .0.saturating_add(other.1 .0),
+                self.1 .1.saturating_add(other.1 .1),
+            ),
         )
     }
 }
@@ -239,7 +306,8 @@ impl From<Idx> for Position {
     }
 }
 
-/// Direction represents the direction indicated by the player.
+/// Direction represents the direction indicated by the player, or the direction something moves
+/// in, in screen coordinates.
 #[derive(Clone, Debug, Default, PartialEq)]
 pub(crate) enum Direction {
     #[default]
@@ -249,6 +317,32 @@ pub(crate) enum Direction {
     Down,
 }
 
+// Every conversion between screen and board directions goes through these two methods. The board
+// is drawn as is, so for now they map each direction to its namesake.
+impl Direction {
+    /// Returns the direction on the board that this screen direction corresponds to.
+    #[must_use]
+    pub(crate) fn to_board(&self) -> BoardDirection {
+        match self {
+            Self::Left => BoardDirection::Left,
+            Self::Right => BoardDirection::Right,
+            Self::Up => BoardDirection::Up,
+            Self::Down => BoardDirection::Down,
+        }
+    }
+
+    /// Returns the screen direction that the given board direction is drawn as.
+    #[must_use]
+    pub(crate) fn from_board(direction: &BoardDirection) -> Self {
+        match direction {
+            BoardDirection::Left => Self::Left,
+            BoardDirection::Right => Self::Right,
+            BoardDirection::Up => Self::Up,
+            BoardDirection::Down => Self::Down,
+        }
+    }
+}
+
 impl std::fmt::Display for Direction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
@@ -265,6 +359,9 @@ impl std::fmt::Display for Direction {
 mod test {
     use std::collections::BTreeSet;
 
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
     use super::*;
     use rstest::*;
 
@@ -272,6 +369,51 @@ mod test {
         Rectangle(Idx(x, y, z), Bounds2D(width, height))
     }
 
+    #[rstest]
+    #[case::inside(Idx(4, 4, 0), true)]
+    #[case::origin(Idx(0, 0, 0), true)]
+    #[case::right_edge(Idx(5, 0, 0), false)]
+    #[case::bottom_edge(Idx(0, 5, 0), false)]
+    fn contains_idx_excludes_the_far_edges(#[case] idx: Idx, #[case] contained: bool) {
+        let r = rectangle(0, 0, 0, 5, 5);
+        assert_eq!(r.contains_or_err(Geometry::Idx(&idx)).is_ok(), contained);
+    }
+
+    #[rstest]
+    fn direction_conversions_round_trip(
+        #[values(Direction::Left, Direction::Right, Direction::Up, Direction::Down)]
+        direction: Direction,
+    ) {
+        assert_eq!(Direction::from_board(&direction.to_board()), direction);
+        assert_eq!(direction.to_board().to_string(), direction.to_string());
+    }
+
+    #[rstest]
+    #[case::top_left(Idx(0, 0, 1), vec![Idx(1, 0, 1), Idx(0, 1, 1)])]
+    #[case::top_right(Idx(3, 0, 1), vec![Idx(2, 0, 1), Idx(3, 1, 1)])]
+    #[case::bottom_left(Idx(0, 3, 1), vec![Idx(1, 3, 1), Idx(0, 2, 1)])]
+    #[case::bottom_right(Idx(3, 3, 1), vec![Idx(2, 3, 1), Idx(3, 2, 1)])]
+    #[case::top_edge(Idx(1, 0, 1), vec![Idx(0, 0, 1), Idx(2, 0, 1), Idx(1, 1, 1)])]
+    #[case::left_edge(Idx(0, 2, 1), vec![Idx(1, 2, 1), Idx(0, 1, 1), Idx(0, 3, 1)])]
+    #[case::center(
+        Idx(1, 2, 1),
+        vec![Idx(0, 2, 1), Idx(2, 2, 1), Idx(1, 1, 1), Idx(1, 3, 1)]
+    )]
+    fn validate_neighbors(#[case] idx: Idx, #[case] expected: Vec<Idx>) {
+        assert_eq!(idx.neighbors(&Bounds2D(4, 4)), expected);
+    }
+
+    #[rstest]
+    #[case::top_left(Idx(0, 0, 0), vec![Idx(1, 1, 0)])]
+    #[case::bottom_edge(Idx(2, 3, 0), vec![Idx(1, 2, 0), Idx(3, 2, 0)])]
+    #[case::center(
+        Idx(1, 1, 0),
+        vec![Idx(0, 0, 0), Idx(2, 0, 0), Idx(2, 2, 0), Idx(0, 2, 0)]
+    )]
+    fn validate_diagonal_neighbors(#[case] idx: Idx, #[case] expected: Vec<Idx>) {
+        assert_eq!(idx.diagonal_neighbors(&Bounds2D(4, 4)), expected);
+    }
+
     #[rstest]
     #[case::move_right(
         1,
@@ -440,6 +582,8 @@ mod test {
     #[case::away_from_origin_shrink_3x3_by_1(rectangle(10, 10, 0, 3, 3), (1, 1), rectangle(11, 11, 0, 1, 1))]
     #[case::away_from_origin_shrink_20x20_by_5(rectangle(10, 10, 0, 20, 20), (5, 5), rectangle(15, 15, 0, 10, 10))]
     #[case::near_origin_shrink_30x30_by_5(rectangle(3, 3, 0, 30, 30), (5, 5), rectangle(8, 8, 0, 20, 20))]
+    // used to wrap around to a width close to usize::MAX
+    #[case::narrower_than_both_margins(rectangle(0, 0, 0, 3, 3), (2, 2), rectangle(2, 2, 0, 0, 0))]
     fn validate_shrink_by(
         #[case] initial: Rectangle,
         #[case] margin: (usize, usize),
@@ -448,4 +592,184 @@ mod test {
         let actual = initial.shrink_by(margin.0, margin.1);
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn idx_arithmetic_reports_leaving_the_coordinates() {
+        assert_eq!(Idx(3, 4, 1).checked_add(2, 1).ok(), Some(Idx(5, 5, 1)));
+        assert_eq!(Idx(3, 4, 1).checked_sub(3, 4).ok(), Some(Idx(0, 0, 1)));
+        let e = Idx(3, 4, 1)
+            .checked_sub(1, 5)
+            .expect_err("y would go past the top");
+        assert_eq!(
+            e.inner.to_string(),
+            "idx(3,4,1) - (1, 5) is out of the range of coordinates"
+        );
+        assert!(Idx(usize::MAX, 0, 0).checked_add(1, 0).is_err());
+    }
+
+    #[test]
+    fn translating_past_the_end_of_the_coordinates_fails_in_place() {
+        let mut r = rectangle(usize::MAX - 1, 0, 0, 1, 1);
+        assert!(r.translate(2, &Direction::Right).is_err());
+        assert_eq!(r, rectangle(usize::MAX - 1, 0, 0, 1, 1));
+        assert_eq!(r.extents(), (usize::MAX, 1));
+    }
+
+    #[test]
+    fn the_corners_of_an_empty_rectangle_are_its_origin() {
+        let r = rectangle(4, 4, 0, 0, 0);
+        assert_eq!(r.relative_idx(&Position::BottomRight), (0, 0));
+        assert_eq!(r.relative_idx(&Position::TopRight), (0, 0));
+        assert_eq!(r.relative_idx(&Position::BottomLeft), (0, 0));
+    }
+
+    #[rstest]
+    #[case::inside(rectangle(6, 6, 0, 2, 2), true)]
+    #[case::same(rectangle(5, 5, 0, 5, 5), true)]
+    // these fit within the outer rectangle's size but not where it is
+    #[case::left_of_it(rectangle(0, 6, 0, 2, 2), false)]
+    #[case::above_it(rectangle(6, 0, 0, 2, 2), false)]
+    #[case::past_its_right_edge(rectangle(9, 6, 0, 2, 2), false)]
+    fn contains_a_rectangle_away_from_the_origin(
+        #[case] inner: Rectangle,
+        #[case] contained: bool,
+    ) {
+        let outer = rectangle(5, 5, 0, 5, 5);
+        assert_eq!(
+            outer.contains_or_err(Geometry::Rectangle(&inner)).is_ok(),
+            contained
+        );
+    }
+
+    /// The side of the space random rectangles are placed in, and the most they're moved or
+    /// grown by.
+    const SPACE: usize = 48;
+
+    /// The number of random cases each property is checked against.
+    const CASES: usize = 2000;
+
+    fn random_rectangles(seed: u64) -> impl Iterator<Item = (Rectangle, StdRng)> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        std::iter::from_fn(move || {
+            let r = rectangle(
+                rng.gen_range(0..SPACE),
+                rng.gen_range(0..SPACE),
+                rng.gen_range(0..4),
+                rng.gen_range(0..SPACE),
+                rng.gen_range(0..SPACE),
+            );
+            // a generator of its own for whatever the property does with the rectangle
+            Some((r, StdRng::seed_from_u64(rng.gen())))
+        })
+        .take(CASES)
+    }
+
+    fn random_direction(rng: &mut StdRng) -> Direction {
+        match rng.gen_range(0..4) {
+            0 => Direction::Left,
+            1 => Direction::Right,
+            2 => Direction::Up,
+            _ => Direction::Down,
+        }
+    }
+
+    fn assert_extents_follow_the_origin(r: &Rectangle) {
+        let (x_extent, y_extent) = r.extents();
+        assert!(x_extent >= r.x() && y_extent >= r.y(), "{}", r);
+    }
+
+    #[test]
+    fn extents_never_precede_the_origin() {
+        for (r, mut rng) in random_rectangles(1) {
+            assert_extents_follow_the_origin(&r);
+            let mut moved = r.clone();
+            moved
+                .translate(rng.gen_range(0..SPACE), &random_direction(&mut rng))
+                .expect("translations within the space can't fail");
+            assert_extents_follow_the_origin(&moved);
+            let (x_margin, y_margin) = (rng.gen_range(0..SPACE), rng.gen_range(0..SPACE));
+            assert_extents_follow_the_origin(&r.expand_by(x_margin, y_margin));
+            assert_extents_follow_the_origin(&r.shrink_by(x_margin, y_margin));
+        }
+    }
+
+    #[test]
+    fn containing_a_rectangle_agrees_with_containing_its_cells() {
+        let mut contained = 0;
+        for (outer, mut rng) in random_rectangles(2) {
+            let inner = rectangle(
+                rng.gen_range(0..SPACE),
+                rng.gen_range(0..SPACE),
+                outer.z(),
+                rng.gen_range(1..SPACE / 2),
+                rng.gen_range(1..SPACE / 2),
+            );
+            let whole = outer.contains_or_err(Geometry::Rectangle(&inner)).is_ok();
+            let cells = inner
+                .clone()
+                .into_iter()
+                .all(|idx| outer.contains_or_err(Geometry::Idx(&idx)).is_ok());
+            assert_eq!(whole, cells, "{} in {}", inner, outer);
+            contained += whole as usize;
+        }
+        // both sides of the property were checked
+        assert!(contained > 0 && contained < CASES, "{}", contained);
+    }
+
+    #[test]
+    fn translating_back_and_forth_round_trips_unless_clamped() {
+        for (r, mut rng) in random_rectangles(3) {
+            let mag = rng.gen_range(0..SPACE);
+            let direction = random_direction(&mut rng);
+            let (back, clamped) = match direction {
+                Direction::Left => (Direction::Right, r.x() < mag),
+                Direction::Right => (Direction::Left, false),
+                Direction::Up => (Direction::Down, r.y() < mag),
+                Direction::Down => (Direction::Up, false),
+            };
+            let mut moved = r.clone();
+            moved
+                .translate(mag, &direction)
+                .and_then(|_| moved.translate(mag, &back))
+                .expect("translations within the space can't fail");
+            assert_eq!(moved.dimensions(), r.dimensions());
+            if !clamped {
+                assert_eq!(moved, r, "{} by {}", direction, mag);
+            }
+        }
+    }
+
+    #[test]
+    fn expanding_then_shrinking_round_trips_unless_clamped() {
+        for (r, mut rng) in random_rectangles(4) {
+            let (x_margin, y_margin) = (rng.gen_range(0..SPACE), rng.gen_range(0..SPACE));
+            let expanded = r.expand_by(x_margin, y_margin);
+            assert!(
+                expanded.contains_or_err(Geometry::Rectangle(&r)).is_ok(),
+                "{} expanded to {}",
+                r,
+                expanded
+            );
+            if r.x() >= x_margin && r.y() >= y_margin {
+                assert_eq!(expanded.shrink_by(x_margin, y_margin), r);
+            }
+        }
+    }
+
+    #[test]
+    fn shrinking_stays_inside() {
+        for (r, mut rng) in random_rectangles(5) {
+            let (x_margin, y_margin) = (rng.gen_range(0..SPACE), rng.gen_range(0..SPACE));
+            let shrunk = r.shrink_by(x_margin, y_margin);
+            assert!(shrunk.width() <= r.width() && shrunk.height() <= r.height());
+            for idx in shrunk.clone().into_iter() {
+                assert!(
+                    r.contains_or_err(Geometry::Idx(&idx)).is_ok(),
+                    "{} shrunk to {}",
+                    r,
+                    shrunk
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,430 @@
+use super::events::UserInput;
+use super::geometry::Direction;
+
+/// Label shown in prompts in place of the key for an action nothing is bound to.
+const UNBOUND: &str = "(unbound)";
+
+/// A key, independent of the terminal library reporting it.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Key {
+    Char(char),
+    Left,
+    Right,
+    Up,
+    Down,
+    Enter,
+    Esc,
+    Backspace,
+}
+
+/// A key along with whether Ctrl has to be held down with it.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct KeyBinding {
+    pub(crate) key: Key,
+    pub(crate) ctrl: bool,
+}
+
+impl KeyBinding {
+    pub(crate) fn plain(key: Key) -> Self {
+        Self { key, ctrl: false }
+    }
+
+    pub(crate) fn ctrl(key: Key) -> Self {
+        Self { key, ctrl: true }
+    }
+}
+
+/// A human readable label for a key binding, eg "q", "Ctrl+R" or "←".
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct KeyDisplay(String);
+
+impl std::fmt::Display for KeyDisplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(&self.0)
+    }
+}
+
+impl From<&KeyBinding> for KeyDisplay {
+    fn from(binding: &KeyBinding) -> Self {
+        let key = match (&binding.key, binding.ctrl) {
+            (Key::Char(' '), _) => "Space".to_string(),
+            (Key::Char(c), true) => c.to_uppercase().to_string(),
+            (Key::Char(c), false) => c.to_string(),
+            (Key::Left, _) => "←".to_string(),
+            (Key::Right, _) => "→".to_string(),
+            (Key::Up, _) => "↑".to_string(),
+            (Key::Down, _) => "↓".to_string(),
+            (Key::Enter, _) => "Enter".to_string(),
+            (Key::Esc, _) => "Esc".to_string(),
+            (Key::Backspace, _) => "Backspace".to_string(),
+        };
+        if binding.ctrl {
+            return Self(format!("Ctrl+{}", key));
+        }
+        Self(key)
+    }
+}
+
+/// The placeholders prompt templates can refer to keys by, and the actions they stand for.
+fn placeholders() -> [(&'static str, UserInput); 17] {
+    [
+        ("{quit}", UserInput::Quit),
+        ("{new_game}", UserInput::NewGame),
+        ("{replay}", UserInput::Replay),
+        ("{confirm}", UserInput::Confirm),
+        ("{cancel}", UserInput::Cancel),
+        ("{left}", UserInput::Direction(Direction::Left)),
+        ("{right}", UserInput::Direction(Direction::Right)),
+        ("{up}", UserInput::Direction(Direction::Up)),
+        ("{down}", UserInput::Direction(Direction::Down)),
+        ("{increase}", UserInput::Increase),
+        ("{decrease}", UserInput::Decrease),
+        ("{erase}", UserInput::Erase),
+        ("{write}", UserInput::WriteNotation),
+        ("{undo}", UserInput::Undo),
+        ("{redo}", UserInput::Redo),
+        ("{continue}", UserInput::Continue),
+        ("{autoplay}", UserInput::ToggleAutoPlay),
+    ]
+}
+
+/// Actions the game can't be played, or left, without.
+fn essentials() -> [UserInput; 5] {
+    [
+        UserInput::Quit,
+        UserInput::Direction(Direction::Left),
+        UserInput::Direction(Direction::Right),
+        UserInput::Direction(Direction::Up),
+        UserInput::Direction(Direction::Down),
+    ]
+}
+
+/// Which key triggers which action. Several keys may trigger the same action; the first one bound
+/// is the one prompts refer to.
+#[derive(Clone, Debug)]
+pub(crate) struct Keymap {
+    bindings: Vec<(KeyBinding, UserInput)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self::empty();
+        for (arrow, vi, direction) in [
+            (Key::Left, 'h', Direction::Left),
+            (Key::Right, 'l', Direction::Right),
+            (Key::Up, 'k', Direction::Up),
+            (Key::Down, 'j', Direction::Down),
+        ] {
+            keymap.bind(
+                KeyBinding::plain(arrow),
+                UserInput::Direction(direction.clone()),
+            );
+            keymap.bind(
+                KeyBinding::plain(Key::Char(vi)),
+                UserInput::Direction(direction),
+            );
+        }
+        keymap.bind(KeyBinding::plain(Key::Char('q')), UserInput::Quit);
+        keymap.bind(KeyBinding::plain(Key::Char('n')), UserInput::NewGame);
+        keymap.bind(KeyBinding::plain(Key::Char('r')), UserInput::Replay);
+        keymap.bind(KeyBinding::plain(Key::Char('c')), UserInput::Continue);
+        // h is taken by the vi keys, so the heatmap is on Shift+H
+        keymap.bind(KeyBinding::plain(Key::Char('H')), UserInput::ShowHeatmap);
+        keymap.bind(KeyBinding::plain(Key::Char('p')), UserInput::CyclePack);
+        keymap.bind(KeyBinding::plain(Key::Char('t')), UserInput::PreviewThemes);
+        keymap.bind(KeyBinding::plain(Key::Enter), UserInput::Confirm);
+        // for the questions answered with y or n, n being a new game
+        keymap.bind(KeyBinding::plain(Key::Char('y')), UserInput::Confirm);
+        keymap.bind(KeyBinding::plain(Key::Esc), UserInput::Cancel);
+        keymap.bind(KeyBinding::plain(Key::Char(' ')), UserInput::PowerUp);
+        keymap.bind(KeyBinding::plain(Key::Char('G')), UserInput::ToggleGrid);
+        keymap.bind(KeyBinding::plain(Key::Char('E')), UserInput::EditBoard);
+        keymap.bind(KeyBinding::plain(Key::Char('u')), UserInput::Undo);
+        keymap.bind(KeyBinding::ctrl(Key::Char('z')), UserInput::Undo);
+        keymap.bind(KeyBinding::plain(Key::Char('U')), UserInput::Redo);
+        keymap.bind(KeyBinding::ctrl(Key::Char('r')), UserInput::Redo);
+        keymap.bind(KeyBinding::ctrl(Key::Char('s')), UserInput::SaveGame);
+        keymap.bind(KeyBinding::plain(Key::Char('s')), UserInput::SaveGame);
+        // l alone moves right
+        keymap.bind(KeyBinding::ctrl(Key::Char('l')), UserInput::LoadGame);
+        keymap.bind(KeyBinding::plain(Key::Char('a')), UserInput::ToggleAutoPlay);
+        // = is + without Shift on most layouts
+        keymap.bind(KeyBinding::plain(Key::Char('+')), UserInput::Increase);
+        keymap.bind(KeyBinding::plain(Key::Char('=')), UserInput::Increase);
+        keymap.bind(KeyBinding::plain(Key::Char('-')), UserInput::Decrease);
+        for digit in 0..10 {
+            let c = char::from_digit(digit, 10).expect("every digit is below 10");
+            keymap.bind(
+                KeyBinding::plain(Key::Char(c)),
+                UserInput::Digit(digit as u8),
+            );
+        }
+        keymap.bind(KeyBinding::plain(Key::Backspace), UserInput::Erase);
+        keymap.bind(KeyBinding::plain(Key::Char('w')), UserInput::WriteNotation);
+        // raw mode turns Ctrl+C into a key event on every platform rather than a signal
+        keymap.bind(KeyBinding::ctrl(Key::Char('c')), UserInput::Quit);
+        keymap
+    }
+}
+
+impl Keymap {
+    /// A keymap with nothing bound.
+    pub(crate) fn empty() -> Self {
+        Self {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Binds the key to the action, replacing whatever it was bound to before.
+    pub(crate) fn bind(&mut self, binding: KeyBinding, action: UserInput) {
+        self.unbind(&binding);
+        self.bindings.push((binding, action));
+    }
+
+    pub(crate) fn unbind(&mut self, binding: &KeyBinding) {
+        self.bindings.retain(|(b, _)| b != binding);
+    }
+
+    /// Returns the action the key triggers. A key held with Ctrl that isn't bound as such
+    /// triggers the same action as it does on its own.
+    pub(crate) fn action_for(&self, binding: &KeyBinding) -> Option<UserInput> {
+        let find = |binding: &KeyBinding| {
+            self.bindings
+                .iter()
+                .find(|(b, _)| b == binding)
+                .map(|(_, action)| action.clone())
+        };
+        find(binding).or_else(|| find(&KeyBinding::plain(binding.key.clone())))
+    }
+
+    /// Returns the label of the key prompts should tell the player to press for the action.
+    pub(crate) fn key_for(&self, action: UserInput) -> Option<KeyDisplay> {
+        self.bindings
+            .iter()
+            .find(|(_, a)| *a == action)
+            .map(|(binding, _)| binding.into())
+    }
+
+    /// Returns the essential actions no key triggers.
+    pub(crate) fn unbound_essentials(&self) -> Vec<UserInput> {
+        essentials()
+            .into_iter()
+            .filter(|action| self.key_for(action.clone()).is_none())
+            .collect()
+    }
+
+    /// Fills in the keys for the placeholders in the template, eg "press {quit} to quit".
+    pub(crate) fn render(&self, template: &str) -> String {
+        placeholders()
+            .into_iter()
+            .fold(template.to_string(), |text, (placeholder, action)| {
+                if !text.contains(placeholder) {
+                    return text;
+                }
+                let label = self
+                    .key_for(action)
+                    .map_or_else(|| UNBOUND.to_string(), |key| key.to_string());
+                text.replace(placeholder, &label)
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+
+    #[rstest]
+    #[case::plain(KeyBinding::plain(Key::Char('q')), "q")]
+    #[case::ctrl(KeyBinding::ctrl(Key::Char('r')), "Ctrl+R")]
+    #[case::left(KeyBinding::plain(Key::Left), "←")]
+    #[case::right(KeyBinding::plain(Key::Right), "→")]
+    #[case::up(KeyBinding::plain(Key::Up), "↑")]
+    #[case::down(KeyBinding::plain(Key::Down), "↓")]
+    #[case::enter(KeyBinding::plain(Key::Enter), "Enter")]
+    #[case::esc(KeyBinding::plain(Key::Esc), "Esc")]
+    #[case::space(KeyBinding::plain(Key::Char(' ')), "Space")]
+    #[case::backspace(KeyBinding::plain(Key::Backspace), "Backspace")]
+    fn key_for_labels_the_binding(#[case] binding: KeyBinding, #[case] expected: &str) {
+        let mut keymap = Keymap::empty();
+        keymap.bind(binding, UserInput::NewGame);
+        assert_eq!(
+            keymap.key_for(UserInput::NewGame),
+            Some(KeyDisplay(expected.to_string()))
+        );
+        assert_eq!(keymap.key_for(UserInput::Quit), None);
+    }
+
+    #[test]
+    fn key_for_prefers_the_first_binding() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.key_for(UserInput::Quit).unwrap().to_string(), "q");
+        assert_eq!(
+            keymap
+                .key_for(UserInput::Direction(Direction::Left))
+                .unwrap()
+                .to_string(),
+            "←"
+        );
+    }
+
+    #[test]
+    fn heatmap_is_on_shift_h_beside_the_vi_keys() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action_for(&KeyBinding::plain(Key::Char('H'))),
+            Some(UserInput::ShowHeatmap)
+        );
+        assert_eq!(
+            keymap.action_for(&KeyBinding::plain(Key::Char('h'))),
+            Some(UserInput::Direction(Direction::Left))
+        );
+    }
+
+    #[test]
+    fn undo_is_on_u_and_ctrl_z() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action_for(&KeyBinding::plain(Key::Char('u'))),
+            Some(UserInput::Undo)
+        );
+        assert_eq!(keymap.key_for(UserInput::Undo).unwrap().to_string(), "u");
+        assert_eq!(
+            keymap.action_for(&KeyBinding::ctrl(Key::Char('z'))),
+            Some(UserInput::Undo)
+        );
+    }
+
+    #[test]
+    fn redo_is_on_shift_u_and_ctrl_r() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action_for(&KeyBinding::plain(Key::Char('U'))),
+            Some(UserInput::Redo)
+        );
+        assert_eq!(
+            keymap.action_for(&KeyBinding::ctrl(Key::Char('r'))),
+            Some(UserInput::Redo)
+        );
+        assert_eq!(keymap.key_for(UserInput::Redo).unwrap().to_string(), "U");
+    }
+
+    #[test]
+    fn saving_is_on_s_and_ctrl_s_and_loading_on_ctrl_l() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action_for(&KeyBinding::ctrl(Key::Char('s'))),
+            Some(UserInput::SaveGame)
+        );
+        assert_eq!(
+            keymap.action_for(&KeyBinding::plain(Key::Char('s'))),
+            Some(UserInput::SaveGame)
+        );
+        assert_eq!(
+            keymap.action_for(&KeyBinding::ctrl(Key::Char('l'))),
+            Some(UserInput::LoadGame)
+        );
+        assert_eq!(
+            keymap.action_for(&KeyBinding::plain(Key::Char('l'))),
+            Some(UserInput::Direction(Direction::Right))
+        );
+    }
+
+    #[test]
+    fn autoplay_is_on_a() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action_for(&KeyBinding::plain(Key::Char('a'))),
+            Some(UserInput::ToggleAutoPlay)
+        );
+        assert_eq!(
+            keymap
+                .key_for(UserInput::ToggleAutoPlay)
+                .unwrap()
+                .to_string(),
+            "a"
+        );
+    }
+
+    #[test]
+    fn digits_are_typed_as_themselves() {
+        let keymap = Keymap::default();
+        for (c, digit) in ('0'..='9').zip(0..) {
+            assert_eq!(
+                keymap.action_for(&KeyBinding::plain(Key::Char(c))),
+                Some(UserInput::Digit(digit))
+            );
+        }
+    }
+
+    #[test]
+    fn ctrl_only_matters_when_bound() {
+        let keymap = Keymap::default();
+        let ctrl_c = KeyBinding::ctrl(Key::Char('c'));
+        assert_eq!(keymap.action_for(&ctrl_c), Some(UserInput::Quit));
+        let c = KeyBinding::plain(Key::Char('c'));
+        assert_eq!(keymap.action_for(&c), Some(UserInput::Continue));
+        let ctrl_z = KeyBinding::ctrl(Key::Char('z'));
+        assert_eq!(keymap.action_for(&ctrl_z), Some(UserInput::Undo));
+        assert_eq!(keymap.action_for(&KeyBinding::plain(Key::Char('z'))), None);
+        let ctrl_n = KeyBinding::ctrl(Key::Char('n'));
+        assert_eq!(keymap.action_for(&ctrl_n), Some(UserInput::NewGame));
+    }
+
+    #[test]
+    fn rebinding_a_key_replaces_its_action() {
+        let mut keymap = Keymap::default();
+        keymap.bind(KeyBinding::plain(Key::Char('q')), UserInput::NewGame);
+        let q = KeyBinding::plain(Key::Char('q'));
+        assert_eq!(keymap.action_for(&q), Some(UserInput::NewGame));
+        assert_eq!(
+            keymap.key_for(UserInput::Quit).unwrap().to_string(),
+            "Ctrl+C"
+        );
+    }
+
+    #[test]
+    fn render_substitutes_placeholders() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.render("press {quit} to quit or {new_game} to start new game"),
+            "press q to quit or n to start new game"
+        );
+        assert_eq!(keymap.render("{left}{right}{up}{down}"), "←→↑↓");
+        assert_eq!(keymap.render("{unknown}"), "{unknown}");
+    }
+
+    #[test]
+    fn render_falls_back_for_unbound_actions() {
+        let mut keymap = Keymap::default();
+        keymap.unbind(&KeyBinding::plain(Key::Char('n')));
+        assert_eq!(
+            keymap.render("press {new_game} to start over"),
+            "press (unbound) to start over"
+        );
+    }
+
+    #[test]
+    fn render_uses_remapped_bindings() {
+        let mut keymap = Keymap::default();
+        keymap.unbind(&KeyBinding::plain(Key::Char('q')));
+        keymap.bind(KeyBinding::ctrl(Key::Char('x')), UserInput::NewGame);
+        keymap.unbind(&KeyBinding::plain(Key::Char('n')));
+        assert_eq!(
+            keymap.render("press {quit} to quit or {new_game} to start new game"),
+            "press Ctrl+C to quit or Ctrl+X to start new game"
+        );
+    }
+
+    #[test]
+    fn unbound_essentials_are_reported() {
+        assert!(Keymap::default().unbound_essentials().is_empty());
+
+        let mut keymap = Keymap::default();
+        keymap.unbind(&KeyBinding::plain(Key::Char('q')));
+        keymap.unbind(&KeyBinding::ctrl(Key::Char('c')));
+        keymap.unbind(&KeyBinding::plain(Key::Char('n')));
+        assert_eq!(keymap.unbound_essentials(), vec![UserInput::Quit]);
+    }
+}
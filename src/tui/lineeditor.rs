@@ -0,0 +1,87 @@
+//! A field a line of text is typed into a character at a time, eg a number.
+
+/// The text typed into a field so far, which takes only the characters it accepts and only so
+/// many of them.
+#[derive(Clone, Debug)]
+pub(crate) struct LineEditor {
+    text: String,
+    capacity: usize,
+    accepts: fn(char) -> bool,
+}
+
+impl LineEditor {
+    pub(crate) fn new(capacity: usize, accepts: fn(char) -> bool) -> Self {
+        Self {
+            text: String::new(),
+            capacity,
+            accepts,
+        }
+    }
+
+    /// A field for a number of up to the given number of digits.
+    pub(crate) fn digits(capacity: usize) -> Self {
+        Self::new(capacity, |c| c.is_ascii_digit())
+    }
+
+    /// Types the given text into the field, as far as the field takes it.
+    pub(crate) fn with_text(mut self, text: &str) -> Self {
+        for c in text.chars() {
+            self.insert(c);
+        }
+        self
+    }
+
+    /// Types the character at the end of the field, returning whether the field took it.
+    pub(crate) fn insert(&mut self, c: char) -> bool {
+        if !(self.accepts)(c) || self.text.chars().count() >= self.capacity {
+            return false;
+        }
+        self.text.push(c);
+        true
+    }
+
+    /// Takes back the last character typed, returning whether there was one.
+    pub(crate) fn backspace(&mut self) -> bool {
+        self.text.pop().is_some()
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+
+    #[rstest]
+    #[case::digits("123", "123")]
+    #[case::letters_refused("1a2b", "12")]
+    #[case::capped("1234567", "1234")]
+    fn typed_text_is_filtered_and_capped(#[case] typed: &str, #[case] expected: &str) {
+        let field = LineEditor::digits(4).with_text(typed);
+        assert_eq!(field.text(), expected);
+    }
+
+    #[test]
+    fn a_refused_character_is_reported() {
+        let mut field = LineEditor::digits(2);
+        assert!(field.insert('4'));
+        assert!(!field.insert('x'));
+        assert!(field.insert('2'));
+        assert!(!field.insert('0'));
+        assert_eq!(field.text(), "42");
+    }
+
+    #[test]
+    fn backspace_takes_back_one_character_at_a_time() {
+        let mut field = LineEditor::digits(4).with_text("12");
+        assert!(field.backspace());
+        assert_eq!(field.text(), "1");
+        assert!(field.backspace());
+        assert!(!field.backspace());
+        assert_eq!(field.text(), "");
+    }
+}
@@ -1,10 +1,17 @@
 pub(crate) mod canvas;
+pub(crate) mod capabilities;
 pub(crate) mod drawbuffer;
 pub(crate) mod colors;
 pub(crate) mod geometry;
 pub(crate) mod tuxel;
 pub(crate) mod crossterm;
+pub(crate) mod dirty;
 pub(crate) mod error;
 pub(crate) mod events;
+pub(crate) mod keymap;
+pub(crate) mod lineeditor;
 pub(crate) mod renderer;
 pub(crate) mod textbuffer;
+pub(crate) mod watchdog;
+#[cfg(test)]
+pub(crate) mod testing;
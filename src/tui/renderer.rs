@@ -6,4 +6,61 @@ pub(crate) trait Renderer {
     fn render(&mut self, c: &Canvas) -> Result<()>;
     fn clear(&mut self, c: &Canvas) -> Result<()>;
     fn recover(&mut self);
+
+    /// Draws every cell of the canvas, whether or not it changed, eg once it has been laid out
+    /// anew. Renderers that can't do better draw what changed.
+    fn repaint(&mut self, c: &Canvas) -> Result<()> {
+        self.render(c)
+    }
+
+    /// Marks the start of a frame: everything drawn until `end_frame` is shown at once. Renderers
+    /// that draw every call on its own need do nothing.
+    fn begin_frame(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Marks the end of the frame `begin_frame` started, even if drawing it failed.
+    fn end_frame(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Sets the window title. Renderers that don't draw to a terminal window ignore it.
+    fn set_title(&mut self, _title: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The calls made on the terminal, through a `Renderer` or an `EventSource`, named in errors so
+/// that a failure says which of them it came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TerminalOperation {
+    SizeHint,
+    Render,
+    Clear,
+    SetTitle,
+    NextEvent,
+}
+
+impl TerminalOperation {
+    #[cfg(test)]
+    pub(crate) const ALL: [TerminalOperation; 5] = [
+        Self::SizeHint,
+        Self::Render,
+        Self::Clear,
+        Self::SetTitle,
+        Self::NextEvent,
+    ];
+}
+
+impl std::fmt::Display for TerminalOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::SizeHint => "querying the terminal size",
+            Self::Render => "rendering",
+            Self::Clear => "clearing the terminal",
+            Self::SetTitle => "setting the window title",
+            Self::NextEvent => "reading input",
+        };
+        write!(f, "{}", name)
+    }
 }
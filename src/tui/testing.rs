@@ -0,0 +1,371 @@
+//! Renderer and event source doubles for driving the game without a terminal.
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use super::canvas::Canvas;
+use super::error::Result;
+use super::events::{Event, EventSource, UserInput};
+use super::renderer::{Renderer, TerminalOperation};
+use super::tuxel::WIDE_CONTINUATION;
+
+/// A Renderer that keeps an in-memory screen, updated the same way a terminal would be, and
+/// captures a snapshot of it after every render. It counts the frames bracketed with
+/// `begin_frame` and `end_frame` too, and panics if they nest or an unbegun one is ended.
+pub(crate) struct TestRenderer {
+    width: usize,
+    height: usize,
+    screen: Vec<Vec<char>>,
+    frames: Rc<RefCell<Vec<String>>>,
+    recovered: Rc<Cell<bool>>,
+    frame_count: Rc<Cell<usize>>,
+    in_frame: Rc<Cell<bool>>,
+}
+
+impl TestRenderer {
+    pub(crate) fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            screen: vec![vec![' '; width]; height],
+            frames: Rc::new(RefCell::new(Vec::new())),
+            recovered: Rc::new(Cell::new(false)),
+            frame_count: Rc::new(Cell::new(0)),
+            in_frame: Rc::new(Cell::new(false)),
+        }
+    }
+
+    /// Returns a handle to the captured frames that remains usable after the renderer has been
+    /// moved into the game.
+    pub(crate) fn frames(&self) -> Rc<RefCell<Vec<String>>> {
+        self.frames.clone()
+    }
+
+    /// Returns a handle that reports whether the terminal would have been restored.
+    pub(crate) fn recovered(&self) -> Rc<Cell<bool>> {
+        self.recovered.clone()
+    }
+
+    /// Returns a handle that counts the frames ended so far.
+    pub(crate) fn frame_count(&self) -> Rc<Cell<usize>> {
+        self.frame_count.clone()
+    }
+
+    /// Returns a handle that reports whether a frame has been begun and not yet ended.
+    pub(crate) fn in_frame(&self) -> Rc<Cell<bool>> {
+        self.in_frame.clone()
+    }
+
+    fn snapshot(&self) -> String {
+        self.screen
+            .iter()
+            // the terminal draws double-width characters across their continuation cell
+            .map(|row| {
+                row.iter()
+                    .filter(|c| **c != WIDE_CONTINUATION)
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl Renderer for TestRenderer {
+    fn size_hint(&self) -> Result<(u16, u16)> {
+        Ok((self.width as u16, self.height as u16))
+    }
+
+    fn render(&mut self, c: &Canvas) -> Result<()> {
+        for stack in c.get_changed() {
+            let output = match stack.content() {
+                Some(c) => c,
+                None => continue,
+            };
+            let (x, y) = stack.coordinates();
+            if let Some(cell) = self.screen.get_mut(y).and_then(|row| row.get_mut(x)) {
+                *cell = output;
+            }
+        }
+        let frame = self.snapshot();
+        self.frames.borrow_mut().push(frame);
+        Ok(())
+    }
+
+    fn clear(&mut self, _c: &Canvas) -> Result<()> {
+        for row in self.screen.iter_mut() {
+            row.fill(' ');
+        }
+        Ok(())
+    }
+
+    fn repaint(&mut self, c: &Canvas) -> Result<()> {
+        // everything is drawn below, whatever changed
+        let _ = c.get_changed();
+        c.for_each_row(|y, cells| {
+            if let Some(row) = self.screen.get_mut(y) {
+                for (screen, cell) in row.iter_mut().zip(cells) {
+                    if let Some(content) = cell.content() {
+                        *screen = content;
+                    }
+                }
+            }
+            Ok(())
+        })?;
+        let frame = self.snapshot();
+        self.frames.borrow_mut().push(frame);
+        Ok(())
+    }
+
+    fn begin_frame(&mut self) -> Result<()> {
+        assert!(
+            !self.in_frame.replace(true),
+            "a frame was begun inside another"
+        );
+        Ok(())
+    }
+
+    fn end_frame(&mut self) -> Result<()> {
+        assert!(
+            self.in_frame.replace(false),
+            "a frame was ended that wasn't begun"
+        );
+        self.frame_count.set(self.frame_count.get() + 1);
+        Ok(())
+    }
+
+    fn recover(&mut self) {
+        self.recovered.set(true);
+    }
+}
+
+/// An EventSource that replays a fixed sequence of events and then asks to quit, unless it is
+/// only polled, in which case it has nothing more to report.
+pub(crate) struct MockEventSource {
+    events: RefCell<VecDeque<Event>>,
+}
+
+impl MockEventSource {
+    pub(crate) fn new(events: impl IntoIterator<Item = Event>) -> Self {
+        Self {
+            events: RefCell::new(events.into_iter().collect()),
+        }
+    }
+}
+
+impl EventSource for MockEventSource {
+    fn next_event(&self) -> Result<Event> {
+        Ok(self
+            .events
+            .borrow_mut()
+            .pop_front()
+            .unwrap_or(Event::UserInput(UserInput::Quit)))
+    }
+
+    fn poll_event(&self, _timeout: std::time::Duration) -> Result<Option<Event>> {
+        Ok(self.events.borrow_mut().pop_front())
+    }
+}
+
+/// Fails one chosen call made on the terminal, shared by a `FaultyRenderer` and `FaultyEvents` so
+/// that calls through either are told apart and counted.
+#[derive(Clone, Default)]
+pub(crate) struct FaultInjector {
+    calls: Rc<RefCell<Vec<TerminalOperation>>>,
+    fault: Rc<Cell<Option<(TerminalOperation, usize)>>>,
+}
+
+impl FaultInjector {
+    /// Forgets the calls made so far and fails the given call of the given operation from now on,
+    /// counting from 1; None fails nothing.
+    pub(crate) fn arm(&self, fault: Option<(TerminalOperation, usize)>) {
+        self.calls.borrow_mut().clear();
+        self.fault.set(fault);
+    }
+
+    /// Returns the number of calls of the given operation made since the injector was armed.
+    pub(crate) fn calls(&self, operation: TerminalOperation) -> usize {
+        self.calls
+            .borrow()
+            .iter()
+            .filter(|c| **c == operation)
+            .count()
+    }
+
+    fn call(&self, operation: TerminalOperation) -> Result<()> {
+        self.calls.borrow_mut().push(operation);
+        if self.fault.get() == Some((operation, self.calls(operation))) {
+            let message = format!("injected failure {}", operation);
+            return Err(std::io::Error::other(message).into());
+        }
+        Ok(())
+    }
+}
+
+/// A TestRenderer whose calls can be made to fail, see `FaultInjector`, and whose size can be
+/// changed while the game runs.
+pub(crate) struct FaultyRenderer {
+    inner: TestRenderer,
+    faults: FaultInjector,
+    size: Rc<Cell<(u16, u16)>>,
+    recoveries: Rc<Cell<usize>>,
+}
+
+impl FaultyRenderer {
+    pub(crate) fn new(inner: TestRenderer, faults: FaultInjector) -> Self {
+        let size = (inner.width as u16, inner.height as u16);
+        Self {
+            inner,
+            faults,
+            size: Rc::new(Cell::new(size)),
+            recoveries: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Returns a handle to the size the renderer reports; see `FaultyEvents::resizing`.
+    pub(crate) fn size(&self) -> Rc<Cell<(u16, u16)>> {
+        self.size.clone()
+    }
+
+    /// Returns a handle that counts the times the terminal would have been restored.
+    pub(crate) fn recoveries(&self) -> Rc<Cell<usize>> {
+        self.recoveries.clone()
+    }
+}
+
+impl Renderer for FaultyRenderer {
+    fn size_hint(&self) -> Result<(u16, u16)> {
+        self.faults.call(TerminalOperation::SizeHint)?;
+        Ok(self.size.get())
+    }
+
+    fn render(&mut self, c: &Canvas) -> Result<()> {
+        self.faults.call(TerminalOperation::Render)?;
+        self.inner.render(c)
+    }
+
+    fn clear(&mut self, c: &Canvas) -> Result<()> {
+        self.faults.call(TerminalOperation::Clear)?;
+        self.inner.clear(c)
+    }
+
+    fn repaint(&mut self, c: &Canvas) -> Result<()> {
+        self.faults.call(TerminalOperation::Render)?;
+        self.inner.repaint(c)
+    }
+
+    fn begin_frame(&mut self) -> Result<()> {
+        self.inner.begin_frame()
+    }
+
+    fn end_frame(&mut self) -> Result<()> {
+        self.inner.end_frame()
+    }
+
+    fn recover(&mut self) {
+        self.recoveries.set(self.recoveries.get() + 1);
+        self.inner.recover();
+    }
+
+    fn set_title(&mut self, title: &str) -> Result<()> {
+        self.faults.call(TerminalOperation::SetTitle)?;
+        self.inner.set_title(title)
+    }
+}
+
+/// A MockEventSource whose calls can be made to fail, see `FaultInjector`.
+pub(crate) struct FaultyEvents {
+    inner: MockEventSource,
+    faults: FaultInjector,
+    // the sizes the terminal takes on with each resize, in turn
+    resizes: RefCell<VecDeque<(u16, u16)>>,
+    size: Option<Rc<Cell<(u16, u16)>>>,
+    delivered: Rc<Cell<usize>>,
+}
+
+impl FaultyEvents {
+    pub(crate) fn new(inner: MockEventSource, faults: FaultInjector) -> Self {
+        Self {
+            inner,
+            faults,
+            resizes: RefCell::new(VecDeque::new()),
+            size: None,
+            delivered: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Resizes the terminal whose size the given handle sets to each of the given sizes in turn,
+    /// just before a resize event is delivered.
+    pub(crate) fn resizing(
+        mut self,
+        size: Rc<Cell<(u16, u16)>>,
+        sizes: impl IntoIterator<Item = (u16, u16)>,
+    ) -> Self {
+        self.size = Some(size);
+        self.resizes = RefCell::new(sizes.into_iter().collect());
+        self
+    }
+
+    /// Returns a handle that counts the events delivered so far.
+    pub(crate) fn delivered(&self) -> Rc<Cell<usize>> {
+        self.delivered.clone()
+    }
+}
+
+impl EventSource for FaultyEvents {
+    fn next_event(&self) -> Result<Event> {
+        self.faults.call(TerminalOperation::NextEvent)?;
+        let event = self.inner.next_event()?;
+        if let (Event::Resize, Some(size)) = (&event, &self.size) {
+            if let Some(next) = self.resizes.borrow_mut().pop_front() {
+                size.set(next);
+            }
+        }
+        self.delivered.set(self.delivered.get() + 1);
+        Ok(event)
+    }
+}
+
+/// A TestRenderer that takes the given time over every render, like a terminal too slow to keep
+/// up with animations.
+pub(crate) struct SlowRenderer {
+    inner: TestRenderer,
+    delay: std::time::Duration,
+}
+
+impl SlowRenderer {
+    pub(crate) fn new(inner: TestRenderer, delay: std::time::Duration) -> Self {
+        Self { inner, delay }
+    }
+}
+
+impl Renderer for SlowRenderer {
+    fn size_hint(&self) -> Result<(u16, u16)> {
+        self.inner.size_hint()
+    }
+
+    fn render(&mut self, c: &Canvas) -> Result<()> {
+        std::thread::sleep(self.delay);
+        self.inner.render(c)
+    }
+
+    fn repaint(&mut self, c: &Canvas) -> Result<()> {
+        std::thread::sleep(self.delay);
+        self.inner.repaint(c)
+    }
+
+    fn begin_frame(&mut self) -> Result<()> {
+        self.inner.begin_frame()
+    }
+
+    fn end_frame(&mut self) -> Result<()> {
+        self.inner.end_frame()
+    }
+
+    fn clear(&mut self, c: &Canvas) -> Result<()> {
+        self.inner.clear(c)
+    }
+
+    fn recover(&mut self) {
+        self.inner.recover();
+    }
+}
@@ -3,13 +3,14 @@ use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex, MutexGuard};
 
 use textwrap::wrap;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use super::canvas::{Canvas, Modifier};
 use super::colors::Rgb;
-use super::drawbuffer::{DrawBufferInner, DrawBufferOwner};
+use super::drawbuffer::{DrawBufferInner, DrawBufferOwner, Owner};
 use super::error::{InnerError, Result};
 use super::geometry::{Position, Rectangle};
-use super::tuxel::Tuxel;
+use super::tuxel::{Tuxel, WIDE_CONTINUATION};
 
 #[derive(Clone, Default, PartialEq)]
 pub(crate) enum HAlignment {
@@ -33,6 +34,19 @@ pub(crate) struct FormatOptions {
     pub valign: VAlignment,
 }
 
+/// What `TextBuffer::write` does with text that won't fit in the buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) enum OverflowPolicy {
+    /// Keep everything written and let `flush` cut off whatever doesn't fit.
+    #[default]
+    Drop,
+    /// Forget the oldest writes to make room for the new one, like a log.
+    Scroll,
+    /// Refuse writes that don't fit.
+    Error,
+}
+
 pub(crate) struct CharBuf {
     text: String,
     fgcolor: Option<Rgb>,
@@ -51,9 +65,10 @@ impl CharBuf {
             .collect()
     }
 
+    /// Returns the number of cells the text takes up, counting double-width characters twice.
     #[inline]
     fn len(&self) -> usize {
-        self.text.len()
+        self.text.width()
     }
 }
 
@@ -63,6 +78,7 @@ pub(crate) struct TextBuffer {
     bufs: Vec<CharBuf>,
     inner: Arc<Mutex<DrawBufferInner>>,
     format: FormatOptions,
+    overflow: OverflowPolicy,
     sender: Sender<Tuxel>,
 }
 
@@ -73,7 +89,12 @@ impl std::fmt::Display for TextBuffer {
 }
 
 impl TextBuffer {
-    pub(crate) fn new(sender: Sender<Tuxel>, rectangle: Rectangle, canvas: Canvas) -> Self {
+    pub(crate) fn new(
+        sender: Sender<Tuxel>,
+        rectangle: Rectangle,
+        canvas: Canvas,
+        owner: Owner,
+    ) -> Self {
         let mut buf: Vec<_> = Vec::with_capacity(rectangle.height());
         for _ in 0..rectangle.height() {
             let row: Vec<Tuxel> = Vec::with_capacity(rectangle.width());
@@ -86,9 +107,15 @@ impl TextBuffer {
                 border: false,
                 buf,
                 modifiers: Vec::new(),
+                border_modifiers: Vec::new(),
+                pulse: None,
+                fade: None,
                 canvas,
+                owner,
+                recolored_for: None,
             })),
             format: FormatOptions::default(),
+            overflow: OverflowPolicy::default(),
             sender,
         }
     }
@@ -106,25 +133,56 @@ impl TextBuffer {
         Ok(())
     }
 
-    pub fn write(&mut self, s: &str, fgcolor: Option<Rgb>, bgcolor: Option<Rgb>) {
-        self.bufs.push(CharBuf {
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn set_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.overflow = policy
+    }
+
+    /// The number of lines of text that fit in the buffer.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn max_lines(&self) -> usize {
+        text_area(&self.lock()).height()
+    }
+
+    /// Adds a line of text to be drawn by the next `flush`. What happens to text that won't fit
+    /// depends on the buffer's `OverflowPolicy`.
+    pub fn write(&mut self, s: &str, fgcolor: Option<Rgb>, bgcolor: Option<Rgb>) -> Result<()> {
+        let charbuf = CharBuf {
             text: s.to_string(),
             fgcolor,
             bgcolor,
-        })
+        };
+        let area = text_area(&self.lock());
+        let needed = charbuf.wrap(area.width()).len();
+        let overflows = |bufs: &[CharBuf]| {
+            let lines: usize = bufs.iter().map(|cb| cb.wrap(area.width()).len()).sum();
+            lines + needed > area.height()
+        };
+        match self.overflow {
+            OverflowPolicy::Drop => (),
+            OverflowPolicy::Scroll => {
+                while !self.bufs.is_empty() && overflows(&self.bufs) {
+                    self.bufs.remove(0);
+                }
+            }
+            OverflowPolicy::Error => {
+                if overflows(&self.bufs) {
+                    return Err(InnerError::TextBufferOverflow {
+                        text: charbuf.text,
+                        lines: area.height(),
+                    }
+                    .into());
+                }
+            }
+        }
+        self.bufs.push(charbuf);
+        Ok(())
     }
 
     pub fn flush(&mut self) -> Result<()> {
         let mut inner = self.lock();
-        let mut rect = inner.rectangle.clone();
-        let mut y_offset = 0;
-        let mut x_offset = 0;
-
-        if inner.border {
-            rect = rect.shrink_by(1, 1);
-            y_offset += 1;
-            x_offset += 1;
-        }
+        let rect = text_area(&inner);
+        let (x_offset, y_offset) = if inner.border { (1, 1) } else { (0, 0) };
 
         if rect.width() == 0 || rect.height() == 0 {
             return Ok(());
@@ -165,7 +223,7 @@ impl TextBuffer {
         for charbuf in bufs_iter {
             let buflen = charbuf.len();
 
-            if y_index > rect.height() {
+            if y_index >= rect.height() + y_offset {
                 // can't write beyond the bottom of the rectangle
                 break;
             }
@@ -183,16 +241,29 @@ impl TextBuffer {
                 HAlignment::Right => width_diff,
             } + x_offset;
 
-            for (offset, c) in charbuf.text.chars().enumerate() {
-                let pos = Position::Coordinates(x_index + offset, y_index);
-                let tuxel = inner.get_tuxel_mut(pos)?;
-                tuxel.set_content(c);
-                if let Some(c) = &charbuf.bgcolor {
-                    tuxel.set_bgcolor(c.clone());
+            let mut offset = 0;
+            for c in charbuf.text.chars() {
+                let width = c.width().unwrap_or(0);
+                // characters without a width of their own, like combining marks, have no cell to
+                // go in
+                if width == 0 {
+                    continue;
                 }
-                if let Some(c) = &charbuf.fgcolor {
-                    tuxel.set_fgcolor(c.clone());
+                let cells = std::iter::once(c)
+                    .chain(std::iter::repeat(WIDE_CONTINUATION))
+                    .take(width);
+                for (cell, content) in cells.enumerate() {
+                    let pos = Position::Coordinates(x_index + offset + cell, y_index);
+                    let tuxel = inner.get_tuxel_mut(pos)?;
+                    tuxel.set_content(content);
+                    if let Some(c) = &charbuf.bgcolor {
+                        tuxel.set_bgcolor(c.clone());
+                    }
+                    if let Some(c) = &charbuf.fgcolor {
+                        tuxel.set_fgcolor(c.clone());
+                    }
                 }
+                offset += width;
             }
 
             y_index += 1;
@@ -202,6 +273,15 @@ impl TextBuffer {
     }
 }
 
+/// The part of the buffer that text goes in: all of it, less the border if it has one.
+fn text_area(inner: &DrawBufferInner) -> Rectangle {
+    if inner.border {
+        inner.rectangle.shrink_by(1, 1)
+    } else {
+        inner.rectangle.clone()
+    }
+}
+
 #[cfg(test)]
 impl TextBuffer {
     pub(crate) fn set_sender(&mut self, sender: Sender<Tuxel>) {
@@ -517,7 +597,7 @@ mod test {
         };
         let rect = Rectangle(Idx(0, 0, 0), bounds);
         let canvas = Canvas::new(20, 20);
-        let mut tbuf = canvas.get_text_buffer(rect.clone())?;
+        let mut tbuf = canvas.get_text_buffer(rect.clone(), Owner::Named("test"))?;
 
         if let Some(fo) = fo {
             tbuf.format(fo);
@@ -532,7 +612,7 @@ mod test {
         }
 
         tbuf.fill(' ')?;
-        tbuf.write(text, None, None);
+        tbuf.write(text, None, None)?;
         tbuf.flush()?;
 
         let rect = (&tbuf as &dyn DrawBufferOwner).rectangle();
@@ -564,4 +644,121 @@ mod test {
 
         Ok(())
     }
+
+    #[rstest]
+    #[case::narrow("ab", "    ab    ")]
+    #[case::wide("\u{1f418}", "    \u{1f418}\u{0}    ")]
+    #[case::wide_and_narrow("\u{1f418}1", "    \u{1f418}\u{0}1   ")]
+    #[case::two_wide("\u{1f418}\u{1f418}", "   \u{1f418}\u{0}\u{1f418}\u{0}   ")]
+    fn flush_centers_wide_characters_by_their_width(
+        #[case] text: &str,
+        #[case] expected: &str,
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let rect = Rectangle(Idx(0, 0, 0), Bounds2D(10, 1));
+        let canvas = Canvas::new(20, 20);
+        let mut tbuf = canvas.get_text_buffer(rect, Owner::Named("test"))?;
+        tbuf.fill(' ')?;
+        tbuf.write(text, None, None)?;
+        tbuf.flush()?;
+
+        let inner = tbuf.lock();
+        let actual: String = (0..10)
+            .map(|x| {
+                inner
+                    .get_tuxel(Position::Coordinates(x, 0))
+                    .map(|t| t.content())
+            })
+            .collect::<Result<_>>()?;
+        assert_eq!(actual, expected);
+        Ok(())
+    }
+
+    fn rows(tbuf: &TextBuffer) -> Result<Vec<String>> {
+        let inner = tbuf.lock();
+        let rect = inner.rectangle.clone();
+        (0..rect.height())
+            .map(|y| {
+                (0..rect.width())
+                    .map(|x| {
+                        inner
+                            .get_tuxel(Position::Coordinates(x, y))
+                            .map(|t| t.content())
+                    })
+                    .collect::<Result<String>>()
+                    .map(|row| row.trim_end().to_string())
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case::no_border(Border::Off, 5)]
+    #[case::border(Border::On, 3)]
+    fn max_lines_leaves_out_the_border(
+        #[case] border: Border,
+        #[case] expected: usize,
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let canvas = Canvas::new(20, 20);
+        let mut tbuf = canvas.get_text_buffer(
+            Rectangle(Idx(0, 0, 0), Bounds2D(10, 5)),
+            Owner::Named("test"),
+        )?;
+        if let Border::On = border {
+            tbuf.draw_border()?;
+        }
+        assert_eq!(tbuf.max_lines(), expected);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::drop(OverflowPolicy::Drop, 10, &["line 0", "line 1", "line 2", "line 3", "line 4"])]
+    #[case::scroll(OverflowPolicy::Scroll, 10, &["line 5", "line 6", "line 7", "line 8", "line 9"])]
+    #[case::error(OverflowPolicy::Error, 5, &["line 0", "line 1", "line 2", "line 3", "line 4"])]
+    fn overflow_policy_decides_what_is_kept(
+        #[case] policy: OverflowPolicy,
+        #[case] accepted: usize,
+        #[case] expected: &[&str],
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let canvas = Canvas::new(20, 20);
+        let mut tbuf = canvas.get_text_buffer(
+            Rectangle(Idx(0, 0, 0), Bounds2D(10, 5)),
+            Owner::Named("test"),
+        )?;
+        tbuf.set_overflow_policy(policy);
+        tbuf.format(FormatOptions {
+            halign: HAlignment::Left,
+            valign: VAlignment::Top,
+        });
+        tbuf.fill(' ')?;
+        let written = (0..10)
+            .filter(|n| tbuf.write(&format!("line {}", n), None, None).is_ok())
+            .count();
+        assert_eq!(written, accepted);
+        tbuf.flush()?;
+        assert_eq!(rows(&tbuf)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn scroll_makes_room_for_wrapped_lines() -> std::result::Result<(), Box<dyn std::error::Error>>
+    {
+        let canvas = Canvas::new(20, 20);
+        let mut tbuf = canvas.get_text_buffer(
+            Rectangle(Idx(0, 0, 0), Bounds2D(10, 3)),
+            Owner::Named("test"),
+        )?;
+        tbuf.set_overflow_policy(OverflowPolicy::Scroll);
+        tbuf.format(FormatOptions {
+            halign: HAlignment::Left,
+            valign: VAlignment::Top,
+        });
+        tbuf.fill(' ')?;
+        for line in ["one", "two", "three"] {
+            tbuf.write(line, None, None)?;
+        }
+        // wraps onto two lines, so both of the oldest lines have to go
+        tbuf.write("four five six", None, None)?;
+        tbuf.flush()?;
+        assert_eq!(rows(&tbuf)?, ["three", "four five", "six"]);
+        Ok(())
+    }
 }
@@ -1,35 +1,36 @@
-use std::sync::mpsc::SyncSender;
-
 use super::colors::Rgb;
+use super::dirty::DirtySet;
 use super::geometry::Idx;
 
+/// Content of the cell covered by the right half of a double-width character. Renderers skip it so
+/// that the terminal is left to draw the character across both cells.
+pub(crate) const WIDE_CONTINUATION: char = '\u{0}';
+
 pub(crate) struct Tuxel {
     active: bool,
     content: char,
     idx: Idx,
-    idx_sender: SyncSender<Idx>,
+    changes: DirtySet,
     fgcolor: Option<Rgb>,
     bgcolor: Option<Rgb>,
 }
 
 impl Tuxel {
-    pub(crate) fn new(idx: Idx, idx_sender: SyncSender<Idx>) -> Self {
+    pub(crate) fn new(idx: Idx, changes: DirtySet) -> Self {
         Tuxel {
             active: false,
             content: '-',
             fgcolor: None,
             bgcolor: None,
             idx,
-            idx_sender,
+            changes,
         }
     }
 
     pub(crate) fn set_content(&mut self, c: char) {
         self.active = true;
         self.content = c;
-        self.idx_sender
-            .send(self.idx.clone())
-            .expect("idx sender has a big buffer, it shouldn't fail");
+        self.changes.mark(&self.idx);
     }
 
     pub(crate) fn set_bgcolor(&mut self, color: Rgb) {
@@ -43,9 +44,12 @@ impl Tuxel {
     pub(crate) fn clear(&mut self) {
         self.active = false;
         self.content = ' ';
-        self.idx_sender
-            .send(self.idx.clone())
-            .expect("idx sender has a big buffer, it shouldn't fail");
+        self.changes.mark(&self.idx);
+    }
+
+    /// Reports the tuxel as changed without changing it, eg after its owner's colors changed.
+    pub(crate) fn touch(&self) {
+        self.changes.mark(&self.idx);
     }
 
     pub(crate) fn active(&self) -> bool {
@@ -62,9 +66,7 @@ impl Tuxel {
 
     pub(crate) fn set_idx(&mut self, idx: &Idx) {
         self.idx = idx.clone();
-        self.idx_sender
-            .send(self.idx.clone())
-            .expect("idx sender has a big buffer, it shouldn't fail");
+        self.changes.mark(&self.idx);
     }
 
     pub(crate) fn colors(&self) -> (Option<Rgb>, Option<Rgb>) {
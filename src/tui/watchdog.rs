@@ -0,0 +1,431 @@
+use std::io::Write;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Weak};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How long the terminal may take to accept a frame before animations are turned off.
+pub(crate) const SOFT_DEADLINE: Duration = Duration::from_secs(2);
+
+/// How many soft deadlines the terminal may take to accept a frame before the game gives up on it.
+const HARD_DEADLINE_FACTOR: u32 = 5;
+
+/// How often the monitor thread checks on the terminal.
+const MONITOR_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How the terminal is keeping up with the frames written to it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Health {
+    Healthy,
+    /// A frame took longer than the soft deadline to go through. The game should stop animating;
+    /// it stays degraded for the rest of the session so that animations don't flap on and off
+    /// over a connection that is merely slow.
+    Degraded,
+    /// Nothing has gone through for longer than the hard deadline.
+    Stuck,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Deadlines {
+    pub(crate) soft: Duration,
+    pub(crate) hard: Duration,
+}
+
+impl Default for Deadlines {
+    fn default() -> Self {
+        Self::from_soft(SOFT_DEADLINE)
+    }
+}
+
+impl Deadlines {
+    /// Deadlines with the given soft deadline and a hard deadline a few times longer.
+    pub(crate) fn from_soft(soft: Duration) -> Self {
+        Self {
+            soft,
+            hard: soft * HARD_DEADLINE_FACTOR,
+        }
+    }
+}
+
+/// Tracks how long the oldest frame not yet through to the terminal has been waiting. Times are
+/// passed in rather than read so that the transitions can be tested without sleeping.
+#[derive(Debug)]
+struct Watchdog {
+    deadlines: Deadlines,
+    // when the writer last made progress while frames were waiting, if any are
+    waiting_since: Option<Instant>,
+    degraded: bool,
+}
+
+impl Watchdog {
+    fn new(deadlines: Deadlines) -> Self {
+        Self {
+            deadlines,
+            waiting_since: None,
+            degraded: false,
+        }
+    }
+
+    /// A frame was handed to the writer.
+    fn queued(&mut self, now: Instant) {
+        self.waiting_since.get_or_insert(now);
+    }
+
+    /// The writer got some frames through; `drained` tells whether any are still waiting.
+    fn progressed(&mut self, now: Instant, drained: bool) {
+        self.waiting_since = if drained { None } else { Some(now) };
+    }
+
+    fn health(&mut self, now: Instant) -> Health {
+        let waited = self
+            .waiting_since
+            .map_or(Duration::ZERO, |since| now.saturating_duration_since(since));
+        if waited >= self.deadlines.hard {
+            return Health::Stuck;
+        }
+        self.degraded |= waited >= self.deadlines.soft;
+        if self.degraded {
+            Health::Degraded
+        } else {
+            Health::Healthy
+        }
+    }
+}
+
+/// A shared view of how the terminal is keeping up, for the writer to report progress to and for
+/// the game to check before animating.
+#[derive(Clone, Debug)]
+pub(crate) struct WatchdogHandle {
+    watchdog: Arc<Mutex<Watchdog>>,
+}
+
+impl WatchdogHandle {
+    pub(crate) fn new(deadlines: Deadlines) -> Self {
+        Self {
+            watchdog: Arc::new(Mutex::new(Watchdog::new(deadlines))),
+        }
+    }
+
+    pub(crate) fn health(&self) -> Health {
+        self.lock().health(Instant::now())
+    }
+
+    /// Returns true if frames are going through too slowly to animate.
+    pub(crate) fn is_degraded(&self) -> bool {
+        self.health() != Health::Healthy
+    }
+
+    fn queued(&self) {
+        self.lock().queued(Instant::now());
+    }
+
+    fn progressed(&self, drained: bool) {
+        self.lock().progressed(Instant::now(), drained);
+    }
+
+    /// Checks on the terminal from a thread of its own, calling `on_stuck` once if it gets stuck.
+    /// The thread stops once that happens or every other handle has been dropped.
+    pub(crate) fn monitor(&self, on_stuck: impl FnOnce() + Send + 'static) -> JoinHandle<()> {
+        self.monitor_every(MONITOR_INTERVAL, on_stuck)
+    }
+
+    fn monitor_every(
+        &self,
+        interval: Duration,
+        on_stuck: impl FnOnce() + Send + 'static,
+    ) -> JoinHandle<()> {
+        let watchdog = Arc::downgrade(&self.watchdog);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            match health(&watchdog) {
+                Some(Health::Stuck) => {
+                    log::error!("the terminal stopped accepting output");
+                    on_stuck();
+                    return;
+                }
+                Some(_) => continue,
+                None => return,
+            }
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Watchdog> {
+        // the writer locks it as it's dropped, which mustn't panic
+        self.watchdog.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn health(watchdog: &Weak<Mutex<Watchdog>>) -> Option<Health> {
+    let watchdog = watchdog.upgrade()?;
+    let health = watchdog
+        .lock()
+        .expect("watchdog lock should not be poisoned")
+        .health(Instant::now());
+    Some(health)
+}
+
+#[derive(Default)]
+struct Pending {
+    bytes: Vec<u8>,
+    closed: bool,
+    // set by the worker once it stops, whether because it was closed or failed to write
+    finished: bool,
+}
+
+/// A writer that hands everything written to it to a thread of its own, so that a terminal that
+/// stops accepting output, eg over a congested ssh connection, can't block the game. Frames are
+/// queued on every flush and whatever has piled up while the thread was busy goes out in one
+/// write, reporting progress to the watchdog as it does.
+pub(crate) struct WatchedWriter {
+    pending: Arc<(Mutex<Pending>, Condvar)>,
+    watchdog: WatchdogHandle,
+    written: Vec<u8>,
+}
+
+impl WatchedWriter {
+    pub(crate) fn new<W: Write + Send + 'static>(inner: W, watchdog: WatchdogHandle) -> Self {
+        let pending = Arc::new((Mutex::new(Pending::default()), Condvar::new()));
+        let worker_pending = pending.clone();
+        let worker_watchdog = watchdog.clone();
+        std::thread::spawn(move || {
+            drain(inner, &worker_pending, worker_watchdog);
+            let (pending, finished) = &*worker_pending;
+            if let Ok(mut pending) = pending.lock() {
+                pending.finished = true;
+            }
+            finished.notify_all();
+        });
+        Self {
+            pending,
+            watchdog,
+            written: Vec::new(),
+        }
+    }
+}
+
+impl Write for WatchedWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.written.is_empty() {
+            return Ok(());
+        }
+        let (pending, wakeup) = &*self.pending;
+        let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.bytes.append(&mut self.written);
+        self.watchdog.queued();
+        wakeup.notify_one();
+        Ok(())
+    }
+}
+
+impl Drop for WatchedWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+        let (pending, wakeup) = &*self.pending;
+        let mut pending = match pending.lock() {
+            Ok(pending) => pending,
+            Err(_) => return,
+        };
+        pending.closed = true;
+        wakeup.notify_all();
+        // give whatever was written last, usually the escape sequences restoring the terminal, a
+        // chance to go out before the program exits, but don't wait on a terminal that is stuck
+        let soft = self.watchdog.lock().deadlines.soft;
+        let _ = wakeup.wait_timeout_while(pending, soft, |pending| !pending.finished);
+    }
+}
+
+fn drain<W: Write>(mut inner: W, pending: &(Mutex<Pending>, Condvar), watchdog: WatchdogHandle) {
+    let (pending, wakeup) = pending;
+    loop {
+        let bytes = {
+            let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+            while pending.bytes.is_empty() && !pending.closed {
+                pending = wakeup
+                    .wait(pending)
+                    .expect("writer lock should not be poisoned");
+            }
+            if pending.bytes.is_empty() {
+                return;
+            }
+            std::mem::take(&mut pending.bytes)
+        };
+        if let Err(e) = inner.write_all(&bytes).and_then(|_| inner.flush()) {
+            log::error!("unable to write to the terminal: {}", e);
+            return;
+        }
+        let drained = pending
+            .lock()
+            .expect("writer lock should not be poisoned")
+            .bytes
+            .is_empty();
+        watchdog.progressed(drained);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::{channel, Receiver, Sender};
+
+    use rstest::*;
+
+    use super::*;
+
+    fn deadlines() -> Deadlines {
+        Deadlines::from_soft(Duration::from_secs(2))
+    }
+
+    #[test]
+    fn hard_deadline_is_a_multiple_of_the_soft_one() {
+        assert_eq!(Deadlines::default().soft, SOFT_DEADLINE);
+        assert_eq!(Deadlines::default().hard, Duration::from_secs(10));
+    }
+
+    #[rstest]
+    #[case::nothing_waiting(None, Health::Healthy)]
+    #[case::just_queued(Some(0), Health::Healthy)]
+    #[case::under_soft(Some(1999), Health::Healthy)]
+    #[case::at_soft(Some(2000), Health::Degraded)]
+    #[case::under_hard(Some(9999), Health::Degraded)]
+    #[case::at_hard(Some(10000), Health::Stuck)]
+    fn health_follows_the_deadlines(#[case] waited_ms: Option<u64>, #[case] expected: Health) {
+        let start = Instant::now();
+        let mut watchdog = Watchdog::new(deadlines());
+        let now = match waited_ms {
+            Some(ms) => {
+                watchdog.queued(start);
+                start + Duration::from_millis(ms)
+            }
+            None => start + Duration::from_secs(60),
+        };
+        assert_eq!(watchdog.health(now), expected);
+    }
+
+    #[test]
+    fn degradation_outlasts_the_slow_frame() {
+        let start = Instant::now();
+        let mut watchdog = Watchdog::new(deadlines());
+        watchdog.queued(start);
+        assert_eq!(watchdog.health(start + deadlines().soft), Health::Degraded);
+        watchdog.progressed(start + deadlines().soft, true);
+        assert_eq!(watchdog.health(start + deadlines().hard), Health::Degraded);
+    }
+
+    #[test]
+    fn progress_resets_the_wait() {
+        let start = Instant::now();
+        let mut watchdog = Watchdog::new(deadlines());
+        watchdog.queued(start);
+        let later = start + Duration::from_secs(1);
+        // frames still waiting behind the ones that went through only count from now on
+        watchdog.progressed(later, false);
+        watchdog.queued(later + Duration::from_secs(1));
+        assert_eq!(watchdog.health(start + deadlines().soft), Health::Healthy);
+        assert_eq!(watchdog.health(later + deadlines().soft), Health::Degraded);
+    }
+
+    /// A writer that blocks until told how many writes to let through.
+    struct BlockingWriter {
+        written: Sender<Vec<u8>>,
+        permits: Receiver<()>,
+    }
+
+    impl Write for BlockingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let _ = self.permits.recv();
+            let _ = self.written.send(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn blocking_writer() -> (BlockingWriter, Receiver<Vec<u8>>, Sender<()>) {
+        let (written, output) = channel();
+        let (permit, permits) = channel();
+        (BlockingWriter { written, permits }, output, permit)
+    }
+
+    #[test]
+    fn writes_never_block_the_caller() {
+        let (inner, output, permit) = blocking_writer();
+        let watchdog = WatchdogHandle::new(Deadlines::from_soft(Duration::from_secs(60)));
+        let mut writer = WatchedWriter::new(inner, watchdog.clone());
+        for frame in ["a", "b", "c"] {
+            write!(writer, "{}", frame).expect("writes should be queued");
+            writer.flush().expect("flushes should be queued");
+        }
+        assert_eq!(watchdog.health(), Health::Healthy);
+
+        // whatever piled up while the first write was blocked goes out together
+        permit.send(()).expect("the writer should be running");
+        permit.send(()).expect("the writer should be running");
+        let mut received = Vec::new();
+        while received.len() < 3 {
+            received.extend(output.recv().expect("frames should be written"));
+        }
+        assert_eq!(received, b"abc");
+    }
+
+    #[test]
+    fn dropping_the_writer_lets_the_last_frame_out() {
+        let (inner, output, permit) = blocking_writer();
+        let watchdog = WatchdogHandle::new(Deadlines::from_soft(Duration::from_secs(60)));
+        let mut writer = WatchedWriter::new(inner, watchdog);
+        write!(writer, "bye").expect("writes should be queued");
+        permit.send(()).expect("the writer should be running");
+        drop(writer);
+        assert_eq!(output.try_recv().expect("the frame should be out"), b"bye");
+    }
+
+    #[test]
+    fn dropping_a_stuck_writer_gives_up() {
+        let (inner, output, _permit) = blocking_writer();
+        let watchdog = WatchdogHandle::new(Deadlines::from_soft(Duration::from_millis(20)));
+        let mut writer = WatchedWriter::new(inner, watchdog);
+        write!(writer, "bye").expect("writes should be queued");
+        drop(writer);
+        assert!(output.try_recv().is_err());
+    }
+
+    #[test]
+    fn stuck_writer_degrades_then_recovers_once() {
+        let (inner, _output, _permit) = blocking_writer();
+        let watchdog = WatchdogHandle::new(Deadlines {
+            soft: Duration::from_millis(20),
+            hard: Duration::from_millis(200),
+        });
+        let mut writer = WatchedWriter::new(inner, watchdog.clone());
+        let recovered = Arc::new(AtomicUsize::new(0));
+        let on_stuck = recovered.clone();
+        let monitor = watchdog.monitor_every(Duration::from_millis(5), move || {
+            on_stuck.fetch_add(1, Ordering::SeqCst);
+        });
+
+        write!(writer, "frame").expect("writes should be queued");
+        writer.flush().expect("flushes should be queued");
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(watchdog.health(), Health::Degraded);
+        assert_eq!(recovered.load(Ordering::SeqCst), 0);
+
+        monitor.join().expect("the monitor should not panic");
+        assert_eq!(watchdog.health(), Health::Stuck);
+        assert_eq!(recovered.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn monitor_stops_with_the_last_handle() {
+        let watchdog = WatchdogHandle::new(deadlines());
+        let monitor = watchdog.monitor_every(Duration::from_millis(5), || {
+            panic!("nothing was written so nothing can be stuck")
+        });
+        drop(watchdog);
+        monitor.join().expect("the monitor should stop quietly");
+    }
+}
@@ -1,31 +1,83 @@
 use std::collections::HashMap;
-use std::sync::OnceLock;
-
-use palette::{FromColor, Lch, Srgb};
-use rand::thread_rng;
-
-use crate::engine::board::Board;
+use std::io::stdout;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use rand::{thread_rng, Rng};
+
+use crate::engine::board::{Board, BoardConfig, MoveOutcome, TakenMove};
+use crate::engine::highscore::HighScore;
+use crate::engine::powerup::MERGES_PER_POWER_UP;
+use crate::engine::practice::Profile;
 use crate::engine::round::Idx as BoardIdx;
-use crate::engine::round::{AnimationHint, Hint};
-
-use super::error::{Error, Result};
+use crate::engine::round::{
+    display_value, AnimationHint, Card, Hint, RewindHint, RewindPlan, Round, Score, WINNING_CARD,
+};
+use crate::engine::strategy::Strategy;
+
+use super::error::{Error, Result, TerminalContext};
+use crate::bell::{Notification, VisualBell};
+use crate::config::GameConfig;
+use crate::export::{FrameDirectory, FrameExportRenderer, TeeRenderer};
+use crate::frametimes::{FrameSample, FrameTimer};
+use crate::milestones::{self, Milestones};
+use crate::outlook::Outlook;
+use crate::packs::{BuiltinPack, LabelPack, PackChoice};
+use crate::paths;
+use crate::persist::{FileSink, FrameSink, PersistEvent, PersistenceHandle, Sinks};
+use crate::prefs::Preferences;
+use crate::quality::{AdaptiveQuality, AnimationSpeed, EasingFn, QualityLevel};
+use crate::scripts::{GameSummary, Scripts};
+use crate::session::Session;
+use crate::startup::{self, RunPlan, Ttys};
+use crate::themes::{BuiltinTheme, Theme};
 use crate::tui::canvas::{Canvas, Modifier};
-use crate::tui::drawbuffer::{DrawBuffer, DrawBufferOwner};
-use crate::tui::error::InnerError as TuiError;
+use crate::tui::capabilities::SyncMode;
+use crate::tui::colors::Rgb;
+use crate::tui::crossterm::{emergency_recover, Crossterm, CrosstermEvents};
+use crate::tui::drawbuffer::{DrawBuffer, DrawBufferOwner, Owner, PulseHandle};
+use crate::tui::error::{InnerError, TuiError};
 use crate::tui::events::{Event, EventSource, UserInput};
 use crate::tui::geometry::{Bounds2D, Direction, Idx, Rectangle};
-use crate::tui::renderer::Renderer;
+use crate::tui::keymap::Keymap;
+use crate::tui::renderer::{Renderer, TerminalOperation};
 use crate::tui::textbuffer::{FormatOptions, HAlignment, TextBuffer, VAlignment};
+use crate::tui::watchdog::{WatchdogHandle, WatchedWriter};
+use editor::{Editor, EditorView};
+use policy::{input_policy, InputPolicy};
+
+pub(crate) mod editor;
+pub(crate) mod mirror;
+pub(crate) mod policy;
 
 /// TUI representation of a 2048 game board.
 struct Tui48Board {
     canvas: Canvas,
     board: DrawBuffer,
     score: TextBuffer,
+    high_score: TextBuffer,
+    moves: TextBuffer,
+    outlook_rectangle: Option<Rectangle>,
+    outlook: Option<TextBuffer>,
+    pressure: Option<TextBuffer>,
+    max_tile: Option<TextBuffer>,
+    power_ups: Option<TextBuffer>,
+    seed: Option<TextBuffer>,
+    merge_markers: Vec<DrawBuffer>,
     slots: Vec<Vec<Slot>>,
     disappearing_slots: Vec<Slot>,
+    // whether tiles merged away fade out rather than staying put until the animation is over
+    fade_merged: bool,
     moving_slots: Vec<Slot>,
     done_slots: HashMap<BoardIdx, Slot>,
+    // how many times a tile has come to rest in each slot, for the heatmap
+    tile_occupancy: Vec<Vec<u32>>,
+    labels: Arc<LabelPack>,
+    theme: Arc<Theme>,
+    layout: LayoutSpec,
+    easing: EasingFn,
 }
 
 const BOARD_FIXED_Y_OFFSET: usize = 5;
@@ -35,118 +87,711 @@ const BOARD_X_PADDING: usize = 1;
 const BOARD_Y_PADDING: usize = 1;
 const TILE_HEIGHT: usize = 5;
 const TILE_WIDTH: usize = 6;
+/// The number of cells inside a tile's border that its label can take up.
+pub(crate) const TILE_INTERIOR_WIDTH: usize = TILE_WIDTH - 2;
 const NEW_TILE_HORIZONTAL_OFFSET: usize = 4;
 const NEW_TILE_VERTICAL_OFFSET: usize = 4;
+/// The number of frames a tile merged away takes to fade into the board.
+const DISAPPEARING_FRAMES: usize = 3;
+
+/// The color of the board behind the tiles.
+fn board_background() -> Rgb {
+    Rgb::new(40, 0, 0).set_lightness(0.2)
+}
+const OUTLOOK_CELLS: usize = 5;
+const TOP_BAR_X: usize = 18;
+const TOP_BAR_Y: usize = 1;
+const TOP_BAR_HEIGHT: usize = 3;
+const TOP_BAR_GAP: usize = 1;
+const FRAME_DELAY: Duration = Duration::from_millis(5);
+const FLASH_FRAME_DELAY: Duration = Duration::from_millis(60);
+const TILE_ENTER_DURATION: Duration = Duration::from_millis(500);
+const CELEBRATION_FRAME_DELAY: Duration = Duration::from_millis(25);
+const CELEBRATION_CYCLES: usize = 2;
+const CELEBRATION_PERIOD: usize = 16;
+/// The number of frames the border takes to move out to a grown board, or back in.
+const GROWTH_FRAMES: usize = 6;
+const MILESTONE_CYCLES: usize = 1;
+const AI_DELAY: Duration = Duration::from_millis(200);
+/// Shown over the board once the game is over; see `Keymap::render` for the placeholders, along
+/// with {score} for the final score and {moves} for the number of moves it took.
+const GAME_OVER_PROMPT: &str = concat!(
+    "game over! {score} points in {moves} moves. ",
+    "press {quit} to quit or {new_game} to start new game"
+);
+/// Shown in place of `GAME_OVER_PROMPT` when there is a move to take back.
+const GAME_OVER_UNDO_PROMPT: &str = concat!(
+    "game over! {score} points in {moves} moves. ",
+    "press {quit} to quit, {new_game} to start new game or {undo} to undo the last move"
+);
+/// Shown over the board once the game is won; see `Keymap::render` for the placeholders.
+const WIN_PROMPT: &str = concat!(
+    "you win! press {continue} or move to keep playing, ",
+    "{new_game} to start new game or {quit} to quit"
+);
+/// Shown along the bottom of the screen while the moves are replayed; see `Keymap::render` for the
+/// placeholders, along with {move} for the number of moves made up to the round shown and {moves}
+/// for the number made in all.
+const REPLAY_PROMPT: &str =
+    "replay: move {move} of {moves}  {left}/{right} step  {replay} back to the game";
+/// Shown over the board when there is a game left from last time; see `Keymap::render` for the
+/// placeholders.
+const RESUME_PROMPT: &str =
+    "resume the game you left last time? press {confirm} to resume or {new_game} for a new game";
+/// Shown along the bottom of the theme preview; see `Keymap::render` for the placeholders, along
+/// with {theme} for the name of the theme shown and {shade} for whether it is light or dark.
+const THEME_PREVIEW_PROMPT: &str =
+    "{left} {theme} ({shade}) {right}  {confirm} to apply, {cancel} to go back";
+/// Shown along the bottom of the board editor; see `Keymap::render` for the placeholders.
+const EDITOR_PROMPT: &str =
+    "{increase}/{decrease} tile  0-9 score  {confirm} play  {write} save  {cancel} back";
+/// Shown along the bottom of the screen once the terminal is found to be too slow to animate.
+const SLOW_TERMINAL_WARNING: &str = "slow terminal \u{2014} animations disabled";
+const COARSER_ANIMATIONS_NOTE: &str = "slow terminal \u{2014} animations simplified";
+/// Shown along the bottom of the screen while the computer plays; see `Keymap::render` for the
+/// placeholders.
+const AUTOPLAY_NOTE: &str = "the computer is playing \u{2014} press {autoplay} to take over";
+// the score breakdown stays up for a second unless a key is pressed, dimming over its last frames
+const SCORE_BREAKDOWN_FRAMES: usize = 20;
+const SCORE_BREAKDOWN_FADE_FRAMES: usize = 5;
+const SCORE_BREAKDOWN_FRAME_DELAY: Duration = Duration::from_millis(50);
+// moves are replayed this many times faster than they were played, so that holding a key down
+// scrubs through them
+const REPLAY_SPEEDUP: u32 = 2;
 
 const BOARD_LAYER_IDX: usize = 2;
 const LOWER_ANIMATION_LAYER_IDX: usize = 3;
 const TILE_LAYER_IDX: usize = 4;
-const UPPER_ANIMATION_LAYER_IDX: usize = 5;
+// a tile merging into another slides beneath it on a layer of its own; on the same layer the two
+// would run into each other once the tile it merges into stops
+const MERGING_ANIMATION_LAYER_IDX: usize = 5;
+const UPPER_ANIMATION_LAYER_IDX: usize = 6;
+const OVERLAY_LAYER_IDX: usize = 7;
+/// The topmost canvas layer, above the board, every animation layer and the overlay, where the
+/// visual bell flashes.
+pub(crate) const FLASH_LAYER_IDX: usize = OVERLAY_LAYER_IDX + 1;
+/// The number of canvas layers the game draws on, which depends on the features it uses: only
+/// the visual bell draws above the overlay layer.
+pub(crate) fn canvas_depth(visual_bell: bool) -> usize {
+    if visual_bell {
+        FLASH_LAYER_IDX + 1
+    } else {
+        OVERLAY_LAYER_IDX + 1
+    }
+}
+// merge markers share the lower animation layer, which sliding tiles only ever use inside the
+// board's border
+const MARKER_LAYER_IDX: usize = LOWER_ANIMATION_LAYER_IDX;
 
-impl Tui48Board {
-    fn new(game: &Board, canvas: &mut Canvas) -> Result<Self> {
-        let (board_rectangle, score_rectangle) = Self::get_dimensions();
+static STRICT_CHECKS: AtomicBool = AtomicBool::new(false);
 
-        let mut board = canvas.get_draw_buffer(board_rectangle)?;
-        board.draw_border()?;
+/// Makes the board panic, in debug builds, whenever its tiles turn out to disagree with the round
+/// they show rather than only logging it. Always on in tests.
+pub(crate) fn set_strict_checks(strict: bool) {
+    STRICT_CHECKS.store(strict, Ordering::Relaxed);
+}
 
-        let mut score = canvas.get_text_buffer(score_rectangle)?;
-        Self::draw_score(&mut score, game.score())?;
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+fn strict_checks() -> bool {
+    cfg!(test) || STRICT_CHECKS.load(Ordering::Relaxed)
+}
 
-        let (width, height) = game.dimensions();
-        let round = game.current();
-        let mut slots = Vec::with_capacity(height);
-        for y in 0..height {
-            let mut row = Vec::with_capacity(width);
-            for x in 0..width {
-                let mut opt = Slot::Empty;
-                let value = round.get(&BoardIdx(x, y));
-                if value > 0 {
-                    let r = Self::tile_rectangle(x, y, TILE_LAYER_IDX);
-                    let mut card_buffer = canvas.get_text_buffer(r)?;
-                    Tui48Board::draw_tile(&mut card_buffer, value)?;
-                    opt = Slot::Static(Tile::new(value, BoardIdx(x, y), card_buffer));
-                }
-                row.push(opt);
-            }
-            slots.push(row);
+/// The boxes shown in the bar above the board.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Indicator {
+    Score,
+    HighScore,
+    Moves,
+    Outlook,
+    Pressure,
+    MaxTile,
+    PowerUps,
+    Seed,
+}
+
+impl Indicator {
+    fn bounds(&self) -> Bounds2D {
+        match self {
+            Indicator::Score => Bounds2D(10, 3),
+            // room for the best score a game can reach
+            Indicator::HighScore => Bounds2D(15, 3),
+            Indicator::Moves => Bounds2D(14, 3),
+            Indicator::Outlook => Bounds2D(OUTLOOK_CELLS, 1),
+            Indicator::Pressure => Bounds2D(6, 3),
+            // room for the widest legend, a four cell label for the largest tile
+            Indicator::MaxTile => Bounds2D(TILE_INTERIOR_WIDTH + 11, 3),
+            // room for the longest name along with the merges made towards it
+            Indicator::PowerUps => Bounds2D(24, 3),
+            // room for the largest seed
+            Indicator::Seed => Bounds2D(28, 3),
         }
+    }
 
-        board.fill(' ')?;
-        board.modify(Modifier::SetBackgroundColor(40, 0, 0));
-        board.modify(Modifier::SetBGLightness(0.2));
-        board.modify(Modifier::SetForegroundColor(25, 50, 75));
-        board.modify(Modifier::SetFGLightness(0.6));
-        Ok(Self {
-            canvas: canvas.clone(),
-            board: board,
-            score,
-            slots,
-            moving_slots: Vec::new(),
-            done_slots: HashMap::new(),
-            disappearing_slots: Vec::new(),
-        })
+    /// Places the indicator with its left edge at `x`, vertically centered in the top bar.
+    fn rectangle_at(&self, x: usize) -> Rectangle {
+        let bounds = self.bounds();
+        let y = TOP_BAR_Y + (TOP_BAR_HEIGHT - bounds.height()) / 2;
+        Rectangle(Idx(x, y, BOARD_LAYER_IDX), bounds)
+    }
+}
+
+/// The indicators making up the score area, in order. They are always shown.
+const SCORE_AREA: [Indicator; 3] = [Indicator::Score, Indicator::HighScore, Indicator::Moves];
+
+/// Lays out the given indicators left to right in the top bar. The score area always comes first
+/// and is always placed; `check_bounds` is responsible for making sure it fits. The remaining
+/// indicators are placed in order until one of them would extend past `canvas_width`, at which
+/// point it and every indicator after it are hidden. That way turning on a feature never moves the
+/// indicators laid out before it.
+fn top_bar_layout(indicators: &[Indicator], canvas_width: usize) -> Vec<(Indicator, Rectangle)> {
+    let mut layout = Vec::with_capacity(indicators.len() + SCORE_AREA.len());
+    let mut x = TOP_BAR_X;
+    for indicator in SCORE_AREA {
+        let rectangle = indicator.rectangle_at(x);
+        x = rectangle.extents().0 + TOP_BAR_GAP;
+        layout.push((indicator, rectangle));
     }
+    for indicator in indicators.iter().filter(|i| !SCORE_AREA.contains(i)) {
+        let rectangle = indicator.rectangle_at(x);
+        if rectangle.extents().0 > canvas_width {
+            break;
+        }
+        x = rectangle.extents().0 + TOP_BAR_GAP;
+        layout.push((*indicator, rectangle));
+    }
+    layout
+}
 
-    fn get_dimensions() -> (Rectangle, Rectangle) {
-        let board_rectangle = Self::board_rectangle();
-        let score_rectangle = Rectangle(Idx(18, 1, BOARD_LAYER_IDX), Bounds2D(10, 3));
+/// Terminal cells are about twice as tall as they are wide, which square tiles make up for unless
+/// told otherwise.
+pub(crate) const DEFAULT_CELL_ASPECT: f64 = 2.0;
+const CELL_ASPECTS: std::ops::RangeInclusive<f64> = 1.0..=4.0;
+
+/// The columns between two boards laid out side by side, with a line down the middle.
+const DIVIDER_WIDTH: usize = 3;
+
+/// The sizes of the board's tiles and the gaps between them. Everything placed relative to the
+/// tiles follows from these, from the board around them to where new tiles slide in from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct LayoutSpec {
+    tile_width: usize,
+    tile_height: usize,
+    // the number of slots across and down the board
+    columns: usize,
+    rows: usize,
+    // how far right of the first column the last one is; the columns in between are spread
+    // evenly, so the gaps between tiles differ by a cell at most
+    columns_span: usize,
+    // how far outside the board new tiles start out, across and down
+    new_tile_offsets: (usize, usize),
+    // how far right everything is moved, for a board laid out beside another
+    origin_x: usize,
+    // how many times as tall as they are wide the cells square tiles are sized for, or None for
+    // classic tiles
+    cell_aspect: Option<f64>,
+}
 
-        (board_rectangle, score_rectangle)
+impl LayoutSpec {
+    /// Tiles as they have always been laid out, which look taller than wide in most fonts.
+    pub(crate) fn classic() -> Self {
+        Self {
+            tile_width: TILE_WIDTH,
+            tile_height: TILE_HEIGHT,
+            columns: 4,
+            rows: 4,
+            columns_span: (TILE_WIDTH + BOARD_X_PADDING) * 3,
+            new_tile_offsets: (NEW_TILE_HORIZONTAL_OFFSET, NEW_TILE_VERTICAL_OFFSET),
+            origin_x: 0,
+            cell_aspect: None,
+        }
     }
 
-    fn check_bounds(&self) -> Result<()> {
-        let board_rectangle_with_tile_start = self
-            .board
-            .rectangle()
-            .expand_by(NEW_TILE_HORIZONTAL_OFFSET, NEW_TILE_VERTICAL_OFFSET);
+    /// Tiles that look square in a font whose cells are `cell_aspect` times as tall as they are
+    /// wide, on a board that looks as wide as it is tall. Tile widths are rounded to whole cells
+    /// and whatever the rounding leaves over goes into the gaps between tiles, so every tile is
+    /// the same width.
+    pub(crate) fn square(cell_aspect: f64) -> Result<Self> {
+        if !CELL_ASPECTS.contains(&cell_aspect) {
+            return Err(Error::InvalidCellAspect {
+                aspect: cell_aspect,
+                range: CELL_ASPECTS,
+            });
+        }
+        let widen = |cells: usize| (cells as f64 * cell_aspect).round() as usize;
+        let layout = Self {
+            tile_width: widen(TILE_HEIGHT),
+            tile_height: TILE_HEIGHT,
+            columns: 4,
+            rows: 4,
+            columns_span: 0,
+            new_tile_offsets: (widen(NEW_TILE_VERTICAL_OFFSET), NEW_TILE_VERTICAL_OFFSET),
+            origin_x: 0,
+            cell_aspect: Some(cell_aspect),
+        };
+        Ok(layout.with_size(BoardConfig::default()))
+    }
 
-        let combined_rectangle = &board_rectangle_with_tile_start + &self.score.rectangle();
-        let (x_extent, y_extent) = combined_rectangle.extents();
+    /// The same tiles laid out on a board of the given size. Square tiles keep the board looking
+    /// as wide as it is tall, slot for slot.
+    pub(crate) fn with_size(self, size: BoardConfig) -> Self {
+        let (columns, rows) = (size.width, size.height);
+        let packed = self.tile_width * columns + BOARD_X_PADDING * (columns - 1);
+        let columns_width = match self.cell_aspect {
+            None => (self.tile_width + BOARD_X_PADDING) * (columns - 1) + self.tile_width,
+            Some(cell_aspect) => {
+                let rows_height = self.tile_height * rows + BOARD_Y_PADDING * (rows - 1);
+                let height = rows_height as f64 * columns as f64 / rows as f64;
+                ((height * cell_aspect).round() as usize).max(packed)
+            }
+        };
+        Self {
+            columns,
+            rows,
+            columns_span: columns_width - self.tile_width,
+            ..self
+        }
+    }
 
-        let (cwidth, cheight) = self.canvas.dimensions();
-        if cwidth < x_extent || cheight < y_extent {
-            return Err(Error::TerminalTooSmall(cwidth, cheight).into());
+    /// The size of the board laid out.
+    pub(crate) fn size(&self) -> BoardConfig {
+        BoardConfig {
+            width: self.columns,
+            height: self.rows,
         }
+    }
 
-        Ok(())
+    /// The same layout moved right of everything this one needs, past a divider, for a second
+    /// board side by side with the first. Laid out this way the two boards never share a cell,
+    /// so they can animate on the same layers at once.
+    pub(crate) fn beside(&self) -> Self {
+        let (width, _) = LayoutRequirements::new(self).need();
+        Self {
+            origin_x: width + DIVIDER_WIDTH,
+            ..*self
+        }
     }
 
-    #[cfg(test)]
-    fn get_minimum_canvas_extents() -> (usize, usize) {
-        let (board_rectangle, score_rectangle) = Self::get_dimensions();
-        let board_rectangle_with_tile_start =
-            board_rectangle.expand_by(NEW_TILE_HORIZONTAL_OFFSET, NEW_TILE_VERTICAL_OFFSET);
+    /// Where the divider between this board and the one laid out beside it is drawn; see
+    /// `beside`.
+    fn divider_x(&self) -> usize {
+        self.origin_x.saturating_sub(DIVIDER_WIDTH / 2 + 1)
+    }
+
+    pub(crate) fn tile_width(&self) -> usize {
+        self.tile_width
+    }
+
+    pub(crate) fn tile_height(&self) -> usize {
+        self.tile_height
+    }
+
+    /// How far outside the board new tiles start out, across and down.
+    fn new_tile_offsets(&self) -> (usize, usize) {
+        self.new_tile_offsets
+    }
+
+    /// The number of frames it takes tiles to enter, moving a cell per frame along each axis in
+    /// turn.
+    fn enter_frames(&self) -> u32 {
+        (self.new_tile_offsets.0 + self.new_tile_offsets.1) as u32
+    }
 
-        let combined_rectangle = &board_rectangle_with_tile_start + &score_rectangle;
+    /// How far right of the first column the given one is, rounded to the nearest cell.
+    fn column_offset(&self, x: usize) -> usize {
+        let gaps = self.columns - 1;
+        (2 * x * self.columns_span + gaps) / (2 * gaps)
+    }
 
-        combined_rectangle.extents()
+    /// Where the board's left edge is. New tiles that start out further left than the classic
+    /// ones push the board right, so that they still start out on the canvas.
+    fn board_x(&self) -> usize {
+        let (across, _) = self.new_tile_offsets;
+        self.origin_x + BOARD_FIXED_X_OFFSET + across.saturating_sub(NEW_TILE_HORIZONTAL_OFFSET)
     }
 
-    fn board_rectangle() -> Rectangle {
-        let x_bound: usize = TILE_WIDTH * 4 + BOARD_FIXED_X_OFFSET + BOARD_BORDER_WIDTH * 2;
-        let y_bound: usize = TILE_HEIGHT * 4 + BOARD_FIXED_Y_OFFSET;
+    /// Lays out the given indicators in the top bar above this board; see `top_bar_layout`.
+    fn top_bar(
+        &self,
+        indicators: &[Indicator],
+        canvas_width: usize,
+    ) -> Vec<(Indicator, Rectangle)> {
+        top_bar_layout(indicators, canvas_width.saturating_sub(self.origin_x))
+            .into_iter()
+            .map(|(indicator, Rectangle(idx, bounds))| {
+                let idx = Idx(idx.x() + self.origin_x, idx.y(), idx.z());
+                (indicator, Rectangle(idx, bounds))
+            })
+            .collect()
+    }
 
+    fn board_rectangle(&self) -> Rectangle {
+        // a border on either side, and a blank column inside it
+        let width = BOARD_BORDER_WIDTH * 4 + self.columns_span + self.tile_width;
+        let height = BOARD_BORDER_WIDTH * 2
+            + self.tile_height * self.rows
+            + BOARD_Y_PADDING * (self.rows - 1);
         Rectangle(
-            Idx(BOARD_FIXED_X_OFFSET, BOARD_FIXED_Y_OFFSET, BOARD_LAYER_IDX),
-            Bounds2D(x_bound, y_bound),
+            Idx(self.board_x(), BOARD_FIXED_Y_OFFSET, BOARD_LAYER_IDX),
+            Bounds2D(width, height),
         )
     }
 
-    fn tile_rectangle(x: usize, y: usize, z: usize) -> Rectangle {
-        let x_offset = BOARD_FIXED_X_OFFSET + BOARD_BORDER_WIDTH * 2;
+    fn tile_rectangle(&self, x: usize, y: usize, z: usize) -> Rectangle {
+        let x_offset = self.board_x() + BOARD_BORDER_WIDTH * 2;
         let y_offset = BOARD_FIXED_Y_OFFSET + BOARD_BORDER_WIDTH;
         let idx = Idx(
-            x_offset + (BOARD_X_PADDING + TILE_WIDTH) * x,
-            y_offset + (BOARD_Y_PADDING + TILE_HEIGHT) * y,
+            x_offset + self.column_offset(x),
+            y_offset + (BOARD_Y_PADDING + self.tile_height) * y,
             z,
         );
-        let bounds = Bounds2D(TILE_WIDTH, TILE_HEIGHT);
-        Rectangle(idx, bounds)
+        Rectangle(idx, Bounds2D(self.tile_width, self.tile_height))
+    }
+
+    /// Returns the column and row of the slot nearest to the given rectangle, the inverse of
+    /// `tile_rectangle` for rectangles that sit exactly on a slot.
+    fn nearest_slot(&self, r: &Rectangle) -> (usize, usize) {
+        let first = self.tile_rectangle(0, 0, r.0.z());
+        let dx = r.0.x().saturating_sub(first.x());
+        let x = (0..self.columns)
+            .min_by_key(|x| self.column_offset(*x).abs_diff(dx))
+            .unwrap_or(0);
+        let y = r.0.y().saturating_sub(first.y()) / (BOARD_Y_PADDING + self.tile_height);
+        (x, y)
+    }
+
+    /// The cells of the lines between the slots of a board with the given number of columns and
+    /// rows, in canvas coordinates, along with what to draw in each. The lines run through the
+    /// gaps between tiles, so they never show through a tile.
+    fn grid_lines(&self, columns: usize, rows: usize) -> Vec<(usize, usize, char)> {
+        let first = self.tile_rectangle(0, 0, BOARD_LAYER_IDX);
+        let (right, bottom) = self
+            .tile_rectangle(columns - 1, rows - 1, BOARD_LAYER_IDX)
+            .extents();
+        // the gap after every tile but the last of its row or column
+        let column_gaps: Vec<usize> = (0..columns - 1)
+            .flat_map(|x| {
+                let end = self.tile_rectangle(x, 0, BOARD_LAYER_IDX).extents().0;
+                end..self.tile_rectangle(x + 1, 0, BOARD_LAYER_IDX).x()
+            })
+            .collect();
+        let row_gaps: Vec<usize> = (0..rows - 1)
+            .flat_map(|y| {
+                let end = self.tile_rectangle(0, y, BOARD_LAYER_IDX).extents().1;
+                end..self.tile_rectangle(0, y + 1, BOARD_LAYER_IDX).y()
+            })
+            .collect();
+        let mut cells = Vec::new();
+        for y in first.y()..bottom {
+            for x in first.x()..right {
+                let c = match (column_gaps.contains(&x), row_gaps.contains(&y)) {
+                    (true, true) => '\u{253c}',
+                    (true, false) => '\u{2502}',
+                    (false, true) => '\u{2500}',
+                    (false, false) => continue,
+                };
+                cells.push((x, y, c));
+            }
+        }
+        cells
+    }
+}
+
+/// The smallest canvas the game can be laid out on: the board with room around it for new tiles
+/// to slide in from, and the score area. Other indicators are hidden when they don't fit (see
+/// `top_bar_layout`), so they never add to the requirements.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct LayoutRequirements {
+    min_width: usize,
+    min_height: usize,
+}
+
+impl LayoutRequirements {
+    fn new(layout: &LayoutSpec) -> Self {
+        let (across, down) = layout.new_tile_offsets();
+        let board_rectangle_with_tile_start = layout.board_rectangle().expand_by(across, down);
+        let score_area = layout.top_bar(&SCORE_AREA, 0);
+
+        let combined_rectangle = &board_rectangle_with_tile_start + &score_area[0].1;
+        let (min_width, min_height) =
+            score_area[1..]
+                .iter()
+                .fold(combined_rectangle.extents(), |(x, y), (_, r)| {
+                    let (rx, ry) = r.extents();
+                    (x.max(rx), y.max(ry))
+                });
+        Self {
+            min_width,
+            min_height,
+        }
+    }
+
+    fn need(&self) -> (usize, usize) {
+        (self.min_width, self.min_height)
+    }
+
+    /// Returns an error carrying both the given size and the required one unless a canvas of the
+    /// given size is big enough.
+    fn check(&self, have: (usize, usize)) -> Result<()> {
+        if have.0 < self.min_width || have.1 < self.min_height {
+            return Err(Error::TerminalTooSmall {
+                have,
+                need: self.need(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Lists the score each merge in the given move contributed, one line per merge in the order the
+/// merges happened, eg `• (1,3): +16`.
+fn score_breakdown(hint: &AnimationHint) -> Vec<String> {
+    hint.hints()
+        .into_iter()
+        .filter_map(|(_, hint)| match hint {
+            Hint::NewValueToIdx(value, to) => Some(format!(
+                "\u{2022} ({},{}): +{}",
+                to.x(),
+                to.y(),
+                display_value(value)
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The score breakdown of the latest move while it's up, fading out a frame at a time.
+struct ScoreBreakdown {
+    overlay: TextBuffer,
+    lines: Vec<String>,
+    frames_remaining: usize,
+}
+
+impl ScoreBreakdown {
+    /// Counts down one frame, dimming the overlay over its last ones. Returns false once it has
+    /// been up for all of them.
+    fn fade(&mut self) -> Result<bool> {
+        self.frames_remaining = self.frames_remaining.saturating_sub(1);
+        if self.frames_remaining == 0 {
+            return Ok(false);
+        }
+        if self.frames_remaining <= SCORE_BREAKDOWN_FADE_FRAMES {
+            let brightness =
+                self.frames_remaining as f32 / (SCORE_BREAKDOWN_FADE_FRAMES + 1) as f32;
+            Tui48Board::draw_score_breakdown(&mut self.overlay, &self.lines, brightness)?;
+        }
+        Ok(true)
+    }
+}
+
+/// Picks a color for the number of empty slots left, going from green through yellow to red as
+/// the board fills up. Thresholds are fractions of the total so they carry over to other board
+/// sizes: more than half empty is green, less than a quarter is red.
+fn pressure_color(empty: usize, total: usize) -> Rgb {
+    if empty * 2 > total {
+        Rgb::new(60, 200, 60)
+    } else if empty * 4 >= total {
+        Rgb::new(220, 200, 40)
+    } else {
+        Rgb::new(220, 40, 40)
+    }
+}
+
+/// The rules a game is played by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Mode {
+    /// The standard game.
+    #[default]
+    Classic,
+    /// Merging tiles earns power-ups, used with the space bar. Its scores are kept apart from
+    /// those of the standard game.
+    Arcade,
+    /// The board starts 3x3 and grows by a row and a column as the score reaches milestones. Its
+    /// scores are kept off the standard game's high score.
+    Growth,
+}
+
+/// Passive assists that point things out on the board without suggesting a move.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Assist {
+    /// Mark the row or column holding the largest pair of equal adjacent tiles.
+    Merges,
+}
+
+impl Tui48Board {
+    fn new(
+        game: &Board,
+        canvas: &mut Canvas,
+        indicators: &[Indicator],
+        layout: LayoutSpec,
+    ) -> Result<Self> {
+        Self::check_bounds(canvas, &layout)?;
+        let board_rectangle = layout.board_rectangle();
+
+        let mut board = canvas.get_draw_buffer(board_rectangle, Owner::Named("board"))?;
+        board.draw_border()?;
+
+        let top_bar = layout.top_bar(indicators, canvas.dimensions().0);
+        let placed = |indicator: Indicator| {
+            top_bar
+                .iter()
+                .find(|(i, _)| *i == indicator)
+                .map(|(_, r)| r.clone())
+        };
+
+        let score_rectangle = placed(Indicator::Score).expect("the score is always laid out");
+        let score = canvas.get_text_buffer(score_rectangle, Owner::Named("score"))?;
+
+        let high_score_rectangle =
+            placed(Indicator::HighScore).expect("the high score is always laid out");
+        let high_score =
+            canvas.get_text_buffer(high_score_rectangle, Owner::Named("high score"))?;
+
+        let moves_rectangle = placed(Indicator::Moves).expect("the move count is always laid out");
+        let moves = canvas.get_text_buffer(moves_rectangle, Owner::Named("moves"))?;
+
+        let pressure = match placed(Indicator::Pressure) {
+            Some(r) => Some(canvas.get_text_buffer(r, Owner::Named("pressure"))?),
+            None => None,
+        };
+
+        let max_tile = match placed(Indicator::MaxTile) {
+            Some(r) => Some(canvas.get_text_buffer(r, Owner::Named("max tile"))?),
+            None => None,
+        };
+
+        let power_ups = match placed(Indicator::PowerUps) {
+            Some(r) => Some(canvas.get_text_buffer(r, Owner::Named("power-ups"))?),
+            None => None,
+        };
+
+        let seed = match placed(Indicator::Seed) {
+            Some(r) => Some(canvas.get_text_buffer(r, Owner::Named("seed"))?),
+            None => None,
+        };
+
+        let labels = Arc::new(LabelPack::builtin(BuiltinPack::Numbers));
+        let theme = default_theme();
+        let slots = Self::new_tiles_from_board(game, canvas, &layout, &labels, &theme)?;
+
+        board.fill(' ')?;
+        let background = board_background();
+        board.modify(Modifier::SetBackgroundColor(
+            background.r(),
+            background.g(),
+            background.b(),
+        ));
+        board.modify(Modifier::SetForegroundColor(25, 50, 75));
+        board.modify(Modifier::SetFGLightness(0.6));
+        board.highlight_border(Rgb::new(130, 170, 210));
+        let mut tui_board = Self {
+            canvas: canvas.clone(),
+            board: board,
+            score,
+            high_score,
+            moves,
+            outlook_rectangle: placed(Indicator::Outlook),
+            outlook: None,
+            pressure,
+            max_tile,
+            power_ups,
+            seed,
+            merge_markers: Vec::new(),
+            slots,
+            moving_slots: Vec::new(),
+            done_slots: HashMap::new(),
+            disappearing_slots: Vec::new(),
+            fade_merged: true,
+            tile_occupancy: vec![vec![0; layout.columns]; layout.rows],
+            labels,
+            theme,
+            layout,
+            easing: EasingFn::Linear,
+        };
+        tui_board.draw_score(game)?;
+        tui_board.update_move_count(game.move_count())?;
+        tui_board.draw_pressure(game)?;
+        tui_board.draw_max_tile(game)?;
+        tui_board.draw_power_ups(game)?;
+        tui_board.draw_seed(game)?;
+        Ok(tui_board)
+    }
+
+    /// Creates a static tile for every occupied slot of the current round.
+    fn new_tiles_from_board(
+        game: &Board,
+        canvas: &mut Canvas,
+        layout: &LayoutSpec,
+        labels: &Arc<LabelPack>,
+        theme: &Arc<Theme>,
+    ) -> Result<Vec<Vec<Slot>>> {
+        let (width, height) = game.dimensions();
+        let round = game.current();
+        let mut slots = Vec::with_capacity(height);
+        for y in 0..height {
+            let mut row = Vec::with_capacity(width);
+            for x in 0..width {
+                let mut opt = Slot::Empty;
+                let value = round.get(&BoardIdx(x, y));
+                if value > 0 {
+                    let r = layout.tile_rectangle(x, y, TILE_LAYER_IDX);
+                    let mut card_buffer = canvas.get_text_buffer(r, Owner::At("tile", x, y))?;
+                    Tui48Board::draw_tile(&mut card_buffer, value, labels, theme)?;
+                    opt = Slot::Static(Tile::new(
+                        value,
+                        BoardIdx(x, y),
+                        card_buffer,
+                        labels.clone(),
+                        theme.clone(),
+                    ));
+                }
+                row.push(opt);
+            }
+            slots.push(row);
+        }
+        Ok(slots)
+    }
+
+    /// Returns the rectangles of the board and of each panel of the score area.
+    #[cfg(test)]
+    fn get_dimensions() -> (Rectangle, Vec<Rectangle>) {
+        let board_rectangle = LayoutSpec::classic().board_rectangle();
+        let score_area = top_bar_layout(&SCORE_AREA, 0)
+            .into_iter()
+            .map(|(_, r)| r)
+            .collect();
+
+        (board_rectangle, score_area)
+    }
+
+    /// Makes sure the canvas is big enough for the layout before any buffer is taken from it, so
+    /// that a terminal that's too small is never left with part of a board drawn on it.
+    fn check_bounds(canvas: &Canvas, layout: &LayoutSpec) -> Result<()> {
+        LayoutRequirements::new(layout).check(canvas.dimensions())
+    }
+
+    /// Draws the lines between the slots, or clears them. They are drawn on the board itself, so
+    /// tiles, including those sliding across them, always cover them.
+    fn set_grid(&mut self, shown: bool) -> Result<()> {
+        let board = self.board.rectangle();
+        let (columns, rows) = (self.slots[0].len(), self.slots.len());
+        let mut transaction = self.board.begin_transaction();
+        for (x, y, c) in self.layout.grid_lines(columns, rows) {
+            let c = if shown { c } else { ' ' };
+            transaction.set_content(x - board.x(), y - board.y(), c);
+        }
+        transaction.commit()?;
+        Ok(())
     }
 
-    fn draw_tile(dbuf: &mut TextBuffer, value: u8) -> Result<()> {
-        let colors = colors_from_value(value);
+    fn draw_tile(
+        dbuf: &mut TextBuffer,
+        card: Card,
+        labels: &LabelPack,
+        theme: &Theme,
+    ) -> Result<()> {
+        let colors = theme.colors(card);
         dbuf.modify(colors.0);
         dbuf.modify(colors.1);
         dbuf.draw_border()?;
@@ -155,104 +800,524 @@ impl Tui48Board {
             halign: HAlignment::Center,
             valign: VAlignment::Middle,
         });
-        dbuf.write(&format!("{}", 2u32.pow(value as u32)), None, None);
+        dbuf.write(&labels.label(card), None, None)?;
         dbuf.flush()?;
         Ok(())
     }
 
-    fn draw_score(dbuf: &mut TextBuffer, value: u32) -> Result<()> {
+    /// Labels tiles with the given pack from now on, relabelling the tiles already on the board.
+    fn set_labels(&mut self, labels: Arc<LabelPack>, game: &Board) -> Result<()> {
+        self.labels = labels;
+        for slot in self.slots.iter_mut().flatten() {
+            if let Slot::Static(tile) = slot {
+                tile.labels = self.labels.clone();
+                tile.draw()?;
+            }
+        }
+        self.draw_max_tile(game)
+    }
+
+    /// Colors every tile with the given theme, including tiles placed later.
+    fn set_theme(&mut self, theme: Arc<Theme>) -> Result<()> {
+        self.theme = theme;
+        for slot in self.slots.iter_mut().flatten() {
+            if let Slot::Static(tile) = slot {
+                tile.theme = self.theme.clone();
+                tile.draw()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Dims every tile on the board, eg to set them apart from an overlay drawn over them.
+    fn dim_tiles(&mut self) {
+        for slot in self.slots.iter_mut().flatten() {
+            if let Slot::Static(tile) = slot {
+                tile.buf.dim();
+            }
+        }
+    }
+
+    /// Shows the game's score and, in the box next to it, the best score ever reached.
+    fn draw_score(&mut self, game: &Board) -> Result<()> {
+        Self::draw_score_value(&mut self.score, game.score())?;
+        let dbuf = &mut self.high_score;
         dbuf.draw_border()?;
         dbuf.clear()?;
-        dbuf.write(&format!("{}", value), None, None);
+        dbuf.write(&format!("Best: {}", game.high_score().best()), None, None)?;
         dbuf.flush()?;
-        dbuf.modify(Modifier::SetBackgroundColor(75, 50, 25));
+        dbuf.modify(Modifier::SetBackgroundColor(25, 75, 50));
         dbuf.modify(Modifier::SetForegroundColor(0, 0, 0));
         dbuf.modify(Modifier::SetFGLightness(0.2));
         dbuf.modify(Modifier::SetBGLightness(0.8));
         Ok(())
     }
 
-    fn get_slot(&mut self, idx: &BoardIdx) -> Result<Slot> {
-        let s = self
-            .slots
-            .get_mut(idx.y())
-            .ok_or(Error::UnableToRetrieveSlot {
-                context: format!("getting row {}", idx.y()),
-            })?
-            .get_mut(idx.x())
-            .ok_or(Error::UnableToRetrieveSlot {
-                context: format!("getting slot at {},{}", idx.x(), idx.y()),
-            })?;
-        let s = s.take();
-        Ok(s)
+    fn draw_score_value(dbuf: &mut TextBuffer, value: u32) -> Result<()> {
+        Self::draw_score_box(dbuf, &format!("{}", value), milestones::accent(value))
     }
 
-    fn put_slot(&mut self, idx: &BoardIdx, slot: Slot) -> Result<()> {
-        let s = self
-            .slots
-            .get_mut(idx.y())
-            .ok_or(Error::UnableToRetrieveSlot {
-                context: format!("getting row {}", idx.y()),
-            })?
-            .get_mut(idx.x())
-            .ok_or(Error::UnableToRetrieveSlot {
-                context: format!("getting slot at {},{}", idx.x(), idx.y()),
-            })?;
-        let _ = s.replace(slot);
-        Ok(())
+    /// Shows the note for the given milestone in the score box in place of the score.
+    fn draw_milestone(dbuf: &mut TextBuffer, milestone: u32) -> Result<()> {
+        let label = milestones::label(milestone);
+        Self::draw_score_box(dbuf, &label, milestones::accent(milestone))
     }
 
-    fn new_sliding_tile(
-        &mut self,
-        to_idx: &BoardIdx,
-        value: u8,
-        direction: &Direction,
-    ) -> Result<SlidingTile> {
-        let db_rectangle = match direction {
-            Direction::Left => {
-                let mut r = Tui48Board::tile_rectangle(3, to_idx.y(), LOWER_ANIMATION_LAYER_IDX);
-                r.0 .0 += NEW_TILE_HORIZONTAL_OFFSET;
-                r
-            }
-            Direction::Right => {
-                let mut r = Tui48Board::tile_rectangle(0, to_idx.y(), LOWER_ANIMATION_LAYER_IDX);
-                r.0 .0 -= NEW_TILE_HORIZONTAL_OFFSET;
-                r
-            }
-            Direction::Up => {
-                let mut r = Tui48Board::tile_rectangle(to_idx.x(), 3, LOWER_ANIMATION_LAYER_IDX);
-                r.0 .1 += NEW_TILE_VERTICAL_OFFSET;
-                r
-            }
-            Direction::Down => {
-                let mut r = Tui48Board::tile_rectangle(to_idx.x(), 0, LOWER_ANIMATION_LAYER_IDX);
-                r.0 .1 -= NEW_TILE_VERTICAL_OFFSET;
-                r
-            }
-        };
-        log::trace!("getting new textbuffer for rectangle {}", db_rectangle);
-        let buf = self.canvas.get_text_buffer(db_rectangle)?;
-        let mut t = Tile::new(value, to_idx.clone(), buf);
-        t.draw()?;
+    fn draw_score_box(dbuf: &mut TextBuffer, text: &str, accent: Modifier) -> Result<()> {
+        dbuf.draw_border()?;
+        dbuf.clear()?;
+        dbuf.write(text, None, None)?;
+        dbuf.flush()?;
+        dbuf.modify(accent);
+        dbuf.modify(Modifier::SetForegroundColor(0, 0, 0));
+        dbuf.modify(Modifier::SetFGLightness(0.2));
+        dbuf.modify(Modifier::SetBGLightness(0.8));
+        Ok(())
+    }
 
-        let rectangle =
-            Tui48Board::tile_rectangle(to_idx.x(), to_idx.y(), LOWER_ANIMATION_LAYER_IDX);
-        let st = SlidingTile::new(t, rectangle, None);
+    /// Brings the score and every panel next to the board up to date with the given game.
+    fn draw_indicators(&mut self, game: &Board) -> Result<()> {
+        self.draw_score(game)?;
+        self.update_move_count(game.move_count())?;
+        self.draw_pressure(game)?;
+        self.draw_max_tile(game)?;
+        self.draw_power_ups(game)?;
+        self.draw_seed(game)
+    }
 
-        Ok(st)
+    /// Shows the given number of moves made in the move count panel.
+    fn update_move_count(&mut self, count: usize) -> Result<()> {
+        let dbuf = &mut self.moves;
+        dbuf.draw_border()?;
+        dbuf.clear()?;
+        dbuf.write(&format!("Moves: {}", count), None, None)?;
+        dbuf.flush()?;
+        dbuf.modify(Modifier::SetBackgroundColor(75, 50, 25));
+        dbuf.modify(Modifier::SetForegroundColor(0, 0, 0));
+        dbuf.modify(Modifier::SetFGLightness(0.2));
+        dbuf.modify(Modifier::SetBGLightness(0.8));
+        Ok(())
     }
 
-    fn setup_animation(&mut self, hints: &AnimationHint) -> Result<()> {
-        log::trace!("setting up animation with hints:\n{0}", hints);
-        for (idx, hint) in hints.hints() {
-            log::trace!("setting up animation for hint {0} -> {1}", idx, hint);
+    /// Draws the number of empty slots left on the board, if there was room for it in the top bar.
+    fn draw_pressure(&mut self, game: &Board) -> Result<()> {
+        let dbuf = match &mut self.pressure {
+            Some(dbuf) => dbuf,
+            None => return Ok(()),
+        };
+        let (width, height) = game.dimensions();
+        let empty = game.empty_count();
+        dbuf.draw_border()?;
+        dbuf.clear()?;
+        dbuf.format(FormatOptions {
+            halign: HAlignment::Center,
+            valign: VAlignment::Middle,
+        });
+        let color = pressure_color(empty, width * height);
+        dbuf.write(&format!("\u{25a2}{}", empty), Some(color), None)?;
+        dbuf.flush()?;
+        dbuf.modify(Modifier::SetBackgroundColor(75, 50, 25));
+        dbuf.modify(Modifier::SetBGLightness(0.8));
+        Ok(())
+    }
+
+    /// Shows which value the label of the largest tile stands for, eg "Ne = 1024".
+    fn draw_max_tile(&mut self, game: &Board) -> Result<()> {
+        let dbuf = match &mut self.max_tile {
+            Some(dbuf) => dbuf,
+            None => return Ok(()),
+        };
+        dbuf.draw_border()?;
+        dbuf.clear()?;
+        dbuf.write(&self.labels.legend(game.current().max_card()), None, None)?;
+        dbuf.flush()?;
+        dbuf.modify(Modifier::SetBackgroundColor(75, 50, 25));
+        dbuf.modify(Modifier::SetBGLightness(0.8));
+        Ok(())
+    }
+
+    /// Shows the seed the game was started from, which plays out the same game for anyone who
+    /// starts one from it and makes the same moves.
+    fn draw_seed(&mut self, game: &Board) -> Result<()> {
+        let (dbuf, seed) = match (&mut self.seed, game.seed()) {
+            (Some(dbuf), Some(seed)) => (dbuf, seed),
+            _ => return Ok(()),
+        };
+        dbuf.draw_border()?;
+        dbuf.clear()?;
+        dbuf.write(&format!("Seed: {}", seed), None, None)?;
+        dbuf.flush()?;
+        dbuf.modify(Modifier::SetBackgroundColor(50, 50, 75));
+        dbuf.modify(Modifier::SetBGLightness(0.8));
+        Ok(())
+    }
+
+    /// Shows where the power-ups of an arcade game stand: the power-up ready to be used, the
+    /// doubling of the next new tile once that power-up has been used, or else the power-up
+    /// earned next along with the merges made towards it.
+    fn draw_power_ups(&mut self, game: &Board) -> Result<()> {
+        let (dbuf, powers) = match (&mut self.power_ups, game.power_ups()) {
+            (Some(dbuf), Some(powers)) => (dbuf, powers),
+            _ => return Ok(()),
+        };
+        let (text, color) = match powers.charged() {
+            Some(power_up) => (
+                format!("\u{26a1} {}", power_up.name()),
+                Rgb::new(240, 210, 60),
+            ),
+            None if powers.doubling() => ("next tile \u{d7}2".to_string(), Rgb::new(240, 210, 60)),
+            None => (
+                format!(
+                    "{} {}/{}",
+                    powers.next().name(),
+                    powers.merges(),
+                    MERGES_PER_POWER_UP
+                ),
+                Rgb::new(20, 20, 20),
+            ),
+        };
+        dbuf.draw_border()?;
+        dbuf.clear()?;
+        dbuf.write(&text, Some(color), None)?;
+        dbuf.flush()?;
+        dbuf.modify(Modifier::SetBackgroundColor(75, 50, 25));
+        dbuf.modify(Modifier::SetBGLightness(0.8));
+        Ok(())
+    }
+
+    /// Draws the estimated chance of winning as a bar that fades from red to green as the
+    /// estimate improves. The bar's buffer is only acquired once there is an estimate to show,
+    /// and never if there was no room for it in the top bar.
+    fn draw_outlook(&mut self, estimate: f32) -> Result<()> {
+        let dbuf = match (&mut self.outlook, &self.outlook_rectangle) {
+            (Some(dbuf), _) => dbuf,
+            (None, Some(r)) => {
+                let dbuf = self
+                    .canvas
+                    .get_text_buffer(r.clone(), Owner::Named("outlook"))?;
+                self.outlook.insert(dbuf)
+            }
+            (None, None) => return Ok(()),
+        };
+        let estimate = estimate.clamp(0.0, 1.0);
+        let filled = (estimate * OUTLOOK_CELLS as f32).round() as usize;
+        let bar: String = std::iter::repeat_n('\u{2588}', filled)
+            .chain(std::iter::repeat_n('\u{2591}', OUTLOOK_CELLS - filled))
+            .collect();
+        let color = Rgb::new(200, 30, 30).interpolate(&Rgb::new(30, 200, 30), estimate);
+        dbuf.clear()?;
+        dbuf.format(FormatOptions {
+            halign: HAlignment::Left,
+            valign: VAlignment::Top,
+        });
+        dbuf.write(&bar, Some(color), None)?;
+        dbuf.flush()?;
+        Ok(())
+    }
+
+    /// Marks the border at both ends of the row or column holding the given pair of tiles,
+    /// replacing any previous markers; None just clears them. The markers sit above the border,
+    /// so dropping them uncovers it unchanged.
+    fn mark_merge(&mut self, pair: Option<(BoardIdx, BoardIdx)>) -> Result<()> {
+        self.merge_markers.clear();
+        let (first, second) = match pair {
+            Some(pair) => pair,
+            None => return Ok(()),
+        };
+        let board = self.board.rectangle();
+        let (right, bottom) = board.extents();
+        let layout = self.layout;
+        let markers = if first.y() == second.y() {
+            let y = layout.tile_rectangle(0, first.y(), MARKER_LAYER_IDX).y()
+                + layout.tile_height() / 2;
+            [(board.x(), y, '\u{bb}'), (right - 1, y, '\u{ab}')]
+        } else {
+            let x =
+                layout.tile_rectangle(first.x(), 0, MARKER_LAYER_IDX).x() + layout.tile_width() / 2;
+            [(x, board.y(), '\u{2c5}'), (x, bottom - 1, '\u{2c4}')]
+        };
+        for (x, y, c) in markers {
+            let r = Rectangle(Idx(x, y, MARKER_LAYER_IDX), Bounds2D(1, 1));
+            let mut marker = self
+                .canvas
+                .get_draw_buffer(r, Owner::Named("merge marker"))?;
+            marker.fill(c)?;
+            marker.modify(Modifier::SetBackgroundColor(40, 0, 0));
+            marker.modify(Modifier::SetBGLightness(0.2));
+            marker.modify(Modifier::SetForegroundColor(230, 200, 60));
+            self.merge_markers.push(marker);
+        }
+        Ok(())
+    }
+
+    /// Shows an overlay centered on the board listing the score each merge in the given move
+    /// contributed. Returns None if the move merged fewer than two pairs, since the score box
+    /// already tells the whole story then. The overlay disappears when the returned buffer is
+    /// dropped.
+    fn overlay_score_breakdown(&mut self, hint: &AnimationHint) -> Result<Option<TextBuffer>> {
+        let lines = score_breakdown(hint);
+        if lines.len() < 2 {
+            return Ok(None);
+        }
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) + 4;
+        let height = lines.len() + 2;
+        let board = self.board.rectangle();
+        let x = board.x() + board.width().saturating_sub(width) / 2;
+        let y = board.y() + board.height().saturating_sub(height) / 2;
+        let r = Rectangle(Idx(x, y, OVERLAY_LAYER_IDX), Bounds2D(width, height));
+        let mut overlay = self
+            .canvas
+            .get_text_buffer(r, Owner::Named("score breakdown"))?;
+        overlay.modify(Modifier::SetBackgroundColor(20, 20, 30));
+        overlay.format(FormatOptions {
+            halign: HAlignment::Left,
+            valign: VAlignment::Top,
+        });
+        Self::draw_score_breakdown(&mut overlay, &lines, 1.0)?;
+        Ok(Some(overlay))
+    }
+
+    /// Draws the breakdown lines, dimmed by the given factor between 0 (invisible) and 1.
+    fn draw_score_breakdown(
+        dbuf: &mut TextBuffer,
+        lines: &[String],
+        brightness: f32,
+    ) -> Result<()> {
+        let color = Rgb::new(230, 220, 160).set_lightness(0.85 * brightness);
+        dbuf.draw_border()?;
+        dbuf.clear()?;
+        for line in lines {
+            dbuf.write(&format!(" {}", line), Some(color.clone()), None)?;
+        }
+        dbuf.flush()?;
+        Ok(())
+    }
+
+    /// Covers every slot with how many times a tile has come to rest there, on a background that
+    /// gets brighter the more often that happened. The heatmap stays up until the returned buffers
+    /// are dropped.
+    fn display_tile_heatmap(&self) -> Result<Vec<TextBuffer>> {
+        let most = self
+            .tile_occupancy
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(0);
+        let mut cells = Vec::with_capacity(16);
+        for (y, row) in self.tile_occupancy.iter().enumerate() {
+            for (x, count) in row.iter().enumerate() {
+                let r = self.layout.tile_rectangle(x, y, OVERLAY_LAYER_IDX);
+                let mut buf = self.canvas.get_text_buffer(r, Owner::At("heatmap", x, y))?;
+                let heat = match most {
+                    0 => 0.0,
+                    most => *count as f32 / most as f32,
+                };
+                buf.modify(Modifier::SetBackgroundColor(200, 60, 0));
+                buf.modify(Modifier::SetBGLightness(0.1 + 0.6 * heat));
+                buf.format(FormatOptions {
+                    halign: HAlignment::Center,
+                    valign: VAlignment::Middle,
+                });
+                buf.clear()?;
+                buf.write(&format!("{}", count), Some(Rgb::new(255, 255, 255)), None)?;
+                buf.flush()?;
+                cells.push(buf);
+            }
+        }
+        Ok(cells)
+    }
+
+    /// Draws the given round in miniature, one cell per tile showing the last digit of its value
+    /// (or `·` for an empty slot) in the tile's colors, eg for a preview of an opponent's board.
+    /// The rectangle must be exactly one cell per tile; the caller positions the returned buffer.
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn mini_preview(round: &Round, canvas: &mut Canvas, rect: Rectangle) -> Result<DrawBuffer> {
+        if (rect.width(), rect.height()) != round.dimensions() {
+            return Err(TuiError::from(InnerError::RectangleDimensionsMustMatch).into());
+        }
+        let mut buf = canvas.get_draw_buffer(rect, Owner::Named("mini preview"))?;
+        let mut colors = Vec::with_capacity(16);
+        {
+            let mut transaction = buf.begin_transaction();
+            for (y, row) in round.iter_rows().enumerate() {
+                for (x, card) in row.iter().enumerate() {
+                    if *card == 0 {
+                        transaction.set_content(x, y, '\u{b7}');
+                        continue;
+                    }
+                    let digit = char::from_digit(display_value(*card) % 10, 10)
+                        .expect("a number mod 10 is a single digit");
+                    transaction.set_content(x, y, digit);
+                    let (bg, fg) = colors_from_card(*card);
+                    let (fg, _) = fg.apply((None, None));
+                    let (_, bg) = bg.apply((None, None));
+                    colors.push((x, y, fg, bg));
+                }
+            }
+            transaction.commit()?;
+        }
+        for (x, y, fg, bg) in colors {
+            buf.set_color_at(x, y, fg, bg)?;
+        }
+        Ok(buf)
+    }
+
+    /// Pulses the background of the tile at the given position, if there is a tile at rest there.
+    fn flash_tile(&self, idx: &BoardIdx, color: Rgb, cycles: usize) -> Option<PulseHandle> {
+        match self.slots.get(idx.y())?.get(idx.x())? {
+            Slot::Static(tile) => Some(tile.buf.pulse_animation(color, cycles)),
+            _ => None,
+        }
+    }
+
+    fn get_slot(&mut self, idx: &BoardIdx) -> Result<Slot> {
+        let s = self
+            .slots
+            .get_mut(idx.y())
+            .ok_or(Error::UnableToRetrieveSlot {
+                context: format!("getting row {}", idx.y()),
+            })?
+            .get_mut(idx.x())
+            .ok_or(Error::UnableToRetrieveSlot {
+                context: format!("getting slot at {},{}", idx.x(), idx.y()),
+            })?;
+        let s = s.take();
+        Ok(s)
+    }
+
+    fn put_slot(&mut self, idx: &BoardIdx, slot: Slot) -> Result<()> {
+        let s = self
+            .slots
+            .get_mut(idx.y())
+            .ok_or(Error::UnableToRetrieveSlot {
+                context: format!("getting row {}", idx.y()),
+            })?
+            .get_mut(idx.x())
+            .ok_or(Error::UnableToRetrieveSlot {
+                context: format!("getting slot at {},{}", idx.x(), idx.y()),
+            })?;
+        if !matches!(slot, Slot::Empty) {
+            if let Some(count) = self
+                .tile_occupancy
+                .get_mut(idx.y())
+                .and_then(|row| row.get_mut(idx.x()))
+            {
+                *count = count.saturating_add(1);
+            }
+        }
+        let _ = s.replace(slot);
+        Ok(())
+    }
+
+    fn new_sliding_tile(
+        &mut self,
+        to_idx: &BoardIdx,
+        value: u8,
+        direction: &Direction,
+    ) -> Result<SlidingTile> {
+        let layout = self.layout;
+        let (across, down) = layout.new_tile_offsets();
+        let mut db_rectangle = match direction {
+            Direction::Left => {
+                layout.tile_rectangle(layout.columns - 1, to_idx.y(), LOWER_ANIMATION_LAYER_IDX)
+            }
+            Direction::Right => layout.tile_rectangle(0, to_idx.y(), LOWER_ANIMATION_LAYER_IDX),
+            Direction::Up => {
+                layout.tile_rectangle(to_idx.x(), layout.rows - 1, LOWER_ANIMATION_LAYER_IDX)
+            }
+            Direction::Down => layout.tile_rectangle(to_idx.x(), 0, LOWER_ANIMATION_LAYER_IDX),
+        };
+        // new tiles start out beyond the edge they slide in from
+        db_rectangle.0 = match direction {
+            Direction::Left => db_rectangle.0.checked_add(across, 0)?,
+            Direction::Right => db_rectangle.0.checked_sub(across, 0)?,
+            Direction::Up => db_rectangle.0.checked_add(0, down)?,
+            Direction::Down => db_rectangle.0.checked_sub(0, down)?,
+        };
+        log::trace!("getting new textbuffer for rectangle {}", db_rectangle);
+        let owner = Owner::At("new tile", to_idx.x(), to_idx.y());
+        // the rectangle is derived from the board layout, which check_bounds already verified
+        // fits the canvas along with room for the new tile animation
+        let buf = self.canvas.get_text_buffer_unchecked(db_rectangle, owner)?;
+        let mut t = Tile::new(
+            value,
+            to_idx.clone(),
+            buf,
+            self.labels.clone(),
+            self.theme.clone(),
+        );
+        t.draw()?;
+
+        let rectangle = layout.tile_rectangle(to_idx.x(), to_idx.y(), LOWER_ANIMATION_LAYER_IDX);
+        let st = SlidingTile::new(t, rectangle, None, self.easing);
+
+        Ok(st)
+    }
+
+    /// Sets up the animation from one round to the next, working out how the tiles moved from the
+    /// difference between the two so that rounds needn't come straight from `Board::shift`.
+    /// Returns the hint the animation was set up from.
+    fn animate_new_round(&mut self, prev: &Round, next: &Round) -> Result<AnimationHint> {
+        let hint = Round::explain(prev, next).ok_or(Error::NoShiftBetweenRounds)?;
+        self.setup_animation(&hint)?;
+        Ok(hint)
+    }
+
+    fn setup_animation(&mut self, hints: &AnimationHint) -> Result<()> {
+        log::trace!(
+            "setting up animation with hints {}",
+            hints.to_debug_string()
+        );
+        let merge_targets: Vec<BoardIdx> = hints
+            .hints()
+            .into_iter()
+            .filter_map(|(_, hint)| match hint {
+                Hint::NewValueToIdx(_, to_idx) => Some(to_idx),
+                _ => None,
+            })
+            .collect();
+        let layout = self.layout;
+        for (idx, hint) in hints.hints() {
+            log::trace!("setting up animation for hint {0} -> {1}", idx, hint);
             let slot = self.get_slot(&idx)?;
             let new_slot = match hint.clone() {
-                Hint::ToIdx(to_idx) => Slot::to_sliding(slot, to_idx, None)?,
-                Hint::NewValueToIdx(value, to_idx) => Slot::to_sliding(slot, to_idx, Some(value))?,
+                Hint::ToIdx(to_idx) if merge_targets.contains(&to_idx) => Slot::to_sliding(
+                    slot,
+                    to_idx,
+                    None,
+                    MERGING_ANIMATION_LAYER_IDX,
+                    &layout,
+                    self.easing,
+                )?,
+                Hint::ToIdx(to_idx) => Slot::to_sliding(
+                    slot,
+                    to_idx,
+                    None,
+                    UPPER_ANIMATION_LAYER_IDX,
+                    &layout,
+                    self.easing,
+                )?,
+                Hint::NewValueToIdx(value, to_idx) => Slot::to_sliding(
+                    slot,
+                    to_idx,
+                    Some(value),
+                    UPPER_ANIMATION_LAYER_IDX,
+                    &layout,
+                    self.easing,
+                )?,
                 Hint::NewTile(value, slide_direction) => {
-                    let t = self.new_sliding_tile(&idx, value, &slide_direction)?;
-                    Slot::Sliding(t)
+                    let direction = Direction::from_board(&slide_direction);
+                    let t = self.new_sliding_tile(&idx, value, &direction)?;
+                    Slot::Sliding(t.fading_in())
+                }
+                Hint::Remove(_) => {
+                    // the tile fades where it is, as if something had merged into it
+                    if let (Slot::Static(t), true) = (slot, self.fade_merged) {
+                        let dt = DisappearingTile::new(t)?;
+                        self.disappearing_slots.push(Slot::Disappearing(dt));
+                    }
+                    continue;
                 }
             };
             self.moving_slots.push(new_slot);
@@ -267,6 +1332,152 @@ impl Tui48Board {
                 self.canvas
             );
         }
+        if self.fade_merged {
+            // every tile that moved has left its slot by now, so a tile still sitting at a merge
+            // target is the one the merge consumes
+            for idx in merge_targets {
+                match self.get_slot(&idx)? {
+                    Slot::Static(t) => {
+                        let dt = DisappearingTile::new(t)?;
+                        self.disappearing_slots.push(Slot::Disappearing(dt));
+                    }
+                    slot => self.put_slot(&idx, slot)?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets up the animation taking back a move: the tile placed after the move disappears, merged
+    /// tiles split in two, every tile slides back to where it came from and tiles a power-up took
+    /// off the board come back.
+    fn setup_rewind(&mut self, plan: &RewindPlan) -> Result<()> {
+        for (idx, hint) in plan.hints() {
+            log::trace!("setting up rewind for hint {0} -> {1}", idx, hint);
+            match hint {
+                RewindHint::RemoveTile => drop(self.get_slot(&idx)?),
+                RewindHint::ToIdx(value, to_idx) => {
+                    let slot = self.get_slot(&idx)?;
+                    let new_value = (slot.value() != Some(value)).then_some(value);
+                    let mut slot = Slot::to_sliding(
+                        slot,
+                        to_idx,
+                        new_value,
+                        UPPER_ANIMATION_LAYER_IDX,
+                        &self.layout,
+                        self.easing,
+                    )?;
+                    // show the value from before the merge as soon as the tiles split
+                    if let (Slot::Sliding(st), Some(_)) = (&mut slot, new_value) {
+                        st.inner.draw()?;
+                    }
+                    self.moving_slots.push(slot);
+                }
+                RewindHint::PutBack(value) => {
+                    let r = self.layout.tile_rectangle(idx.x(), idx.y(), TILE_LAYER_IDX);
+                    let buf = self
+                        .canvas
+                        .get_text_buffer(r, Owner::At("tile", idx.x(), idx.y()))?;
+                    let mut t = Tile::new(
+                        value,
+                        idx.clone(),
+                        buf,
+                        self.labels.clone(),
+                        self.theme.clone(),
+                    );
+                    t.draw()?;
+                    self.put_slot(&idx, Slot::Static(t))?;
+                }
+                RewindHint::SetValue(value) => {
+                    let mut slot = self.get_slot(&idx)?;
+                    if let Slot::Static(t) = &mut slot {
+                        t.value = value;
+                        t.draw()?;
+                    }
+                    self.put_slot(&idx, slot)?;
+                }
+                RewindHint::SplitToIdx(value, to_idx) => {
+                    // the split tile starts out underneath the tile it splits from
+                    let r = self
+                        .layout
+                        .tile_rectangle(idx.x(), idx.y(), LOWER_ANIMATION_LAYER_IDX);
+                    let owner = Owner::At("split tile", to_idx.x(), to_idx.y());
+                    let buf = self.canvas.get_text_buffer(r, owner)?;
+                    let mut t = Tile::new(
+                        value,
+                        to_idx.clone(),
+                        buf,
+                        self.labels.clone(),
+                        self.theme.clone(),
+                    );
+                    t.draw()?;
+                    let to_rectangle = self.layout.tile_rectangle(
+                        to_idx.x(),
+                        to_idx.y(),
+                        LOWER_ANIMATION_LAYER_IDX,
+                    );
+                    self.moving_slots.push(Slot::Sliding(SlidingTile::new(
+                        t,
+                        to_rectangle,
+                        None,
+                        self.easing,
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets up the static tile at the given index to slide into place from just outside its
+    /// slot, offset away from the center of the board along both axes so that corner tiles slide
+    /// in diagonally. Tiles in the same quadrant move in lockstep and tiles in different quadrants
+    /// move apart, so entering tiles never run into each other.
+    fn tile_enter_animation(&mut self, idx: BoardIdx) -> Result<()> {
+        let value = match self.get_slot(&idx)? {
+            // the static tile's buffer is dropped here; the entering tile gets a fresh one
+            Slot::Static(t) => t.value(),
+            slot => {
+                self.put_slot(&idx, slot)?;
+                return Err(Error::CannotConvertToSliding { idx: Some(idx) });
+            }
+        };
+
+        let height = self.slots.len();
+        let width = self.slots.first().map_or(0, Vec::len);
+        let (across, down) = self.layout.new_tile_offsets();
+        let mut from_rectangle =
+            self.layout
+                .tile_rectangle(idx.x(), idx.y(), UPPER_ANIMATION_LAYER_IDX);
+        from_rectangle.0 = if idx.x() < width / 2 {
+            from_rectangle.0.checked_sub(across, 0)?
+        } else {
+            from_rectangle.0.checked_add(across, 0)?
+        };
+        from_rectangle.0 = if idx.y() < height / 2 {
+            from_rectangle.0.checked_sub(0, down)?
+        } else {
+            from_rectangle.0.checked_add(0, down)?
+        };
+
+        let owner = Owner::At("entering tile", idx.x(), idx.y());
+        let buf = self.canvas.get_text_buffer(from_rectangle, owner)?;
+        let mut t = Tile::new(
+            value,
+            idx.clone(),
+            buf,
+            self.labels.clone(),
+            self.theme.clone(),
+        );
+        t.draw()?;
+        let to_rectangle = self
+            .layout
+            .tile_rectangle(idx.x(), idx.y(), UPPER_ANIMATION_LAYER_IDX);
+        self.moving_slots.push(Slot::Sliding(SlidingTile::new(
+            t,
+            to_rectangle,
+            None,
+            self.easing,
+        )));
         Ok(())
     }
 
@@ -285,12 +1496,113 @@ impl Tui48Board {
         }
 
         let _ = self.moving_slots.drain(0..);
+        // fades that haven't finished yet are cut short along with the rest of the animation
+        let _ = self.disappearing_slots.drain(..);
+
+        Ok(())
+    }
 
+    /// Throws away all animation state and syncs the tiles with the current round. Meant for
+    /// recovering from an animation that was interrupted part way through; it is much cheaper than
+    /// rebuilding the entire Tui48Board.
+    fn clear_all_animations(&mut self, game: &Board) -> Result<()> {
+        log::trace!("clearing all animations");
+        let report = self.sync(&game.current())?;
+        log::trace!("synced the board: {}", report);
         Ok(())
     }
 
+    /// Finishes the animation just played and brings the board in line with the given round,
+    /// where the animation should have left it anyway. Whatever sync still has to change is
+    /// logged, after being checked as an inconsistency in debug builds.
+    fn settle(&mut self, round: &Round) -> Result<SyncReport> {
+        self.teardown_animation()?;
+        #[cfg(debug_assertions)]
+        self.check_consistency(round);
+        let report = self.sync(round)?;
+        if !report.is_empty() {
+            log::warn!("repaired the board after an animation: {}", report);
+        }
+        Ok(report)
+    }
+
+    /// Brings the slots in line with the given round while changing as little as it can: tiles
+    /// are dropped where the round has none, created where the board shows none and redrawn where
+    /// they show another value. Tiles that already agree with the round are left alone, without
+    /// so much as a cell of them written. Anything left over from an animation is thrown away
+    /// first. Returns what had to change, which is nothing when the board showed the round.
+    fn sync(&mut self, round: &Round) -> Result<SyncReport> {
+        let mut report = SyncReport::default();
+        for slot in self
+            .moving_slots
+            .drain(..)
+            .chain(self.disappearing_slots.drain(..))
+            .chain(self.done_slots.drain().map(|(_, slot)| slot))
+        {
+            if let Some(at) = slot.board_index() {
+                report.dropped.push(at);
+            }
+        }
+
+        // tiles have to release their cells before others can be drawn in their place
+        for (y, row) in self.slots.iter_mut().enumerate() {
+            for (x, slot) in row.iter_mut().enumerate() {
+                let at = BoardIdx(x, y);
+                let keep = match slot {
+                    Slot::Empty => true,
+                    Slot::Static(tile) => {
+                        round.get(&at) > 0
+                            && tile.idx == at
+                            && tile.buf.rectangle()
+                                == self.layout.tile_rectangle(x, y, TILE_LAYER_IDX)
+                    }
+                    Slot::Sliding(_) | Slot::Disappearing(_) => false,
+                };
+                if !keep {
+                    *slot = Slot::Empty;
+                    report.dropped.push(at);
+                }
+            }
+        }
+
+        for y in 0..self.slots.len() {
+            for x in 0..self.slots[y].len() {
+                let at = BoardIdx(x, y);
+                let expected = round.get(&at);
+                if let Slot::Static(tile) = &mut self.slots[y][x] {
+                    if tile.value != expected {
+                        tile.value = expected;
+                        tile.draw()?;
+                        report.redrawn.push(at);
+                    }
+                    continue;
+                }
+                if expected == 0 {
+                    continue;
+                }
+                let r = self.layout.tile_rectangle(x, y, TILE_LAYER_IDX);
+                let buf = self.canvas.get_text_buffer(r, Owner::At("tile", x, y))?;
+                let mut t = Tile::new(
+                    expected,
+                    at.clone(),
+                    buf,
+                    self.labels.clone(),
+                    self.theme.clone(),
+                );
+                t.draw()?;
+                self.slots[y][x] = Slot::Static(t);
+                report.created.push(at);
+            }
+        }
+        Ok(report)
+    }
+
     fn animate(&mut self) -> Result<bool> {
         log::trace!("about to animate a frame");
+        #[cfg(debug_assertions)]
+        for (target, at) in self.slot_animation_overlap_check() {
+            log::warn!("slot at {} overlaps another slot moving to {}", at, target);
+        }
         let should_continue = self
             .moving_slots
             .iter_mut()
@@ -305,7 +1617,8 @@ impl Tui48Board {
                     log::trace!("about to animate slot {}\n{}", bidx, slot);
                 }
                 let c = slot.animate()?;
-                if !c {
+                // tiles merged away leave nothing behind once they have faded
+                if !c && !matches!(slot, Slot::Disappearing(_)) {
                     let new_done_slot = match self.done_slots.get_mut(&idx) {
                         // if there is a matching done slot for the current slot's index, then we
                         // need to decide which to keep and avoid tearing down the animation twice
@@ -360,6 +1673,142 @@ impl Tui48Board {
             (None, None) => unreachable!(),
         }
     }
+
+    /// Finds moving slots that target the same board index other than the two halves of a merge,
+    /// which is the one overlap `keep_largest_value_tile` expects. Each overlapping slot is
+    /// reported as its target paired with the board index nearest to where it is drawn.
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    fn slot_animation_overlap_check(&self) -> Vec<(BoardIdx, BoardIdx)> {
+        let mut by_target: HashMap<BoardIdx, Vec<&Slot>> = HashMap::new();
+        for slot in &self.moving_slots {
+            if let Some(target) = slot.board_index() {
+                by_target.entry(target).or_default().push(slot);
+            }
+        }
+        let mut overlaps: Vec<(BoardIdx, BoardIdx)> = by_target
+            .into_iter()
+            .filter(|(_, slots)| match slots.as_slice() {
+                [_] => false,
+                [a, b] => a.new_value().is_some() == b.new_value().is_some(),
+                _ => true,
+            })
+            .flat_map(|(target, slots)| {
+                slots
+                    .into_iter()
+                    .filter_map(|slot| slot.rectangle())
+                    .map(move |r| (target.clone(), self.nearest_board_idx(&r)))
+            })
+            .collect();
+        overlaps.sort_by_key(|(target, at)| (target.y(), target.x(), at.y(), at.x()));
+        overlaps
+    }
+
+    /// Returns the board index of the slot nearest to the given rectangle, the inverse of
+    /// `tile_rectangle` for rectangles that sit exactly on a slot.
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    fn nearest_board_idx(&self, r: &Rectangle) -> BoardIdx {
+        let height = self.slots.len();
+        let width = self.slots.first().map_or(0, Vec::len);
+        let (x, y) = self.layout.nearest_slot(r);
+        BoardIdx(
+            x.min(width.saturating_sub(1)),
+            y.min(height.saturating_sub(1)),
+        )
+    }
+
+    /// Checks that the board shows exactly the given round while no animation is playing: a
+    /// static tile showing the right value wherever the round has one and nowhere else, each
+    /// drawn on its own slot, nothing left over from an animation and nothing drawn on the
+    /// animation layers. Reports everything that disagrees, in slot order.
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    fn verify_consistency(&self, round: &Round) -> std::result::Result<(), Vec<Inconsistency>> {
+        let mut found = Vec::new();
+        for (y, row) in self.slots.iter().enumerate() {
+            for (x, slot) in row.iter().enumerate() {
+                let at = BoardIdx(x, y);
+                let expected = round.get(&at);
+                let tile = match slot {
+                    Slot::Empty if expected > 0 => {
+                        found.push(Inconsistency::MissingTile { at, expected });
+                        continue;
+                    }
+                    Slot::Empty => continue,
+                    Slot::Sliding(_) | Slot::Disappearing(_) => {
+                        found.push(Inconsistency::LeftoverAnimation { at });
+                        continue;
+                    }
+                    Slot::Static(tile) => tile,
+                };
+                if expected == 0 {
+                    found.push(Inconsistency::UnexpectedTile {
+                        at: at.clone(),
+                        actual: tile.value,
+                    });
+                } else if tile.value != expected {
+                    found.push(Inconsistency::WrongValue {
+                        at: at.clone(),
+                        expected,
+                        actual: tile.value,
+                    });
+                }
+                if tile.idx != at {
+                    found.push(Inconsistency::WrongIndex {
+                        at: at.clone(),
+                        actual: tile.idx.clone(),
+                    });
+                }
+                let rectangle = self.layout.tile_rectangle(x, y, TILE_LAYER_IDX);
+                if tile.buf.rectangle() != rectangle {
+                    found.push(Inconsistency::Misplaced {
+                        at,
+                        expected: rectangle,
+                        actual: tile.buf.rectangle(),
+                    });
+                }
+            }
+        }
+        for slot in self
+            .moving_slots
+            .iter()
+            .chain(self.disappearing_slots.iter())
+            .chain(self.done_slots.values())
+        {
+            if let Some(at) = slot.board_index() {
+                found.push(Inconsistency::LeftoverAnimation { at });
+            }
+        }
+        // merge markers are drawn on the lower animation layer between moves
+        let lower = self
+            .merge_markers
+            .is_empty()
+            .then_some(LOWER_ANIMATION_LAYER_IDX);
+        for layer in lower
+            .into_iter()
+            .chain([MERGING_ANIMATION_LAYER_IDX, UPPER_ANIMATION_LAYER_IDX])
+        {
+            if self.canvas.layer_occupied(layer) {
+                found.push(Inconsistency::AnimationLayerOccupied { layer });
+            }
+        }
+        if found.is_empty() {
+            return Ok(());
+        }
+        Err(found)
+    }
+
+    /// Runs `verify_consistency` in debug builds, logging whatever disagrees with the round and
+    /// panicking over it with strict checks on; see `set_strict_checks`.
+    #[cfg(debug_assertions)]
+    fn check_consistency(&self, round: &Round) {
+        if let Err(found) = self.verify_consistency(round) {
+            for inconsistency in found.iter() {
+                log::warn!("board disagrees with the round: {}", inconsistency);
+            }
+            if strict_checks() {
+                panic!("board disagrees with the round: {:?}", found);
+            }
+        }
+    }
 }
 
 impl std::fmt::Display for Tui48Board {
@@ -487,22 +1936,142 @@ impl From<&BoardIdx> for Idx {
     }
 }
 
-#[derive(Default)]
-enum Slot {
-    #[default]
-    Empty,
-    Static(Tile),
-    Sliding(SlidingTile),
+/// A way in which the board's tiles disagree with the round they are meant to show, found by
+/// `Tui48Board::verify_consistency`.
+#[derive(Clone, Debug, PartialEq)]
+enum Inconsistency {
+    /// The round has a tile where the board has none.
+    MissingTile { at: BoardIdx, expected: Card },
+    /// The board has a tile where the round has none.
+    UnexpectedTile { at: BoardIdx, actual: Card },
+    /// The tile shows a different value than the round has.
+    WrongValue {
+        at: BoardIdx,
+        expected: Card,
+        actual: Card,
+    },
+    /// The tile believes it belongs in another slot.
+    WrongIndex { at: BoardIdx, actual: BoardIdx },
+    /// The tile isn't drawn where its slot is.
+    Misplaced {
+        at: BoardIdx,
+        expected: Rectangle,
+        actual: Rectangle,
+    },
+    /// A tile is still sliding, fading or waiting to be torn down.
+    LeftoverAnimation { at: BoardIdx },
+    /// Something is drawn on an animation layer.
+    AnimationLayerOccupied { layer: usize },
 }
 
-impl std::fmt::Display for Slot {
+impl std::fmt::Display for Inconsistency {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            Self::Empty => f.pad("empty")?,
-            Self::Static(t) => write!(f, "{}", t)?,
-            Self::Sliding(st) => write!(f, "{}", st)?,
-        };
-        Ok(())
+            Self::MissingTile { at, expected } => {
+                write!(
+                    f,
+                    "no tile at {} where the round has {}",
+                    at,
+                    display_value(*expected)
+                )
+            }
+            Self::UnexpectedTile { at, actual } => {
+                write!(
+                    f,
+                    "tile {} at {} where the round has none",
+                    display_value(*actual),
+                    at
+                )
+            }
+            Self::WrongValue {
+                at,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "tile at {} is {} rather than {}",
+                at,
+                display_value(*actual),
+                display_value(*expected)
+            ),
+            Self::WrongIndex { at, actual } => {
+                write!(f, "tile at {} thinks it is at {}", at, actual)
+            }
+            Self::Misplaced {
+                at,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "tile at {} is drawn at {} rather than {}",
+                at, actual, expected
+            ),
+            Self::LeftoverAnimation { at } => write!(f, "animation left over at {}", at),
+            Self::AnimationLayerOccupied { layer } => {
+                write!(f, "animation layer {} is occupied", layer)
+            }
+        }
+    }
+}
+
+/// What `Tui48Board::sync` changed to bring the board in line with a round, by slot.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct SyncReport {
+    /// Slots whose tile, or animation, was thrown away.
+    dropped: Vec<BoardIdx>,
+    /// Slots given a tile where they had none.
+    created: Vec<BoardIdx>,
+    /// Slots whose tile was redrawn with another value.
+    redrawn: Vec<BoardIdx>,
+}
+
+impl SyncReport {
+    fn is_empty(&self) -> bool {
+        self.changes() == 0
+    }
+
+    fn changes(&self) -> usize {
+        self.dropped.len() + self.created.len() + self.redrawn.len()
+    }
+}
+
+impl std::fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let list = |slots: &[BoardIdx]| {
+            slots
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        write!(
+            f,
+            "dropped [{}], created [{}], redrawn [{}]",
+            list(&self.dropped),
+            list(&self.created),
+            list(&self.redrawn)
+        )
+    }
+}
+
+#[derive(Default)]
+enum Slot {
+    #[default]
+    Empty,
+    Static(Tile),
+    Sliding(SlidingTile),
+    Disappearing(DisappearingTile),
+}
+
+impl std::fmt::Display for Slot {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Empty => f.pad("empty")?,
+            Self::Static(t) => write!(f, "{}", t)?,
+            Self::Sliding(st) => write!(f, "{}", st)?,
+            Self::Disappearing(dt) => write!(f, "{}", dt)?,
+        };
+        Ok(())
     }
 }
 
@@ -515,31 +2084,33 @@ impl Slot {
         std::mem::take(self)
     }
 
-    fn to_sliding(this: Self, to_idx: BoardIdx, new_value: Option<u8>) -> Result<Self> {
+    fn to_sliding(
+        this: Self,
+        to_idx: BoardIdx,
+        new_value: Option<u8>,
+        layer: usize,
+        layout: &LayoutSpec,
+        easing: EasingFn,
+    ) -> Result<Self> {
         // only allow static tiles to be converted to sliding
         let mut t = match this {
             Self::Static(t) => t,
             Self::Empty => return Err(Error::CannotConvertToSliding { idx: None }),
-            Self::Sliding(_) => {
+            Self::Sliding(_) | Self::Disappearing(_) => {
                 return Err(Error::CannotConvertToSliding {
                     idx: Some(this.idx()?),
                 })
             }
         };
 
-        log::trace!(
-            "about move buffer to layer {0}\n{1}",
-            UPPER_ANIMATION_LAYER_IDX,
-            t.buf
-        );
-        t.buf.switch_layer(UPPER_ANIMATION_LAYER_IDX)?;
+        log::trace!("about move buffer to layer {0}\n{1}", layer, t.buf);
+        t.buf.switch_layer(layer)?;
         t.idx = to_idx.clone();
         if let Some(v) = new_value {
             t.value = v;
         }
-        let to_rectangle =
-            Tui48Board::tile_rectangle(to_idx.0, to_idx.1, UPPER_ANIMATION_LAYER_IDX);
-        let st = SlidingTile::new(t, to_rectangle, new_value);
+        let to_rectangle = layout.tile_rectangle(to_idx.0, to_idx.1, layer);
+        let st = SlidingTile::new(t, to_rectangle, new_value, easing);
 
         Ok(Slot::Sliding(st))
     }
@@ -552,6 +2123,8 @@ impl Slot {
         // only allow sliding tiles to be converted to static
         if let Self::Sliding(st) = this {
             let mut t = st.to_tile();
+            // a tile cut short while fading in shows its colors once it's at rest
+            t.buf.fade(None);
             t.buf.switch_layer(TILE_LAYER_IDX)?;
             t.draw()?;
             return Ok(Slot::Static(t));
@@ -565,6 +2138,7 @@ impl Slot {
             Slot::Empty => unreachable!(),
             Slot::Static(t) => Ok(t.idx.clone()),
             Slot::Sliding(st) => Ok(st.inner.idx.clone()),
+            Slot::Disappearing(dt) => Ok(dt.inner.idx.clone()),
         }
     }
 
@@ -573,6 +2147,7 @@ impl Slot {
             Slot::Empty => Ok(false),
             Slot::Static(_) => Ok(false),
             Slot::Sliding(st) => st.animate(),
+            Slot::Disappearing(dt) => dt.animate(),
         }
     }
 }
@@ -583,13 +2158,14 @@ impl Slot {
             Self::Empty => None,
             Self::Static(t) => Some(t.value()),
             Self::Sliding(st) => Some(st.value()),
+            Self::Disappearing(dt) => Some(dt.inner.value()),
         }
     }
 
     fn new_value(&self) -> Option<u8> {
         match self {
             Self::Empty => None,
-            Self::Static(_) => None,
+            Self::Static(_) | Self::Disappearing(_) => None,
             Self::Sliding(st) => st.new_value(),
         }
     }
@@ -599,6 +2175,7 @@ impl Slot {
             Self::Empty => None,
             Self::Static(t) => Some(t.board_index()),
             Self::Sliding(st) => Some(st.board_index()),
+            Self::Disappearing(dt) => Some(dt.inner.board_index()),
         }
     }
 
@@ -607,13 +2184,14 @@ impl Slot {
             Self::Empty => None,
             Self::Static(t) => Some(t.rectangle()),
             Self::Sliding(st) => Some(st.rectangle()),
+            Self::Disappearing(dt) => Some(dt.inner.rectangle()),
         }
     }
 
     fn to_rectangle(&self) -> Option<Rectangle> {
         match self {
             Self::Empty => None,
-            Self::Static(_) => None,
+            Self::Static(_) | Self::Disappearing(_) => None,
             Self::Sliding(st) => Some(st.to_rectangle()),
         }
     }
@@ -623,6 +2201,8 @@ struct Tile {
     value: u8,
     idx: BoardIdx,
     buf: TextBuffer,
+    labels: Arc<LabelPack>,
+    theme: Arc<Theme>,
 }
 
 impl std::fmt::Display for Tile {
@@ -638,12 +2218,24 @@ impl std::fmt::Display for Tile {
 }
 
 impl Tile {
-    fn new(value: u8, idx: BoardIdx, buf: TextBuffer) -> Self {
-        Self { value, idx, buf }
+    fn new(
+        value: u8,
+        idx: BoardIdx,
+        buf: TextBuffer,
+        labels: Arc<LabelPack>,
+        theme: Arc<Theme>,
+    ) -> Self {
+        Self {
+            value,
+            idx,
+            buf,
+            labels,
+            theme,
+        }
     }
 
     fn draw(&mut self) -> Result<()> {
-        Tui48Board::draw_tile(&mut self.buf, self.value)
+        Tui48Board::draw_tile(&mut self.buf, self.value, &self.labels, &self.theme)
     }
 
     fn value(&self) -> u8 {
@@ -664,6 +2256,13 @@ struct SlidingTile {
     to_rectangle: Rectangle,
     is_animating: bool,
     new_value: Option<u8>,
+    // the frames shown so far out of the cells the slide covers, and the cells covered so far
+    frame: usize,
+    frames: usize,
+    travelled: usize,
+    easing: EasingFn,
+    // whether the tile brightens from black as it slides, reaching its colors as it arrives
+    fading_in: bool,
 }
 
 impl std::fmt::Display for SlidingTile {
@@ -677,13 +2276,31 @@ impl std::fmt::Display for SlidingTile {
 }
 
 impl SlidingTile {
-    fn new(inner: Tile, to_rectangle: Rectangle, new_value: Option<u8>) -> Self {
+    fn new(inner: Tile, to_rectangle: Rectangle, new_value: Option<u8>, easing: EasingFn) -> Self {
+        let from = inner.buf.rectangle().0;
+        let to = &to_rectangle.0;
+        let frames = from.x().abs_diff(to.x()) + from.y().abs_diff(to.y());
         Self {
             inner,
             to_rectangle,
             is_animating: true,
             new_value,
+            frame: 0,
+            frames,
+            travelled: 0,
+            easing,
+            fading_in: false,
+        }
+    }
+
+    /// Starts the tile out black, brightening it a step every frame until it shows its colors
+    /// once it arrives.
+    fn fading_in(mut self) -> Self {
+        self.fading_in = self.frames > 0;
+        if self.fading_in {
+            self.inner.buf.fade(Some(0.0));
         }
+        self
     }
 
     fn to_tile(self) -> Tile {
@@ -695,18 +2312,38 @@ impl SlidingTile {
             return Ok(false);
         }
 
-        if self.inner.buf.rectangle().0.x() == self.to_rectangle.0.x()
-            && self.inner.buf.rectangle().0.y() == self.to_rectangle.0.y()
-        {
+        // eased tiles can arrive early, but every slide takes all of its frames
+        if self.frame >= self.frames {
             // final frame
             // don't move the textbuffer to the tile layer, leave that for
             // Tui48Board.teardown_animation
             if let Some(v) = self.new_value {
                 self.inner.value = v;
             }
+            if self.fading_in {
+                self.inner.buf.fade(None);
+                self.fading_in = false;
+            }
             self.is_animating = false;
             return Ok(false);
         }
+        self.frame += 1;
+        let travelled = self.easing.travelled(self.frame, self.frames);
+        while self.travelled < travelled {
+            self.step()?;
+            self.travelled += 1;
+        }
+        if self.fading_in {
+            self.inner
+                .buf
+                .fade(Some(self.frame as f32 / self.frames as f32));
+        }
+        Ok(true)
+    }
+
+    /// Moves the tile one cell closer to where it is going, along whichever axis it is further
+    /// from it.
+    fn step(&self) -> Result<()> {
         let moving_idx = self.inner.buf.rectangle().0;
         let to_idx = &self.to_rectangle.0;
         let moving_buf = &self.inner.buf;
@@ -714,48 +2351,48 @@ impl SlidingTile {
             moving_idx.x() as i16 - to_idx.x() as i16,
             moving_idx.y() as i16 - to_idx.y() as i16,
         ) {
-            (0, 0) => Ok(true), //no translation necessary
+            (0, 0) => Ok(()), //no translation necessary
             (x, y) if x != 0 && y != 0 && x.abs() > y.abs() && x > 0 => {
                 moving_buf.translate(Direction::Left)?;
-                Ok(true)
+                Ok(())
             }
             (x, y) if x != 0 && y != 0 && x.abs() > y.abs() && x < 0 => {
                 moving_buf.translate(Direction::Right)?;
-                Ok(true)
+                Ok(())
             }
             (x, y) if x != 0 && y != 0 && x.abs() < y.abs() && y > 0 => {
                 moving_buf.translate(Direction::Up)?;
-                Ok(true)
+                Ok(())
             }
             (x, y) if x != 0 && y != 0 && x.abs() < y.abs() && y < 0 => {
                 moving_buf.translate(Direction::Down)?;
-                Ok(true)
+                Ok(())
             }
             (x, y) if x != 0 && y != 0 && x.abs() == y.abs() && y > 0 => {
                 moving_buf.translate(Direction::Up)?;
-                Ok(true)
+                Ok(())
             }
             (x, y) if x != 0 && y != 0 && x.abs() == y.abs() && y < 0 => {
                 moving_buf.translate(Direction::Down)?;
-                Ok(true)
+                Ok(())
             }
             (x, 0) if x > 0 => {
                 moving_buf.translate(Direction::Left)?;
-                Ok(true)
+                Ok(())
             }
             (x, 0) if x < 0 => {
                 moving_buf.translate(Direction::Right)?;
-                Ok(true)
+                Ok(())
             }
             (0, y) if y > 0 => {
                 moving_buf.translate(Direction::Up)?;
-                Ok(true)
+                Ok(())
             }
             (0, y) if y < 0 => {
                 moving_buf.translate(Direction::Down)?;
-                Ok(true)
+                Ok(())
             }
-            _ => Ok(true),
+            _ => Ok(()),
         }
     }
 }
@@ -782,71 +2419,200 @@ impl SlidingTile {
     }
 }
 
-struct Colors {
-    // TODO: change this from canvas::Modifer to colors::Rgb
-    card_colors: HashMap<u8, (Modifier, Modifier)>,
+/// A tile consumed by a merge, fading into the board beneath the tile merging into it, or taken
+/// off the board by a power-up.
+struct DisappearingTile {
+    inner: Tile,
+    from: Rgb,
+    frame: usize,
 }
 
-static DEFAULT_COLORS: OnceLock<Colors> = OnceLock::new();
-static MAX_TILE_EXPONENT: u8 = 17;
+impl std::fmt::Display for DisappearingTile {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "DT({},{}/{})",
+            self.inner, self.frame, DISAPPEARING_FRAMES
+        )
+    }
+}
 
-pub(crate) fn init() -> Result<()> {
-    if let Some(_) = DEFAULT_COLORS.get() {
-        // already set, no need to do anything else
-        return Ok(());
-    }
-    let fg_hue = 28.0 + 180.0;
-    let incr = |inc: u8, num: f32, div: u8| -> f32 { inc as f32 * num / div as f32 };
-    let bg_hue = |i: u8| -> f32 { incr(i, 360.0, MAX_TILE_EXPONENT) };
-    let bg_chroma = |i: u8| -> f32 { 30.0 + incr(i, 60.0, i) };
-    let fg_chroma = |i: u8| -> f32 { 90.0 - incr(i, 40.0, MAX_TILE_EXPONENT/2) };
-
-    let defaults = Colors {
-        card_colors: HashMap::from_iter(
-            (1..MAX_TILE_EXPONENT)
-                .into_iter()
-                .map(|i| {
-                    (
-                        i,
-                        Lch::new(80.0, bg_chroma(i), bg_hue(i)),
-                        Lch::new(20.0, fg_chroma(i), fg_hue),
-                    )
-                })
-                .map(|(k, bg_hsv, fg_hsv)| {
-                    (
-                        k,
-                        Srgb::from_color(bg_hsv).into_format::<u8>(),
-                        Srgb::from_color(fg_hsv).into_format::<u8>(),
-                    )
-                })
-                .map(|(k, bg_rgb, fg_rgb)| {
-                    (
-                        k,
-                        (
-                            Modifier::SetBackgroundColor(bg_rgb.red, bg_rgb.green, bg_rgb.blue),
-                            Modifier::SetForegroundColor(fg_rgb.red, fg_rgb.green, fg_rgb.blue),
-                        ),
-                    )
-                }),
-        ),
-    };
-    let _ = DEFAULT_COLORS.set(defaults);
+impl DisappearingTile {
+    fn new(inner: Tile) -> Result<Self> {
+        inner.buf.switch_layer(LOWER_ANIMATION_LAYER_IDX)?;
+        let background = board_background();
+        let from = inner
+            .theme
+            .colors(inner.value)
+            .0
+            .apply((None, None))
+            .1
+            .unwrap_or(background);
+        Ok(Self {
+            inner,
+            from,
+            frame: 0,
+        })
+    }
+
+    fn animate(&mut self) -> Result<bool> {
+        if self.frame == DISAPPEARING_FRAMES {
+            return Ok(false);
+        }
+        self.frame += 1;
+        let progress = self.frame as f32 / DISAPPEARING_FRAMES as f32;
+        let background = self.from.interpolate(&board_background(), progress);
+        let buf = &mut self.inner.buf;
+        buf.modify(Modifier::SetBackgroundColor(
+            background.r(),
+            background.g(),
+            background.b(),
+        ));
+        buf.draw_border()?;
+        buf.clear()?;
+        buf.write("\u{b7}", None, None)?;
+        buf.flush()?;
+        Ok(true)
+    }
+}
+
+/// Lays out sample tiles in a theme's colors, one of every value up to 2048, along with a score
+/// box and a caption, so that a theme can be looked at before it is applied.
+struct PreviewBoard {
+    theme: Arc<Theme>,
+    layout: LayoutSpec,
+    labels: Arc<LabelPack>,
+    score: u32,
+    caption: String,
+}
+
+/// A preview drawn on a canvas, which stays there until this is dropped.
+struct Preview {
+    #[cfg_attr(not(test), allow(dead_code))]
+    tiles: Vec<(Card, TextBuffer)>,
+    _score: Option<TextBuffer>,
+    _caption: Option<TextBuffer>,
+}
+
+impl PreviewBoard {
+    fn new(theme: Arc<Theme>) -> Self {
+        Self {
+            theme,
+            layout: LayoutSpec::classic(),
+            labels: Arc::new(LabelPack::builtin(BuiltinPack::Numbers)),
+            score: 0,
+            caption: String::new(),
+        }
+    }
+
+    fn with_labels(mut self, labels: Arc<LabelPack>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    fn with_layout(mut self, layout: LayoutSpec) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    fn with_score(mut self, score: u32) -> Self {
+        self.score = score;
+        self
+    }
+
+    fn with_caption(mut self, caption: String) -> Self {
+        self.caption = caption;
+        self
+    }
+
+    /// Where each sample tile goes on a canvas of the given size: in rows as long as the board's
+    /// where the board's tiles would be, leaving out the largest values when there isn't room for
+    /// them all.
+    /// The bottom line is kept clear for the caption.
+    fn tile_layout(&self, width: usize, height: usize) -> Vec<(Card, Rectangle)> {
+        let fits = |r: &Rectangle| r.extents().0 <= width && r.extents().1 < height;
+        let columns = (0..self.layout.columns)
+            .take_while(|x| fits(&self.layout.tile_rectangle(*x, 0, OVERLAY_LAYER_IDX)))
+            .count();
+        if columns == 0 {
+            return Vec::new();
+        }
+        (1..=WINNING_CARD)
+            .enumerate()
+            .map(|(i, card)| {
+                let r = self
+                    .layout
+                    .tile_rectangle(i % columns, i / columns, OVERLAY_LAYER_IDX);
+                (card, r)
+            })
+            .take_while(|(_, r)| fits(r))
+            .collect()
+    }
+
+    /// Draws the preview on the given canvas, which should be one of its own: the preview takes
+    /// up the cells the game's board and score would.
+    fn draw(&self, canvas: &Canvas) -> Result<Preview> {
+        let (width, height) = canvas.dimensions();
+        let mut tiles = Vec::new();
+        for (card, r) in self.tile_layout(width, height) {
+            let mut buf = canvas.get_text_buffer(r, Owner::Named("preview tile"))?;
+            Tui48Board::draw_tile(&mut buf, card, &self.labels, &self.theme)?;
+            tiles.push((card, buf));
+        }
+
+        let mut score_rectangle = Indicator::Score.rectangle_at(TOP_BAR_X);
+        score_rectangle.0 .2 = OVERLAY_LAYER_IDX;
+        let score = if score_rectangle.extents().0 <= width && score_rectangle.extents().1 < height
+        {
+            let mut buf = canvas.get_text_buffer(score_rectangle, Owner::Named("preview score"))?;
+            Tui48Board::draw_score_value(&mut buf, self.score)?;
+            Some(buf)
+        } else {
+            None
+        };
+
+        let caption = if width > 0 && height > 0 {
+            let r = Rectangle(Idx(0, height - 1, OVERLAY_LAYER_IDX), Bounds2D(width, 1));
+            let mut buf = canvas.get_text_buffer(r, Owner::Named("preview caption"))?;
+            buf.modify(Modifier::SetBackgroundColor(20, 20, 30));
+            buf.format(FormatOptions {
+                halign: HAlignment::Center,
+                valign: VAlignment::Top,
+            });
+            buf.clear()?;
+            buf.write(&self.caption, None, None)?;
+            buf.flush()?;
+            Some(buf)
+        } else {
+            None
+        };
+
+        Ok(Preview {
+            tiles,
+            _score: score,
+            _caption: caption,
+        })
+    }
+}
+
+static DEFAULT_THEME: OnceLock<Arc<Theme>> = OnceLock::new();
 
+pub(crate) fn init() -> Result<()> {
+    let _ = DEFAULT_THEME.get_or_init(|| Arc::new(Theme::builtin(BuiltinTheme::Classic)));
     Ok(())
 }
 
-#[inline(always)]
-fn colors_from_value(value: u8) -> (Modifier, Modifier) {
-    let (background, foreground) = DEFAULT_COLORS
+/// The theme tiles are colored with until another one is chosen.
+fn default_theme() -> Arc<Theme> {
+    DEFAULT_THEME
         .get()
-        .expect("DEFAULT_COLORS should always be initialized by this point")
-        .card_colors
-        .get(&value)
-        .unwrap_or(&(
-            Modifier::SetBackgroundColor(255, 255, 255),
-            Modifier::SetForegroundColor(90, 0, 0),
-        ));
-    (background.clone(), foreground.clone())
+        .expect("DEFAULT_THEME should always be initialized by this point")
+        .clone()
+}
+
+#[inline(always)]
+fn colors_from_card(card: Card) -> (Modifier, Modifier) {
+    default_theme().colors(card)
 }
 
 pub(crate) struct Tui48<R: Renderer, E: EventSource> {
@@ -855,51 +2621,621 @@ pub(crate) struct Tui48<R: Renderer, E: EventSource> {
     canvas: Canvas,
     board: Board,
     tui_board: Option<Tui48Board>,
+    outlook: Outlook,
+    estimate: Option<f32>,
+    bell: VisualBell,
+    session: Session,
+    frame_delay: Duration,
+    flash_delay: Duration,
+    enter_duration: Duration,
+    easing: EasingFn,
+    celebration_delay: Duration,
+    assist: Option<Assist>,
+    milestones: Milestones,
+    score_breakdown: bool,
+    breakdown_delay: Duration,
+    // the breakdown of the latest move while it's up
+    breakdown: Option<ScoreBreakdown>,
+    practice: Option<Profile>,
+    mode: Mode,
+    // whether the game being played started from a position built in the board editor
+    edited: bool,
+    keymap: Keymap,
+    watchdog: Option<WatchdogHandle>,
+    // tells the player something along the bottom of the screen, eg that animations are off
+    // while the terminal is too slow for them, along with what it says
+    note: Option<(String, TextBuffer)>,
+    // how finely moves are animated, and whether the player has been told it got coarser
+    quality: AdaptiveQuality,
+    quality_noted: bool,
+    // carried over from one Tui48Board to the next so the heatmap covers the whole session
+    tile_occupancy: Vec<Vec<u32>>,
+    heatmap: Option<Vec<TextBuffer>>,
+    // the packs the pack key cycles through and the one tiles are labelled with
+    label_packs: Vec<(PackChoice, Arc<LabelPack>)>,
+    label_pack: usize,
+    // the labels scripts give tiles in place of every pack's
+    label_overrides: Vec<(Card, String)>,
+    scripts: Scripts,
+    // the themes the preview offers and the one tiles are colored with
+    themes: Vec<Arc<Theme>>,
+    theme: usize,
+    // whether the lines between slots are shown
+    grid: bool,
+    layout: LayoutSpec,
+    persistence: Option<PersistenceHandle>,
+    // the best score as last saved, so that it's only saved again once beaten
+    saved_high_score: Score,
+    // what is saved when a preference changes, so that settings it was loaded with are kept
+    prefs: Preferences,
+    frame_timer: FrameTimer,
+    // whether the canvas was laid out anew since it was last rendered, and so is repainted whole
+    repaint: bool,
+    // whether the last frame rendered shows everything drawn so far, so that waiting for input
+    // needn't render another
+    settled: bool,
+    // input held for a later state, see `input_policy`
+    held_input: Option<UserInput>,
+    // the window title last handed to the renderer
+    title: Option<String>,
+    // whether the first game starts from a position built in the board editor
+    start_in_editor: bool,
+    // whether the game has been won as far as the player was told, so that they're only told
+    // once however long the game goes on
+    win_announced: bool,
+    // likewise for beating the best score the game is measured against
+    record_announced: bool,
+    // where the board editor writes positions down
+    position_file: Option<PathBuf>,
+    // where games are saved to and loaded from
+    save_file: Option<PathBuf>,
+    // where the game being played is left on quitting, and whether the one left there last time
+    // is still to be offered
+    resume_file: Option<PathBuf>,
+    offer_resume: bool,
+    // whether the terminal became too small because the board grew rather than because it was
+    // resized
+    outgrown: bool,
+    // whether the computer makes the moves, and how long it waits between them
+    autoplay: bool,
+    ai_delay: Duration,
+}
+
+/// How long the files written during a game get to be flushed once the player quits.
+const PERSIST_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Exit status when the game gives up on a terminal that stopped accepting output.
+const STUCK_TERMINAL_EXIT_CODE: i32 = 2;
+
+/// What only the command line says about a game in the terminal, besides the settings it
+/// overrides the config file's with.
+#[derive(Debug, Default)]
+pub(crate) struct LaunchOptions {
+    /// Whether the first game starts from a position built in the board editor.
+    pub(crate) edit: bool,
+    /// Whether to wrap frames in synchronized updates.
+    pub(crate) synchronized_updates: SyncMode,
+    /// Where every frame drawn is exported to, if anywhere.
+    pub(crate) export_frames: Option<PathBuf>,
+}
+
+impl Tui48<TeeRenderer<Crossterm<WatchedWriter>, FrameExportRenderer>, CrosstermEvents> {
+    /// Sets up a game in the terminal as described by the config file at the given path, or with
+    /// the default config if there's no such file, after letting `overrides` have their say. The
+    /// config is read before the terminal is switched into raw mode so that problems with it are
+    /// reported legibly.
+    pub(crate) fn new_from_config_file(
+        path: &Path,
+        overrides: impl FnOnce(&mut GameConfig),
+        options: LaunchOptions,
+    ) -> anyhow::Result<Self> {
+        let mut config = GameConfig::load(path)?;
+        overrides(&mut config);
+
+        let prefs_path = paths::prefs_file()?;
+        let prefs = Preferences::load(&prefs_path)?;
+        config.pack = config.pack.or(prefs.pack.clone());
+        config.square_tiles |= prefs.square_tiles.unwrap_or(false);
+
+        let keymap = Keymap::default();
+
+        // frames are written from a thread of their own so that a terminal that stops accepting
+        // output can't freeze the game
+        let watchdog = WatchdogHandle::new(config.render_deadlines());
+
+        // checked before anything switches the terminal into raw mode
+        let ((canvas, renderer), event_source) = match startup::validate(Ttys::detect())? {
+            RunPlan::Interactive => (
+                Canvas::new_from_writer(
+                    WatchedWriter::new(stdout(), watchdog.clone()),
+                    canvas_depth(config.visual_bell),
+                )?,
+                CrosstermEvents::default().with_keymap(keymap.clone()),
+            ),
+        };
+
+        // files written as the game goes are written on a background thread, which gets a
+        // chance to finish even if the game panics
+        let frames = match &options.export_frames {
+            Some(dir) => Some(Box::new(FrameDirectory::create(dir.clone())?) as Box<dyn FrameSink>),
+            None => None,
+        };
+        let high_score_path = paths::high_score_file()?;
+        let high_score = HighScore::load(&high_score_path)?;
+        let persistence = PersistenceHandle::background(Sinks {
+            prefs: Some(Box::new(FileSink::replacing(prefs_path))),
+            high_score: Some(Box::new(FileSink::replacing(high_score_path))),
+            stats: Some(Box::new(FileSink::appending(paths::stats_file()?))),
+            journal: Some(Box::new(FileSink::appending(paths::journal_file()?))),
+            frames,
+            ..Sinks::default()
+        });
+
+        // asked once the terminal is in raw mode, so that its replies aren't echoed
+        let renderer = renderer
+            .with_titles(!config.no_title)
+            .detect_capabilities(options.synchronized_updates, &event_source);
+        // so that bug reports say what the terminal could do
+        log::info!("{}", renderer.capabilities());
+        // exported frames are drawn from the whole canvas, whatever the terminal was sent
+        let exporter = options
+            .export_frames
+            .is_some()
+            .then(|| FrameExportRenderer::new(persistence.clone()));
+        let renderer = TeeRenderer::new(renderer, exporter);
+        let outlook = Outlook::new(config.outlook, event_source.sender());
+        let scripts = Scripts::load(
+            &paths::scripts_dir(),
+            &prefs.scripts.clone().unwrap_or_default(),
+        );
+
+        init()?;
+        let tui48 = Self::from_config(&config, renderer, event_source)?
+            .with_canvas(canvas)
+            .with_outlook(outlook)
+            .with_keymap(keymap.clone())
+            .with_watchdog(watchdog.clone())
+            .with_theme(prefs.theme.unwrap_or(BuiltinTheme::Classic))
+            .with_grid(prefs.grid.unwrap_or(false))
+            .with_scripts(scripts)
+            .with_preferences(prefs)
+            .with_persistence(persistence.clone())
+            .with_high_score(high_score)
+            .with_editor(options.edit)
+            .with_position_file(paths::position_file()?)
+            .with_save_file(paths::save_file()?)
+            .with_resume_file(paths::resume_file()?);
+
+        // the diagnostic goes to the log since stderr is usually the very terminal that got stuck
+        watchdog.monitor(|| {
+            emergency_recover();
+            std::process::exit(STUCK_TERMINAL_EXIT_CODE);
+        });
+
+        for action in keymap.unbound_essentials() {
+            log::warn!("no key is bound to {:?}", action);
+        }
+
+        persistence.install_panic_hook();
+
+        Ok(tui48)
+    }
 }
 
 impl<R: Renderer, E: EventSource> Tui48<R, E> {
     pub(crate) fn new(board: Board, renderer: R, event_source: E) -> Result<Self> {
-        let (width, height) = renderer.size_hint()?;
+        let (width, height) = renderer.size_hint().during(TerminalOperation::SizeHint)?;
+        let size = board.size();
+        let won = board.has_won();
         Ok(Self {
             board,
             renderer,
             event_source,
             canvas: Canvas::new(width as usize, height as usize),
             tui_board: None,
+            outlook: Outlook::disabled(),
+            estimate: None,
+            bell: VisualBell::disabled(),
+            session: Session::new(),
+            frame_delay: FRAME_DELAY,
+            flash_delay: FLASH_FRAME_DELAY,
+            enter_duration: TILE_ENTER_DURATION,
+            easing: EasingFn::Linear,
+            celebration_delay: CELEBRATION_FRAME_DELAY,
+            assist: None,
+            milestones: Milestones::new(),
+            score_breakdown: false,
+            breakdown_delay: SCORE_BREAKDOWN_FRAME_DELAY,
+            breakdown: None,
+            practice: None,
+            edited: false,
+            mode: Mode::Classic,
+            keymap: Keymap::default(),
+            watchdog: None,
+            note: None,
+            quality: AdaptiveQuality::default(),
+            quality_noted: false,
+            tile_occupancy: vec![vec![0; size.width]; size.height],
+            heatmap: None,
+            label_packs: BuiltinPack::ALL
+                .into_iter()
+                .map(|pack| {
+                    let labels = Arc::new(LabelPack::builtin(pack));
+                    (PackChoice::Builtin(pack), labels)
+                })
+                .collect(),
+            label_pack: 0,
+            themes: BuiltinTheme::ALL
+                .into_iter()
+                .map(|theme| Arc::new(Theme::builtin(theme)))
+                .collect(),
+            theme: 0,
+            grid: false,
+            layout: LayoutSpec::classic().with_size(size),
+            persistence: None,
+            saved_high_score: 0,
+            prefs: Preferences::default(),
+            frame_timer: FrameTimer::disabled(),
+            repaint: false,
+            settled: false,
+            held_input: None,
+            title: None,
+            start_in_editor: false,
+            win_announced: won,
+            record_announced: false,
+            position_file: None,
+            save_file: None,
+            resume_file: None,
+            offer_resume: false,
+            outgrown: false,
+            autoplay: false,
+            ai_delay: AI_DELAY,
+            label_overrides: Vec::new(),
+            scripts: Scripts::none(),
         })
     }
 
-    pub(crate) fn run(mut self) -> Result<()> {
-        let mut state = GameState::Active;
+    /// Draw on the given canvas, eg one created alongside the renderer to match the terminal.
+    pub(crate) fn with_canvas(mut self, canvas: Canvas) -> Self {
+        self.canvas = canvas;
+        self
+    }
+
+    /// Use the given outlook to estimate the chance of winning after every move.
+    pub(crate) fn with_outlook(mut self, outlook: Outlook) -> Self {
+        self.outlook = outlook;
+        self
+    }
+
+    /// Label tiles with the given pack. A pack read from a file joins the built-in packs the pack
+    /// key cycles through.
+    pub(crate) fn with_labels(mut self, choice: PackChoice, labels: LabelPack) -> Self {
+        self.label_pack = match self.label_packs.iter().position(|(c, _)| *c == choice) {
+            Some(i) => i,
+            None => {
+                let labels = labels.relabeled(&self.label_overrides);
+                self.label_packs.push((choice, Arc::new(labels)));
+                self.label_packs.len() - 1
+            }
+        };
+        self
+    }
+
+    /// Run the given scripts, which may veto moves and relabel tiles whatever the pack.
+    pub(crate) fn with_scripts(mut self, mut scripts: Scripts) -> Self {
+        self.label_overrides = scripts.tile_labels();
+        for (_, labels) in self.label_packs.iter_mut() {
+            *labels = Arc::new(labels.relabeled(&self.label_overrides));
+        }
+        self.scripts = scripts;
+        self
+    }
+
+    /// Color tiles with the given theme.
+    pub(crate) fn with_theme(mut self, theme: BuiltinTheme) -> Self {
+        self.theme = self
+            .themes
+            .iter()
+            .position(|t| t.theme() == theme)
+            .expect("every built-in theme is offered");
+        self
+    }
+
+    /// Whether to show lines between the board's slots.
+    pub(crate) fn with_grid(mut self, shown: bool) -> Self {
+        self.grid = shown;
+        self
+    }
+
+    /// Lay the board out with tiles of the given sizes.
+    pub(crate) fn with_layout(mut self, layout: LayoutSpec) -> Self {
+        self.layout = layout.with_size(self.board.size());
+        self
+    }
+
+    /// Start from the given preferences when saving one changed while playing, so that the rest
+    /// are written back as they were loaded.
+    pub(crate) fn with_preferences(mut self, prefs: Preferences) -> Self {
+        self.prefs = prefs;
+        self
+    }
+
+    /// Save preferences changed while playing, such as the label pack, with the given handle.
+    pub(crate) fn with_persistence(mut self, persistence: PersistenceHandle) -> Self {
+        self.persistence = Some(persistence);
+        self
+    }
+
+    /// Measure every game against the given high score, saving it with the persistence handle
+    /// whenever a game beats it.
+    pub(crate) fn with_high_score(mut self, high_score: HighScore) -> Self {
+        self.saved_high_score = high_score.best();
+        self.board.set_high_score(high_score);
+        self
+    }
+
+    /// Time every animation frame with the given timer.
+    pub(crate) fn with_frame_timer(mut self, frame_timer: FrameTimer) -> Self {
+        self.frame_timer = frame_timer;
+        self
+    }
+
+    /// Use the given bell to flash the screen when a notification fires.
+    pub(crate) fn with_bell(mut self, bell: VisualBell) -> Self {
+        self.bell = bell;
+        self
+    }
+
+    /// Use the given assist, if any, after every move.
+    pub(crate) fn with_assist(mut self, assist: Option<Assist>) -> Self {
+        self.assist = assist;
+        self
+    }
+
+    /// Whether to briefly list the score each merge contributed after moves with several merges.
+    pub(crate) fn with_score_breakdown(mut self, enabled: bool) -> Self {
+        self.score_breakdown = enabled;
+        self
+    }
+
+    /// Start new games from practice positions of the given profile, if any, rather than from an
+    /// empty board. This only affects new games; the board passed to `new` is played as is.
+    pub(crate) fn with_practice(mut self, profile: Option<Profile>) -> Self {
+        if profile.is_some() {
+            self.session = Session::practice();
+        }
+        self.practice = profile;
+        self
+    }
+
+    /// Play new games by the rules of the given mode. Like practice, this only affects new games;
+    /// the board passed to `new` is played as is.
+    pub(crate) fn with_mode(mut self, mode: Mode) -> Self {
+        match mode {
+            Mode::Classic => (),
+            Mode::Arcade => self.session.set_arcade(),
+            Mode::Growth => self.session.set_growth(),
+        }
+        self.mode = mode;
+        self
+    }
+
+    /// Refer to keys in prompts as bound by the given keymap. It should be the keymap the event
+    /// source maps key presses with.
+    pub(crate) fn with_keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
+    /// Stop animating if the given watchdog finds the terminal too slow to keep up.
+    /// Opens the board editor before the first game, to start it from a position of the player's
+    /// making.
+    pub(crate) fn with_editor(mut self, enabled: bool) -> Self {
+        self.start_in_editor = enabled;
+        self
+    }
+
+    /// Has the board editor write positions down to the given file.
+    pub(crate) fn with_position_file(mut self, path: PathBuf) -> Self {
+        self.position_file = Some(path);
+        self
+    }
+
+    /// Saves games to the given file, and loads them from it.
+    pub(crate) fn with_save_file(mut self, path: PathBuf) -> Self {
+        self.save_file = Some(path);
+        self
+    }
+
+    /// Leaves the game being played in the given file on quitting, and offers to resume the one
+    /// left there last time on starting.
+    pub(crate) fn with_resume_file(mut self, path: PathBuf) -> Self {
+        self.resume_file = Some(path);
+        self
+    }
+
+    /// Has the computer wait the given time between its moves while it plays.
+    pub(crate) fn with_ai_delay(mut self, delay: Duration) -> Self {
+        self.ai_delay = delay;
+        self
+    }
+
+    pub(crate) fn with_watchdog(mut self, watchdog: WatchdogHandle) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// Animate moves at the given quality however fast the terminal turns out to be, rather than
+    /// adapting to it.
+    pub(crate) fn with_animation_quality(mut self, level: QualityLevel) -> Self {
+        self.quality = AdaptiveQuality::pinned(level);
+        self
+    }
+
+    /// Animate moves at the given speed: moves and tiles entering the board take longer or
+    /// shorter, or, turned off, moves are shown in one go.
+    pub(crate) fn with_animation_speed(mut self, speed: AnimationSpeed) -> Self {
+        match (speed.scale(FRAME_DELAY), speed.scale(TILE_ENTER_DURATION)) {
+            (Some(frame_delay), Some(enter_duration)) => {
+                self.frame_delay = frame_delay;
+                self.enter_duration = enter_duration;
+            }
+            _ => self.quality = AdaptiveQuality::pinned(QualityLevel::Instant),
+        }
+        self
+    }
+
+    /// Slide tiles along the given curve rather than one cell every frame.
+    pub(crate) fn with_easing(mut self, easing: EasingFn) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Sets up the game the given config describes. The outlook isn't part of it since it needs a
+    /// way to post events to the event source.
+    pub(crate) fn from_config(config: &GameConfig, renderer: R, event_source: E) -> Result<Self> {
+        let mode = config.mode.unwrap_or_default();
+        let size = match mode {
+            Mode::Growth => config.size.unwrap_or_else(BoardConfig::growing),
+            _ => config.size.unwrap_or_default(),
+        };
+        if config.practice.is_some() && !size.is_classic() {
+            return Err(Error::PracticeNeedsClassicBoard(size));
+        }
+        // games are always dealt from a seed, so that any of them can be shared
+        let seed = config.seed.unwrap_or_else(|| thread_rng().gen());
+        let board = match config.practice {
+            Some(profile) => Board::practice_position_seeded(seed, profile)?,
+            None => Board::new_seeded(seed, size),
+        };
+        let board = match mode {
+            Mode::Classic => board,
+            Mode::Arcade => board.with_power_ups(),
+            Mode::Growth => board.with_growth(&BoardConfig::GROWTH_MILESTONES),
+        };
+        let mut tui48 = Self::new(board, renderer, event_source)?
+            .with_bell(VisualBell::new(config.visual_bell))
+            .with_assist(config.assist)
+            .with_score_breakdown(config.score_breakdown)
+            .with_practice(config.practice)
+            .with_mode(mode);
+        if let Some(level) = config.animation_quality {
+            tui48 = tui48.with_animation_quality(level);
+        }
+        if let Some(speed) = config.animation_speed {
+            tui48 = tui48.with_animation_speed(speed);
+        }
+        if let Some(easing) = config.easing {
+            tui48 = tui48.with_easing(easing);
+        }
+        if config.square_tiles {
+            let aspect = config.cell_aspect.unwrap_or(DEFAULT_CELL_ASPECT);
+            tui48 = tui48.with_layout(LayoutSpec::square(aspect)?);
+        }
+        if let Some(choice) = &config.pack {
+            tui48 = tui48.with_labels(choice.clone(), LabelPack::load(choice)?);
+        }
+        if let Some(ms) = config.ai_delay_ms {
+            tui48 = tui48.with_ai_delay(Duration::from_millis(ms));
+        }
+        Ok(tui48)
+    }
+
+    /// Takes control of the terminal and plays until the player quits, restoring the terminal and
+    /// flushing the files written during the games before returning statistics for them.
+    pub(crate) fn run(mut self) -> Result<Session> {
+        let played = self.play();
+        if let Some(persistence) = &self.persistence {
+            persistence.shutdown(PERSIST_SHUTDOWN_TIMEOUT);
+        }
+        played?;
+        Ok(self.session)
+    }
+
+    /// Plays until the player quits. The terminal is restored exactly once however this returns.
+    fn play(&mut self) -> Result<()> {
+        let needed = canvas_depth(self.bell.is_enabled());
+        if self.canvas.depth() < needed {
+            self.renderer.recover();
+            return Err(TuiError::from(InnerError::CanvasTooShallow {
+                requested: needed,
+                available: self.canvas.depth(),
+            })
+            .into());
+        }
+        self.refresh_outlook();
+        self.offer_resume =
+            !self.start_in_editor && self.resume_file.as_ref().is_some_and(|path| path.exists());
+        let mut state = match (self.start_in_editor, self.offer_resume) {
+            (true, _) => GameState::Editor,
+            (false, true) => GameState::Resume,
+            (false, false) => GameState::Active,
+        };
         loop {
             state = match state {
-                GameState::Quit => return Ok(()),
-                GameState::Reset => self.reset()?,
-                GameState::TerminalTooSmall => match self.run_terminal_too_small() {
-                    Err(e) => {
-                        self.renderer.recover();
-                        return Err(e);
+                GameState::Quit => {
+                    self.leave_for_later();
+                    self.session.abandon_game(self.board.score());
+                    self.session.close();
+                    self.renderer.recover();
+                    if let Some(persistence) = &self.persistence {
+                        persistence.submit(PersistEvent::StatsUpdate(self.session.summary()));
                     }
+                    return Ok(());
+                }
+                GameState::Reset => match self.reset() {
+                    Err(e) => return self.give_up(e),
+                    Ok(state) => state,
+                },
+                GameState::TerminalTooSmall => match self.run_terminal_too_small() {
+                    Err(e) => return self.give_up(e),
                     Ok(state) => state,
                 },
                 GameState::Active => match self.run_game_active() {
-                    Err(e) => {
-                        self.renderer.recover();
-                        return Err(e);
-                    }
+                    Err(e) => return self.give_up(e),
+                    Ok(state) => state,
+                },
+                GameState::Won => match self.run_won() {
+                    Err(e) => return self.give_up(e),
                     Ok(state) => state,
                 },
                 GameState::Over => match self.run_game_over() {
+                    Err(e) => return self.give_up(e),
+                    Ok(state) => state,
+                },
+                GameState::Replay => match self.run_replay() {
                     Err(e) => {
                         self.renderer.recover();
                         return Err(e);
                     }
                     Ok(state) => state,
                 },
+                GameState::ThemePreview => match self.run_theme_preview() {
+                    Err(e) => return self.give_up(e),
+                    Ok(state) => state,
+                },
+                GameState::Editor => match self.run_editor() {
+                    Err(e) => return self.give_up(e),
+                    Ok(state) => state,
+                },
+                GameState::Resume => match self.run_resume() {
+                    Err(e) => return self.give_up(e),
+                    Ok(state) => state,
+                },
             }
         }
     }
 
+    /// Gives up on the game over the given error: drops the board, which may have been left
+    /// halfway through an animation, sweeps up any cells left behind on the canvas so that it
+    /// can be drawn on again, and restores the terminal.
+    fn give_up(&mut self, e: Error) -> Result<()> {
+        self.tui_board = None;
+        self.canvas.sanitize();
+        self.renderer.recover();
+        Err(e)
+    }
+
     /// Run consumes the Tui48 instance and takes control of the terminal to begin gameplay.
     fn run_game_active(&mut self) -> Result<GameState> {
         self.tui_board = match self.resize()? {
@@ -908,91 +3244,607 @@ impl<R: Renderer, E: EventSource> Tui48<R, E> {
         };
 
         loop {
-            self.renderer.render(&self.canvas)?;
+            // a board grown too large for the terminal waits for it to be made larger
+            if self.tui_board.is_none() {
+                return Ok(GameState::TerminalTooSmall);
+            }
+            self.warn_if_slow()?;
+            self.show_script_notices()?;
+            self.note_autoplay()?;
+            self.update_title()?;
+            // a move ends on a frame of its own
+            if !std::mem::take(&mut self.settled) {
+                self.render()?;
+            }
             log::trace!("rendered, waiting for input");
-            match self.event_source.next_event()? {
+            let event = match (self.breakdown.is_some(), self.autoplay) {
+                // the score breakdown fades out a frame at a time until something happens
+                (true, _) => match self.poll_event_in(GameState::Active, self.breakdown_delay)? {
+                    Some(event) => event,
+                    None => {
+                        self.fade_score_breakdown()?;
+                        continue;
+                    }
+                },
+                (false, false) => self.next_event_in(GameState::Active)?,
+                // the computer moves unless the player presses something in the meantime
+                (false, true) => match self.poll_event_in(GameState::Active, self.ai_delay)? {
+                    Some(event) => event,
+                    None => match Strategy::Greedy.choose(&self.board.current()) {
+                        Some(d) => {
+                            Event::UserInput(UserInput::Direction(Direction::from_board(&d)))
+                        }
+                        None => {
+                            self.toggle_autoplay()?;
+                            continue;
+                        }
+                    },
+                },
+            };
+            // any key dismisses the score breakdown, going on to do what it does
+            if matches!(event, Event::UserInput(_)) {
+                self.breakdown = None;
+            }
+            // any key dismisses the heatmap without doing anything else
+            if matches!(event, Event::UserInput(_)) && self.heatmap.take().is_some() {
+                continue;
+            }
+            match event {
                 Event::UserInput(UserInput::Direction(d)) => {
                     let game_over = self.shift(d)?;
-                    if game_over {
-                        return Ok(GameState::Over);
+                    self.grow()?;
+                    match self.state_after_move(game_over) {
+                        GameState::Active => (),
+                        state => return Ok(state),
                     }
                 }
+                Event::UserInput(UserInput::Replay) => match self.board.move_count() {
+                    0 => self.notify(Notification::InvalidMove)?,
+                    _ => return Ok(GameState::Replay),
+                },
                 Event::UserInput(UserInput::NewGame) => return Ok(GameState::Reset),
                 Event::UserInput(UserInput::Quit) => break,
-                Event::Resize => {
+                Event::UserInput(UserInput::ShowHeatmap) => self.show_heatmap()?,
+                Event::UserInput(UserInput::PreviewThemes) => return Ok(GameState::ThemePreview),
+                Event::UserInput(UserInput::EditBoard) => return Ok(GameState::Editor),
+                Event::UserInput(UserInput::SaveGame) => {
+                    let note = self.save_game();
+                    self.show_note(&note)?;
+                }
+                Event::UserInput(UserInput::LoadGame) => match self.load_game()? {
+                    Some(GameState::Active) | None => (),
+                    Some(state) => return Ok(state),
+                },
+                Event::UserInput(UserInput::ToggleAutoPlay) => self.toggle_autoplay()?,
+                // dropped by the input policy, see `run_editor`
+                Event::UserInput(
+                    UserInput::Increase
+                    | UserInput::Decrease
+                    | UserInput::Digit(_)
+                    | UserInput::Erase
+                    | UserInput::WriteNotation
+                    | UserInput::Continue,
+                ) => (),
+                Event::UserInput(UserInput::Confirm | UserInput::Cancel) => (),
+                Event::UserInput(UserInput::PowerUp) => {
+                    let game_over = self.use_power_up()?;
+                    match self.state_after_move(game_over) {
+                        GameState::Active => (),
+                        state => return Ok(state),
+                    }
+                }
+                Event::UserInput(UserInput::ToggleGrid) => self.toggle_grid()?,
+                Event::UserInput(UserInput::Undo) => {
+                    if let Some(plan) = self.undo()? {
+                        self.show_undo(&plan)?;
+                    }
+                }
+                Event::UserInput(UserInput::Redo) => {
+                    let game_over = self.redo()?;
+                    match self.state_after_move(game_over) {
+                        GameState::Active => (),
+                        state => return Ok(state),
+                    }
+                }
+                Event::UserInput(UserInput::CyclePack) => {
+                    self.cycle_label_pack();
                     self.tui_board = match self.resize()? {
                         Some(tb) => Some(tb),
                         None => return Ok(GameState::TerminalTooSmall),
                     };
                 }
+                Event::Resize => {
+                    self.tui_board = match self.resize()? {
+                        Some(tb) => Some(tb),
+                        None => return Ok(GameState::TerminalTooSmall),
+                    };
+                }
+                Event::Estimate {
+                    generation,
+                    estimate,
+                } => self.update_estimate(generation, estimate)?,
             }
         }
         Ok(GameState::Quit)
     }
 
-    fn run_game_over(&mut self) -> Result<GameState> {
-        self.resize()?;
+    /// Returns the state the game is in after a move, given whether the move ended it: over if
+    /// it did, won if it made the first 2048 of the game, and still going on otherwise.
+    fn state_after_move(&mut self, game_over: bool) -> GameState {
+        if game_over {
+            return GameState::Over;
+        }
+        if self.win_announced || !self.board.has_won() {
+            return GameState::Active;
+        }
+        self.win_announced = true;
+        GameState::Won
+    }
+
+    /// Shows that the game is won over the board until the player chooses to keep playing or to
+    /// leave it.
+    fn run_won(&mut self) -> Result<GameState> {
+        self.tui_board = match self.resize()? {
+            Some(tb) => Some(tb),
+            None => return Ok(GameState::TerminalTooSmall),
+        };
+
+        if let Some(tui_board) = &self.tui_board {
+            let mut message_rectangle = tui_board.board.rectangle().shrink_by(5, 8);
+            message_rectangle.0 .2 = OVERLAY_LAYER_IDX;
+            let mut buf = self
+                .canvas
+                .get_text_buffer(message_rectangle, Owner::Named("message"))?;
+            buf.clear()?;
+            buf.write(&self.keymap.render(WIN_PROMPT), None, None)?;
+            buf.flush()?;
+            buf.modify(Modifier::SetBackgroundColor(90, 70, 10));
+            self.warn_if_slow()?;
+            self.show_script_notices()?;
+            self.render()?;
+            match self.next_event_in(GameState::Won)? {
+                Event::UserInput(UserInput::Continue) => return Ok(GameState::Active),
+                // the move is made as soon as the game goes on
+                Event::UserInput(input @ UserInput::Direction(_)) => {
+                    self.held_input = Some(input);
+                    return Ok(GameState::Active);
+                }
+                Event::UserInput(UserInput::NewGame) => return Ok(GameState::Reset),
+                Event::UserInput(UserInput::Quit) => return Ok(GameState::Quit),
+                Event::UserInput(UserInput::CyclePack) => self.cycle_label_pack(),
+                Event::UserInput(UserInput::ToggleGrid) => self.toggle_grid()?,
+                // dropped by the input policy
+                Event::UserInput(
+                    UserInput::ShowHeatmap
+                    | UserInput::PreviewThemes
+                    | UserInput::Confirm
+                    | UserInput::Cancel
+                    | UserInput::PowerUp
+                    | UserInput::EditBoard
+                    | UserInput::Increase
+                    | UserInput::Decrease
+                    | UserInput::Digit(_)
+                    | UserInput::Erase
+                    | UserInput::WriteNotation
+                    | UserInput::Undo
+                    | UserInput::Redo
+                    | UserInput::Replay
+                    | UserInput::SaveGame
+                    | UserInput::LoadGame
+                    | UserInput::ToggleAutoPlay,
+                ) => (),
+                // the overlay is laid out afresh below
+                Event::Resize => (),
+                Event::Estimate {
+                    generation,
+                    estimate,
+                } => self.update_estimate(generation, estimate)?,
+            }
+        }
+
+        // the overlay is drawn again, with whatever changed
+        Ok(GameState::Won)
+    }
+
+    /// Asks whether to go on with the game left last time before anything else is played.
+    fn run_resume(&mut self) -> Result<GameState> {
+        self.tui_board = match self.resize()? {
+            Some(tb) => Some(tb),
+            None => return Ok(GameState::TerminalTooSmall),
+        };
 
         if let Some(tui_board) = &self.tui_board {
+            let mut message_rectangle = tui_board.board.rectangle().shrink_by(5, 8);
+            message_rectangle.0 .2 = OVERLAY_LAYER_IDX;
+            let mut buf = self
+                .canvas
+                .get_text_buffer(message_rectangle, Owner::Named("message"))?;
+            buf.clear()?;
+            buf.write(&self.keymap.render(RESUME_PROMPT), None, None)?;
+            buf.flush()?;
+            buf.modify(Modifier::SetBackgroundColor(40, 70, 90));
+            self.warn_if_slow()?;
+            self.show_script_notices()?;
+            self.render()?;
+            match self.next_event_in(GameState::Resume)? {
+                Event::UserInput(UserInput::Confirm) => return self.resume(),
+                Event::UserInput(UserInput::NewGame) => {
+                    self.offer_resume = false;
+                    if let Some(path) = &self.resume_file {
+                        remove_if_present(path);
+                    }
+                    return Ok(GameState::Active);
+                }
+                // the game left last time is offered again next time
+                Event::UserInput(UserInput::Quit) => return Ok(GameState::Quit),
+                // dropped by the input policy
+                Event::UserInput(
+                    UserInput::Direction(_)
+                    | UserInput::ShowHeatmap
+                    | UserInput::CyclePack
+                    | UserInput::PreviewThemes
+                    | UserInput::Cancel
+                    | UserInput::PowerUp
+                    | UserInput::ToggleGrid
+                    | UserInput::EditBoard
+                    | UserInput::Increase
+                    | UserInput::Decrease
+                    | UserInput::Digit(_)
+                    | UserInput::Erase
+                    | UserInput::WriteNotation
+                    | UserInput::Undo
+                    | UserInput::Redo
+                    | UserInput::Continue
+                    | UserInput::SaveGame
+                    | UserInput::LoadGame
+                    | UserInput::ToggleAutoPlay
+                    | UserInput::Replay,
+                ) => (),
+                // the overlay is laid out afresh below
+                Event::Resize => (),
+                Event::Estimate {
+                    generation,
+                    estimate,
+                } => self.update_estimate(generation, estimate)?,
+            }
+        }
+
+        Ok(GameState::Resume)
+    }
+
+    /// Plays the game left last time in place of the one just started, returning the state it's
+    /// in. The file it was left in is done with either way; the player is told if it couldn't be
+    /// read.
+    fn resume(&mut self) -> Result<GameState> {
+        self.offer_resume = false;
+        let path = match &self.resume_file {
+            Some(path) => path.clone(),
+            None => return Ok(GameState::Active),
+        };
+        let loaded = Board::load_from(&path);
+        remove_if_present(&path);
+        let board = match loaded {
+            Ok(Some(board)) => board,
+            Ok(None) => return Ok(GameState::Active),
+            Err(e) => {
+                self.show_note(&e.to_string())?;
+                return Ok(GameState::Active);
+            }
+        };
+        self.start(board)?;
+        match self.board.is_game_over() {
+            true => Ok(GameState::Over),
+            false => Ok(GameState::Active),
+        }
+    }
+
+    /// Leaves the game being played in the resume file on quitting, unless there's nothing in it
+    /// worth coming back to: a lost game is cleared away, and one that hasn't moved yet leaves
+    /// whatever was left before alone. Failing to leave it is only logged since the player is on
+    /// their way out.
+    fn leave_for_later(&self) {
+        let path = match &self.resume_file {
+            Some(path) => path,
+            None => return,
+        };
+        if self.board.is_game_over() {
+            remove_if_present(path);
+        } else if self.board.move_count() > 0 {
+            if let Err(e) = self.board.save_to(path) {
+                log::error!("unable to leave the game in {:?}: {}", path, e);
+            }
+        }
+    }
+
+    fn run_game_over(&mut self) -> Result<GameState> {
+        // the computer doesn't go on to play the next game, or one taken back to
+        self.autoplay = false;
+        if self.resize()?.is_none() {
+            return Ok(GameState::TerminalTooSmall);
+        }
+
+        if let Some(tui_board) = &mut self.tui_board {
+            tui_board.dim_tiles();
             let board_rectangle = tui_board.board.rectangle();
             let message_rectangle = board_rectangle.shrink_by(5, 8);
-            let mut buf = self.canvas.get_text_buffer(message_rectangle)?;
+            let mut buf = self
+                .canvas
+                .get_text_buffer(message_rectangle, Owner::Named("message"))?;
             buf.clear()?;
-            buf.write(
-                "game over! press 'q' to quit or 'n' to start new game",
-                None,
-                None,
-            );
+            let prompt = match self.board.can_undo() {
+                true => GAME_OVER_UNDO_PROMPT,
+                false => GAME_OVER_PROMPT,
+            };
+            let prompt = self
+                .keymap
+                .render(prompt)
+                .replace("{score}", &self.board.score().to_string())
+                .replace("{moves}", &self.board.move_count().to_string());
+            buf.write(&prompt, None, None)?;
             buf.flush()?;
+            self.warn_if_slow()?;
+            self.show_script_notices()?;
+            self.render()?;
+            match self.next_event_in(GameState::Over)? {
+                // looking back over how the game got lost
+                Event::UserInput(UserInput::Replay) => return Ok(GameState::Replay),
+                Event::UserInput(UserInput::NewGame) => return Ok(GameState::Reset),
+                Event::UserInput(UserInput::Quit) => return Ok(GameState::Quit),
+                Event::UserInput(UserInput::EditBoard) => return Ok(GameState::Editor),
+                // the game goes on from before the last move, on a board built afresh
+                Event::UserInput(UserInput::Undo) => {
+                    if self.undo()?.is_some() {
+                        return Ok(GameState::Active);
+                    }
+                }
+                Event::UserInput(UserInput::LoadGame) => {
+                    if let Some(state) = self.load_game()? {
+                        return Ok(state);
+                    }
+                }
+                // held or dropped by the input policy
+                Event::UserInput(
+                    UserInput::Direction(_)
+                    | UserInput::ShowHeatmap
+                    | UserInput::PreviewThemes
+                    | UserInput::Confirm
+                    | UserInput::Cancel
+                    | UserInput::PowerUp
+                    | UserInput::Increase
+                    | UserInput::Decrease
+                    | UserInput::Digit(_)
+                    | UserInput::Erase
+                    | UserInput::WriteNotation
+                    | UserInput::Redo
+                    | UserInput::Continue
+                    | UserInput::SaveGame
+                    | UserInput::ToggleAutoPlay,
+                ) => (),
+                Event::UserInput(UserInput::CyclePack) => self.cycle_label_pack(),
+                Event::UserInput(UserInput::ToggleGrid) => self.toggle_grid()?,
+                Event::Resize => {
+                    self.tui_board = match self.resize()? {
+                        Some(tb) => Some(tb),
+                        None => return Ok(GameState::TerminalTooSmall),
+                    };
+                }
+                Event::Estimate {
+                    generation,
+                    estimate,
+                } => self.update_estimate(generation, estimate)?,
+            }
+        }
+
+        // the overlay is drawn again, with whatever changed
+        Ok(GameState::Over)
+    }
+
+    /// Steps back and forth through the moves made so far, each one animated faster than it was
+    /// played so that holding a key down scrubs through them. However the replay is left, the moves
+    /// it took back are made again first, so the game goes on from where it was.
+    fn run_replay(&mut self) -> Result<GameState> {
+        let mut taken = Vec::new();
+        let state = self.replay(&mut taken);
+        while let Some(taken_move) = taken.pop() {
+            self.board.put_back(taken_move);
+        }
+        let state = state?;
+        // the prompt is outside the board, which is drawn afresh by whatever comes next
+        self.renderer.clear(&self.canvas)?;
+        // the replay may have been left before a growth it took back
+        if self.board.size() != self.layout.size() {
+            self.tui_board = match self.resize()? {
+                Some(tb) => Some(tb),
+                None => return Ok(GameState::TerminalTooSmall),
+            };
+        }
+        match state {
+            GameState::Active if self.board.is_game_over() => Ok(GameState::Over),
+            state => Ok(state),
+        }
+    }
+
+    /// Runs the replay for `run_replay`, keeping the moves it takes back in the given list as it
+    /// goes, and returns the state to go to once they're made again; active stands for going back
+    /// to the game, whether or not it's over.
+    fn replay(&mut self, taken: &mut Vec<TakenMove>) -> Result<GameState> {
+        self.tui_board = match self.resize()? {
+            Some(tb) => Some(tb),
+            None => return Ok(GameState::TerminalTooSmall),
+        };
+        let moves = self.board.move_count();
+
+        loop {
+            // held until the next step has been shown
+            let _prompt = self.show_replay_prompt(self.board.move_count(), moves)?;
             self.renderer.render(&self.canvas)?;
-            match self.event_source.next_event()? {
-                Event::UserInput(UserInput::Direction(d)) => {
-                    let game_over = self.shift(d)?;
-                    if game_over {
-                        return Ok(GameState::Over);
+            match self.next_event_in(GameState::Replay)? {
+                Event::UserInput(UserInput::Direction(Direction::Left) | UserInput::Undo) => {
+                    match self.board.rewind_plan() {
+                        Some(plan) => {
+                            let taken_move = self.board.take_back().expect("a move was made");
+                            taken.push(taken_move);
+                            self.show_replay_step(|tui_board| tui_board.setup_rewind(&plan))?;
+                        }
+                        None => self.notify(Notification::InvalidMove)?,
+                    }
+                    if self.tui_board.is_none() {
+                        return Ok(GameState::TerminalTooSmall);
                     }
                 }
-                Event::UserInput(UserInput::NewGame) => return Ok(GameState::Reset),
+                // only what the replay took back is made again
+                Event::UserInput(UserInput::Direction(Direction::Right) | UserInput::Redo) => {
+                    match taken.pop() {
+                        Some(taken_move) => {
+                            let hint = self.board.put_back(taken_move);
+                            self.show_replay_step(|tui_board| tui_board.setup_animation(&hint))?;
+                        }
+                        None => self.notify(Notification::InvalidMove)?,
+                    }
+                    if self.tui_board.is_none() {
+                        return Ok(GameState::TerminalTooSmall);
+                    }
+                }
+                Event::UserInput(UserInput::Replay | UserInput::Confirm | UserInput::Cancel) => {
+                    return Ok(GameState::Active)
+                }
                 Event::UserInput(UserInput::Quit) => return Ok(GameState::Quit),
+                // dropped by the input policy
+                Event::UserInput(
+                    UserInput::Direction(_)
+                    | UserInput::NewGame
+                    | UserInput::ShowHeatmap
+                    | UserInput::CyclePack
+                    | UserInput::PreviewThemes
+                    | UserInput::PowerUp
+                    | UserInput::ToggleGrid
+                    | UserInput::EditBoard
+                    | UserInput::Increase
+                    | UserInput::Decrease
+                    | UserInput::Digit(_)
+                    | UserInput::Erase
+                    | UserInput::WriteNotation
+                    | UserInput::Continue
+                    | UserInput::SaveGame
+                    | UserInput::LoadGame
+                    | UserInput::ToggleAutoPlay,
+                ) => (),
                 Event::Resize => {
                     self.tui_board = match self.resize()? {
                         Some(tb) => Some(tb),
                         None => return Ok(GameState::TerminalTooSmall),
                     };
                 }
+                Event::Estimate {
+                    generation,
+                    estimate,
+                } => self.update_estimate(generation, estimate)?,
             }
         }
+    }
 
-        Ok(GameState::Active)
+    /// Shows where the replay is along the bottom of the screen for as long as the returned buffer
+    /// is held.
+    fn show_replay_prompt(&mut self, shown: usize, moves: usize) -> Result<TextBuffer> {
+        let (width, height) = self.canvas.dimensions();
+        let r = Rectangle(Idx(0, height - 1, BOARD_LAYER_IDX), Bounds2D(width, 1));
+        let mut buf = self
+            .canvas
+            .get_text_buffer(r, Owner::Named("replay prompt"))?;
+        buf.format(FormatOptions {
+            halign: HAlignment::Center,
+            valign: VAlignment::Top,
+        });
+        buf.clear()?;
+        let prompt = self
+            .keymap
+            .render(REPLAY_PROMPT)
+            .replace("{move}", &shown.to_string())
+            .replace("{moves}", &moves.to_string());
+        buf.write(&prompt, None, None)?;
+        buf.flush()?;
+        Ok(buf)
+    }
+
+    /// Plays a step of the replay, set up on the board by the given function, with the panels
+    /// showing the round stepped to. A growth stepped over moves no tiles, the board only changes
+    /// size; see `show_size_change`.
+    fn show_replay_step(
+        &mut self,
+        setup: impl FnOnce(&mut Tui48Board) -> Result<()>,
+    ) -> Result<()> {
+        if self.board.size() != self.layout.size() {
+            return self.show_size_change();
+        }
+        let mut tui_board = self
+            .tui_board
+            .take()
+            .expect("why wouldn't we have a tui board at this point?");
+        tui_board.draw_score(&self.board)?;
+        tui_board.update_move_count(self.board.move_count())?;
+        tui_board.draw_pressure(&self.board)?;
+        tui_board.draw_max_tile(&self.board)?;
+        tui_board.draw_power_ups(&self.board)?;
+        setup(&mut tui_board)?;
+        self.play_animation(&mut tui_board, self.frame_delay / REPLAY_SPEEDUP)?;
+        tui_board.settle(&self.board.current())?;
+        tui_board.mark_merge(self.merge_assist())?;
+        let _ = self.tui_board.replace(tui_board);
+        Ok(())
     }
 
     fn run_terminal_too_small(&mut self) -> Result<GameState> {
-        self.renderer.clear(&self.canvas)?;
+        self.clear()?;
         loop {
             let (c_width, c_height) = self.canvas.dimensions();
             let canvas_rectangle = Rectangle(Idx(0, 0, 0), Bounds2D(c_width, c_height));
             let message_rectangle = canvas_rectangle.shrink_by(2, 2);
-            let mut buf = self.canvas.get_text_buffer(message_rectangle)?;
+            let mut buf = self
+                .canvas
+                .get_text_buffer(message_rectangle, Owner::Named("message"))?;
             buf.clear()?;
+            let (min_width, min_height) = LayoutRequirements::new(&self.layout).need();
+            let grew = match self.outgrown {
+                true => format!("the board grew to {} and ", self.layout.size()),
+                false => String::new(),
+            };
             buf.write(
-                "the terminal is too small, please make it bigger!",
+                &format!(
+                    "{}the terminal is too small at {} x {}, please make it at least {} x {}!",
+                    grew, c_width, c_height, min_width, min_height
+                ),
                 None,
                 None,
-            );
+            )?;
             buf.flush()?;
-            self.renderer.render(&self.canvas)?;
-            match self.event_source.next_event()? {
+            self.render()?;
+            match self.next_event_in(GameState::TerminalTooSmall)? {
                 Event::Resize => {
                     self.tui_board = match self.resize()? {
                         Some(tb) => Some(tb),
                         None => continue,
                     };
+                    self.outgrown = false;
+                    if let Some(tui_board) = &mut self.tui_board {
+                        tui_board.clear_all_animations(&self.board)?;
+                        #[cfg(debug_assertions)]
+                        tui_board.check_consistency(&self.board.current());
+                    }
                     break;
                 }
+                Event::UserInput(UserInput::Direction(_)) => {
+                    self.notify(Notification::InvalidMove)?
+                }
+                Event::UserInput(UserInput::NewGame) => return Ok(GameState::Reset),
+                Event::UserInput(UserInput::Quit) => return Ok(GameState::Quit),
                 _ => continue,
             }
         }
-        self.renderer.clear(&self.canvas)?;
+        self.clear()?;
+        // the game left last time is offered once the terminal is large enough to ask
+        if self.offer_resume {
+            return Ok(GameState::Resume);
+        }
         if self.board.is_game_over() {
             Ok(GameState::Over)
         } else {
@@ -1000,290 +3852,5401 @@ impl<R: Renderer, E: EventSource> Tui48<R, E> {
         }
     }
 
-    fn reset(&mut self) -> Result<GameState> {
-        let rng = thread_rng();
-        self.board = Board::new(rng);
-        self.tui_board = self.resize()?;
-        Ok(GameState::Active)
+    /// Shows the themes one at a time, starting with the one in use, until the player applies one
+    /// or goes back to the game. The preview is drawn on a canvas of its own, leaving the game's
+    /// as it was.
+    fn run_theme_preview(&mut self) -> Result<GameState> {
+        let mut highlighted = self.theme;
+        let mut canvas = self.preview_canvas()?;
+        let state = loop {
+            let theme = self.themes[highlighted].clone();
+            let _preview = PreviewBoard::new(theme.clone())
+                .with_labels(self.label_packs[self.label_pack].1.clone())
+                .with_layout(self.layout)
+                .with_score(self.board.score())
+                .with_caption(self.theme_caption(&theme))
+                .draw(&canvas)?;
+            self.renderer
+                .render(&canvas)
+                .during(TerminalOperation::Render)?;
+            match self.next_event_in(GameState::ThemePreview)? {
+                Event::UserInput(UserInput::Direction(Direction::Left)) => {
+                    highlighted = (highlighted + self.themes.len() - 1) % self.themes.len();
+                }
+                Event::UserInput(UserInput::Direction(Direction::Right)) => {
+                    highlighted = (highlighted + 1) % self.themes.len();
+                }
+                Event::UserInput(UserInput::Confirm) => {
+                    self.apply_theme(highlighted);
+                    break GameState::Active;
+                }
+                Event::UserInput(UserInput::Cancel) => break GameState::Active,
+                Event::UserInput(UserInput::Quit) => break GameState::Quit,
+                Event::UserInput(_) => (),
+                Event::Resize => canvas = self.preview_canvas()?,
+                // drawn once the game is laid out again
+                Event::Estimate {
+                    generation,
+                    estimate,
+                } if generation == self.outlook.generation() => self.estimate = Some(estimate),
+                Event::Estimate { .. } => (),
+            }
+        };
+        self.renderer
+            .clear(&canvas)
+            .during(TerminalOperation::Clear)?;
+        Ok(state)
     }
 
-    fn resize(&mut self) -> Result<Option<Tui48Board>> {
-        let (width, height) = self.renderer.size_hint()?;
-        self.canvas = Canvas::new(width as usize, height as usize);
-
-        match Tui48Board::new(&self.board, &mut self.canvas) {
-            Ok(tb) => match tb.check_bounds() {
-                Err(_) => Ok(None),
-                Ok(_) => Ok(Some(tb)),
-            },
-            Err(Error::TerminalTooSmall(_, _)) => Ok(None),
-            Err(e) => match &e {
-                Error::TuiError { source: tui_error } => match tui_error.inner {
-                    TuiError::OutOfBoundsX(_) => Ok(None),
-                    TuiError::OutOfBoundsY(_) => Ok(None),
-                    _ => Err(e),
+    /// Lets the player build a position, starting out from the one on the board, and start a game
+    /// from it or write it down. Like the theme preview, the editor is drawn on a canvas of its
+    /// own, leaving the game's as it was.
+    fn run_editor(&mut self) -> Result<GameState> {
+        let mut editor = Editor::new(&self.board.current());
+        let mut canvas = self.preview_canvas()?;
+        let mut view = match self.editor_view(&canvas, &editor) {
+            Err(Error::TerminalTooSmall { .. }) => return Ok(GameState::TerminalTooSmall),
+            view => view?,
+        };
+        let position = loop {
+            self.renderer
+                .render(&canvas)
+                .during(TerminalOperation::Render)?;
+            match self.next_event_in(GameState::Editor)? {
+                // a position that can't be played is pointed out, leaving it to be fixed
+                Event::UserInput(UserInput::Confirm) => match editor.position() {
+                    Ok(round) => break Some(round),
+                    Err(e) => view.show_status(Some(&e.to_string()))?,
                 },
-                _ => Err(e),
-            },
+                Event::UserInput(UserInput::WriteNotation) => {
+                    let written = self.write_position(&editor);
+                    view.show_status(Some(&written))?;
+                }
+                Event::UserInput(UserInput::Cancel) => break None,
+                Event::UserInput(UserInput::Quit) => {
+                    self.renderer
+                        .clear(&canvas)
+                        .during(TerminalOperation::Clear)?;
+                    return Ok(GameState::Quit);
+                }
+                Event::UserInput(input) => view.apply(&mut editor, &input)?,
+                Event::Resize => {
+                    drop(view);
+                    canvas = self.preview_canvas()?;
+                    view = match self.editor_view(&canvas, &editor) {
+                        Err(Error::TerminalTooSmall { .. }) => {
+                            return Ok(GameState::TerminalTooSmall)
+                        }
+                        view => view?,
+                    };
+                }
+                // drawn once the game is laid out again
+                Event::Estimate {
+                    generation,
+                    estimate,
+                } if generation == self.outlook.generation() => self.estimate = Some(estimate),
+                Event::Estimate { .. } => (),
+            }
+        };
+        drop(view);
+        self.renderer
+            .clear(&canvas)
+            .during(TerminalOperation::Clear)?;
+        let round = match position {
+            Some(round) => round,
+            None => return Ok(GameState::Active),
+        };
+        let state = self.start(Board::from_position(thread_rng(), round))?;
+        self.edited = true;
+        Ok(state)
+    }
+
+    fn editor_view(&self, canvas: &Canvas, editor: &Editor) -> Result<EditorView> {
+        EditorView::new(
+            canvas,
+            self.layout,
+            self.label_packs[self.label_pack].1.clone(),
+            self.themes[self.theme].clone(),
+            self.keymap.render(EDITOR_PROMPT),
+            editor,
+        )
+    }
+
+    /// Writes the position built in the editor down to the position file, returning what to tell
+    /// the player.
+    fn write_position(&self, editor: &Editor) -> String {
+        let path = match &self.position_file {
+            Some(path) => path,
+            None => return String::from("there's nowhere to write the position to"),
+        };
+        let written = editor
+            .position()
+            .and_then(|round| Ok(std::fs::write(path, round.to_notation())?));
+        match written {
+            Ok(()) => format!("position written to {}", path.display()),
+            Err(e) => e.to_string(),
         }
     }
 
-    fn shift(&mut self, direction: Direction) -> Result<bool> {
-        let mut game_over = false;
-        if let Some(hint) = self.board.shift(direction) {
-            game_over = hint.game_over();
-            let mut tui_board = self
-                .tui_board
-                .take()
-                .expect("why wouldn't we have a tui board at this point?");
-            Tui48Board::draw_score(&mut tui_board.score, self.board.score())?;
-            log::trace!("Tui48Board prior to setting up animation\n{}", tui_board);
-            log::trace!("Canvas prior to setting up animation\n{}", self.canvas);
-            tui_board.setup_animation(&hint)?;
-            log::trace!("after setting up animation\n{}", tui_board);
-            let mut fc = 0;
-            while tui_board.animate()? {
-                log::trace!("generated animation frame {0}\n{1}", fc, tui_board);
-                std::thread::sleep(std::time::Duration::from_millis(5));
-                self.renderer.render(&self.canvas)?;
-                log::trace!("rendered frame {} after sleeping 1ms", fc);
+    /// Saves the game being played to the save file, returning what to tell the player.
+    fn save_game(&self) -> String {
+        let path = match &self.save_file {
+            Some(path) => path,
+            None => return String::from("there's nowhere to save the game to"),
+        };
+        match self.board.save_to(path) {
+            Ok(()) => format!("game saved to {}", path.display()),
+            Err(e) => e.to_string(),
+        }
+    }
 
-                fc += 1;
+    /// Plays the game in the save file in place of the one being played, returning the state the
+    /// loaded game is in, or None if there was no game to load. The player is told either way.
+    fn load_game(&mut self) -> Result<Option<GameState>> {
+        let path = match &self.save_file {
+            Some(path) => path.clone(),
+            None => {
+                self.show_note("there's nowhere to load a game from")?;
+                return Ok(None);
             }
-            tui_board.teardown_animation()?;
-            self.renderer.render(&self.canvas)?;
-            let _ = self.tui_board.replace(tui_board);
+        };
+        let board = match Board::load_from(&path) {
+            Ok(Some(board)) => board,
+            Ok(None) => {
+                self.show_note(&format!("there's no saved game in {}", path.display()))?;
+                return Ok(None);
+            }
+            Err(e) => {
+                self.show_note(&e.to_string())?;
+                return Ok(None);
+            }
+        };
+        let mut state = self.start(board)?;
+        if self.board.is_game_over() {
+            state = GameState::Over;
         }
-        Ok(game_over)
+        self.show_note(&format!("game loaded from {}", path.display()))?;
+        Ok(Some(state))
     }
-}
-
-enum GameState {
-    Active,
-    Over,
-    Reset,
-    TerminalTooSmall,
-    Quit,
-}
 
-#[cfg(test)]
-mod test {
-    use std::collections::HashMap;
+    /// A blank canvas the size of the terminal to preview themes on.
+    fn preview_canvas(&mut self) -> Result<Canvas> {
+        let (width, height) = self
+            .renderer
+            .size_hint()
+            .during(TerminalOperation::SizeHint)?;
+        self.clear()?;
+        Ok(Canvas::with_depth(
+            width as usize,
+            height as usize,
+            self.canvas.depth(),
+        )?)
+    }
 
-    use env_logger;
-    use log::Log;
-    use rand::SeedableRng;
-    use rstest::*;
+    /// The caption naming the given theme in the preview.
+    fn theme_caption(&self, theme: &Theme) -> String {
+        let shade = if theme.is_dark() { "dark" } else { "light" };
+        self.keymap
+            .render(THEME_PREVIEW_PROMPT)
+            .replace("{theme}", theme.name())
+            .replace("{shade}", shade)
+    }
 
-    use super::*;
-    use crate::engine::round::Round;
-
-    fn generate_round_from(idxs: HashMap<BoardIdx, u8>) -> Round {
-        let mut round = Round::default();
-        for x in 0..3 {
-            for y in 0..3 {
-                let idx = BoardIdx(x, y);
-                if let Some(v) = idxs.get(&idx) {
-                    round.set_value(&idx, v.clone());
-                }
-            }
+    /// Colors tiles with the given theme and saves it as the one to start with next time. Takes
+    /// effect the next time the board is laid out.
+    fn apply_theme(&mut self, theme: usize) {
+        self.theme = theme;
+        let theme = self.themes[theme].theme();
+        log::debug!("coloring tiles with the {} theme", theme.name());
+        if let Some(persistence) = &self.persistence {
+            self.prefs.theme = Some(theme);
+            persistence.submit(PersistEvent::PrefsChanged(self.prefs.to_toml()));
         }
-        round
     }
 
-    fn setup(
-        width: usize,
-        height: usize,
-        idxs: HashMap<BoardIdx, u8>,
-    ) -> Result<(Board, Canvas, Tui48Board)> {
-        let mut canvas = Canvas::new(width, height);
-        let rng = rand::rngs::SmallRng::seed_from_u64(10);
-        let mut game_board = Board::new(rng);
-        let round = generate_round_from(idxs);
-        game_board.set_initial_round(round);
-
-        let tui_board = Tui48Board::new(&game_board, &mut canvas)?;
-        Ok((game_board, canvas, tui_board))
+    /// Whether the game being played is measured against the saved high score: only standard
+    /// games on the classic board that started from an empty one are.
+    fn counts_for_high_score(&self) -> bool {
+        self.mode == Mode::Classic
+            && self.practice.is_none()
+            && !self.edited
+            && self.board.power_ups().is_none()
+            && !self.board.grows()
+            && self.board.size().is_classic()
     }
 
-    fn verify_occupied_layers(c: &Canvas, occupied: Vec<usize>, not_occupied: Vec<usize>) {
-        for zdx in occupied.iter() {
-            assert!(c.layer_occupied(*zdx), "layer {} should be occupied", zdx)
+    /// Saves the best score if the game being played has beaten it since it was last saved.
+    fn save_high_score(&mut self) {
+        if !self.counts_for_high_score() {
+            return;
         }
-        for zdx in not_occupied.iter() {
-            assert!(
-                !c.layer_occupied(*zdx),
-                "layer {} should not be occupied",
-                zdx
-            )
+        let high_score = self.board.high_score();
+        if high_score.best() <= self.saved_high_score {
+            return;
+        }
+        if let Some(persistence) = &self.persistence {
+            persistence.submit(PersistEvent::HighScore(high_score.to_json()));
         }
+        self.saved_high_score = high_score.best();
     }
 
-    fn debug(args: core::fmt::Arguments) -> log::Record {
-        log::Record::builder()
-            .level(log::Level::Debug)
-            .args(args)
-            .build()
+    fn reset(&mut self) -> Result<GameState> {
+        let seed = thread_rng().gen();
+        let board = match self.practice {
+            Some(profile) => Board::practice_position_seeded(seed, profile)?,
+            // a board that grew starts the next game at the size it started this one
+            None => Board::new_seeded(seed, self.board.first_size()),
+        };
+        self.start(board)
     }
 
-    #[test]
-    fn test_slide() -> Result<()> {
-        init()?;
+    /// Abandons the game being played for one on the given board, played by the rules of the
+    /// mode chosen.
+    fn start(&mut self, mut board: Board) -> Result<GameState> {
+        self.session.abandon_game(self.board.score());
+        let size = board.size();
+        if size != self.layout.size() {
+            // the heatmap only covers boards the size of the one it was started on
+            self.layout = self.layout.with_size(size);
+            self.tile_occupancy = vec![vec![0; size.width]; size.height];
+            self.tui_board = None;
+        }
+        board.set_high_score(self.board.high_score().clone());
+        // a game started from a winning position has nothing left to announce
+        self.win_announced = board.has_won();
+        self.record_announced = false;
+        // an arcade game loaded from a save keeps the power-ups it had earned
+        self.board = match self.mode {
+            Mode::Arcade if board.power_ups().is_none() => board.with_power_ups(),
+            Mode::Growth if !board.grows() => board.with_growth(&BoardConfig::GROWTH_MILESTONES),
+            _ => board,
+        };
+        self.milestones = Milestones::new();
+        self.outgrown = false;
+        self.edited = false;
+        self.refresh_outlook();
+        self.tui_board = self.resize()?;
+        self.animate_entering_tiles()?;
+        Ok(GameState::Active)
+    }
 
-        let logger = env_logger::Logger::from_default_env();
+    /// Slides every tile of a freshly created board into place.
+    fn animate_entering_tiles(&mut self) -> Result<()> {
+        let mut tui_board = match self.tui_board.take() {
+            Some(tui_board) => tui_board,
+            None => return Ok(()),
+        };
+        let round = self.board.current();
+        let (width, height) = self.board.dimensions();
+        for y in 0..height {
+            for x in 0..width {
+                if round.get(&BoardIdx(x, y)) > 0 {
+                    tui_board.tile_enter_animation(BoardIdx(x, y))?;
+                }
+            }
+        }
+        let frame_delay = self.enter_duration / self.layout.enter_frames();
+        self.play_animation(&mut tui_board, frame_delay)?;
+        tui_board.settle(&round)?;
+        let _ = self.tui_board.replace(tui_board);
+        Ok(())
+    }
 
-        let idxs = HashMap::from([(BoardIdx(0, 0), 2), (BoardIdx(0, 1), 2)]);
-        let (mut game_board, canvas, mut tui_board) = setup(100, 100, idxs)?;
+    /// Plays the board's animation until every tile has arrived, rendering frames after the
+    /// given delay unless moves are instant. Only every few frames are rendered while the
+    /// animation quality is coarser than full. Frames are timed with the frame timer, leaving the
+    /// delay out.
+    fn play_animation(&mut self, tui_board: &mut Tui48Board, frame_delay: Duration) -> Result<()> {
+        let mut fc = 0;
+        // frames generated since the last one rendered
+        let mut pending = 0;
+        loop {
+            let started = self.frame_timer.now();
+            if !tui_board.animate()? {
+                return Ok(());
+            }
+            let animated = self.frame_timer.now();
+            log::trace!("generated animation frame {0}\n{1}", fc, tui_board);
+            if self.instant_moves() {
+                continue;
+            }
+            let Some(step) = self.quality.level().step() else {
+                continue;
+            };
+            pending += 1;
+            if pending < step {
+                continue;
+            }
+            pending = 0;
+            std::thread::sleep(frame_delay);
+            let rendering = self.frame_timer.now();
+            self.render_adapting()?;
+            if let (Some(started), Some(animated), Some(rendering), Some(rendered)) =
+                (started, animated, rendering, self.frame_timer.now())
+            {
+                self.frame_timer.record(FrameSample {
+                    animate: animated - started,
+                    changed: self.canvas.last_changed(),
+                    render: rendered - rendering,
+                });
+            }
+            log::trace!("rendered frame {} after sleeping {:?}", fc, frame_delay);
+            fc += 1;
+        }
+    }
 
-        let hint = game_board
-            .shift(Direction::Down)
-            .expect("down should definitely result in hints");
-        assert_eq!(hint.hints().len(), 3);
+    /// Pulses the winning tile and the board if the current round holds a winning tile.
+    fn celebrate(&mut self, tui_board: &Tui48Board) -> Result<()> {
+        if self.instant_moves() {
+            return Ok(());
+        }
+        let round = self.board.current();
+        let (width, height) = self.board.dimensions();
+        let winner = (0..height)
+            .flat_map(|y| (0..width).map(move |x| BoardIdx(x, y)))
+            .find(|idx| round.get(idx) >= WINNING_CARD);
+        let winner = match winner {
+            Some(idx) => idx,
+            None => return Ok(()),
+        };
+        let board_pulse = tui_board
+            .board
+            .pulse_animation(Rgb::new(90, 70, 10), CELEBRATION_CYCLES);
+        let mut pulses: Vec<PulseHandle> = tui_board
+            .flash_tile(&winner, Rgb::new(255, 215, 0), CELEBRATION_CYCLES)
+            .into_iter()
+            .chain(std::iter::once(board_pulse))
+            .map(|pulse| pulse.with_period(CELEBRATION_PERIOD))
+            .collect();
+        loop {
+            let mut pulsing = false;
+            for pulse in pulses.iter_mut() {
+                pulsing |= pulse.tick();
+            }
+            self.render()?;
+            if !pulsing {
+                return Ok(());
+            }
+            std::thread::sleep(self.celebration_delay);
+        }
+    }
 
-        let hints = hint.hints();
-        let (idx1, hint1) = hints.get(0).expect("expecting three hints");
-        let (idx2, hint2) = hints.get(1).expect("expecting three hints");
-        let (idx3, hint3) = hints.get(2).expect("expecting three hints");
+    /// Flashes the score box with the note for the given milestone, then shows the score again.
+    fn announce_milestone(&mut self, tui_board: &mut Tui48Board, milestone: u32) -> Result<()> {
+        if self.instant_moves() {
+            return Ok(());
+        }
+        Tui48Board::draw_milestone(&mut tui_board.score, milestone)?;
+        let mut pulse = tui_board
+            .score
+            .pulse_animation(Rgb::new(255, 240, 200), MILESTONE_CYCLES)
+            .with_period(CELEBRATION_PERIOD);
+        loop {
+            let pulsing = pulse.tick();
+            self.render()?;
+            if !pulsing {
+                break;
+            }
+            std::thread::sleep(self.celebration_delay);
+        }
+        tui_board.draw_score(&self.board)?;
+        self.render()?;
+        Ok(())
+    }
 
-        assert_eq!(*idx1, BoardIdx(0, 1));
-        assert!(matches!(hint1, Hint::ToIdx(BoardIdx(0, 3))));
+    /// Shows the score breakdown for the given move over the board. It stays up while the game
+    /// waits for input, fading out over `SCORE_BREAKDOWN_FRAMES` frames unless a key is pressed
+    /// first; see `fade_score_breakdown`.
+    fn show_score_breakdown(
+        &mut self,
+        tui_board: &mut Tui48Board,
+        hint: &AnimationHint,
+    ) -> Result<()> {
+        // the previous move's breakdown makes way for this one's
+        self.breakdown = None;
+        if self.instant_moves() {
+            return Ok(());
+        }
+        let overlay = match tui_board.overlay_score_breakdown(hint)? {
+            Some(overlay) => overlay,
+            None => return Ok(()),
+        };
+        self.breakdown = Some(ScoreBreakdown {
+            overlay,
+            lines: score_breakdown(hint),
+            frames_remaining: SCORE_BREAKDOWN_FRAMES,
+        });
+        self.render()
+    }
+
+    /// Takes the score breakdown one frame closer to disappearing, for when no key was pressed in
+    /// a frame's time.
+    fn fade_score_breakdown(&mut self) -> Result<()> {
+        if let Some(breakdown) = &mut self.breakdown {
+            if !breakdown.fade()? {
+                self.breakdown = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Switches to the next label pack and saves it as the one to start with next time. Takes
+    /// effect the next time the board is laid out.
+    fn cycle_label_pack(&mut self) {
+        self.label_pack = (self.label_pack + 1) % self.label_packs.len();
+        let (choice, labels) = &self.label_packs[self.label_pack];
+        log::debug!("labelling tiles with the {} pack", labels.name());
+        if let Some(persistence) = &self.persistence {
+            self.prefs.pack = Some(choice.clone());
+            persistence.submit(PersistEvent::PrefsChanged(self.prefs.to_toml()));
+        }
+    }
+
+    /// Shows or hides the lines between slots and saves the choice for next time.
+    fn toggle_grid(&mut self) -> Result<()> {
+        self.grid = !self.grid;
+        if let Some(tui_board) = &mut self.tui_board {
+            tui_board.set_grid(self.grid)?;
+        }
+        if let Some(persistence) = &self.persistence {
+            self.prefs.grid = Some(self.grid);
+            persistence.submit(PersistEvent::PrefsChanged(self.prefs.to_toml()));
+        }
+        Ok(())
+    }
+
+    /// Shows the heatmap of where tiles have come to rest until the next key is pressed.
+    fn show_heatmap(&mut self) -> Result<()> {
+        if let Some(tui_board) = &self.tui_board {
+            self.heatmap = Some(tui_board.display_tile_heatmap()?);
+        }
+        Ok(())
+    }
+
+    /// Forget the current estimate and start estimating the current position.
+    fn refresh_outlook(&mut self) {
+        self.estimate = None;
+        self.outlook.submit(self.board.current());
+    }
+
+    /// Shows the given estimate, unless it was posted for a position the board has since moved on
+    /// from.
+    fn update_estimate(&mut self, generation: u64, estimate: f32) -> Result<()> {
+        if generation != self.outlook.generation() {
+            log::trace!("dropping stale estimate for generation {}", generation);
+            return Ok(());
+        }
+        self.estimate = Some(estimate);
+        if let Some(tui_board) = &mut self.tui_board {
+            tui_board.draw_outlook(estimate)?;
+        }
+        Ok(())
+    }
+
+    /// Flash the visual bell for the given notification, if it is enabled and hasn't flashed too
+    /// recently.
+    fn notify(&mut self, notification: Notification) -> Result<()> {
+        if self.instant_moves() || !self.bell.ring(&self.canvas, notification)? {
+            return Ok(());
+        }
+        while self.bell.is_flashing() {
+            self.render()?;
+            std::thread::sleep(self.flash_delay);
+            self.bell.advance();
+        }
+        self.render()?;
+        Ok(())
+    }
+
+    /// Draws what changed on the game's canvas since the last frame, or all of it if it was laid
+    /// out anew.
+    /// Every call is one frame, which the renderer is told begins and ends even if drawing it
+    /// fails part way.
+    fn render(&mut self) -> Result<()> {
+        self.renderer
+            .begin_frame()
+            .during(TerminalOperation::Render)?;
+        let drawn = if std::mem::take(&mut self.repaint) {
+            self.renderer.repaint(&self.canvas)
+        } else {
+            self.renderer.render(&self.canvas)
+        };
+        let ended = self.renderer.end_frame();
+        drawn.and(ended).during(TerminalOperation::Render)
+    }
+
+    /// Blanks the terminal, eg before drawing a screen other than the game's.
+    fn clear(&mut self) -> Result<()> {
+        self.renderer
+            .clear(&self.canvas)
+            .during(TerminalOperation::Clear)
+    }
+
+    /// Waits for the next key press, resize or message from a background task.
+    fn next_event(&self) -> Result<Event> {
+        self.event_source
+            .next_event()
+            .during(TerminalOperation::NextEvent)
+    }
+
+    /// Waits for the next event the given state acts on: input the state ignores is dropped, and
+    /// input it buffers is held, see `input_policy`. Input held earlier is delivered first once a
+    /// state that allows it asks.
+    fn next_event_in(&mut self, state: GameState) -> Result<Event> {
+        if let Some(event) = self.held_event_for(state) {
+            return Ok(event);
+        }
+        loop {
+            let event = self.next_event()?;
+            if let Some(event) = self.filter_event(state, event) {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Like `next_event_in`, but waits at most the given time, returning None if nothing the
+    /// given state acts on came in the meantime.
+    fn poll_event_in(&mut self, state: GameState, timeout: Duration) -> Result<Option<Event>> {
+        if let Some(event) = self.held_event_for(state) {
+            return Ok(Some(event));
+        }
+        let deadline = Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let event = match self
+                .event_source
+                .poll_event(left)
+                .during(TerminalOperation::NextEvent)?
+            {
+                Some(event) => event,
+                None => return Ok(None),
+            };
+            if let Some(event) = self.filter_event(state, event) {
+                return Ok(Some(event));
+            }
+        }
+    }
+
+    /// Takes the input held earlier if the given state allows it.
+    fn held_event_for(&mut self, state: GameState) -> Option<Event> {
+        let input = self.held_input.take()?;
+        if input_policy(state, &input) == InputPolicy::Allowed {
+            return Some(Event::UserInput(input));
+        }
+        self.held_input = Some(input);
+        None
+    }
+
+    /// Returns the given event if the given state acts on it, dropping or holding it otherwise,
+    /// see `input_policy`.
+    fn filter_event(&mut self, state: GameState, event: Event) -> Option<Event> {
+        let input = match event {
+            Event::UserInput(input) => input,
+            event => return Some(event),
+        };
+        match input_policy(state, &input) {
+            InputPolicy::Allowed => return Some(Event::UserInput(input)),
+            InputPolicy::Ignored => log::trace!("ignoring {:?} in {:?}", input, state),
+            InputPolicy::Buffered => self.held_input = Some(input),
+        }
+        None
+    }
+
+    /// Renders the canvas, adapting the animation quality to the time it took. The player is told
+    /// the first time animations get coarser.
+    fn render_adapting(&mut self) -> Result<()> {
+        let rendering = Instant::now();
+        self.render()?;
+        let before = self.quality.level();
+        if self.quality.observe(rendering.elapsed()) > before && !self.quality_noted {
+            self.quality_noted = true;
+            if self.note.is_none() {
+                self.show_note(COARSER_ANIMATIONS_NOTE)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if the terminal is too slow to animate, in which case moves are shown in one
+    /// go and everything that would only flash by is skipped.
+    fn instant_moves(&self) -> bool {
+        self.watchdog
+            .as_ref()
+            .is_some_and(|watchdog| watchdog.is_degraded())
+    }
+
+    /// Warns the player along the bottom of the screen once the terminal turns out to be too slow
+    /// to animate.
+    fn warn_if_slow(&mut self) -> Result<()> {
+        if !self.instant_moves()
+            || self
+                .note
+                .as_ref()
+                .is_some_and(|(note, _)| note == SLOW_TERMINAL_WARNING)
+        {
+            return Ok(());
+        }
+        self.show_note(SLOW_TERMINAL_WARNING)
+    }
+
+    /// Hands the moves over to the computer, or takes them back from it.
+    fn toggle_autoplay(&mut self) -> Result<()> {
+        self.autoplay = !self.autoplay;
+        if !self.autoplay
+            && self
+                .note
+                .as_ref()
+                .is_some_and(|(note, _)| note == &self.keymap.render(AUTOPLAY_NOTE))
+        {
+            self.note = None;
+            self.settled = false;
+        }
+        self.note_autoplay()
+    }
+
+    /// Tells the player along the bottom of the screen that the computer is playing, while it is
+    /// and nothing else is being told.
+    fn note_autoplay(&mut self) -> Result<()> {
+        if !self.autoplay || self.note.is_some() {
+            return Ok(());
+        }
+        let note = self.keymap.render(AUTOPLAY_NOTE);
+        self.show_note(&note)
+    }
+
+    /// Tells the player along the bottom of the screen what went wrong with their scripts since
+    /// they were last told, if anything did. Only the latest notice fits; all of them are logged.
+    fn show_script_notices(&mut self) -> Result<()> {
+        let notices = self.scripts.take_notices();
+        for notice in notices.iter() {
+            log::warn!("{}", notice);
+        }
+        match notices.last() {
+            Some(notice) => self.show_note(notice),
+            None => Ok(()),
+        }
+    }
+
+    /// Shows the given note along the bottom of the screen, in place of any shown before.
+    fn show_note(&mut self, note: &str) -> Result<()> {
+        // the note shown before has to give its cells back first
+        self.note = None;
+        let (width, height) = self.canvas.dimensions();
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        let r = Rectangle(Idx(0, height - 1, OVERLAY_LAYER_IDX), Bounds2D(width, 1));
+        let mut buf = self.canvas.get_text_buffer(r, Owner::Named("note"))?;
+        buf.modify(Modifier::SetBackgroundColor(90, 60, 0));
+        buf.format(FormatOptions {
+            halign: HAlignment::Center,
+            valign: VAlignment::Top,
+        });
+        buf.clear()?;
+        buf.write(note, None, None)?;
+        buf.flush()?;
+        self.note = Some((note.to_string(), buf));
+        self.settled = false;
+        Ok(())
+    }
+
+    /// Shows the score in the window title whenever it changes.
+    fn update_title(&mut self) -> Result<()> {
+        let title = format!("tui48 \u{2014} {}", self.board.score());
+        if self.title.as_ref() != Some(&title) {
+            self.renderer
+                .set_title(&title)
+                .during(TerminalOperation::SetTitle)?;
+            self.title = Some(title);
+        }
+        Ok(())
+    }
+
+    /// The indicators to show in the top bar, space permitting.
+    fn indicators(&self) -> Vec<Indicator> {
+        let mut indicators = vec![Indicator::Score];
+        if self.board.power_ups().is_some() {
+            indicators.push(Indicator::PowerUps);
+        }
+        if self.outlook.is_running() {
+            indicators.push(Indicator::Outlook);
+        }
+        indicators.push(Indicator::Pressure);
+        if self.label_packs[self.label_pack].0 != PackChoice::Builtin(BuiltinPack::Numbers) {
+            indicators.push(Indicator::MaxTile);
+        }
+        if self.board.seed().is_some() {
+            indicators.push(Indicator::Seed);
+        }
+        indicators
+    }
+
+    /// The pair of tiles the merges assist should point out, if it is enabled.
+    fn merge_assist(&self) -> Option<(BoardIdx, BoardIdx)> {
+        match self.assist {
+            Some(Assist::Merges) => self
+                .board
+                .current()
+                .largest_mergeable_pair()
+                .map(|(first, second, _)| (first, second)),
+            None => None,
+        }
+    }
+
+    fn resize(&mut self) -> Result<Option<Tui48Board>> {
+        let (width, height) = self
+            .renderer
+            .size_hint()
+            .during(TerminalOperation::SizeHint)?;
+        self.note = None;
+        self.heatmap = None;
+        self.breakdown = None;
+        if let Some(tui_board) = &self.tui_board {
+            self.tile_occupancy = tui_board.tile_occupancy.clone();
+        }
+        self.fit_layout();
+        self.canvas = Canvas::with_depth(width as usize, height as usize, self.canvas.depth())?;
+        self.repaint = true;
+        self.settled = false;
+
+        let indicators = self.indicators();
+        match Tui48Board::new(&self.board, &mut self.canvas, &indicators, self.layout) {
+            Ok(mut tb) => {
+                tb.tile_occupancy = self.tile_occupancy.clone();
+                tb.easing = self.easing;
+                tb.set_theme(self.themes[self.theme].clone())?;
+                tb.set_labels(self.label_packs[self.label_pack].1.clone(), &self.board)?;
+                tb.set_grid(self.grid)?;
+                if let Some(estimate) = self.estimate {
+                    tb.draw_outlook(estimate)?;
+                }
+                tb.mark_merge(self.merge_assist())?;
+                #[cfg(debug_assertions)]
+                tb.check_consistency(&self.board.current());
+                Ok(Some(tb))
+            }
+            Err(Error::TerminalTooSmall { .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn shift(&mut self, direction: Direction) -> Result<bool> {
+        let prior = self.board.current();
+        let best = self.board.high_score().best();
+        if !self.scripts.allows_move(&prior, &direction.to_board()) {
+            log::debug!("a script vetoed moving {}", direction.to_board());
+            self.notify(Notification::InvalidMove)?;
+            return Ok(false);
+        }
+        match self.board.shift(direction.to_board()) {
+            MoveOutcome::Moved(hint) => {
+                self.session.record_move(hint.merges());
+                self.journal_move(direction);
+                let game_over = self.show_move(&prior, &hint, true)?;
+                self.announce_record(best)?;
+                Ok(game_over)
+            }
+            MoveOutcome::Rejected => {
+                self.notify(Notification::InvalidMove)?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Rings the bell if the move just made beat the best score, given the best before it, for
+    /// the first time in the game. Only games measured against the saved high score set records.
+    fn announce_record(&mut self, best: Score) -> Result<()> {
+        if self.record_announced
+            || !self.counts_for_high_score()
+            || self.board.high_score().best() <= best
+        {
+            return Ok(());
+        }
+        self.record_announced = true;
+        self.notify(Notification::NewRecord)
+    }
+
+    /// Adds the move just made to the journal, with enough to play the game back up to it from
+    /// its seed should the game be lost to a crash.
+    fn journal_move(&self, direction: Direction) {
+        if let Some(persistence) = &self.persistence {
+            let seed = match self.board.seed() {
+                Some(seed) => seed.to_string(),
+                None => "-".to_string(),
+            };
+            persistence.submit(PersistEvent::MoveJournal(format!(
+                "seed {} move {} {} score {}",
+                seed,
+                self.board.move_count(),
+                direction.to_board(),
+                self.board.score()
+            )));
+        }
+    }
+
+    /// Takes back the latest move or power-up used, returning the plan for animating it
+    /// backwards, or None if there was none to take back. The board on screen is left as it was;
+    /// see `show_undo`.
+    fn undo(&mut self) -> Result<Option<RewindPlan>> {
+        let plan = self.board.undo();
+        match plan {
+            Some(_) => self.refresh_outlook(),
+            None => self.notify(Notification::InvalidMove)?,
+        }
+        Ok(plan)
+    }
+
+    /// Uses the power-up charged in the arcade mode, if there is one. Power-ups aren't moves, so
+    /// the session doesn't count them or the merges they make.
+    fn use_power_up(&mut self) -> Result<bool> {
+        let prior = self.board.current();
+        match self.board.activate_power_up() {
+            MoveOutcome::Moved(hint) => self.show_move(&prior, &hint, false),
+            MoveOutcome::Rejected => {
+                self.notify(Notification::InvalidMove)?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Makes the move most recently taken back again, returning whether that ends the game.
+    /// Scripts aren't asked about it, having let it through the first time.
+    fn redo(&mut self) -> Result<bool> {
+        let prior = self.board.current();
+        match self.board.redo() {
+            Some(hint) if hint.grew_from().is_some() => {
+                self.show_size_change()?;
+                Ok(false)
+            }
+            Some(hint) => {
+                let shifted = hint.direction().is_some();
+                self.show_move(&prior, &hint, shifted)
+            }
+            None => {
+                self.notify(Notification::InvalidMove)?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Grows the board if the growth mode is due to grow it, between moves; see `Board::grow`.
+    fn grow(&mut self) -> Result<()> {
+        if self.board.grow().is_some() {
+            self.refresh_outlook();
+            self.show_size_change()?;
+        }
+        Ok(())
+    }
+
+    /// Shows the board grown, or shrunk back as a growth is taken back: its border moves out, or
+    /// in, to the size the board is now over a few frames, then the board is drawn afresh at that
+    /// size, new empty slots and all. If the terminal is too small for the grown board, no board
+    /// is left to show until the terminal is made larger.
+    fn show_size_change(&mut self) -> Result<()> {
+        let from = self.layout.board_rectangle();
+        self.fit_layout();
+        let to = self.layout.board_rectangle();
+        if LayoutRequirements::new(&self.layout)
+            .check(self.canvas.dimensions())
+            .is_err()
+        {
+            self.outgrown = true;
+            self.tui_board = None;
+            return Ok(());
+        }
+        if !self.instant_moves() {
+            let Rectangle(origin, Bounds2D(from_width, from_height)) = from;
+            let Bounds2D(to_width, to_height) = to.1;
+            let step = |from: usize, to: usize, frame: usize| {
+                let (from, to) = (from as isize, to as isize);
+                (from + (to - from) * frame as isize / GROWTH_FRAMES as isize) as usize
+            };
+            for frame in 1..GROWTH_FRAMES {
+                let bounds = Bounds2D(
+                    step(from_width, to_width, frame),
+                    step(from_height, to_height, frame),
+                );
+                let mut idx = origin.clone();
+                idx.2 = UPPER_ANIMATION_LAYER_IDX;
+                let mut border = self
+                    .canvas
+                    .get_draw_buffer(Rectangle(idx, bounds), Owner::Named("growth"))?;
+                border.draw_border()?;
+                self.render()?;
+                drop(border);
+                std::thread::sleep(self.celebration_delay);
+            }
+        }
+        self.tui_board = self.resize()?;
+        Ok(())
+    }
+
+    /// Lays the board out at the size it is now, should it have grown or shrunk since it was laid
+    /// out. The heatmap goes on counting for the slots the board still has.
+    fn fit_layout(&mut self) {
+        let size = self.board.size();
+        if size != self.layout.size() {
+            self.layout = self.layout.with_size(size);
+        }
+        // the heatmap may still be the one the board was laid out with before
+        self.tile_occupancy.resize(size.height, vec![0; size.width]);
+        for row in self.tile_occupancy.iter_mut() {
+            row.resize(size.width, 0);
+        }
+    }
+
+    /// Shows the move just taken back undone by the given plan: the tiles slide back to where they
+    /// came from and the panels go back to what they showed before it.
+    fn show_undo(&mut self, plan: &RewindPlan) -> Result<()> {
+        // a growth taken back moves nothing, the board only shrinks
+        if self.board.size() != self.layout.size() {
+            return self.show_size_change();
+        }
+        let mut tui_board = self
+            .tui_board
+            .take()
+            .expect("why wouldn't we have a tui board at this point?");
+        tui_board.draw_indicators(&self.board)?;
+        tui_board.setup_rewind(plan)?;
+        self.play_animation(&mut tui_board, self.frame_delay)?;
+        tui_board.settle(&self.board.current())?;
+        tui_board.mark_merge(self.merge_assist())?;
+        self.render_adapting()?;
+        let _ = self.tui_board.replace(tui_board);
+        self.settled = true;
+        Ok(())
+    }
+
+    /// Shows the change the board just made to the given round, as described by the given hint,
+    /// and returns whether the game is over. Shifts are animated from the difference between the
+    /// rounds, power-ups from their hint since no shift explains what they do.
+    ///
+    /// The board has already made the move, so it stands even if showing it fails part way; the
+    /// error is only about the screen.
+    fn show_move(&mut self, prior: &Round, hint: &AnimationHint, shifted: bool) -> Result<bool> {
+        let had_won = prior.max_card() >= WINNING_CARD;
+        // the board rather than the hint decides, since a power-up may still rescue the game
+        let game_over = self.board.is_game_over();
+        self.save_high_score();
+        if game_over {
+            self.session.finish_game(self.board.score());
+            self.scripts.game_over(&GameSummary {
+                score: self.board.score(),
+                moves: self.board.move_count(),
+                max_tile: display_value(self.board.current().max_card()),
+            });
+        }
+        let mut tui_board = self
+            .tui_board
+            .take()
+            .expect("why wouldn't we have a tui board at this point?");
+        tui_board.draw_indicators(&self.board)?;
+        log::trace!("Tui48Board prior to setting up animation\n{}", tui_board);
+        log::trace!("Canvas prior to setting up animation\n{}", self.canvas);
+        tui_board.fade_merged = !self.instant_moves();
+        if shifted {
+            tui_board.animate_new_round(prior, &self.board.current())?;
+        } else {
+            tui_board.setup_animation(hint)?;
+        }
+        log::trace!("after setting up animation\n{}", tui_board);
+        self.play_animation(&mut tui_board, self.frame_delay)?;
+        tui_board.settle(&self.board.current())?;
+        tui_board.mark_merge(self.merge_assist())?;
+        // timing the final frame too lets animations recover from being instant
+        self.render_adapting()?;
+        if !had_won {
+            self.celebrate(&tui_board)?;
+        }
+        for milestone in self.milestones.cross(self.board.score()) {
+            self.announce_milestone(&mut tui_board, milestone)?;
+        }
+        if self.score_breakdown {
+            self.show_score_breakdown(&mut tui_board, hint)?;
+        }
+        let _ = self.tui_board.replace(tui_board);
+        self.settled = true;
+        self.refresh_outlook();
+        if game_over {
+            self.notify(Notification::GameOver)?;
+        }
+        Ok(game_over)
+    }
+}
+
+/// Removes the given file, logging rather than failing if it can't be.
+fn remove_if_present(path: &Path) {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            log::error!("unable to remove {:?}: {}", path, e)
+        }
+        _ => (),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum GameState {
+    Active,
+    Won,
+    Over,
+    Replay,
+    Reset,
+    TerminalTooSmall,
+    ThemePreview,
+    Editor,
+    Resume,
+    Quit,
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+
+    use env_logger;
+    use log::Log;
+    use rand::{Rng, SeedableRng};
+    use rstest::*;
+
+    use super::*;
+    use crate::engine::direction::Direction as BoardDirection;
+    use crate::engine::fixtures::{card, with_tiles};
+    use crate::engine::powerup::PowerUp;
+    use crate::engine::round::{DIRECTIONS, MAX_CARD};
+
+    fn numbers() -> Arc<LabelPack> {
+        Arc::new(LabelPack::builtin(BuiltinPack::Numbers))
+    }
+
+    fn setup(
+        width: usize,
+        height: usize,
+        tiles: &[(BoardIdx, u32)],
+    ) -> Result<(Board, Canvas, Tui48Board)> {
+        setup_with_layout(width, height, tiles, LayoutSpec::classic())
+    }
+
+    fn setup_with_layout(
+        width: usize,
+        height: usize,
+        tiles: &[(BoardIdx, u32)],
+        layout: LayoutSpec,
+    ) -> Result<(Board, Canvas, Tui48Board)> {
+        let mut canvas = Canvas::new(width, height);
+        let rng = rand::rngs::SmallRng::seed_from_u64(10);
+        let mut game_board = Board::new(rng, BoardConfig::default());
+        game_board.set_initial_round(with_tiles(tiles));
+
+        let tui_board = Tui48Board::new(&game_board, &mut canvas, &[Indicator::Score], layout)?;
+        Ok((game_board, canvas, tui_board))
+    }
+
+    fn verify_occupied_layers(c: &Canvas, occupied: Vec<usize>, not_occupied: Vec<usize>) {
+        for zdx in occupied.iter() {
+            assert!(c.layer_occupied(*zdx), "layer {} should be occupied", zdx)
+        }
+        for zdx in not_occupied.iter() {
+            assert!(
+                !c.layer_occupied(*zdx),
+                "layer {} should not be occupied",
+                zdx
+            )
+        }
+    }
+
+    fn debug(args: core::fmt::Arguments) -> log::Record {
+        log::Record::builder()
+            .level(log::Level::Debug)
+            .args(args)
+            .build()
+    }
+
+    #[test]
+    fn mini_preview_shows_a_cell_per_tile() -> Result<()> {
+        init()?;
+        let round = with_tiles(&[
+            (BoardIdx(0, 0), 2048),
+            (BoardIdx(3, 0), 2048),
+            (BoardIdx(0, 3), 2048),
+            (BoardIdx(3, 3), 2048),
+            (BoardIdx(1, 2), 16),
+        ]);
+        let mut canvas = Canvas::new(20, 10);
+        let rect = Rectangle(Idx(5, 3, OVERLAY_LAYER_IDX), Bounds2D(4, 4));
+        let _preview = Tui48Board::mini_preview(&round, &mut canvas, rect)?;
+
+        let cells: HashMap<_, _> = canvas
+            .get_changed()
+            .into_iter()
+            .map(|stack| (stack.coordinates(), stack))
+            .collect();
+        let rows: Vec<String> = (0..4)
+            .map(|y| {
+                (0..4)
+                    .map(|x| cells[&(5 + x, 3 + y)].content().unwrap_or(' '))
+                    .collect()
+            })
+            .collect();
+        // empty slots show a middle dot
+        let rows: Vec<String> = rows.iter().map(|r| r.replace('\u{b7}', ".")).collect();
+        assert_eq!(rows, ["8..8", "....", ".6..", "8..8"]);
+
+        let (bg, fg) = colors_from_card(card(2048));
+        let expected = (fg.apply((None, None)).0, bg.apply((None, None)).1);
+        let rgb = |c: Option<Rgb>| c.map(|c| (c.r(), c.g(), c.b()));
+        let (fg, bg) = cells[&(5, 3)].colors();
+        assert_eq!((rgb(fg), rgb(bg)), (rgb(expected.0), rgb(expected.1)));
+        assert_eq!(cells[&(6, 3)].colors().1.map(|c| c.r()), None);
+        Ok(())
+    }
+
+    #[test]
+    fn mini_preview_needs_a_cell_per_tile() -> Result<()> {
+        init()?;
+        let mut canvas = Canvas::new(20, 10);
+        let rect = Rectangle(Idx(0, 0, OVERLAY_LAYER_IDX), Bounds2D(8, 4));
+        let preview = Tui48Board::mini_preview(&Round::default(), &mut canvas, rect);
+        assert!(preview.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn dim_tiles_darkens_every_tile() -> Result<()> {
+        init()?;
+        let tiles = [(BoardIdx(0, 0), 2), (BoardIdx(3, 2), 64)];
+        let (_, canvas, mut tui_board) = setup(100, 50, &tiles)?;
+        let tile_cells = |canvas: &Canvas| -> HashMap<(usize, usize), Rgb> {
+            canvas
+                .get_changed()
+                .into_iter()
+                .filter_map(|stack| Some((stack.coordinates(), stack.colors().1?)))
+                .collect()
+        };
+        let before = tile_cells(&canvas);
+
+        tui_board.dim_tiles();
+        let after = tile_cells(&canvas);
+        for (x, y) in [(0, 0), (3, 2)] {
+            let Idx(cx, cy, _) = LayoutSpec::classic().tile_rectangle(x, y, TILE_LAYER_IDX).0;
+            let center = (cx + TILE_WIDTH / 2, cy + TILE_HEIGHT / 2);
+            let dimmed = after.get(&center).expect("the tile should be redrawn");
+            assert!(dimmed.lightness() < before[&center].lightness());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn animate_new_round_follows_the_difference_between_rounds() -> Result<()> {
+        init()?;
+        let tiles = [
+            (BoardIdx(0, 1), 2),
+            (BoardIdx(1, 1), 2),
+            (BoardIdx(2, 1), 4),
+            (BoardIdx(3, 1), 4),
+        ];
+        let (_, canvas, mut tui_board) = setup(100, 50, &tiles)?;
+        let prev = with_tiles(&tiles);
+        let next = with_tiles(&[
+            (BoardIdx(0, 1), 4),
+            (BoardIdx(1, 1), 8),
+            (BoardIdx(2, 3), 2),
+        ]);
+
+        let hint = tui_board.animate_new_round(&prev, &next)?;
+        assert_eq!(
+            hint.hints(),
+            vec![
+                (BoardIdx(1, 1), Hint::NewValueToIdx(card(4), BoardIdx(0, 1))),
+                (BoardIdx(2, 1), Hint::ToIdx(BoardIdx(1, 1))),
+                (BoardIdx(3, 1), Hint::NewValueToIdx(card(8), BoardIdx(1, 1))),
+                (BoardIdx(2, 3), Hint::NewTile(card(2), BoardDirection::Left)),
+            ]
+        );
+        let mut moving: Vec<BoardIdx> = tui_board
+            .moving_slots
+            .iter()
+            .filter_map(Slot::board_index)
+            .collect();
+        moving.sort_by_key(|idx| (idx.y(), idx.x()));
+        // the three sliding tiles plus the new one
+        assert_eq!(
+            moving,
+            vec![
+                BoardIdx(0, 1),
+                BoardIdx(1, 1),
+                BoardIdx(1, 1),
+                BoardIdx(2, 3)
+            ]
+        );
+
+        while tui_board.animate()? {}
+        tui_board.teardown_animation()?;
+        assert_eq!(tui_board.slots[1][0].value(), Some(card(4)));
+        assert_eq!(tui_board.slots[1][1].value(), Some(card(8)));
+        assert_eq!(tui_board.slots[1][2].value(), None);
+        assert_eq!(tui_board.slots[3][2].value(), Some(card(2)));
+        verify_occupied_layers(
+            &canvas,
+            vec![BOARD_LAYER_IDX, TILE_LAYER_IDX],
+            vec![MERGING_ANIMATION_LAYER_IDX, UPPER_ANIMATION_LAYER_IDX],
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    fn eased_slides_take_as_long_and_end_in_their_slots(
+        #[values(EasingFn::EaseIn, EasingFn::EaseOut, EasingFn::EaseInOut)] easing: EasingFn,
+    ) -> Result<()> {
+        init()?;
+        let tiles = [
+            (BoardIdx(3, 0), 2),
+            (BoardIdx(2, 2), 4),
+            (BoardIdx(3, 2), 4),
+        ];
+        let next = with_tiles(&[(BoardIdx(0, 0), 2), (BoardIdx(0, 2), 8)]);
+        let mut frames = Vec::new();
+        for easing in [EasingFn::Linear, easing] {
+            let (_, _canvas, mut tui_board) = setup(100, 50, &tiles)?;
+            tui_board.easing = easing;
+            tui_board.animate_new_round(&with_tiles(&tiles), &next)?;
+            let mut count = 0;
+            while tui_board.animate()? {
+                count += 1;
+            }
+            tui_board.teardown_animation()?;
+            assert_eq!(tui_board.slots[0][0].value(), Some(card(2)));
+            assert_eq!(tui_board.slots[2][0].value(), Some(card(8)));
+            assert_eq!(tui_board.slots[2][3].value(), None);
+            frames.push(count);
+        }
+        assert_eq!(frames[0], frames[1]);
+        Ok(())
+    }
+
+    #[test]
+    fn new_tiles_fade_in_as_they_slide_onto_the_board() -> Result<()> {
+        init()?;
+        let tiles = [(BoardIdx(3, 1), 2)];
+        let (_, _canvas, mut tui_board) = setup(100, 50, &tiles)?;
+        let next = with_tiles(&[(BoardIdx(0, 1), 2), (BoardIdx(2, 3), 2)]);
+        tui_board.animate_new_round(&with_tiles(&tiles), &next)?;
+        let fade = |tui_board: &Tui48Board| {
+            tui_board.moving_slots.iter().find_map(|slot| match slot {
+                Slot::Sliding(st) if st.board_index() == BoardIdx(2, 3) => {
+                    Some(st.inner.buf.lock().fade)
+                }
+                _ => None,
+            })
+        };
+
+        let mut fades = vec![fade(&tui_board).expect("the new tile should be sliding")];
+        while tui_board.animate()? {
+            fades.extend(fade(&tui_board));
+        }
+        assert_eq!(fades[0], Some(0.0), "new tiles start out black");
+        assert!(
+            fades.windows(2).all(|w| w[0] < w[1]),
+            "new tiles should brighten every frame: {:?}",
+            fades
+        );
+
+        tui_board.teardown_animation()?;
+        match &tui_board.slots[3][2] {
+            Slot::Static(t) => assert_eq!(t.buf.lock().fade, None),
+            slot => panic!("expected the new tile to have settled, found {}", slot),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn animate_new_round_refuses_rounds_no_shift_explains() -> Result<()> {
+        init()?;
+        let tiles = [(BoardIdx(0, 0), 2)];
+        let (_, _canvas, mut tui_board) = setup(100, 50, &tiles)?;
+        let next = with_tiles(&[
+            (BoardIdx(0, 0), 2),
+            (BoardIdx(1, 0), 2),
+            (BoardIdx(2, 0), 2),
+        ]);
+        let result = tui_board.animate_new_round(&with_tiles(&tiles), &next);
+        assert!(matches!(result, Err(Error::NoShiftBetweenRounds)));
+        assert!(tui_board.moving_slots.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn merging_tiles_slide_on_separate_layers() -> Result<()> {
+        init()?;
+        let tiles = [
+            (BoardIdx(0, 1), 2),
+            (BoardIdx(1, 1), 2),
+            (BoardIdx(2, 1), 4),
+            (BoardIdx(3, 1), 4),
+        ];
+        let (mut game_board, canvas, mut tui_board) = setup(100, 50, &tiles)?;
+        let hint = game_board
+            .shift(BoardDirection::Left)
+            .hint()
+            .expect("left should merge both pairs");
+
+        tui_board.setup_animation(&hint)?;
+        let merging: Vec<BoardIdx> = tui_board
+            .moving_slots
+            .iter()
+            .filter(|slot| {
+                slot.rectangle()
+                    .is_some_and(|r| r.z() == MERGING_ANIMATION_LAYER_IDX)
+            })
+            .filter_map(Slot::board_index)
+            .collect();
+        // the 2 at (0, 1) stays put, so only the 4 sliding from (2, 1) slides beneath another tile
+        assert_eq!(merging, vec![BoardIdx(1, 1)]);
+
+        // each frame would fail with CellAlreadyOwned if a slider ran into another on its layer
+        let mut frames = 0;
+        while tui_board.animate()? {
+            frames += 1;
+        }
+        assert!(frames > 0);
+        tui_board.teardown_animation()?;
+
+        let rendered = canvas.to_string();
+        for (x, y) in [(0, 1), (1, 1)] {
+            let slot = &tui_board.slots[y][x];
+            assert_eq!(
+                slot.rectangle(),
+                Some(LayoutSpec::classic().tile_rectangle(x, y, TILE_LAYER_IDX)),
+                "{}",
+                rendered
+            );
+        }
+        assert_eq!(tui_board.slots[1][0].value(), Some(card(4)));
+        assert_eq!(tui_board.slots[1][1].value(), Some(card(8)));
+        verify_occupied_layers(
+            &canvas,
+            vec![BOARD_LAYER_IDX, TILE_LAYER_IDX],
+            vec![MERGING_ANIMATION_LAYER_IDX, UPPER_ANIMATION_LAYER_IDX],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_slide() -> Result<()> {
+        init()?;
+
+        let logger = env_logger::Logger::from_default_env();
+
+        let tiles = [(BoardIdx(0, 0), 4), (BoardIdx(0, 1), 4)];
+        let (mut game_board, canvas, mut tui_board) = setup(100, 100, &tiles)?;
+
+        let hint = game_board
+            .shift(BoardDirection::Down)
+            .hint()
+            .expect("down should definitely result in hints");
+        assert_eq!(hint.hints().len(), 3);
+
+        let hints = hint.hints();
+        let (idx1, hint1) = hints.get(0).expect("expecting three hints");
+        let (idx2, hint2) = hints.get(1).expect("expecting three hints");
+        let (idx3, hint3) = hints.get(2).expect("expecting three hints");
+
+        assert_eq!(*idx1, BoardIdx(0, 1));
+        assert!(matches!(hint1, Hint::ToIdx(BoardIdx(0, 3))));
         assert_eq!(*idx2, BoardIdx(0, 0));
         assert!(matches!(hint2, Hint::NewValueToIdx(3, BoardIdx(0, 3))));
-        assert_eq!(*idx3, BoardIdx(2, 0));
-        assert!(matches!(hint3, Hint::NewTile(1, Direction::Down)));
+        assert_eq!(*idx3, BoardIdx(3, 0));
+        assert!(matches!(hint3, Hint::NewTile(1, BoardDirection::Down)));
+
+        verify_occupied_layers(&canvas, vec![2, 4], vec![0, 1, 3, 5, 6, 7]);
+        tui_board.setup_animation(&hint)?;
+        verify_occupied_layers(&canvas, vec![2, 3, 5, 6], vec![0, 1, 4, 7, 8]);
+
+        // TODO: verify board after setup
+        assert_eq!(tui_board.moving_slots.len(), 3);
+        assert_eq!(tui_board.done_slots.len(), 0);
+        assert_eq!(tui_board.disappearing_slots.len(), 0);
+
+        while tui_board.animate()? {
+            // TODO: verify intermediate states after every animation frame
+            verify_occupied_layers(&canvas, vec![2, 3, 5, 6], vec![0, 1, 4, 7, 8]);
+            logger.log(&debug(format_args!(
+                "moving slot count: {}",
+                tui_board.moving_slots.len()
+            )));
+            logger.log(&debug(format_args!(
+                "active moving slot count: {}",
+                tui_board
+                    .moving_slots
+                    .iter()
+                    .map(|s| match s {
+                        Slot::Empty => 0,
+                        _ => 1,
+                    })
+                    .sum::<u16>()
+            )));
+            logger.log(&debug(format_args!(
+                "non-empty done slot count: {}",
+                tui_board
+                    .done_slots
+                    .iter()
+                    .map(|(_, s)| match s {
+                        Slot::Empty => 0,
+                        _ => 1,
+                    })
+                    .sum::<u16>()
+            )));
+            logger.log(&debug(format_args!(
+                "done_slot count  : {}",
+                tui_board.done_slots.len()
+            )));
+        }
+        tui_board.teardown_animation()?;
+        assert_eq!(tui_board.moving_slots.len(), 0);
+        assert_eq!(tui_board.done_slots.len(), 0);
+        assert_eq!(tui_board.disappearing_slots.len(), 0);
+        verify_occupied_layers(&canvas, vec![2, 4], vec![0, 1, 3, 5, 6, 7]);
+        // TODO: verify canvas after teardown
+
+        Ok(())
+    }
+
+    /// Sets up the animation of two 4s merging downwards into the bottom left corner, where the
+    /// lower of the two stays put and is merged into.
+    fn merge_into_standing_tile(fade_merged: bool) -> Result<(Canvas, Tui48Board)> {
+        let tiles = [(BoardIdx(0, 2), 4), (BoardIdx(0, 3), 4)];
+        let (mut game_board, canvas, mut tui_board) = setup(100, 100, &tiles)?;
+        tui_board.fade_merged = fade_merged;
+        let hint = game_board
+            .shift(BoardDirection::Down)
+            .hint()
+            .expect("down should definitely result in hints");
+        tui_board.setup_animation(&hint)?;
+        Ok((canvas, tui_board))
+    }
+
+    /// The content and background color of a cell.
+    type CellLook = (Option<char>, Option<(u8, u8, u8)>);
+
+    /// The content and background color of every cell changed since the canvas was last looked
+    /// at.
+    fn changed_cells(canvas: &Canvas) -> HashMap<(usize, usize), CellLook> {
+        canvas
+            .get_changed()
+            .into_iter()
+            .map(|stack| {
+                let bg = stack.colors().1.map(|c| (c.r(), c.g(), c.b()));
+                (stack.coordinates(), (stack.content(), bg))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_slide_fades_the_tile_merged_into() -> Result<()> {
+        init()?;
+        let (canvas, mut tui_board) = merge_into_standing_tile(true)?;
+        assert_eq!(tui_board.disappearing_slots.len(), 1);
+        let consumed = LayoutSpec::classic().tile_rectangle(0, 3, LOWER_ANIMATION_LAYER_IDX);
+        assert_eq!(
+            tui_board.disappearing_slots[0].rectangle(),
+            Some(consumed.clone())
+        );
+        verify_occupied_layers(&canvas, vec![2, 3, 6], vec![4, 5]);
+
+        let before = changed_cells(&canvas);
+        let center = (
+            consumed.x() + TILE_WIDTH / 2,
+            consumed.y() + TILE_HEIGHT / 2,
+        );
+        assert_eq!(before[&center].0, Some('4'));
+        assert!(tui_board.animate()?);
+        let after = changed_cells(&canvas);
+        assert_eq!(after[&center].0, Some('\u{b7}'));
+        let lightness = |cell: &CellLook| {
+            let (r, g, b) = cell.1.expect("tiles have a background");
+            Rgb::new(r, g, b).lightness()
+        };
+        assert!(lightness(&after[&center]) < lightness(&before[&center]));
+
+        while tui_board.animate()? {
+            // the fading tile stays beneath the tile arriving on top of it
+            verify_occupied_layers(&canvas, vec![2, 3, 6], vec![4, 5]);
+        }
+        tui_board.teardown_animation()?;
+        assert_eq!(tui_board.disappearing_slots.len(), 0);
+        assert_eq!(tui_board.slots[3][0].value(), Some(card(8)));
+        verify_occupied_layers(&canvas, vec![2, 4], vec![3, 5, 6]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_slide_without_fading_leaves_the_tile_merged_into() -> Result<()> {
+        init()?;
+        let mut finished = Vec::new();
+        for fade_merged in [false, true] {
+            let (canvas, mut tui_board) = merge_into_standing_tile(fade_merged)?;
+            if !fade_merged {
+                assert_eq!(tui_board.disappearing_slots.len(), 0);
+                verify_occupied_layers(&canvas, vec![2, 3, 4, 6], vec![5]);
+            }
+            while tui_board.animate()? {}
+            tui_board.teardown_animation()?;
+            verify_occupied_layers(&canvas, vec![2, 4], vec![3, 5, 6]);
+            finished.push(changed_cells(&canvas));
+        }
+        // the fade leaves no trace once the animation is over
+        assert_eq!(finished[0], finished[1]);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::double_next_spawn(PowerUp::DoubleNextSpawn)]
+    #[case::remove_smallest(PowerUp::RemoveSmallest)]
+    #[case::merge_all(PowerUp::MergeAll)]
+    fn power_ups_animate_without_leaking_layers(
+        #[case] power_up: PowerUp,
+        #[values(false, true)] fade_merged: bool,
+    ) -> Result<()> {
+        init()?;
+        let tiles = [
+            (BoardIdx(0, 0), 2),
+            (BoardIdx(1, 0), 2),
+            (BoardIdx(3, 1), 8),
+            (BoardIdx(3, 2), 8),
+        ];
+        let (game_board, canvas, mut tui_board) = setup(100, 100, &tiles)?;
+        let mut game_board = game_board.with_power_ups();
+        game_board.charge_power_up(power_up);
+        tui_board.fade_merged = fade_merged;
+        let hint = game_board
+            .activate_power_up()
+            .hint()
+            .expect("every power-up applies to the board");
+        tui_board.setup_animation(&hint)?;
+        while tui_board.animate()? {}
+        tui_board.teardown_animation()?;
+        verify_occupied_layers(&canvas, vec![2, 4], vec![3, 5, 6, 7]);
+        let round = game_board.current();
+        for (y, row) in tui_board.slots.iter().enumerate() {
+            for (x, slot) in row.iter().enumerate() {
+                let expected = Some(round.get(&BoardIdx(x, y))).filter(|card| *card > 0);
+                assert_eq!(slot.value(), expected, "slot ({},{})", x, y);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn slot_animation_overlap_check_ignores_merges() -> Result<()> {
+        init()?;
+
+        let tiles = [(BoardIdx(0, 0), 4), (BoardIdx(0, 1), 4)];
+        let (mut game_board, _canvas, mut tui_board) = setup(100, 100, &tiles)?;
+        let hint = game_board
+            .shift(BoardDirection::Down)
+            .hint()
+            .expect("down should definitely result in hints");
+        tui_board.setup_animation(&hint)?;
+        assert!(tui_board.slot_animation_overlap_check().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn slot_animation_overlap_check_reports_duplicate_targets() -> Result<()> {
+        init()?;
+
+        let tiles = [(BoardIdx(3, 0), 4), (BoardIdx(0, 3), 8)];
+        let (_game_board, _canvas, mut tui_board) = setup(100, 100, &tiles)?;
+        for idx in [BoardIdx(3, 0), BoardIdx(0, 3)] {
+            let slot = tui_board.get_slot(&idx)?;
+            let slot = Slot::to_sliding(
+                slot,
+                BoardIdx(3, 3),
+                None,
+                UPPER_ANIMATION_LAYER_IDX,
+                &LayoutSpec::classic(),
+                EasingFn::Linear,
+            )?;
+            tui_board.moving_slots.push(slot);
+        }
+        assert_eq!(
+            tui_board.slot_animation_overlap_check(),
+            vec![
+                (BoardIdx(3, 3), BoardIdx(3, 0)),
+                (BoardIdx(3, 3), BoardIdx(0, 3)),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn clear_all_animations_mid_animation() -> Result<()> {
+        init()?;
+
+        let tiles = [(BoardIdx(0, 0), 4), (BoardIdx(0, 1), 4)];
+        let (mut game_board, canvas, mut tui_board) = setup(100, 100, &tiles)?;
+
+        let hint = game_board
+            .shift(BoardDirection::Down)
+            .hint()
+            .expect("down should definitely result in hints");
+        tui_board.setup_animation(&hint)?;
+        assert!(tui_board.animate()?);
+        assert!(!tui_board.moving_slots.is_empty());
+
+        tui_board.clear_all_animations(&game_board)?;
+        assert_eq!(tui_board.moving_slots.len(), 0);
+        assert_eq!(tui_board.done_slots.len(), 0);
+        assert_eq!(tui_board.disappearing_slots.len(), 0);
+        verify_occupied_layers(&canvas, vec![2, 4], vec![0, 1, 3, 5, 6, 7]);
+
+        let round = game_board.current();
+        for (y, row) in tui_board.slots.iter().enumerate() {
+            for (x, slot) in row.iter().enumerate() {
+                let value = round.get(&BoardIdx(x, y));
+                match slot {
+                    Slot::Static(tile) => assert_eq!(tile.value(), value),
+                    Slot::Empty => assert_eq!(value, 0),
+                    _ => panic!("slot ({}, {}) should be static or empty", x, y),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn sync_leaves_a_board_that_shows_the_round_untouched() -> Result<()> {
+        init()?;
+
+        let tiles = [(BoardIdx(0, 0), 4), (BoardIdx(2, 3), 16)];
+        let (game_board, canvas, mut tui_board) = setup(100, 100, &tiles)?;
+        let _ = canvas.get_changed();
+
+        let report = tui_board.sync(&game_board.current())?;
+        assert_eq!(report, SyncReport::default());
+        assert_eq!(changed_cells(&canvas).len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn sync_changes_only_the_slots_that_disagree_with_the_round() -> Result<()> {
+        init()?;
+
+        let tiles = [
+            (BoardIdx(0, 0), 4),
+            (BoardIdx(1, 1), 4),
+            (BoardIdx(3, 3), 4),
+        ];
+        let (_, canvas, mut tui_board) = setup(100, 100, &tiles)?;
+        let _ = canvas.get_changed();
+
+        let round = with_tiles(&[
+            (BoardIdx(0, 0), 4),
+            (BoardIdx(1, 1), 8),
+            (BoardIdx(2, 2), 2),
+        ]);
+        let report = tui_board.sync(&round)?;
+        assert_eq!(report.dropped, vec![BoardIdx(3, 3)]);
+        assert_eq!(report.created, vec![BoardIdx(2, 2)]);
+        assert_eq!(report.redrawn, vec![BoardIdx(1, 1)]);
+        assert_eq!(report.changes(), 3);
+        assert_eq!(tui_board.verify_consistency(&round), Ok(()));
+
+        let layout = LayoutSpec::classic();
+        let changed = changed_cells(&canvas);
+        assert!(!changed.is_empty());
+        for (x, y) in changed.into_keys() {
+            let within = |idx: &BoardIdx| {
+                let slot = layout.tile_rectangle(idx.x(), idx.y(), TILE_LAYER_IDX);
+                (slot.x()..slot.extents().0).contains(&x)
+                    && (slot.y()..slot.extents().1).contains(&y)
+            };
+            assert!(
+                !within(&BoardIdx(0, 0)),
+                "({},{}) belongs to a tile that already agreed",
+                x,
+                y
+            );
+        }
+
+        // a second sync has nothing left to do
+        assert!(tui_board.sync(&round)?.is_empty());
+        assert_eq!(changed_cells(&canvas).len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn settling_after_a_move_has_nothing_to_repair() -> Result<()> {
+        init()?;
+
+        let tiles = [
+            (BoardIdx(0, 0), 4),
+            (BoardIdx(0, 1), 4),
+            (BoardIdx(3, 2), 2),
+        ];
+        let (mut game_board, _canvas, mut tui_board) = setup(100, 100, &tiles)?;
+        let prior = game_board.current();
+        assert!(game_board.shift(BoardDirection::Down).hint().is_some());
+        tui_board.animate_new_round(&prior, &game_board.current())?;
+        while tui_board.animate()? {}
+
+        assert!(tui_board.settle(&game_board.current())?.is_empty());
+        assert!(tui_board.sync(&game_board.current())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn sync_throws_away_an_interrupted_animation() -> Result<()> {
+        init()?;
+
+        let tiles = [(BoardIdx(0, 0), 4), (BoardIdx(0, 1), 4)];
+        let (mut game_board, canvas, mut tui_board) = setup(100, 100, &tiles)?;
+        let hint = game_board
+            .shift(BoardDirection::Down)
+            .hint()
+            .expect("down should definitely result in hints");
+        tui_board.setup_animation(&hint)?;
+        assert!(tui_board.animate()?);
+
+        let report = tui_board.sync(&game_board.current())?;
+        assert!(!report.is_empty());
+        assert!(tui_board.moving_slots.is_empty());
+        assert_eq!(tui_board.verify_consistency(&game_board.current()), Ok(()));
+        verify_occupied_layers(&canvas, vec![2, 4], vec![0, 1, 3, 5, 6, 7]);
+        assert!(tui_board.sync(&game_board.current())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn verify_consistency_after_a_move() -> Result<()> {
+        init()?;
+
+        let tiles = [
+            (BoardIdx(0, 0), 4),
+            (BoardIdx(0, 1), 4),
+            (BoardIdx(2, 2), 8),
+        ];
+        let (mut game_board, _canvas, mut tui_board) = setup(100, 100, &tiles)?;
+        assert_eq!(tui_board.verify_consistency(&game_board.current()), Ok(()));
+
+        let prior = game_board.current();
+        assert!(game_board.shift(BoardDirection::Down).hint().is_some());
+        tui_board.animate_new_round(&prior, &game_board.current())?;
+        while tui_board.animate()? {}
+        tui_board.teardown_animation()?;
+        assert_eq!(tui_board.verify_consistency(&game_board.current()), Ok(()));
+        Ok(())
+    }
+
+    #[test]
+    fn verify_consistency_compares_values_with_the_round() -> Result<()> {
+        init()?;
+
+        let tiles = [
+            (BoardIdx(0, 0), 4),
+            (BoardIdx(1, 0), 8),
+            (BoardIdx(2, 0), 2),
+        ];
+        let (_game_board, _canvas, tui_board) = setup(100, 100, &tiles)?;
+        let round = with_tiles(&[
+            (BoardIdx(1, 0), 16),
+            (BoardIdx(2, 0), 2),
+            (BoardIdx(3, 3), 2),
+        ]);
+        assert_eq!(
+            tui_board.verify_consistency(&round),
+            Err(vec![
+                Inconsistency::UnexpectedTile {
+                    at: BoardIdx(0, 0),
+                    actual: card(4),
+                },
+                Inconsistency::WrongValue {
+                    at: BoardIdx(1, 0),
+                    expected: card(16),
+                    actual: card(8),
+                },
+                Inconsistency::MissingTile {
+                    at: BoardIdx(3, 3),
+                    expected: card(2),
+                },
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn verify_consistency_finds_swapped_tile_buffers() -> Result<()> {
+        init()?;
+
+        let tiles = [(BoardIdx(1, 1), 4), (BoardIdx(3, 2), 8)];
+        let (game_board, _canvas, mut tui_board) = setup(100, 100, &tiles)?;
+        let (Slot::Static(mut first), Slot::Static(mut second)) = (
+            tui_board.get_slot(&BoardIdx(1, 1))?,
+            tui_board.get_slot(&BoardIdx(3, 2))?,
+        ) else {
+            panic!("both slots should hold static tiles");
+        };
+        std::mem::swap(&mut first.buf, &mut second.buf);
+        tui_board.put_slot(&BoardIdx(1, 1), Slot::Static(first))?;
+        tui_board.put_slot(&BoardIdx(3, 2), Slot::Static(second))?;
+
+        let layout = LayoutSpec::classic();
+        assert_eq!(
+            tui_board.verify_consistency(&game_board.current()),
+            Err(vec![
+                Inconsistency::Misplaced {
+                    at: BoardIdx(1, 1),
+                    expected: layout.tile_rectangle(1, 1, TILE_LAYER_IDX),
+                    actual: layout.tile_rectangle(3, 2, TILE_LAYER_IDX),
+                },
+                Inconsistency::Misplaced {
+                    at: BoardIdx(3, 2),
+                    expected: layout.tile_rectangle(3, 2, TILE_LAYER_IDX),
+                    actual: layout.tile_rectangle(1, 1, TILE_LAYER_IDX),
+                },
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn verify_consistency_finds_stale_sliding_slots() -> Result<()> {
+        init()?;
+
+        let tiles = [(BoardIdx(0, 0), 4), (BoardIdx(2, 3), 8)];
+        let (game_board, _canvas, mut tui_board) = setup(100, 100, &tiles)?;
+        let slot = tui_board.get_slot(&BoardIdx(2, 3))?;
+        let slot = Slot::to_sliding(
+            slot,
+            BoardIdx(2, 3),
+            None,
+            UPPER_ANIMATION_LAYER_IDX,
+            &LayoutSpec::classic(),
+            EasingFn::Linear,
+        )?;
+        tui_board.put_slot(&BoardIdx(2, 3), slot)?;
+
+        assert_eq!(
+            tui_board.verify_consistency(&game_board.current()),
+            Err(vec![
+                Inconsistency::LeftoverAnimation { at: BoardIdx(2, 3) },
+                Inconsistency::AnimationLayerOccupied {
+                    layer: UPPER_ANIMATION_LAYER_IDX
+                },
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "board disagrees with the round")]
+    fn check_consistency_panics_with_strict_checks() {
+        init().unwrap();
+
+        let (_game_board, _canvas, tui_board) = setup(100, 100, &[(BoardIdx(0, 0), 4)]).unwrap();
+        tui_board.check_consistency(&with_tiles(&[(BoardIdx(0, 0), 8)]));
+    }
+
+    #[test]
+    fn tiles_enter_into_their_board_positions() -> Result<()> {
+        init()?;
+
+        // one tile in every corner plus a few inner and edge tiles
+        let round = with_tiles(&[
+            (BoardIdx(0, 0), 2),
+            (BoardIdx(3, 0), 4),
+            (BoardIdx(0, 3), 8),
+            (BoardIdx(3, 3), 16),
+            (BoardIdx(1, 1), 32),
+            (BoardIdx(2, 1), 64),
+            (BoardIdx(1, 2), 128),
+            (BoardIdx(2, 3), 256),
+        ]);
+        let mut canvas = Canvas::new(100, 100);
+        let mut game_board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(10),
+            BoardConfig::default(),
+        );
+        game_board.set_initial_round(round.clone());
+        let mut tui_board = Tui48Board::new(
+            &game_board,
+            &mut canvas,
+            &[Indicator::Score],
+            LayoutSpec::classic(),
+        )?;
+
+        for y in 0..4 {
+            for x in 0..4 {
+                if round.get(&BoardIdx(x, y)) > 0 {
+                    tui_board.tile_enter_animation(BoardIdx(x, y))?;
+                }
+            }
+        }
+        assert_eq!(tui_board.moving_slots.len(), 8);
+        for slot in &tui_board.moving_slots {
+            // every tile starts outside its slot, diagonally away from the center
+            let from = slot.rectangle().expect("entering tiles should have a tile");
+            let to = slot
+                .to_rectangle()
+                .expect("entering tiles should be sliding");
+            assert_ne!(from.x(), to.x());
+            assert_ne!(from.y(), to.y());
+        }
+
+        let mut frames = 0;
+        while tui_board.animate()? {
+            frames += 1;
+        }
+        assert_eq!(frames, LayoutSpec::classic().enter_frames());
+        tui_board.teardown_animation()?;
+        verify_occupied_layers(&canvas, vec![2, 4], vec![0, 1, 3, 5, 6, 7]);
+
+        let current = game_board.current();
+        for (y, row) in tui_board.slots.iter().enumerate() {
+            for (x, slot) in row.iter().enumerate() {
+                let value = current.get(&BoardIdx(x, y));
+                match slot {
+                    Slot::Static(tile) => {
+                        assert_eq!(tile.value(), value);
+                        assert_eq!(tile.board_index(), BoardIdx(x, y));
+                        assert_eq!(
+                            tile.rectangle(),
+                            LayoutSpec::classic().tile_rectangle(x, y, TILE_LAYER_IDX)
+                        );
+                    }
+                    Slot::Empty => assert_eq!(value, 0),
+                    _ => panic!("slot ({}, {}) should be static or empty", x, y),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn tile_enter_animation_requires_a_tile() -> Result<()> {
+        init()?;
+
+        let tiles = [(BoardIdx(0, 0), 4)];
+        let (_, _, mut tui_board) = setup(100, 100, &tiles)?;
+        assert!(tui_board.tile_enter_animation(BoardIdx(1, 1)).is_err());
+        assert!(tui_board.moving_slots.is_empty());
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::zero(0, 0)]
+    #[case::small(10, 10)]
+    fn check_bounds_error_if_terminal_is_too_small_for_board(
+        #[case] width: usize,
+        #[case] height: usize,
+    ) -> Result<()> {
+        init()?;
+
+        let tiles = [(BoardIdx(0, 0), 4), (BoardIdx(0, 1), 4)];
+        match setup(width, height, &tiles) {
+            Err(Error::TerminalTooSmall { have, need }) => {
+                assert_eq!(have, (width, height));
+                assert_eq!(need, LayoutRequirements::new(&LayoutSpec::classic()).need());
+            }
+            Err(e) => panic!("expected the terminal to be too small, got {}", e),
+            Ok(_) => panic!("a {} x {} terminal should be too small", width, height),
+        }
+        Ok(())
+    }
+
+    /// Tries every size within a cell of the requirements, which only fits once it meets them
+    /// along both axes, whichever optional indicators are shown.
+    #[rstest]
+    fn check_bounds_at_requirement_boundaries(
+        #[values(-1, 0, 1)] dw: isize,
+        #[values(-1, 0, 1)] dh: isize,
+        #[values(
+            &[Indicator::Score][..],
+            &[Indicator::Score, Indicator::Outlook, Indicator::Pressure][..]
+        )]
+        indicators: &[Indicator],
+        #[values(LayoutSpec::classic(), LayoutSpec::square(DEFAULT_CELL_ASPECT).unwrap())]
+        layout: LayoutSpec,
+    ) -> Result<()> {
+        init()?;
+
+        let requirements = LayoutRequirements::new(&layout);
+        let width = (requirements.min_width as isize + dw) as usize;
+        let height = (requirements.min_height as isize + dh) as usize;
+        let mut canvas = Canvas::new(width, height);
+        let mut game_board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(10),
+            BoardConfig::default(),
+        );
+        game_board.set_initial_round(with_tiles(&[(BoardIdx(0, 0), 4), (BoardIdx(0, 1), 4)]));
+
+        match Tui48Board::new(&game_board, &mut canvas, indicators, layout) {
+            Ok(_) => assert!(
+                dw >= 0 && dh >= 0,
+                "{} x {} should be too small",
+                width,
+                height
+            ),
+            Err(Error::TerminalTooSmall { have, need }) => {
+                assert!(dw < 0 || dh < 0, "{} x {} should fit", width, height);
+                assert_eq!(have, (width, height));
+                assert_eq!(need, requirements.need());
+                // nothing was drawn before the size was found to be too small
+                verify_occupied_layers(&canvas, vec![], (0..8).collect());
+            }
+            Err(e) => panic!("expected the terminal to be too small, got {}", e),
+        }
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::top(BoardDirection::Down)]
+    #[case::bottom(BoardDirection::Up)]
+    #[case::left(BoardDirection::Right)]
+    #[case::right(BoardDirection::Left)]
+    fn check_bounds_animation(
+        #[case] slide_dir: BoardDirection,
+        #[values(LayoutSpec::classic(), LayoutSpec::square(DEFAULT_CELL_ASPECT).unwrap())]
+        layout: LayoutSpec,
+    ) -> Result<()> {
+        init()?;
+
+        let tiles = [(BoardIdx(1, 1), 4), (BoardIdx(2, 2), 4)];
+        let (x_extent, y_extent) = LayoutRequirements::new(&layout).need();
+        let (mut game_board, _, mut tui_board) =
+            setup_with_layout(x_extent, y_extent, &tiles, layout)?;
+
+        let hint = game_board
+            .shift(slide_dir.clone())
+            .hint()
+            .expect(format!("{:?} slide should result in hints", slide_dir).as_str());
+
+        let r = tui_board.draw_score(&game_board);
+        assert!(r.is_ok());
+        let r = tui_board.setup_animation(&hint);
+        assert!(r.is_ok());
+        while tui_board.animate()? {}
+        let r = tui_board.teardown_animation();
+        assert!(r.is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn new_tiles_from_full_board_are_all_static() -> Result<()> {
+        init()?;
+
+        let mut cards = [[0; 4]; 4];
+        for (y, row) in cards.iter_mut().enumerate() {
+            for (x, card) in row.iter_mut().enumerate() {
+                *card = (1 + (x + y) % 5) as u8;
+            }
+        }
+        let mut game_board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(10),
+            BoardConfig::default(),
+        );
+        game_board.set_initial_round(Round::from_cards(cards));
+        let mut canvas = Canvas::new(100, 50);
+
+        let slots = Tui48Board::new_tiles_from_board(
+            &game_board,
+            &mut canvas,
+            &LayoutSpec::classic(),
+            &numbers(),
+            &default_theme(),
+        )?;
+        assert_eq!(slots.len(), 4);
+        for (y, row) in slots.iter().enumerate() {
+            assert_eq!(row.len(), 4);
+            for (x, slot) in row.iter().enumerate() {
+                let tile = match slot {
+                    Slot::Static(tile) => tile,
+                    other => panic!("slot ({}, {}) should be static, got {}", x, y, other),
+                };
+                assert_eq!(tile.value, cards[y][x]);
+                assert_eq!(tile.idx, BoardIdx(x, y));
+                assert_eq!(
+                    tile.buf.rectangle(),
+                    LayoutSpec::classic().tile_rectangle(x, y, TILE_LAYER_IDX)
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn new_tiles_from_board_leaves_empty_slots_empty() -> Result<()> {
+        init()?;
+
+        let tiles = [(BoardIdx(0, 0), 4), (BoardIdx(2, 1), 32)];
+        let mut game_board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(10),
+            BoardConfig::default(),
+        );
+        game_board.set_initial_round(with_tiles(&tiles));
+        let mut canvas = Canvas::new(100, 50);
+
+        let slots = Tui48Board::new_tiles_from_board(
+            &game_board,
+            &mut canvas,
+            &LayoutSpec::classic(),
+            &numbers(),
+            &default_theme(),
+        )?;
+        for (y, row) in slots.iter().enumerate() {
+            for (x, slot) in row.iter().enumerate() {
+                let value = tiles
+                    .iter()
+                    .find(|(idx, _)| *idx == BoardIdx(x, y))
+                    .map(|(_, value)| *value);
+                match (slot, value) {
+                    (Slot::Static(tile), Some(value)) => assert_eq!(tile.value, card(value)),
+                    (Slot::Empty, None) => (),
+                    (slot, value) => panic!("({}, {}) is {} for {:?}", x, y, slot, value),
+                }
+            }
+        }
+        // nothing but the tiles was drawn
+        assert!(canvas.layer_occupied(TILE_LAYER_IDX));
+        assert!(!canvas.layer_occupied(BOARD_LAYER_IDX));
+        Ok(())
+    }
+
+    /// Reads the value displayed in the middle row of the tile at the given board position.
+    fn tile_text(frame: &str, x: usize, y: usize) -> String {
+        let r = LayoutSpec::classic().tile_rectangle(x, y, TILE_LAYER_IDX);
+        let row = frame
+            .lines()
+            .nth(r.y() + TILE_HEIGHT / 2)
+            .expect("frame should be tall enough to contain the board");
+        row.chars()
+            .skip(r.x() + 1)
+            .take(TILE_WIDTH - 2)
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+
+    fn board_text(frame: &str) -> Vec<String> {
+        let mut texts = Vec::new();
+        for y in 0..4 {
+            for x in 0..4 {
+                texts.push(tile_text(frame, x, y));
+            }
+        }
+        texts
+    }
+
+    fn round_text(round: &Round) -> Vec<String> {
+        let mut texts = Vec::new();
+        for y in 0..4 {
+            for x in 0..4 {
+                texts.push(match round.get(&BoardIdx(x, y)) {
+                    0 => String::new(),
+                    card => format!("{}", display_value(card)),
+                });
+            }
+        }
+        texts
+    }
+
+    /// A game played by rotating through the directions, skipping those that would be rejected;
+    /// this plays badly enough that the game is over quickly.
+    struct Recording {
+        initial: Round,
+        moves: Vec<BoardDirection>,
+        merges: usize,
+        first_merge: Option<Round>,
+        last: Round,
+        score: u32,
+        game_over: bool,
+    }
+
+    fn record_game(seed: u64, max_moves: usize) -> Recording {
+        let rotation = [
+            BoardDirection::Up,
+            BoardDirection::Left,
+            BoardDirection::Down,
+            BoardDirection::Right,
+        ];
+        let mut board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(seed),
+            BoardConfig::default(),
+        );
+        let initial = board.current();
+        let mut moves = Vec::new();
+        let mut merges = 0;
+        let mut first_merge = None;
+        let mut game_over = false;
+        while !game_over && moves.len() < max_moves {
+            let direction = (0..rotation.len())
+                .map(|i| rotation[(moves.len() + i) % rotation.len()].clone())
+                .find(|d| board.current().would_change(d))
+                .expect("the game should end before running out of moves");
+            let hint = match board.shift(direction.clone()) {
+                MoveOutcome::Moved(hint) => hint,
+                MoveOutcome::Rejected => unreachable!(),
+            };
+            merges += hint.merges();
+            if first_merge.is_none() && board.score() > 0 {
+                first_merge = Some(board.current());
+            }
+            game_over = hint.game_over();
+            moves.push(direction);
+        }
+        Recording {
+            initial,
+            moves,
+            merges,
+            first_merge,
+            last: board.current(),
+            score: board.score(),
+            game_over,
+        }
+    }
+
+    #[test]
+    fn game_over_prompt_follows_the_keymap() -> Result<()> {
+        use crate::tui::keymap::{Key, KeyBinding};
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let seed = 13;
+        let recording = record_game(seed, usize::MAX);
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events = MockEventSource::new(
+            recording
+                .moves
+                .iter()
+                .map(|d| Event::UserInput(UserInput::Direction(Direction::from_board(d)))),
+        );
+        let mut keymap = Keymap::default();
+        keymap.unbind(&KeyBinding::plain(Key::Char('n')));
+        keymap.bind(KeyBinding::ctrl(Key::Char('r')), UserInput::NewGame);
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(seed),
+            BoardConfig::default(),
+        );
+        let mut tui48 = Tui48::new(board, renderer, events)?.with_keymap(keymap);
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.run()?;
+
+        let frames = frames.borrow();
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(last_frame.contains("Ctrl+R"), "{}", last_frame);
+        assert!(!last_frame.contains("press n"), "{}", last_frame);
+        Ok(())
+    }
+
+    #[test]
+    fn undo_takes_back_the_move_that_ended_the_game() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let seed = 13;
+        let recording = record_game(seed, usize::MAX);
+        assert!(recording.game_over);
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events = MockEventSource::new(
+            recording
+                .moves
+                .iter()
+                .map(|d| Event::UserInput(UserInput::Direction(Direction::from_board(d))))
+                .chain([Event::UserInput(UserInput::Undo)]),
+        );
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(seed),
+            BoardConfig::default(),
+        );
+        let mut tui48 = Tui48::new(board, renderer, events)?;
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.play()?;
+
+        assert_eq!(tui48.board.move_count(), recording.moves.len() - 1);
+        assert!(!tui48.board.is_game_over());
+        let frames = frames.borrow();
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(!last_frame.contains("game over"), "{}", last_frame);
+        Ok(())
+    }
+
+    #[test]
+    fn a_new_game_starts_counting_moves_over() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let events = MockEventSource::new([
+            Event::UserInput(UserInput::Direction(Direction::Left)),
+            Event::UserInput(UserInput::NewGame),
+        ]);
+        let mut board = Board::new_seeded(1, BoardConfig::default());
+        board.set_initial_round(with_tiles(&[(BoardIdx(2, 0), 2), (BoardIdx(3, 0), 2)]));
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let mut tui48 = Tui48::new(board, renderer, events)?;
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.play()?;
+
+        let frames = frames.borrow();
+        assert!(
+            frames.iter().any(|frame| frame.contains("Moves: 1")),
+            "the move should have been counted"
+        );
+        assert_eq!(tui48.board.move_count(), 0);
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(last_frame.contains("Moves: 0"), "{}", last_frame);
+        Ok(())
+    }
+
+    #[test]
+    fn undo_slides_the_tiles_back_and_restores_the_panels() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let events = MockEventSource::new([
+            Event::UserInput(UserInput::Direction(Direction::Left)),
+            Event::UserInput(UserInput::Undo),
+        ]);
+        let mut board = Board::new_seeded(1, BoardConfig::default());
+        board.set_initial_round(with_tiles(&[(BoardIdx(2, 0), 2), (BoardIdx(3, 0), 2)]));
+        let start = board.current();
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let mut tui48 = Tui48::new(board, renderer, events)?;
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.play()?;
+
+        assert_eq!(tui48.board.current(), start);
+        let frames = frames.borrow();
+        let moved = frames
+            .iter()
+            .rposition(|frame| frame.contains("Moves: 1"))
+            .expect("the move should have been shown");
+        let undone = frames.last().expect("frames should have been rendered");
+        assert_eq!(board_text(undone), round_text(&start));
+        assert!(undone.contains("Moves: 0"), "{}", undone);
+        assert!(
+            frames[..frames.len() - 1]
+                .iter()
+                .skip(moved + 1)
+                .any(|frame| board_text(frame) != round_text(&start)),
+            "the tiles should have been seen sliding back"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn redo_shows_the_move_taken_back_made_again() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let start = with_tiles(&[(BoardIdx(2, 0), 2), (BoardIdx(3, 0), 2)]);
+        let board = || {
+            let mut board = Board::new_seeded(1, BoardConfig::default());
+            board.set_initial_round(start.clone());
+            board
+        };
+        let mut moved = board();
+        assert!(matches!(
+            moved.shift(BoardDirection::Left),
+            MoveOutcome::Moved(_)
+        ));
+
+        let events = MockEventSource::new(
+            [
+                UserInput::Direction(Direction::Left),
+                UserInput::Undo,
+                UserInput::Redo,
+                // there is nothing left to make again
+                UserInput::Redo,
+            ]
+            .map(Event::UserInput),
+        );
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let mut tui48 = Tui48::new(board(), renderer, events)?;
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.play()?;
+
+        assert_eq!(tui48.board.current(), moved.current());
+        let frames = frames.borrow();
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert_eq!(board_text(last_frame), round_text(&moved.current()));
+        assert!(last_frame.contains("Moves: 1"), "{}", last_frame);
+        Ok(())
+    }
+
+    #[test]
+    fn undo_on_the_first_round_changes_nothing() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let events = MockEventSource::new([
+            Event::UserInput(UserInput::Undo),
+            Event::UserInput(UserInput::Direction(Direction::Left)),
+            Event::UserInput(UserInput::Undo),
+            Event::UserInput(UserInput::Undo),
+        ]);
+        let mut board = Board::new_seeded(1, BoardConfig::default());
+        board.set_initial_round(with_tiles(&[(BoardIdx(3, 0), 2)]));
+        let start = board.current();
+        let mut tui48 = Tui48::new(board, TestRenderer::new(100, 50), events)?;
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.play()?;
+
+        assert_eq!(tui48.board.current(), start);
+        assert_eq!(tui48.board.move_count(), 0);
+        Ok(())
+    }
+
+    fn winning_game(events: impl IntoIterator<Item = UserInput>) -> Result<WinningGame> {
+        use crate::engine::fixtures::one_merge_from_winning;
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events = MockEventSource::new(events.into_iter().map(Event::UserInput));
+        let mut board = Board::new_seeded(1, BoardConfig::default());
+        board.set_initial_round(one_merge_from_winning());
+        let mut tui48 = Tui48::new(board, renderer, events)?;
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.celebration_delay = Duration::ZERO;
+        tui48.play()?;
+        Ok((tui48, frames))
+    }
+
+    type WinningGame = (
+        Tui48<crate::tui::testing::TestRenderer, crate::tui::testing::MockEventSource>,
+        Rc<RefCell<Vec<String>>>,
+    );
+
+    #[test]
+    fn winning_is_announced_once_and_the_game_can_go_on() -> Result<()> {
+        let (tui48, frames) = winning_game([
+            UserInput::Direction(Direction::Left),
+            UserInput::Continue,
+            UserInput::Direction(Direction::Down),
+            UserInput::Direction(Direction::Right),
+        ])?;
+
+        assert_eq!(tui48.board.move_count(), 3);
+        assert!(tui48.board.has_won());
+        let frames = frames.borrow();
+        let announced: Vec<usize> = frames
+            .iter()
+            .enumerate()
+            .filter(|(_, frame)| frame.contains("you win!"))
+            .map(|(i, _)| i)
+            .collect();
+        assert!(!announced.is_empty(), "the win should have been announced");
+        assert_eq!(
+            announced.last().unwrap() - announced[0] + 1,
+            announced.len(),
+            "the win should only be announced once"
+        );
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(!last_frame.contains("you win!"), "{}", last_frame);
+        Ok(())
+    }
+
+    #[test]
+    fn moving_keeps_a_won_game_going() -> Result<()> {
+        let (tui48, frames) = winning_game([
+            UserInput::Direction(Direction::Left),
+            UserInput::Direction(Direction::Down),
+        ])?;
+
+        // the move that dismissed the announcement was made too
+        assert_eq!(tui48.board.move_count(), 2);
+        let frames = frames.borrow();
+        assert!(frames.iter().any(|f| f.contains("you win!")));
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(!last_frame.contains("you win!"), "{}", last_frame);
+        Ok(())
+    }
+
+    #[test]
+    fn a_won_game_can_be_left_for_a_new_one() -> Result<()> {
+        let (tui48, frames) =
+            winning_game([UserInput::Direction(Direction::Left), UserInput::NewGame])?;
+
+        assert!(frames.borrow().iter().any(|f| f.contains("you win!")));
+        assert_eq!(tui48.board.move_count(), 0);
+        assert!(!tui48.board.has_won());
+        assert_eq!(tui48.session.games_played(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn from_config_builds_the_configured_game() -> Result<()> {
+        use crate::config::test::config_file;
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let path = config_file("seed = 7\nassist = \"merges\"\nscore-breakdown = true\n");
+        let config = GameConfig::load(&path);
+        std::fs::remove_file(&path)?;
+        let config = config?;
+
+        let renderer = TestRenderer::new(100, 50);
+        let tui48 = Tui48::from_config(&config, renderer, MockEventSource::new([]))?;
+        assert_eq!(
+            tui48.board.current(),
+            Board::new_seeded(7, BoardConfig::default()).current()
+        );
+        assert_eq!(tui48.assist, Some(Assist::Merges));
+        assert!(tui48.score_breakdown);
+        assert_eq!(tui48.practice, None);
+        Ok(())
+    }
+
+    #[test]
+    fn arcade_mode_uses_power_ups_with_the_space_bar() -> Result<()> {
+        use crate::config::test::config_file;
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let path = config_file("seed = 7\nmode = \"arcade\"\n");
+        let config = GameConfig::load(&path);
+        std::fs::remove_file(&path)?;
+        let config = config?;
+
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events = MockEventSource::new([Event::UserInput(UserInput::PowerUp)]);
+        let mut tui48 = Tui48::from_config(&config, renderer, events)?;
+        assert_eq!(tui48.mode, Mode::Arcade);
+        tui48
+            .board
+            .set_initial_round(with_tiles(&[(BoardIdx(0, 0), 2), (BoardIdx(1, 0), 2)]));
+        tui48.board.charge_power_up(PowerUp::MergeAll);
+        tui48.frame_delay = Duration::ZERO;
+        let session = tui48.run()?;
+
+        let frames = frames.borrow();
+        let first_frame = frames.first().expect("frames should have been rendered");
+        assert!(
+            first_frame.contains("\u{26a1} merge all"),
+            "{}",
+            first_frame
+        );
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(last_frame.contains("double next 0/20"), "{}", last_frame);
+        assert_eq!(tile_text(last_frame, 0, 0), "4");
+        assert_eq!(tile_text(last_frame, 1, 0), "");
+        // using a power-up isn't a move, so the game doesn't count as played
+        assert!(session.summary().starts_with("arcade session summary\n"));
+        assert_eq!(session.games_played(), 0);
+        Ok(())
+    }
+
+    struct GrowingGame {
+        tui48: Tui48<crate::tui::testing::FaultyRenderer, crate::tui::testing::FaultyEvents>,
+        frames: Rc<RefCell<Vec<String>>>,
+    }
+
+    /// A game in the growth mode on a terminal of the given size, starting 3x3 with a pair of 2s
+    /// that grows the board once merged, playing the given events; the terminal takes on the
+    /// given sizes as it's resized.
+    fn growing_game(
+        terminal: (usize, usize),
+        events: impl IntoIterator<Item = Event>,
+        resizes: impl IntoIterator<Item = (u16, u16)>,
+    ) -> Result<GrowingGame> {
+        use crate::tui::testing::{
+            FaultInjector, FaultyEvents, FaultyRenderer, MockEventSource, TestRenderer,
+        };
+
+        let faults = FaultInjector::default();
+        let inner = TestRenderer::new(terminal.0, terminal.1);
+        let frames = inner.frames();
+        let renderer = FaultyRenderer::new(inner, faults.clone());
+        let size = renderer.size();
+        let events =
+            FaultyEvents::new(MockEventSource::new(events), faults).resizing(size, resizes);
+        let mut board = Board::new_seeded(5, BoardConfig::growing()).with_growth(&[4]);
+        board.set_initial_round(Round::from_cards([[1, 1, 0], [0, 0, 0], [0, 0, 0]]));
+        let mut tui48 = Tui48::new(board, renderer, events)?.with_mode(Mode::Growth);
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.celebration_delay = Duration::ZERO;
+        Ok(GrowingGame { tui48, frames })
+    }
+
+    #[rstest]
+    #[case::fitting(true)]
+    #[case::outgrown(false)]
+    fn the_board_is_laid_out_afresh_as_it_grows(#[case] fits: bool) -> Result<()> {
+        init()?;
+        let need = |size| LayoutRequirements::new(&LayoutSpec::classic().with_size(size)).need();
+        let small = need(BoardConfig::growing());
+        let grown = need(BoardConfig {
+            width: 4,
+            height: 4,
+        });
+        // a row short of the grown board, which the small one still fits in
+        let terminal = match fits {
+            true => grown,
+            false => (grown.0, grown.1 - 1),
+        };
+        assert!(terminal.0 >= small.0 && terminal.1 >= small.1);
+        let GrowingGame { mut tui48, frames } = growing_game(
+            terminal,
+            [
+                Event::UserInput(UserInput::Direction(Direction::Left)),
+                Event::Resize,
+            ],
+            [(grown.0 as u16, grown.1 as u16)],
+        )?;
+        tui48.play()?;
+
+        assert_eq!(tui48.board.dimensions(), (4, 4));
+        assert_eq!(tui48.layout.size(), tui48.board.size());
+        assert_eq!(tui48.tile_occupancy.len(), 4);
+        assert!(tui48.tile_occupancy.iter().all(|row| row.len() == 4));
+        let tui_board = tui48
+            .tui_board
+            .as_ref()
+            .expect("the board should be shown once the terminal fits it");
+        assert_eq!(tui_board.verify_consistency(&tui48.board.current()), Ok(()));
+
+        let frames = frames.borrow();
+        let outgrown = frames
+            .iter()
+            .any(|frame| frame.contains("the board grew to 4x4 and the terminal is too small"));
+        assert_eq!(outgrown, !fits);
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert_eq!(tile_text(last_frame, 0, 0), "4");
+        assert!(tui48
+            .session
+            .summary()
+            .starts_with("growth session summary\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn taking_back_a_growth_shrinks_the_board_on_screen() -> Result<()> {
+        init()?;
+        let GrowingGame { mut tui48, frames } = growing_game(
+            (100, 50),
+            [
+                Event::UserInput(UserInput::Direction(Direction::Left)),
+                Event::UserInput(UserInput::Undo),
+            ],
+            [],
+        )?;
+        tui48.play()?;
+
+        assert_eq!(tui48.board.dimensions(), (3, 3));
+        assert_eq!(tui48.board.move_count(), 1);
+        assert_eq!(tui48.layout.size(), BoardConfig::growing());
+        let tui_board = tui48.tui_board.as_ref().expect("the board should be shown");
+        assert_eq!(tui_board.verify_consistency(&tui48.board.current()), Ok(()));
+        let frames = frames.borrow();
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert_eq!(tile_text(last_frame, 0, 0), "4");
+        Ok(())
+    }
+
+    #[test]
+    fn a_replay_steps_over_a_growth_and_leaves_the_board_grown() -> Result<()> {
+        init()?;
+        let GrowingGame { mut tui48, frames } = growing_game(
+            (100, 50),
+            [
+                UserInput::Direction(Direction::Left),
+                UserInput::Replay,
+                // the growth, then the move
+                UserInput::Direction(Direction::Left),
+                UserInput::Direction(Direction::Left),
+                UserInput::Direction(Direction::Right),
+                UserInput::Replay,
+            ]
+            .map(Event::UserInput),
+            [],
+        )?;
+        tui48.play()?;
+
+        assert_eq!(tui48.board.dimensions(), (4, 4));
+        assert_eq!(tui48.layout.size(), tui48.board.size());
+        let tui_board = tui48.tui_board.as_ref().expect("the board should be shown");
+        assert_eq!(tui_board.verify_consistency(&tui48.board.current()), Ok(()));
+        let frames = frames.borrow();
+        assert!(frames
+            .iter()
+            .any(|frame| frame.contains("replay: move 0 of 1")));
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(!last_frame.contains("replay:"), "{}", last_frame);
+        assert_eq!(tile_text(last_frame, 0, 0), "4");
+        Ok(())
+    }
+
+    #[test]
+    fn new_from_config_file_rejects_invalid_config_before_taking_the_terminal() -> Result<()> {
+        let path = crate::config::test::config_file("seed = \"not a number\"\n");
+        let result = Tui48::new_from_config_file(&path, |_| (), LaunchOptions::default());
+        std::fs::remove_file(&path)?;
+        let e = result.err().expect("the config should be rejected");
+        assert!(
+            matches!(e.downcast_ref(), Some(Error::InvalidConfig { .. })),
+            "{:?}",
+            e
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn play_full_game() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let seed = 13;
+        let recording = record_game(seed, usize::MAX);
+        let first_merge = recording
+            .first_merge
+            .clone()
+            .expect("the game should have merged tiles at some point");
+
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let recovered = renderer.recovered();
+        let events = MockEventSource::new(
+            recording
+                .moves
+                .iter()
+                .map(|d| Event::UserInput(UserInput::Direction(Direction::from_board(d)))),
+        );
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(seed),
+            BoardConfig::default(),
+        );
+        let mut tui48 = Tui48::new(board, renderer, events)?;
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        let session = tui48.run()?;
+        assert!(
+            recovered.get(),
+            "the terminal should be restored when run returns"
+        );
+
+        let frames = frames.borrow();
+        assert_eq!(board_text(&frames[0]), round_text(&recording.initial));
+
+        let merge_frame = frames
+            .iter()
+            .position(|f| board_text(f) == round_text(&first_merge));
+        assert!(merge_frame.is_some(), "no frame shows the first merge");
+
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(last_frame.contains("game over!"), "{}", last_frame);
+        assert_eq!(
+            board_text(&frames[frames.len() - 2]),
+            round_text(&recording.last),
+            "the board should be shown in its final state before the game over message"
+        );
+
+        assert!(last_frame.contains("press q"), "{}", last_frame);
+        let outcome = format!(
+            "{} points in {} moves",
+            recording.score,
+            recording.moves.len()
+        );
+        // the message wraps wherever it needs to
+        let words = last_frame.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert!(words.contains(&outcome), "{}", last_frame);
+
+        let summary = session.summary();
+        assert!(
+            summary.contains("games played   1 (0 abandoned)"),
+            "{}",
+            summary
+        );
+        assert!(
+            summary.contains(&format!("moves          {}", recording.moves.len())),
+            "{}",
+            summary
+        );
+        assert!(
+            summary.contains(&format!("merges         {}", recording.merges)),
+            "{}",
+            summary
+        );
+        assert!(
+            summary.contains(&format!("best score     {}", recording.score)),
+            "{}",
+            summary
+        );
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::without_bell(false)]
+    #[case::with_bell(true)]
+    fn games_play_on_canvases_as_deep_as_their_features_need(
+        #[case] visual_bell: bool,
+    ) -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        // played to the end so that the bell, if enabled, rings for the game being over
+        let recording = record_game(13, usize::MAX);
+        assert!(recording.game_over);
+        let events = MockEventSource::new(
+            recording
+                .moves
+                .iter()
+                .map(|d| Event::UserInput(UserInput::Direction(Direction::from_board(d)))),
+        );
+        let depth = canvas_depth(visual_bell);
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(13),
+            BoardConfig::default(),
+        );
+        let mut tui48 = Tui48::new(board, TestRenderer::new(100, 50), events)?
+            .with_canvas(Canvas::with_depth(100, 50, depth)?)
+            .with_bell(VisualBell::new(visual_bell));
+        tui48.frame_delay = Duration::ZERO;
+        tui48.flash_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        let session = tui48.run()?;
+        let summary = session.summary();
+        let moves = format!("moves          {}", recording.moves.len());
+        assert!(summary.contains(&moves), "{}", summary);
+        Ok(())
+    }
+
+    #[test]
+    fn the_bell_rings_for_a_new_record() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        // the move merges the two tiles for a score of 4
+        let frames_rendered = |best: Score| -> Result<usize> {
+            let mut high_score = HighScore::default();
+            high_score.record(best);
+            let renderer = TestRenderer::new(100, 50);
+            let frames = renderer.frames();
+            let events =
+                MockEventSource::new([Event::UserInput(UserInput::Direction(Direction::Left))]);
+            let mut board = Board::new(
+                rand::rngs::SmallRng::seed_from_u64(13),
+                BoardConfig::default(),
+            );
+            board.set_initial_round(with_tiles(&[(BoardIdx(0, 0), 2), (BoardIdx(3, 0), 2)]));
+            let mut tui48 = Tui48::new(board, renderer, events)?
+                .with_canvas(Canvas::with_depth(100, 50, canvas_depth(true))?)
+                .with_bell(VisualBell::new(true))
+                .with_high_score(high_score);
+            tui48.frame_delay = Duration::ZERO;
+            tui48.flash_delay = Duration::ZERO;
+            tui48.enter_duration = Duration::ZERO;
+            tui48.run()?;
+            let rendered = frames.borrow().len();
+            Ok(rendered)
+        };
+
+        let beaten = frames_rendered(2)?;
+        let not_beaten = frames_rendered(100)?;
+        assert!(beaten > not_beaten, "{} vs {} frames", beaten, not_beaten);
+        Ok(())
+    }
+
+    #[test]
+    fn run_refuses_a_canvas_too_shallow_for_the_bell() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let renderer = TestRenderer::new(100, 50);
+        let recovered = renderer.recovered();
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(5),
+            BoardConfig::default(),
+        );
+        let tui48 = Tui48::new(board, renderer, MockEventSource::new([]))?
+            .with_canvas(Canvas::with_depth(100, 50, canvas_depth(false))?)
+            .with_bell(VisualBell::new(true));
+        match tui48.run() {
+            Err(Error::TuiError { source }) => match source.inner {
+                InnerError::CanvasTooShallow {
+                    requested,
+                    available,
+                } => assert_eq!(
+                    (requested, available),
+                    (canvas_depth(true), canvas_depth(false))
+                ),
+                other => panic!("expected the canvas to be too shallow, got {:?}", other),
+            },
+            Err(e) => panic!("expected the canvas to be too shallow, got {:?}", e),
+            Ok(_) => panic!("expected the canvas to be too shallow"),
+        }
+        assert!(recovered.get());
+        Ok(())
+    }
+
+    /// A game that passes through every state, with the number of moves on the board once each
+    /// event has been handled.
+    fn eventful_game() -> Vec<(Event, usize)> {
+        vec![
+            (Event::UserInput(UserInput::Direction(Direction::Left)), 1),
+            // the first resize makes the terminal too small, the second makes it big enough again
+            (Event::Resize, 1),
+            (Event::UserInput(UserInput::Direction(Direction::Right)), 1),
+            (Event::Resize, 1),
+            (Event::UserInput(UserInput::Direction(Direction::Right)), 2),
+            (Event::UserInput(UserInput::ShowHeatmap), 2),
+            (Event::UserInput(UserInput::Direction(Direction::Up)), 2),
+            (Event::UserInput(UserInput::PreviewThemes), 2),
+            (Event::UserInput(UserInput::Direction(Direction::Right)), 2),
+            (Event::UserInput(UserInput::Cancel), 2),
+            (Event::UserInput(UserInput::ToggleGrid), 2),
+            (Event::UserInput(UserInput::NewGame), 0),
+        ]
+    }
+
+    struct FaultyGame {
+        tui48: Tui48<crate::tui::testing::FaultyRenderer, crate::tui::testing::FaultyEvents>,
+        faults: crate::tui::testing::FaultInjector,
+        recoveries: Rc<std::cell::Cell<usize>>,
+        delivered: Rc<std::cell::Cell<usize>>,
+    }
+
+    fn faulty_game() -> Result<FaultyGame> {
+        use crate::tui::testing::{
+            FaultInjector, FaultyEvents, FaultyRenderer, MockEventSource, TestRenderer,
+        };
+
+        let faults = FaultInjector::default();
+        let renderer = FaultyRenderer::new(TestRenderer::new(100, 50), faults.clone());
+        let recoveries = renderer.recoveries();
+        let events = eventful_game().into_iter().map(|(event, _)| event);
+        let events = FaultyEvents::new(MockEventSource::new(events), faults.clone())
+            .resizing(renderer.size(), [(40, 12), (100, 50)]);
+        let delivered = events.delivered();
+        let mut board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(3),
+            BoardConfig::default(),
+        );
+        board.set_initial_round(with_tiles(&[(BoardIdx(1, 0), 2), (BoardIdx(3, 1), 4)]));
+        let mut tui48 = Tui48::new(board, renderer, events)?;
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        Ok(FaultyGame {
+            tui48,
+            faults,
+            recoveries,
+            delivered,
+        })
+    }
+
+    #[test]
+    fn every_terminal_failure_restores_the_terminal_once() -> Result<()> {
+        init()?;
+        let mut clean = faulty_game()?;
+        clean.faults.arm(None);
+        clean.tui48.play()?;
+        assert_eq!(clean.recoveries.get(), 1);
+        let moves: Vec<usize> = eventful_game().into_iter().map(|(_, m)| m).collect();
+
+        for operation in TerminalOperation::ALL {
+            let calls = clean.faults.calls(operation);
+            assert!(calls > 0, "the game never got to {}", operation);
+            for call in 1..=calls {
+                let mut game = faulty_game()?;
+                game.faults.arm(Some((operation, call)));
+                let case = format!("{} failing on call {}", operation, call);
+                match game.tui48.play() {
+                    Err(Error::TerminalFailed { operation: o, .. }) => assert_eq!(o, operation),
+                    other => panic!("{}: expected the failure, got {:?}", case, other),
+                }
+                assert_eq!(game.recoveries.get(), 1, "{}", case);
+                // a move is made before it's shown, so it stands even if showing it failed
+                let expected = match game.delivered.get() {
+                    0 => 0,
+                    delivered => moves[delivered.min(moves.len()) - 1],
+                };
+                assert_eq!(game.tui48.board.move_count(), expected, "{}", case);
+
+                let mut canvas = game.tui48.canvas.clone();
+                drop(game.tui48);
+                canvas.reclaim()?;
+                // collect the cells handed back as a renderer would, so there's room to report more
+                canvas.get_changed();
+                let (width, height) = canvas.dimensions();
+                let whole_canvas = (0..canvas.depth())
+                    .map(|z| {
+                        let r = Rectangle(Idx(0, 0, z), Bounds2D(width, height));
+                        canvas.get_draw_buffer(r, Owner::Named("after"))
+                    })
+                    .collect::<std::result::Result<Vec<DrawBuffer>, _>>();
+                assert!(whole_canvas.is_ok(), "{}: cells left behind", case);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn a_new_tile_starting_above_the_canvas_is_an_error() -> Result<()> {
+        init()?;
+        // new tiles start out further up than the board is from the top of the canvas
+        let layout = LayoutSpec {
+            new_tile_offsets: (NEW_TILE_HORIZONTAL_OFFSET, BOARD_FIXED_Y_OFFSET + 10),
+            ..LayoutSpec::classic()
+        };
+        let (_, _, mut tui_board) = setup_with_layout(100, 60, &[], layout)?;
+        match tui_board.new_sliding_tile(&BoardIdx(1, 0), 2, &Direction::Down) {
+            Err(Error::TuiError { source }) => assert!(
+                matches!(source.inner, InnerError::CoordinateOverflow { op: '-', .. }),
+                "{}",
+                source
+            ),
+            other => panic!("expected the tile out of range, got {:?}", other.err()),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn a_game_given_up_mid_animation_leaves_the_canvas_clean() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let renderer = TestRenderer::new(100, 50);
+        let recovered = renderer.recovered();
+        let mut board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(3),
+            BoardConfig::default(),
+        );
+        board.set_initial_round(with_tiles(&[(BoardIdx(1, 0), 2), (BoardIdx(3, 1), 4)]));
+        let mut tui48 = Tui48::new(board, renderer, MockEventSource::new([]))?;
+        tui48.tui_board = tui48.resize()?;
+
+        // the new tile slides in on a layer taken up by another buffer, so the animation is set
+        // up halfway, after the tiles already on the board started sliding
+        let (width, height) = tui48.canvas.dimensions();
+        let layer = Rectangle(
+            Idx(0, 0, LOWER_ANIMATION_LAYER_IDX),
+            Bounds2D(width, height),
+        );
+        let blocker = tui48
+            .canvas
+            .get_draw_buffer(layer, Owner::Named("blocker"))?;
+        let e = tui48
+            .shift(Direction::Left)
+            .expect_err("the new tile shouldn't find room");
+        assert!(matches!(
+            &e,
+            Error::TuiError { source } if matches!(source.inner, InnerError::CellAlreadyOwned { .. })
+        ));
+        drop(blocker);
+        assert!(tui48.give_up(e).is_err());
+        assert!(recovered.get());
+
+        assert!(tui48.tui_board.is_none());
+        assert_eq!(tui48.canvas.orphaned_cells(), 0);
+        // the canvas can be drawn on again from scratch
+        let mut canvas = tui48.canvas.clone();
+        let indicators = tui48.indicators();
+        Tui48Board::new(&tui48.board, &mut canvas, &indicators, tui48.layout)?;
+        Ok(())
+    }
+
+    #[test]
+    fn a_move_is_a_frame_per_animation_step_and_one_to_settle() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let first = with_tiles(&[(BoardIdx(3, 0), 2), (BoardIdx(2, 1), 4)]);
+        let board = || {
+            let mut board = Board::new(
+                rand::rngs::SmallRng::seed_from_u64(5),
+                BoardConfig::default(),
+            );
+            board.set_initial_round(first.clone());
+            board
+        };
+
+        // the steps the move's animation takes, counted on a board of its own
+        let mut counting = Tui48::new(
+            board(),
+            TestRenderer::new(100, 50),
+            MockEventSource::new([]),
+        )?;
+        let mut tui_board = counting
+            .resize()?
+            .expect("the terminal should be big enough");
+        let mut moved = board();
+        assert!(moved.shift(BoardDirection::Left).hint().is_some());
+        tui_board.animate_new_round(&first, &moved.current())?;
+        let mut steps = 0;
+        while tui_board.animate()? {
+            steps += 1;
+        }
+        assert!(steps > 1, "{}", steps);
+
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let frame_count = renderer.frame_count();
+        let in_frame = renderer.in_frame();
+        let left = Event::UserInput(UserInput::Direction(Direction::Left));
+        let events = MockEventSource::new([left]);
+        let mut tui48 = Tui48::new(board(), renderer, events)?;
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        assert_eq!(tui48.run_game_active()?, GameState::Quit);
+
+        // the board as laid out, every step, and the tiles settled; waiting for the quit after the
+        // move draws nothing more
+        assert_eq!(frame_count.get(), 1 + steps + 1);
+        assert_eq!(frames.borrow().len(), frame_count.get());
+        assert!(!in_frame.get());
+        Ok(())
+    }
+
+    #[test]
+    fn a_frame_that_fails_to_render_is_still_ended() -> Result<()> {
+        use crate::tui::testing::{
+            FaultInjector, FaultyEvents, FaultyRenderer, MockEventSource, TestRenderer,
+        };
+
+        init()?;
+        let events = || {
+            MockEventSource::new(
+                [Direction::Left, Direction::Down]
+                    .map(|d| Event::UserInput(UserInput::Direction(d))),
+            )
+        };
+        let game = |faults: &FaultInjector| -> Result<_> {
+            let inner = TestRenderer::new(100, 50);
+            let handles = (inner.frame_count(), inner.in_frame());
+            let renderer = FaultyRenderer::new(inner, faults.clone());
+            let events = FaultyEvents::new(events(), faults.clone());
+            let mut board = Board::new(
+                rand::rngs::SmallRng::seed_from_u64(3),
+                BoardConfig::default(),
+            );
+            board.set_initial_round(with_tiles(&[(BoardIdx(1, 0), 2), (BoardIdx(3, 1), 4)]));
+            let mut tui48 = Tui48::new(board, renderer, events)?;
+            tui48.frame_delay = Duration::ZERO;
+            tui48.enter_duration = Duration::ZERO;
+            Ok((tui48, handles))
+        };
+
+        let clean = FaultInjector::default();
+        let (mut tui48, _) = game(&clean)?;
+        tui48.play()?;
+        let renders = clean.calls(TerminalOperation::Render);
+        for call in 1..=renders {
+            let faults = FaultInjector::default();
+            faults.arm(Some((TerminalOperation::Render, call)));
+            let (mut tui48, (frame_count, in_frame)) = game(&faults)?;
+            // the test renderer panics on a frame begun inside another or ended twice
+            assert!(tui48.play().is_err(), "render {}", call);
+            assert!(!in_frame.get(), "render {}", call);
+            assert_eq!(frame_count.get(), call, "render {}", call);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn slow_terminal_plays_moves_without_animating() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+        use crate::tui::watchdog::{Deadlines, WatchdogHandle};
+
+        init()?;
+        let seed = 13;
+        let recording = record_game(seed, 20);
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events = MockEventSource::new(
+            recording
+                .moves
+                .iter()
+                .map(|d| Event::UserInput(UserInput::Direction(Direction::from_board(d)))),
+        );
+        // a zero soft deadline is missed right away
+        let watchdog = WatchdogHandle::new(Deadlines {
+            soft: Duration::ZERO,
+            hard: Duration::MAX,
+        });
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(seed),
+            BoardConfig::default(),
+        );
+        let tui48 = Tui48::new(board, renderer, events)?.with_watchdog(watchdog);
+        let session = tui48.run()?;
+
+        let frames = frames.borrow();
+        let moves = recording.moves.len();
+        assert!(
+            frames.len() <= 2 * moves + 1,
+            "{} frames rendered for {} moves",
+            frames.len(),
+            moves
+        );
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert_eq!(board_text(last_frame), round_text(&recording.last));
+        assert!(last_frame.contains("animations disabled"), "{}", last_frame);
+        assert!(
+            session
+                .summary()
+                .contains(&format!("moves          {}", moves)),
+            "{}",
+            session.summary()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn session_counts_games_across_new_games() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let seed = 13;
+        let recording = record_game(seed, 10);
+        assert!(!recording.game_over);
+
+        let renderer = TestRenderer::new(100, 50);
+        let recovered = renderer.recovered();
+        // abandon the seeded game for a new one, then quit the new game before moving
+        let events = MockEventSource::new(
+            recording
+                .moves
+                .iter()
+                .map(|d| Event::UserInput(UserInput::Direction(Direction::from_board(d))))
+                .chain([Event::UserInput(UserInput::NewGame)]),
+        );
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(seed),
+            BoardConfig::default(),
+        );
+        let mut tui48 = Tui48::new(board, renderer, events)?;
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        let session = tui48.run()?;
+
+        assert!(recovered.get());
+        assert_eq!(session.games_played(), 1);
+        assert_eq!(session.average_score(), Some(recording.score));
+        let summary = session.summary();
+        assert!(
+            summary.contains("games played   1 (1 abandoned)"),
+            "{}",
+            summary
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn quitting_adds_the_session_summary_to_the_stats_file() -> Result<()> {
+        use crate::persist::{FileSink, Sinks};
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let path = crate::config::test::config_file("earlier session\n");
+        let persistence = PersistenceHandle::synchronous(Sinks {
+            stats: Some(Box::new(FileSink::appending(path.clone()))),
+            ..Default::default()
+        });
+        let events = MockEventSource::new([
+            Event::UserInput(UserInput::Direction(Direction::Left)),
+            Event::UserInput(UserInput::Direction(Direction::Right)),
+        ]);
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(13),
+            BoardConfig::default(),
+        );
+        let mut tui48 =
+            Tui48::new(board, TestRenderer::new(100, 50), events)?.with_persistence(persistence);
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        let session = tui48.run()?;
+
+        let stats = std::fs::read_to_string(&path);
+        std::fs::remove_file(&path)?;
+        assert_eq!(stats?, format!("earlier session\n{}\n", session.summary()));
+        assert_eq!(session.games_played(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn moves_are_added_to_the_journal_as_they_are_made() -> Result<()> {
+        use crate::persist::{FileSink, Sinks};
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let path = crate::config::test::config_file("");
+        std::fs::remove_file(&path)?;
+        let persistence = PersistenceHandle::synchronous(Sinks {
+            journal: Some(Box::new(FileSink::appending(path.clone()))),
+            ..Default::default()
+        });
+        let events = MockEventSource::new([
+            Event::UserInput(UserInput::Direction(Direction::Left)),
+            Event::UserInput(UserInput::Direction(Direction::Right)),
+        ]);
+        let mut board = Board::new_seeded(13, BoardConfig::default());
+        board.set_initial_round(with_tiles(&[(BoardIdx(0, 0), 2), (BoardIdx(3, 0), 2)]));
+        let mut tui48 =
+            Tui48::new(board, TestRenderer::new(100, 50), events)?.with_persistence(persistence);
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.run()?;
+
+        let journal = std::fs::read_to_string(&path);
+        std::fs::remove_file(&path)?;
+        let journal = journal?;
+        let entries: Vec<&str> = journal.lines().collect();
+        assert_eq!(entries.len(), 2, "{}", journal);
+        assert_eq!(entries[0], "seed 13 move 1 left score 4");
+        assert!(
+            entries[1].starts_with("seed 13 move 2 right score "),
+            "{}",
+            journal
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::more_than_half(9, 16, (60, 200, 60))]
+    #[case::half(8, 16, (220, 200, 40))]
+    #[case::quarter(4, 16, (220, 200, 40))]
+    #[case::less_than_quarter(3, 16, (220, 40, 40))]
+    #[case::full(0, 16, (220, 40, 40))]
+    #[case::larger_board_half(18, 36, (220, 200, 40))]
+    #[case::larger_board_less_than_quarter(8, 36, (220, 40, 40))]
+    fn pressure_color_thresholds(
+        #[case] empty: usize,
+        #[case] total: usize,
+        #[case] expected: (u8, u8, u8),
+    ) {
+        let color = pressure_color(empty, total);
+        assert_eq!((color.r(), color.g(), color.b()), expected);
+    }
+
+    #[rstest]
+    #[case::score_only(vec![], vec![])]
+    #[case::outlook(vec![Indicator::Outlook], vec![Indicator::Outlook])]
+    #[case::pressure(vec![Indicator::Pressure], vec![Indicator::Pressure])]
+    // the pressure doesn't fit next to the outlook on the narrowest canvas
+    #[case::all(vec![Indicator::Outlook, Indicator::Pressure], vec![Indicator::Outlook])]
+    fn top_bar_layout_at_minimum_width(
+        #[case] indicators: Vec<Indicator>,
+        #[case] expected: Vec<Indicator>,
+    ) {
+        let (width, _) = LayoutRequirements::new(&LayoutSpec::classic()).need();
+        let mut requested = vec![Indicator::Score];
+        requested.extend(indicators);
+        let layout = top_bar_layout(&requested, width);
+
+        let placed: Vec<Indicator> = layout.iter().map(|(i, _)| *i).collect();
+        assert_eq!(placed[..SCORE_AREA.len()], SCORE_AREA);
+        assert_eq!(placed[SCORE_AREA.len()..], expected);
+        for (i, (_, r)) in layout.iter().enumerate() {
+            assert!(r.extents().0 <= width, "{:?} exceeds width {}", r, width);
+            for (_, other) in &layout[i + 1..] {
+                assert!(r.extents().0 <= other.x(), "{:?} overlaps {:?}", r, other);
+            }
+        }
+    }
+
+    #[test]
+    fn top_bar_layout_compact_hides_what_does_not_fit() {
+        let requested = [Indicator::Score, Indicator::Outlook, Indicator::Pressure];
+        let wide = top_bar_layout(&requested, 100);
+        let pressure_extent = wide[4].1.extents().0;
+
+        let compact = top_bar_layout(&requested, pressure_extent - 1);
+        let placed: Vec<Indicator> = compact.iter().map(|(i, _)| *i).collect();
+        assert_eq!(
+            placed,
+            vec![
+                Indicator::Score,
+                Indicator::HighScore,
+                Indicator::Moves,
+                Indicator::Outlook
+            ]
+        );
+        // hiding an indicator doesn't move the ones before it
+        assert_eq!(compact[..], wide[..4]);
+
+        let placed: Vec<Indicator> = top_bar_layout(&requested, 0)
+            .iter()
+            .map(|(i, _)| *i)
+            .collect();
+        assert_eq!(placed, SCORE_AREA);
+    }
+
+    #[test]
+    fn pressure_indicator_shows_empty_count() -> Result<()> {
+        init()?;
+
+        let tiles = [(BoardIdx(0, 0), 4), (BoardIdx(0, 1), 4)];
+        let mut canvas = Canvas::new(100, 50);
+        let mut game_board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(10),
+            BoardConfig::default(),
+        );
+        game_board.set_initial_round(with_tiles(&tiles));
+        let requested = [Indicator::Score, Indicator::Pressure];
+        let tui_board =
+            Tui48Board::new(&game_board, &mut canvas, &requested, LayoutSpec::classic())?;
+
+        let pressure = tui_board
+            .pressure
+            .as_ref()
+            .expect("pressure should be shown");
+        let r = pressure.rectangle();
+        let text: String = canvas
+            .get_changed()
+            .into_iter()
+            .filter(|stack| {
+                let (x, y) = stack.coordinates();
+                y == r.y() + 1 && x > r.x() && x + 1 < r.extents().0
+            })
+            .filter_map(|stack| Some((stack.coordinates(), stack.content()?)))
+            .collect::<BTreeMap<_, _>>()
+            .into_values()
+            .collect();
+        assert_eq!(text.trim(), "\u{25a2}14");
+        Ok(())
+    }
+
+    #[test]
+    fn move_count_panel_shows_moves_made() -> Result<()> {
+        init()?;
+
+        let tiles = [(BoardIdx(0, 0), 4), (BoardIdx(0, 1), 4)];
+        let (mut game_board, canvas, mut tui_board) = setup(100, 50, &tiles)?;
+        game_board.shift(BoardDirection::Up);
+        tui_board.update_move_count(game_board.move_count())?;
+
+        let r = tui_board.moves.rectangle();
+        let text: String = canvas
+            .get_changed()
+            .into_iter()
+            .filter(|stack| {
+                let (x, y) = stack.coordinates();
+                y == r.y() + 1 && x > r.x() && x + 1 < r.extents().0
+            })
+            .filter_map(|stack| Some((stack.coordinates(), stack.content()?)))
+            .collect::<BTreeMap<_, _>>()
+            .into_values()
+            .collect();
+        assert_eq!(text.trim(), "Moves: 1");
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::narrow(1.5, 8)]
+    #[case::typical(DEFAULT_CELL_ASPECT, 10)]
+    #[case::wide(2.5, 13)]
+    // leaves cells over that don't split evenly between the gaps
+    #[case::uneven(1.8, 9)]
+    fn square_tiles_are_evenly_spaced_across_the_board(
+        #[case] aspect: f64,
+        #[case] tile_width: usize,
+    ) -> Result<()> {
+        let layout = LayoutSpec::square(aspect)?;
+        let tiles: Vec<Rectangle> = (0..4)
+            .map(|x| layout.tile_rectangle(x, 1, TILE_LAYER_IDX))
+            .collect();
+        for tile in tiles.iter() {
+            assert_eq!(tile.dimensions(), (tile_width, TILE_HEIGHT), "{}", tile);
+        }
+        let gaps: Vec<usize> = tiles
+            .windows(2)
+            .map(|pair| {
+                pair[1]
+                    .x()
+                    .checked_sub(pair[0].extents().0)
+                    .unwrap_or_else(|| panic!("{} overlaps {}", pair[0], pair[1]))
+            })
+            .collect();
+        let (narrowest, widest) = (gaps.iter().min(), gaps.iter().max());
+        assert!(narrowest >= Some(&BOARD_X_PADDING), "{:?}", gaps);
+        assert!(
+            widest <= narrowest.map(|gap| gap + 1).as_ref(),
+            "{:?}",
+            gaps
+        );
+
+        // the tiles and the gaps between them span the board inside its border and the blank
+        // column along either side
+        let board = layout.board_rectangle();
+        let interior = board.width() - BOARD_BORDER_WIDTH * 4;
+        assert_eq!(tiles[0].x(), board.x() + BOARD_BORDER_WIDTH * 2);
+        assert_eq!(tile_width * 4 + gaps.iter().sum::<usize>(), interior);
+        // which looks about as wide as the rows are tall
+        let rows_height = board.height() - BOARD_BORDER_WIDTH * 2;
+        assert_eq!(interior, (rows_height as f64 * aspect).round() as usize);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::zero(0.0)]
+    #[case::too_narrow(0.9)]
+    #[case::too_wide(4.5)]
+    #[case::not_a_number(f64::NAN)]
+    fn square_tiles_need_a_sensible_cell_aspect(#[case] aspect: f64) {
+        assert!(matches!(
+            LayoutSpec::square(aspect),
+            Err(Error::InvalidCellAspect { .. })
+        ));
+    }
+
+    #[rstest]
+    #[case::classic(LayoutSpec::classic(), (67, 37))]
+    #[case::narrow(LayoutSpec::square(1.5).unwrap(), (79, 37))]
+    #[case::square(LayoutSpec::square(DEFAULT_CELL_ASPECT).unwrap(), (94, 37))]
+    #[case::wide(LayoutSpec::square(2.5).unwrap(), (110, 37))]
+    #[case::five_by_five(
+        LayoutSpec::classic().with_size(BoardConfig { width: 5, height: 5 }),
+        (74, 43)
+    )]
+    #[case::square_six_by_six(
+        LayoutSpec::square(DEFAULT_CELL_ASPECT)
+            .unwrap()
+            .with_size(BoardConfig { width: 6, height: 6 }),
+        (118, 49)
+    )]
+    fn layout_requirements_follow_the_tiles(
+        #[case] layout: LayoutSpec,
+        #[case] need: (usize, usize),
+    ) {
+        let requirements = LayoutRequirements::new(&layout);
+        assert_eq!(requirements.need(), need);
+        // room for new tiles to slide in from either side of the board
+        let (across, down) = layout.new_tile_offsets();
+        let (right, bottom) = layout.board_rectangle().extents();
+        assert!(need.0 >= right + across && need.1 >= bottom + down);
+        assert!(layout.tile_rectangle(0, 0, TILE_LAYER_IDX).x() >= across);
+    }
+
+    #[rstest]
+    fn five_by_five_boards_animate_to_the_round_they_shift_to(
+        #[values(
+            BoardDirection::Up,
+            BoardDirection::Down,
+            BoardDirection::Left,
+            BoardDirection::Right
+        )]
+        direction: BoardDirection,
+        #[values(LayoutSpec::classic(), LayoutSpec::square(DEFAULT_CELL_ASPECT).unwrap())]
+        layout: LayoutSpec,
+    ) -> Result<()> {
+        init()?;
+
+        let size = BoardConfig::new(5, 5).expect("5x5 is a supported size");
+        let layout = layout.with_size(size);
+        let (width, height) = LayoutRequirements::new(&layout).need();
+        let mut canvas = Canvas::new(width, height);
+        let mut game_board = Board::new(rand::rngs::SmallRng::seed_from_u64(10), size);
+        let mut round = Round::empty(5, 5);
+        for (x, y, value) in [(0, 0, 2), (4, 0, 2), (2, 2, 4), (2, 4, 4), (4, 4, 8)] {
+            round.set_value(&BoardIdx(x, y), card(value));
+        }
+        game_board.set_initial_round(round);
+        let mut tui_board = Tui48Board::new(&game_board, &mut canvas, &[Indicator::Score], layout)?;
+
+        let hint = game_board
+            .shift(direction.clone())
+            .hint()
+            .expect("every direction moves a tile");
+        tui_board.setup_animation(&hint)?;
+        while tui_board.animate()? {}
+        let report = tui_board.settle(&game_board.current())?;
+        assert!(report.is_empty(), "shifting {:?}: {}", direction, report);
+        Ok(())
+    }
+
+    #[rstest]
+    fn boards_of_any_size_lay_every_slot_out_inside_the_board(
+        #[values(LayoutSpec::classic(), LayoutSpec::square(DEFAULT_CELL_ASPECT).unwrap())]
+        layout: LayoutSpec,
+        #[values((3, 3), (5, 5), (6, 6), (8, 3), (3, 8))] size: (usize, usize),
+    ) {
+        let (columns, rows) = size;
+        let layout = layout.with_size(BoardConfig {
+            width: columns,
+            height: rows,
+        });
+        let board = layout.board_rectangle();
+        let first = layout.tile_rectangle(0, 0, TILE_LAYER_IDX);
+        let last = layout.tile_rectangle(columns - 1, rows - 1, TILE_LAYER_IDX);
+        // the tiles fill the board inside its border and the blank column along either side
+        assert_eq!(first.x(), board.x() + BOARD_BORDER_WIDTH * 2);
+        assert_eq!(first.y(), board.y() + BOARD_BORDER_WIDTH);
+        assert_eq!(last.extents().0 + BOARD_BORDER_WIDTH * 2, board.extents().0);
+        assert_eq!(last.extents().1 + BOARD_BORDER_WIDTH, board.extents().1);
+        for y in 0..rows {
+            for x in 1..columns {
+                let (left, right) = (
+                    layout.tile_rectangle(x - 1, y, TILE_LAYER_IDX),
+                    layout.tile_rectangle(x, y, TILE_LAYER_IDX),
+                );
+                assert!(right.x() > left.extents().0, "{} overlaps {}", left, right);
+                assert_eq!(layout.nearest_slot(&right), (x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn from_config_plays_on_the_configured_size() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let size = BoardConfig {
+            width: 6,
+            height: 6,
+        };
+        let mut config = GameConfig {
+            seed: Some(7),
+            size: Some(size),
+            ..GameConfig::default()
+        };
+        let renderer = TestRenderer::new(120, 60);
+        let frames = renderer.frames();
+        let events = MockEventSource::new(
+            [
+                Direction::Left,
+                Direction::Up,
+                Direction::Right,
+                Direction::Down,
+            ]
+            .map(|direction| Event::UserInput(UserInput::Direction(direction))),
+        );
+        let mut tui48 = Tui48::from_config(&config, renderer, events)?;
+        assert_eq!(tui48.board.dimensions(), (6, 6));
+        assert_eq!(tui48.layout, LayoutSpec::classic().with_size(size));
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.play()?;
+        assert!(tui48.board.move_count() > 0);
+
+        // every tile on the board is drawn in its slot
+        let frame = frames.borrow().last().cloned().unwrap_or_default();
+        let round = tui48.board.current();
+        for (y, row) in round.iter_rows().enumerate() {
+            for (x, card) in row.iter().enumerate().filter(|(_, card)| **card > 0) {
+                let r = tui48.layout.tile_rectangle(x, y, TILE_LAYER_IDX);
+                let line = frame
+                    .lines()
+                    .nth(r.y() + r.height() / 2)
+                    .expect("frame should be tall enough to contain the board");
+                let text: String = line.chars().skip(r.x() + 1).take(r.width() - 2).collect();
+                assert_eq!(text.trim(), display_value(*card).to_string(), "{}", frame);
+            }
+        }
+
+        // practice positions are only made for the classic board
+        config.practice = Some(Profile::MidGame);
+        let renderer = TestRenderer::new(120, 60);
+        assert!(matches!(
+            Tui48::from_config(&config, renderer, MockEventSource::new([])),
+            Err(Error::PracticeNeedsClassicBoard(s)) if s == size
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn the_seed_a_game_was_started_from_is_shown_while_it_lasts() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let config = GameConfig {
+            seed: Some(2024),
+            ..GameConfig::default()
+        };
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events = MockEventSource::new([
+            Event::UserInput(UserInput::Direction(Direction::Left)),
+            Event::UserInput(UserInput::NewGame),
+        ]);
+        let mut tui48 = Tui48::from_config(&config, renderer, events)?;
+        // the same seed deals the same tiles
+        assert_eq!(
+            tui48.board.current(),
+            Board::new_seeded(2024, BoardConfig::default()).current()
+        );
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.play()?;
+
+        let frames = frames.borrow();
+        assert!(frames[0].contains("Seed: 2024"), "{}", frames[0]);
+        // new games are dealt from seeds of their own
+        let seed = tui48.board.seed().expect("new games should be seeded");
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(
+            last_frame.contains(&format!("Seed: {}", seed)),
+            "{}",
+            last_frame
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    fn slides_across_square_tiles_end_exactly_on_their_slots(
+        #[values(1.5, DEFAULT_CELL_ASPECT, 2.5)] aspect: f64,
+        #[values(BoardDirection::Left, BoardDirection::Right)] direction: BoardDirection,
+    ) -> Result<()> {
+        init()?;
+        let layout = LayoutSpec::square(aspect)?;
+        let tiles = [(BoardIdx(0, 1), 2), (BoardIdx(3, 2), 4)];
+        let (mut game_board, canvas, mut tui_board) = setup_with_layout(120, 50, &tiles, layout)?;
+        let prior = game_board.current();
+        game_board.shift(direction);
+        tui_board.animate_new_round(&prior, &game_board.current())?;
+        let mut frames = 0;
+        while tui_board.animate()? {
+            frames += 1;
+        }
+        tui_board.teardown_animation()?;
+        // the tiles slid all the way across, a cell per frame
+        assert!(frames >= layout.columns_span, "{} frames", frames);
+
+        let round = game_board.current();
+        for (y, row) in tui_board.slots.iter().enumerate() {
+            for (x, slot) in row.iter().enumerate() {
+                match (slot, round.get(&BoardIdx(x, y))) {
+                    (Slot::Static(tile), value) => {
+                        assert_eq!(tile.value, value);
+                        assert_eq!(
+                            tile.buf.rectangle(),
+                            layout.tile_rectangle(x, y, TILE_LAYER_IDX)
+                        );
+                    }
+                    (Slot::Empty, 0) => (),
+                    (slot, value) => panic!("({}, {}) is {} for {}", x, y, slot, value),
+                }
+            }
+        }
+        verify_occupied_layers(
+            &canvas,
+            vec![BOARD_LAYER_IDX, TILE_LAYER_IDX],
+            vec![
+                LOWER_ANIMATION_LAYER_IDX,
+                MERGING_ANIMATION_LAYER_IDX,
+                UPPER_ANIMATION_LAYER_IDX,
+            ],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn wide_tiles_show_five_digit_values_whole() -> Result<()> {
+        use crate::tui::testing::TestRenderer;
+
+        init()?;
+        let layout = LayoutSpec::square(DEFAULT_CELL_ASPECT)?;
+        let tiles = [(BoardIdx(0, 0), 16384), (BoardIdx(1, 0), 65536)];
+        let (_, canvas, _tui_board) = setup_with_layout(120, 50, &tiles, layout)?;
+        let mut renderer = TestRenderer::new(120, 50);
+        let frames = renderer.frames();
+        renderer.render(&canvas)?;
+        let frame = frames.borrow().last().cloned().unwrap_or_default();
+        for (idx, value) in tiles {
+            let r = layout.tile_rectangle(idx.x(), idx.y(), TILE_LAYER_IDX);
+            let row = frame
+                .lines()
+                .nth(r.y() + r.height() / 2)
+                .expect("frame should be tall enough to contain the board");
+            let text: String = row.chars().skip(r.x() + 1).take(r.width() - 2).collect();
+            assert_eq!(text.trim(), value.to_string(), "{}", frame);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn from_config_sets_the_animation_speed() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        let mut config = GameConfig {
+            animation_speed: Some(AnimationSpeed::Slow),
+            ..GameConfig::default()
+        };
+        let renderer = TestRenderer::new(100, 50);
+        let tui48 = Tui48::from_config(&config, renderer, MockEventSource::new([]))?;
+        assert_eq!(tui48.frame_delay, FRAME_DELAY * 3);
+        assert_eq!(tui48.enter_duration, TILE_ENTER_DURATION * 3);
+        assert_eq!(tui48.quality.level(), QualityLevel::Full);
+
+        // moves are shown in one go however finely they would have been animated
+        config.animation_speed = Some(AnimationSpeed::Off);
+        config.animation_quality = Some(QualityLevel::TwoCell);
+        let renderer = TestRenderer::new(100, 50);
+        let tui48 = Tui48::from_config(&config, renderer, MockEventSource::new([]))?;
+        assert_eq!(tui48.quality.level(), QualityLevel::Instant);
+        Ok(())
+    }
+
+    #[test]
+    fn from_config_lays_out_square_tiles() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        let mut config = GameConfig {
+            square_tiles: true,
+            cell_aspect: Some(2.5),
+            ..GameConfig::default()
+        };
+        let renderer = TestRenderer::new(120, 50);
+        let tui48 = Tui48::from_config(&config, renderer, MockEventSource::new([]))?;
+        assert_eq!(tui48.layout, LayoutSpec::square(2.5)?);
+
+        // the aspect only matters to square tiles
+        config.square_tiles = false;
+        let renderer = TestRenderer::new(120, 50);
+        let tui48 = Tui48::from_config(&config, renderer, MockEventSource::new([]))?;
+        assert_eq!(tui48.layout, LayoutSpec::classic());
+
+        config.square_tiles = true;
+        config.cell_aspect = Some(9.0);
+        let renderer = TestRenderer::new(120, 50);
+        assert!(matches!(
+            Tui48::from_config(&config, renderer, MockEventSource::new([])),
+            Err(Error::InvalidCellAspect { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn check_bounds_covers_every_score_area_panel() -> Result<()> {
+        init()?;
+
+        let (width, height) = LayoutRequirements::new(&LayoutSpec::classic()).need();
+        let (_, score_area) = Tui48Board::get_dimensions();
+        for r in &score_area {
+            assert!(r.extents().0 <= width, "{:?} exceeds width {}", r, width);
+        }
+
+        let tiles = [(BoardIdx(0, 0), 4), (BoardIdx(0, 1), 4)];
+        assert!(setup(width, height, &tiles).is_ok());
+
+        // wide enough to hold every panel but not the board and its animations
+        let moves_extent = score_area[1].extents().0;
+        assert!(matches!(
+            setup(moves_extent, height, &tiles),
+            Err(Error::TerminalTooSmall { .. })
+        ));
+        Ok(())
+    }
+
+    fn cell(frame: &str, x: usize, y: usize) -> Option<char> {
+        frame.lines().nth(y)?.chars().nth(x)
+    }
+
+    #[rstest]
+    #[case::horizontal(
+        vec![(BoardIdx(0, 1), 8), (BoardIdx(1, 1), 8), (BoardIdx(2, 2), 2)],
+        [(5, 14, '\u{bb}'), (35, 14, '\u{ab}')]
+    )]
+    #[case::vertical(
+        vec![(BoardIdx(2, 0), 16), (BoardIdx(2, 1), 16), (BoardIdx(0, 0), 2), (BoardIdx(1, 0), 2)],
+        [(24, 5, '\u{2c5}'), (24, 29, '\u{2c4}')]
+    )]
+    fn merge_markers_point_at_the_largest_pair(
+        #[case] tiles: Vec<(BoardIdx, u32)>,
+        #[case] expected: [(usize, usize, char); 2],
+    ) -> Result<()> {
+        use crate::tui::testing::TestRenderer;
+
+        init()?;
+        let (game_board, canvas, mut tui_board) = setup(100, 50, &tiles)?;
+        let pair = game_board
+            .current()
+            .largest_mergeable_pair()
+            .map(|(first, second, _)| (first, second));
+        tui_board.mark_merge(pair)?;
+
+        let mut renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        renderer.render(&canvas)?;
+        let frames = frames.borrow();
+        for (x, y, c) in expected {
+            assert_eq!(cell(&frames[0], x, y), Some(c), "marker at ({}, {})", x, y);
+        }
+        assert_eq!(tui_board.merge_markers.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn clearing_merge_markers_leaves_the_border_intact() -> Result<()> {
+        use crate::tui::testing::TestRenderer;
+
+        init()?;
+        let tiles = [(BoardIdx(0, 1), 8), (BoardIdx(1, 1), 8)];
+        let (_, canvas, mut tui_board) = setup(100, 50, &tiles)?;
+        let mut renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+
+        renderer.render(&canvas)?;
+        tui_board.mark_merge(Some((BoardIdx(0, 1), BoardIdx(1, 1))))?;
+        renderer.render(&canvas)?;
+        tui_board.mark_merge(None)?;
+        renderer.render(&canvas)?;
+
+        let frames = frames.borrow();
+        assert_ne!(frames[1], frames[0], "the markers should have been drawn");
+        assert_eq!(frames[2], frames[0], "the border should be restored");
+        assert!(tui_board.merge_markers.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn grid_lines_run_through_the_padding_between_tiles() {
+        // tiles start at (7, 6) and are laid out every 7 columns and every 6 rows
+        let (columns, rows) = ([13, 20, 27], [11, 17, 23]);
+        let mut expected = Vec::new();
+        for y in 6..29 {
+            for x in 7..34 {
+                match (columns.contains(&x), rows.contains(&y)) {
+                    (true, true) => expected.push((x, y, '\u{253c}')),
+                    (true, false) => expected.push((x, y, '\u{2502}')),
+                    (false, true) => expected.push((x, y, '\u{2500}')),
+                    (false, false) => (),
+                }
+            }
+        }
+        assert_eq!(LayoutSpec::classic().grid_lines(4, 4), expected);
+    }
+
+    #[rstest]
+    #[case::standard(4, 4)]
+    #[case::small(2, 2)]
+    #[case::wide(6, 3)]
+    fn grid_lines_never_fall_inside_a_tile(#[case] columns: usize, #[case] rows: usize) {
+        let lines = LayoutSpec::classic().grid_lines(columns, rows);
+        assert_eq!(
+            lines.len(),
+            (columns - 1) * TILE_HEIGHT * rows
+                + (rows - 1) * TILE_WIDTH * columns
+                + (columns - 1) * (rows - 1)
+        );
+        for y in 0..rows {
+            for x in 0..columns {
+                let tile = LayoutSpec::classic().tile_rectangle(x, y, TILE_LAYER_IDX);
+                let (right, bottom) = tile.extents();
+                for (cx, cy, _) in &lines {
+                    assert!(
+                        !(tile.x()..right).contains(cx) || !(tile.y()..bottom).contains(cy),
+                        "({}, {}) is inside {}",
+                        cx,
+                        cy,
+                        tile
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hiding_the_grid_clears_exactly_its_cells() -> Result<()> {
+        use crate::tui::testing::TestRenderer;
+
+        init()?;
+        let tiles = [(BoardIdx(0, 0), 2), (BoardIdx(3, 2), 64)];
+        let (_, canvas, mut tui_board) = setup(100, 50, &tiles)?;
+        let mut renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+
+        renderer.render(&canvas)?;
+        tui_board.set_grid(true)?;
+        renderer.render(&canvas)?;
+        tui_board.set_grid(false)?;
+        renderer.render(&canvas)?;
+
+        let frames = frames.borrow();
+        let mut cleared = Vec::new();
+        for (y, (shown, hidden)) in frames[1].lines().zip(frames[2].lines()).enumerate() {
+            for (x, (a, b)) in shown.chars().zip(hidden.chars()).enumerate() {
+                if a != b {
+                    assert_eq!(b, ' ', "({}, {}) should have been cleared", x, y);
+                    cleared.push((x, y));
+                }
+            }
+        }
+        let grid: Vec<(usize, usize)> = LayoutSpec::classic()
+            .grid_lines(4, 4)
+            .into_iter()
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert_eq!(cleared, grid);
+        assert_eq!(frames[2], frames[0], "the board should be as it was");
+        Ok(())
+    }
+
+    #[test]
+    fn score_breakdown_overlay_lists_each_merge() -> Result<()> {
+        use crate::tui::testing::TestRenderer;
+
+        init()?;
+        let tiles = [
+            (BoardIdx(0, 0), 2),
+            (BoardIdx(1, 0), 2),
+            (BoardIdx(0, 2), 8),
+            (BoardIdx(2, 2), 8),
+        ];
+        let (game_board, canvas, mut tui_board) = setup(100, 50, &tiles)?;
+        let hint = game_board
+            .current()
+            .shift(
+                rand::rngs::SmallRng::seed_from_u64(10),
+                &BoardDirection::Left,
+            )
+            .expect("the move should merge both pairs");
+        assert_eq!(
+            score_breakdown(&hint),
+            vec!["\u{2022} (0,0): +4", "\u{2022} (0,2): +16"]
+        );
+
+        let overlay = tui_board
+            .overlay_score_breakdown(&hint)?
+            .expect("two merges should be broken down");
+        let mut renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        renderer.render(&canvas)?;
+        drop(overlay);
+        renderer.render(&canvas)?;
+
+        let frames = frames.borrow();
+        for line in score_breakdown(&hint) {
+            assert!(
+                frames[0].contains(&line),
+                "{:?} missing from\n{}",
+                line,
+                frames[0]
+            );
+            assert!(!frames[1].contains(&line), "{:?} should be gone", line);
+        }
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::left_to_fade(vec![], SCORE_BREAKDOWN_FRAMES)]
+    #[case::dismissed(vec![UserInput::ToggleGrid], 1)]
+    fn score_breakdown_fades_out_unless_a_key_dismisses_it(
+        #[case] after: Vec<UserInput>,
+        #[case] shown_in: usize,
+    ) -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events = MockEventSource::new(
+            std::iter::once(UserInput::Direction(Direction::Left))
+                .chain(after)
+                .map(Event::UserInput),
+        );
+        let mut board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(10),
+            BoardConfig::default(),
+        );
+        board.set_initial_round(with_tiles(&[
+            (BoardIdx(0, 0), 2),
+            (BoardIdx(1, 0), 2),
+            (BoardIdx(0, 2), 8),
+            (BoardIdx(2, 2), 8),
+        ]));
+        let mut tui48 = Tui48::new(board, renderer, events)?.with_score_breakdown(true);
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.breakdown_delay = Duration::ZERO;
+        tui48.run()?;
+
+        let frames = frames.borrow();
+        let shown = frames
+            .iter()
+            .filter(|frame| frame.contains("(0,2): +16"))
+            .count();
+        assert_eq!(shown, shown_in);
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(!last_frame.contains("(0,2): +16"), "{}", last_frame);
+        Ok(())
+    }
+
+    #[test]
+    fn score_breakdown_skips_single_merges() -> Result<()> {
+        init()?;
+        let tiles = [(BoardIdx(0, 0), 2), (BoardIdx(1, 0), 2)];
+        let (game_board, _, mut tui_board) = setup(100, 50, &tiles)?;
+        let hint = game_board
+            .current()
+            .shift(
+                rand::rngs::SmallRng::seed_from_u64(10),
+                &BoardDirection::Left,
+            )
+            .expect("the move should merge the pair");
+        assert!(tui_board.overlay_score_breakdown(&hint)?.is_none());
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::letters(LabelPack::builtin(BuiltinPack::Letters), "B", "K = 2048")]
+    #[case::wide(
+        LabelPack::builtin(BuiltinPack::Numbers).with_label(2, "\u{1f418}"),
+        "\u{1f418}",
+        "2048"
+    )]
+    fn set_labels_relabels_tiles_and_the_max_tile(
+        #[case] pack: LabelPack,
+        #[case] four: &str,
+        #[case] legend: &str,
+    ) -> Result<()> {
+        use crate::tui::testing::TestRenderer;
+        use unicode_width::UnicodeWidthStr;
+
+        init()?;
+        let tiles = [(BoardIdx(1, 1), 4), (BoardIdx(3, 3), 2048)];
+        let mut canvas = Canvas::new(100, 50);
+        let mut game_board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(10),
+            BoardConfig::default(),
+        );
+        game_board.set_initial_round(with_tiles(&tiles));
+        let indicators = [Indicator::Score, Indicator::MaxTile];
+        let mut tui_board =
+            Tui48Board::new(&game_board, &mut canvas, &indicators, LayoutSpec::classic())?;
+        tui_board.set_labels(Arc::new(pack), &game_board)?;
+
+        let mut renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        renderer.render(&canvas)?;
+        let frames = frames.borrow();
+        let r = LayoutSpec::classic().tile_rectangle(1, 1, TILE_LAYER_IDX);
+        let row = frames[0]
+            .lines()
+            .nth(r.y() + TILE_HEIGHT / 2)
+            .expect("the frame should hold the board");
+        // the right border stays put whether or not the label is double-width
+        let at = row.find(four).expect("the tile should show its label");
+        let border = row[at..]
+            .find('\u{2551}')
+            .map(|b| at + b)
+            .expect("the tile should have a border");
+        assert_eq!(row[..border].width(), r.x() + TILE_WIDTH - 1, "{:?}", row);
+        let max_tile = frames[0]
+            .lines()
+            .nth(2)
+            .expect("the frame should hold the indicators");
+        assert!(max_tile.contains(legend), "{}", frames[0]);
+        Ok(())
+    }
+
+    #[test]
+    fn put_slot_counts_tiles_coming_to_rest() -> Result<()> {
+        init()?;
+        let tiles = [(BoardIdx(0, 0), 4)];
+        let (_, _canvas, mut tui_board) = setup(100, 50, &tiles)?;
+
+        for _ in 0..5 {
+            let slot = tui_board.get_slot(&BoardIdx(0, 0))?;
+            tui_board.put_slot(&BoardIdx(0, 0), slot)?;
+        }
+        // putting back an empty slot isn't a tile coming to rest
+        let slot = tui_board.get_slot(&BoardIdx(1, 0))?;
+        tui_board.put_slot(&BoardIdx(1, 0), slot)?;
+
+        assert_eq!(tui_board.tile_occupancy[0][0], 5);
+        assert_eq!(tui_board.tile_occupancy.iter().flatten().sum::<u32>(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn heatmap_shows_until_the_next_key_and_outlives_new_games() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events = MockEventSource::new([
+            Event::UserInput(UserInput::Direction(Direction::Left)),
+            Event::UserInput(UserInput::NewGame),
+            Event::UserInput(UserInput::ShowHeatmap),
+            // dismisses the heatmap rather than starting another game
+            Event::UserInput(UserInput::NewGame),
+        ]);
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(13),
+            BoardConfig::default(),
+        );
+        let mut tui48 = Tui48::new(board, renderer, events)?;
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        let session = tui48.run()?;
+
+        let frames = frames.borrow();
+        // every slot shows a count while the heatmap is up, empty slots included
+        let shown: Vec<Vec<u32>> = frames
+            .iter()
+            .map(|frame| board_text(frame))
+            .filter_map(|texts| texts.iter().map(|t| t.parse().ok()).collect())
+            .collect();
+        assert_eq!(shown.len(), 1, "the heatmap should be shown in one frame");
+        // the new game's two entering tiles alone would only add up to two
+        assert!(shown[0].iter().sum::<u32>() > 2, "{:?}", shown[0]);
+        assert_eq!(session.games_played(), 1);
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(board_text(last_frame).iter().any(String::is_empty));
+        Ok(())
+    }
+
+    #[test]
+    fn cycling_the_pack_relabels_tiles_and_saves_the_choice() -> Result<()> {
+        use crate::persist::{FileSink, Sinks};
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let prefs_path = crate::config::test::config_file("");
+        let persistence = PersistenceHandle::synchronous(Sinks {
+            prefs: Some(Box::new(FileSink::replacing(prefs_path.clone()))),
+            ..Default::default()
+        });
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events = MockEventSource::new([Event::UserInput(UserInput::CyclePack)]);
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(13),
+            BoardConfig::default(),
+        );
+        let mut tui48 = Tui48::new(board, renderer, events)?.with_persistence(persistence);
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.run()?;
+
+        let prefs = Preferences::load(&prefs_path);
+        std::fs::remove_file(&prefs_path)?;
+        assert_eq!(prefs?.pack, Some(PackChoice::Builtin(BuiltinPack::Letters)));
+        let frames = frames.borrow();
+        let last_frame = frames.last().expect("frames should have been rendered");
+        let labels: Vec<String> = board_text(last_frame)
+            .into_iter()
+            .filter(|t| !t.is_empty())
+            .collect();
+        assert_eq!(labels.len(), 2, "{}", last_frame);
+        for label in labels {
+            assert!(label == "A" || label == "B", "{}", last_frame);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "scripting")]
+    #[test]
+    fn a_label_script_relabels_tiles_whatever_the_pack() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let dir = std::env::temp_dir().join(format!("tui48-label-script-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let script = r#"fn tile_label(value) { if value == 2 { "two" } }"#;
+        std::fs::write(dir.join("two.rhai"), script)?;
+        let scripts = Scripts::load(&dir, &BTreeMap::new());
+        std::fs::remove_dir_all(&dir)?;
+
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let mut board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(13),
+            BoardConfig::default(),
+        );
+        board.set_initial_round(with_tiles(&[(BoardIdx(0, 0), 2), (BoardIdx(3, 3), 4)]));
+        let mut tui48 =
+            Tui48::new(board, renderer, MockEventSource::new([]))?.with_scripts(scripts);
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.run()?;
+
+        let frames = frames.borrow();
+        let last_frame = frames.last().expect("frames should have been rendered");
+        let mut labels: Vec<String> = board_text(last_frame)
+            .into_iter()
+            .filter(|t| !t.is_empty())
+            .collect();
+        labels.sort();
+        assert_eq!(labels, vec!["4", "two"], "{}", last_frame);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::beaten(2, 4)]
+    #[case::not_beaten(100, 100)]
+    fn the_high_score_is_shown_next_to_the_score_and_saved_once_beaten(
+        #[case] best: Score,
+        #[case] expected: Score,
+    ) -> Result<()> {
+        use crate::persist::{FileSink, Sinks};
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let path = crate::config::test::config_file("");
+        std::fs::remove_file(&path)?;
+        let persistence = PersistenceHandle::synchronous(Sinks {
+            high_score: Some(Box::new(FileSink::replacing(path.clone()))),
+            ..Default::default()
+        });
+        let mut high_score = HighScore::default();
+        high_score.record(best);
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events =
+            MockEventSource::new([Event::UserInput(UserInput::Direction(Direction::Left))]);
+        let mut board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(13),
+            BoardConfig::default(),
+        );
+        board.set_initial_round(with_tiles(&[(BoardIdx(0, 0), 2), (BoardIdx(3, 0), 2)]));
+        let mut tui48 = Tui48::new(board, renderer, events)?
+            .with_persistence(persistence)
+            .with_high_score(high_score);
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.run()?;
+
+        let saved = HighScore::load(&path)?;
+        let _ = std::fs::remove_file(&path);
+        match best < expected {
+            true => assert_eq!(saved.best(), expected),
+            false => assert_eq!(
+                saved,
+                HighScore::default(),
+                "nothing should have been saved"
+            ),
+        }
+        let frames = frames.borrow();
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(
+            last_frame.contains(&format!("Best: {}", expected)),
+            "{}",
+            last_frame
+        );
+        Ok(())
+    }
+
+    /// The high score saved after playing the given inputs on a board whose move left beats a
+    /// best of 2, set up as the given function does.
+    fn saved_high_score_after(
+        board: Board,
+        inputs: impl IntoIterator<Item = UserInput>,
+        setup: impl FnOnce(TestGame) -> TestGame,
+    ) -> Result<HighScore> {
+        use crate::persist::{FileSink, Sinks};
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let path = crate::config::test::config_file("");
+        std::fs::remove_file(&path)?;
+        let persistence = PersistenceHandle::synchronous(Sinks {
+            high_score: Some(Box::new(FileSink::replacing(path.clone()))),
+            ..Default::default()
+        });
+        let mut high_score = HighScore::default();
+        high_score.record(2);
+        let events = MockEventSource::new(inputs.into_iter().map(Event::UserInput));
+        let tui48 = Tui48::new(board, TestRenderer::new(100, 50), events)?
+            .with_persistence(persistence)
+            .with_high_score(high_score);
+        let mut tui48 = setup(tui48);
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.run()?;
+
+        let saved = HighScore::load(&path);
+        let _ = std::fs::remove_file(&path);
+        saved
+    }
+
+    /// A classic board whose move left merges a pair of 2s.
+    fn pair_of_twos() -> Board {
+        let mut board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(13),
+            BoardConfig::default(),
+        );
+        board.set_initial_round(with_tiles(&[(BoardIdx(0, 0), 2), (BoardIdx(3, 0), 2)]));
+        board
+    }
+
+    #[test]
+    fn practice_games_leave_the_saved_high_score_alone() -> Result<()> {
+        let saved = saved_high_score_after(
+            pair_of_twos(),
+            [UserInput::Direction(Direction::Left)],
+            |tui48| tui48.with_practice(Some(Profile::MidGame)),
+        )?;
+        assert_eq!(
+            saved,
+            HighScore::default(),
+            "nothing should have been saved"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn arcade_games_leave_the_saved_high_score_alone() -> Result<()> {
+        let saved = saved_high_score_after(
+            pair_of_twos().with_power_ups(),
+            [UserInput::Direction(Direction::Left)],
+            |tui48| tui48.with_mode(Mode::Arcade),
+        )?;
+        assert_eq!(
+            saved,
+            HighScore::default(),
+            "nothing should have been saved"
+        );
+        Ok(())
+    }
 
-        verify_occupied_layers(&canvas, vec![2, 4], vec![0, 1, 3, 5, 6, 7]);
-        tui_board.setup_animation(&hint)?;
-        verify_occupied_layers(&canvas, vec![2, 3, 5], vec![0, 1, 4, 6, 7]);
+    #[test]
+    fn games_started_in_the_editor_leave_the_saved_high_score_alone() -> Result<()> {
+        let saved = saved_high_score_after(
+            pair_of_twos(),
+            [
+                UserInput::EditBoard,
+                UserInput::Confirm,
+                UserInput::Direction(Direction::Left),
+            ],
+            |tui48| tui48,
+        )?;
+        assert_eq!(
+            saved,
+            HighScore::default(),
+            "nothing should have been saved"
+        );
+        Ok(())
+    }
 
-        // TODO: verify board after setup
-        assert_eq!(tui_board.moving_slots.len(), 3);
-        assert_eq!(tui_board.done_slots.len(), 0);
-        assert_eq!(tui_board.disappearing_slots.len(), 0);
+    #[test]
+    fn toggling_the_grid_draws_it_and_saves_the_choice() -> Result<()> {
+        use crate::persist::{FileSink, Sinks};
+        use crate::tui::testing::{MockEventSource, TestRenderer};
 
-        while tui_board.animate()? {
-            // TODO: verify intermediate states after every animation frame
-            verify_occupied_layers(&canvas, vec![2, 3, 5], vec![0, 1, 4, 6, 7]);
-            logger.log(&debug(format_args!(
-                "moving slot count: {}",
-                tui_board.moving_slots.len()
-            )));
-            logger.log(&debug(format_args!(
-                "active moving slot count: {}",
-                tui_board
-                    .moving_slots
-                    .iter()
-                    .map(|s| match s {
-                        Slot::Empty => 0,
-                        _ => 1,
-                    })
-                    .sum::<u16>()
-            )));
-            logger.log(&debug(format_args!(
-                "non-empty done slot count: {}",
-                tui_board
-                    .done_slots
-                    .iter()
-                    .map(|(_, s)| match s {
-                        Slot::Empty => 0,
-                        _ => 1,
-                    })
-                    .sum::<u16>()
-            )));
-            logger.log(&debug(format_args!(
-                "done_slot count  : {}",
-                tui_board.done_slots.len()
-            )));
+        init()?;
+        let prefs_path = crate::config::test::config_file("");
+        let persistence = PersistenceHandle::synchronous(Sinks {
+            prefs: Some(Box::new(FileSink::replacing(prefs_path.clone()))),
+            ..Default::default()
+        });
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events = MockEventSource::new([Event::UserInput(UserInput::ToggleGrid)]);
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(13),
+            BoardConfig::default(),
+        );
+        let mut tui48 = Tui48::new(board, renderer, events)?.with_persistence(persistence);
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.run()?;
+
+        let prefs = Preferences::load(&prefs_path);
+        std::fs::remove_file(&prefs_path)?;
+        assert_eq!(prefs?.grid, Some(true));
+        let frames = frames.borrow();
+        let last_frame = frames.last().expect("frames should have been rendered");
+        for (x, y, c) in LayoutSpec::classic().grid_lines(4, 4) {
+            assert_eq!(cell(last_frame, x, y), Some(c), "{}", last_frame);
         }
-        tui_board.teardown_animation()?;
-        assert_eq!(tui_board.moving_slots.len(), 0);
-        assert_eq!(tui_board.done_slots.len(), 0);
-        assert_eq!(tui_board.disappearing_slots.len(), 0);
-        verify_occupied_layers(&canvas, vec![2, 4], vec![0, 1, 3, 5, 6, 7]);
-        // TODO: verify canvas after teardown
+        Ok(())
+    }
+
+    #[test]
+    fn theme_preview_shows_every_value_up_to_2048_once() -> Result<()> {
+        use crate::tui::testing::TestRenderer;
 
+        init()?;
+        let canvas = Canvas::new(100, 50);
+        let preview = PreviewBoard::new(Arc::new(Theme::builtin(BuiltinTheme::Dusk)))
+            .with_score(1234)
+            .with_caption("dusk".to_string())
+            .draw(&canvas)?;
+        let cards: Vec<Card> = preview.tiles.iter().map(|(card, _)| *card).collect();
+        assert_eq!(cards, (1..=WINNING_CARD).collect::<Vec<Card>>());
+
+        let mut renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        renderer.render(&canvas)?;
+        let frames = frames.borrow();
+        let shown: Vec<String> = board_text(&frames[0])
+            .into_iter()
+            .filter(|t| !t.is_empty())
+            .collect();
+        let expected: Vec<String> = (1..=WINNING_CARD)
+            .map(|card| display_value(card).to_string())
+            .collect();
+        assert_eq!(shown, expected, "{}", frames[0]);
+        assert!(frames[0].contains("1234"), "{}", frames[0]);
+        assert_eq!(frames[0].lines().last().map(str::trim), Some("dusk"));
         Ok(())
     }
 
     #[rstest]
-    #[case::zero(0, 0)]
-    #[case::small(10, 10)]
-    #[case::height_too_small(100, 24)]
-    #[case::width_too_small(35, 100)]
-    fn check_bounds_error_if_terminal_is_too_small_for_board(
+    #[case::roomy(100, 50, 11)]
+    #[case::three_columns_of_two(30, 20, 6)]
+    #[case::too_narrow_for_any(10, 50, 0)]
+    fn theme_preview_leaves_out_tiles_that_dont_fit(
         #[case] width: usize,
         #[case] height: usize,
+        #[case] shown: usize,
     ) -> Result<()> {
         init()?;
+        let canvas = Canvas::new(width, height);
+        let preview = PreviewBoard::new(default_theme()).draw(&canvas)?;
+        let cards: Vec<Card> = preview.tiles.iter().map(|(card, _)| *card).collect();
+        // the smallest values are the ones kept
+        assert_eq!(cards, (1..=shown as Card).collect::<Vec<Card>>());
+        for (_, buf) in preview.tiles.iter() {
+            let (right, bottom) = buf.rectangle().extents();
+            assert!(right <= width && bottom < height, "{}", buf.rectangle());
+        }
+        Ok(())
+    }
 
-        let idxs = HashMap::from([(BoardIdx(0, 0), 2), (BoardIdx(0, 1), 2)]);
-        let r = setup(width, height, idxs);
-        assert!(r.is_err());
+    type TestGame = Tui48<crate::tui::testing::TestRenderer, crate::tui::testing::MockEventSource>;
+
+    /// A game on a 100x50 test terminal that plays the given events, with its board laid out and
+    /// rendered once, along with the frames rendered.
+    fn laid_out_game(
+        events: impl IntoIterator<Item = Event>,
+    ) -> Result<(TestGame, Rc<RefCell<Vec<String>>>)> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(13),
+            BoardConfig::default(),
+        );
+        let mut tui48 = Tui48::new(board, renderer, MockEventSource::new(events))?;
+        tui48.tui_board = tui48.resize()?;
+        tui48.renderer.render(&tui48.canvas)?;
+        Ok((tui48, frames))
+    }
+
+    #[test]
+    fn theme_preview_cycles_through_the_themes_and_wraps() -> Result<()> {
+        init()?;
+        let right = || Event::UserInput(UserInput::Direction(Direction::Right));
+        let left = || Event::UserInput(UserInput::Direction(Direction::Left));
+        let (mut tui48, frames) = laid_out_game([
+            right(),
+            right(),
+            right(),
+            left(),
+            Event::UserInput(UserInput::Cancel),
+        ])?;
+        assert!(matches!(tui48.run_theme_preview()?, GameState::Active));
+
+        let frames = frames.borrow();
+        let captions: Vec<&str> = frames[1..]
+            .iter()
+            .map(|frame| {
+                let caption = frames_last_line(frame);
+                BuiltinTheme::ALL
+                    .into_iter()
+                    .map(|theme| theme.name())
+                    .find(|name| caption.contains(&format!(" {} (", name)))
+                    .unwrap_or_else(|| panic!("no theme named in {:?}", caption))
+            })
+            .collect();
+        assert_eq!(captions, ["classic", "dusk", "paper", "classic", "paper"]);
+        assert!(frames_last_line(&frames[2]).contains("(dark)"));
+        assert!(frames_last_line(&frames[1]).contains("Enter to apply, Esc to go back"));
+        // backing out leaves the theme as it was
+        assert_eq!(tui48.theme, 0);
         Ok(())
     }
 
-    #[rstest]
-    fn check_bounds_width_animation_errors(
-        // TODO: try submitting feature to rstest to so we can do something like
-        // #[range(36usize..66)]
-        #[values(
-            36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57,
-            58, 59, 60, 61, 62, 63, 64, 65, 66
-        )]
-        width: usize,
-    ) -> Result<()> {
+    fn frames_last_line(frame: &str) -> &str {
+        frame.lines().last().unwrap_or_default()
+    }
+
+    #[test]
+    fn theme_preview_leaves_the_game_canvas_alone() -> Result<()> {
         init()?;
-        let height = 100usize;
+        let right = || Event::UserInput(UserInput::Direction(Direction::Right));
+        let (mut tui48, _frames) = laid_out_game([
+            right(),
+            Event::Resize,
+            right(),
+            Event::Estimate {
+                generation: 0,
+                estimate: 0.5,
+            },
+            Event::UserInput(UserInput::Confirm),
+        ])?;
+        let before = tui48.canvas.to_string();
+        assert!(changed_cells(&tui48.canvas).is_empty());
+
+        assert!(matches!(tui48.run_theme_preview()?, GameState::Active));
+        assert_eq!(tui48.canvas.to_string(), before);
+        assert!(changed_cells(&tui48.canvas).is_empty());
+        assert_eq!(tui48.themes[tui48.theme].theme(), BuiltinTheme::Paper);
+        assert_eq!(tui48.estimate, Some(0.5));
+        Ok(())
+    }
 
-        let idxs = HashMap::from([(BoardIdx(0, 0), 2), (BoardIdx(0, 1), 2)]);
-        let r = setup(width, height, idxs);
-        assert!(r.is_ok());
-        let (_board, _canvas, tui48_board) = r.unwrap();
-        let r = tui48_board.check_bounds();
-        assert!(r.is_err());
+    #[test]
+    fn estimates_for_a_position_moved_on_from_are_dropped() -> Result<()> {
+        init()?;
+        let (mut tui48, _frames) = laid_out_game([])?;
+        let stale = tui48.outlook.generation();
+        tui48.refresh_outlook();
+
+        tui48.update_estimate(stale, 0.5)?;
+        assert_eq!(tui48.estimate, None);
+        tui48.update_estimate(tui48.outlook.generation(), 0.25)?;
+        assert_eq!(tui48.estimate, Some(0.25));
         Ok(())
     }
 
     #[rstest]
-    fn check_bounds_height_animation_errors(
-        #[values(30, 31, 32, 33, 34, 35, 36)] height: usize,
+    #[case::applied(UserInput::Confirm, Some(BuiltinTheme::Dusk))]
+    #[case::cancelled(UserInput::Cancel, None)]
+    fn applying_a_previewed_theme_saves_it(
+        #[case] choice: UserInput,
+        #[case] saved: Option<BuiltinTheme>,
     ) -> Result<()> {
+        use crate::persist::{FileSink, Sinks};
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
         init()?;
-        let width = 100usize;
+        let prefs_path = crate::config::test::config_file("");
+        let persistence = PersistenceHandle::synchronous(Sinks {
+            prefs: Some(Box::new(FileSink::replacing(prefs_path.clone()))),
+            ..Default::default()
+        });
+        let renderer = TestRenderer::new(100, 50);
+        let events = MockEventSource::new([
+            Event::UserInput(UserInput::PreviewThemes),
+            Event::UserInput(UserInput::Direction(Direction::Right)),
+            Event::UserInput(choice),
+        ]);
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(13),
+            BoardConfig::default(),
+        );
+        let mut tui48 = Tui48::new(board, renderer, events)?.with_persistence(persistence);
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.run()?;
+
+        let prefs = Preferences::load(&prefs_path);
+        std::fs::remove_file(&prefs_path)?;
+        assert_eq!(prefs?.theme, saved);
+        Ok(())
+    }
 
-        let idxs = HashMap::from([(BoardIdx(0, 0), 2), (BoardIdx(0, 1), 2)]);
-        let r = setup(width, height, idxs);
-        assert!(r.is_ok());
-        let (_board, _canvas, tui48_board) = r.unwrap();
-        let r = tui48_board.check_bounds();
-        assert!(r.is_err());
+    /// A laid out game whose board holds the given round, for the editor to start out from.
+    fn editing(
+        round: Round,
+        inputs: impl IntoIterator<Item = UserInput>,
+    ) -> Result<(TestGame, Rc<RefCell<Vec<String>>>)> {
+        init()?;
+        let (mut tui48, frames) = laid_out_game(inputs.into_iter().map(Event::UserInput))?;
+        tui48.board.set_initial_round(round);
+        tui48.enter_duration = Duration::ZERO;
+        Ok((tui48, frames))
+    }
+
+    #[test]
+    fn the_editor_points_out_a_position_that_cant_be_played_and_carries_on() -> Result<()> {
+        let start = with_tiles(&[(BoardIdx(0, 0), 8), (BoardIdx(1, 0), 2)]).with_score(8);
+        let (mut tui48, frames) = editing(
+            start.clone(),
+            [
+                UserInput::Erase,
+                UserInput::Confirm,
+                UserInput::Digit(9),
+                UserInput::Confirm,
+            ],
+        )?;
+        assert_eq!(tui48.run_editor()?, GameState::Active);
+
+        let frames = frames.borrow();
+        let refused = frames
+            .iter()
+            .position(|frame| frames_last_line(frame).contains("score 0 is less than the 8"))
+            .expect("the position should have been refused");
+        // the score typed after the refusal is the one the game starts with
+        assert!(frames[refused + 1..]
+            .iter()
+            .any(|frame| frame.contains("9_")));
+        assert_eq!(tui48.board.current(), start.with_score(9));
+        assert_eq!(tui48.board.move_count(), 0);
         Ok(())
     }
 
-    #[rstest]
-    #[case::top(Direction::Down)]
-    #[case::bottom(Direction::Up)]
-    #[case::left(Direction::Right)]
-    #[case::right(Direction::Left)]
-    fn check_bounds_animation(#[case] slide_dir: Direction) -> Result<()> {
+    #[test]
+    fn the_editor_writes_down_the_position_built() -> Result<()> {
+        let start = with_tiles(&[(BoardIdx(0, 0), 2)]);
+        let (tui48, frames) = editing(
+            start,
+            [
+                UserInput::Direction(Direction::Down),
+                UserInput::Increase,
+                UserInput::Increase,
+                UserInput::WriteNotation,
+                UserInput::Cancel,
+            ],
+        )?;
+        let path = crate::config::test::config_file("");
+        let mut tui48 = tui48.with_position_file(path.clone());
+        assert_eq!(tui48.run_editor()?, GameState::Active);
+
+        let notation = std::fs::read_to_string(&path);
+        std::fs::remove_file(&path)?;
+        let expected = with_tiles(&[(BoardIdx(0, 0), 2), (BoardIdx(0, 1), 4)]);
+        assert_eq!(Round::from_notation(&notation?)?, expected);
+        let frames = frames.borrow();
+        let written = frames.last().map(|frame| frames_last_line(frame));
+        assert!(written.is_some_and(|line| line.contains("position written to")));
+        // backing out leaves the game as it was
+        assert_eq!(tui48.board.current(), with_tiles(&[(BoardIdx(0, 0), 2)]));
+        Ok(())
+    }
+
+    #[test]
+    fn games_are_saved_and_loaded_again_with_their_history() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
         init()?;
+        let path = crate::config::test::config_file("");
+        std::fs::remove_file(&path)?;
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events = MockEventSource::new(
+            [
+                UserInput::LoadGame,
+                UserInput::Direction(Direction::Left),
+                UserInput::Direction(Direction::Down),
+                UserInput::SaveGame,
+                UserInput::Direction(Direction::Right),
+                UserInput::Direction(Direction::Up),
+                UserInput::LoadGame,
+            ]
+            .map(Event::UserInput),
+        );
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(13),
+            BoardConfig::default(),
+        );
+        let mut tui48 = Tui48::new(board, renderer, events)?.with_save_file(path.clone());
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.play()?;
+
+        let saved = Board::load_from(&path);
+        std::fs::remove_file(&path)?;
+        let saved = saved?.expect("the game should have been saved");
+        // the game goes on from where it was saved, with the moves made before then
+        assert_eq!(tui48.board.current(), saved.current());
+        assert_eq!(tui48.board.move_count(), 2);
+        assert!(tui48.board.can_undo());
+
+        let frames = frames.borrow();
+        let notes: Vec<&str> = frames.iter().map(|frame| frames_last_line(frame)).collect();
+        for note in [
+            "there's no saved game in",
+            "game saved to",
+            "game loaded from",
+        ] {
+            assert!(notes.iter().any(|line| line.contains(note)), "{}", note);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn the_computer_plays_until_the_game_is_over() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
 
-        let idxs = HashMap::from([(BoardIdx(1, 1), 2), (BoardIdx(2, 2), 2)]);
-        let (x_extent, y_extent) = Tui48Board::get_minimum_canvas_extents();
-        let (mut game_board, _, mut tui_board) = setup(x_extent, y_extent, idxs)?;
+        init()?;
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events = MockEventSource::new([Event::UserInput(UserInput::ToggleAutoPlay)]);
+        let board = Board::new_seeded(7, BoardConfig::default());
+        let mut tui48 = Tui48::new(board, renderer, events)?.with_ai_delay(Duration::ZERO);
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.celebration_delay = Duration::ZERO;
+        tui48.play()?;
+
+        assert!(tui48.board.move_count() > 0);
+        assert!(tui48.board.is_game_over() || tui48.board.has_won());
+        assert!(frames
+            .borrow()
+            .iter()
+            .any(|frame| frames_last_line(frame).contains("the computer is playing")));
+        Ok(())
+    }
 
-        let hint = game_board
-            .shift(slide_dir.clone())
-            .expect(format!("{:?} slide should result in hints", slide_dir).as_str());
+    #[test]
+    fn the_player_takes_over_from_the_computer() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
 
-        let r = Tui48Board::draw_score(&mut tui_board.score, game_board.score());
-        assert!(r.is_ok());
-        let r = tui_board.setup_animation(&hint);
-        assert!(r.is_ok());
+        init()?;
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events = MockEventSource::new(
+            [UserInput::ToggleAutoPlay, UserInput::ToggleAutoPlay].map(Event::UserInput),
+        );
+        let board = Board::new_seeded(7, BoardConfig::default());
+        let mut tui48 = Tui48::new(board, renderer, events)?.with_ai_delay(Duration::ZERO);
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.play()?;
+
+        // the player pressed a again before the computer got to move
+        assert_eq!(tui48.board.move_count(), 0);
+        assert!(!tui48.autoplay);
+        let frames = frames.borrow();
+        let last = frames.last().expect("the game should have been drawn");
+        assert!(!frames_last_line(last).contains("the computer is playing"));
+        Ok(())
+    }
+
+    /// Plays a game on the given resume file with the given input, returning it once the player
+    /// quits.
+    fn resumable_game(
+        path: &Path,
+        events: impl IntoIterator<Item = UserInput>,
+    ) -> Result<TestGame> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        let events = MockEventSource::new(events.into_iter().map(Event::UserInput));
+        let board = Board::new_seeded(17, BoardConfig::default());
+        let mut tui48 = Tui48::new(board, TestRenderer::new(100, 50), events)?
+            .with_resume_file(path.to_path_buf());
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        tui48.play()?;
+        Ok(tui48)
+    }
+
+    #[test]
+    fn a_game_left_on_quitting_is_resumed_next_time() -> Result<()> {
+        init()?;
+        let path = crate::config::test::config_file("");
+        std::fs::remove_file(&path)?;
+        let left = resumable_game(
+            &path,
+            [
+                UserInput::Direction(Direction::Left),
+                UserInput::Direction(Direction::Down),
+            ],
+        )?;
+        assert!(path.exists(), "the game should have been left for later");
+
+        let resumed = resumable_game(&path, [UserInput::Confirm]);
+        std::fs::remove_file(&path)?;
+        let resumed = resumed?;
+        assert_eq!(resumed.board.current(), left.board.current());
+        assert_eq!(resumed.board.move_count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn declining_to_resume_clears_the_game_left() -> Result<()> {
+        init()?;
+        let path = crate::config::test::config_file("");
+        std::fs::remove_file(&path)?;
+        resumable_game(&path, [UserInput::Direction(Direction::Left)])?;
+        // quitting while asked leaves the game for next time
+        resumable_game(&path, [])?;
+        assert!(path.exists());
+
+        let declined = resumable_game(&path, [UserInput::NewGame])?;
+        assert!(!path.exists(), "the game left should have been cleared");
+        assert_eq!(declined.board.move_count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn resuming_waits_for_the_terminal_to_be_large_enough_to_ask() -> Result<()> {
+        use crate::tui::testing::{
+            FaultInjector, FaultyEvents, FaultyRenderer, MockEventSource, TestRenderer,
+        };
+
+        init()?;
+        let path = crate::config::test::config_file("");
+        std::fs::remove_file(&path)?;
+        let left = resumable_game(&path, [UserInput::Direction(Direction::Up)])?;
+
+        let faults = FaultInjector::default();
+        let inner = TestRenderer::new(100, 50);
+        let frames = inner.frames();
+        let renderer = FaultyRenderer::new(inner, faults.clone());
+        let size = renderer.size();
+        size.set((40, 12));
+        let events = MockEventSource::new([Event::Resize, Event::UserInput(UserInput::Confirm)]);
+        let events = FaultyEvents::new(events, faults).resizing(size, [(100, 50)]);
+        let board = Board::new_seeded(17, BoardConfig::default());
+        let mut tui48 = Tui48::new(board, renderer, events)?.with_resume_file(path.to_path_buf());
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        let played = tui48.play();
+        std::fs::remove_file(&path)?;
+        played?;
+
+        let frames = frames.borrow();
+        let asked = frames
+            .iter()
+            .position(|frame| frame.contains("resume the game"))
+            .expect("the player should have been asked");
+        assert!(frames[..asked]
+            .iter()
+            .any(|frame| frame.contains("too small")));
+        assert_eq!(tui48.board.current(), left.board.current());
+        Ok(())
+    }
+
+    #[test]
+    fn flash_tile_pulses_only_tiles_at_rest() -> Result<()> {
+        init()?;
+        let tiles = [(BoardIdx(1, 1), 2048)];
+        let (_, _canvas, tui_board) = setup(100, 50, &tiles)?;
+        let gold = Rgb::new(255, 215, 0);
+
+        assert!(tui_board
+            .flash_tile(&BoardIdx(0, 0), gold.clone(), 1)
+            .is_none());
+        let mut pulse = tui_board
+            .flash_tile(&BoardIdx(1, 1), gold, 1)
+            .expect("the winning tile should pulse");
+        let frames = std::iter::from_fn(|| Some(pulse.tick()))
+            .take_while(|pulsing| *pulsing)
+            .count();
+        assert_eq!(frames, crate::tui::drawbuffer::PULSE_PERIOD - 1);
+        Ok(())
+    }
+
+    fn play(tui_board: &mut Tui48Board) -> Result<()> {
         while tui_board.animate()? {}
-        let r = tui_board.teardown_animation();
-        assert!(r.is_ok());
+        tui_board.teardown_animation()
+    }
+
+    #[test]
+    fn every_tile_has_colors_of_its_own() -> Result<()> {
+        init()?;
+        // only the empty slot has no tile to color
+        let fallback = colors_from_card(0);
+        for card in 1..=MAX_CARD + 1 {
+            assert!(
+                colors_from_card(card) != fallback,
+                "the {} tile has no colors",
+                display_value(card)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn tiles_show_their_values_on_screen() -> Result<()> {
+        use crate::tui::testing::TestRenderer;
+
+        init()?;
+        let mut renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let mut render = |canvas: &Canvas| -> Result<Vec<String>> {
+            renderer.render(canvas)?;
+            Ok(board_text(
+                &frames.borrow().last().cloned().unwrap_or_default(),
+            ))
+        };
+        let spawned = |texts: &[String]| -> Vec<String> {
+            texts.iter().filter(|t| !t.is_empty()).cloned().collect()
+        };
+
+        // freshly spawned tiles read 2 or 4
+        for seed in 0..20 {
+            let mut canvas = Canvas::new(100, 50);
+            let game_board = Board::new(
+                rand::rngs::SmallRng::seed_from_u64(seed),
+                BoardConfig::default(),
+            );
+            let _tui_board = Tui48Board::new(
+                &game_board,
+                &mut canvas,
+                &[Indicator::Score],
+                LayoutSpec::classic(),
+            )?;
+            let tiles = spawned(&render(&canvas)?);
+            assert!(!tiles.is_empty(), "seed {}", seed);
+            assert!(
+                tiles.iter().all(|t| t == "2" || t == "4"),
+                "seed {}: {:?}",
+                seed,
+                tiles
+            );
+        }
+
+        // and two 2s merge into a 4
+        let tiles = [(BoardIdx(0, 0), 2), (BoardIdx(1, 0), 2)];
+        let (mut game_board, canvas, mut tui_board) = setup(100, 50, &tiles)?;
+        let before = render(&canvas)?;
+        assert_eq!((before[0].as_str(), before[1].as_str()), ("2", "2"));
+        let hint = game_board
+            .shift(BoardDirection::Left)
+            .hint()
+            .expect("the 2s should merge");
+        tui_board.setup_animation(&hint)?;
+        play(&mut tui_board)?;
+        let after = render(&canvas)?;
+        assert_eq!(after[0], "4");
+        let new_tiles = spawned(&after[1..]);
+        assert!(
+            new_tiles == ["2"] || new_tiles == ["4"],
+            "only the new tile should be left: {:?}",
+            after
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rewinding_a_move_restores_the_canvas() -> Result<()> {
+        use crate::tui::testing::TestRenderer;
+
+        init()?;
+        let mut canvas = Canvas::new(100, 50);
+        let mut game_board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(21),
+            BoardConfig::default(),
+        );
+        let mut tui_board = Tui48Board::new(
+            &game_board,
+            &mut canvas,
+            &[Indicator::Score],
+            LayoutSpec::classic(),
+        )?;
+        let mut renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let mut render = |canvas: &Canvas| -> Result<String> {
+            renderer.render(canvas)?;
+            Ok(frames.borrow().last().cloned().unwrap_or_default())
+        };
+
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(22);
+        let mut rewound = 0;
+        for _ in 0..30 {
+            let before = render(&canvas)?;
+            let direction = DIRECTIONS[rng.gen_range(0..DIRECTIONS.len())].clone();
+            let prior = game_board.current();
+            let hint = match game_board.shift(direction) {
+                MoveOutcome::Moved(hint) => hint,
+                MoveOutcome::Rejected => continue,
+            };
+            tui_board.setup_animation(&hint)?;
+            play(&mut tui_board)?;
+            let after = render(&canvas)?;
+            assert_eq!(board_text(&after), round_text(&game_board.current()));
+
+            let plan = game_board.rewind_plan().expect("a move was just made");
+            tui_board.setup_rewind(&plan)?;
+            play(&mut tui_board)?;
+            let rewound_frame = render(&canvas)?;
+            assert_eq!(board_text(&rewound_frame), round_text(&prior));
+            assert_eq!(rewound_frame, before, "rewinding {:?}", plan);
+            verify_occupied_layers(
+                &canvas,
+                vec![BOARD_LAYER_IDX, TILE_LAYER_IDX],
+                vec![
+                    LOWER_ANIMATION_LAYER_IDX,
+                    MERGING_ANIMATION_LAYER_IDX,
+                    UPPER_ANIMATION_LAYER_IDX,
+                ],
+            );
+            rewound += 1;
+
+            // and forward again to carry on with the game
+            tui_board.setup_animation(&hint)?;
+            play(&mut tui_board)?;
+            assert_eq!(render(&canvas)?, after);
+            if game_board.is_game_over() {
+                break;
+            }
+        }
+        assert!(rewound > 10, "only {} moves were rewound", rewound);
+        Ok(())
+    }
+
+    /// The rounds of the given recording, starting with the first.
+    fn recorded_rounds(seed: u64, recording: &Recording) -> Vec<Round> {
+        let mut board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(seed),
+            BoardConfig::default(),
+        );
+        let mut rounds = vec![board.current()];
+        for direction in recording.moves.iter() {
+            assert!(matches!(
+                board.shift(direction.clone()),
+                MoveOutcome::Moved(_)
+            ));
+            rounds.push(board.current());
+        }
+        rounds
+    }
+
+    /// Plays the given moves, then the given input, on a board dealt from the given seed,
+    /// returning the frames rendered and the statistics of the session.
+    fn replayed_game(
+        seed: u64,
+        moves: &[BoardDirection],
+        input: impl IntoIterator<Item = UserInput>,
+    ) -> Result<(Vec<String>, Session)> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        init()?;
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let events = MockEventSource::new(
+            moves
+                .iter()
+                .map(|d| UserInput::Direction(Direction::from_board(d)))
+                .chain(input)
+                .map(Event::UserInput),
+        );
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(seed),
+            BoardConfig::default(),
+        );
+        let mut tui48 = Tui48::new(board, renderer, events)?;
+        tui48.frame_delay = Duration::ZERO;
+        tui48.enter_duration = Duration::ZERO;
+        let session = tui48.run()?;
+        let frames = frames.borrow().clone();
+        Ok((frames, session))
+    }
+
+    #[test]
+    fn replay_steps_through_the_moves_and_leaves_the_game_as_it_was() -> Result<()> {
+        let seed = 13;
+        let recording = record_game(seed, 3);
+        let rounds = recorded_rounds(seed, &recording);
+        let (frames, _) = replayed_game(
+            seed,
+            &recording.moves,
+            [
+                UserInput::Replay,
+                UserInput::Direction(Direction::Left),
+                UserInput::Direction(Direction::Left),
+                UserInput::Direction(Direction::Left),
+                // there is nothing before the first round
+                UserInput::Direction(Direction::Left),
+                UserInput::Direction(Direction::Right),
+                UserInput::Replay,
+            ],
+        )?;
+
+        for (n, round) in rounds.iter().enumerate() {
+            let prompt = format!("replay: move {} of 3", n);
+            assert!(
+                frames
+                    .iter()
+                    .any(|frame| frame.contains(&prompt) && board_text(frame) == round_text(round)),
+                "{} should have been shown",
+                prompt
+            );
+        }
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(!last_frame.contains("replay:"), "{}", last_frame);
+        assert_eq!(board_text(last_frame), round_text(&recording.last));
+        Ok(())
+    }
+
+    #[test]
+    fn the_game_goes_on_after_a_replay_as_if_there_had_been_none() -> Result<()> {
+        let seed = 13;
+        let recording = record_game(seed, 6);
+        let (played, rest) = recording.moves.split_at(3);
+        let replay = [
+            UserInput::Replay,
+            UserInput::Direction(Direction::Left),
+            UserInput::Direction(Direction::Left),
+            UserInput::Direction(Direction::Right),
+            UserInput::Replay,
+        ];
+        let rest = rest
+            .iter()
+            .map(|d| UserInput::Direction(Direction::from_board(d)));
+        let (frames, _) = replayed_game(seed, played, replay.into_iter().chain(rest))?;
+
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert_eq!(board_text(last_frame), round_text(&recording.last));
+        Ok(())
+    }
 
+    #[test]
+    fn quitting_the_replay_leaves_the_game_where_it_was() -> Result<()> {
+        let seed = 13;
+        let recording = record_game(seed, 4);
+        let (_, session) = replayed_game(
+            seed,
+            &recording.moves,
+            [
+                UserInput::Replay,
+                UserInput::Direction(Direction::Left),
+                UserInput::Direction(Direction::Left),
+                UserInput::Quit,
+            ],
+        )?;
+
+        assert_eq!(session.average_score(), Some(recording.score));
+        Ok(())
+    }
+
+    /// Plays the first of the same moves on a game whose terminal takes the given time to render,
+    /// returning the quality each move was animated at, the frames rendered and how long the
+    /// moves took.
+    fn play_slowly(
+        quality: AdaptiveQuality,
+        render_time: Duration,
+        moves: usize,
+    ) -> Result<(Vec<QualityLevel>, Vec<String>, Duration)> {
+        use crate::tui::testing::{MockEventSource, SlowRenderer, TestRenderer};
+
+        let recording = record_game(9, moves);
+        let renderer = TestRenderer::new(100, 50);
+        let frames = renderer.frames();
+        let renderer = SlowRenderer::new(renderer, render_time);
+        let board = Board::new(
+            rand::rngs::SmallRng::seed_from_u64(9),
+            BoardConfig::default(),
+        );
+        let mut tui48 = Tui48::new(board, renderer, MockEventSource::new([]))?;
+        tui48.quality = quality;
+        tui48.frame_delay = Duration::ZERO;
+        tui48.tui_board = tui48.resize()?;
+
+        let mut levels = Vec::new();
+        let started = Instant::now();
+        for direction in recording.moves.iter() {
+            levels.push(tui48.quality.level());
+            tui48.shift(Direction::from_board(direction))?;
+        }
+        let elapsed = started.elapsed();
+        let frames = frames.borrow().clone();
+        Ok((levels, frames, elapsed))
+    }
+
+    #[test]
+    fn slow_terminals_get_coarser_animations() -> Result<()> {
+        init()?;
+        let render_time = Duration::from_millis(4);
+        // a budget the renderer misses by far more than the margin, without making the test slow
+        let budget = Duration::from_millis(1);
+        let (levels, frames, elapsed) = play_slowly(AdaptiveQuality::new(budget), render_time, 12)?;
+        let (_, full_frames, full_elapsed) =
+            play_slowly(AdaptiveQuality::pinned(QualityLevel::Full), render_time, 12)?;
+
+        // tiles move further with every frame until moves are shown in one go
+        let mut steps = levels.clone();
+        steps.dedup();
+        assert_eq!(
+            steps,
+            [
+                QualityLevel::Full,
+                QualityLevel::TwoCell,
+                QualityLevel::FourCell,
+                QualityLevel::Instant
+            ],
+            "{:?}",
+            levels
+        );
+        // once instant, each move renders only its final frame
+        assert!(frames.len() < full_frames.len() / 2, "{}", frames.len());
+        assert!(
+            elapsed < full_elapsed / 2,
+            "{:?} vs {:?}",
+            elapsed,
+            full_elapsed
+        );
+        // the moves end up looking the same however they were animated, but for the note
+        let last_frame = frames.last().expect("frames should have been rendered");
+        let full_last_frame = full_frames
+            .last()
+            .expect("frames should have been rendered");
+        let above_note = |frame: &str| -> Vec<String> {
+            let mut lines: Vec<String> = frame.lines().map(String::from).collect();
+            lines.pop();
+            lines
+        };
+        assert_eq!(above_note(last_frame), above_note(full_last_frame));
+        assert!(
+            frames_last_line(last_frame).contains(COARSER_ANIMATIONS_NOTE),
+            "{}",
+            last_frame
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    fn pinned_animation_quality_ignores_slow_terminals(
+        #[values(QualityLevel::Full, QualityLevel::FourCell)] level: QualityLevel,
+    ) -> Result<()> {
+        init()?;
+        // slow enough to miss the default frame budget by far more than the margin
+        let quality = AdaptiveQuality::pinned(level);
+        let (levels, frames, _) = play_slowly(quality, Duration::from_millis(40), 3)?;
+        assert!(levels.iter().all(|l| *l == level), "{:?}", levels);
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(
+            !last_frame.contains(COARSER_ANIMATIONS_NOTE),
+            "{}",
+            last_frame
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn a_lost_game_can_be_replayed() -> Result<()> {
+        let seed = 13;
+        let recording = record_game(seed, usize::MAX);
+        assert!(recording.game_over);
+        let (frames, _) = replayed_game(
+            seed,
+            &recording.moves,
+            [
+                UserInput::Replay,
+                UserInput::Direction(Direction::Left),
+                UserInput::Replay,
+            ],
+        )?;
+
+        let moves = recording.moves.len();
+        let prompt = format!("replay: move {} of {}", moves - 1, moves);
+        assert!(frames.iter().any(|frame| frame.contains(&prompt)));
+        let last_frame = frames.last().expect("frames should have been rendered");
+        assert!(last_frame.contains("game over!"), "{}", last_frame);
+        assert!(!last_frame.contains("replay:"), "{}", last_frame);
+        Ok(())
+    }
+
+    #[test]
+    fn from_config_pins_the_animation_quality() -> Result<()> {
+        use crate::tui::testing::{MockEventSource, TestRenderer};
+
+        let config = GameConfig {
+            animation_quality: Some(QualityLevel::TwoCell),
+            ..GameConfig::default()
+        };
+        let renderer = TestRenderer::new(100, 50);
+        let mut tui48 = Tui48::from_config(&config, renderer, MockEventSource::new([]))?;
+        for _ in 0..100 {
+            tui48.quality.observe(Duration::from_secs(1));
+        }
+        assert_eq!(tui48.quality.level(), QualityLevel::TwoCell);
         Ok(())
     }
 }
@@ -0,0 +1,362 @@
+//! The board editor: the player builds a position tile by tile, along with the score it was
+//! reached with, to start a game from or to write down and share (see `Round::to_notation`).
+use std::sync::Arc;
+
+use super::{
+    board_background, Indicator, LayoutRequirements, LayoutSpec, Tui48Board, OVERLAY_LAYER_IDX,
+    SCORE_AREA, TILE_LAYER_IDX,
+};
+use crate::engine::round::{Card, Idx as BoardIdx, Round, MAX_CARD};
+use crate::error::{Error, Result};
+use crate::milestones;
+use crate::packs::LabelPack;
+use crate::themes::Theme;
+use crate::tui::canvas::{Canvas, Modifier};
+use crate::tui::colors::Rgb;
+use crate::tui::drawbuffer::{DrawBuffer, DrawBufferOwner, Owner};
+use crate::tui::events::UserInput;
+use crate::tui::geometry::{Bounds2D, Direction, Idx, Rectangle};
+use crate::tui::lineeditor::LineEditor;
+use crate::tui::textbuffer::{FormatOptions, HAlignment, TextBuffer, VAlignment};
+
+/// The most digits the score takes, enough for the highest score a 4x4 board can reach.
+const SCORE_DIGITS: usize = 7;
+
+/// The border of the slot under the cursor.
+fn cursor_color() -> Rgb {
+    Rgb::new(250, 220, 60)
+}
+
+/// The border of an empty slot.
+fn empty_slot_color() -> Rgb {
+    Rgb::new(90, 90, 110)
+}
+
+/// The position being built, with the cursor picking the slot to change.
+pub(super) struct Editor {
+    round: Round,
+    cursor: BoardIdx,
+    score: LineEditor,
+}
+
+impl Editor {
+    /// Starts out from the given round, with the cursor in the top left slot.
+    pub(super) fn new(round: &Round) -> Self {
+        let score = LineEditor::digits(SCORE_DIGITS).with_text(&round.score().to_string());
+        Self {
+            round: round.clone(),
+            cursor: BoardIdx(0, 0),
+            score,
+        }
+    }
+
+    pub(super) fn cursor(&self) -> BoardIdx {
+        self.cursor.clone()
+    }
+
+    /// Moves the cursor one slot in the given direction, unless it is at that edge of the board
+    /// already. Returns whether it moved.
+    pub(super) fn move_cursor(&mut self, direction: &Direction) -> bool {
+        let BoardIdx(x, y) = self.cursor;
+        let (width, height) = self.round.dimensions();
+        let moved = match direction {
+            Direction::Left => BoardIdx(x.saturating_sub(1), y),
+            Direction::Right => BoardIdx((x + 1).min(width - 1), y),
+            Direction::Up => BoardIdx(x, y.saturating_sub(1)),
+            Direction::Down => BoardIdx(x, (y + 1).min(height - 1)),
+        };
+        if moved == self.cursor {
+            return false;
+        }
+        self.cursor = moved;
+        true
+    }
+
+    /// Raises, or lowers, the tile under the cursor to the next value and returns it. Values go
+    /// round from the largest tile a board can hold to an empty slot.
+    pub(super) fn cycle(&mut self, up: bool) -> Card {
+        let card = match (self.round.get(&self.cursor), up) {
+            (card, true) if card >= MAX_CARD => 0,
+            (card, true) => card + 1,
+            (0, false) => MAX_CARD,
+            (card, false) => card - 1,
+        };
+        self.round.set_value(&self.cursor, card);
+        card
+    }
+
+    /// The position built so far, if a game can be started from it: it must pass
+    /// `Round::validate` and have a move to make. An empty score counts as 0.
+    pub(super) fn position(&self) -> Result<Round> {
+        // the score is only ever digits, too few of them to overflow
+        let score = self.score.text().parse().unwrap_or(0);
+        let round = self.round.clone().with_score(score);
+        round.validate()?;
+        if !round.has_moves() {
+            return Err(Error::InvalidRound {
+                reason: String::from("there's no move to make from it"),
+            });
+        }
+        Ok(round)
+    }
+}
+
+/// The editor drawn on a canvas of its own, which stays there until this is dropped.
+pub(super) struct EditorView {
+    canvas: Canvas,
+    layout: LayoutSpec,
+    labels: Arc<LabelPack>,
+    theme: Arc<Theme>,
+    _board: DrawBuffer,
+    slots: Vec<Vec<Option<TextBuffer>>>,
+    score: TextBuffer,
+    status: TextBuffer,
+    prompt: String,
+}
+
+impl EditorView {
+    /// Draws the editor on the given canvas, which must be big enough for the layout, with the
+    /// given prompt along the bottom.
+    pub(super) fn new(
+        canvas: &Canvas,
+        layout: LayoutSpec,
+        labels: Arc<LabelPack>,
+        theme: Arc<Theme>,
+        prompt: String,
+        editor: &Editor,
+    ) -> Result<Self> {
+        let (width, height) = canvas.dimensions();
+        LayoutRequirements::new(&layout).check((width, height))?;
+
+        let mut board = canvas.get_draw_buffer(layout.board_rectangle(), Owner::Named("board"))?;
+        board.draw_border()?;
+        board.fill(' ')?;
+        let background = board_background();
+        board.modify(Modifier::SetBackgroundColor(
+            background.r(),
+            background.g(),
+            background.b(),
+        ));
+
+        let score_rectangle = layout
+            .top_bar(&SCORE_AREA, width)
+            .into_iter()
+            .find(|(i, _)| *i == Indicator::Score)
+            .map(|(_, r)| r)
+            .expect("the score is always laid out");
+        let score = canvas.get_text_buffer(score_rectangle, Owner::Named("score"))?;
+
+        let r = Rectangle(Idx(0, height - 1, OVERLAY_LAYER_IDX), Bounds2D(width, 1));
+        let mut status = canvas.get_text_buffer(r, Owner::Named("status"))?;
+        status.modify(Modifier::SetBackgroundColor(20, 20, 30));
+        status.format(FormatOptions {
+            halign: HAlignment::Center,
+            valign: VAlignment::Top,
+        });
+
+        let (columns, rows) = editor.round.dimensions();
+        let mut view = Self {
+            canvas: canvas.clone(),
+            layout,
+            labels,
+            theme,
+            _board: board,
+            slots: (0..rows)
+                .map(|_| (0..columns).map(|_| None).collect())
+                .collect(),
+            score,
+            status,
+            prompt,
+        };
+        for y in 0..rows {
+            for x in 0..columns {
+                view.draw_slot(editor, &BoardIdx(x, y))?;
+            }
+        }
+        view.draw_score(editor)?;
+        view.show_status(None)?;
+        Ok(view)
+    }
+
+    /// Acts on the given input if it edits the position, redrawing what it changed.
+    pub(super) fn apply(&mut self, editor: &mut Editor, input: &UserInput) -> Result<()> {
+        match input {
+            UserInput::Direction(direction) => {
+                let left = editor.cursor();
+                if editor.move_cursor(direction) {
+                    self.draw_slot(editor, &left)?;
+                    self.draw_slot(editor, &editor.cursor())?;
+                }
+            }
+            UserInput::Increase | UserInput::Decrease => {
+                editor.cycle(*input == UserInput::Increase);
+                self.draw_slot(editor, &editor.cursor())?;
+            }
+            // the score is only redrawn if the field took the key
+            UserInput::Digit(digit) if editor.score.insert(char::from(b'0' + digit)) => {
+                self.draw_score(editor)?;
+            }
+            UserInput::Erase if editor.score.backspace() => self.draw_score(editor)?,
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Shows the given message along the bottom, or the prompt if there is none.
+    pub(super) fn show_status(&mut self, message: Option<&str>) -> Result<()> {
+        self.status.clear()?;
+        self.status
+            .write(message.unwrap_or(&self.prompt), None, None)?;
+        self.status.flush()?;
+        Ok(())
+    }
+
+    /// Draws the given slot anew: its tile, or an outline if it is empty, with the border
+    /// highlighted if the cursor is on it.
+    fn draw_slot(&mut self, editor: &Editor, idx: &BoardIdx) -> Result<()> {
+        // a fresh buffer rather than the old one recolored, which would keep the highlight
+        let BoardIdx(x, y) = *idx;
+        self.slots[y][x] = None;
+        let r = self.layout.tile_rectangle(x, y, TILE_LAYER_IDX);
+        let mut buf = self.canvas.get_text_buffer(r, Owner::At("slot", x, y))?;
+        match editor.round.get(idx) {
+            0 => {
+                let (background, border) = (board_background(), empty_slot_color());
+                buf.modify(Modifier::SetBackgroundColor(
+                    background.r(),
+                    background.g(),
+                    background.b(),
+                ));
+                buf.modify(Modifier::SetForegroundColor(
+                    border.r(),
+                    border.g(),
+                    border.b(),
+                ));
+                buf.draw_border()?;
+                buf.clear()?;
+                buf.flush()?;
+            }
+            card => Tui48Board::draw_tile(&mut buf, card, &self.labels, &self.theme)?,
+        }
+        if *idx == editor.cursor {
+            buf.highlight_border(cursor_color());
+        }
+        self.slots[y][x] = Some(buf);
+        Ok(())
+    }
+
+    fn draw_score(&mut self, editor: &Editor) -> Result<()> {
+        // the score is shown as typed, with a mark where the next digit goes
+        let text = format!("{}_", editor.score.text());
+        Tui48Board::draw_score_box(&mut self.score, &text, milestones::accent(0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use rstest::*;
+
+    use super::*;
+    use crate::engine::fixtures::round;
+    use crate::packs::BuiltinPack;
+    use crate::tui::geometry::Direction;
+    use crate::tui48::{canvas_depth, default_theme, init};
+
+    fn view(editor: &Editor) -> Result<(Canvas, EditorView)> {
+        init()?;
+        let canvas = Canvas::with_depth(100, 50, canvas_depth(false))?;
+        let labels = Arc::new(LabelPack::builtin(BuiltinPack::Numbers));
+        let view = EditorView::new(
+            &canvas,
+            LayoutSpec::classic(),
+            labels,
+            default_theme(),
+            String::from("editing"),
+            editor,
+        )?;
+        Ok((canvas, view))
+    }
+
+    #[rstest]
+    #[case::left(Direction::Left, BoardIdx(0, 2))]
+    #[case::up(Direction::Up, BoardIdx(1, 0))]
+    #[case::right(Direction::Right, BoardIdx(3, 2))]
+    #[case::down(Direction::Down, BoardIdx(1, 3))]
+    fn the_cursor_stops_at_the_edges(#[case] direction: Direction, #[case] expected: BoardIdx) {
+        let mut editor = Editor::new(&Round::default());
+        editor.cursor = BoardIdx(1, 2);
+        for _ in 0..5 {
+            editor.move_cursor(&direction);
+        }
+        assert_eq!(editor.cursor(), expected);
+        assert!(!editor.move_cursor(&direction));
+    }
+
+    #[test]
+    fn values_cycle_round_through_an_empty_slot() {
+        let mut editor = Editor::new(&Round::default());
+        assert_eq!(editor.cycle(false), MAX_CARD);
+        assert_eq!(editor.cycle(true), 0);
+        let cards: Vec<Card> = (0..=MAX_CARD).map(|_| editor.cycle(true)).collect();
+        assert_eq!(cards, (1..=MAX_CARD).chain([0]).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn changing_a_tile_redraws_its_slot_alone() -> Result<()> {
+        let mut editor = Editor::new(&Round::default());
+        editor.cursor = BoardIdx(2, 1);
+        let (canvas, mut view) = view(&editor)?;
+        let _ = canvas.get_changed();
+
+        view.apply(&mut editor, &UserInput::Increase)?;
+        let changed: HashSet<(usize, usize)> = canvas
+            .get_changed()
+            .iter()
+            .map(|stack| stack.coordinates())
+            .collect();
+        let slot = LayoutSpec::classic().tile_rectangle(2, 1, TILE_LAYER_IDX);
+        assert!(!changed.is_empty());
+        for (x, y) in changed {
+            assert!(
+                (slot.x()..slot.extents().0).contains(&x)
+                    && (slot.y()..slot.extents().1).contains(&y),
+                "({},{}) is outside {:?}",
+                x,
+                y,
+                slot
+            );
+        }
+        assert_eq!(editor.round.get(&BoardIdx(2, 1)), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn typed_digits_make_up_the_score() -> Result<()> {
+        let start = round!([[8, 2, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8);
+        let mut editor = Editor::new(&start);
+        let (_canvas, mut view) = view(&editor)?;
+        for input in [UserInput::Erase, UserInput::Digit(1), UserInput::Digit(2)] {
+            view.apply(&mut editor, &input)?;
+        }
+        assert_eq!(editor.position()?.score(), 12);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::score_too_low(
+        round!([[8, 2, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 4),
+        "score 4 is less than the 8"
+    )]
+    #[case::no_move(
+        round!([[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]], 0),
+        "there's no move to make"
+    )]
+    fn positions_a_game_cant_start_from_are_refused(#[case] start: Round, #[case] expected: &str) {
+        let e = Editor::new(&start)
+            .position()
+            .expect_err("the position should be refused");
+        assert!(e.to_string().contains(expected), "{}", e);
+    }
+}
@@ -0,0 +1,626 @@
+//! The mirror match: the player races a computer opponent on a board of its own beside theirs.
+//! Both boards start out the same and are dealt the same new tiles move for move (see
+//! `SpawnSequence`), so only the moves made tell them apart.
+use std::time::Duration;
+
+use rand::{thread_rng, Rng};
+
+use super::{
+    canvas_depth, Indicator, LayoutRequirements, LayoutSpec, Tui48Board, BOARD_LAYER_IDX,
+    FRAME_DELAY, OVERLAY_LAYER_IDX, TOP_BAR_Y,
+};
+use crate::engine::board::{Board, BoardConfig, MoveOutcome};
+use crate::engine::direction::Direction as BoardDirection;
+use crate::engine::round::Round;
+use crate::engine::spawns::SpawnSequence;
+use crate::engine::strategy::Strategy;
+use crate::error::{Error, Result, TerminalContext};
+use crate::tui::canvas::Canvas;
+use crate::tui::drawbuffer::{DrawBuffer, DrawBufferOwner, Owner};
+use crate::tui::events::{Event, EventSource, UserInput};
+use crate::tui::geometry::{Bounds2D, Idx, Rectangle};
+use crate::tui::keymap::Keymap;
+use crate::tui::renderer::{Renderer, TerminalOperation};
+use crate::tui::textbuffer::{FormatOptions, HAlignment, TextBuffer, VAlignment};
+
+const RESULT_PROMPT: &str = "press {quit} to quit or {new_game} for a rematch";
+
+/// How a mirror match ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Outcome {
+    Player,
+    Opponent,
+    Draw,
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Player => f.pad("you win!"),
+            Self::Opponent => f.pad("the computer wins!"),
+            Self::Draw => f.pad("it's a draw!"),
+        }
+    }
+}
+
+/// Decides the match once it is over: the first to reach the winning tile wins, or else, once
+/// neither board can move, whoever scored more. Since the player always moves first, and the
+/// opponent doesn't get to reply to a winning move, two boards never reach the winning tile on
+/// the same turn.
+fn decide(player: &Board, opponent: &Board) -> Option<Outcome> {
+    if player.has_won() {
+        return Some(Outcome::Player);
+    }
+    if opponent.has_won() {
+        return Some(Outcome::Opponent);
+    }
+    if !player.is_game_over() || !opponent.is_game_over() {
+        return None;
+    }
+    Some(match player.score().cmp(&opponent.score()) {
+        std::cmp::Ordering::Greater => Outcome::Player,
+        std::cmp::Ordering::Less => Outcome::Opponent,
+        std::cmp::Ordering::Equal => Outcome::Draw,
+    })
+}
+
+/// One of the two boards of a match, along with what is drawn for it.
+struct Side {
+    name: &'static str,
+    board: Board,
+    layout: LayoutSpec,
+    tui_board: Option<Tui48Board>,
+    label: Option<TextBuffer>,
+    // the round before the move that is yet to be animated, if any
+    prior: Option<Round>,
+}
+
+impl Side {
+    fn new(name: &'static str, board: Board, layout: LayoutSpec) -> Self {
+        Self {
+            name,
+            board,
+            layout,
+            tui_board: None,
+            label: None,
+            prior: None,
+        }
+    }
+
+    fn lay_out(&mut self, canvas: &mut Canvas) -> Result<()> {
+        // the old buffers have to release their cells first
+        self.tui_board = None;
+        self.label = None;
+        let tui_board = Tui48Board::new(&self.board, canvas, &[Indicator::Score], self.layout)?;
+        let rectangle = Rectangle(
+            Idx(
+                self.layout.board_rectangle().x(),
+                TOP_BAR_Y + 1,
+                BOARD_LAYER_IDX,
+            ),
+            Bounds2D(self.name.len(), 1),
+        );
+        let mut label = canvas.get_text_buffer(rectangle, Owner::Named("name"))?;
+        label.write(self.name, None, None)?;
+        label.flush()?;
+        self.tui_board = Some(tui_board);
+        self.label = Some(label);
+        Ok(())
+    }
+
+    /// Shifts the board, placing the new tile dealt for its next move. Returns false if the
+    /// shift didn't move anything.
+    fn shift(&mut self, direction: BoardDirection, spawns: &mut SpawnSequence) -> bool {
+        let spawn = spawns.get(self.board.move_count());
+        let prior = self.board.current();
+        match self.board.shift_spawning(direction, &spawn) {
+            MoveOutcome::Moved(_) => {
+                self.prior = Some(prior);
+                true
+            }
+            MoveOutcome::Rejected => false,
+        }
+    }
+
+    fn start_animation(&mut self) -> Result<()> {
+        let (Some(prior), Some(tui_board)) = (self.prior.take(), &mut self.tui_board) else {
+            return Ok(());
+        };
+        tui_board.draw_score(&self.board)?;
+        tui_board.update_move_count(self.board.move_count())?;
+        tui_board.animate_new_round(&prior, &self.board.current())?;
+        Ok(())
+    }
+
+    fn animate(&mut self) -> Result<bool> {
+        match &mut self.tui_board {
+            Some(tui_board) => tui_board.animate(),
+            None => Ok(false),
+        }
+    }
+
+    fn finish_animation(&mut self) -> Result<()> {
+        if let Some(tui_board) = &mut self.tui_board {
+            tui_board.teardown_animation()?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays mirror matches in the terminal until the player quits. The player plays the board on
+/// the left with the arrow keys; the opponent replies to every move on the board on the right
+/// with the moves its strategy picks, and plays on alone once the player is stuck.
+pub(crate) struct MirrorMatch<R: Renderer, E: EventSource> {
+    renderer: R,
+    event_source: E,
+    canvas: Canvas,
+    keymap: Keymap,
+    strategy: Strategy,
+    spawns: SpawnSequence,
+    player: Side,
+    opponent: Side,
+    divider: Option<DrawBuffer>,
+    // how the match ended, or what size the terminal needs to be
+    overlay: Option<TextBuffer>,
+    frame_delay: Duration,
+}
+
+impl<R: Renderer, E: EventSource> MirrorMatch<R, E> {
+    /// Sets up a match whose boards and new tiles all follow from the given seed, against an
+    /// opponent playing the given strategy.
+    pub(crate) fn new(seed: u64, strategy: Strategy, renderer: R, event_source: E) -> Result<Self> {
+        let (width, height) = renderer.size_hint().during(TerminalOperation::SizeHint)?;
+        let layout = LayoutSpec::classic();
+        Ok(Self {
+            renderer,
+            event_source,
+            canvas: Canvas::new(width as usize, height as usize),
+            keymap: Keymap::default(),
+            strategy,
+            spawns: SpawnSequence::new(seed),
+            player: Side::new(
+                "you",
+                Board::new_seeded(seed, BoardConfig::default()),
+                layout,
+            ),
+            opponent: Side::new(
+                strategy.name(),
+                Board::new_seeded(seed, BoardConfig::default()),
+                layout.beside(),
+            ),
+            divider: None,
+            overlay: None,
+            frame_delay: FRAME_DELAY,
+        })
+    }
+
+    /// Plays until the player quits, returning how the last match that was played to the end
+    /// ended. The terminal is restored exactly once however this returns.
+    pub(crate) fn run(mut self) -> Result<Option<Outcome>> {
+        let result = self.play();
+        self.renderer.recover();
+        result
+    }
+
+    fn play(&mut self) -> Result<Option<Outcome>> {
+        let mut outcome = None;
+        self.lay_out()?;
+        loop {
+            let decided = decide(&self.player.board, &self.opponent.board);
+            if let Some(decided) = decided {
+                outcome = Some(decided);
+                self.show_result(decided)?;
+            } else if self.player.board.is_game_over()
+                && self.player.tui_board.is_some()
+                && self.opponent_turn()
+            {
+                // the player is stuck, so the opponent races on alone
+                self.animate()?;
+                continue;
+            }
+            self.render()?;
+            match self.next_event()? {
+                Event::UserInput(UserInput::Direction(d)) if decided.is_none() => {
+                    self.take_turn(d.to_board())?
+                }
+                Event::UserInput(UserInput::NewGame) if decided.is_some() => self.rematch()?,
+                Event::UserInput(UserInput::Quit) => return Ok(outcome),
+                Event::Resize => self.lay_out()?,
+                _ => (),
+            }
+        }
+    }
+
+    /// Makes the player's move and the opponent's reply, unless the player's move won the match
+    /// or moved nothing, and animates both at once.
+    fn take_turn(&mut self, direction: BoardDirection) -> Result<()> {
+        if self.player.tui_board.is_none() || !self.player.shift(direction, &mut self.spawns) {
+            return Ok(());
+        }
+        if decide(&self.player.board, &self.opponent.board).is_none() {
+            self.opponent_turn();
+        }
+        self.animate()
+    }
+
+    /// Makes the move the opponent's strategy picks. Returns false if it has none to make.
+    fn opponent_turn(&mut self) -> bool {
+        match self.strategy.choose(&self.opponent.board.current()) {
+            Some(direction) => self.opponent.shift(direction, &mut self.spawns),
+            None => false,
+        }
+    }
+
+    /// Animates the moves both boards have made since they were last animated, in the same
+    /// frames. The boards never share a cell, see `LayoutSpec::beside`, so their tiles can slide
+    /// on the same layers.
+    fn animate(&mut self) -> Result<()> {
+        self.player.start_animation()?;
+        self.opponent.start_animation()?;
+        loop {
+            // both boards animate every frame, whether or not the other one is done
+            let mut animating = self.player.animate()?;
+            animating |= self.opponent.animate()?;
+            if !animating {
+                break;
+            }
+            std::thread::sleep(self.frame_delay);
+            self.render()?;
+        }
+        self.player.finish_animation()?;
+        self.opponent.finish_animation()?;
+        #[cfg(debug_assertions)]
+        for side in [&self.player, &self.opponent] {
+            if let Some(tui_board) = &side.tui_board {
+                tui_board.check_consistency(&side.board.current());
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts a new match with a fresh seed.
+    fn rematch(&mut self) -> Result<()> {
+        let seed = thread_rng().gen();
+        self.spawns = SpawnSequence::new(seed);
+        self.player.board = Board::new_seeded(seed, BoardConfig::default());
+        self.opponent.board = Board::new_seeded(seed, BoardConfig::default());
+        self.lay_out()
+    }
+
+    /// Lays both boards out on a canvas the size of the terminal, or asks for a larger terminal
+    /// if they don't fit.
+    fn lay_out(&mut self) -> Result<()> {
+        let (width, height) = self
+            .renderer
+            .size_hint()
+            .during(TerminalOperation::SizeHint)?;
+        self.player.tui_board = None;
+        self.opponent.tui_board = None;
+        self.divider = None;
+        self.overlay = None;
+        self.canvas = Canvas::with_depth(width as usize, height as usize, canvas_depth(false))?;
+        self.renderer
+            .clear(&self.canvas)
+            .during(TerminalOperation::Clear)?;
+
+        let requirements = LayoutRequirements::new(&self.opponent.layout);
+        if let Err(Error::TerminalTooSmall { have, need }) =
+            requirements.check(self.canvas.dimensions())
+        {
+            let rectangle = Rectangle(Idx(0, 0, OVERLAY_LAYER_IDX), Bounds2D(have.0, have.1));
+            let mut buf = self
+                .canvas
+                .get_text_buffer(rectangle, Owner::Named("message"))?;
+            buf.write(
+                &format!(
+                    "the terminal is too small at {} x {} for a mirror match, please make it at \
+                     least {} x {}!",
+                    have.0, have.1, need.0, need.1
+                ),
+                None,
+                None,
+            )?;
+            buf.flush()?;
+            self.overlay = Some(buf);
+            return Ok(());
+        }
+
+        self.player.lay_out(&mut self.canvas)?;
+        self.opponent.lay_out(&mut self.canvas)?;
+        let rectangle = Rectangle(
+            Idx(self.opponent.layout.divider_x(), 0, BOARD_LAYER_IDX),
+            Bounds2D(1, requirements.need().1),
+        );
+        let mut divider = self
+            .canvas
+            .get_draw_buffer(rectangle, Owner::Named("divider"))?;
+        divider.fill('\u{2502}')?;
+        self.divider = Some(divider);
+        Ok(())
+    }
+
+    /// Shows how the match ended across both boards.
+    fn show_result(&mut self, outcome: Outcome) -> Result<()> {
+        if self.overlay.is_some() || self.player.tui_board.is_none() {
+            return Ok(());
+        }
+        let (width, _) = LayoutRequirements::new(&self.opponent.layout).need();
+        let y = self.player.layout.board_rectangle().y() + 8;
+        let rectangle = Rectangle(Idx(width / 4, y, OVERLAY_LAYER_IDX), Bounds2D(width / 2, 5));
+        let mut buf = self
+            .canvas
+            .get_text_buffer(rectangle, Owner::Named("result"))?;
+        buf.draw_border()?;
+        buf.clear()?;
+        let scores = format!(
+            "{} {} \u{2014} {} {}",
+            self.player.name,
+            self.player.board.score(),
+            self.opponent.name,
+            self.opponent.board.score()
+        );
+        buf.format(FormatOptions {
+            halign: HAlignment::Center,
+            valign: VAlignment::Middle,
+        });
+        buf.write(&outcome.to_string(), None, None)?;
+        buf.write(&scores, None, None)?;
+        buf.write(&self.keymap.render(RESULT_PROMPT), None, None)?;
+        buf.flush()?;
+        self.overlay = Some(buf);
+        Ok(())
+    }
+
+    fn render(&mut self) -> Result<()> {
+        self.renderer
+            .begin_frame()
+            .during(TerminalOperation::Render)?;
+        let drawn = self.renderer.render(&self.canvas);
+        let ended = self.renderer.end_frame();
+        drawn.and(ended).during(TerminalOperation::Render)
+    }
+
+    fn next_event(&self) -> Result<Event> {
+        self.event_source
+            .next_event()
+            .during(TerminalOperation::NextEvent)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+    use crate::engine::fixtures::{full_without_merges, one_merge_from_winning, round};
+    use crate::tui::error::Result as TuiResult;
+    use crate::tui::geometry::Direction;
+    use crate::tui::testing::MockEventSource;
+    use crate::tui48::{init, Slot, DIVIDER_WIDTH};
+
+    const SEED: u64 = 2;
+
+    /// A Renderer that only keeps count of the frames it is asked to render, for matches too long
+    /// to keep every frame of.
+    struct CountingRenderer {
+        size: (u16, u16),
+        frames: usize,
+    }
+
+    impl Renderer for CountingRenderer {
+        fn size_hint(&self) -> TuiResult<(u16, u16)> {
+            Ok(self.size)
+        }
+
+        fn render(&mut self, c: &Canvas) -> TuiResult<()> {
+            // the canvas only holds so many changes
+            let _ = c.get_changed();
+            self.frames += 1;
+            Ok(())
+        }
+
+        fn clear(&mut self, _c: &Canvas) -> TuiResult<()> {
+            Ok(())
+        }
+
+        fn recover(&mut self) {}
+    }
+
+    fn mirror_match(
+        size: (u16, u16),
+        strategy: Strategy,
+        directions: impl IntoIterator<Item = Direction>,
+    ) -> Result<MirrorMatch<CountingRenderer, MockEventSource>> {
+        let renderer = CountingRenderer { size, frames: 0 };
+        let events = directions
+            .into_iter()
+            .map(|d| Event::UserInput(UserInput::Direction(d)));
+        let mut mirror = MirrorMatch::new(SEED, strategy, renderer, MockEventSource::new(events))?;
+        mirror.frame_delay = Duration::ZERO;
+        Ok(mirror)
+    }
+
+    fn board(round: Round) -> Board {
+        let mut board = Board::new_seeded(SEED, BoardConfig::default());
+        board.set_initial_round(round);
+        board
+    }
+
+    #[rstest]
+    #[case::undecided(round!([[2, 0, 0, 0]; 4]), round!([[4, 0, 0, 0]; 4]), None)]
+    #[case::one_merge_away(one_merge_from_winning(), round!([[4, 0, 0, 0]; 4]), None)]
+    #[case::player_reached_the_tile(
+        round!([[2048, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]),
+        round!([[4, 0, 0, 0]; 4]),
+        Some(Outcome::Player)
+    )]
+    #[case::opponent_reached_the_tile(
+        round!([[2, 0, 0, 0]; 4]),
+        round!([[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 4096]]),
+        Some(Outcome::Opponent)
+    )]
+    // a player stuck with a lower score keeps the match going until the opponent is stuck too
+    #[case::player_stuck(
+        full_without_merges(),
+        round!([[2, 0, 0, 0]; 4]),
+        None
+    )]
+    #[case::both_stuck_draw(full_without_merges(), full_without_merges(), Some(Outcome::Draw))]
+    #[case::both_stuck_higher_score(
+        full_without_merges().with_score(100),
+        full_without_merges(),
+        Some(Outcome::Player)
+    )]
+    #[case::both_stuck_lower_score(
+        full_without_merges(),
+        full_without_merges().with_score(100),
+        Some(Outcome::Opponent)
+    )]
+    fn decide_follows_the_winning_tile_then_the_score(
+        #[case] player: Round,
+        #[case] opponent: Round,
+        #[case] expected: Option<Outcome>,
+    ) {
+        assert_eq!(decide(&board(player), &board(opponent)), expected);
+    }
+
+    #[rstest]
+    #[case::classic(LayoutSpec::classic(), (2 * 67 + 3, 37))]
+    #[case::square(LayoutSpec::square(2.0).unwrap(), (2 * 94 + 3, 37))]
+    fn boards_side_by_side_need_twice_the_width(
+        #[case] layout: LayoutSpec,
+        #[case] expected: (usize, usize),
+    ) {
+        let single = LayoutRequirements::new(&layout).need();
+        let both = LayoutRequirements::new(&layout.beside()).need();
+        assert_eq!(both, expected);
+        assert_eq!(both.1, single.1);
+        assert_eq!(both.0, 2 * single.0 + DIVIDER_WIDTH);
+    }
+
+    #[rstest]
+    fn boards_side_by_side_never_share_a_cell(
+        #[values(LayoutSpec::classic(), LayoutSpec::square(2.0).unwrap())] layout: LayoutSpec,
+    ) {
+        let right = layout.beside();
+        let divider = right.divider_x();
+        let (left_width, _) = LayoutRequirements::new(&layout).need();
+        assert!(left_width < divider && divider < right.origin_x);
+
+        // everything the board on the right draws, from where new tiles start out to its top bar
+        let (across, down) = right.new_tile_offsets();
+        let extents = right.board_rectangle().expand_by(across, down);
+        assert!(extents.x() > divider, "{} reaches the divider", extents);
+        for (_, r) in right.top_bar(&[Indicator::Score], usize::MAX) {
+            assert!(r.x() > divider, "{} reaches the divider", r);
+        }
+    }
+
+    #[test]
+    fn both_boards_animate_in_the_same_frames() -> Result<()> {
+        init()?;
+
+        let mut mirror = mirror_match((140, 40), Strategy::Corner, [])?;
+        let start = round!([[2, 2, 0, 4], [0, 0, 0, 0], [0, 8, 0, 0], [0, 0, 0, 2]]);
+        mirror.player.board = board(start.clone());
+        mirror.opponent.board = board(start);
+        mirror.lay_out()?;
+
+        // the player shifts right while the opponent, playing the corner, shifts down
+        assert!(mirror
+            .player
+            .shift(BoardDirection::Right, &mut mirror.spawns));
+        assert!(mirror.opponent_turn());
+        mirror.player.start_animation()?;
+        mirror.opponent.start_animation()?;
+        let regions = [
+            (0, mirror.opponent.layout.divider_x()),
+            (mirror.opponent.layout.divider_x() + 1, usize::MAX),
+        ];
+        let mut frames = 0;
+        loop {
+            let sides = [&mirror.player, &mirror.opponent];
+            for (side, (from, to)) in sides.into_iter().zip(regions) {
+                let tui_board = side.tui_board.as_ref().expect("both boards fit");
+                for r in tui_board.moving_slots.iter().filter_map(Slot::rectangle) {
+                    assert!(
+                        from <= r.x() && r.extents().0 <= to,
+                        "{} strays out of {}'s side",
+                        r,
+                        side.name
+                    );
+                }
+            }
+            let mut animating = mirror.player.animate()?;
+            animating |= mirror.opponent.animate()?;
+            if !animating {
+                break;
+            }
+            frames += 1;
+            let _ = mirror.canvas.get_changed();
+        }
+        assert!(frames > 0);
+        mirror.player.finish_animation()?;
+        mirror.opponent.finish_animation()?;
+        for side in [&mirror.player, &mirror.opponent] {
+            let tui_board = side.tui_board.as_ref().expect("both boards fit");
+            assert_eq!(tui_board.verify_consistency(&side.board.current()), Ok(()));
+        }
+        assert_ne!(
+            mirror.player.board.current(),
+            mirror.opponent.board.current()
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    // the winning merge is along the top row
+    #[case::player_merges_first(Direction::Left, Outcome::Player)]
+    // moving the other way leaves the winning merge to the opponent
+    #[case::opponent_merges_first(Direction::Down, Outcome::Opponent)]
+    fn the_first_to_the_winning_tile_wins(#[case] first: Direction, #[case] expected: Outcome) {
+        init().unwrap();
+
+        let mut mirror = mirror_match((140, 40), Strategy::Greedy, [first]).unwrap();
+        mirror.player.board = board(one_merge_from_winning());
+        mirror.opponent.board = board(one_merge_from_winning());
+        assert_eq!(mirror.run().unwrap(), Some(expected));
+    }
+
+    #[test]
+    fn a_scripted_race_is_decided_by_the_seed() -> Result<()> {
+        init()?;
+
+        let script = [
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+            Direction::Up,
+        ]
+        .into_iter()
+        .cycle()
+        .take(4000);
+        let mut mirror = mirror_match((140, 40), Strategy::Greedy, script)?;
+        mirror.play()?;
+        // going round and round the directions happens to beat the greedy opponent with this seed
+        let (player, opponent) = (&mirror.player.board, &mirror.opponent.board);
+        assert_eq!(decide(player, opponent), Some(Outcome::Player));
+        assert!(player.is_game_over() && opponent.is_game_over());
+        assert!(player.score() > opponent.score());
+        Ok(())
+    }
+
+    #[test]
+    fn a_terminal_too_small_for_both_boards_plays_nothing() -> Result<()> {
+        init()?;
+
+        let (width, height) = LayoutRequirements::new(&LayoutSpec::classic().beside()).need();
+        let size = (width as u16 - 1, height as u16);
+        let mut mirror = mirror_match(size, Strategy::Greedy, [Direction::Left])?;
+        mirror.lay_out()?;
+        assert!(mirror.player.tui_board.is_none());
+        assert!(mirror.overlay.is_some());
+        assert_eq!(mirror.run()?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,500 @@
+//! Which input each state of the game acts on. Input a state has no use for is dropped, or, if
+//! it's likely meant for a state that comes later, held until a state that acts on it asks for
+//! input, so that keys pressed as the game changes state neither get lost nor land on whatever
+//! happens to be on screen.
+use super::GameState;
+use crate::tui::events::UserInput;
+
+/// What a state of the game does with a command.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum InputPolicy {
+    /// The state acts on it.
+    Allowed,
+    /// The state drops it.
+    Ignored,
+    /// The state holds on to it, in place of anything held before, and it's delivered to the
+    /// next state that allows it.
+    Buffered,
+}
+
+/// The policy of every state for every command. Every command is listed for every state rather
+/// than left to a default, so that a new state or command can't be added without deciding what
+/// each state does with it.
+pub(super) fn input_policy(state: GameState, input: &UserInput) -> InputPolicy {
+    use InputPolicy::*;
+    match state {
+        GameState::Active => match input {
+            UserInput::Direction(_)
+            | UserInput::NewGame
+            | UserInput::Quit
+            | UserInput::ShowHeatmap
+            | UserInput::CyclePack
+            | UserInput::PreviewThemes
+            | UserInput::PowerUp
+            | UserInput::ToggleGrid
+            | UserInput::Replay
+            | UserInput::EditBoard
+            | UserInput::Undo
+            | UserInput::Redo
+            | UserInput::SaveGame
+            | UserInput::LoadGame
+            | UserInput::ToggleAutoPlay
+            // there's nothing to confirm or cancel, but like any key they dismiss the heatmap
+            | UserInput::Confirm
+            | UserInput::Cancel => Allowed,
+            // only the editor has a position to change
+            UserInput::Increase
+            | UserInput::Decrease
+            | UserInput::Digit(_)
+            | UserInput::Erase
+            | UserInput::WriteNotation
+            // the game is already going on
+            | UserInput::Continue => Ignored,
+        },
+        GameState::Won => match input {
+            UserInput::Continue
+            | UserInput::NewGame
+            | UserInput::Quit
+            | UserInput::CyclePack
+            | UserInput::ToggleGrid
+            // any move keeps the game going, and is made once it does
+            | UserInput::Direction(_) => Allowed,
+            // the game goes on or ends before anything else is done with it
+            UserInput::ShowHeatmap
+            | UserInput::PreviewThemes
+            | UserInput::Confirm
+            | UserInput::Cancel
+            | UserInput::PowerUp
+            | UserInput::EditBoard
+            | UserInput::Increase
+            | UserInput::Decrease
+            | UserInput::Digit(_)
+            | UserInput::Erase
+            | UserInput::WriteNotation
+            | UserInput::Undo
+            | UserInput::Redo
+            | UserInput::Replay
+            | UserInput::SaveGame
+            | UserInput::LoadGame
+            | UserInput::ToggleAutoPlay => Ignored,
+        },
+        GameState::Over => match input {
+            UserInput::NewGame
+            | UserInput::Quit
+            | UserInput::CyclePack
+            | UserInput::ToggleGrid
+            | UserInput::Replay
+            | UserInput::EditBoard
+            // taking back the last move or loading a saved game are ways out of a lost game
+            | UserInput::Undo
+            | UserInput::LoadGame => Allowed,
+            // a lost game isn't worth going back to
+            UserInput::SaveGame => Ignored,
+            // there is no move left for the computer to make either
+            UserInput::ToggleAutoPlay => Ignored,
+            // a lost game is as far as the game goes, so there is never a move to make again
+            UserInput::Redo => Ignored,
+            // there is no move left to make, but a move pressed just as the game ended is meant
+            // for the next one rather than lost
+            UserInput::Direction(_) => Buffered,
+            // a power-up that could free up the board keeps the game from being over, so there
+            // is none to use
+            UserInput::ShowHeatmap
+            | UserInput::PreviewThemes
+            | UserInput::Confirm
+            | UserInput::Cancel
+            | UserInput::PowerUp
+            | UserInput::Increase
+            | UserInput::Decrease
+            | UserInput::Digit(_)
+            | UserInput::Erase
+            | UserInput::WriteNotation
+            | UserInput::Continue => Ignored,
+        },
+        GameState::TerminalTooSmall => match input {
+            // moves are refused with a notification rather than dropped without a word
+            UserInput::Direction(_) | UserInput::NewGame | UserInput::Quit => Allowed,
+            UserInput::ShowHeatmap
+            | UserInput::CyclePack
+            | UserInput::PreviewThemes
+            | UserInput::Confirm
+            | UserInput::Cancel
+            | UserInput::PowerUp
+            | UserInput::ToggleGrid
+            | UserInput::Replay
+            | UserInput::EditBoard
+            | UserInput::Increase
+            | UserInput::Decrease
+            | UserInput::Digit(_)
+            | UserInput::Erase
+            | UserInput::WriteNotation
+            | UserInput::Undo
+            | UserInput::Redo
+            | UserInput::Continue
+            | UserInput::SaveGame
+            | UserInput::LoadGame
+            | UserInput::ToggleAutoPlay => Ignored,
+        },
+        GameState::ThemePreview => match input {
+            // left and right flip through the themes
+            UserInput::Direction(_) | UserInput::Confirm | UserInput::Cancel | UserInput::Quit => {
+                Allowed
+            }
+            UserInput::NewGame
+            | UserInput::ShowHeatmap
+            | UserInput::CyclePack
+            | UserInput::PreviewThemes
+            | UserInput::PowerUp
+            | UserInput::ToggleGrid
+            | UserInput::Replay
+            | UserInput::EditBoard
+            | UserInput::Increase
+            | UserInput::Decrease
+            | UserInput::Digit(_)
+            | UserInput::Erase
+            | UserInput::WriteNotation
+            | UserInput::Undo
+            | UserInput::Redo
+            | UserInput::Continue
+            | UserInput::SaveGame
+            | UserInput::LoadGame
+            | UserInput::ToggleAutoPlay => Ignored,
+        },
+        GameState::Replay => match input {
+            // left and right step back and forth through the moves, as do undo and redo
+            UserInput::Direction(_)
+            | UserInput::Undo
+            | UserInput::Redo
+            | UserInput::Replay
+            | UserInput::Confirm
+            | UserInput::Cancel
+            | UserInput::Quit => Allowed,
+            // the moves are only looked at, not played
+            UserInput::NewGame
+            | UserInput::ShowHeatmap
+            | UserInput::CyclePack
+            | UserInput::PreviewThemes
+            | UserInput::PowerUp
+            | UserInput::ToggleGrid
+            | UserInput::EditBoard
+            | UserInput::Increase
+            | UserInput::Decrease
+            | UserInput::Digit(_)
+            | UserInput::Erase
+            | UserInput::WriteNotation
+            | UserInput::Continue
+            | UserInput::SaveGame
+            | UserInput::LoadGame
+            | UserInput::ToggleAutoPlay => Ignored,
+        },
+        GameState::Editor => match input {
+            // the arrows move the cursor and the digits type the score
+            UserInput::Direction(_)
+            | UserInput::Increase
+            | UserInput::Decrease
+            | UserInput::Digit(_)
+            | UserInput::Erase
+            | UserInput::WriteNotation
+            | UserInput::Confirm
+            | UserInput::Cancel
+            | UserInput::Quit => Allowed,
+            UserInput::NewGame
+            | UserInput::ShowHeatmap
+            | UserInput::CyclePack
+            | UserInput::PreviewThemes
+            | UserInput::PowerUp
+            | UserInput::ToggleGrid
+            | UserInput::Replay
+            | UserInput::EditBoard
+            | UserInput::Undo
+            | UserInput::Redo
+            | UserInput::Continue
+            | UserInput::SaveGame
+            | UserInput::LoadGame
+            | UserInput::ToggleAutoPlay => Ignored,
+        },
+        GameState::Resume => match input {
+            // y or Enter resumes the game left last time, and n starts a new one
+            UserInput::Confirm | UserInput::NewGame | UserInput::Quit => Allowed,
+            UserInput::Direction(_)
+            | UserInput::ShowHeatmap
+            | UserInput::CyclePack
+            | UserInput::PreviewThemes
+            | UserInput::Cancel
+            | UserInput::PowerUp
+            | UserInput::ToggleGrid
+            | UserInput::EditBoard
+            | UserInput::Increase
+            | UserInput::Decrease
+            | UserInput::Digit(_)
+            | UserInput::Erase
+            | UserInput::WriteNotation
+            | UserInput::Undo
+            | UserInput::Redo
+            | UserInput::Continue
+            | UserInput::SaveGame
+            | UserInput::LoadGame
+            | UserInput::ToggleAutoPlay
+            | UserInput::Replay => Ignored,
+        },
+        // neither waits for input
+        GameState::Reset | GameState::Quit => Ignored,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+    use crate::engine::board::{Board, BoardConfig};
+    use crate::engine::fixtures::{full_without_merges, round};
+    use crate::error::Result;
+    use crate::tui::events::Event;
+    use crate::tui::geometry::Direction;
+    use crate::tui::testing::{MockEventSource, TestRenderer};
+    use crate::tui48::{init, Tui48};
+
+    /// The states that wait for input.
+    const WAITING: [GameState; 8] = [
+        GameState::Active,
+        GameState::Won,
+        GameState::Over,
+        GameState::TerminalTooSmall,
+        GameState::ThemePreview,
+        GameState::Replay,
+        GameState::Editor,
+        GameState::Resume,
+    ];
+
+    fn commands() -> Vec<UserInput> {
+        vec![
+            UserInput::Direction(Direction::Left),
+            UserInput::Direction(Direction::Right),
+            UserInput::Direction(Direction::Up),
+            UserInput::Direction(Direction::Down),
+            UserInput::NewGame,
+            UserInput::Quit,
+            UserInput::ShowHeatmap,
+            UserInput::CyclePack,
+            UserInput::PreviewThemes,
+            UserInput::Confirm,
+            UserInput::Cancel,
+            UserInput::PowerUp,
+            UserInput::ToggleGrid,
+            UserInput::Replay,
+            UserInput::EditBoard,
+            UserInput::Increase,
+            UserInput::Decrease,
+            UserInput::Digit(0),
+            UserInput::Digit(9),
+            UserInput::Erase,
+            UserInput::WriteNotation,
+            UserInput::Undo,
+            UserInput::Redo,
+            UserInput::Continue,
+            UserInput::SaveGame,
+            UserInput::LoadGame,
+            UserInput::ToggleAutoPlay,
+        ]
+    }
+
+    fn tui48(
+        board: Board,
+        events: impl IntoIterator<Item = Event>,
+    ) -> Result<Tui48<TestRenderer, MockEventSource>> {
+        init()?;
+        let mut tui48 = Tui48::new(
+            board,
+            TestRenderer::new(100, 50),
+            MockEventSource::new(events),
+        )?;
+        tui48.frame_delay = std::time::Duration::ZERO;
+        tui48.enter_duration = std::time::Duration::ZERO;
+        Ok(tui48)
+    }
+
+    fn game_over() -> Board {
+        let mut board = Board::new_seeded(1, BoardConfig::default());
+        board.set_initial_round(full_without_merges());
+        board
+    }
+
+    #[rstest]
+    #[case::left(
+        UserInput::Direction(Direction::Left),
+        GameState::Over,
+        InputPolicy::Buffered
+    )]
+    #[case::too_small(UserInput::NewGame, GameState::TerminalTooSmall, InputPolicy::Allowed)]
+    #[case::power_up(UserInput::PowerUp, GameState::Over, InputPolicy::Ignored)]
+    #[case::preview(UserInput::NewGame, GameState::ThemePreview, InputPolicy::Ignored)]
+    #[case::replay_in_game(UserInput::Replay, GameState::Active, InputPolicy::Allowed)]
+    #[case::replay_game_over(UserInput::Replay, GameState::Over, InputPolicy::Allowed)]
+    #[case::step_in_replay(
+        UserInput::Direction(Direction::Left),
+        GameState::Replay,
+        InputPolicy::Allowed
+    )]
+    #[case::new_game_in_replay(UserInput::NewGame, GameState::Replay, InputPolicy::Ignored)]
+    #[case::digit_in_game(UserInput::Digit(4), GameState::Active, InputPolicy::Ignored)]
+    #[case::digit_in_editor(UserInput::Digit(4), GameState::Editor, InputPolicy::Allowed)]
+    #[case::undo_game_over(UserInput::Undo, GameState::Over, InputPolicy::Allowed)]
+    #[case::undo_in_replay(UserInput::Undo, GameState::Replay, InputPolicy::Allowed)]
+    #[case::redo_in_replay(UserInput::Redo, GameState::Replay, InputPolicy::Allowed)]
+    #[case::replay_won(UserInput::Replay, GameState::Won, InputPolicy::Ignored)]
+    #[case::continue_won(UserInput::Continue, GameState::Won, InputPolicy::Allowed)]
+    #[case::continue_in_game(UserInput::Continue, GameState::Active, InputPolicy::Ignored)]
+    #[case::move_won(
+        UserInput::Direction(Direction::Up),
+        GameState::Won,
+        InputPolicy::Allowed
+    )]
+    #[case::load_game_over(UserInput::LoadGame, GameState::Over, InputPolicy::Allowed)]
+    #[case::save_game_over(UserInput::SaveGame, GameState::Over, InputPolicy::Ignored)]
+    #[case::save_in_editor(UserInput::SaveGame, GameState::Editor, InputPolicy::Ignored)]
+    #[case::autoplay_in_game(UserInput::ToggleAutoPlay, GameState::Active, InputPolicy::Allowed)]
+    #[case::resume(UserInput::Confirm, GameState::Resume, InputPolicy::Allowed)]
+    #[case::move_resume(
+        UserInput::Direction(Direction::Left),
+        GameState::Resume,
+        InputPolicy::Ignored
+    )]
+    #[case::autoplay_won(UserInput::ToggleAutoPlay, GameState::Won, InputPolicy::Ignored)]
+    fn policy_cells(
+        #[case] input: UserInput,
+        #[case] state: GameState,
+        #[case] expected: InputPolicy,
+    ) {
+        assert_eq!(input_policy(state, &input), expected);
+    }
+
+    #[test]
+    fn quit_is_allowed_in_every_state_that_waits_for_input() {
+        for state in WAITING {
+            assert_eq!(
+                input_policy(state, &UserInput::Quit),
+                InputPolicy::Allowed,
+                "{:?}",
+                state
+            );
+        }
+    }
+
+    #[test]
+    fn every_command_is_dispatched_per_the_policy() -> Result<()> {
+        for state in WAITING {
+            for input in commands() {
+                let events = [Event::UserInput(input.clone()), Event::Resize];
+                let mut tui48 = tui48(Board::new_seeded(1, BoardConfig::default()), events)?;
+                let event = tui48.next_event_in(state)?;
+                let cell = format!("{:?} in {:?}", input, state);
+                match input_policy(state, &input) {
+                    InputPolicy::Allowed => {
+                        assert!(
+                            matches!(event, Event::UserInput(i) if i == input),
+                            "{}",
+                            cell
+                        );
+                        assert_eq!(tui48.held_input, None, "{}", cell);
+                    }
+                    InputPolicy::Ignored => {
+                        assert!(matches!(event, Event::Resize), "{}", cell);
+                        assert_eq!(tui48.held_input, None, "{}", cell);
+                    }
+                    InputPolicy::Buffered => {
+                        assert!(matches!(event, Event::Resize), "{}", cell);
+                        assert_eq!(tui48.held_input, Some(input), "{}", cell);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn the_newest_buffered_input_is_held_until_a_state_allows_it() -> Result<()> {
+        let events = [Direction::Left, Direction::Up]
+            .map(|d| Event::UserInput(UserInput::Direction(d)))
+            .into_iter()
+            .chain([Event::UserInput(UserInput::CyclePack)]);
+        let mut tui48 = tui48(Board::new_seeded(1, BoardConfig::default()), events)?;
+
+        let event = tui48.next_event_in(GameState::Over)?;
+        assert!(matches!(event, Event::UserInput(UserInput::CyclePack)));
+        assert_eq!(tui48.held_input, Some(UserInput::Direction(Direction::Up)));
+        // the preview doesn't allow anything held either, so it waits for new input
+        tui48.held_input = Some(UserInput::PowerUp);
+        let event = tui48.next_event_in(GameState::ThemePreview)?;
+        assert!(matches!(event, Event::UserInput(UserInput::Quit)));
+        assert_eq!(tui48.held_input, Some(UserInput::PowerUp));
+
+        tui48.held_input = Some(UserInput::Direction(Direction::Up));
+        let event = tui48.next_event_in(GameState::Active)?;
+        assert!(matches!(
+            event,
+            Event::UserInput(UserInput::Direction(Direction::Up))
+        ));
+        assert_eq!(tui48.held_input, None);
+        Ok(())
+    }
+
+    #[test]
+    fn a_move_during_the_game_over_overlay_doesnt_resume_play() -> Result<()> {
+        let events =
+            [UserInput::Direction(Direction::Left), UserInput::ToggleGrid].map(Event::UserInput);
+        let mut tui48 = tui48(game_over(), events)?;
+        tui48.tui_board = tui48.resize()?;
+
+        // the overlay stays up through the move and the grid being toggled
+        assert!(matches!(tui48.run_game_over()?, GameState::Over));
+        assert_eq!(
+            tui48.held_input,
+            Some(UserInput::Direction(Direction::Left))
+        );
+        assert!(matches!(tui48.run_game_over()?, GameState::Quit));
+        assert_eq!(tui48.board.move_count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn a_move_pressed_during_the_game_over_overlay_is_made_in_the_next_game() -> Result<()> {
+        let events =
+            [UserInput::Direction(Direction::Left), UserInput::NewGame].map(Event::UserInput);
+        let mut tui48 = tui48(game_over(), events)?;
+        tui48.tui_board = tui48.resize()?;
+
+        assert!(matches!(tui48.run_game_over()?, GameState::Reset));
+        // a new game that the move is known to change
+        let mut board = Board::new_seeded(2, BoardConfig::default());
+        board.set_initial_round(round!([
+            [0, 0, 0, 2],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 0]
+        ]));
+        tui48.board = board;
+        assert!(matches!(tui48.run_game_active()?, GameState::Quit));
+        assert_eq!(tui48.board.move_count(), 1);
+        assert_eq!(tui48.held_input, None);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::quit(UserInput::Quit, GameState::Quit)]
+    #[case::new_game(UserInput::NewGame, GameState::Reset)]
+    fn a_terminal_too_small_can_still_be_left(
+        #[case] input: UserInput,
+        #[case] expected: GameState,
+    ) -> Result<()> {
+        init()?;
+        let events = MockEventSource::new([Event::UserInput(input), Event::Resize]);
+        let mut tui48 = Tui48::new(
+            Board::new_seeded(1, BoardConfig::default()),
+            TestRenderer::new(20, 10),
+            events,
+        )?;
+        assert_eq!(tui48.run_terminal_too_small()?, expected);
+        Ok(())
+    }
+}